@@ -0,0 +1,107 @@
+//! `cargo bench`; `per_pixel` is how raster rendering used to fill, kept around for comparison.
+//! `rows` and `per_pixel` draw on whole pixels, `antialiased` at sub-pixel positions. `phases`
+//! times the steps `--timing` prints one at a time: building (splitting and coloring), filling,
+//! the lines, and encoding
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use image::{Rgb, RgbImage};
+use mondrian::{encode, prelude::*, render::raster, size::SizePreset};
+
+fn composition(preset: SizePreset, antialias: bool) -> Composition {
+    CompositionBuilder::new()
+        .preset(preset)
+        .seed(7)
+        .levels(8)
+        .antialias(antialias)
+        .build()
+        .expect("preset compositions build")
+}
+
+/// the old `get_pixel_mut` loop, column by column
+fn per_pixel(composition: &Composition) -> RgbImage {
+    let mut imagebuf = RgbImage::new(composition.width(), composition.height());
+    let mut fill = |rectangle: &Rectangle, color: Rgb<u8>| {
+        let rectangle = rectangle.snapped();
+        let (x, y) = (rectangle.x as u32, rectangle.y as u32);
+        let x_end = ((rectangle.x + rectangle.width) as u32).min(imagebuf.width());
+        let y_end = ((rectangle.y + rectangle.height) as u32).min(imagebuf.height());
+        for x in x..x_end {
+            for y in y..y_end {
+                *imagebuf.get_pixel_mut(x, y) = color;
+            }
+        }
+    };
+    for pane in composition.panes() {
+        fill(&pane.rectangle, pane.color);
+    }
+    for edge in composition.edges() {
+        fill(
+            &edge.to_rectangle(composition.border_width()),
+            Rgb([0, 0, 0]),
+        );
+    }
+    for band in composition.frame() {
+        fill(&band, composition.frame_color());
+    }
+    imagebuf
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    group.sample_size(20);
+    for preset in [SizePreset::FullHd, SizePreset::Uhd, SizePreset::FiveK] {
+        let composition = composition(preset, false);
+        let name = preset.to_string();
+        group.bench_with_input(BenchmarkId::new("rows", &name), &composition, |b, c| {
+            b.iter(|| c.render_rgb())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("antialiased", &name),
+            &self::composition(preset, true),
+            |b, c| b.iter(|| c.render_rgb()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("per_pixel", &name),
+            &composition,
+            |b, c| b.iter(|| per_pixel(c)),
+        );
+    }
+    group.finish();
+}
+
+fn phases(c: &mut Criterion) {
+    let mut group = c.benchmark_group("phases");
+    group.sample_size(20);
+    for preset in [SizePreset::FullHd, SizePreset::Uhd] {
+        let name = preset.to_string();
+        let builder = CompositionBuilder::new().preset(preset).seed(7).levels(8);
+        group.bench_with_input(BenchmarkId::new("build", &name), &builder, |b, builder| {
+            b.iter(|| builder.clone().build().expect("preset compositions build"))
+        });
+        let composition = self::composition(preset, true);
+        group.bench_with_input(BenchmarkId::new("fill", &name), &composition, |b, c| {
+            b.iter(|| raster::render_fill(c))
+        });
+        let filled = raster::render_fill(&composition);
+        group.bench_with_input(BenchmarkId::new("border", &name), &composition, |b, c| {
+            b.iter_batched(
+                || filled.clone(),
+                |mut image| raster::render_lines(c, &mut image),
+                BatchSize::LargeInput,
+            )
+        });
+        let image = composition.render_rgb();
+        let parameters = composition.parameters();
+        group.bench_with_input(BenchmarkId::new("encode", &name), &image, |b, image| {
+            b.iter(|| {
+                let mut png = vec![];
+                encode::write_png(image, &parameters, &mut png).expect("PNGs encode");
+                png
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render, phases);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+//! tells the crate which compiler is building it, for `plugin::load` to check plugins against
+
+use std::{env, process::Command};
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_owned())
+        .unwrap_or_else(|| "an unknown rustc".to_owned());
+    println!("cargo:rustc-env=MONDRIAN_RUSTC={version}");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -0,0 +1,80 @@
+use std::error::Error;
+
+use image::Rgb;
+use mondrian::{
+    fill::{FillOptions, FillStrategy},
+    plugin::{self, Registry},
+    prelude::*,
+    strategy::SplitStrategy,
+};
+use rand::RngCore;
+
+/// always down the middle of the long side
+#[derive(Debug)]
+struct Halves;
+
+impl SplitStrategy<Rectangle> for Halves {
+    fn split(
+        &self,
+        item: &Rectangle,
+        _depth: usize,
+        options: &SplitOptions,
+        _rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        item.divide_within(item.width >= item.height, 0.5, options)
+    }
+}
+
+/// the palette from left to right across the canvas, by where each pane's middle is
+#[derive(Debug)]
+struct Sweep {
+    canvas: Rectangle,
+}
+
+impl FillStrategy for Sweep {
+    fn colors(
+        &self,
+        panes: &[Rectangle],
+        palette: &Palette,
+        _options: &FillOptions,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<Rgb<u8>> {
+        let colors = palette.colors();
+        panes
+            .iter()
+            .map(|pane| {
+                let middle = (pane.x + pane.width / 2.0 - self.canvas.x) / self.canvas.width;
+                colors[((middle * colors.len() as f32) as usize).min(colors.len() - 1)]
+            })
+            .collect()
+    }
+}
+
+fn register(registry: &mut Registry) {
+    registry.register_split("halves", |_canvas| Box::new(Halves));
+    registry.register_fill("sweep", |canvas| Box::new(Sweep { canvas: *canvas }));
+}
+
+// in a crate of its own with `crate-type = ["cdylib"]`, this is all it takes for
+// `--features plugins -- --plugin libsweep.so --split-plugin halves --fill-plugin sweep`
+mondrian::export_plugin!(register);
+
+/// registers a split strategy and a fill of its own, then uses them
+fn main() -> Result<(), Box<dyn Error>> {
+    plugin::install(register);
+    let composition = CompositionBuilder::new()
+        .size(1920, 1080)
+        .seed(7)
+        .levels(6)
+        .split_plugin("halves")
+        .fill_plugin("sweep")
+        .build()?;
+
+    composition.save("plugin.png")?;
+    println!(
+        "wrote plugin.png with {} and {}",
+        plugin::splits().join(", "),
+        plugin::fills().join(", ")
+    );
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::error::Error;
+
+use mondrian::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let composition = CompositionBuilder::new()
+        .size(3840, 2160)
+        .seed(7)
+        .palette(Palette::CLASSIC)
+        .levels(6)
+        .build()?;
+
+    // unlike `RgbImage::save`, this embeds the seed and friends as PNG text chunks
+    composition.save("composition.png")?;
+    println!("wrote composition.png (seed {})", composition.seed());
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::{error::Error, fs};
+
+use image::Rgb;
+use mondrian::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let palette = Palette::new([
+        Rgb([0xf4, 0xf1, 0xe8]),
+        Rgb([0xc8, 0x1d, 0x25]),
+        Rgb([0x0b, 0x3d, 0x91]),
+    ])
+    .with_weights([6, 1, 1]);
+
+    let composition = CompositionBuilder::new()
+        .size(1600, 900)
+        .palette(palette)
+        .levels(5)
+        .build()?;
+
+    fs::write("composition.svg", composition.render_svg())?;
+    println!("wrote composition.svg (seed {})", composition.seed());
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::error::Error;
+
+use mondrian::prelude::*;
+
+/// prints the split tree as JSON, e.g. `cargo run --example tree | jq .`
+fn main() -> Result<(), Box<dyn Error>> {
+    let composition = CompositionBuilder::new()
+        .size(800, 600)
+        .seed(42)
+        .levels(3)
+        .build()?;
+
+    print!("{}", composition.tree_json());
+    Ok(())
+}
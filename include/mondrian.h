@@ -0,0 +1,68 @@
+#ifndef MONDRIAN_H
+#define MONDRIAN_H
+
+/* written by cbindgen from src/ffi.rs; change that, then run it again */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// it worked
+#define MONDRIAN_OK 0
+
+// the buffer was null, or its stride too small for a row of pixels
+#define MONDRIAN_BAD_BUFFER -1
+
+// there's no such pixel format
+#define MONDRIAN_BAD_FORMAT -2
+
+// the composition couldn't be built, e.g. for a zero width or too many levels
+#define MONDRIAN_BUILD_FAILED -3
+
+// something went wrong inside that shouldn't have; it didn't get past the call
+#define MONDRIAN_PANICKED -4
+
+// three bytes a pixel: red, green, blue
+#define MONDRIAN_RGB8 0
+
+// four bytes a pixel: red, green, blue and an opaque alpha
+#define MONDRIAN_RGBA8 1
+
+// four bytes a pixel: blue, green, red and an opaque alpha, i.e. cairo's and pixman's ARGB32
+// (and XRGB8888) on little-endian machines
+#define MONDRIAN_BGRA8 2
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// renders a Mondrian `width` by `height` pixels from `seed`, split `levels` times (0 for the
+// default) in the classic palette, into `out_buf`: `height` rows of `format` pixels, each row
+// starting `stride` bytes after the one before. returns [`MONDRIAN_OK`] or one of the other
+// codes, and [`mondrian_last_error`] says why
+//
+// the same arguments always give the same pixels, from any thread
+//
+// # Safety
+//
+// `out_buf` has to be null or point to `stride * height` writable bytes, which nothing else
+// touches until this returns
+int mondrian_generate(uint32_t width,
+                      uint32_t height,
+                      uint64_t seed,
+                      uint32_t levels,
+                      uint32_t format,
+                      uint8_t *out_buf,
+                      size_t stride);
+
+// why the last call that failed on this thread did, as UTF-8; empty if none has. it's good
+// until the next call that fails on the same thread, and isn't the caller's to free
+const char *mondrian_last_error(void);
+
+// the crate version this was built from, e.g. `0.1.0`; static, and isn't the caller's to free
+const char *mondrian_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MONDRIAN_H */
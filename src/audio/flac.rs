@@ -0,0 +1,524 @@
+//! FLAC, the free lossless codec: every frame's subframes (constant, verbatim, fixed and LPC
+//! predicted), Rice coded residuals and stereo decorrelation. the checksums aren't checked;
+//! a damaged file just sounds wrong
+
+use super::{AudioError, Track};
+
+/// reads big-endian bits, most significant first, the way FLAC packs them
+struct Bits<'a> {
+    bytes: &'a [u8],
+    /// how many bits in we are
+    at: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, at: 0 }
+    }
+
+    fn bit(&mut self) -> Result<bool, AudioError> {
+        let byte = self.bytes.get(self.at / 8).ok_or_else(ended)?;
+        let bit = byte >> (7 - self.at % 8) & 1;
+        self.at += 1;
+        Ok(bit == 1)
+    }
+
+    /// the next `count` bits, up to 64, as an unsigned number
+    fn unsigned(&mut self, count: u32) -> Result<u64, AudioError> {
+        let mut value = 0u64;
+        for _ in 0..count {
+            value = value << 1 | self.bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    /// the next `count` bits as a two's complement number
+    fn signed(&mut self, count: u32) -> Result<i64, AudioError> {
+        if count == 0 {
+            return Ok(0);
+        }
+        let value = self.unsigned(count)? as i64;
+        Ok(value << (64 - count) >> (64 - count))
+    }
+
+    /// how many zeros there are before the next one, which is skipped too
+    fn unary(&mut self) -> Result<u64, AudioError> {
+        let mut zeros = 0;
+        while !self.bit()? {
+            zeros += 1;
+        }
+        Ok(zeros)
+    }
+
+    /// on to the start of the next byte, unless it's at one already
+    fn align(&mut self) {
+        self.at = self.at.div_ceil(8) * 8;
+    }
+
+    fn byte_offset(&self) -> usize {
+        self.at / 8
+    }
+}
+
+fn ended() -> AudioError {
+    AudioError::Format("the FLAC file ends partway through a frame".to_owned())
+}
+
+fn invalid(why: &str) -> AudioError {
+    AudioError::Format(format!("not a FLAC file we can read: {why}"))
+}
+
+/// what the stream info block says about every frame
+struct StreamInfo {
+    sample_rate: u32,
+    bits: u32,
+    /// 0 if the encoder didn't know
+    total: u64,
+}
+
+pub(super) fn decode(bytes: &[u8]) -> Result<Track, AudioError> {
+    let mut at = 4;
+    let mut info = None;
+    // metadata blocks, of which only the stream info (always first) matters here
+    loop {
+        let header = bytes.get(at..at + 4).ok_or_else(ended)?;
+        let (last, kind) = (header[0] & 0x80 != 0, header[0] & 0x7f);
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let body = bytes.get(at + 4..at + 4 + length).ok_or_else(ended)?;
+        if kind == 0 {
+            let mut bits = Bits::new(body);
+            // block and frame sizes, which we don't need to know in advance
+            bits.unsigned(16 + 16 + 24 + 24)?;
+            let sample_rate = bits.unsigned(20)? as u32;
+            // how many channels, which every frame says again
+            bits.unsigned(3)?;
+            let sample_bits = bits.unsigned(5)? as u32 + 1;
+            let total = bits.unsigned(36)?;
+            info = Some(StreamInfo {
+                sample_rate,
+                bits: sample_bits,
+                total,
+            });
+        }
+        at += 4 + length;
+        if last {
+            break;
+        }
+    }
+    let info = info.ok_or_else(|| invalid("there's no stream info"))?;
+
+    let mut samples = vec![];
+    let mut bits = Bits::new(&bytes[at..]);
+    while bits.byte_offset() + 2 <= bits.bytes.len() {
+        if info.total > 0 && samples.len() as u64 >= info.total {
+            break;
+        }
+        // anything after the last frame that isn't one, like an ID3 tag, is the end
+        if bits.unsigned(14)? != 0x3ffe {
+            break;
+        }
+        frame(&mut bits, &info, &mut samples)?;
+    }
+    if info.total > 0 {
+        samples.truncate(info.total as usize);
+    }
+    Ok(Track {
+        sample_rate: info.sample_rate,
+        samples,
+    })
+}
+
+/// decodes one frame, from just past its sync code, mixing its channels into `out`
+fn frame(bits: &mut Bits<'_>, info: &StreamInfo, out: &mut Vec<f32>) -> Result<(), AudioError> {
+    // reserved, and whether frames are numbered by frame or by sample
+    bits.unsigned(2)?;
+    let size_code = bits.unsigned(4)?;
+    let rate_code = bits.unsigned(4)?;
+    let assignment = bits.unsigned(4)?;
+    let sample_bits = match bits.unsigned(3)? {
+        0 => info.bits,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => return Err(invalid("a frame's sample size is reserved")),
+    };
+    bits.unsigned(1)?;
+    // the frame or sample number, UTF-8 style: as many leading ones as bytes, then 10xxxxxx
+    let first = bits.unsigned(8)?;
+    let following = (first as u8).leading_ones().saturating_sub(1);
+    bits.unsigned(8 * following)?;
+    let size = match size_code {
+        0 => return Err(invalid("a frame's block size is reserved")),
+        1 => 192,
+        2..=5 => 576 << (size_code - 2),
+        6 => bits.unsigned(8)? + 1,
+        7 => bits.unsigned(16)? + 1,
+        _ => 256 << (size_code - 8),
+    } as usize;
+    match rate_code {
+        12 => bits.unsigned(8)?,
+        13 | 14 => bits.unsigned(16)?,
+        15 => return Err(invalid("a frame's sample rate is invalid")),
+        _ => 0,
+    };
+    // the header's CRC-8
+    bits.unsigned(8)?;
+
+    let channels = match assignment {
+        0..=7 => assignment as usize + 1,
+        8..=10 => 2,
+        _ => return Err(invalid("a frame's channel assignment is reserved")),
+    };
+    let mut decoded = Vec::with_capacity(channels);
+    for channel in 0..channels {
+        // the side channel of a stereo pair needs a bit more to hold a difference
+        let side = matches!((assignment, channel), (8, 1) | (9, 0) | (10, 1));
+        decoded.push(subframe(bits, sample_bits + side as u32, size)?);
+    }
+    if let [first, second] = &mut decoded[..] {
+        for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+            (*a, *b) = match assignment {
+                // left and side
+                8 => (*a, *a - *b),
+                // side and right
+                9 => (*a + *b, *b),
+                // mid and side; the side's low bit is the one the mid lost halving
+                10 => {
+                    let mid = *a << 1 | (*b & 1);
+                    ((mid + *b) >> 1, (mid - *b) >> 1)
+                }
+                _ => (*a, *b),
+            };
+        }
+    }
+    bits.align();
+    // the frame's CRC-16
+    bits.unsigned(16)?;
+
+    let scale = 2f32.powi(sample_bits as i32 - 1);
+    out.extend((0..size).map(|n| {
+        let total: i64 = decoded.iter().map(|channel| channel[n]).sum();
+        total as f32 / channels as f32 / scale
+    }));
+    Ok(())
+}
+
+fn subframe(bits: &mut Bits<'_>, sample_bits: u32, size: usize) -> Result<Vec<i64>, AudioError> {
+    if bits.bit()? {
+        return Err(invalid("a subframe's padding bit is set"));
+    }
+    let kind = bits.unsigned(6)?;
+    // low bits every sample had as zeros, which were left out
+    let wasted = match bits.bit()? {
+        true => bits.unary()? as u32 + 1,
+        false => 0,
+    };
+    let sample_bits = sample_bits
+        .checked_sub(wasted)
+        .ok_or_else(|| invalid("too many wasted bits"))?;
+    let mut samples = match kind {
+        0 => vec![bits.signed(sample_bits)?; size],
+        1 => (0..size)
+            .map(|_| bits.signed(sample_bits))
+            .collect::<Result<_, _>>()?,
+        8..=12 => {
+            let order = kind as usize - 8;
+            const FIXED: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+            let warmup = (0..order)
+                .map(|_| bits.signed(sample_bits))
+                .collect::<Result<Vec<_>, _>>()?;
+            lpc(bits, warmup, size, FIXED[order], 0)?
+        }
+        32..=63 => {
+            let order = kind as usize - 31;
+            let warmup = (0..order)
+                .map(|_| bits.signed(sample_bits))
+                .collect::<Result<Vec<_>, _>>()?;
+            let precision = bits.unsigned(4)? as u32 + 1;
+            if precision == 16 {
+                return Err(invalid("an LPC subframe's precision is invalid"));
+            }
+            let shift = bits.signed(5)?;
+            if shift < 0 {
+                return Err(invalid("an LPC subframe shifts the wrong way"));
+            }
+            let coefficients = (0..order)
+                .map(|_| bits.signed(precision))
+                .collect::<Result<Vec<_>, _>>()?;
+            lpc(bits, warmup, size, &coefficients, shift as u32)?
+        }
+        _ => return Err(invalid("a subframe's type is reserved")),
+    };
+    if wasted > 0 {
+        for sample in &mut samples {
+            *sample <<= wasted;
+        }
+    }
+    Ok(samples)
+}
+
+/// `warmup`, then each sample after predicted from the ones before, the first coefficient for the
+/// one just before, and corrected by the residual
+fn lpc(
+    bits: &mut Bits<'_>,
+    mut samples: Vec<i64>,
+    size: usize,
+    coefficients: &[i64],
+    shift: u32,
+) -> Result<Vec<i64>, AudioError> {
+    let order = samples.len();
+    if order > size {
+        return Err(invalid("a subframe has more warm-up samples than samples"));
+    }
+    let residual = residual(bits, size, order)?;
+    samples.reserve(size - order);
+    for error in residual {
+        let n = samples.len();
+        let prediction: i64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(i, coefficient)| coefficient * samples[n - 1 - i])
+            .sum();
+        samples.push((prediction >> shift) + error);
+    }
+    Ok(samples)
+}
+
+/// the `size - order` prediction errors, Rice coded in 2^k partitions
+fn residual(bits: &mut Bits<'_>, size: usize, order: usize) -> Result<Vec<i64>, AudioError> {
+    let (parameter_bits, escape) = match bits.unsigned(2)? {
+        0 => (4, 15),
+        1 => (5, 31),
+        _ => return Err(invalid("a residual's coding method is reserved")),
+    };
+    let partition_order = bits.unsigned(4)? as u32;
+    let partitions = 1usize << partition_order;
+    if !size.is_multiple_of(partitions) || size / partitions < order {
+        return Err(invalid("a residual's partitions don't fit its block"));
+    }
+    let mut errors = Vec::with_capacity(size - order);
+    for partition in 0..partitions {
+        // the first partition's share starts after the warm-up samples
+        let count = size / partitions - if partition == 0 { order } else { 0 };
+        let parameter = bits.unsigned(parameter_bits)?;
+        if parameter == escape {
+            let raw = bits.unsigned(5)? as u32;
+            for _ in 0..count {
+                errors.push(bits.signed(raw)?);
+            }
+            continue;
+        }
+        for _ in 0..count {
+            let value = bits.unary()? << parameter | bits.unsigned(parameter as u32)?;
+            // zigzag: 0, -1, 1, -2, 2...
+            errors.push((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `fields`, each a value and how many bits it's written in, packed most significant first
+    /// and padded out to a whole byte
+    fn pack(fields: &[(i64, u32)]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut at = 0;
+        for &(value, count) in fields {
+            for bit in (0..count).rev() {
+                if at % 8 == 0 {
+                    bytes.push(0);
+                }
+                if value >> bit & 1 == 1 {
+                    *bytes.last_mut().expect("pushed one") |= 0x80 >> (at % 8);
+                }
+                at += 1;
+            }
+        }
+        bytes
+    }
+
+    /// a stream of 16-bit 44.1kHz mono, `total` samples long, then `frames`
+    fn stream(total: i64, frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0x80, 0, 0, 34]);
+        bytes.extend(pack(&[
+            (4096, 16),
+            (4096, 16),
+            (0, 24),
+            (0, 24),
+            (44100, 20),
+            (0, 3),
+            (15, 5),
+            (total, 36),
+            (0, 64),
+            (0, 64),
+        ]));
+        bytes.extend(frames.concat());
+        bytes
+    }
+
+    /// a frame of `size` samples in channels assigned by `assignment`, their sample size by
+    /// `bits` (0 for the stream's), and its `subframes`
+    fn frame(assignment: i64, bits: i64, size: i64, subframes: &[(i64, u32)]) -> Vec<u8> {
+        let mut header = vec![
+            (0x3ffe, 14),
+            (0, 2),
+            (6, 4),
+            (0, 4),
+            (assignment, 4),
+            (bits, 3),
+            (0, 1),
+            (0, 8),
+            (size - 1, 8),
+            (0, 8),
+        ];
+        header.extend_from_slice(subframes);
+        let mut frame = pack(&header);
+        frame.extend([0, 0]);
+        frame
+    }
+
+    fn frames() -> Vec<Vec<u8>> {
+        let verbatim = frame(
+            0,
+            0,
+            4,
+            &[
+                (0, 1),
+                (1, 6),
+                (0, 1),
+                (1000, 16),
+                (-1000, 16),
+                (16384, 16),
+                (-32768, 16),
+            ],
+        );
+        // second order: a ramp, then 50 short of where it was heading
+        let fixed = frame(
+            0,
+            4,
+            6,
+            &[
+                (0, 1),
+                (10, 6),
+                (0, 1),
+                (0, 16),
+                (100, 16),
+                (0, 2),
+                (0, 4),
+                (2, 4),
+                (1, 1),
+                (0, 2),
+                (1, 1),
+                (0, 2),
+                (1, 1),
+                (0, 2),
+                // -50 zigzags to 99, 24 << 2 | 3
+                (0, 24),
+                (1, 1),
+                (3, 2),
+            ],
+        );
+        // (3 × the last - the one before) / 2, its residual escaped to 3 bits a sample
+        let lpc = frame(
+            0,
+            0,
+            4,
+            &[
+                (0, 1),
+                (33, 6),
+                (0, 1),
+                (10, 16),
+                (20, 16),
+                (4, 4),
+                (1, 5),
+                (3, 5),
+                (-1, 5),
+                (1, 2),
+                (0, 4),
+                (31, 5),
+                (3, 5),
+                (0, 3),
+                (1, 3),
+            ],
+        );
+        // 8-bit mid and side: mid 5 and side 3 are left 7 and right 4
+        let stereo = frame(
+            10,
+            1,
+            2,
+            &[
+                (0, 1),
+                (0, 6),
+                (0, 1),
+                (5, 8),
+                (0, 1),
+                (0, 6),
+                (0, 1),
+                (3, 9),
+            ],
+        );
+        vec![verbatim, fixed, lpc, stereo]
+    }
+
+    #[test]
+    fn decodes_every_kind_of_subframe() {
+        let mut bytes = stream(16, &frames());
+        // an ID3 tag after the last frame
+        bytes.extend(b"TAG");
+        let track = decode(&bytes).expect("it decodes");
+        assert_eq!(track.sample_rate, 44100);
+        let sixteen = [
+            1000, -1000, 16384, -32768, 0, 100, 200, 300, 400, 450, 10, 20, 25, 28,
+        ]
+        .map(|sample| sample as f32 / 32768.0);
+        assert_eq!(track.samples[..14], sixteen);
+        assert_eq!(track.samples[14..], [11.0 / 256.0; 2]);
+    }
+
+    #[test]
+    fn stops_at_the_total() {
+        let track = decode(&stream(6, &frames())).expect("it decodes");
+        assert_eq!(track.samples.len(), 6);
+        // an encoder that didn't know the total
+        let track = decode(&stream(0, &frames())).expect("it decodes");
+        assert_eq!(track.samples.len(), 16);
+    }
+
+    #[test]
+    fn malformed_streams_are_refused() {
+        let message = |bytes: &[u8]| decode(bytes).err().map(|err| err.to_string());
+        let ends = Some("the FLAC file ends partway through a frame".to_owned());
+
+        let whole = stream(16, &frames());
+        assert_eq!(message(&whole[..20]), ends);
+        assert_eq!(message(&whole[..whole.len() - 5]), ends);
+
+        let mut padding = b"fLaC".to_vec();
+        padding.extend([0x81, 0, 0, 0]);
+        assert_eq!(
+            message(&padding),
+            Some("not a FLAC file we can read: there's no stream info".to_owned())
+        );
+
+        let mut reserved = frame(0, 0, 1, &[(0, 1), (1, 6), (0, 1), (0, 16)]);
+        // block size code 0
+        reserved[2] &= 0x0f;
+        assert_eq!(
+            message(&stream(1, &[reserved])),
+            Some("not a FLAC file we can read: a frame's block size is reserved".to_owned())
+        );
+
+        let padded = frame(0, 0, 1, &[(1, 1)]);
+        assert_eq!(
+            message(&stream(1, &[padded])),
+            Some("not a FLAC file we can read: a subframe's padding bit is set".to_owned())
+        );
+    }
+}
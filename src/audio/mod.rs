@@ -0,0 +1,285 @@
+//! composing to a song: time runs left to right across the canvas, loud stretches are cut finer,
+//! beats become the lines standing up, and brighter passages cut higher and lean on the
+//! palette's lighter colors
+//!
+//! reading WAV and FLAC files needs the `audio` feature; what's done with the sound doesn't
+
+#[cfg(feature = "audio")]
+mod flac;
+#[cfg(feature = "audio")]
+mod wav;
+
+use std::{error::Error, f32::consts::PI, fmt, io, ops::Range, path::Path};
+
+use image::Rgb;
+use rand::Rng;
+
+use crate::{
+    color::{luma, Palette},
+    fill::FillOptions,
+    geometry::{Rectangle, SplitOptions},
+    tree::Tree,
+};
+
+/// how many stretches of time a track is cut into, left to right
+const SLICES: usize = 256;
+/// how many samples from the middle of each slice its brightness is measured over
+const FRAME: usize = 512;
+/// how many frequencies, evenly spaced up to half the sample rate, that's measured at
+const BANDS: usize = 64;
+/// how near a pane's sides an onset can be and still get a line of its own
+const MARGIN: f32 = 0.1;
+
+/// a decoded track, mixed down to one channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub sample_rate: u32,
+    /// from -1 to 1
+    pub samples: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    Io(io::Error),
+    /// it's not a WAV or FLAC file we can read, and why
+    Format(String),
+    /// built without the `audio` feature
+    Disabled,
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Format(why) => write!(f, "{why}"),
+            Self::Disabled => write!(f, "reading audio needs the `audio` feature"),
+        }
+    }
+}
+
+impl Error for AudioError {}
+
+impl From<io::Error> for AudioError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// the WAV (8 to 32 bit PCM, or float) or FLAC file at `path`, whichever it turns out to be
+#[cfg(feature = "audio")]
+pub fn read(path: impl AsRef<Path>) -> Result<Track, AudioError> {
+    let bytes = std::fs::read(path)?;
+    match bytes.get(..4) {
+        Some(b"RIFF") => wav::decode(&bytes),
+        Some(b"fLaC") => flac::decode(&bytes),
+        _ => Err(AudioError::Format(
+            "that's not a WAV or FLAC file".to_owned(),
+        )),
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn read(_path: impl AsRef<Path>) -> Result<Track, AudioError> {
+    Err(AudioError::Disabled)
+}
+
+/// what a track sounds like over time: each from 0 to 1, one of each per slice of it, the loudest,
+/// most sudden and brightest slices 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    /// how loud, as the root mean square of the samples
+    pub loudness: Vec<f32>,
+    /// how much louder than the slice before, which is where the beats are
+    pub onsets: Vec<f32>,
+    /// where the sound's spectrum is centered, high for cymbals and low for bass
+    pub brightness: Vec<f32>,
+}
+
+impl Features {
+    pub fn of(track: &Track) -> Self {
+        let samples = &track.samples;
+        let slices: Vec<&[f32]> = (0..SLICES)
+            .map(|i| &samples[i * samples.len() / SLICES..(i + 1) * samples.len() / SLICES])
+            .collect();
+        let loudness: Vec<f32> = slices
+            .iter()
+            .map(|slice| {
+                let squares: f32 = slice.iter().map(|sample| sample * sample).sum();
+                (squares / slice.len().max(1) as f32).sqrt()
+            })
+            .collect();
+        let onsets: Vec<f32> = (0..SLICES)
+            .map(|i| match i {
+                0 => 0.0,
+                i => (loudness[i] - loudness[i - 1]).max(0.0),
+            })
+            .collect();
+        let brightness = slices
+            .iter()
+            .enumerate()
+            .map(|(i, slice)| {
+                // the frame can run past a short slice into its neighbors
+                let middle = i * samples.len() / SLICES + slice.len() / 2;
+                let start = middle.saturating_sub(FRAME / 2).min(samples.len());
+                centroid(&samples[start..(start + FRAME).min(samples.len())])
+            })
+            .collect();
+        Self {
+            loudness: normalized(loudness),
+            onsets: normalized(onsets),
+            brightness: normalized(brightness),
+        }
+    }
+
+    /// the slices under `rectangle`, which is somewhere in `bounds`
+    fn span(&self, bounds: &Rectangle, rectangle: &Rectangle) -> Range<usize> {
+        let slice = |x: f32| {
+            let along = (x - bounds.x) / bounds.width.max(f32::MIN_POSITIVE);
+            ((along * SLICES as f32).floor().max(0.0) as usize).min(SLICES - 1)
+        };
+        let from = slice(rectangle.x);
+        from..slice(rectangle.x + rectangle.width).max(from + 1)
+    }
+
+    /// the average of `values` over `span`
+    fn mean(values: &[f32], span: Range<usize>) -> f32 {
+        let count = span.len().max(1) as f32;
+        values[span].iter().sum::<f32>() / count
+    }
+}
+
+/// where a frame of samples' spectrum is centered, in [`BANDS`], windowed so its ends don't ring
+fn centroid(frame: &[f32]) -> f32 {
+    let length = frame.len() as f32;
+    let windowed: Vec<f32> = frame
+        .iter()
+        .enumerate()
+        .map(|(n, sample)| sample * (0.5 - 0.5 * (2.0 * PI * n as f32 / length).cos()))
+        .collect();
+    let (mut weighted, mut total) = (0.0, 0.0);
+    for band in 1..=BANDS {
+        // every so many of the frame's bins, up to the one at half the sample rate
+        let bin = band as f32 * length / (2 * BANDS) as f32;
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, sample) in windowed.iter().enumerate() {
+            let angle = 2.0 * PI * bin * n as f32 / length;
+            re += sample * angle.cos();
+            im -= sample * angle.sin();
+        }
+        let magnitude = re.hypot(im);
+        weighted += band as f32 * magnitude;
+        total += magnitude;
+    }
+    if total > 0.0 {
+        weighted / total
+    } else {
+        0.0
+    }
+}
+
+/// `values` stretched to run from 0 to 1, or all 0 if they're all the same
+fn normalized(values: Vec<f32>) -> Vec<f32> {
+    let low = values.iter().copied().fold(f32::MAX, f32::min);
+    let high = values.iter().copied().fold(f32::MIN, f32::max);
+    let range = high - low;
+    values
+        .into_iter()
+        .map(|value| {
+            if range > 0.0 {
+                (value - low) / range
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// cuts every node across its long side for as many of `levels` as the loudest of the stretch
+/// of the track under it is loud, from a quarter of them for the quietest to all for the loudest: side by side
+/// at the strongest onset under it, or one above the other higher the brighter it is
+pub fn layout(
+    bounds: Rectangle,
+    features: &Features,
+    levels: usize,
+    options: &SplitOptions,
+) -> Tree<Rectangle> {
+    let mut tree = Tree::new(bounds);
+    tree.split_by(|rectangle, depth| {
+        let span = features.span(&bounds, rectangle);
+        // anything loud under it is worth cutting down to
+        let loudness = features.loudness[span.clone()]
+            .iter()
+            .copied()
+            .fold(0.0, f32::max);
+        let deepest = (levels as f32 * (0.25 + 0.75 * loudness)).round() as usize;
+        // nothing left to split once a pane is down to a pixel
+        if depth >= deepest || rectangle.width.max(rectangle.height) < 2.0 {
+            return None;
+        }
+        let side_by_side = rectangle.width >= rectangle.height;
+        let t = if side_by_side {
+            let slice_width = bounds.width / SLICES as f32;
+            span.filter_map(|slice| {
+                let x = bounds.x + (slice as f32 + 0.5) * slice_width;
+                let t = (x - rectangle.x) / rectangle.width;
+                (MARGIN..=1.0 - MARGIN)
+                    .contains(&t)
+                    .then_some((features.onsets[slice], t))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or(0.5, |(_, t)| t)
+        } else {
+            let brightness = Features::mean(&features.brightness, span);
+            0.8 - 0.6 * brightness
+        };
+        Some(rectangle.divide(side_by_side, t, options))
+    });
+    tree
+}
+
+/// a color for each of `leaves`, which cover `bounds`: more often filled the louder the track is
+/// under it, and from a palette weighted towards the colors as light as the sound is bright
+pub fn colors<R: Rng + ?Sized>(
+    leaves: &[Rectangle],
+    bounds: &Rectangle,
+    features: &Features,
+    palette: &Palette,
+    options: &FillOptions,
+    rng: &mut R,
+) -> Vec<Rgb<u8>> {
+    let colors = palette.colors();
+    leaves
+        .iter()
+        .map(|leaf| {
+            let span = features.span(bounds, leaf);
+            let loudness = Features::mean(&features.loudness, span.clone());
+            let brightness = Features::mean(&features.brightness, span);
+            let probability = (options.probability * (0.5 + loudness)).clamp(0.0, 1.0);
+            if !rng.gen_bool(probability as f64) {
+                return options.background;
+            }
+            let weights: Vec<f32> = colors
+                .iter()
+                .zip(palette.weights())
+                .map(|(&color, &weight)| {
+                    let nearness = 1.0 - (luma(color) / 255.0 - brightness).abs();
+                    weight as f32 * (0.25 + nearness)
+                })
+                .collect();
+            let total: f32 = weights.iter().sum();
+            if total <= 0.0 {
+                return options.background;
+            }
+            let mut pick = rng.gen_range(0.0..total);
+            for (color, weight) in colors.iter().zip(&weights) {
+                if pick < *weight {
+                    return *color;
+                }
+                pick -= weight;
+            }
+            // rounding can leave a sliver past the end, which is the last color's
+            let last = weights.iter().rposition(|weight| *weight > 0.0);
+            colors[last.expect("the weights add up to more than nothing")]
+        })
+        .collect()
+}
@@ -0,0 +1,199 @@
+//! RIFF WAVE files: PCM from 8 to 32 bits, and 32 or 64 bit float, including the extensible
+//! header that says which of those it is further in
+
+use super::{AudioError, Track};
+
+const PCM: u16 = 1;
+const FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xfffe;
+
+struct Format {
+    tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    bits: u16,
+}
+
+pub(super) fn decode(bytes: &[u8]) -> Result<Track, AudioError> {
+    let invalid = |why: &str| AudioError::Format(format!("not a WAV file we can read: {why}"));
+    if bytes.get(8..12) != Some(b"WAVE") {
+        return Err(invalid("it's RIFF, but not WAVE"));
+    }
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+
+    let (mut format, mut data) = (None, None);
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let (id, length) = (&bytes[at..at + 4], u32_at(at + 4) as usize);
+        let body = at + 8;
+        // a chunk that says it runs past the end is cut short, as when the length wasn't filled in
+        let end = body.saturating_add(length).min(bytes.len());
+        match id {
+            b"fmt " if end - body >= 16 => {
+                let mut tag = u16_at(body);
+                // the real tag is the first two bytes of the subformat GUID
+                if tag == EXTENSIBLE && end - body >= 26 {
+                    tag = u16_at(body + 24);
+                }
+                format = Some(Format {
+                    tag,
+                    channels: u16_at(body + 2),
+                    sample_rate: u32_at(body + 4),
+                    bits: u16_at(body + 14),
+                });
+            }
+            b"data" => data = Some(&bytes[body..end]),
+            _ => {}
+        }
+        // chunks start on even bytes
+        at = end + (length & 1);
+    }
+    let format = format.ok_or_else(|| invalid("there's no fmt chunk"))?;
+    let data = data.ok_or_else(|| invalid("there's no data chunk"))?;
+    if format.channels == 0 {
+        return Err(invalid("it has no channels"));
+    }
+
+    let sample: fn(&[u8]) -> f32 = match (format.tag, format.bits) {
+        (PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (PCM, 24) => |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0,
+        (PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (FLOAT, 64) => |b| f64::from_le_bytes(b[..8].try_into().expect("8 bytes")) as f32,
+        (tag, bits) => {
+            return Err(invalid(&format!(
+                "format {tag} at {bits} bits isn't 8 to 32 bit PCM or float"
+            )))
+        }
+    };
+    let width = format.bits as usize / 8;
+    let channels = format.channels as usize;
+    let samples = data
+        .chunks_exact(width * channels)
+        .map(|frame| {
+            let total: f32 = frame.chunks_exact(width).map(sample).sum();
+            total / channels as f32
+        })
+        .collect();
+    Ok(Track {
+        sample_rate: format.sample_rate,
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a WAV file of `data` in format `tag`, `channels` channels of `bits` bits each
+    fn wav(tag: u16, channels: u16, bits: u16, data: &[u8]) -> Vec<u8> {
+        let mut fmt = vec![];
+        fmt.extend(tag.to_le_bytes());
+        fmt.extend(channels.to_le_bytes());
+        fmt.extend(8000u32.to_le_bytes());
+        fmt.extend((8000 * channels as u32 * bits as u32 / 8).to_le_bytes());
+        fmt.extend((channels * bits / 8).to_le_bytes());
+        fmt.extend(bits.to_le_bytes());
+        riff(&[(b"fmt ", fmt), (b"data", data.to_vec())])
+    }
+
+    fn riff(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, body) in chunks {
+            bytes.extend_from_slice(*id);
+            bytes.extend((body.len() as u32).to_le_bytes());
+            bytes.extend(body);
+            if body.len() % 2 == 1 {
+                bytes.push(0);
+            }
+        }
+        bytes
+    }
+
+    fn samples(bytes: &[u8]) -> Vec<f32> {
+        decode(bytes).expect("it decodes").samples
+    }
+
+    #[test]
+    fn decodes_pcm() {
+        assert_eq!(samples(&wav(PCM, 1, 8, &[128, 192, 0])), [0.0, 0.5, -1.0]);
+        let sixteen: Vec<u8> = [16384i16, -32768, 0, -8192]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        // stereo, mixed down
+        assert_eq!(samples(&wav(PCM, 2, 16, &sixteen)), [-0.25, -0.125]);
+        let twenty_four = [0x00, 0x00, 0x40, 0x00, 0x00, 0xc0, 0xff, 0xff, 0x7f];
+        assert_eq!(
+            samples(&wav(PCM, 1, 24, &twenty_four)),
+            [0.5, -0.5, 8_388_607.0 / 8_388_608.0]
+        );
+        let track = decode(&wav(PCM, 1, 8, &[128])).expect("it decodes");
+        assert_eq!(track.sample_rate, 8000);
+    }
+
+    #[test]
+    fn decodes_float_and_extensible() {
+        let floats: Vec<u8> = [0.25f32, -0.75]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        assert_eq!(samples(&wav(FLOAT, 1, 32, &floats)), [0.25, -0.75]);
+
+        // the extension: its size, valid bits, channel mask, then the GUID, PCM's tag first
+        let mut fmt = wav(EXTENSIBLE, 1, 16, &[])[20..36].to_vec();
+        fmt.extend(22u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+        fmt.extend(4u32.to_le_bytes());
+        fmt.extend(PCM.to_le_bytes());
+        fmt.extend([0; 14]);
+        let bytes = riff(&[(b"fmt ", fmt), (b"data", 16384i16.to_le_bytes().to_vec())]);
+        assert_eq!(samples(&bytes), [0.5]);
+    }
+
+    #[test]
+    fn skips_other_chunks_and_short_data() {
+        let fmt = wav(PCM, 1, 8, &[])[20..36].to_vec();
+        let bytes = riff(&[
+            (b"LIST", b"odd".to_vec()),
+            (b"fmt ", fmt),
+            (b"data", vec![255, 128]),
+        ]);
+        assert_eq!(samples(&bytes), [127.0 / 128.0, 0.0]);
+        // a data chunk whose length was never filled in
+        let mut unfinished = wav(PCM, 1, 8, &[192, 64]);
+        let length = unfinished.len() - 6;
+        unfinished[length..length + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(samples(&unfinished), [0.5, -0.5]);
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        let message = |bytes: &[u8]| decode(bytes).err().map(|err| err.to_string());
+        let invalid = |why: &str| Some(format!("not a WAV file we can read: {why}"));
+        assert_eq!(
+            message(b"RIFF\0\0\0\0AVI LIST"),
+            invalid("it's RIFF, but not WAVE")
+        );
+        assert_eq!(
+            message(&riff(&[(b"data", vec![0])])),
+            invalid("there's no fmt chunk")
+        );
+        let fmt = wav(PCM, 1, 8, &[])[20..36].to_vec();
+        assert_eq!(
+            message(&riff(&[(b"fmt ", fmt)])),
+            invalid("there's no data chunk")
+        );
+        assert_eq!(
+            message(&wav(PCM, 0, 16, &[])),
+            invalid("it has no channels")
+        );
+        assert_eq!(
+            message(&wav(PCM, 1, 12, &[0, 0])),
+            invalid("format 1 at 12 bits isn't 8 to 32 bit PCM or float")
+        );
+    }
+}
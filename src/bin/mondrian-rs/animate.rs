@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use image::RgbImage;
+use mondrian::{
+    oklab,
+    prelude::*,
+    render::raster,
+    video::{self, Video},
+    zoom::Zoom,
+};
+
+use crate::{
+    generate::{self, Format, GenerateArgs, Renderer},
+    progress::Bar,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Animation {
+    /// a whole new composition every frame
+    Reroll,
+    /// one composition being made: the lines appear split by split, then the colors go in
+    Split,
+    /// each composition slowly fades into the next
+    Crossfade,
+    /// each composition turns into the next: the lines slide over to where the next one's are,
+    /// panes grow and shrink away, and the colors change as they go
+    Morph,
+    /// one composition zoomed into forever, panes cut again as they get big on screen
+    Zoom,
+}
+
+pub fn run(args: &GenerateArgs, animation: Animation) -> Result<(), Box<dyn Error>> {
+    let output = args.output();
+    if args.format.is_some_and(Format::is_markup) || Format::markup(&output).is_some() {
+        return Err("--animate writes images; markup formats are one composition at a time".into());
+    }
+    if args.format == Some(Format::Packed) {
+        return Err("--animate writes images; --format packed is one panel's buffer".into());
+    }
+    args.check_colors()?;
+    let mut bar = Bar::new("frames", generate::is_stdout(&output));
+    let mut sink = if video::is_video(&output) && args.format.is_none() {
+        // every frame is the same size, so one composition says how big the video is
+        let composition = args.builder()?.levels(0).build()?;
+        Sink::Video(Video::create(
+            &output,
+            composition.output_width(),
+            composition.output_height(),
+            args.fps,
+        )?)
+    } else {
+        Sink::Images {
+            output,
+            format: args.format,
+        }
+    };
+
+    let renderer = Renderer::new(args)?;
+    match animation {
+        Animation::Reroll => {
+            for frame in 0..args.frames {
+                let composition = nth(args, frame, args.frames)?;
+                let image = renderer.render(&composition)?;
+                sink.push(frame, &image, &composition.parameters())?;
+                bar.set(frame + 1, args.frames);
+            }
+        }
+        Animation::Split | Animation::Zoom if args.palette_drift.is_some() => {
+            let name = animation.to_possible_value().expect("no skipped values");
+            return Err(format!(
+                "--animate {} is one composition; --palette-drift needs more",
+                name.get_name()
+            )
+            .into());
+        }
+        Animation::Split => {
+            let composition = args.builder()?.build()?;
+            let parameters = composition.parameters();
+            // one stage per level of lines, one with none, and one with the colors
+            let stages = composition.levels() + 2;
+            for frame in 0..args.frames {
+                let depth = frame * stages / args.frames.max(1);
+                let mut image = raster::render_splits(&composition, depth);
+                renderer.finish(&mut image, &parameters);
+                sink.push(frame, &image, &parameters)?;
+                bar.set(frame + 1, args.frames);
+            }
+        }
+        Animation::Crossfade => {
+            let hold = args.hold.max(1);
+            // the last one fading in counts too
+            let count = args.frames.saturating_sub(1) / hold + 2;
+            let mut current = nth(args, 0, count)?;
+            let mut next = nth(args, 1, count)?;
+            let (mut from, mut to) = (renderer.render(&current)?, renderer.render(&next)?);
+            for frame in 0..args.frames {
+                let (index, into) = (frame / hold, frame % hold);
+                if into == 0 && index > 0 {
+                    current = next;
+                    next = nth(args, index + 1, count)?;
+                    from = to;
+                    to = renderer.render(&next)?;
+                }
+                // hold still for the first half, then fade
+                let t = (into as f32 - hold as f32 / 2.0).max(0.0) / (hold as f32 / 2.0);
+                let image = if t > 0.0 {
+                    blend(&from, &to, t)
+                } else {
+                    from.clone()
+                };
+                sink.push(frame, &image, &current.parameters())?;
+                bar.set(frame + 1, args.frames);
+            }
+        }
+        Animation::Morph if !args.style.splits() => {
+            return Err(format!(
+                "--style {} has no lines to morph; try crossfade",
+                args.style
+            )
+            .into());
+        }
+        Animation::Morph => {
+            let hold = args.hold.max(1);
+            let count = args.frames.saturating_sub(1) / hold + 2;
+            let mut current = nth(args, 0, count)?;
+            let mut still = renderer.render(&current)?;
+            for frame in 0..args.frames {
+                let (index, into) = (frame / hold, frame % hold);
+                if into == 0 && index > 0 {
+                    current = nth(args, index, count)?;
+                    still = renderer.render(&current)?;
+                }
+                // held like a crossfade, then eased in and out of
+                let t = (into as f32 - hold as f32 / 2.0).max(0.0) / (hold as f32 / 2.0);
+                let image = if t > 0.0 {
+                    let eased = t * t * (3.0 - 2.0 * t);
+                    let next = nth(args, index + 1, count)?;
+                    renderer.render(&next.morphed_from(&current, eased))?
+                } else {
+                    still.clone()
+                };
+                sink.push(frame, &image, &current.parameters())?;
+                bar.set(frame + 1, args.frames);
+            }
+        }
+        Animation::Zoom if !args.style.splits() => {
+            return Err(format!("--style {} has no panes to zoom into", args.style).into());
+        }
+        Animation::Zoom => {
+            let mut zoom = Zoom::new(args.builder()?.build()?);
+            // twice as close every --hold frames
+            let factor = 2f32.powf(1.0 / args.hold.max(1) as f32);
+            for frame in 0..args.frames {
+                let composition = zoom.frame();
+                let image = renderer.render(composition)?;
+                sink.push(frame, &image, &composition.parameters())?;
+                bar.set(frame + 1, args.frames);
+                zoom.advance(factor);
+            }
+        }
+    }
+    sink.finish()
+}
+
+/// the composition for the nth frame (or step) of an animation `count` of them long
+fn nth(args: &GenerateArgs, n: usize, count: usize) -> Result<Composition, Box<dyn Error>> {
+    Ok(args.nth_builder(n, count)?.build()?)
+}
+
+/// `t` of the way from `from` to `to`, mixed in OKLab so a red pane fading into a blue one goes
+/// through purple rather than a muddy one. compositions have few colors, so each pair is only
+/// worked out once
+fn blend(from: &RgbImage, to: &RgbImage, t: f32) -> RgbImage {
+    let mut image = from.clone();
+    let mut mixed = HashMap::new();
+    for (pixel, target) in image.pixels_mut().zip(to.pixels()) {
+        *pixel = *mixed
+            .entry((*pixel, *target))
+            .or_insert_with(|| oklab::mix(*pixel, *target, t));
+    }
+    image
+}
+
+/// where frames go
+enum Sink {
+    Video(Video),
+    /// stdout, or numbered files
+    Images {
+        output: PathBuf,
+        format: Option<generate::Format>,
+    },
+}
+
+impl Sink {
+    fn push(
+        &mut self,
+        frame: usize,
+        image: &RgbImage,
+        parameters: &Parameters,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Video(video) => video.push(image)?,
+            Self::Images { output, format } => {
+                let path = if generate::is_stdout(output) {
+                    output.clone()
+                } else {
+                    numbered(output, frame + 1)
+                };
+                generate::save(image, parameters, &path, *format)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        if let Self::Video(video) = self {
+            video.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// `mondrian.png` -> `mondrian-0001.png`
+fn numbered(output: &Path, frame: usize) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match output.extension() {
+        Some(extension) => format!("{stem}-{frame:04}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{frame:04}"),
+    };
+    output.with_file_name(name)
+}
@@ -0,0 +1,198 @@
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{CommandFactory, Parser};
+use mondrian::{
+    playlist::{Entry, Playlist, Value},
+    schedule,
+    trace::{self, Level},
+};
+use rand::Rng;
+
+use crate::{generate::GenerateArgs, metrics::Metrics, parse};
+
+#[derive(clap::Args, Debug)]
+pub struct DaemonArgs {
+    /// time between compositions, e.g. `30m`, `1h30m`, `45s`
+    #[arg(long, default_value = "30m", value_name = "DURATION", value_parser = parse::duration)]
+    pub interval: Duration,
+
+    /// wait up to this much longer each time, picked at random
+    #[arg(long, value_name = "DURATION", value_parser = parse::duration)]
+    pub jitter: Option<Duration>,
+
+    /// a playlist.toml of settings named after the flags, each put up on a cron-like schedule
+    /// like `0 7 * * 1-5` in local time, to go through sizes, palettes and styles over the day;
+    /// the flags come from it rather than from here
+    #[arg(long, value_name = "FILE", conflicts_with = "GenerateArgs")]
+    pub playlist: Option<PathBuf>,
+
+    /// where to answer Prometheus's scrapes of /metrics: images made, how long they took and
+    /// how many panes they had, and errors. e.g. `127.0.0.1:9090`
+    #[arg(long, value_name = "ADDRESS")]
+    pub metrics_addr: Option<String>,
+
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+/// regenerates until SIGINT/SIGTERM; a picture being written when the signal lands is finished first
+///
+/// every round gets a new seed. with --seed or --seed-from the sequence of seeds is fixed, so two
+/// machines given the same flags show the same pictures. with --calendar there's a round at
+/// local midnight too, so it's never a day behind
+///
+/// with --playlist, each round is drawn with the flags of the entry that's up, and there's a
+/// round as soon as the next one's time comes
+pub fn run(args: DaemonArgs) -> Result<(), Box<dyn Error>> {
+    let (stop, stopped) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(());
+    })?;
+
+    // every entry's flags, checked before the first round
+    let playlist = match &args.playlist {
+        Some(path) => {
+            let playlist = Playlist::open(path)?;
+            let flags = playlist
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    flags(entry)
+                        .map_err(|why| format!("entry {} of the playlist: {why}", index + 1))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some((playlist, flags))
+        }
+        None => None,
+    };
+
+    let metrics = Arc::new(Metrics::default());
+    if let Some(address) = &args.metrics_addr {
+        crate::metrics::listen(address, metrics.clone())?;
+    }
+
+    let mut rng = rand::thread_rng();
+    for round in 0u64.. {
+        // which entry is up, and when the next one's time comes, in minutes after 1970-01-01 UTC
+        let (generate, next) = match &playlist {
+            Some((playlist, flags)) => {
+                let offset = schedule::utc_offset()?;
+                let now = now().as_secs() as i64 / 60 + offset as i64;
+                let entry = playlist.at(now);
+                trace::event(Level::Info, "playlist", &[("entry", &(entry + 1))]);
+                let next = playlist.next(now).map(|next| next - offset as i64);
+                (&flags[entry], next)
+            }
+            None => (&args.generate, None),
+        };
+        let seed = generate.nth_seed(round).unwrap_or_else(|| rng.gen());
+        let span = trace::span(Level::Info, "round", &[("round", &round), ("seed", &seed)]);
+        let start = Instant::now();
+        let result = generate
+            .builder()
+            .and_then(|builder| Ok(builder.seed(seed).build()?))
+            .and_then(|composition| {
+                generate.write(&composition)?;
+                Ok(composition.panes().len())
+            });
+        match result {
+            Ok(panes) => {
+                metrics.made(panes, start.elapsed());
+                eprintln!("wrote {} (seed {seed})", generate.output().display())
+            }
+            // bad flags won't get better by waiting
+            Err(err) if round == 0 => return Err(err),
+            Err(err) => {
+                metrics.failed();
+                trace::event(Level::Info, "failed", &[("error", &err)]);
+                eprintln!("error: {err}");
+            }
+        }
+        drop(span);
+
+        let jitter = args
+            .jitter
+            .map(|jitter| rng.gen_range(Duration::ZERO..=jitter))
+            .unwrap_or_default();
+        let mut wait = args.interval + jitter;
+        if generate.calendar.is_some() {
+            wait = wait.min(until_midnight(schedule::utc_offset()?));
+        }
+        if let Some(next) = next {
+            let at = Duration::from_secs(next.max(0) as u64 * 60);
+            wait = wait.min(at.saturating_sub(now()));
+        }
+        match stopped.recv_timeout(wait) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+/// how long it's been since 1970-01-01, in UTC
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// how long until the next day starts on a clock `offset` minutes ahead of UTC
+fn until_midnight(offset: i32) -> Duration {
+    const DAY: i128 = 86_400_000_000_000;
+    let local = now().as_nanos() as i128 + offset as i128 * 60_000_000_000;
+    Duration::from_nanos((DAY - local.rem_euclid(DAY)) as u64)
+}
+
+/// the command line of one of a playlist's entries
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct EntryArgs {
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+/// `entry`'s settings as the flags they're named after: `--name=value`, just `--name` for
+/// `true`, and an array again and again, as many values at a time as the flag takes
+fn flags(entry: &Entry) -> Result<GenerateArgs, String> {
+    let mut command = EntryArgs::command();
+    command.build();
+    let at_a_time = |name: &str| {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name))
+            .and_then(|arg| arg.get_num_args())
+            .map_or(1, |range| range.max_values().max(1))
+    };
+    let mut flags = vec![];
+    for (name, value) in entry.merged() {
+        match value {
+            Value::Text(value) => flags.push(format!("--{name}={value}")),
+            Value::Switch(true) => flags.push(format!("--{name}")),
+            Value::Switch(false) => {}
+            Value::List(values) => {
+                for chunk in values.chunks(at_a_time(name)) {
+                    flags.push(format!("--{name}"));
+                    flags.extend(chunk.iter().cloned());
+                }
+            }
+        }
+    }
+    EntryArgs::try_parse_from(flags)
+        .map(|args| args.generate)
+        // just what's wrong, without clap's usage after it
+        .map_err(|err| {
+            let message = err.to_string();
+            let first = message.lines().next().unwrap_or_default();
+            first.trim_start_matches("error: ").to_owned()
+        })
+}
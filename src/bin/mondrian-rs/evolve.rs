@@ -0,0 +1,129 @@
+use std::{error::Error, fs, io::ErrorKind, path::PathBuf};
+
+use mondrian::{
+    evolve::{Genome, Member, Population},
+    prelude::*,
+    timing::Timings,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::generate::{self, GenerateArgs};
+
+#[derive(clap::Args, Debug)]
+pub struct EvolveArgs {
+    /// the population file to carry on from, which is made if it isn't there yet and written
+    /// back after; star a member's line with `*` to breed from it next time
+    pub population: PathBuf,
+
+    /// how many layouts a generation has
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u64).range(2..))]
+    pub population_size: u64,
+
+    /// breed from these members, numbered from 1 as they're listed, instead of the starred ones;
+    /// without either, the better scoring half are bred from
+    #[arg(long, value_name = "N,...", value_delimiter = ',')]
+    pub favorites: Vec<usize>,
+
+    /// how many generations to breed in one go; those after the first breed from the best scoring
+    #[arg(long, default_value_t = 1)]
+    pub generations: usize,
+
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+/// breeds the population in the file, or starts one from compositions made the way generating
+/// would, then writes every member next to --output, numbered, and lists how they score
+pub fn run(args: EvolveArgs) -> Result<(), Box<dyn Error>> {
+    let output = args.generate.output();
+    if generate::is_stdout(&output) {
+        return Err("evolve writes a file per member; give --output a file name".into());
+    }
+    let size = args.population_size as usize;
+    // a population that was only just made is shown before it's bred from
+    let (mut population, generations) = match fs::read_to_string(&args.population) {
+        Ok(text) => (
+            text.parse::<Population>()
+                .map_err(|err| format!("{}: {err}", args.population.display()))?,
+            args.generations,
+        ),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let members = (0..size)
+                .map(|n| {
+                    let composition = args.generate.nth_builder(n, size)?.build()?;
+                    Ok(Member {
+                        genome: Genome::of(composition.tree()),
+                        seed: composition.seed(),
+                        favorite: false,
+                    })
+                })
+                .collect::<Result<_, Box<dyn Error>>>()?;
+            let population = Population {
+                generation: 0,
+                members,
+            };
+            (population, 0)
+        }
+        Err(err) => return Err(format!("{}: {err}", args.population.display()).into()),
+    };
+    let build = |member: &Member| -> Result<Composition, Box<dyn Error>> {
+        Ok(args
+            .generate
+            .builder()?
+            .seed(member.seed)
+            .genome(member.genome.clone())
+            .build()?)
+    };
+
+    for round in 0..generations {
+        let mut parents = match round {
+            0 if !args.favorites.is_empty() => args
+                .favorites
+                .iter()
+                .map(|&n| match n {
+                    1.. if n <= population.members.len() => Ok(n - 1),
+                    _ => Err(format!(
+                        "there's no member {n}; there are {}",
+                        population.members.len()
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            0 => population.favorites(),
+            _ => vec![],
+        };
+        if parents.is_empty() {
+            let scores = population
+                .members
+                .iter()
+                .map(|member| Ok(build(member)?.score().total))
+                .collect::<Result<Vec<f64>, Box<dyn Error>>>()?;
+            parents = (0..scores.len()).collect();
+            parents.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+            parents.truncate(scores.len().div_ceil(2));
+        }
+        let seed = args
+            .generate
+            .nth_seed(format!("evolve:{}", population.generation))
+            .unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        population = population.bred(&parents, size, &mut rng);
+    }
+    fs::write(&args.population, population.to_string())?;
+
+    println!("generation {}", population.generation);
+    for (n, member) in population.members.iter().enumerate() {
+        let composition = build(member)?;
+        let path = generate::suffixed(&output, &format!("-{}", n + 1));
+        args.generate
+            .write_to(&composition, &path, &mut Timings::new())?;
+        println!(
+            "{:>3}  {}  {:>4} panes  {}",
+            n + 1,
+            path.display(),
+            member.genome.panes(),
+            composition.score()
+        );
+    }
+    Ok(())
+}
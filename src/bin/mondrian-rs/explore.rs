@@ -0,0 +1,343 @@
+use crate::generate::GenerateArgs;
+
+#[derive(clap::Args, Debug)]
+pub struct ExploreArgs {
+    /// where to start; `s` saves to --output and `p` the parameters next to it
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+#[cfg(all(feature = "tui", unix))]
+pub use tui::run;
+
+#[cfg(not(all(feature = "tui", unix)))]
+pub fn run(_args: ExploreArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err("explore needs mondrian-rs to be built with the `tui` feature, on a Unix terminal".into())
+}
+
+#[cfg(all(feature = "tui", unix))]
+mod tui {
+    use std::error::Error;
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::io::{self, IsTerminal, Write};
+    use std::time::Duration;
+
+    use image::Rgb;
+    use mondrian::{edit::History, encode, palettes::BUILTIN, prelude::*};
+
+    use super::ExploreArgs;
+    use crate::{
+        preview,
+        terminal::{Key, Terminal},
+    };
+
+    /// the --max-aspect values `[` and `]` step through; `None` is no limit
+    const ASPECTS: [Option<f32>; 7] = [
+        Some(2.0),
+        Some(2.5),
+        Some(3.0),
+        Some(4.0),
+        Some(6.0),
+        Some(8.0),
+        None,
+    ];
+
+    const HELP: &str = "space new seed  ←→ seed  ↑↓ levels  [] max aspect  c palette  s save  \
+                        p parameters  tab/click a pane to edit it  q quit";
+
+    /// what's been changed from the flags so far
+    struct State {
+        seed: u64,
+        levels: usize,
+        max_aspect: Option<f32>,
+        /// an index into [`BUILTIN`], or the palette from the flags
+        palette: Option<usize>,
+        /// edits made, and undone, since the layout last changed
+        history: History,
+        /// the pane the edit keys change
+        selected: Option<usize>,
+        /// what the last key did, for the status line
+        message: String,
+    }
+
+    impl State {
+        /// the full-size composition, as the flags make it but for the changes
+        fn build(
+            &self,
+            args: &ExploreArgs,
+            split_options: &SplitOptions,
+        ) -> Result<Composition, Box<dyn Error>> {
+            let mut builder = args
+                .generate
+                .builder()?
+                .seed(self.seed)
+                .levels(self.levels)
+                .split_options(SplitOptions {
+                    max_aspect: self.max_aspect,
+                    ..split_options.clone()
+                })
+                .history(self.history.clone());
+            if let Some(index) = self.palette {
+                builder = builder.palette(BUILTIN[index].1.clone());
+            }
+            Ok(builder.build()?)
+        }
+
+        /// flags that make the same composition from the command line
+        fn flags(&self) -> String {
+            let mut flags = format!("--seed {} --levels {}", self.seed, self.levels);
+            if let Some(max_aspect) = self.max_aspect {
+                write!(flags, " --max-aspect {max_aspect}").unwrap();
+            }
+            if let Some(index) = self.palette {
+                write!(flags, " --palette-file {}", BUILTIN[index].0).unwrap();
+            }
+            flags.extend(preview::flag(&self.history.done));
+            flags
+        }
+    }
+
+    /// a live preview in the terminal, changed a key at a time
+    pub fn run(args: ExploreArgs) -> Result<(), Box<dyn Error>> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return Err(
+                "explore draws in the terminal, so it needs one on stdin and stdout".into(),
+            );
+        }
+        let start = args.generate.builder()?.build()?;
+        let split_options = start.split_options().clone();
+        let mut state = State {
+            seed: start.seed(),
+            levels: start.levels(),
+            max_aspect: split_options.max_aspect,
+            palette: None,
+            history: start.history(),
+            selected: None,
+            message: String::new(),
+        };
+
+        let terminal = Terminal::new()?;
+        let mut composition = start;
+        // what's on the screen, to find the panes clicked on
+        let mut view: Option<View> = None;
+        let mut drawn = None;
+        loop {
+            let size = terminal.size()?;
+            if drawn != Some(size) {
+                view = Some(draw(&composition, &state, size)?);
+                drawn = Some(size);
+            }
+            let Some(key) = terminal.key(Duration::from_millis(250))? else {
+                continue;
+            };
+            let aspect = ASPECTS
+                .iter()
+                .position(|aspect| *aspect == state.max_aspect)
+                .unwrap_or(ASPECTS.len() - 1);
+            let panes = composition.panes().len();
+            state.message.clear();
+            // whether the layout changes, which throws away the edits to the old one
+            let mut layout = true;
+            match key {
+                Key::Char('q') | Key::Escape => break,
+                Key::Char(' ' | 'r') => state.seed = rand::random(),
+                Key::Right | Key::Char('n') => state.seed = state.seed.wrapping_add(1),
+                Key::Left | Key::Char('b') => state.seed = state.seed.wrapping_sub(1),
+                Key::Up | Key::Char('+' | '=') => state.levels = (state.levels + 1).min(16),
+                Key::Down | Key::Char('-') => state.levels = state.levels.saturating_sub(1),
+                Key::Char('[') => state.max_aspect = ASPECTS[aspect.saturating_sub(1)],
+                Key::Char(']') => state.max_aspect = ASPECTS[(aspect + 1).min(ASPECTS.len() - 1)],
+                Key::Char('c') => {
+                    state.palette = match state.palette {
+                        None => Some(0),
+                        Some(index) if index + 1 < BUILTIN.len() => Some(index + 1),
+                        Some(_) => None,
+                    };
+                    layout = false;
+                }
+                Key::Char('s') => {
+                    let output = args.generate.output();
+                    state.message = match args.generate.write(&composition) {
+                        Ok(()) => format!("saved {}", output.display()),
+                        Err(err) => format!("couldn't save: {err}"),
+                    };
+                    drawn = None;
+                    continue;
+                }
+                Key::Char('p') => {
+                    let path = args.generate.output().with_extension("txt");
+                    let text = encode::description(&composition.parameters()) + "\n";
+                    state.message = match fs::write(&path, text) {
+                        Ok(()) => format!("saved the parameters to {}", path.display()),
+                        Err(err) => format!("couldn't save the parameters: {err}"),
+                    };
+                    drawn = None;
+                    continue;
+                }
+                Key::Char('\t') | Key::BackTab if panes > 0 => {
+                    state.selected = Some(match (state.selected, key) {
+                        (None, Key::BackTab) => panes - 1,
+                        (None, _) => 0,
+                        (Some(pane), Key::BackTab) => (pane + panes - 1) % panes,
+                        (Some(pane), _) => (pane + 1) % panes,
+                    });
+                    drawn = None;
+                    continue;
+                }
+                Key::Char('.') => {
+                    state.selected = None;
+                    drawn = None;
+                    continue;
+                }
+                Key::Click { column, row } => {
+                    if let Some(view) = &view {
+                        state.selected = view.pane_at(column, row);
+                    }
+                    drawn = None;
+                    continue;
+                }
+                Key::Char(key @ ('u' | 'U' | 'h')) => {
+                    let output = args.generate.output();
+                    state.message =
+                        preview::history(&mut composition, key, &output).unwrap_or_default();
+                    state.history = composition.history();
+                    let panes = composition.panes().len();
+                    state.selected = state.selected.filter(|pane| *pane < panes);
+                    drawn = None;
+                    continue;
+                }
+                Key::Char(key) => {
+                    let edited = state
+                        .selected
+                        .and_then(|pane| preview::edit(&mut composition, pane, key));
+                    match (edited, state.selected) {
+                        (Some(Ok(pane)), _) => {
+                            state.selected = Some(pane);
+                            state.history = composition.history();
+                        }
+                        (Some(Err(err)), _) => state.message = err.to_string(),
+                        (None, None) if "xmo".contains(key) => {
+                            state.message = "pick a pane first, with tab or a click".to_owned()
+                        }
+                        (None, _) => continue,
+                    }
+                    drawn = None;
+                    continue;
+                }
+                _ => continue,
+            }
+            if layout {
+                state.history = History::default();
+                state.selected = None;
+            }
+            match state.build(&args, &split_options) {
+                Ok(next) => composition = next,
+                Err(err) => state.message = err.to_string(),
+            }
+            drawn = None;
+        }
+        drop(terminal);
+        println!("{}", state.flags());
+        Ok(())
+    }
+
+    /// where the preview went on the screen
+    struct View {
+        preview: Composition,
+        /// the column its left edge is in
+        left: u32,
+    }
+
+    impl View {
+        /// the pane in a character cell, going by its top half
+        fn pane_at(&self, column: u16, row: u16) -> Option<usize> {
+            let x = (column as u32).checked_sub(self.left)?;
+            let y = row as u32 * 2;
+            if x >= self.preview.width() || y >= self.preview.height() {
+                return None;
+            }
+            preview::pane_at(&self.preview, x as f32 + 0.5, y as f32 + 0.5)
+        }
+    }
+
+    /// the composition as big as fits above the status line, two pixels to a character cell
+    fn draw(
+        composition: &Composition,
+        state: &State,
+        (columns, rows): (u16, u16),
+    ) -> Result<View, Box<dyn Error>> {
+        let (columns, rows) = (columns as u32, rows.saturating_sub(1).max(1) as u32);
+        let aspect = composition.width() as f32 / composition.height() as f32;
+        let width = columns
+            .min((rows as f32 * 2.0 * aspect).round() as u32)
+            .max(1);
+        let height = ((width as f32 / aspect).round() as u32).clamp(2, rows * 2) & !1;
+
+        let small = preview::resized(composition, width, height)?;
+        let mut image = small.render_rgb();
+        if let Some(pane) = state.selected.and_then(|pane| small.panes().get(pane)) {
+            preview::outline(&mut image, &pane.rectangle);
+        }
+
+        let mut screen = String::from("\x1b[H\x1b[2J");
+        let left = (columns - image.width()) / 2;
+        let pad = " ".repeat(left as usize);
+        for y in (0..image.height()).step_by(2) {
+            screen.push_str(&pad);
+            let mut last = None;
+            for x in 0..image.width() {
+                let top = *image.get_pixel(x, y);
+                let bottom = *image.get_pixel(x, (y + 1).min(image.height() - 1));
+                if last != Some((top, bottom)) {
+                    let (Rgb([r, g, b]), Rgb([br, bg, bb])) = (top, bottom);
+                    write!(screen, "\x1b[38;2;{r};{g};{b};48;2;{br};{bg};{bb}m").unwrap();
+                    last = Some((top, bottom));
+                }
+                screen.push('▀');
+            }
+            screen.push_str("\x1b[0m\r\n");
+        }
+        let palette = match state.palette {
+            Some(index) => BUILTIN[index].0,
+            None => "from the flags",
+        };
+        let aspect = match state.max_aspect {
+            Some(max_aspect) => max_aspect.to_string(),
+            None => "any".to_owned(),
+        };
+        let status = match (state.message.is_empty(), state.selected) {
+            (false, _) => &state.message,
+            (true, Some(_)) => preview::EDIT_HELP,
+            (true, None) => HELP,
+        };
+        let pane = match state.selected {
+            Some(pane) => format!("  pane {pane}"),
+            None => String::new(),
+        };
+        let settings = format!(
+            " seed {}  levels {}  max aspect {aspect}  palette {palette}{pane} ",
+            state.seed, state.levels
+        );
+        // cut to the width, or the last line wraps and scrolls everything up
+        let line: Vec<char> = format!("{settings} {status}").chars().collect();
+        let line = &line[..line.len().min(columns as usize)];
+        let (settings, status) = line.split_at(settings.chars().count().min(line.len()));
+        let (settings, status): (String, String) =
+            (settings.iter().collect(), status.iter().collect());
+        write!(
+            screen,
+            "\x1b[{};1H\x1b[7m{settings}\x1b[0m{status}",
+            rows + 1
+        )
+        .unwrap();
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(screen.as_bytes())?;
+        stdout.flush()?;
+        Ok(View {
+            preview: small,
+            left,
+        })
+    }
+}
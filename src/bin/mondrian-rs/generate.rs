@@ -0,0 +1,1352 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser, TypedValueParser},
+    ArgAction, ArgGroup, ValueEnum,
+};
+use image::{Rgb, RgbImage};
+use mondrian::{
+    calendar::{Calendar, Date, Span},
+    color::{to_hex, Harmony},
+    cvd::Deficiency,
+    dashboard::{Dashboard, Usage},
+    detail, display,
+    dither::Dither,
+    edit::{Edit, History},
+    eink::Panel,
+    encode::{self, EncodeError},
+    filter::Pipeline,
+    icc,
+    mosaic::{self, Mosaic},
+    oklab, palettes, plugin,
+    prelude::*,
+    render::{
+        self,
+        gpu::{self, GpuError},
+        raster, svg, terminal, tikz,
+    },
+    schedule::{self, Schedule},
+    score::Score,
+    seed,
+    strategy::Builtin,
+    style::Style,
+    symmetry::Symmetry,
+    text::{Anchor, Card, Text},
+    timeline,
+    timing::Timings,
+    trace::{self, Level},
+    tree::Pick,
+    viewport::Viewport,
+    wallpaper,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    animate::{self, Animation},
+    gui, icons,
+    monitors::{self, Monitors},
+    parse,
+    progress::Bar,
+    sheet, sprites,
+};
+
+#[derive(clap::Args, Debug)]
+#[command(group(ArgGroup::new("sequence").args(["animate", "contact_sheet", "sprite_sheet"])))]
+pub struct GenerateArgs {
+    /// named canvas size, or `auto` for the primary display; --width/--height still override it
+    #[arg(long, value_enum)]
+    pub size: Option<Size>,
+
+    /// image width [default: 4096, or 256 with --avatar]
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// image height [default: 2160, or 256 with --avatar]
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// draw only this part of the canvas, `x,y,width,height` in pixels, cut straight out, or if
+    /// none of them is over 1, in fractions of it, blown up to the image size: one of several
+    /// monitors, a slice of a wider wallpaper, a close-up
+    #[arg(long, value_name = "X,Y,W,H", conflicts_with = "monitors")]
+    pub viewport: Option<Viewport>,
+
+    /// split iterations (max 2^n this many squares) [default: 5, or 3 with --avatar or --icons]
+    #[arg(long)]
+    pub levels: Option<usize>,
+
+    /// split one pane at a time until there are about N, instead of every pane --levels times;
+    /// detail masks, keep-clear zones and QR codes still go by levels
+    #[arg(long, value_name = "N", conflicts_with_all = ["levels", "symmetry", "from_image"])]
+    pub target_panes: Option<usize>,
+
+    /// split one pane at a time, this one next, until there are --target-panes or 2^levels:
+    /// the largest, or any, likelier the bigger it is, for panes at all sorts of depths
+    /// [default: largest]
+    #[arg(long, value_enum, conflicts_with_all = ["symmetry", "from_image"])]
+    pub pick: Option<Pick>,
+
+    /// colors to use: hex (#ff0000 or #f00), CSS names (crimson), rgb(...) or hsl(...)
+    #[arg(long, action=ArgAction::Append, num_args=4, value_parser=parse::color, default_values = ["#ffffff", "#ff0000", "#ffff00", "#0000ff"])]
+    pub palette: Vec<Rgb<u8>>,
+
+    /// take the palette from this image instead, cut down to --palette-size colors and
+    /// weighted by how much of it each covers
+    #[arg(long, value_name = "PATH", conflicts_with = "palette_file")]
+    pub palette_from: Option<PathBuf>,
+
+    /// a palette file (GIMP .gpl, a color per line with optional weights, or .json), or the name
+    /// of a built-in or installed one; see `palette list`
+    #[arg(long, value_name = "PATH|NAME", conflicts_with = "random_palette")]
+    pub palette_file: Option<String>,
+
+    /// how many colors --palette-from picks
+    #[arg(long, default_value_t = 5, value_name = "N", requires = "palette_from")]
+    pub palette_size: usize,
+
+    /// make up a palette around a random hue instead (the same one for the same --seed), and
+    /// print it as a --palette to keep
+    #[arg(long, value_enum, value_name = "HARMONY", num_args = 0..=1, default_missing_value = "complementary", conflicts_with = "palette_from")]
+    pub random_palette: Option<Harmony>,
+
+    /// go by the time of day instead: warm colors at sunrise and sunset, bright ones at noon, dark
+    /// at night, blended in between, with --background the palette's first. the schedule can be
+    /// changed in ~/.config/mondrian-rs/schedule; give `HH:MM` to go by that time instead of now
+    #[arg(long, value_name = "HH:MM", num_args = 0..=1, default_missing_value = "now", value_parser = parse::clock, conflicts_with_all = ["palette_from", "palette_file", "random_palette"])]
+    pub adaptive_time: Option<Clock>,
+
+    // TODO: forward weights
+    /// rng seed; same seed and flags give the same image
+    #[arg(long, conflicts_with = "seed_from")]
+    pub seed: Option<u64>,
+
+    /// hash some text (username, commit hash, hostname...) into the seed
+    #[arg(long, value_name = "STRING")]
+    pub seed_from: Option<String>,
+
+    /// keep every pane within this aspect ratio, e.g. 3 for at most 3:1 (at least 2)
+    #[arg(long, value_name = "RATIO")]
+    pub max_aspect: Option<f32>,
+
+    /// where and which way panes get cut
+    #[arg(long, value_enum, default_value_t, value_name = "NAME")]
+    pub split_strategy: Builtin,
+
+    /// about how many dense patches fit across the long side, for `--split-strategy noise`
+    #[arg(long, default_value_t = 3.0, value_name = "F")]
+    pub noise_frequency: f32,
+
+    /// layers of finer detail in the noise, each twice as fine, from 1 to 16
+    #[arg(long, default_value_t = 4, value_name = "N")]
+    pub noise_octaves: u32,
+
+    /// a split strategy from a plugin, by the name it registered, in place of --split-strategy
+    #[arg(long, value_name = "NAME")]
+    pub split_plugin: Option<String>,
+
+    /// a fill from a plugin, by the name it registered, in place of --color-strategy
+    #[arg(long, value_name = "NAME")]
+    pub fill_plugin: Option<String>,
+
+    /// a shared library with split strategies and fills in it, built against the same
+    /// mondrian-rs; can be given more than once (needs the `plugins` feature)
+    #[arg(long, value_name = "PATH")]
+    pub plugin: Vec<PathBuf>,
+
+    /// chance that a pane is also cut corner to corner into two triangles
+    #[arg(long, default_value_t = 0.0, value_name = "P")]
+    pub diagonal: f32,
+
+    /// chance that a squarish pane is cut across the way its parent was cut, rather than the
+    /// same way again; 1 for strictly alternating cuts there
+    #[arg(long, default_value_t = 0.0, value_name = "P")]
+    pub cross: f32,
+
+    /// split and color rules of your own: a script with any of `should_split(rect, depth)`,
+    /// `split_ratio(rect)` and `pick_color(rect, neighbors)` in it, in a small rhai-like
+    /// language; --levels becomes how deep it may go. see examples/rules.rhai (needs the `script`
+    /// feature)
+    #[arg(long, value_name = "PATH")]
+    pub script: Option<PathBuf>,
+
+    /// lay out one half or quarter and mirror it into the rest, for kaleidoscopic variants;
+    /// `rotational` turns the left half upside down for the right
+    #[arg(long, value_enum, value_name = "HOW")]
+    pub symmetry: Option<Symmetry>,
+
+    /// a grayscale image stretched over the canvas: white parts get all --levels of splits,
+    /// darker ones fewer and black none, to keep small panes where you want them
+    #[arg(long, value_name = "PATH")]
+    pub detail_mask: Option<PathBuf>,
+
+    /// an area, `x,y,w,h` in pixels, for no cuts to go through, say behind a clock or a dock;
+    /// panes mostly inside it are left the background. can be given more than once
+    #[arg(long, value_name = "X,Y,W,H", value_parser = detail::parse_zone)]
+    pub keep_clear: Vec<Rectangle>,
+
+    /// text to draw over the picture, with `\n` between lines; `{seed}`, `{date}`, `{time}`,
+    /// `{width}`, `{height}` and `{levels}` are filled in, and `{{` and `}}` are braces
+    #[arg(long, value_name = "STRING")]
+    pub text: Option<String>,
+
+    /// a TrueType font for --text, --title, --calendar, the --stats-legend and --contact-sheet
+    /// labels [default: DejaVu Sans or whatever sans serif is around]
+    #[arg(long, value_name = "PATH")]
+    pub font: Option<PathBuf>,
+
+    /// the --text em size in pixels [default: a twentieth of the height]
+    #[arg(long, value_name = "PX", requires = "text")]
+    pub text_size: Option<f32>,
+
+    #[arg(long, value_parser=parse::color, default_value = "#000000")]
+    pub text_color: Rgb<u8>,
+
+    /// where the --text goes, half an em in from the edges
+    #[arg(long, value_enum, default_value_t, value_name = "WHERE")]
+    pub text_anchor: Anchor,
+
+    /// a title for a social-share card, say with `--size og`: as big as fits, in --font, with the
+    /// panes behind it kept clear and left the background
+    #[arg(long, value_name = "STRING")]
+    pub title: Option<String>,
+
+    /// a line under the --title, half its size
+    #[arg(long, value_name = "STRING", requires = "title")]
+    pub subtitle: Option<String>,
+
+    #[arg(long, value_parser=parse::color, default_value = "#000000")]
+    pub title_color: Rgb<u8>,
+
+    /// where the --title and --subtitle go, an em in from the edges
+    #[arg(long, value_enum, default_value_t = Anchor::Left, value_name = "WHERE")]
+    pub title_anchor: Anchor,
+
+    /// keep a square pane for a QR code of this, say an event's link, in the palette's darkest
+    /// color on its lightest
+    #[arg(long, value_name = "URL", conflicts_with = "symmetry")]
+    pub qr: Option<String>,
+
+    /// keep a pane for this month's calendar, or the year's as a strip of days with the ones gone
+    /// by filled in, in the line color on the palette's lightest; the daemon makes a new one at
+    /// midnight
+    #[arg(long, value_enum, value_name = "SPAN", conflicts_with_all = ["symmetry", "qr"])]
+    pub calendar: Option<Span>,
+
+    /// the day the --calendar is for, like 2024-05-17 [default: today]
+    #[arg(long, value_name = "DATE", requires = "calendar")]
+    pub date: Option<Date>,
+
+    /// a few small panes in a corner, a bar each for how busy the CPU is and how full memory and
+    /// the disk are, for daemon wallpapers that double as a dashboard (needs the `stats`
+    /// feature, on Linux)
+    #[arg(long)]
+    pub stats_overlay: bool,
+
+    /// where the --stats-overlay goes
+    #[arg(long, value_enum, default_value_t = Anchor::TopRight, value_name = "WHERE", requires = "stats_overlay")]
+    pub stats_anchor: Anchor,
+
+    /// label the --stats-overlay's bars with what they are and how full, in --font
+    #[arg(long, requires = "stats_overlay")]
+    pub stats_legend: bool,
+
+    /// make an image that repeats seamlessly as a background: the canvas edges are split lines,
+    /// half on each side; there's no matte, and the frame is the lines' width and black. only for
+    /// the mondrian, lines-only and sketchy styles
+    #[arg(long, conflicts_with_all = ["matte", "frame_width"])]
+    pub tileable: bool,
+
+    /// changes to make to the panes once they're laid out, by pane index: `split:N`, `merge:N`
+    /// or `color:N:#rrggbb`, comma separated; explore and --gui print them for what was edited
+    #[arg(long, value_name = "EDITS", value_delimiter = ',')]
+    pub edits: Vec<Edit>,
+
+    /// carry on from an edit history that explore or --gui saved with `h`: the edits in it are
+    /// made, and the undone ones can be redone
+    #[arg(long, value_name = "PATH", conflicts_with = "edits")]
+    pub history: Option<PathBuf>,
+
+    /// what kind of picture to make
+    #[arg(long, value_enum, default_value_t)]
+    pub style: Style,
+
+    /// about how many cells or tiles `voronoi`, `truchet`, `hex` and `penrose` make
+    /// [default: 2^levels]
+    #[arg(long, value_name = "N")]
+    pub cells: Option<usize>,
+
+    /// chance that a `lines-only` line running into the edge stops short of it, like in an
+    /// unfinished study; there's no frame then unless --frame-width asks for one
+    #[arg(long, default_value_t = 0.0, value_name = "P")]
+    pub unfinished: f32,
+
+    /// soft shadows this many pixels across under the colored panes, falling down and to the
+    /// right
+    #[arg(long, value_name = "PIXELS", num_args = 0..=1, default_missing_value = "24")]
+    pub shadow: Option<u32>,
+
+    /// for TVs and signage that cut off the edges: nothing small this far in from each side, in
+    /// percent of the width and height. the panes out there are big, and the text, QR code,
+    /// calendar and stats stay inside
+    #[arg(long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "5")]
+    pub safe_area: Option<f32>,
+
+    /// approximate this photo: panes keep being halved, up to --levels deep, wherever the part of
+    /// it underneath is busy, and each takes the palette color (or --background) closest to
+    /// what it covers
+    #[arg(long, value_name = "PATH")]
+    pub from_image: Option<PathBuf>,
+
+    /// compose to this song, a WAV or FLAC file, time running left to right: the loud parts are
+    /// cut up to --levels deep and filled more, the lines standing up fall on beats, and brighter
+    /// passages cut higher and take lighter colors (needs the `audio` feature)
+    #[arg(long, value_name = "TRACK", conflicts_with_all = ["from_image", "target_panes", "symmetry"])]
+    pub from_audio: Option<PathBuf>,
+
+    /// data art from a CSV file with a header line, like commits or the temperature a day: a
+    /// strip standing up for each row, cut up to --levels deep and colored further along the
+    /// palette the higher its --column is
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["from_image", "from_audio", "target_panes", "symmetry"])]
+    pub from_csv: Option<PathBuf>,
+
+    /// the --from-csv column to read, by its name in the header [default: the last]
+    #[arg(long, value_name = "NAME", requires = "from_csv")]
+    pub column: Option<String>,
+
+    /// how much a --from-image pane's colors may vary, as a standard deviation in 0-255 steps,
+    /// before it gets halved again
+    #[arg(long, default_value_t = mosaic::DEFAULT_TOLERANCE, requires = "from_image")]
+    pub tolerance: f32,
+
+    /// `grayscale` to turn every color (--background too) into a gray as light, or `duotone`
+    /// and two colors to put them on a ramp between those instead; `color` leaves them be.
+    /// the layout and which pane gets which color stay the same
+    #[arg(long, num_args = 1..=3, value_name = "MODE")]
+    pub mode: Vec<String>,
+
+    /// the palette's dark version instead: near-whites and grays (--background too) go dark,
+    /// the other colors a little less bright, and the lines (and a black frame) light gray
+    #[arg(long)]
+    pub dark: bool,
+
+    /// write the light and the dark version of the same composition, the dark one beside
+    /// --output with `-dark` on the end of its name, e.g. for a desktop's light and dark themes
+    #[arg(long, conflicts_with_all = ["dark", "animate", "monitors", "contact_sheet", "set_wallpaper", "gui"])]
+    pub both: bool,
+
+    /// chance that a pane gets a palette color; the rest are left as --background
+    #[arg(long, default_value_t = 1.0, value_name = "P")]
+    pub fill_probability: f32,
+
+    /// how strongly big panes are left unfilled; 0 treats all panes the same
+    #[arg(long, default_value_t = 0.0, value_name = "STRENGTH")]
+    pub fill_area_bias: f32,
+
+    /// chance that a pane in a palette color is striped or checkered in another one, like
+    /// Mondrian's pier-and-ocean studies
+    #[arg(long, default_value_t = 0.0, value_name = "P")]
+    pub pattern: f32,
+
+    /// color of unfilled panes
+    #[arg(long, value_parser=parse::color, default_value = "#ffffff")]
+    pub background: Rgb<u8>,
+
+    /// how filled panes pick their color
+    #[arg(long, value_enum, default_value_t)]
+    pub color_strategy: ColorStrategy,
+
+    /// where to write the image, `-` for stdout [default: mondrian.png, or stdout for
+    /// --format ppm/raw-rgb]
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// how to encode the image [default: from the --output extension, or png on stdout]
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// put a `<style>` block in SVGs that colors everything by its class, for pages to override
+    #[arg(long)]
+    pub svg_style: bool,
+
+    /// how wide --format tikz pictures are, in centimeters
+    #[arg(long, value_name = "CM", default_value_t = tikz::DEFAULT_WIDTH)]
+    pub tikz_width: f32,
+
+    /// the print shop's ICC output profile, for `--format tiff-cmyk` to convert through and embed;
+    /// without one, black is pulled out of the other inks by a simple rule and no profile is
+    /// embedded
+    #[arg(long, value_name = "PATH", requires = "format", conflicts_with_all = ["animate", "monitors"])]
+    pub icc_profile: Option<PathBuf>,
+
+    /// pixels per inch to record in PNG, JPEG and TIFF files, so prints come out the right size;
+    /// doesn't change the pixels [default: the --size preset's, for print sizes]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub dpi: Option<u32>,
+
+    /// write a sequence of frames instead of one image; to stdout they're back to back, to a video
+    /// (.mp4, .webm, .gif, needs the `ffmpeg` feature) they're encoded, to anything else they're
+    /// numbered, e.g. `mondrian-0001.png`
+    #[arg(long, value_enum, value_name = "KIND", num_args = 0..=1, default_missing_value = "reroll", conflicts_with_all = ["monitors", "set_wallpaper"])]
+    pub animate: Option<Animation>,
+
+    /// how many frames --animate writes
+    #[arg(long, default_value_t = 30, requires = "animate")]
+    pub frames: usize,
+
+    /// frame rate of --animate videos
+    #[arg(long, default_value_t = 30, requires = "animate")]
+    pub fps: u32,
+
+    /// how many frames each composition stays up for in `--animate crossfade` or `morph`, the
+    /// second half of them turning into the next one; for `zoom`, how many it takes to get twice
+    /// as close
+    #[arg(
+        long,
+        default_value_t = 60,
+        value_name = "FRAMES",
+        requires = "animate"
+    )]
+    pub hold: usize,
+
+    /// over the compositions of --animate reroll, crossfade or morph, or a --contact-sheet, turn
+    /// the palette's hues (and --background's) this many degrees by the last one, and with
+    /// `:LIGHTNESS` make them that much lighter, or darker if it's negative, where black to white
+    /// is 1; e.g. `120:-0.2` for a slideshow that goes from day into dusk
+    #[arg(long, value_name = "DEGREES[:LIGHTNESS]", value_parser = parse::drift, requires = "sequence")]
+    pub palette_drift: Option<(f32, f32)>,
+
+    /// width of the lines between panes, 0 for none [default: a thousandth of the longest side,
+    /// or 4 with --avatar]
+    #[arg(long, value_name = "PIXELS")]
+    pub border_width: Option<u32>,
+
+    /// make the first split's lines this many times as wide as the deepest, which are
+    /// --border-width, and the ones between in proportion
+    #[arg(long, default_value_t = 1.0, value_name = "RATIO")]
+    pub border_taper: f32,
+
+    /// line width around the whole canvas, 0 for none [default: same as interior lines]
+    #[arg(long)]
+    pub frame_width: Option<u32>,
+
+    /// color of the line around the whole canvas
+    #[arg(long, value_parser=parse::color, default_value = "#000000")]
+    pub frame_color: Rgb<u8>,
+
+    /// margin around the composition, for print-style framing; the image size doesn't change
+    #[arg(long, default_value_t = 0)]
+    pub matte: u32,
+
+    /// color of the matte
+    #[arg(long, value_parser=parse::color, default_value = "#ffffff")]
+    pub matte_color: Rgb<u8>,
+
+    /// `on` for smooth edges at sub-pixel positions, `off` to cut on whole pixels and draw hard
+    /// edges (what older versions made)
+    #[arg(
+        long,
+        default_value = "on",
+        action = ArgAction::Set,
+        value_parser = PossibleValuesParser::new(["on", "off"]).map(|value| value == "on")
+    )]
+    pub antialias: bool,
+
+    /// make the image the desktop background once it's written (needs the `wallpaper` feature)
+    #[arg(long)]
+    pub set_wallpaper: bool,
+
+    /// one image per attached monitor, named after it and written next to --output (needs the
+    /// `display` feature)
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with_all = ["size", "width", "height", "set_wallpaper"])]
+    pub monitors: Option<Monitors>,
+
+    /// small square identicon preset; explicit --width/--height/--levels still win
+    #[arg(long)]
+    pub avatar: bool,
+
+    /// draw this many times bigger, then scale down with a Lanczos filter for smoother edges;
+    /// uses factor² times the memory, and PNGs are no longer written a strip at a time
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    pub supersample: u32,
+
+    /// what draws the image; `gpu` needs the `gpu` feature and a working adapter (--monitors and
+    /// `--animate split` always draw on the cpu)
+    #[arg(long, value_enum, default_value_t)]
+    pub backend: Backend,
+
+    /// recolor the output as someone with this kind of color blindness would see it, to check
+    /// the palette still works; the embedded parameters are still the real ones
+    #[arg(long, value_enum, value_name = "KIND", conflicts_with = "monitors")]
+    pub simulate_cvd: Option<Deficiency>,
+
+    /// what to do to the image once it's drawn, in order, e.g. `vignette,grain:0.2,blur:1.5,
+    /// chromatic:1`; each takes an amount after a colon or goes without. like --simulate-cvd,
+    /// they're not in the embedded parameters
+    #[arg(long, value_name = "FILTERS", conflicts_with = "monitors")]
+    pub filter: Option<Pipeline>,
+
+    /// bring the image down to an e-paper panel's colors, after --filter: black and white, four
+    /// grays, or the seven of an ACeP panel; `--format packed` writes the buffer the panel takes
+    #[arg(long, value_enum, value_name = "PANEL", conflicts_with = "monitors")]
+    pub eink: Option<Panel>,
+
+    /// how colors are made up wherever the output has fewer than the image (--eink, --format
+    /// png8): a Bayer pattern, Floyd–Steinberg error diffusion, or a blue-noise pattern, grainy
+    /// but even
+    #[arg(long, value_enum, value_name = "ALGORITHM", default_value_t)]
+    pub dither: Dither,
+
+    /// bits per channel: 16 for PNG or TIFF that holds up to editing without banding, 32 for
+    /// floating point EXR or HDR in linear light; deeper images are always drawn on the cpu
+    #[arg(
+        long,
+        value_name = "BITS",
+        default_value = "8",
+        value_parser = PossibleValuesParser::new(["8", "16", "32"]).map(|bits| bits.parse::<u32>().unwrap()),
+        conflicts_with_all = ["animate", "monitors", "simulate_cvd", "filter", "eink"]
+    )]
+    pub depth: u32,
+
+    /// ROWSxCOLS compositions from the same flags in one image, each shrunk to fit across
+    /// --width and labeled with its seed, to pick one to make at full size
+    #[arg(long, value_name = "ROWSxCOLS", value_parser = parse::grid, conflicts_with_all = ["animate", "monitors", "set_wallpaper", "gui"])]
+    pub contact_sheet: Option<(u32, u32)>,
+
+    /// one square composition at 16, 32, 48, 64, 128, 256 and 512 pixels, as a favicon: into a
+    /// multi-size icon if --output ends in `.ico`, or as `icon-16.png` and so on into the
+    /// --output directory [default: icons]. the lines are a pixel wide or more at every size
+    #[arg(long, conflicts_with_all = ["size", "width", "height", "animate", "monitors", "contact_sheet", "gui", "set_wallpaper", "both"])]
+    pub icons: bool,
+
+    /// COLSxROWS compositions from the same flags, each --cell big, packed edge to edge into one
+    /// atlas for a game engine, with a manifest of where each is and its seed written next to it
+    /// as --output with a `.json` extension
+    #[arg(long, value_name = "COLSxROWS", value_parser = parse::columns_rows, conflicts_with_all = ["size", "width", "height", "monitors", "set_wallpaper", "gui", "icons", "both"])]
+    pub sprite_sheet: Option<(u32, u32)>,
+
+    /// how big each --sprite-sheet cell is, in pixels
+    #[arg(long, value_name = "WxH", value_parser = parse::dimensions, default_value = "128x128", requires = "sprite_sheet")]
+    pub cell: (u32, u32),
+
+    /// make N compositions from the same flags and keep the one that scores best on color
+    /// balance, how much the pane sizes vary and how evenly the lines are spread
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["animate", "monitors", "contact_sheet", "gui"])]
+    pub best_of: Option<u32>,
+
+    /// print each --best-of candidate's seed and scores to stderr
+    #[arg(long, requires = "best_of")]
+    pub scores: bool,
+
+    /// open a window with the composition and sliders for levels, max aspect and border width
+    /// instead of writing it; `s` writes it to --output, and closing prints the flags for it
+    /// (needs the `gui` feature)
+    #[arg(long, conflicts_with_all = ["animate", "monitors", "set_wallpaper"])]
+    pub gui: bool,
+
+    /// print how long each step took to stderr: splitting, coloring, filling the panes, drawing
+    /// the lines, and encoding. PNGs aren't written a strip at a time, so the steps come apart
+    #[arg(long, conflicts_with_all = ["animate", "monitors", "contact_sheet", "gui"])]
+    pub timing: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// the raster renderer; PNGs are streamed out a strip at a time
+    #[default]
+    Cpu,
+    /// instanced quads through wgpu, read back for encoding
+    Gpu,
+}
+
+/// a [`Backend`] ready to draw, how much to supersample by, and what to do to the finished image;
+/// the GPU is only set up once, however many images it draws
+pub struct Renderer {
+    gpu: Option<gpu::Renderer>,
+    supersample: u32,
+    filter: Option<Pipeline>,
+    eink: Option<(Panel, Dither)>,
+    /// for --format png8, if it's dithered down to the composition's own colors
+    indexed: Option<Dither>,
+    simulate_cvd: Option<Deficiency>,
+}
+
+impl Renderer {
+    pub fn new(args: &GenerateArgs) -> Result<Self, GpuError> {
+        let gpu = match args.backend {
+            Backend::Cpu => None,
+            Backend::Gpu => Some(gpu::Renderer::new()?),
+        };
+        Ok(Self {
+            gpu,
+            supersample: args.supersample,
+            filter: args.filter.clone(),
+            eink: args.eink.map(|panel| (panel, args.dither)),
+            indexed: (args.format == Some(Format::Png8) && args.dither != Dither::None)
+                .then_some(args.dither),
+            simulate_cvd: args.simulate_cvd,
+        })
+    }
+
+    pub fn render(&self, composition: &Composition) -> Result<RgbImage, GpuError> {
+        self.render_with(composition, |_, _| {})
+    }
+
+    /// [`render`](Self::render), calling `progress` as the cpu paints; see
+    /// [`raster::render_with`]
+    pub fn render_with(
+        &self,
+        composition: &Composition,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<RgbImage, GpuError> {
+        let mut image = match &self.gpu {
+            None => raster::render_with(composition, self.supersample, progress),
+            Some(renderer) => renderer.render_supersampled(composition, self.supersample)?,
+        };
+        self.finish(&mut image, &composition.parameters());
+        Ok(image)
+    }
+
+    /// whether [`finish`](Self::finish) does anything
+    pub fn finishes(&self) -> bool {
+        self.filter.is_some()
+            || self.eink.is_some()
+            || self.indexed.is_some()
+            || self.simulate_cvd.is_some()
+    }
+
+    /// what happens to every image after it's drawn, for the ones drawn some other way; the
+    /// parameters are what it was drawn from
+    pub fn finish(&self, image: &mut RgbImage, parameters: &Parameters) {
+        if let Some(filter) = &self.filter {
+            filter.apply(image);
+        }
+        if let Some((panel, dither)) = self.eink {
+            panel.quantize(image, dither);
+        }
+        if let Some(dither) = self.indexed {
+            dither.apply(image, &parameters.colors());
+        }
+        // last, since it's how the finished image would look
+        if let Some(deficiency) = self.simulate_cvd {
+            deficiency.apply(image);
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// with the parameters embedded, like every PNG we write
+    Png,
+    /// an indexed PNG in just the composition's own colors, a few KB even at 4K; anything in
+    /// between, like antialiased edges, becomes the nearest unless --dither says otherwise
+    Png8,
+    /// with the parameters in EXIF
+    Jpeg,
+    /// binary PPM, parameters in header comments; `ffmpeg -f image2pipe -c:v ppm -i -`
+    Ppm,
+    /// bare RGB bytes, no header at all; `ffmpeg -f rawvideo -pix_fmt rgb24 -s WxH -i -`
+    RawRgb,
+    /// CMYK TIFF for print, through --icc-profile if given, parameters in `ImageDescription`
+    TiffCmyk,
+    /// a standalone page of positioned `div`s, palette colors as CSS custom properties
+    Html,
+    /// a `tikzpicture` for LaTeX documents, --tikz-width wide
+    Tikz,
+    /// vectors, each pane with its depth, path and a class for its color
+    Svg,
+    /// a page the composition's size, a point to a pixel, in vectors
+    Pdf,
+    /// a preview in truecolor half blocks, 80 columns across, to look at in the terminal; to
+    /// stdout unless --output says otherwise
+    Terminal,
+    /// the framebuffer an --eink panel takes: color indices packed a few pixels to a byte, row
+    /// by row, no header
+    Packed,
+}
+
+impl Format {
+    /// the formats written from the composition itself rather than its pixels, by their extension
+    pub fn markup(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => Some(Self::Html),
+            "tex" | "tikz" => Some(Self::Tikz),
+            "svg" => Some(Self::Svg),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn is_markup(self) -> bool {
+        matches!(
+            self,
+            Self::Html | Self::Tikz | Self::Svg | Self::Pdf | Self::Terminal
+        )
+    }
+
+    /// only for the formats made of pixels; the others are written by [`GenerateArgs::write`]
+    pub fn write(
+        self,
+        image: &RgbImage,
+        parameters: &Parameters,
+        mut writer: impl Write,
+    ) -> Result<(), EncodeError> {
+        match self {
+            Self::Png => encode::write_png(image, parameters, writer),
+            Self::Png8 => encode::write_png8(image, &parameters.colors(), parameters, writer),
+            Self::Jpeg => encode::write_jpeg(image, parameters, writer),
+            Self::Ppm => encode::write_ppm(image, parameters, writer),
+            Self::TiffCmyk => encode::write_tiff_cmyk(image, None, parameters, writer),
+            Self::Html | Self::Tikz | Self::Svg | Self::Pdf | Self::Terminal => {
+                unreachable!("markup is written from the composition")
+            }
+            Self::Packed => unreachable!("packed buffers are written knowing the panel"),
+            Self::RawRgb => {
+                writer.write_all(image.as_raw())?;
+                Ok(writer.flush()?)
+            }
+        }
+    }
+}
+
+/// which time of day --adaptive-time goes by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+    Now,
+    /// minutes after midnight
+    At(u32),
+}
+
+/// a [`SizePreset`], or whatever the primary display is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Auto,
+    Preset(SizePreset),
+}
+
+impl ValueEnum for Size {
+    fn value_variants<'a>() -> &'a [Self] {
+        const VARIANTS: &[Size] = &[
+            Size::Auto,
+            Size::Preset(SizePreset::FullHd),
+            Size::Preset(SizePreset::Qhd),
+            Size::Preset(SizePreset::Uhd),
+            Size::Preset(SizePreset::FiveK),
+            Size::Preset(SizePreset::Ultrawide),
+            Size::Preset(SizePreset::Phone),
+            Size::Preset(SizePreset::OpenGraph),
+            Size::Preset(SizePreset::A4),
+        ];
+        VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Auto => Some(PossibleValue::new("auto").help("the primary display's resolution")),
+            Self::Preset(preset) => preset.to_possible_value(),
+        }
+    }
+}
+
+/// --dark's lines, and its frame if it would have been black
+const DARK_LINE: Rgb<u8> = Rgb([200, 200, 200]);
+
+/// `builder` with the --dark versions of `palette`, `background` and `frame_color`
+fn darkened(
+    builder: CompositionBuilder,
+    palette: &Palette,
+    background: Rgb<u8>,
+    frame_color: Rgb<u8>,
+) -> CompositionBuilder {
+    let frame_color = match frame_color {
+        Rgb([0, 0, 0]) => DARK_LINE,
+        color => oklab::dark(color),
+    };
+    builder
+        .palette(palette.dark())
+        .background(oklab::dark(background))
+        .line_color(DARK_LINE)
+        .frame_color(frame_color)
+}
+
+/// width, height, levels, border width
+const AVATAR_PRESET: (u32, u32, usize, u32) = (256, 256, 3, 4);
+
+impl GenerateArgs {
+    /// everything but writing the file
+    pub fn builder(&self) -> Result<CompositionBuilder, Box<dyn Error>> {
+        self.builder_with(0.0, self.dark)
+    }
+
+    /// [`Self::builder`] for the `n`th of `count` compositions made from the same flags: its own
+    /// seed, if there's one to make it from, and --palette-drift as far along as it's got
+    pub fn nth_builder(
+        &self,
+        n: usize,
+        count: usize,
+    ) -> Result<CompositionBuilder, Box<dyn Error>> {
+        let along = n as f32 / count.saturating_sub(1).max(1) as f32;
+        let mut builder = self.builder_with(along, self.dark)?;
+        if let Some(seed) = self.nth_seed(n) {
+            builder = builder.seed(seed);
+        }
+        Ok(builder)
+    }
+
+    /// with the colors `along` of the way through --palette-drift, and their --dark versions if
+    /// `dark`
+    fn builder_with(&self, along: f32, dark: bool) -> Result<CompositionBuilder, Box<dyn Error>> {
+        let (width, height, levels) = if self.avatar {
+            let (width, height, levels, _) = AVATAR_PRESET;
+            (width, height, levels)
+        } else {
+            (4096, 2160, 5)
+        };
+
+        let mut builder = CompositionBuilder::new();
+        let (width, height) = match self.size {
+            Some(Size::Preset(preset)) => {
+                builder = builder.preset(preset);
+                preset.dimensions()
+            }
+            Some(Size::Auto) => {
+                let display = display::primary()?;
+                (display.width, display.height)
+            }
+            None => (width, height),
+        };
+        let palette = match (&self.palette_from, &self.palette_file) {
+            (Some(path), _) => {
+                let image = image::open(path)
+                    .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+                Palette::from_image(&image.to_rgb8(), self.palette_size)
+            }
+            (None, Some(file)) if Path::new(file).is_file() => {
+                palettes::read(file).map_err(|err| format!("couldn't read {file}: {err}"))?
+            }
+            (None, Some(name)) => palettes::find(name)?,
+            (None, None) => match self.random_palette {
+                _ if self.adaptive_time.is_some() => {
+                    let minute = match self.adaptive_time {
+                        Some(Clock::At(minute)) => minute,
+                        _ => schedule::local_time()?,
+                    };
+                    Schedule::user()?.palette_at(minute)
+                }
+                Some(harmony) => {
+                    let mut rng = match self.nth_seed("palette") {
+                        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+                        None => ChaCha8Rng::from_entropy(),
+                    };
+                    let palette = harmony.palette(&mut rng);
+                    // without the `#`s, which would start a comment in most shells
+                    let hexes: Vec<String> = palette
+                        .colors()
+                        .iter()
+                        .map(|color| to_hex(*color).split_off(1))
+                        .collect();
+                    eprintln!("{harmony} palette: --palette {}", hexes.join(" "));
+                    palette
+                }
+                None => Palette::new(self.palette.clone()).with_weights([10, 2, 1, 1]),
+            },
+        };
+        let background = match self.adaptive_time {
+            Some(_) => palette.colors().first().copied().unwrap_or(self.background),
+            None => self.background,
+        };
+        let (palette, background) = match parse::tone(&self.mode)? {
+            Some(tone) => (palette.toned(tone), tone.apply(background)),
+            None => (palette, background),
+        };
+        let (palette, background) = match self.palette_drift {
+            Some((hue, lightness)) => (
+                palette.drifted(hue * along, lightness * along),
+                oklab::drift(background, hue * along, lightness * along),
+            ),
+            None => (palette, background),
+        };
+        let mut builder = builder
+            .size(self.width.unwrap_or(width), self.height.unwrap_or(height))
+            .levels(self.levels.unwrap_or(levels))
+            .palette(palette.clone())
+            .split_strategy(self.split_strategy)
+            .noise(self.noise_frequency, self.noise_octaves)
+            .diagonal(self.diagonal)
+            .cross(self.cross)
+            .style(self.style)
+            .unfinished(self.unfinished)
+            .border_taper(self.border_taper)
+            .shadow(self.shadow.unwrap_or(0))
+            .safe_area(self.safe_area.unwrap_or(0.0))
+            .fill_probability(self.fill_probability)
+            .fill_area_bias(self.fill_area_bias)
+            .pattern(self.pattern)
+            .background(background)
+            .color_strategy(self.color_strategy)
+            .frame_color(self.frame_color)
+            .matte(self.matte, self.matte_color)
+            .antialias(self.antialias)
+            .edits(self.edits.clone());
+        if dark {
+            builder = darkened(builder, &palette, background, self.frame_color);
+        }
+        if let Some(path) = &self.history {
+            let history: History = fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| text.parse())
+                .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+            builder = builder.history(history);
+        }
+        if let Some(dpi) = self.dpi {
+            builder = builder.dpi(dpi);
+        }
+        if let Some(viewport) = self.viewport {
+            builder = builder.viewport(viewport);
+        }
+        if let Some(max_aspect) = self.max_aspect {
+            builder = builder.max_aspect(max_aspect);
+        }
+        if let Some(cells) = self.cells {
+            builder = builder.cells(cells);
+        }
+        if let Some(panes) = self.target_panes {
+            builder = builder.target_panes(panes);
+        }
+        if let Some(pick) = self.pick {
+            builder = builder.pick(pick);
+        }
+        if let Some(path) = &self.script {
+            builder = builder.script(path.clone());
+        }
+        if let Some(symmetry) = self.symmetry {
+            builder = builder.symmetry(symmetry);
+        }
+        if let Some(path) = &self.detail_mask {
+            builder = builder.detail_mask(path.clone());
+        }
+        if !self.keep_clear.is_empty() {
+            builder = builder.keep_clear(self.keep_clear.clone());
+        }
+        if let Some(text) = &self.text {
+            builder = builder.text(Text {
+                font: self.font.clone(),
+                size: self.text_size,
+                color: self.text_color,
+                anchor: self.text_anchor,
+                ..Text::new(mondrian::metadata::unescape(text))
+            });
+        }
+        if let Some(title) = &self.title {
+            builder = builder.card(Card {
+                subtitle: self.subtitle.as_deref().map(mondrian::metadata::unescape),
+                font: self.font.clone(),
+                color: self.title_color,
+                anchor: self.title_anchor,
+                ..Card::new(mondrian::metadata::unescape(title))
+            });
+        }
+        if let Some(data) = &self.qr {
+            builder = builder.qr(data.clone());
+        }
+        if self.stats_overlay {
+            builder = builder.dashboard(Dashboard {
+                anchor: self.stats_anchor,
+                legend: self.stats_legend,
+                font: self.font.clone(),
+                ..Dashboard::new(Usage::sample()?)
+            });
+        }
+        if let Some(span) = self.calendar {
+            builder = builder.calendar(Calendar {
+                date: self.date,
+                font: self.font.clone(),
+                ..Calendar::new(span)
+            });
+        }
+        if self.tileable {
+            builder = builder.tileable(true);
+        }
+        load_plugins(&self.plugin)?;
+        if let Some(name) = &self.split_plugin {
+            builder = builder.split_plugin(name);
+        }
+        if let Some(name) = &self.fill_plugin {
+            builder = builder.fill_plugin(name);
+        }
+        if let Some(path) = &self.from_image {
+            builder = builder.mosaic(Mosaic {
+                path: path.clone(),
+                tolerance: self.tolerance,
+            });
+        }
+        if let Some(path) = &self.from_audio {
+            builder = builder.audio(path);
+        }
+        if let Some(path) = &self.from_csv {
+            let values = timeline::read(path, self.column.as_deref())
+                .map_err(|err| format!("{}: {err}", path.display()))?;
+            builder = builder.timeline(values);
+        }
+        if let Some(frame_width) = self.frame_width {
+            builder = builder.frame_width(frame_width);
+        }
+        if let Some(border_width) = self.border_width {
+            builder = builder.border_width(border_width);
+        } else if self.avatar {
+            builder = builder.border_width(AVATAR_PRESET.3);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        } else if let Some(text) = &self.seed_from {
+            builder = builder.seed_from(text);
+        }
+        Ok(builder)
+    }
+
+    /// writes the composition and its --dark version, which is made with the same seed and from
+    /// the same colors, so only they're different
+    fn write_both(&self, light: &Composition, timings: &mut Timings) -> Result<(), Box<dyn Error>> {
+        let output = self.output();
+        if is_stdout(&output) {
+            return Err("--both writes two files; give --output a file name".into());
+        }
+        let dark = darkened(
+            self.builder()?.seed(light.seed()),
+            light.palette(),
+            light.fill_options().background,
+            light.frame_color(),
+        )
+        .build_timed(timings)?;
+        let dark_output = suffixed(&output, "-dark");
+        self.write_to(light, &output, timings)?;
+        self.write_to(&dark, &dark_output, timings)
+    }
+
+    /// the best scoring of `count` compositions, each with its own seed
+    fn best_of(&self, count: usize, timings: &mut Timings) -> Result<Composition, Box<dyn Error>> {
+        let mut best: Option<(Score, Composition)> = None;
+        for n in 0..count {
+            let composition = self.nth_builder(n, count)?.build_timed(timings)?;
+            let score = composition.score();
+            if self.scores {
+                eprintln!("seed {:<20}  {score}", composition.seed());
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(best, _)| score.total > best.total)
+            {
+                best = Some((score, composition));
+            }
+        }
+        let (score, composition) = best.expect("--best-of is at least 1");
+        if self.scores {
+            eprintln!("kept {:<20}  {score}", composition.seed());
+        }
+        Ok(composition)
+    }
+
+    /// the seed for one of several compositions made from the same flags, e.g. a frame or a
+    /// monitor; `None` (pick one at random) unless --seed or --seed-from fixes them all
+    pub fn nth_seed(&self, n: impl fmt::Display) -> Option<u64> {
+        let base = self
+            .seed
+            .or_else(|| self.seed_from.as_deref().map(seed::from_text))?;
+        Some(seed::from_text(&format!("{base}:{n}")))
+    }
+
+    pub fn output(&self) -> PathBuf {
+        match (&self.output, self.format) {
+            (Some(output), _) => output.clone(),
+            (None, Some(Format::Ppm | Format::RawRgb | Format::Terminal)) => PathBuf::from("-"),
+            (None, Some(Format::TiffCmyk)) => PathBuf::from("mondrian.tif"),
+            (None, Some(Format::Html)) => PathBuf::from("mondrian.html"),
+            (None, Some(Format::Tikz)) => PathBuf::from("mondrian.tex"),
+            (None, Some(Format::Svg)) => PathBuf::from("mondrian.svg"),
+            (None, Some(Format::Pdf)) => PathBuf::from("mondrian.pdf"),
+            (None, Some(Format::Packed)) => PathBuf::from("mondrian.bin"),
+            (None, _) => PathBuf::from("mondrian.png"),
+        }
+    }
+
+    /// writes the image where --output says, then sets it as wallpaper if asked to
+    pub fn write(&self, composition: &Composition) -> Result<(), Box<dyn Error>> {
+        self.write_to(composition, &self.output(), &mut Timings::new())
+    }
+
+    /// [`write`](Self::write) to `output` instead, adding how long it took to `timings`
+    pub fn write_to(
+        &self,
+        composition: &Composition,
+        output: &Path,
+        timings: &mut Timings,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.set_wallpaper && is_stdout(output) {
+            return Err("--set-wallpaper needs --output to be a file, not stdout".into());
+        }
+        let start = Instant::now();
+        if let Some(text) = self.markup(composition, output) {
+            timings.record("markup", start.elapsed());
+            if self.set_wallpaper {
+                return Err("--set-wallpaper needs an image, not markup".into());
+            }
+            if is_stdout(output) {
+                io::stdout().lock().write_all(text.as_bytes())?;
+            } else {
+                fs::write(output, text)?;
+            }
+            return Ok(());
+        }
+        self.check_colors()?;
+        if self.icc_profile.is_some() && self.format != Some(Format::TiffCmyk) {
+            return Err("--icc-profile only goes with --format tiff-cmyk".into());
+        }
+        if self.format == Some(Format::Packed) {
+            let Some(panel) = self.eink else {
+                return Err("--format packed needs --eink to say which panel it's for".into());
+            };
+            if self.set_wallpaper {
+                return Err("--set-wallpaper needs an image, not a panel's buffer".into());
+            }
+            let image = timings.time("render", || self.render(composition, output))?;
+            let buffer = timings.time("encode", || panel.pack(&image));
+            if is_stdout(output) {
+                io::stdout().lock().write_all(&buffer)?;
+            } else {
+                fs::write(output, buffer)?;
+            }
+            return Ok(());
+        }
+        if self.depth > 8 {
+            timings.time("render and encode", || self.write_deep(composition, output))?;
+        } else if let Some(path) = &self.icc_profile {
+            let profile = icc::Profile::read(path)
+                .map_err(|err| format!("couldn't use {}: {err}", path.display()))?;
+            let image = timings.time("render", || self.render(composition, output))?;
+            let writer: Box<dyn Write> = if is_stdout(output) {
+                Box::new(io::stdout().lock())
+            } else {
+                Box::new(File::create(output)?)
+            };
+            let parameters = composition.parameters();
+            timings.time("encode", || {
+                encode::write_tiff_cmyk(&image, Some(&profile), &parameters, BufWriter::new(writer))
+            })?;
+        } else if self.timing && self.backend == Backend::Cpu && self.supersample == 1 {
+            let parameters = composition.parameters();
+            let mut image = timings.time("fill", || raster::render_fill(composition));
+            timings.time("border", || raster::render_lines(composition, &mut image));
+            let renderer = Renderer::new(self)?;
+            if renderer.finishes() {
+                timings.time("filter", || renderer.finish(&mut image, &parameters));
+            }
+            timings.time("encode", || save(&image, &parameters, output, self.format))?;
+        } else if self.backend == Backend::Cpu
+            && self.supersample == 1
+            && self.simulate_cvd.is_none()
+            && self.filter.is_none()
+            && self.eink.is_none()
+            && self.dither == Dither::None
+        {
+            timings.time("render and encode", || {
+                save_composition(composition, output, self.format)
+            })?;
+        } else {
+            let image = timings.time("render", || self.render(composition, output))?;
+            timings.time("encode", || {
+                save(&image, &composition.parameters(), output, self.format)
+            })?;
+        }
+        trace::event(
+            Level::Info,
+            "wrote",
+            &[("output", &output.display()), ("seed", &composition.seed())],
+        );
+        if self.set_wallpaper {
+            wallpaper::set(output)?;
+        }
+        Ok(())
+    }
+
+    /// [`Renderer::render`] with a progress bar, unless `output` is stdout
+    fn render(&self, composition: &Composition, output: &Path) -> Result<RgbImage, GpuError> {
+        let mut bar = Bar::new("rendering", is_stdout(output));
+        Renderer::new(self)?.render_with(composition, |done, total| bar.set(done, total))
+    }
+
+    /// --dither with nothing to bring down to fewer colors, or --format png8 with something that
+    /// takes the image off the composition's own
+    pub fn check_colors(&self) -> Result<(), &'static str> {
+        let png8 = self.format == Some(Format::Png8);
+        if self.dither != Dither::None && self.eink.is_none() && !png8 {
+            return Err("--dither only goes with --eink or --format png8");
+        }
+        if png8 && (self.eink.is_some() || self.simulate_cvd.is_some()) {
+            return Err("--format png8 is in the composition's colors; --eink and --simulate-cvd change them");
+        }
+        Ok(())
+    }
+
+    /// the text to write instead of an image, for --format html, tikz, svg, pdf or terminal, or an
+    /// --output ending in one of their extensions
+    fn markup(&self, composition: &Composition, output: &Path) -> Option<String> {
+        let format = match self.format {
+            Some(format) => format,
+            None => Format::markup(output)?,
+        };
+        match format {
+            Format::Html => Some(composition.render_html()),
+            Format::Tikz => Some(composition.render_tikz(self.tikz_width)),
+            Format::Svg => Some(render::draw(
+                composition,
+                svg::Document::new(composition).styled(self.svg_style),
+            )),
+            Format::Pdf => Some(composition.render_pdf()),
+            Format::Terminal => Some(render::draw(
+                composition,
+                terminal::Preview::new(composition, 80),
+            )),
+            _ => None,
+        }
+    }
+
+    /// [`write`](Self::write) for --depth 16 and 32
+    fn write_deep(&self, composition: &Composition, output: &Path) -> Result<(), Box<dyn Error>> {
+        let parameters = composition.parameters();
+        if self.depth == 32 {
+            if is_stdout(output) || self.format.is_some() {
+                return Err("--depth 32 only writes .exr and .hdr files".into());
+            }
+            let image = raster::render_as::<f32>(composition, self.supersample);
+            return Ok(encode::save_float(&image, output)?);
+        }
+        let image = raster::render_as::<u16>(composition, self.supersample);
+        match (self.format, is_stdout(output)) {
+            (None, false) => encode::save16(&image, &parameters, output)?,
+            (None | Some(Format::Png), true) => {
+                encode::write_png16(&image, &parameters, BufWriter::new(io::stdout().lock()))?
+            }
+            (Some(Format::Png), false) => {
+                encode::write_png16(&image, &parameters, BufWriter::new(File::create(output)?))?
+            }
+            (Some(_), _) => return Err("--depth 16 only writes PNG and TIFF".into()),
+        }
+        Ok(())
+    }
+}
+
+/// `-` means stdout
+pub fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// like [`encode::save`], but understands `-` and can be told the format
+pub fn save(
+    image: &RgbImage,
+    parameters: &Parameters,
+    path: &Path,
+    format: Option<Format>,
+) -> Result<(), EncodeError> {
+    match (format, is_stdout(path)) {
+        (None, false) => encode::save(image, parameters, path),
+        (format, true) => format.unwrap_or(Format::Png).write(
+            image,
+            parameters,
+            BufWriter::new(io::stdout().lock()),
+        ),
+        (Some(format), false) => {
+            format.write(image, parameters, BufWriter::new(File::create(path)?))
+        }
+    }
+}
+
+/// registers what the --plugin libraries have in them; ones already loaded are skipped
+pub fn load_plugins(paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    for path in paths {
+        plugin::load(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// [`save`] for a whole composition; PNGs are streamed out a strip at a time instead of being
+/// rendered all at once
+pub fn save_composition(
+    composition: &Composition,
+    path: &Path,
+    format: Option<Format>,
+) -> Result<(), EncodeError> {
+    let mut bar = Bar::new("rendering", is_stdout(path));
+    let progress = |done, total| bar.set(done, total);
+    let mut writer: BufWriter<Box<dyn Write>> = match (format, is_stdout(path)) {
+        (None, false) => return composition.save_with(path, progress),
+        (None | Some(Format::Png), true) => BufWriter::new(Box::new(io::stdout().lock())),
+        (Some(Format::Png), false) => BufWriter::new(Box::new(File::create(path)?)),
+        (Some(_), _) => {
+            let image = raster::render_with(composition, 1, progress);
+            return save(&image, &composition.parameters(), path, format);
+        }
+    };
+    composition.write_png_with(&mut writer, progress)?;
+    Ok(writer.flush()?)
+}
+
+/// `path` with `suffix` after its name, before the extension: `mondrian-dark.png`
+pub fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_owned();
+    name.push(suffix);
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// everything generating would do up to rendering, then a report on stdout
+pub fn stats(args: GenerateArgs) -> Result<(), Box<dyn Error>> {
+    let composition = args.builder()?.build()?;
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "seed      {}", composition.seed())?;
+    write!(stdout, "{}", composition.stats())?;
+    Ok(())
+}
+
+pub fn run(args: GenerateArgs) -> Result<(), Box<dyn Error>> {
+    if args.gui {
+        return gui::run(&args);
+    }
+    if let Some(mode) = args.monitors {
+        return monitors::run(&args, mode);
+    }
+    if let Some(animation) = args.animate {
+        return animate::run(&args, animation);
+    }
+    if let Some(grid) = args.contact_sheet {
+        return sheet::run(&args, grid);
+    }
+    if args.icons {
+        return icons::run(&args);
+    }
+    if let Some(grid) = args.sprite_sheet {
+        return sprites::run(&args, grid, args.cell);
+    }
+    let mut timings = Timings::new();
+    let composition = match args.best_of {
+        Some(count) => args.best_of(count as usize, &mut timings)?,
+        None => args.builder()?.build_timed(&mut timings)?,
+    };
+    if args.both {
+        args.write_both(&composition, &mut timings)?;
+    } else {
+        args.write_to(&composition, &args.output(), &mut timings)?;
+    }
+    if args.timing {
+        eprint!("{timings}");
+    }
+    Ok(())
+}
@@ -0,0 +1,472 @@
+use crate::generate::GenerateArgs;
+
+#[cfg(feature = "gui")]
+pub use panel::run;
+
+#[cfg(not(feature = "gui"))]
+pub fn run(_args: &GenerateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--gui needs mondrian-rs built with `--features gui`".into())
+}
+
+#[cfg(feature = "gui")]
+mod panel {
+    use std::error::Error;
+    use std::fmt::Write as _;
+
+    use image::{Rgb, RgbImage};
+    use mondrian::{edit::History, prelude::*};
+
+    use super::GenerateArgs;
+    use crate::{
+        preview,
+        window::{Event, Key, Window},
+    };
+
+    /// the control panel under the preview: a row per slider, then the buttons
+    const ROW: u32 = 24;
+    const PAD: u32 = 8;
+    const PANEL: u32 = PAD * 2 + ROW * (SLIDERS as u32 + 1);
+    /// room for the labels left of the tracks and the values right of them, in `fixed`'s
+    /// 6-pixel characters
+    const LABEL: u32 = 6 * 12;
+    const VALUE: u32 = 6 * 8;
+    const BUTTON: u32 = 6 * 10;
+
+    const PANEL_COLOR: Rgb<u8> = Rgb([0xee, 0xee, 0xee]);
+    const TRACK_COLOR: Rgb<u8> = Rgb([0xbb, 0xbb, 0xbb]);
+    const BUTTON_COLOR: Rgb<u8> = Rgb([0xd4, 0xd4, 0xd4]);
+    const AROUND_COLOR: Rgb<u8> = Rgb([0x40, 0x40, 0x40]);
+    const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+    const HELP: &str = "space reroll  <- -> seed  s save  click a pane to edit it  q quit";
+
+    const SLIDERS: usize = 3;
+    const LEVELS: usize = 0;
+    const ASPECT: usize = 1;
+    const BORDER: usize = 2;
+
+    /// a value between `min` and `max` in steps of `step`, picked by where on its track the
+    /// mouse is
+    struct Slider {
+        label: &'static str,
+        min: f32,
+        max: f32,
+        step: f32,
+        value: f32,
+    }
+
+    impl Slider {
+        /// moves it to `fraction` of the way along, returning whether that changed anything
+        fn set(&mut self, fraction: f32) -> bool {
+            let steps = ((self.max - self.min) * fraction.clamp(0.0, 1.0) / self.step).round();
+            let value = (self.min + steps * self.step).min(self.max);
+            let changed = value != self.value;
+            self.value = value;
+            changed
+        }
+
+        fn fraction(&self) -> f32 {
+            (self.value - self.min) / (self.max - self.min)
+        }
+    }
+
+    /// what the panel is set to
+    struct Controls {
+        seed: u64,
+        sliders: [Slider; SLIDERS],
+        /// the slider being dragged
+        active: Option<usize>,
+        /// edits made, and undone, since the layout last changed
+        history: History,
+        /// the pane the edit keys change
+        selected: Option<usize>,
+        /// what the last save did, for the status line
+        message: String,
+    }
+
+    impl Controls {
+        /// `None` for no limit, at the far end of the track
+        fn max_aspect(&self) -> Option<f32> {
+            let slider = &self.sliders[ASPECT];
+            (slider.value < slider.max).then_some(slider.value)
+        }
+
+        fn levels(&self) -> usize {
+            self.sliders[LEVELS].value as usize
+        }
+
+        fn border_width(&self) -> u32 {
+            self.sliders[BORDER].value as u32
+        }
+
+        fn value(&self, index: usize) -> String {
+            match (index, self.max_aspect()) {
+                (ASPECT, Some(max_aspect)) => format!("{max_aspect:.1}"),
+                (ASPECT, None) => "any".to_owned(),
+                _ => format!("{}", self.sliders[index].value),
+            }
+        }
+
+        /// the full-size composition, as the flags make it but for the panel
+        fn build(
+            &self,
+            args: &GenerateArgs,
+            split_options: &SplitOptions,
+        ) -> Result<Composition, Box<dyn Error>> {
+            let builder = args
+                .builder()?
+                .seed(self.seed)
+                .levels(self.levels())
+                .border_width(self.border_width())
+                .split_options(SplitOptions {
+                    max_aspect: self.max_aspect(),
+                    ..split_options.clone()
+                })
+                .history(self.history.clone());
+            Ok(builder.build()?)
+        }
+
+        /// flags that make the same composition from the command line
+        fn flags(&self) -> String {
+            let mut flags = format!(
+                "--seed {} --levels {} --border-width {}",
+                self.seed,
+                self.levels(),
+                self.border_width()
+            );
+            if let Some(max_aspect) = self.max_aspect() {
+                write!(flags, " --max-aspect {max_aspect}").unwrap();
+            }
+            flags.extend(preview::flag(&self.history.done));
+            flags
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Hit {
+        Slider(usize),
+        Reroll,
+        /// somewhere on the preview, in its pixels
+        Preview(u32, u32),
+    }
+
+    /// where things are in a window of a given size
+    struct Layout {
+        width: u32,
+        /// of the preview, which the panel is under
+        height: u32,
+    }
+
+    impl Layout {
+        fn of((width, height): (u16, u16)) -> Self {
+            Self {
+                width: width as u32,
+                height: (height as u32).saturating_sub(PANEL),
+            }
+        }
+
+        /// the top of a row of the panel
+        fn row(&self, row: usize) -> u32 {
+            self.height + PAD + row as u32 * ROW
+        }
+
+        /// the left and right ends of the sliders' tracks
+        fn track(&self) -> (u32, u32) {
+            let left = PAD + LABEL;
+            (left, (self.width.saturating_sub(PAD + VALUE)).max(left + 1))
+        }
+
+        /// where the preview goes: centered above the panel
+        fn preview(&self, preview: &Preview) -> (u32, u32) {
+            let (width, height) = preview.image.dimensions();
+            (
+                self.width.saturating_sub(width) / 2,
+                self.height.saturating_sub(height) / 2,
+            )
+        }
+
+        /// the slider, button or bit of preview under a point
+        fn hit(&self, x: i16, y: i16, preview: Option<&Preview>) -> Option<Hit> {
+            let (x, y) = (x.max(0) as u32, y.max(0) as u32);
+            if let (Some(preview), true) = (preview, y < self.height) {
+                let (left, top) = self.preview(preview);
+                let (x, y) = (x.checked_sub(left)?, y.checked_sub(top)?);
+                let (width, height) = preview.image.dimensions();
+                return (x < width && y < height).then_some(Hit::Preview(x, y));
+            }
+            let row = (y.checked_sub(self.height + PAD)? / ROW) as usize;
+            let (left, right) = self.track();
+            match row {
+                row if row < SLIDERS && (left - PAD..right + PAD).contains(&x) => {
+                    Some(Hit::Slider(row))
+                }
+                SLIDERS if (PAD..PAD + BUTTON).contains(&x) => Some(Hit::Reroll),
+                _ => None,
+            }
+        }
+    }
+
+    /// the composition in a window with sliders for levels, max aspect and border width, drawn
+    /// again as they move
+    pub fn run(args: &GenerateArgs) -> Result<(), Box<dyn Error>> {
+        let start = args.builder()?.build()?;
+        let split_options = start.split_options().clone();
+        let border = start.border_width() as f32;
+        let mut controls = Controls {
+            seed: start.seed(),
+            sliders: [
+                Slider {
+                    label: "levels",
+                    min: 1.0,
+                    max: 16.0,
+                    step: 1.0,
+                    value: start.levels().clamp(1, 16) as f32,
+                },
+                Slider {
+                    label: "max aspect",
+                    min: 2.0,
+                    max: 10.0,
+                    step: 0.5,
+                    value: split_options.max_aspect.unwrap_or(10.0).clamp(2.0, 10.0),
+                },
+                Slider {
+                    label: "border",
+                    min: 0.0,
+                    max: (border * 4.0).max(32.0),
+                    step: 1.0,
+                    value: border,
+                },
+            ],
+            active: None,
+            history: start.history(),
+            selected: None,
+            message: String::new(),
+        };
+
+        // about the shape of the image, as big as fits on most screens
+        let aspect = start.width() as f32 / start.height() as f32;
+        let width = 960f32.min(720.0 * aspect);
+        let height = (width / aspect).round() as u32 + PANEL;
+        let mut window = Window::open("mondrian", width as u16, height as u16)?;
+
+        let mut composition = start;
+        let mut preview = None;
+        loop {
+            let mut relayout = false;
+            let mut redraw = false;
+            let mut wait = true;
+            // everything that's queued up, so dragging doesn't fall behind drawing
+            while let Some(event) = window.event(wait)? {
+                wait = false;
+                let layout = Layout::of(window.size());
+                let hit = match event {
+                    Event::Press { x, y } => layout.hit(x, y, preview.as_ref()),
+                    _ => None,
+                };
+                let panes = composition.panes().len();
+                match (event, hit) {
+                    (Event::Close | Event::Key(Key::Escape | Key::Char('q')), _) => {
+                        println!("{}", controls.flags());
+                        return Ok(());
+                    }
+                    (Event::Key(Key::Char(' ' | 'r')), _) | (_, Some(Hit::Reroll)) => {
+                        controls.seed = rand::random();
+                        relayout = true;
+                    }
+                    (Event::Key(Key::Right | Key::Char('n')), _) => {
+                        controls.seed = controls.seed.wrapping_add(1);
+                        relayout = true;
+                    }
+                    (Event::Key(Key::Left | Key::Char('b')), _) => {
+                        controls.seed = controls.seed.wrapping_sub(1);
+                        relayout = true;
+                    }
+                    (Event::Key(Key::Char('s')), _) => {
+                        let output = args.output();
+                        controls.message = match args.write(&composition) {
+                            Ok(()) => format!("saved {}", output.display()),
+                            Err(err) => format!("couldn't save: {err}"),
+                        };
+                        redraw = true;
+                    }
+                    (Event::Key(key @ (Key::Char('\t') | Key::BackTab)), _) if panes > 0 => {
+                        controls.selected = Some(match (controls.selected, key) {
+                            (None, Key::BackTab) => panes - 1,
+                            (None, _) => 0,
+                            (Some(pane), Key::BackTab) => (pane + panes - 1) % panes,
+                            (Some(pane), _) => (pane + 1) % panes,
+                        });
+                        redraw = true;
+                    }
+                    (Event::Key(Key::Char('.')), _) => {
+                        controls.selected = None;
+                        redraw = true;
+                    }
+                    (Event::Key(Key::Char(key @ ('u' | 'U' | 'h'))), _) => {
+                        let output = args.output();
+                        controls.message =
+                            preview::history(&mut composition, key, &output).unwrap_or_default();
+                        controls.history = composition.history();
+                        let panes = composition.panes().len();
+                        controls.selected = controls.selected.filter(|pane| *pane < panes);
+                        preview = None;
+                        redraw = true;
+                    }
+                    (Event::Key(Key::Char(key)), _) => {
+                        let edited = controls
+                            .selected
+                            .and_then(|pane| preview::edit(&mut composition, pane, key));
+                        match edited {
+                            Some(Ok(pane)) => {
+                                controls.selected = Some(pane);
+                                controls.history = composition.history();
+                                preview = None;
+                            }
+                            Some(Err(err)) => controls.message = err.to_string(),
+                            None if "xmo".contains(key) => {
+                                controls.message = "click a pane first".to_owned()
+                            }
+                            None => {}
+                        }
+                        redraw = true;
+                    }
+                    (Event::Press { .. }, Some(Hit::Preview(x, y))) => {
+                        if let Some(preview) = &preview {
+                            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+                            controls.selected = preview::pane_at(&preview.small, x, y);
+                        }
+                        redraw = true;
+                    }
+                    (Event::Press { x, .. } | Event::Drag { x, .. }, _) => {
+                        if let Event::Press { .. } = event {
+                            controls.active = match hit {
+                                Some(Hit::Slider(index)) => Some(index),
+                                _ => None,
+                            };
+                        }
+                        if let Some(index) = controls.active {
+                            let (left, right) = layout.track();
+                            let fraction = (x as f32 - left as f32) / (right - left) as f32;
+                            relayout |= controls.sliders[index].set(fraction);
+                        }
+                    }
+                    (Event::Release, _) => controls.active = None,
+                    (Event::Resize { .. }, _) => {
+                        preview = None;
+                        redraw = true;
+                    }
+                    (Event::Expose, _) => redraw = true,
+                    (Event::Key(_) | Event::Motion { .. }, _) => {}
+                }
+            }
+            if relayout {
+                // the edits were to panes that aren't there any more
+                controls.history = History::default();
+                controls.selected = None;
+                match controls.build(args, &split_options) {
+                    Ok(next) => {
+                        composition = next;
+                        controls.message.clear();
+                    }
+                    Err(err) => controls.message = err.to_string(),
+                }
+                preview = None;
+            }
+            if relayout || redraw || preview.is_none() {
+                let layout = Layout::of(window.size());
+                let preview = match &mut preview {
+                    Some(preview) => preview,
+                    None => preview.insert(Preview::new(&composition, &layout)?),
+                };
+                draw(&window, &layout, &controls, preview)?;
+            }
+        }
+    }
+
+    /// the composition as big as fits above the panel
+    struct Preview {
+        /// laid out again at the preview's size, with the same panes
+        small: Composition,
+        image: RgbImage,
+    }
+
+    impl Preview {
+        fn new(composition: &Composition, layout: &Layout) -> Result<Self, Box<dyn Error>> {
+            let aspect = composition.width() as f32 / composition.height() as f32;
+            let (columns, rows) = (layout.width.max(1), layout.height.max(1));
+            let width = columns.min((rows as f32 * aspect).round() as u32).max(1);
+            let height = ((width as f32 / aspect).round() as u32).clamp(1, rows);
+            let small = preview::resized(composition, width, height)?;
+            let image = small.render_rgb();
+            Ok(Self { small, image })
+        }
+    }
+
+    fn draw(
+        window: &Window,
+        layout: &Layout,
+        controls: &Controls,
+        preview: &Preview,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = window.size();
+        let mut frame = RgbImage::from_pixel(width as u32, height as u32, PANEL_COLOR);
+        fill(&mut frame, 0, 0, layout.width, layout.height, AROUND_COLOR);
+        let (x, y) = layout.preview(preview);
+        image::imageops::replace(&mut frame, &preview.image, x as i64, y as i64);
+        let selected = controls
+            .selected
+            .and_then(|pane| preview.small.panes().get(pane));
+        if let Some(pane) = selected {
+            let Rectangle {
+                x: left,
+                y: top,
+                width,
+                height,
+            } = pane.rectangle;
+            let outline = Rectangle::new(left + x as f32, top + y as f32, width, height);
+            preview::outline(&mut frame, &outline);
+        }
+
+        let (left, right) = layout.track();
+        for (index, slider) in controls.sliders.iter().enumerate() {
+            let middle = layout.row(index) + ROW / 2;
+            fill(&mut frame, left, middle - 2, right - left, 4, TRACK_COLOR);
+            let knob = left + (slider.fraction() * (right - left) as f32).round() as u32;
+            fill(&mut frame, knob.saturating_sub(3), middle - 8, 7, 16, BLACK);
+        }
+        let button = layout.row(SLIDERS) + 2;
+        fill(&mut frame, PAD, button, BUTTON, ROW - 4, BUTTON_COLOR);
+        window.put(&frame)?;
+
+        // baselines of `fixed`, which is 13 pixels tall with 2 below the line
+        let baseline = |row: usize| (layout.row(row) + ROW / 2 + 4) as i16;
+        for (index, slider) in controls.sliders.iter().enumerate() {
+            let y = baseline(index);
+            window.text(PAD as i16, y, slider.label, BLACK, PANEL_COLOR);
+            let value = controls.value(index);
+            window.text((right + PAD) as i16, y, &value, BLACK, PANEL_COLOR);
+        }
+        let y = baseline(SLIDERS);
+        window.text((PAD + 12) as i16, y, "reroll", BLACK, BUTTON_COLOR);
+        let status = match (controls.message.is_empty(), controls.selected) {
+            (false, _) => format!("seed {}  {}", controls.seed, controls.message),
+            (true, Some(pane)) => format!(
+                "seed {}  pane {pane}  {}",
+                controls.seed,
+                preview::EDIT_HELP
+            ),
+            (true, None) => format!("seed {}  {HELP}", controls.seed),
+        };
+        window.text((PAD * 2 + BUTTON) as i16, y, &status, BLACK, PANEL_COLOR);
+        window.flush()
+    }
+
+    /// a solid rectangle, clipped to the image
+    fn fill(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+        for y in y..(y + height).min(image.height()) {
+            for x in x..(x + width).min(image.width()) {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
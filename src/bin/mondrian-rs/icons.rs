@@ -0,0 +1,95 @@
+//! one composition at the sizes icons come in, for a favicon or an app's icon set
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
+
+use image::RgbImage;
+use mondrian::encode;
+
+use crate::generate::{is_stdout, GenerateArgs, Renderer};
+
+/// the usual sizes, from a browser tab's up
+const SIZES: &[u32] = &[16, 32, 48, 64, 128, 256, 512];
+
+/// where it goes without --output; a `.ico` is one file, anything else a directory
+const DIRECTORY: &str = "icons";
+
+/// how many levels there are unless --levels or --target-panes say: a 16 pixel icon has no room
+/// for more panes
+const LEVELS: usize = 3;
+
+/// icons smaller than this are drawn on whole pixels, laid out the same but without the soft
+/// edges that'd blur a line a pixel wide into two
+const SHARP: u32 = 128;
+
+/// the lines at `size` pixels: never under one, so they're still there at 16, and heavier for
+/// the size the smaller it is, so the panes still read as panes
+fn line(size: u32) -> u32 {
+    1 + size / 64
+}
+
+/// lays the composition out once, square, and writes it at each of [`SIZES`]: a multi-size icon
+/// if --output ends in `.ico`, or `icon-16.png` and so on into the --output directory. the lines
+/// are made as wide as [`line`] says, or --border-width and --frame-width shrunk along with the
+/// rest but kept a pixel wide, and the smallest are kept sharp
+pub fn run(args: &GenerateArgs) -> Result<(), Box<dyn Error>> {
+    let largest = *SIZES.last().expect("there are sizes");
+    let mut builder = args.builder()?.size(largest, largest);
+    if args.levels.is_none() && args.target_panes.is_none() {
+        builder = builder.levels(LEVELS);
+    }
+    if args.border_width.is_none() {
+        builder = builder.border_width(line(largest));
+    }
+    let parameters = builder.build()?.parameters();
+    let renderer = Renderer::new(args)?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DIRECTORY));
+    let ico = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ico"));
+    if is_stdout(&output) {
+        return Err("icons go in a directory or a .ico file, not stdout".into());
+    }
+    if !ico {
+        fs::create_dir_all(&output)?;
+    }
+
+    let mut images: Vec<RgbImage> = vec![];
+    for &size in SIZES {
+        let mut resized = parameters.resized(size, size);
+        resized.border_width = match args.border_width {
+            Some(0) => 0,
+            Some(_) => resized.border_width.max(1),
+            None => line(size),
+        };
+        resized.frame_width = match args.frame_width {
+            Some(0) => 0,
+            Some(_) => resized.frame_width.max(1),
+            None => resized.border_width,
+        };
+        if size < SHARP {
+            resized.antialias = false;
+        }
+        let image = renderer.render(&resized.builder().build()?)?;
+        if ico {
+            images.push(image);
+        } else {
+            let path = output.join(format!("icon-{size}.png"));
+            encode::save(&image, &resized, &path)?;
+            eprintln!("wrote {}", path.display());
+        }
+    }
+    if ico {
+        encode::write_ico(&images, BufWriter::new(File::create(&output)?))?;
+        eprintln!("wrote {}", output.display());
+    }
+    Ok(())
+}
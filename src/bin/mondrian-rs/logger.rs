@@ -0,0 +1,257 @@
+//! writes what the library logs to stderr, with the spans it's in, as text or as a JSON object
+//! per line to collect
+
+use std::{
+    fmt::{self, Write as _},
+    io::{self, Write as _},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    Level, LevelFilter, Log, Metadata, Record,
+};
+use mondrian::trace::{self, Written};
+
+/// how each line is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// the level, the spans it's in, then the message and fields
+    #[default]
+    Text,
+    /// an object per line with `time` (seconds since the epoch), `level`, `message`, `fields`
+    /// and `spans`; fields that are numbers are written as numbers
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+struct Logger {
+    format: LogFormat,
+}
+
+/// starts writing everything up to `verbosity` (1 for info, 2 or more for debug, 0 for
+/// nothing), in `format`
+pub fn init(verbosity: u8, format: LogFormat) {
+    let level = match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    };
+    // only ever fails if there's a logger already, and then that one's writing
+    if log::set_boxed_logger(Box::new(Logger { format })).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(trace::TARGET)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut fields = Pairs(vec![]);
+        let _ = record.key_values().visit(&mut fields);
+        let message = record.args().to_string();
+        let line = trace::spans(|spans| match self.format {
+            LogFormat::Text => text_line(record.level(), &message, &fields.0, spans),
+            LogFormat::Json => json_line(record.level(), &message, &fields.0, spans),
+        });
+        // logging is best effort; a closed stderr isn't worth failing over
+        let _ = writeln!(io::stderr().lock(), "{line}");
+    }
+
+    fn flush(&self) {}
+}
+
+/// collects a record's key-values, written out
+struct Pairs(Written);
+
+impl<'kvs> VisitSource<'kvs> for Pairs {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// `INFO outer{a=1}:inner: message b=2`
+fn text_line(
+    level: Level,
+    message: &str,
+    fields: &[(String, String)],
+    spans: &[(&'static str, Written)],
+) -> String {
+    let mut line = format!("{level:>5} ");
+    for (name, span_fields) in spans {
+        line.push_str(name);
+        if !span_fields.is_empty() {
+            let pairs: Vec<String> = span_fields
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect();
+            let _ = write!(line, "{{{}}}", pairs.join(" "));
+        }
+        line.push(':');
+    }
+    if !spans.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(message);
+    for (key, value) in fields {
+        let _ = write!(line, " {key}={value}");
+    }
+    line
+}
+
+fn json_line(
+    level: Level,
+    message: &str,
+    fields: &[(String, String)],
+    spans: &[(&'static str, Written)],
+) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let object = |pairs: &[(String, String)], name: Option<&str>| {
+        let mut entries: Vec<String> = name
+            .map(|name| format!("\"name\":{}", string(name)))
+            .into_iter()
+            .collect();
+        entries.extend(
+            pairs
+                .iter()
+                .map(|(key, value)| format!("{}:{}", string(key), value_json(value))),
+        );
+        format!("{{{}}}", entries.join(","))
+    };
+    let spans: Vec<String> = spans
+        .iter()
+        .map(|(name, span_fields)| object(span_fields, Some(name)))
+        .collect();
+    format!(
+        "{{\"time\":{time:.3},\"level\":{},\"message\":{},\"fields\":{},\"spans\":[{}]}}",
+        string(&level.to_string()),
+        string(message),
+        object(fields, None),
+        spans.join(",")
+    )
+}
+/// numbers as they are, anything else as a string
+fn value_json(value: &str) -> String {
+    if is_number(value) {
+        value.to_owned()
+    } else {
+        string(value)
+    }
+}
+
+/// whether `text` is a number as JSON writes them: no leading zeros, `+` or bare `.`
+fn is_number(text: &str) -> bool {
+    /// how many digits `text` starts with, and what's after them
+    fn digits(text: &str) -> (usize, &str) {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        (end, &text[end..])
+    }
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let (whole, rest) = digits(text);
+    if whole == 0 || (whole > 1 && text.starts_with('0')) {
+        return false;
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => match digits(fraction) {
+            (0, _) => return false,
+            (_, rest) => rest,
+        },
+        None => rest,
+    };
+    match rest.strip_prefix(['e', 'E']) {
+        Some(exponent) => {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            matches!(digits(exponent), (1.., ""))
+        }
+        None => rest.is_empty(),
+    }
+}
+
+/// `text` as a JSON string, quotes and all
+fn string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Written {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn writes_lines() {
+        let spans = [("build", pairs(&[("seed", "7")])), ("split", vec![])];
+        let fields = pairs(&[("width", "480"), ("color", "#ffffff")]);
+        assert_eq!(
+            text_line(Level::Info, "pane", &fields, &spans),
+            " INFO build{seed=7}:split: pane width=480 color=#ffffff"
+        );
+        assert_eq!(text_line(Level::Debug, "close", &[], &[]), "DEBUG close");
+        let json = json_line(Level::Info, "say \"hi\"\n", &fields, &spans);
+        let (_, json) = json.split_once(',').unwrap();
+        assert_eq!(
+            json,
+            r##""level":"INFO","message":"say \"hi\"\n","fields":{"width":480,"color":"#ffffff"},"spans":[{"name":"build","seed":7},{"name":"split"}]}"##
+        );
+    }
+
+    #[test]
+    fn numbers_are_json_numbers() {
+        for number in ["0", "-1", "25.179688", "1e5", "2.5E-3"] {
+            assert!(is_number(number), "{number}");
+        }
+        for other in [
+            "", "-", "01", "+1", ".5", "1.", "1e", "1e+", "0x10", "NaN", "inf",
+        ] {
+            assert!(!is_number(other), "{other}");
+        }
+        assert_eq!(string("a\tb\u{1}"), r#""a\tb\u0001""#);
+    }
+}
@@ -0,0 +1,134 @@
+use std::process::ExitCode;
+
+use clap::{ArgAction, Parser, Subcommand};
+
+use crate::{
+    daemon::DaemonArgs, evolve::EvolveArgs, explore::ExploreArgs, generate::GenerateArgs,
+    logger::LogFormat, palette::PaletteArgs, reproduce::ReproduceArgs,
+    screensaver::ScreensaverArgs, serve::ServeArgs, treemap::TreemapArgs, validate::ValidateArgs,
+};
+
+mod animate;
+mod daemon;
+mod evolve;
+mod explore;
+mod generate;
+mod gui;
+mod icons;
+mod logger;
+mod metrics;
+mod monitors;
+mod palette;
+mod parse;
+#[cfg(any(feature = "gui", all(feature = "tui", unix)))]
+mod preview;
+mod progress;
+mod reproduce;
+mod screensaver;
+mod serve;
+mod sheet;
+mod sprites;
+#[cfg(all(feature = "tui", unix))]
+mod terminal;
+mod treemap;
+mod validate;
+#[cfg(feature = "gui")]
+mod window;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+
+    /// log to stderr what's being done: `-v` for each step (building, splitting, coloring,
+    /// rendering, encoding) and how long it took, `-vv` for every pane too
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// how to write those logs: text, or a JSON object per line to collect, e.g. from `daemon`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t,
+        global = true
+    )]
+    log_format: LogFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// re-render an image made earlier at a new size, using the parameters embedded in it
+    Reproduce(ReproduceArgs),
+
+    /// keep making new compositions on an interval, e.g. as a rotating wallpaper
+    Daemon(DaemonArgs),
+
+    /// print pane counts, sizes and color coverage instead of rendering
+    Stats(GenerateArgs),
+
+    /// check that compositions tile the canvas exactly, without rendering them
+    Validate(ValidateArgs),
+
+    /// lay panes out from data, each sized by its weight, instead of splitting at random
+    Treemap(TreemapArgs),
+
+    /// browse the built-in palettes and the ones installed in the config directory
+    Palette(PaletteArgs),
+
+    /// breed layouts over generations from favorites or the best scoring, keeping the population
+    /// in a file to carry on with later
+    Evolve(EvolveArgs),
+
+    /// a live preview in the terminal: reroll, change levels, aspect and palette a key at a time,
+    /// then save (needs the `tui` feature)
+    Explore(ExploreArgs),
+
+    /// cover the screen with a new composition every so often, morphed into from the last, until a
+    /// key, a click or the mouse moving (needs the `gui` feature)
+    Screensaver(ScreensaverArgs),
+
+    /// answer HTTP requests for images and their layouts as JSON, e.g. `/image.png?seed=7`
+    Serve(ServeArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    logger::init(cli.verbose, cli.log_format);
+
+    let result = match cli.command {
+        Some(Command::Reproduce(args)) => reproduce::run(args),
+        Some(Command::Daemon(args)) => daemon::run(args),
+        Some(Command::Stats(args)) => generate::stats(args),
+        Some(Command::Validate(args)) => validate::run(args),
+        Some(Command::Treemap(args)) => treemap::run(args),
+        Some(Command::Palette(args)) => palette::run(args),
+        Some(Command::Evolve(args)) => evolve::run(args),
+        Some(Command::Explore(args)) => explore::run(args),
+        Some(Command::Screensaver(args)) => screensaver::run(args),
+        Some(Command::Serve(args)) => serve::run(args),
+        None => generate::run(cli.generate),
+    };
+    // Display rather than the Debug that returning the error from main would give us
+    if let Err(err) = result {
+        eprintln!("error: {err}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::Cli;
+
+    #[test]
+    fn arguments_are_consistent() {
+        Cli::command().debug_assert();
+    }
+}
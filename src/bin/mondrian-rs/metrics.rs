@@ -0,0 +1,262 @@
+//! counts of what's been made, for Prometheus to scrape from `serve`'s `/metrics` or the
+//! daemon's --metrics-addr
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, BufWriter},
+    net::{TcpListener, TcpStream},
+    sync::{atomic::AtomicUsize, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use mondrian::trace::{self, Level};
+
+use crate::serve::{self, Deadline, Response, Slot};
+
+/// how many scrapes are answered at once
+const MAX_SCRAPES: usize = 16;
+/// seconds, from a quick small one to a big print
+const SECONDS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+/// panes in an image, four times as many each
+const PANES: &[f64] = &[
+    1.0, 4.0, 16.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0,
+];
+
+/// how many fell at or under each of `bounds`, and what they added up to
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write(&self, text: &mut String, name: &str, help: &str) {
+        let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} histogram");
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(text, "{name}_bucket{{le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(text, "{name}_bucket{{le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(text, "{name}_sum {}\n{name}_count {}", self.sum, self.count);
+    }
+}
+
+#[derive(Debug)]
+struct Counts {
+    images: u64,
+    /// images that couldn't be made or written
+    failures: u64,
+    seconds: Histogram,
+    panes: Histogram,
+    /// answers by status, for `serve`
+    responses: BTreeMap<u16, u64>,
+}
+
+/// what's been made so far, shared between threads
+#[derive(Debug)]
+pub struct Metrics(Mutex<Counts>);
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self(Mutex::new(Counts {
+            images: 0,
+            failures: 0,
+            seconds: Histogram::new(SECONDS),
+            panes: Histogram::new(PANES),
+            responses: BTreeMap::new(),
+        }))
+    }
+}
+
+impl Metrics {
+    fn counts(&self) -> std::sync::MutexGuard<'_, Counts> {
+        // counts are still counts after a thread panicked holding them
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// an image made with `panes` panes, from parameters to encoded in `took`
+    pub fn made(&self, panes: usize, took: Duration) {
+        let mut counts = self.counts();
+        counts.images += 1;
+        counts.seconds.observe(took.as_secs_f64());
+        counts.panes.observe(panes as f64);
+    }
+
+    /// one that couldn't be made or written
+    pub fn failed(&self) {
+        self.counts().failures += 1;
+    }
+
+    /// a request answered with `status`
+    pub fn answered(&self, status: u16) {
+        *self.counts().responses.entry(status).or_default() += 1;
+    }
+
+    /// in Prometheus's text format
+    pub fn render(&self) -> String {
+        let counts = self.counts();
+        let mut text = String::new();
+        let mut counter = |name: &str, help: &str, value: u64| {
+            let _ = writeln!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+            );
+        };
+        counter("mondrian_images_total", "Images made.", counts.images);
+        counter(
+            "mondrian_render_failures_total",
+            "Images that couldn't be made or written.",
+            counts.failures,
+        );
+        counts.seconds.write(
+            &mut text,
+            "mondrian_render_seconds",
+            "Time from parameters to an encoded image.",
+        );
+        counts
+            .panes
+            .write(&mut text, "mondrian_panes", "Panes in each image made.");
+        if !counts.responses.is_empty() {
+            let name = "mondrian_http_responses_total";
+            let _ = writeln!(text, "# HELP {name} Requests answered, by status.");
+            let _ = writeln!(text, "# TYPE {name} counter");
+            for (status, count) in &counts.responses {
+                let _ = writeln!(text, "{name}{{code=\"{status}\"}} {count}");
+            }
+        }
+        text
+    }
+
+    /// what to answer a scrape with
+    pub fn response(&self) -> Response {
+        Response::new(200, "text/plain; version=0.0.4", self.render())
+    }
+}
+
+/// answers GET /metrics on `address` from a thread of its own, each connection on a thread of
+/// its own, for as long as the program runs; past [`MAX_SCRAPES`] at once they're turned away
+pub fn listen(address: &str, metrics: Arc<Metrics>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("metrics on http://{}/metrics", listener.local_addr()?);
+    thread::spawn(move || {
+        let open = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for stream in listener.incoming().flatten() {
+                let Some(slot) = Slot::take(&open, MAX_SCRAPES) else {
+                    let response = Response::error(503, "too busy; try again later");
+                    // it's small enough not to wait on, unless the other end isn't reading
+                    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                    let _ = serve::write_response(BufWriter::new(stream), (response, false));
+                    continue;
+                };
+                let metrics = &metrics;
+                scope.spawn(move || {
+                    // one that's slow to ask, or goes away, holds up only itself
+                    if let Err(err) = scrape(stream, metrics) {
+                        trace::event(Level::Debug, "dropped", &[("error", &err)]);
+                    }
+                    drop(slot);
+                });
+            }
+        });
+    });
+    Ok(())
+}
+
+fn scrape(stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let mut reader = Deadline::reader(&stream)?;
+    let (response, head) = match serve::read_request(&mut reader)? {
+        Ok(request) => match (request.path.as_str(), request.method.as_str()) {
+            ("/metrics", "GET" | "HEAD") => (metrics.response(), request.method == "HEAD"),
+            ("/metrics", _) => (Response::allow("GET, HEAD"), false),
+            _ => (Response::error(404, "there's just /metrics"), false),
+        },
+        Err(response) => (response, false),
+    };
+    serve::write_response(BufWriter::new(stream), (response, head))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    #[test]
+    fn counts_failures_apart_from_responses() {
+        let metrics = Metrics::default();
+        metrics.made(3, Duration::from_millis(20));
+        metrics.made(100, Duration::from_secs(1));
+        metrics.failed();
+        for status in [200, 200, 404, 429] {
+            metrics.answered(status);
+        }
+        let text = metrics.render();
+        for line in [
+            "mondrian_images_total 2",
+            "mondrian_render_failures_total 1",
+            "mondrian_render_seconds_bucket{le=\"0.025\"} 1",
+            "mondrian_render_seconds_bucket{le=\"+Inf\"} 2",
+            "mondrian_panes_bucket{le=\"4\"} 1",
+            "mondrian_panes_bucket{le=\"256\"} 2",
+            "mondrian_panes_sum 103",
+            "mondrian_http_responses_total{code=\"200\"} 2",
+            "mondrian_http_responses_total{code=\"404\"} 1",
+            "mondrian_http_responses_total{code=\"429\"} 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line} in\n{text}");
+        }
+        assert!(!text.contains("errors"));
+        // nothing about requests from the daemon, which doesn't answer any
+        assert!(!Metrics::default().render().contains("http"));
+    }
+
+    #[test]
+    fn a_slow_scrape_holds_up_only_itself() {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .to_string();
+        let metrics = Arc::new(Metrics::default());
+        metrics.failed();
+        listen(&address, metrics).unwrap();
+
+        // one that's connected and says nothing
+        let _slow = TcpStream::connect(&address).unwrap();
+        let mut scrape = TcpStream::connect(&address).unwrap();
+        scrape.set_read_timeout(Some(serve::PATIENCE / 2)).unwrap();
+        scrape
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        scrape.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("\nmondrian_render_failures_total 1\n"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::{error::Error, path::PathBuf};
+
+use clap::ValueEnum;
+use mondrian::{
+    display::{self, Display},
+    viewport::Viewport,
+};
+
+use crate::generate::GenerateArgs;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monitors {
+    /// a separate composition for each monitor, at its resolution
+    Each,
+    /// one composition across the whole desktop, cut up along the monitors
+    Span,
+}
+
+pub fn run(args: &GenerateArgs, mode: Monitors) -> Result<(), Box<dyn Error>> {
+    let displays = display::displays()?;
+    match mode {
+        Monitors::Each => each(args, &displays),
+        Monitors::Span => span(args, &displays),
+    }
+}
+
+fn each(args: &GenerateArgs, displays: &[Display]) -> Result<(), Box<dyn Error>> {
+    for display in displays {
+        let mut builder = args.builder()?.size(display.width, display.height);
+        // same flags, same pictures, but not the same picture on every monitor
+        if let Some(seed) = args.nth_seed(&display.name) {
+            builder = builder.seed(seed);
+        }
+        let path = output_for(args, display);
+        builder.build()?.save(&path)?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn span(args: &GenerateArgs, displays: &[Display]) -> Result<(), Box<dyn Error>> {
+    // the virtual desktop can start left of or above the primary monitor
+    let left = displays.iter().map(|d| d.x).min().unwrap_or(0);
+    let top = displays.iter().map(|d| d.y).min().unwrap_or(0);
+    let right = displays
+        .iter()
+        .map(|d| d.x + d.width as i32)
+        .max()
+        .unwrap_or(0);
+    let bottom = displays
+        .iter()
+        .map(|d| d.y + d.height as i32)
+        .max()
+        .unwrap_or(0);
+    let composition = args
+        .builder()?
+        .size((right - left) as u32, (bottom - top) as u32)
+        .build()?;
+
+    // each piece is the whole desktop's composition with a viewport onto its monitor, so
+    // `reproduce` gives back that piece, and the full span without the viewport
+    let parameters = composition.parameters();
+    for display in displays {
+        let (x, y) = ((display.x - left) as f32, (display.y - top) as f32);
+        let viewport = Viewport::new(x, y, display.width as f32, display.height as f32);
+        let piece = parameters.builder().viewport(viewport).build()?;
+        let path = output_for(args, display);
+        piece.save(&path)?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// `DP-1.png` next to --output, with its extension
+fn output_for(args: &GenerateArgs, display: &Display) -> PathBuf {
+    let name: String = display
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // windows calls them `\\.\DISPLAY1`
+    let name = match name.trim_matches('_') {
+        "" => "display",
+        name => name,
+    };
+    let output = args.output();
+    let extension = output.extension().unwrap_or("png".as_ref());
+    output.with_file_name(name).with_extension(extension)
+}
@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+
+use clap::Subcommand;
+use image::Rgb;
+use mondrian::{
+    color::{to_hex, Palette},
+    palettes::{self, BUILTIN},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct PaletteArgs {
+    #[command(subcommand)]
+    pub command: PaletteCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PaletteCommand {
+    /// every built-in palette, then the ones installed in the config directory
+    List,
+
+    /// one palette's colors and weights
+    Show {
+        /// a built-in or installed palette, or a palette file
+        name: String,
+    },
+}
+
+pub fn run(args: PaletteArgs) -> Result<(), Box<dyn Error>> {
+    match args.command {
+        PaletteCommand::List => {
+            for (name, palette) in BUILTIN {
+                println!("{name:<16}built-in   {}", swatches(&palette));
+            }
+            for path in palettes::installed()? {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                // one bad file shouldn't hide the rest
+                match palettes::read(&path) {
+                    Ok(palette) => println!("{name:<16}installed  {}", swatches(&palette)),
+                    Err(err) => println!("{name:<16}installed  ({err})"),
+                }
+            }
+            if let Some(dir) = palettes::user_dir() {
+                eprintln!("installed palettes go in {}", dir.display());
+            }
+        }
+        PaletteCommand::Show { name } => {
+            let palette = if std::path::Path::new(&name).is_file() {
+                palettes::read(&name)?
+            } else {
+                palettes::find(&name)?
+            };
+            let total: u32 = palette.weights().iter().sum();
+            for (color, weight) in palette.colors().iter().zip(palette.weights()) {
+                let share = *weight as f64 / total.max(1) as f64 * 100.0;
+                println!("{}  {weight:>5}  {share:5.1}%", swatch(*color));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// every color of `palette`, in a row
+fn swatches(palette: &Palette) -> String {
+    let swatches: Vec<String> = palette.colors().iter().map(|c| swatch(*c)).collect();
+    swatches.join(" ")
+}
+
+/// the hex code, after a block of the color itself when stdout is a terminal
+fn swatch(color: Rgb<u8>) -> String {
+    let Rgb([r, g, b]) = color;
+    if io::stdout().is_terminal() {
+        format!("\x1b[48;2;{r};{g};{b}m  \x1b[0m {}", to_hex(color))
+    } else {
+        to_hex(color)
+    }
+}
@@ -0,0 +1,114 @@
+//! value parsers for flags clap can't handle on its own
+
+use std::time::Duration;
+
+use image::Rgb;
+use mondrian::{
+    color::{self, Tone},
+    schedule,
+};
+
+use crate::generate::Clock;
+
+/// any color [`color::parse`] understands, e.g. `#ff0000`, `f00`, `crimson` or `rgb(255 0 0)`
+pub fn color(input: &str) -> Result<Rgb<u8>, String> {
+    color::parse(input).map_err(|err| err.to_string())
+}
+
+/// `color`, `grayscale`, or `duotone` and two colors, e.g. `duotone navy gold`
+pub fn tone(values: &[String]) -> Result<Option<Tone>, String> {
+    let values: Vec<&str> = values.iter().map(String::as_str).collect();
+    match values[..] {
+        [] | ["color"] => Ok(None),
+        ["grayscale"] => Ok(Some(Tone::Grayscale)),
+        ["duotone", dark, light] => Ok(Some(Tone::Duotone(color(dark)?, color(light)?))),
+        ["duotone", ..] => Err(
+            "duotone needs two colors, dark then light, e.g. `--mode duotone navy gold`".to_owned(),
+        ),
+        [mode, ..] => Err(format!(
+            "unknown mode {mode:?}; use color, grayscale, or duotone <dark> <light>"
+        )),
+    }
+}
+
+/// `ROWSxCOLS`, e.g. `4x6`, both at least 1
+pub fn grid(input: &str) -> Result<(u32, u32), String> {
+    by(input).ok_or_else(|| format!("expected ROWSxCOLS, e.g. 4x6, not {input:?}"))
+}
+
+/// `COLSxROWS`, e.g. `8x4`, both at least 1, the other way round from [`grid`] like sizes are
+pub fn columns_rows(input: &str) -> Result<(u32, u32), String> {
+    by(input).ok_or_else(|| format!("expected COLSxROWS, e.g. 8x4, not {input:?}"))
+}
+
+/// `WxH` in pixels, e.g. `128x128`, both at least 1
+pub fn dimensions(input: &str) -> Result<(u32, u32), String> {
+    by(input).ok_or_else(|| format!("expected WIDTHxHEIGHT in pixels, e.g. 128x128, not {input:?}"))
+}
+
+/// two numbers above 0 with an `x` between
+fn by(input: &str) -> Option<(u32, u32)> {
+    let (first, second) = input.split_once(['x', 'X'])?;
+    let (first, second) = (first.trim().parse().ok()?, second.trim().parse().ok()?);
+    (first > 0 && second > 0).then_some((first, second))
+}
+
+/// `DEGREES` or `DEGREES:LIGHTNESS`, e.g. `120` or `120:-0.2`, the lightness 0 if left out
+pub fn drift(input: &str) -> Result<(f32, f32), String> {
+    let (hue, lightness) = input.split_once(':').unwrap_or((input, "0"));
+    let number = |text: &str| text.trim().parse::<f32>().ok().filter(|n| n.is_finite());
+    match (number(hue), number(lightness)) {
+        (Some(hue), Some(lightness)) if (-1.0..=1.0).contains(&lightness) => Ok((hue, lightness)),
+        _ => Err(format!(
+            "expected DEGREES or DEGREES:LIGHTNESS, the lightness from -1 to 1, e.g. 120:-0.2, \
+             not {input:?}"
+        )),
+    }
+}
+
+/// `now`, or a time of day as `HH:MM`
+pub fn clock(input: &str) -> Result<Clock, String> {
+    if input == "now" {
+        return Ok(Clock::Now);
+    }
+    schedule::parse_time(input)
+        .map(Clock::At)
+        .ok_or_else(|| format!("expected a 24-hour time like 18:30, or `now`, not {input:?}"))
+}
+
+/// parses `30m`, `1h30m`, `45s`, `2d` into a Duration
+pub fn duration(input: &str) -> Result<Duration, String> {
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => {
+                return Err(format!(
+                    "unknown unit {c:?} in {input:?}; use s, m, h, or d"
+                ))
+            }
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("expected a number before {c:?} in {input:?}"))?;
+        total += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!(
+            "{input:?} needs a unit, e.g. {number}s or {number}m"
+        ));
+    }
+    if total == 0 {
+        return Err("duration must be longer than 0s".to_owned());
+    }
+    Ok(Duration::from_secs(total))
+}
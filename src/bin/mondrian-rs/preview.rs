@@ -0,0 +1,140 @@
+//! what `explore` and `--gui` share: small previews of big compositions, and picking out and
+//! editing their panes
+
+use std::{fs, path::Path};
+
+use image::{Rgb, RgbImage};
+use mondrian::{
+    edit::{Edit, EditError},
+    prelude::*,
+};
+
+/// keys that edit the selected pane, the same in the terminal and the window
+pub const EDIT_HELP: &str =
+    "tab next pane  x split  m merge  o color  u undo  U redo  h save history  . deselect";
+
+/// `composition` laid out again at `width` × `height`, with lines that don't vanish at that size;
+/// pane indices are the same in both. it's all of the canvas, whatever the viewport, since that's
+/// where panes are picked out
+pub fn resized(
+    composition: &Composition,
+    width: u32,
+    height: u32,
+) -> Result<Composition, BuildError> {
+    let mut parameters = composition.parameters().resized(width, height);
+    parameters.viewport = None;
+    let mut builder = parameters.builder();
+    if composition.border_width() > 0 {
+        builder = builder.border_width(parameters.border_width.max(1));
+    }
+    builder.build()
+}
+
+/// the pane with the point `(x, y)` in it
+pub fn pane_at(composition: &Composition, x: f32, y: f32) -> Option<usize> {
+    composition.panes().iter().position(|pane| {
+        let Rectangle {
+            x: left,
+            y: top,
+            width,
+            height,
+        } = pane.rectangle;
+        (left..left + width).contains(&x) && (top..top + height).contains(&y)
+    })
+}
+
+/// inverts the pixels just inside `rectangle`'s edges, so it shows on any color
+pub fn outline(image: &mut RgbImage, rectangle: &Rectangle) {
+    let clamp = |value: f32, max: u32| (value.round().max(0.0) as u32).min(max);
+    let (left, top) = (
+        clamp(rectangle.x, image.width()),
+        clamp(rectangle.y, image.height()),
+    );
+    let right = clamp(rectangle.x + rectangle.width, image.width());
+    let bottom = clamp(rectangle.y + rectangle.height, image.height());
+    if left >= right || top >= bottom {
+        return;
+    }
+    for y in top..bottom {
+        for x in left..right {
+            let edge = x == left || x + 1 == right || y == top || y + 1 == bottom;
+            if edge {
+                let Rgb([r, g, b]) = *image.get_pixel(x, y);
+                image.put_pixel(x, y, Rgb([255 - r, 255 - g, 255 - b]));
+            }
+        }
+    }
+}
+
+/// makes the edit `key` stands for to the selected pane, if it's one of [`EDIT_HELP`]'s, and
+/// returns the pane to select after: the first half of a split, or what a merge made (`o` steps
+/// through the palette and then the background)
+pub fn edit(
+    composition: &mut Composition,
+    pane: usize,
+    key: char,
+) -> Option<Result<usize, EditError>> {
+    let edit = match key {
+        'x' => Edit::Split(pane),
+        'm' => Edit::Merge(pane),
+        'o' => {
+            let mut colors = composition.palette().colors().to_vec();
+            let background = composition.fill_options().background;
+            if !colors.contains(&background) {
+                colors.push(background);
+            }
+            let current = composition.panes().get(pane)?.color;
+            let next = match colors.iter().position(|color| *color == current) {
+                Some(index) => colors[(index + 1) % colors.len()],
+                None => colors[0],
+            };
+            Edit::Color(pane, next)
+        }
+        _ => return None,
+    };
+    let Rectangle { x, y, .. } = composition.panes().get(pane)?.rectangle;
+    Some(composition.apply(edit).map(|()| {
+        // whatever has the old pane's top left corner now
+        pane_at(composition, x + 0.5, y + 0.5).unwrap_or(0)
+    }))
+}
+
+/// `u` undoes the last edit, `U` redoes it, and `h` saves the history next to `output` to carry
+/// on from with --history; what happened, for the status line, if it was one of those
+pub fn history(composition: &mut Composition, key: char, output: &Path) -> Option<String> {
+    let message = match key {
+        'u' => match composition.undo() {
+            Ok(Some(edit)) => format!("undid {edit}"),
+            Ok(None) => "nothing to undo".to_owned(),
+            Err(err) => format!("couldn't undo: {err}"),
+        },
+        'U' => match composition.redo() {
+            Ok(Some(edit)) => format!("redid {edit}"),
+            Ok(None) => "nothing to redo".to_owned(),
+            Err(err) => format!("couldn't redo: {err}"),
+        },
+        'h' => {
+            let path = output.with_extension("edits");
+            let text = format!(
+                "# carry on with the same flags and --history {}\n{}",
+                path.display(),
+                composition.history()
+            );
+            match fs::write(&path, text) {
+                Ok(()) => format!("saved the history to {}", path.display()),
+                Err(err) => format!("couldn't save the history: {err}"),
+            }
+        }
+        _ => return None,
+    };
+    Some(message)
+}
+
+/// `--edits` for the flags that make the same composition
+pub fn flag(edits: &[Edit]) -> Option<String> {
+    if edits.is_empty() {
+        return None;
+    }
+    let edits: Vec<String> = edits.iter().map(Edit::to_string).collect();
+    Some(format!(" --edits {}", edits.join(",")))
+}
@@ -0,0 +1,80 @@
+//! a progress bar on stderr for renders and batches that take a while
+
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use mondrian::trace::{self, Level};
+
+/// how long something has to take before there's a bar at all, so quick ones don't flicker
+const DELAY: Duration = Duration::from_millis(300);
+/// how often it's redrawn at most
+const REFRESH: Duration = Duration::from_millis(100);
+const WIDTH: usize = 30;
+
+/// `label [#####.....] 42% 420/1000`, redrawn in place, then cleared away when it's dropped
+pub struct Bar {
+    label: &'static str,
+    shown: bool,
+    started: Instant,
+    drawn: Option<Instant>,
+    enabled: bool,
+}
+
+impl Bar {
+    /// a bar that shows itself only if someone's there to watch: stdout and stderr are both
+    /// terminals, nothing's being written `to_stdout` (images least of all), and nothing is
+    /// being logged
+    pub fn new(label: &'static str, to_stdout: bool) -> Self {
+        let enabled = io::stderr().is_terminal()
+            && io::stdout().is_terminal()
+            && !to_stdout
+            && !trace::enabled(Level::Info);
+        Self {
+            label,
+            shown: false,
+            started: Instant::now(),
+            drawn: None,
+            enabled,
+        }
+    }
+
+    /// `done` out of `total`
+    pub fn set(&mut self, done: usize, total: usize) {
+        if !self.enabled || self.started.elapsed() < DELAY {
+            return;
+        }
+        let finished = done >= total;
+        if !finished && self.drawn.is_some_and(|drawn| drawn.elapsed() < REFRESH) {
+            return;
+        }
+        let fraction = if total == 0 {
+            1.0
+        } else {
+            done as f64 / total as f64
+        };
+        let filled = ((fraction * WIDTH as f64) as usize).min(WIDTH);
+        let mut stderr = io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r{} [{}{}] {:>3}% {done}/{total}",
+            self.label,
+            "#".repeat(filled),
+            ".".repeat(WIDTH - filled),
+            (fraction * 100.0) as u32
+        );
+        let _ = stderr.flush();
+        self.shown = true;
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl Drop for Bar {
+    fn drop(&mut self) {
+        if self.shown {
+            // clear the line so whatever's printed next starts clean
+            let _ = write!(io::stderr().lock(), "\r\x1b[2K");
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{error::Error, path::PathBuf};
+
+use mondrian::decode;
+
+use crate::generate;
+
+#[derive(clap::Args, Debug)]
+pub struct ReproduceArgs {
+    /// a PNG or JPEG this tool wrote
+    pub input: PathBuf,
+
+    /// new width; keeps the original aspect ratio if --height is left out
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// new height; keeps the original aspect ratio if --width is left out
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// where to write the image, `-` for PNG on stdout [default: next to the input, e.g.
+    /// `input-7680x4320.png`]
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// shared libraries with the split strategy or fill plugins it was made with
+    #[arg(long, value_name = "PATH")]
+    pub plugin: Vec<PathBuf>,
+}
+
+pub fn run(args: ReproduceArgs) -> Result<(), Box<dyn Error>> {
+    let parameters = decode::read(&args.input)?;
+    if parameters.version != env!("CARGO_PKG_VERSION") {
+        eprintln!(
+            "warning: {} was made by version {}; the layout may differ",
+            args.input.display(),
+            parameters.version
+        );
+    }
+
+    let scale = |from: u32, to: u32, other: u32| {
+        (other as u64 * to as u64 / from.max(1) as u64).max(1) as u32
+    };
+    let (width, height) = match (args.width, args.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, scale(parameters.width, width, parameters.height)),
+        (None, Some(height)) => (scale(parameters.height, height, parameters.width), height),
+        (None, None) => (parameters.width, parameters.height),
+    };
+
+    let output = args.output.unwrap_or_else(|| {
+        let stem = args.input.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = args.input.extension().unwrap_or_default().to_string_lossy();
+        args.input
+            .with_file_name(format!("{stem}-{width}x{height}.{extension}"))
+    });
+
+    generate::load_plugins(&args.plugin)?;
+    let composition = parameters.resized(width, height).builder().build()?;
+    Ok(generate::save_composition(&composition, &output, None)?)
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use crate::{generate::GenerateArgs, parse};
+
+#[derive(clap::Args, Debug)]
+pub struct ScreensaverArgs {
+    /// time each composition is shown for, e.g. `10s`, `1m`
+    #[arg(long, default_value = "10s", value_name = "DURATION", value_parser = parse::duration)]
+    pub interval: Duration,
+
+    /// time it takes to turn from one into the next; `0s` cuts straight to it
+    #[arg(long, default_value = "1s", value_name = "DURATION", value_parser = parse::duration)]
+    pub fade: Duration,
+
+    /// the size is the screen's, whatever --width and --height say
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+#[cfg(feature = "gui")]
+pub use saver::run;
+
+#[cfg(not(feature = "gui"))]
+pub fn run(_args: ScreensaverArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err("the screensaver needs mondrian-rs built with `--features gui`".into())
+}
+
+#[cfg(feature = "gui")]
+mod saver {
+    use std::error::Error;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use image::RgbImage;
+    use mondrian::prelude::*;
+    use rand::Rng;
+
+    use super::ScreensaverArgs;
+    use crate::window::{Event, Window};
+
+    /// how often the fade draws a new frame
+    const FRAME: Duration = Duration::from_millis(33);
+    /// how often it looks for input while a composition is showing
+    const POLL: Duration = Duration::from_millis(50);
+    /// how far the mouse has to go, in pixels, to count as being moved rather than nudged
+    const JIGGLE: i32 = 8;
+
+    /// what the input since it was last looked at asks for
+    enum Wake {
+        /// put the picture up again
+        Redraw,
+        /// make one the new size
+        Resize,
+        Quit,
+    }
+
+    /// what's come in, stopping at anything that wakes it; some mice report moving without
+    /// being touched, so until it settles where the mouse first was, only far from there counts
+    fn wake(
+        window: &mut Window,
+        settled: &mut Option<(i16, i16)>,
+    ) -> Result<Option<Wake>, Box<dyn Error>> {
+        while let Some(event) = window.event(false)? {
+            match event {
+                Event::Expose => return Ok(Some(Wake::Redraw)),
+                Event::Resize { .. } => return Ok(Some(Wake::Resize)),
+                Event::Motion { x, y } | Event::Drag { x, y } => {
+                    let (from_x, from_y) = *settled.get_or_insert((x, y));
+                    let (dx, dy) = ((x - from_x) as i32, (y - from_y) as i32);
+                    if dx.abs() > JIGGLE || dy.abs() > JIGGLE {
+                        return Ok(Some(Wake::Quit));
+                    }
+                }
+                Event::Key(_) | Event::Press { .. } | Event::Release | Event::Close => {
+                    return Ok(Some(Wake::Quit))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// `to` as far as `t` of the way from `from`, which is the same size
+    fn faded(from: &RgbImage, to: &RgbImage, t: f32) -> RgbImage {
+        let t = (t.clamp(0.0, 1.0) * 256.0) as u32;
+        let pixels = from
+            .as_raw()
+            .iter()
+            .zip(to.as_raw())
+            .map(|(&a, &b)| ((a as u32 * (256 - t) + b as u32 * t) >> 8) as u8)
+            .collect();
+        RgbImage::from_raw(from.width(), from.height(), pixels).expect("the same size as both")
+    }
+
+    /// covers the screen and shows a new composition every --interval, morphed into from the last,
+    /// until a key, a click or the mouse moving; seeds go like the daemon's
+    pub fn run(args: ScreensaverArgs) -> Result<(), Box<dyn Error>> {
+        let mut window = Window::fullscreen("mondrian")?;
+        let mut rng = rand::thread_rng();
+        let mut settled = None;
+        let mut shown: Option<(Composition, RgbImage)> = None;
+        for round in 0u64.. {
+            let (width, height) = window.size();
+            let seed = args.generate.nth_seed(round).unwrap_or_else(|| rng.gen());
+            let build = || -> Result<Composition, Box<dyn Error>> {
+                let builder = args.generate.builder()?;
+                Ok(builder
+                    .size(width as u32, height as u32)
+                    .seed(seed)
+                    .build()?)
+            };
+            let composition = build()?;
+            let next = composition.render_rgb();
+
+            // the last one turned into this one, unless there isn't one or it's a different size;
+            // layouts morph, and anything without lines to move fades
+            if let Some((last, last_image)) = shown
+                .take()
+                .filter(|(_, image)| image.dimensions() == next.dimensions())
+            {
+                let start = Instant::now();
+                while start.elapsed() < args.fade {
+                    let t = start.elapsed().as_secs_f32() / args.fade.as_secs_f32();
+                    let frame = if composition.style().splits() {
+                        let eased = t * t * (3.0 - 2.0 * t);
+                        build()?.morphed_from(&last, eased).render_rgb()
+                    } else {
+                        faded(&last_image, &next, t)
+                    };
+                    window.put(&frame)?;
+                    window.flush()?;
+                    if let Some(Wake::Quit) = wake(&mut window, &mut settled)? {
+                        return Ok(());
+                    }
+                    thread::sleep(FRAME);
+                }
+            }
+            window.put(&next)?;
+            window.flush()?;
+            shown = Some((composition, next));
+
+            let until = Instant::now() + args.interval;
+            while Instant::now() < until {
+                match wake(&mut window, &mut settled)? {
+                    Some(Wake::Quit) => return Ok(()),
+                    Some(Wake::Resize) => break,
+                    Some(Wake::Redraw) => {
+                        window.put(&shown.as_ref().expect("it was just shown").1)?;
+                        window.flush()?;
+                    }
+                    None => thread::sleep(POLL),
+                }
+            }
+        }
+        Ok(())
+    }
+}
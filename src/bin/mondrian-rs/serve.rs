@@ -0,0 +1,1049 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use mondrian::{
+    color::{self, to_hex},
+    metadata::{Parameters, KEY_PREFIX},
+    prelude::*,
+    seed,
+    trace::{self, Level},
+};
+
+use crate::{generate::GenerateArgs, metrics::Metrics};
+
+/// the most a request's line and headers can come to
+const MAX_HEAD: u64 = 16 * 1024;
+/// the most a POST's body can be
+const MAX_BODY: usize = 64 * 1024;
+/// parameters that name files on the machine it's running on, which nobody asking gets to pick
+const FILES: &[&str] = &[
+    "split-plugin",
+    "fill-plugin",
+    "from-image",
+    "from-audio",
+    "script",
+    "detail-mask",
+    "font",
+    "title-font",
+    "calendar-font",
+    "stats-font",
+];
+/// how long a request can take to come in, head and body, before it's given up on
+pub const PATIENCE: Duration = Duration::from_secs(10);
+/// the most of anything a POST can give a list of: keep-clear zones, edits, palette colors
+const MAX_LIST: usize = 64;
+/// the most characters a POST's text, title, subtitle or QR code can have
+const MAX_TEXT: usize = 1024;
+/// how many addresses the rate limit keeps track of before it forgets the ones that are back to
+/// a full allowance
+const KEPT: usize = 10_000;
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// where to listen, e.g. `127.0.0.1:8080`, or `0.0.0.0:8080` for other machines too
+    #[arg(long, default_value = "127.0.0.1:8080", value_name = "ADDRESS")]
+    pub listen: String,
+
+    /// the most pixels a request can ask for, across times down
+    #[arg(long, default_value_t = 3840 * 2160, value_name = "N")]
+    pub max_pixels: u64,
+
+    /// the most levels a request can ask for, or 2 to the power of it panes; cells, target panes
+    /// and treemap entries count as the levels they'd take, and a timeline's strips add to
+    /// --levels
+    #[arg(long, default_value_t = 12, value_name = "N")]
+    pub max_levels: usize,
+
+    /// requests a minute from each address, answered or not, before they're turned away with a
+    /// 429 until there's room again; 0 for no limit. behind a proxy, they're all the proxy's
+    #[arg(long, default_value_t = 60, value_name = "N")]
+    pub rate_limit: u32,
+
+    /// how many connections are answered at once; the ones over it are turned away with a 503
+    #[arg(long, default_value_t = 64, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_connections: u32,
+
+    /// what's drawn, unless the request says otherwise; the output flags are ignored
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+/// answers `GET /image.png`, `/image.svg` and `/layout.json`, the split tree and its colors,
+/// and `POST /generate`, each connection on a thread of its own, until stopped
+///
+/// `?seed=`, `width=`, `height=` and `levels=` change what's drawn; a seed that isn't a number
+/// is hashed like --seed-from. whatever's drawn gets an `ETag` made from its parameters, so
+/// caches can ask again with `If-None-Match` and get a `304` without it being rendered. with no
+/// seed from the request or the flags it's a new one each time, and isn't to be cached
+///
+/// `/generate` takes a JSON object of parameters in the schema of the ones embedded in images,
+/// over the ones the flags make, and answers with the PNG: `{"seed":7,"palette":"#fff,#000"}`.
+/// palettes and weights can be arrays too, colors can be anything --palette takes, and a
+/// palette without weights weighs its colors the same. it needs the `json` feature
+///
+/// `/metrics` is for Prometheus, like the daemon's --metrics-addr, and isn't rate limited
+pub fn run(args: ServeArgs) -> Result<(), Box<dyn Error>> {
+    // bad flags won't get better by being asked for, and what they make is what POSTs change
+    let base = args.generate.builder()?.build()?.parameters();
+    let limits = Limits {
+        pixels: args.max_pixels,
+        levels: args.max_levels,
+        rate: args.rate_limit,
+        buckets: Mutex::new(HashMap::new()),
+    };
+    let metrics = Metrics::default();
+    let open = AtomicUsize::new(0);
+    let listener = TcpListener::bind(&args.listen)?;
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    trace::event(Level::Info, "failed", &[("error", &err)]);
+                    continue;
+                }
+            };
+            let Some(slot) = Slot::take(&open, args.max_connections as usize) else {
+                metrics.answered(503);
+                let mut response = Response::error(503, "too busy; try again later");
+                response.headers.push(("Retry-After", "1".into()));
+                // it's small enough not to wait on, unless the other end isn't reading
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                let _ = write_response(BufWriter::new(stream), (response, false));
+                continue;
+            };
+            let (generate, base, limits) = (&args.generate, &base, &limits);
+            let metrics = &metrics;
+            scope.spawn(move || {
+                // the other end going away is no reason to stop
+                if let Err(err) = serve(stream, generate, base, limits, metrics) {
+                    trace::event(Level::Debug, "dropped", &[("error", &err)]);
+                }
+                drop(slot);
+            });
+        }
+    });
+    Ok(())
+}
+
+/// one of the connections being answered, given back when it's dropped
+pub struct Slot<'a>(&'a AtomicUsize);
+
+impl<'a> Slot<'a> {
+    /// one of `most`, if they aren't all taken
+    pub fn take(open: &'a AtomicUsize, most: usize) -> Option<Self> {
+        open.fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| {
+            (taken < most).then_some(taken + 1)
+        })
+        .ok()
+        .map(|_| Self(open))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// a connection that has until [`PATIENCE`] from when it was opened for the request to come in,
+/// however slowly it trickles, rather than that long for each read
+pub struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Deadline {
+    /// buffered, with [`PATIENCE`] from now
+    pub fn reader(stream: &TcpStream) -> io::Result<BufReader<Self>> {
+        Ok(BufReader::new(Self {
+            stream: stream.try_clone()?,
+            until: Instant::now() + PATIENCE,
+        }))
+    }
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the request took too long to come in",
+            ));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buffer)
+    }
+}
+
+/// how much anyone can ask for
+struct Limits {
+    pixels: u64,
+    levels: usize,
+    /// requests a minute
+    rate: u32,
+    /// how many requests each address has left, and when that was
+    buckets: Mutex<HashMap<IpAddr, (f64, Instant)>>,
+}
+
+impl Limits {
+    /// whether `address` can make another request now, and takes it off its allowance if so;
+    /// the allowance fills back up at `rate` a minute
+    fn allow(&self, address: IpAddr) -> bool {
+        if self.rate == 0 {
+            return true;
+        }
+        let (rate, now) = (self.rate as f64, Instant::now());
+        let refilled = |(left, at): (f64, Instant)| {
+            (left + now.duration_since(at).as_secs_f64() * rate / 60.0).min(rate)
+        };
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= KEPT {
+            buckets.retain(|_, bucket| refilled(*bucket) < rate);
+        }
+        let left = buckets
+            .get(&address)
+            .map_or(rate, |bucket| refilled(*bucket));
+        let allowed = left >= 1.0;
+        buckets.insert(address, (if allowed { left - 1.0 } else { left }, now));
+        allowed
+    }
+
+    /// whether `width` by `height` with `levels` is in bounds, and what's too much if not
+    fn check(&self, width: u32, height: u32, levels: usize) -> Result<(), String> {
+        if width as u64 * height as u64 > self.pixels {
+            return Err(format!(
+                "{width}x{height} is more than the {} pixels allowed",
+                self.pixels
+            ));
+        }
+        if levels > self.levels {
+            return Err(format!(
+                "{levels} levels is more than the {} allowed",
+                self.levels
+            ));
+        }
+        Ok(())
+    }
+
+    /// [`check`](Self::check) for everything in `parameters` that makes work: cells, target
+    /// panes, treemap entries and a bred layout's panes count as the levels it'd take to make
+    /// that many, and a timeline's strips as that many more levels on top of --levels, since
+    /// each is cut that deep. lists are kept to [`MAX_LIST`] and text to [`MAX_TEXT`]
+    fn admit(&self, parameters: &Parameters) -> Result<(), String> {
+        // 2 to the power of it is at least `panes`
+        let levels_for = |panes: usize| panes.max(1).next_power_of_two().ilog2() as usize;
+        let timeline = parameters
+            .timeline
+            .as_ref()
+            .map(|values| parameters.levels + levels_for(values.len()));
+        let levels = parameters
+            .cells
+            .into_iter()
+            .chain(parameters.target_panes)
+            .chain(parameters.treemap.as_ref().map(Vec::len))
+            .chain(parameters.genome.as_ref().map(|genome| genome.panes()))
+            .map(levels_for)
+            .chain(timeline)
+            .fold(parameters.levels, usize::max);
+        self.check(parameters.width, parameters.height, levels)?;
+
+        let lists = [
+            ("keep-clear zones", parameters.keep_clear.len()),
+            ("edits", parameters.edits.len()),
+            ("palette colors", parameters.palette.colors().len()),
+        ];
+        if let Some((key, count)) = lists.into_iter().find(|&(_, count)| count > MAX_LIST) {
+            return Err(format!("{count} {key} is more than the {MAX_LIST} allowed"));
+        }
+        let texts = [
+            ("text", parameters.text.as_ref().map(|text| &text.text)),
+            ("title", parameters.card.as_ref().map(|card| &card.title)),
+            (
+                "subtitle",
+                parameters
+                    .card
+                    .as_ref()
+                    .and_then(|card| card.subtitle.as_ref()),
+            ),
+            ("qr", parameters.qr.as_ref()),
+        ];
+        for (key, text) in texts {
+            let length = text.map_or(0, |text| text.chars().count());
+            if length > MAX_TEXT {
+                return Err(format!(
+                    "{key} is {length} characters, more than the {MAX_TEXT} allowed"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// a request as far as it matters here
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    query: Vec<(String, String)>,
+    if_none_match: Option<String>,
+    body: Vec<u8>,
+}
+
+/// what goes back
+pub struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type", content_type.to_owned())],
+            body: body.into(),
+        }
+    }
+
+    /// a line of plain text saying what's wrong
+    pub fn error(status: u16, why: impl std::fmt::Display) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", format!("{why}\n"))
+    }
+
+    /// the methods `allowed` there, for a request that isn't one
+    pub fn allow(allowed: &str) -> Self {
+        let mut response = Self::error(405, format!("only {allowed} is answered there"));
+        response.headers.push(("Allow", allowed.to_owned()));
+        response
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    generate: &GenerateArgs,
+    base: &Parameters,
+    limits: &Limits,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    let address = stream.peer_addr()?.ip();
+    let mut reader = Deadline::reader(&stream)?;
+    let response = match read_request(&mut reader)? {
+        Ok(request) => {
+            let span = trace::span(
+                Level::Info,
+                "request",
+                &[
+                    ("address", &address),
+                    ("method", &request.method),
+                    ("path", &request.path),
+                ],
+            );
+            // scrapes aren't limited, or counted
+            let scrape = request.path == "/metrics";
+            let response = if scrape || limits.allow(address) {
+                respond(&request, generate, base, limits, metrics)
+            } else {
+                let mut response = Response::error(429, "too many requests; try again later");
+                let wait = (60.0 / limits.rate as f64).ceil();
+                response.headers.push(("Retry-After", wait.to_string()));
+                response
+            };
+            trace::event(Level::Info, "answered", &[("status", &response.status)]);
+            if !scrape {
+                metrics.answered(response.status);
+            }
+            drop(span);
+            // a HEAD is the GET's headers alone
+            (response, request.method == "HEAD")
+        }
+        Err(response) => (response, false),
+    };
+    write_response(BufWriter::new(stream), response)
+}
+
+/// the request, or what to answer if it doesn't make sense
+pub fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut head = reader.take(MAX_HEAD);
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Ok(Err(Response::error(400, "that isn't an HTTP request")));
+    };
+    let (method, target) = (method.to_owned(), target.to_owned());
+
+    let (mut if_none_match, mut length, mut chunked) = (None, None, false);
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 {
+            return Ok(Err(Response::error(
+                431,
+                "the request's headers are too long",
+            )));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+        match name.as_str() {
+            "if-none-match" => if_none_match = Some(value.to_owned()),
+            "content-length" => match value.parse::<usize>() {
+                Ok(value) => length = Some(value),
+                Err(_) => return Ok(Err(Response::error(400, "that isn't a Content-Length"))),
+            },
+            "transfer-encoding" => chunked = true,
+            _ => {}
+        }
+    }
+
+    let mut body = vec![];
+    if method == "POST" {
+        match length {
+            _ if chunked => {
+                return Ok(Err(Response::error(411, "the body needs a Content-Length")))
+            }
+            None => return Ok(Err(Response::error(411, "the body needs a Content-Length"))),
+            Some(length) if length > MAX_BODY => {
+                let why = format!("the body can be up to {MAX_BODY} bytes, not {length}");
+                return Ok(Err(Response::error(413, why)));
+            }
+            Some(length) => {
+                body.resize(length, 0);
+                head.into_inner().read_exact(&mut body)?;
+            }
+        }
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    Ok(Ok(Request {
+        path: decode(path),
+        method,
+        query,
+        if_none_match,
+        body,
+    }))
+}
+
+/// `%XX`s and `+`s in a URL back into what they stand for
+fn decode(text: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        rest = after;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => match rest
+                .get(..2)
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok())
+            {
+                Some(decoded) => {
+                    bytes.push(decoded);
+                    rest = &rest[2..];
+                }
+                None => bytes.push(byte),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// what `request` asks for
+fn respond(
+    request: &Request,
+    generate: &GenerateArgs,
+    base: &Parameters,
+    limits: &Limits,
+    metrics: &Metrics,
+) -> Response {
+    match (request.path.as_str(), request.method.as_str()) {
+        ("/image.png" | "/image.svg" | "/layout.json", "GET" | "HEAD") => {
+            get(request, generate, base, limits, metrics)
+        }
+        ("/generate", "POST") => post(request, base, limits, metrics),
+        ("/metrics", "GET" | "HEAD") => metrics.response(),
+        ("/image.png" | "/image.svg" | "/layout.json" | "/metrics", _) => {
+            Response::allow("GET, HEAD")
+        }
+        ("/generate", _) => Response::allow("POST"),
+        _ => Response::error(
+            404,
+            "there's /image.png, /image.svg, /layout.json, POST /generate and /metrics",
+        ),
+    }
+}
+
+/// the composition the flags and `request`'s query make, as the image or layout its path names
+fn get(
+    request: &Request,
+    generate: &GenerateArgs,
+    base: &Parameters,
+    limits: &Limits,
+    metrics: &Metrics,
+) -> Response {
+    let start = Instant::now();
+    let mut builder = match generate.builder() {
+        Ok(builder) => builder,
+        Err(err) => return Response::error(500, err),
+    };
+    let mut seeded = generate.seed.is_some() || generate.seed_from.is_some();
+    for (key, value) in &request.query {
+        let number = || {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("{key} should be a whole number above 0, not {value:?}"))
+        };
+        let changed = match key.as_str() {
+            "seed" => {
+                seeded = true;
+                Ok(match value.parse() {
+                    Ok(seed) => builder.seed(seed),
+                    Err(_) => builder.seed(seed::from_text(value)),
+                })
+            }
+            "width" => number().map(|width| builder.width(width)),
+            "height" => number().map(|height| builder.height(height)),
+            "levels" => number().map(|levels| builder.levels(levels as usize)),
+            _ => Err(format!("{key} isn't one of seed, width, height and levels")),
+        };
+        builder = match changed {
+            Ok(builder) => builder,
+            Err(why) => return Response::error(400, why),
+        };
+    }
+    // what the flags ask for is up to whoever's running it, but not what's asked for on top
+    let asked = |key: &str| request.query.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let number = |key, default| asked(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+    if !request.query.is_empty() {
+        let (width, height) = (number("width", base.width), number("height", base.height));
+        let levels = number("levels", base.levels as u32) as usize;
+        if let Err(why) = limits.check(width, height, levels) {
+            return Response::error(400, why);
+        }
+    }
+    let composition = match builder.build() {
+        Ok(composition) => composition,
+        Err(err) => return Response::error(400, err),
+    };
+
+    // the same parameters draw the same thing, and each path is its own thing drawn
+    let pairs: Vec<String> = composition
+        .parameters()
+        .to_pairs()
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    let etag = format!(
+        "\"{:016x}\"",
+        seed::from_text(&format!("{}\n{}", request.path, pairs.join("\n")))
+    );
+    let cache = if seeded {
+        "public, max-age=86400"
+    } else {
+        "no-store"
+    };
+    let headers = vec![
+        ("ETag", etag.clone()),
+        ("Cache-Control", cache.to_owned()),
+        ("X-Seed", composition.seed().to_string()),
+    ];
+    let fresh = request.if_none_match.as_deref().is_some_and(|tags| {
+        tags.split(',')
+            .any(|tag| tag.trim() == etag || tag.trim() == "*")
+    });
+    if fresh {
+        return Response {
+            status: 304,
+            headers,
+            body: vec![],
+        };
+    }
+
+    let (content_type, body) = match request.path.as_str() {
+        "/image.png" => match png(&composition, metrics) {
+            Ok(png) => ("image/png", png),
+            Err(response) => return response,
+        },
+        "/image.svg" => ("image/svg+xml", composition.render_svg().into_bytes()),
+        _ => ("application/json", composition.tree_json().into_bytes()),
+    };
+    if request.path != "/layout.json" {
+        metrics.made(composition.panes().len(), start.elapsed());
+    }
+    let mut response = Response::new(200, content_type, body);
+    response.headers.extend(headers);
+    response
+}
+
+/// the PNG of the parameters in `request`'s body, over `base`
+fn post(request: &Request, base: &Parameters, limits: &Limits, metrics: &Metrics) -> Response {
+    let start = Instant::now();
+    let Ok(body) = std::str::from_utf8(&request.body) else {
+        return Response::error(400, "the body isn't UTF-8");
+    };
+    let given = match settings(body) {
+        Ok(given) => given,
+        Err(response) => return response,
+    };
+    let given = match adhoc(given) {
+        Ok(given) => given,
+        Err(why) => return Response::error(400, why),
+    };
+    if let Some((key, _)) = given.iter().find(|(key, _)| FILES.contains(&key.as_str())) {
+        return Response::error(400, format!("{key} names a file, which can't be asked for"));
+    }
+
+    // what's given goes over what the flags make, with a new seed unless there's one given
+    let mut pairs: Vec<(String, String)> = base
+        .to_pairs()
+        .into_iter()
+        .map(|(key, value)| {
+            let key = key.strip_prefix(KEY_PREFIX).unwrap_or(&key).to_owned();
+            (key, value)
+        })
+        .filter(|(key, _)| given.iter().all(|(given, _)| given != key))
+        .collect();
+    if given.iter().all(|(key, _)| key != "seed") {
+        pairs.retain(|(key, _)| key != "seed");
+        pairs.push(("seed".into(), rand::random::<u64>().to_string()));
+    }
+    pairs.extend(given);
+    let prefixed: Vec<(String, String)> = pairs
+        .into_iter()
+        .map(|(key, value)| (format!("{KEY_PREFIX}{key}"), value))
+        .collect();
+    let parameters = Parameters::from_pairs(
+        prefixed
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
+    let parameters = match parameters {
+        Ok(parameters) => parameters,
+        Err(err) => return Response::error(400, err),
+    };
+    if let Err(why) = limits.admit(&parameters) {
+        return Response::error(400, why);
+    }
+    let composition = match parameters.builder().build() {
+        Ok(composition) => composition,
+        Err(err) => return Response::error(400, err),
+    };
+    match png(&composition, metrics) {
+        Ok(png) => {
+            metrics.made(composition.panes().len(), start.elapsed());
+            let mut response = Response::new(200, "image/png", png);
+            response.headers.push(("Cache-Control", "no-store".into()));
+            response
+                .headers
+                .push(("X-Seed", composition.seed().to_string()));
+            response
+        }
+        Err(response) => response,
+    }
+}
+
+/// a palette as `#rrggbb`s, whatever they were written as, and the same weight for each color
+/// if it's given without weights
+fn adhoc(mut given: Vec<(String, String)>) -> Result<Vec<(String, String)>, String> {
+    let Some(at) = given.iter().position(|(key, _)| key == "palette") else {
+        return Ok(given);
+    };
+    let colors = given[at]
+        .1
+        .split(',')
+        .map(|color| color::parse(color.trim()).map(to_hex))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("palette: {err}"))?;
+    if given.iter().all(|(key, _)| key != "weights") {
+        given.push(("weights".into(), vec!["1"; colors.len()].join(",")));
+    }
+    given[at].1 = colors.join(",");
+    Ok(given)
+}
+
+/// a JSON object's keys and values, as the text [`Parameters::from_pairs`] takes; arrays are
+/// joined with commas
+#[cfg(feature = "json")]
+fn settings(body: &str) -> Result<Vec<(String, String)>, Response> {
+    use serde_json::Value;
+
+    let object: serde_json::Map<String, Value> = serde_json::from_str(body)
+        .map_err(|err| Response::error(400, format!("the body isn't a JSON object: {err}")))?;
+    let scalar = |key: &str, value: &Value| match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(boolean) => Ok(boolean.to_string()),
+        _ => Err(Response::error(
+            400,
+            format!("{key} should be a string, number, boolean or array of them"),
+        )),
+    };
+    object
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Array(values) => values
+                    .iter()
+                    .map(|value| scalar(key, value))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(","),
+                value => scalar(key, value)?,
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "json"))]
+fn settings(_body: &str) -> Result<Vec<(String, String)>, Response> {
+    Err(Response::error(
+        501,
+        "POST /generate needs mondrian-rs built with `--features json`",
+    ))
+}
+
+/// `composition` encoded, or a 500 counted as a failure if it can't be
+fn png(composition: &Composition, metrics: &Metrics) -> Result<Vec<u8>, Response> {
+    let mut png = vec![];
+    composition.write_png(&mut png).map_err(|err| {
+        metrics.failed();
+        Response::error(500, err)
+    })?;
+    Ok(png)
+}
+
+pub fn write_response(
+    mut writer: impl Write,
+    (response, head): (Response, bool),
+) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    write!(writer, "HTTP/1.1 {} {reason}\r\n", response.status)?;
+    for (name, value) in &response.headers {
+        write!(writer, "{name}: {value}\r\n")?;
+    }
+    write!(
+        writer,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    )?;
+    if !head {
+        writer.write_all(&response.body)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use image::Rgb;
+
+    use super::*;
+
+    fn read(request: &str) -> io::Result<Result<Request, Response>> {
+        read_request(&mut request.as_bytes())
+    }
+
+    /// the status of what's answered to `request`, which doesn't make sense
+    fn refused(request: &str) -> u16 {
+        match read(request) {
+            Ok(Err(response)) => response.status,
+            Ok(Ok(_)) => panic!("{request:?} was let through"),
+            Err(err) => panic!("{request:?} failed: {err}"),
+        }
+    }
+
+    fn limits(rate: u32) -> Limits {
+        Limits {
+            pixels: 1000 * 1000,
+            levels: 12,
+            rate,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn parameters() -> Parameters {
+        CompositionBuilder::new()
+            .size(800, 600)
+            .levels(4)
+            .build()
+            .unwrap()
+            .parameters()
+    }
+
+    #[test]
+    fn reads_requests() {
+        let get = "GET /image.png?seed=hello+world&width=%32%30&levels HTTP/1.1\r\n\
+                   Host: localhost\r\nIF-NONE-MATCH: \"abc\"\r\nnot a header\r\n\r\n";
+        let Ok(Ok(request)) = read(get) else {
+            panic!("a GET is read");
+        };
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("GET", "/image.png")
+        );
+        let query = [("seed", "hello world"), ("width", "20"), ("levels", "")];
+        let query: Vec<(String, String)> = query
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+        assert_eq!(request.query, query);
+        assert_eq!(request.if_none_match.as_deref(), Some("\"abc\""));
+        assert!(request.body.is_empty());
+
+        let post =
+            "POST /generate HTTP/1.1\r\nContent-Length: 11\r\n\r\n{\"seed\":7}\nand the rest";
+        let Ok(Ok(request)) = read(post) else {
+            panic!("a POST is read");
+        };
+        assert_eq!(request.body, b"{\"seed\":7}\n");
+        assert_eq!(decode("100%25%2x%"), "100%%2x%");
+    }
+
+    #[test]
+    fn malformed_requests_are_refused() {
+        assert_eq!(refused(""), 400);
+        assert_eq!(refused("hello\r\n\r\n"), 400);
+        assert_eq!(refused("GET /\r\n\r\n"), 400);
+        let length = "POST /generate HTTP/1.1\r\nContent-Length: -1\r\n\r\n";
+        assert_eq!(refused(length), 400);
+        assert_eq!(refused("POST /generate HTTP/1.1\r\n\r\n"), 411);
+        let chunked = "POST /generate HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello";
+        assert_eq!(refused(chunked), 411);
+    }
+
+    #[test]
+    fn truncated_requests_are_refused() {
+        // headers that never end, whether the other end stops or keeps going
+        assert_eq!(refused("GET / HTTP/1.1\r\nHost: localhost\r\n"), 431);
+        let endless = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(20_000)
+        );
+        assert_eq!(refused(&endless), 431);
+        // a first line cut off before its version isn't one
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(20_000));
+        assert_eq!(refused(&long_line), 400);
+
+        // a body shorter than it says it is
+        let short = "POST /generate HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}";
+        let err = read(short).err().expect("a short body is an error");
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_bodies_are_refused() {
+        let most = format!(
+            "POST /generate HTTP/1.1\r\nContent-Length: {MAX_BODY}\r\n\r\n{}",
+            " ".repeat(MAX_BODY)
+        );
+        assert!(matches!(read(&most), Ok(Ok(_))));
+        let over = format!(
+            "POST /generate HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert_eq!(refused(&over), 413);
+    }
+
+    #[test]
+    fn checks_pixels_and_levels() {
+        let limits = limits(0);
+        assert_eq!(limits.check(1000, 1000, 12), Ok(()));
+        assert_eq!(
+            limits.check(1001, 1000, 1),
+            Err("1001x1000 is more than the 1000000 pixels allowed".to_owned())
+        );
+        // enough to overflow a u32
+        assert!(limits.check(u32::MAX, u32::MAX, 1).is_err());
+        assert_eq!(
+            limits.check(10, 10, 13),
+            Err("13 levels is more than the 12 allowed".to_owned())
+        );
+    }
+
+    #[test]
+    fn admits_what_fits() {
+        let limits = limits(0);
+        let base = parameters();
+        assert_eq!(limits.admit(&base), Ok(()));
+
+        let too_big = Parameters {
+            width: 2000,
+            ..base.clone()
+        };
+        assert!(limits.admit(&too_big).is_err());
+
+        // 4096 panes take 12 levels and one more takes 13
+        for (panes, admitted) in [(4096, true), (4097, false)] {
+            let cells = Parameters {
+                cells: Some(panes),
+                ..base.clone()
+            };
+            let target = Parameters {
+                target_panes: Some(panes),
+                ..base.clone()
+            };
+            let treemap = Parameters {
+                treemap: Some(vec![1.0; panes]),
+                ..base.clone()
+            };
+            for parameters in [cells, target, treemap] {
+                assert_eq!(limits.admit(&parameters).is_ok(), admitted, "{panes} panes");
+            }
+        }
+
+        // strips are cut --levels deep each
+        let timeline = |levels, strips| Parameters {
+            levels,
+            timeline: Some(vec![1.0; strips]),
+            ..base.clone()
+        };
+        assert_eq!(limits.admit(&timeline(10, 4)), Ok(()));
+        assert_eq!(
+            limits.admit(&timeline(10, 5)),
+            Err("13 levels is more than the 12 allowed".to_owned())
+        );
+    }
+
+    #[test]
+    fn admits_lists_and_text_up_to_a_point() {
+        let limits = limits(0);
+        let base = parameters();
+        let colors = |count| Parameters {
+            palette: Palette::new(vec![Rgb([0, 0, 0]); count]),
+            ..base.clone()
+        };
+        assert_eq!(limits.admit(&colors(MAX_LIST)), Ok(()));
+        assert_eq!(
+            limits.admit(&colors(MAX_LIST + 1)),
+            Err("65 palette colors is more than the 64 allowed".to_owned())
+        );
+        let zones = Parameters {
+            keep_clear: vec![Rectangle::new(0.0, 0.0, 1.0, 1.0); MAX_LIST + 1],
+            ..base.clone()
+        };
+        assert!(limits.admit(&zones).is_err());
+
+        let qr = |text: &str| Parameters {
+            qr: Some(text.to_owned()),
+            ..base.clone()
+        };
+        // characters, not bytes
+        assert_eq!(limits.admit(&qr(&"é".repeat(MAX_TEXT))), Ok(()));
+        assert_eq!(
+            limits.admit(&qr(&"a".repeat(MAX_TEXT + 1))),
+            Err("qr is 1025 characters, more than the 1024 allowed".to_owned())
+        );
+    }
+
+    #[test]
+    fn rate_limits_each_address() {
+        let (first, second) = (
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        );
+        let limits = limits(2);
+        assert!(limits.allow(first));
+        assert!(limits.allow(first));
+        assert!(!limits.allow(first));
+        assert!(!limits.allow(first));
+        assert!(limits.allow(second));
+
+        // a minute at 2 a minute is 2 more, but never more than a full allowance
+        let rewind = |address, by| {
+            let mut buckets = limits.buckets.lock().unwrap();
+            let (_, at) = buckets.get_mut(&address).unwrap();
+            *at -= Duration::from_secs(by);
+        };
+        rewind(first, 30);
+        assert!(limits.allow(first));
+        assert!(!limits.allow(first));
+        rewind(first, 600);
+        assert!(limits.allow(first));
+        assert!(limits.allow(first));
+        assert!(!limits.allow(first));
+
+        let unlimited = self::limits(0);
+        assert!((0..1000).all(|_| unlimited.allow(first)));
+        assert!(unlimited.buckets.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn forgets_addresses_back_to_a_full_allowance() {
+        let limits = limits(2);
+        let long_ago = Instant::now() - Duration::from_secs(120);
+        {
+            let mut buckets = limits.buckets.lock().unwrap();
+            for n in 0..KEPT as u32 {
+                let address = IpAddr::V4(Ipv4Addr::from(n));
+                // the first one is still waiting on its allowance
+                let left = if n == 0 { 0.0 } else { 2.0 };
+                let at = if n == 0 { Instant::now() } else { long_ago };
+                buckets.insert(address, (left, at));
+            }
+        }
+        assert!(limits.allow(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1))));
+        let buckets = limits.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets.contains_key(&IpAddr::V4(Ipv4Addr::from(0))));
+    }
+
+    #[test]
+    fn writes_responses() {
+        let mut written = vec![];
+        let mut response = Response::error(429, "slow down");
+        response.headers.push(("Retry-After", "30".into()));
+        write_response(&mut written, (response, false)).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "HTTP/1.1 429 Too Many Requests\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Retry-After: 30\r\nContent-Length: 10\r\nConnection: close\r\n\r\nslow down\n"
+        );
+        let mut head = vec![];
+        write_response(&mut head, (Response::new(200, "image/png", "png"), true)).unwrap();
+        assert!(head.ends_with(b"Content-Length: 3\r\nConnection: close\r\n\r\n"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::{error::Error, io};
+
+use image::{imageops, ImageFormat, Rgb, RgbImage};
+use mondrian::{
+    polygon::Shape,
+    render::raster,
+    text::{self, Anchor, Font},
+};
+
+use crate::{
+    generate::{is_stdout, Format, GenerateArgs, Renderer},
+    progress::Bar,
+};
+
+/// below this the thumbnails are too small to tell apart
+const MIN_THUMBNAIL: u32 = 16;
+
+/// `rows` × `columns` compositions made from the same flags, each scaled down to fit across
+/// --width and labeled with its seed underneath, in one image, so the best can be made again
+/// at full size with `--seed`
+pub fn run(args: &GenerateArgs, (rows, columns): (u32, u32)) -> Result<(), Box<dyn Error>> {
+    let output = args.output();
+    if args.format.is_some_and(Format::is_markup) || Format::markup(&output).is_some() {
+        return Err("contact sheets are images; write a .png or .jpg".into());
+    }
+    if args.format == Some(Format::Packed) {
+        return Err("contact sheets are images; --format packed is one panel's buffer".into());
+    }
+    let path = args
+        .font
+        .clone()
+        .or_else(text::find_font)
+        .ok_or("couldn't find a font for the contact sheet's labels; give one with --font")?;
+    let font = Font::load(&path)?;
+    let renderer = Renderer::new(args)?;
+
+    let mut sheet: Option<(RgbImage, Layout)> = None;
+    let count = (rows * columns) as usize;
+    let mut bar = Bar::new("thumbnails", is_stdout(&output));
+    for n in 0..rows * columns {
+        // different pictures from the same flags, each its own seed to pass to --seed
+        let full = args.nth_builder(n as usize, count)?.build()?;
+        if sheet.is_none() {
+            let layout = Layout::new(full.width(), full.height(), rows, columns);
+            if layout.thumbnail.0 < MIN_THUMBNAIL || layout.thumbnail.1 < MIN_THUMBNAIL {
+                return Err(format!(
+                    "{columns} columns across {} pixels leaves thumbnails too small to see; use \
+                     fewer, or a bigger --width",
+                    full.width()
+                )
+                .into());
+            }
+            let (width, height) = layout.size();
+            sheet = Some((
+                RgbImage::from_pixel(width, height, Rgb([255, 255, 255])),
+                layout,
+            ));
+        }
+        let (image, layout) = sheet.as_mut().expect("just made");
+
+        let mut parameters = full
+            .parameters()
+            .resized(layout.thumbnail.0, layout.thumbnail.1);
+        // lines too thin to survive shrinking stay a pixel wide
+        if full.border_width() > 0 {
+            parameters.border_width = parameters.border_width.max(1);
+        }
+        if full.frame_width() > 0 {
+            parameters.frame_width = parameters.frame_width.max(1);
+        }
+        let thumbnail = renderer.render(&parameters.builder().build()?)?;
+        let (x, y) = layout.cell(n);
+        imageops::replace(image, &thumbnail, x as i64, y as i64);
+
+        let label = full.seed().to_string();
+        let (width, height) = (layout.thumbnail.0, layout.label);
+        // small enough for the whole seed to fit across, about 0.6 em a digit
+        let size = (height as f32 / 2.2).min(width as f32 / (0.6 * label.len() as f32 + 1.0));
+        let shapes: Vec<Shape> = text::layout(&label, &font, size, Anchor::Center, width, height)
+            .into_iter()
+            .map(|polygon| Shape {
+                polygon: polygon.translated(x as f32, (y + layout.thumbnail.1) as f32),
+                color: Rgb([0, 0, 0]),
+            })
+            .collect();
+        raster::paint_shapes(image, &shapes);
+        bar.set(n as usize + 1, count);
+    }
+    drop(bar);
+
+    let (image, _) = sheet.expect("there's at least one cell");
+    if is_stdout(&output) {
+        let mut buffer = io::Cursor::new(vec![]);
+        image.write_to(&mut buffer, ImageFormat::Png)?;
+        io::Write::write_all(&mut io::stdout().lock(), buffer.get_ref())?;
+    } else {
+        image.save(&output)?;
+        eprintln!("wrote {}", output.display());
+    }
+    Ok(())
+}
+
+/// where everything goes on the sheet, in pixels
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    columns: u32,
+    rows: u32,
+    /// around and between the cells
+    gutter: u32,
+    thumbnail: (u32, u32),
+    /// the strip under each thumbnail its seed goes in
+    label: u32,
+}
+
+impl Layout {
+    /// cells across `width`, with thumbnails the shape of `width` × `height`
+    fn new(width: u32, height: u32, rows: u32, columns: u32) -> Self {
+        let gutter = (width / columns / 32).max(2);
+        let thumbnail_width = width.saturating_sub(gutter * (columns + 1)) / columns;
+        let thumbnail_height = (thumbnail_width as u64 * height as u64 / width as u64) as u32;
+        Self {
+            columns,
+            rows,
+            gutter,
+            thumbnail: (thumbnail_width, thumbnail_height),
+            label: (thumbnail_height / 6).max(16),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let (width, height) = self.thumbnail;
+        (
+            self.gutter + self.columns * (width + self.gutter),
+            self.gutter + self.rows * (height + self.label),
+        )
+    }
+
+    /// the top left of the `n`th thumbnail, in reading order
+    fn cell(&self, n: u32) -> (u32, u32) {
+        let (row, column) = (n / self.columns, n % self.columns);
+        let (width, height) = self.thumbnail;
+        (
+            self.gutter + column * (width + self.gutter),
+            self.gutter + row * (height + self.label),
+        )
+    }
+}
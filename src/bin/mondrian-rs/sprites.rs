@@ -0,0 +1,141 @@
+//! many small compositions packed into one atlas, with a JSON manifest of where each one is, for
+//! game engines to cut up into procedural textures
+
+use std::{error::Error, fmt::Write as _, fs, path::Path};
+
+use image::{imageops, RgbImage};
+
+use crate::{
+    generate::{is_stdout, Format, GenerateArgs, Renderer},
+    progress::Bar,
+};
+
+/// `columns` × `rows` compositions from the same flags, `width` × `height` each, edge to edge in
+/// reading order in --output, and `--output` with a `.json` extension saying where they are
+pub fn run(
+    args: &GenerateArgs,
+    (columns, rows): (u32, u32),
+    (width, height): (u32, u32),
+) -> Result<(), Box<dyn Error>> {
+    let output = args.output();
+    if is_stdout(&output) {
+        return Err("a sprite sheet has its manifest written next to it; give it a file".into());
+    }
+    if args.format.is_some_and(Format::is_markup) || Format::markup(&output).is_some() {
+        return Err("sprite sheets are images; write a .png or .jpg".into());
+    }
+    if args.format == Some(Format::Packed) {
+        return Err("sprite sheets are images; --format packed is one panel's buffer".into());
+    }
+    let (Some(sheet_width), Some(sheet_height)) =
+        (columns.checked_mul(width), rows.checked_mul(height))
+    else {
+        return Err(
+            format!("{columns}x{rows} cells of {width}x{height} is too big an image").into(),
+        );
+    };
+    let renderer = Renderer::new(args)?;
+
+    let mut sheet = RgbImage::new(sheet_width, sheet_height);
+    let mut sprites = vec![];
+    let count = columns as usize * rows as usize;
+    let mut bar = Bar::new("sprites", false);
+    for n in 0..count {
+        // different pictures from the same flags, each its own seed to pass to --seed
+        let composition = args.nth_builder(n, count)?.size(width, height).build()?;
+        let image = renderer.render(&composition)?;
+        let (column, row) = (n as u32 % columns, n as u32 / columns);
+        let (x, y) = (column * width, row * height);
+        imageops::replace(&mut sheet, &image, x as i64, y as i64);
+        sprites.push(Sprite {
+            column,
+            row,
+            x,
+            y,
+            seed: composition.seed(),
+        });
+        bar.set(n + 1, count);
+    }
+    drop(bar);
+
+    sheet.save(&output)?;
+    eprintln!("wrote {}", output.display());
+    let path = output.with_extension("json");
+    fs::write(&path, manifest(&output, (width, height), &sheet, &sprites))?;
+    eprintln!("wrote {}", path.display());
+    Ok(())
+}
+
+/// where one composition went
+#[derive(Debug, Clone, Copy)]
+struct Sprite {
+    column: u32,
+    row: u32,
+    /// its top left, in pixels
+    x: u32,
+    y: u32,
+    seed: u64,
+}
+
+/// ```text
+/// {"image":"sheet.png","width":512,"height":256,"cell":{"width":128,"height":128},
+///  "sprites":[{"index":0,"column":0,"row":0,"x":0,"y":0,"width":128,"height":128,"seed":7},...]}
+/// ```
+///
+/// the image is named relative to the manifest, which is next to it
+fn manifest(
+    image: &Path,
+    (width, height): (u32, u32),
+    sheet: &RgbImage,
+    sprites: &[Sprite],
+) -> String {
+    let name = image.file_name().unwrap_or_default().to_string_lossy();
+    let mut json = String::new();
+    write!(
+        json,
+        r#"{{"image":"{}","width":{},"height":{},"#,
+        escape(&name),
+        sheet.width(),
+        sheet.height(),
+    )
+    .unwrap();
+    write!(
+        json,
+        r#""cell":{{"width":{width},"height":{height}}},"sprites":["#
+    )
+    .unwrap();
+    for (index, sprite) in sprites.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        let Sprite {
+            column,
+            row,
+            x,
+            y,
+            seed,
+        } = sprite;
+        write!(
+            json,
+            r#"{{"index":{index},"column":{column},"row":{row},"x":{x},"y":{y},"#
+        )
+        .unwrap();
+        write!(json, r#""width":{width},"height":{height},"seed":{seed}}}"#).unwrap();
+    }
+    json.push_str("]}\n");
+    json
+}
+
+/// `text` as the inside of a JSON string
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c < ' ' => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
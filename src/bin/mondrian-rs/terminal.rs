@@ -0,0 +1,123 @@
+//! just enough terminal handling for `explore`: raw mode on the alternate screen, the window
+//! size, and keys and clicks as they happen
+
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// something pressed on the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+    /// shift-tab
+    BackTab,
+    /// the left button, in the cell `column` across and `row` down from the top left, from 0
+    Click {
+        column: u16,
+        row: u16,
+    },
+}
+
+/// the terminal in raw mode, with the cursor hidden on the alternate screen and clicks reported;
+/// put back the way it was when dropped, even on the way out of an error
+pub struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: termios is plain data, and tcgetattr fills it in or fails
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        // SAFETY: raw is a valid termios from tcgetattr
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut stdout = io::stdout().lock();
+        // clicks come back as SGR sequences, which don't run out of columns at 223
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h")?;
+        stdout.flush()?;
+        Ok(Self { original })
+    }
+
+    /// columns and rows
+    pub fn size(&self) -> io::Result<(u16, u16)> {
+        // SAFETY: winsize is plain data, and TIOCGWINSZ fills it in or fails
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((size.ws_col.max(1), size.ws_row.max(1)))
+    }
+
+    /// the next key, or `None` if nothing was pressed for `timeout`
+    pub fn key(&self, timeout: Duration) -> io::Result<Option<Key>> {
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+        // SAFETY: one valid pollfd
+        match unsafe { libc::poll(&mut poll, 1, timeout) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                // a resize interrupts it; the caller redraws on the way round
+                return if err.kind() == io::ErrorKind::Interrupted {
+                    Ok(None)
+                } else {
+                    Err(err)
+                };
+            }
+            0 => return Ok(None),
+            _ => {}
+        }
+        let mut bytes = [0; 16];
+        let read = io::stdin().lock().read(&mut bytes)?;
+        Ok(match &bytes[..read] {
+            [0x1b] => Some(Key::Escape),
+            [0x1b, b'[', b'A', ..] => Some(Key::Up),
+            [0x1b, b'[', b'B', ..] => Some(Key::Down),
+            [0x1b, b'[', b'C', ..] => Some(Key::Right),
+            [0x1b, b'[', b'D', ..] => Some(Key::Left),
+            [0x1b, b'[', b'Z', ..] => Some(Key::BackTab),
+            [0x1b, b'[', b'<', rest @ ..] => click(rest),
+            // ctrl-c doesn't signal in raw mode
+            [0x03, ..] => Some(Key::Escape),
+            [byte, ..] if byte.is_ascii() => Some(Key::Char(*byte as char)),
+            _ => None,
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(b"\x1b[?1006l\x1b[?1000l\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        // SAFETY: original came from tcgetattr
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+/// the rest of a `CSI < button ; column ; row M` mouse report, if it's the left button going down
+fn click(report: &[u8]) -> Option<Key> {
+    let report = std::str::from_utf8(report).ok()?;
+    let report = report.strip_suffix('M')?;
+    let mut fields = report.split(';').map(str::parse::<u16>);
+    match (fields.next()?, fields.next()?, fields.next()?) {
+        (Ok(0), Ok(column), Ok(row)) => Some(Key::Click {
+            column: column.checked_sub(1)?,
+            row: row.checked_sub(1)?,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use mondrian::{
+    render::{self, svg},
+    treemap::{self, Item},
+};
+
+use crate::generate::{self, GenerateArgs};
+
+#[derive(clap::Args, Debug)]
+pub struct TreemapArgs {
+    /// labeled weights, `-` for stdin: `label,weight` lines (or `du` output), or a JSON object or
+    /// array of them (needs the `json` feature)
+    pub input: PathBuf,
+
+    /// write each pane's label in it; only SVG has text, so --output has to end in .svg
+    #[arg(long)]
+    pub labels: bool,
+
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+/// one pane per item, sized by its weight, biggest first
+pub fn run(args: TreemapArgs) -> Result<(), Box<dyn Error>> {
+    let mut items = if generate::is_stdout(&args.input) {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        if text.trim_start().starts_with(['{', '[']) {
+            treemap::parse_json(&text)?
+        } else {
+            treemap::parse_csv(&text)?
+        }
+    } else {
+        treemap::read(&args.input)?
+    };
+    // nothing to show for them, and a pane with no area has nowhere to put a label anyway
+    items.retain(|item| item.weight != 0.0);
+    items.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+    let weights: Vec<f64> = items.iter().map(|item| item.weight).collect();
+    let composition = args.generate.builder()?.treemap(weights).build()?;
+
+    let output = args.generate.output();
+    if output
+        .extension()
+        .is_some_and(|extension| extension == "svg")
+    {
+        let labels: Vec<&str> = if args.labels {
+            items
+                .iter()
+                .map(|item: &Item| item.label.as_str())
+                .collect()
+        } else {
+            vec![]
+        };
+        let document = svg::Document::new(&composition)
+            .labeled(&labels)
+            .styled(args.generate.svg_style);
+        let svg = render::draw(&composition, document);
+        fs::write(output, svg)?;
+        return Ok(());
+    }
+    if args.labels {
+        return Err("--labels needs an .svg --output; raster images don't get text".into());
+    }
+    args.generate.write(&composition)
+}
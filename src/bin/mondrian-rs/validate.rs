@@ -0,0 +1,42 @@
+use std::error::Error;
+
+use rand::Rng;
+
+use crate::{generate::GenerateArgs, progress::Bar};
+
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+    /// how many compositions to build and check; each after the first gets its own seed
+    #[arg(long, default_value_t = 1)]
+    pub count: u64,
+
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+/// builds compositions the way generating would, without rendering them, and checks that their
+/// panes tile the canvas with no gaps or overlaps
+pub fn run(args: ValidateArgs) -> Result<(), Box<dyn Error>> {
+    let mut rng = rand::thread_rng();
+    let mut bar = Bar::new("validating", false);
+    for n in 0..args.count {
+        let mut builder = args.generate.builder()?;
+        let seed = match args.generate.nth_seed(n) {
+            // the first one is exactly what generating with these flags makes
+            _ if n == 0 => None,
+            Some(seed) => Some(seed),
+            None => Some(rng.gen()),
+        };
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        let composition = builder.build()?;
+        composition
+            .validate()
+            .map_err(|err| format!("seed {}: {err}", composition.seed()))?;
+        bar.set(n as usize + 1, args.count as usize);
+    }
+    drop(bar);
+    eprintln!("ok: {} compositions tile their canvas", args.count);
+    Ok(())
+}
@@ -0,0 +1,408 @@
+//! just enough X11 for `--gui` and the screensaver: a window to put pixels and a little text in,
+//! and the mouse and keys that come back from it; works under Wayland through XWayland too
+
+use std::error::Error;
+
+use image::{Rgb, RgbImage};
+use xcb::x;
+
+/// something pressed on the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    Escape,
+    /// shift-tab
+    BackTab,
+    /// any other key, like shift on its own
+    Other,
+}
+
+/// what happened to the window, in window pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    /// the left button went down here
+    Press {
+        x: i16,
+        y: i16,
+    },
+    /// the mouse moved here with the left button down
+    Drag {
+        x: i16,
+        y: i16,
+    },
+    /// the mouse moved here with it up, which only [`fullscreen`](Window::fullscreen) windows
+    /// hear about
+    Motion {
+        x: i16,
+        y: i16,
+    },
+    Release,
+    Resize {
+        width: u16,
+        height: u16,
+    },
+    /// some of it needs drawing again
+    Expose,
+    /// the window manager's close button
+    Close,
+}
+
+/// how pixels are laid out in the server's images: where each channel's byte goes in a 32-bit
+/// pixel, and which end comes first
+struct Layout {
+    shifts: [u32; 3],
+    little_endian: bool,
+}
+
+/// a top-level window with a graphics context and the `fixed` font
+pub struct Window {
+    connection: xcb::Connection,
+    window: x::Window,
+    gc: x::Gcontext,
+    depth: u8,
+    layout: Layout,
+    delete: x::Atom,
+    min_keycode: u8,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<x::Keysym>,
+    size: (u16, u16),
+}
+
+impl Window {
+    /// opens on $DISPLAY; only 24-bit TrueColor screens, which is all of them these days
+    pub fn open(title: &str, width: u16, height: u16) -> Result<Self, Box<dyn Error>> {
+        Self::create(title, Some((width, height)))
+    }
+
+    /// like [`open`](Self::open), but asks the window manager to cover the screen with it, and
+    /// hides the pointer over it
+    pub fn fullscreen(title: &str) -> Result<Self, Box<dyn Error>> {
+        Self::create(title, None)
+    }
+
+    /// a window `size` big, or fullscreen without one
+    fn create(title: &str, size: Option<(u16, u16)>) -> Result<Self, Box<dyn Error>> {
+        let (connection, screen) = xcb::Connection::connect(None)
+            .map_err(|err| format!("couldn't connect to the X server: {err}"))?;
+        let setup = connection.get_setup();
+        let screen = setup
+            .roots()
+            .nth(screen as usize)
+            .ok_or("the X server has no such screen")?;
+        let depth = screen.root_depth();
+        let bits_per_pixel = setup
+            .pixmap_formats()
+            .iter()
+            .find(|format| format.depth() == depth)
+            .map(|format| format.bits_per_pixel());
+        let visual = screen
+            .allowed_depths()
+            .flat_map(|depth| depth.visuals())
+            .find(|visual| visual.visual_id() == screen.root_visual());
+        let layout = match (bits_per_pixel, visual) {
+            (Some(32), Some(visual)) if visual.class() == x::VisualClass::TrueColor => Layout {
+                shifts: [visual.red_mask(), visual.green_mask(), visual.blue_mask()]
+                    .map(u32::trailing_zeros),
+                little_endian: setup.image_byte_order() == x::ImageOrder::LsbFirst,
+            },
+            _ => return Err("drawing in a window needs a 24-bit TrueColor X screen".into()),
+        };
+        let (width, height) = size.unwrap_or((screen.width_in_pixels(), screen.height_in_pixels()));
+        let mut events = x::EventMask::EXPOSURE
+            | x::EventMask::KEY_PRESS
+            | x::EventMask::BUTTON_PRESS
+            | x::EventMask::BUTTON_RELEASE
+            | x::EventMask::BUTTON1_MOTION
+            | x::EventMask::STRUCTURE_NOTIFY;
+        let mut attributes = vec![x::Cw::BackPixel(screen.black_pixel())];
+        if size.is_none() {
+            events |= x::EventMask::POINTER_MOTION;
+            attributes.push(x::Cw::EventMask(events));
+            // a pointer that's nothing but a 1×1 mask with nothing in it
+            let blank: x::Pixmap = connection.generate_id();
+            connection.send_request(&x::CreatePixmap {
+                depth: 1,
+                pid: blank,
+                drawable: x::Drawable::Window(screen.root()),
+                width: 1,
+                height: 1,
+            });
+            let clear: x::Gcontext = connection.generate_id();
+            connection.send_request(&x::CreateGc {
+                cid: clear,
+                drawable: x::Drawable::Pixmap(blank),
+                value_list: &[x::Gc::Foreground(0)],
+            });
+            connection.send_request(&x::PolyFillRectangle {
+                drawable: x::Drawable::Pixmap(blank),
+                gc: clear,
+                rectangles: &[x::Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: 1,
+                    height: 1,
+                }],
+            });
+            let cursor: x::Cursor = connection.generate_id();
+            connection.send_request(&x::CreateCursor {
+                cid: cursor,
+                source: blank,
+                mask: blank,
+                fore_red: 0,
+                fore_green: 0,
+                fore_blue: 0,
+                back_red: 0,
+                back_green: 0,
+                back_blue: 0,
+                x: 0,
+                y: 0,
+            });
+            connection.send_request(&x::FreeGc { gc: clear });
+            connection.send_request(&x::FreePixmap { pixmap: blank });
+            attributes.push(x::Cw::Cursor(cursor));
+        } else {
+            attributes.push(x::Cw::EventMask(events));
+        }
+
+        let window: x::Window = connection.generate_id();
+        connection.send_request(&x::CreateWindow {
+            depth: x::COPY_FROM_PARENT as u8,
+            wid: window,
+            parent: screen.root(),
+            x: 0,
+            y: 0,
+            width,
+            height,
+            border_width: 0,
+            class: x::WindowClass::InputOutput,
+            visual: screen.root_visual(),
+            value_list: &attributes,
+        });
+        let font: x::Font = connection.generate_id();
+        connection.send_request(&x::OpenFont {
+            fid: font,
+            name: b"fixed",
+        });
+        let gc: x::Gcontext = connection.generate_id();
+        connection.send_request(&x::CreateGc {
+            cid: gc,
+            drawable: x::Drawable::Window(window),
+            value_list: &[x::Gc::Font(font), x::Gc::GraphicsExposures(false)],
+        });
+        connection.send_request(&x::ChangeProperty {
+            mode: x::PropMode::Replace,
+            window,
+            property: x::ATOM_WM_NAME,
+            r#type: x::ATOM_STRING,
+            data: title.as_bytes(),
+        });
+
+        // ask to be told about the close button rather than be disconnected by it
+        let atom = |name: &[u8]| {
+            connection.send_request(&x::InternAtom {
+                only_if_exists: false,
+                name,
+            })
+        };
+        let (protocols, delete) = (atom(b"WM_PROTOCOLS"), atom(b"WM_DELETE_WINDOW"));
+        let protocols = connection.wait_for_reply(protocols)?.atom();
+        let delete = connection.wait_for_reply(delete)?.atom();
+        connection.send_request(&x::ChangeProperty {
+            mode: x::PropMode::Replace,
+            window,
+            property: protocols,
+            r#type: x::ATOM_ATOM,
+            data: &[delete],
+        });
+        if size.is_none() {
+            let (state, fullscreen) = (atom(b"_NET_WM_STATE"), atom(b"_NET_WM_STATE_FULLSCREEN"));
+            let state = connection.wait_for_reply(state)?.atom();
+            let fullscreen = connection.wait_for_reply(fullscreen)?.atom();
+            connection.send_request(&x::ChangeProperty {
+                mode: x::PropMode::Replace,
+                window,
+                property: state,
+                r#type: x::ATOM_ATOM,
+                data: &[fullscreen],
+            });
+        }
+
+        let (min_keycode, max_keycode) = (setup.min_keycode(), setup.max_keycode());
+        let mapping = connection.send_request(&x::GetKeyboardMapping {
+            first_keycode: min_keycode,
+            count: max_keycode - min_keycode + 1,
+        });
+        let mapping = connection.wait_for_reply(mapping)?;
+
+        connection.send_request(&x::MapWindow { window });
+        connection.flush()?;
+        Ok(Self {
+            window,
+            gc,
+            depth,
+            layout,
+            delete,
+            min_keycode,
+            keysyms_per_keycode: mapping.keysyms_per_keycode().max(1) as usize,
+            keysyms: mapping.keysyms().to_vec(),
+            size: (width, height),
+            connection,
+        })
+    }
+
+    /// width and height, as of the last [`Event::Resize`]
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// the next event, waiting for one if `wait`; events this doesn't care about are skipped
+    pub fn event(&mut self, wait: bool) -> Result<Option<Event>, Box<dyn Error>> {
+        loop {
+            let event = if wait {
+                self.connection.wait_for_event()?
+            } else {
+                match self.connection.poll_for_event()? {
+                    Some(event) => event,
+                    None => return Ok(None),
+                }
+            };
+            let xcb::Event::X(event) = event else {
+                continue;
+            };
+            let event = match event {
+                x::Event::KeyPress(event) => {
+                    let shift = event.state().contains(x::KeyButMask::SHIFT);
+                    Event::Key(self.key(event.detail(), shift).unwrap_or(Key::Other))
+                }
+                x::Event::ButtonPress(event) if event.detail() == 1 => Event::Press {
+                    x: event.event_x(),
+                    y: event.event_y(),
+                },
+                x::Event::MotionNotify(event) if event.state().contains(x::KeyButMask::BUTTON1) => {
+                    Event::Drag {
+                        x: event.event_x(),
+                        y: event.event_y(),
+                    }
+                }
+                x::Event::MotionNotify(event) => Event::Motion {
+                    x: event.event_x(),
+                    y: event.event_y(),
+                },
+                x::Event::ButtonRelease(event) if event.detail() == 1 => Event::Release,
+                x::Event::ConfigureNotify(event) => {
+                    let size = (event.width(), event.height());
+                    if size == self.size {
+                        continue;
+                    }
+                    self.size = size;
+                    Event::Resize {
+                        width: size.0,
+                        height: size.1,
+                    }
+                }
+                x::Event::Expose(event) if event.count() == 0 => Event::Expose,
+                x::Event::ClientMessage(event) => match event.data() {
+                    x::ClientMessageData::Data32([atom, ..])
+                        if atom == xcb::Xid::resource_id(&self.delete) =>
+                    {
+                        Event::Close
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            return Ok(Some(event));
+        }
+    }
+
+    /// what a keycode types, from the keyboard mapping fetched at the start
+    fn key(&self, keycode: u8, shift: bool) -> Option<Key> {
+        let index = keycode.checked_sub(self.min_keycode)? as usize * self.keysyms_per_keycode;
+        let shifted = (shift && self.keysyms_per_keycode > 1)
+            .then(|| self.keysyms.get(index + 1).copied())
+            .flatten()
+            .filter(|keysym| *keysym != 0);
+        match shifted.or(self.keysyms.get(index).copied())? {
+            0xff1b => Some(Key::Escape),
+            0xff09 => Some(Key::Char('\t')),
+            0xfe20 => Some(Key::BackTab),
+            0xff51 => Some(Key::Left),
+            0xff53 => Some(Key::Right),
+            // latin-1 keysyms are the characters themselves
+            keysym @ 0x20..=0x7e => Some(Key::Char(keysym as u8 as char)),
+            _ => None,
+        }
+    }
+
+    /// draws `image` with its top left corner at the window's, in as many requests as it takes
+    pub fn put(&self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        let Layout {
+            shifts: [red, green, blue],
+            little_endian,
+        } = self.layout;
+        let width = image.width() as usize;
+        // the request's own header takes some of the maximum
+        let max = self.connection.get_maximum_request_length() as usize * 4 - 64;
+        let rows = (max / (width * 4).max(1)).max(1);
+        for (n, chunk) in image.as_raw().chunks(width * 3 * rows).enumerate() {
+            let mut data = Vec::with_capacity(chunk.len() / 3 * 4);
+            for pixel in chunk.chunks_exact(3) {
+                let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(u32::from);
+                let pixel = r << red | g << green | b << blue;
+                data.extend(if little_endian {
+                    pixel.to_le_bytes()
+                } else {
+                    pixel.to_be_bytes()
+                });
+            }
+            self.connection.send_request(&x::PutImage {
+                format: x::ImageFormat::ZPixmap,
+                drawable: x::Drawable::Window(self.window),
+                gc: self.gc,
+                width: width as u16,
+                height: (chunk.len() / (width * 3)) as u16,
+                dst_x: 0,
+                dst_y: (n * rows) as i16,
+                left_pad: 0,
+                depth: self.depth,
+                data: &data,
+            });
+        }
+        Ok(())
+    }
+
+    /// `text` in the `fixed` font with its baseline at `y`, over a box of `background`
+    pub fn text(&self, x: i16, y: i16, text: &str, color: Rgb<u8>, background: Rgb<u8>) {
+        let pixel = |Rgb([r, g, b]): Rgb<u8>| {
+            let [red, green, blue] = self.layout.shifts;
+            (r as u32) << red | (g as u32) << green | (b as u32) << blue
+        };
+        self.connection.send_request(&x::ChangeGc {
+            gc: self.gc,
+            value_list: &[
+                x::Gc::Foreground(pixel(color)),
+                x::Gc::Background(pixel(background)),
+            ],
+        });
+        // it's latin-1, and at most 255 of them
+        let text: Vec<u8> = text
+            .chars()
+            .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+            .take(255)
+            .collect();
+        self.connection.send_request(&x::ImageText8 {
+            drawable: x::Drawable::Window(self.window),
+            gc: self.gc,
+            x,
+            y,
+            string: &text,
+        });
+    }
+
+    pub fn flush(&self) -> Result<(), Box<dyn Error>> {
+        Ok(self.connection.flush()?)
+    }
+}
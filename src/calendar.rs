@@ -0,0 +1,327 @@
+//! a calendar in a pane of its own, for a wallpaper that's of some use: this month's days, or
+//! the whole year's with the ones gone by filled in
+//!
+//! today is the day on the clock on the wall, as [`schedule::utc_offset`] has it, and weeks
+//! start on Monday
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use image::Rgb;
+use rand::{Rng, RngCore};
+
+use crate::{
+    geometry::{Rectangle, SplittableGraphic},
+    oklab,
+    polygon::{Polygon, Shape},
+    schedule,
+    text::{self, Anchor, Font},
+};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = ["M", "T", "W", "T", "F", "S", "S"];
+
+/// how much of the calendar there is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Span {
+    /// the month's days in weeks, under its name
+    #[default]
+    Month,
+    /// a square a day in a strip of weeks, the days gone by in a color a month
+    Year,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Span {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// a day, `2024-05-17`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i64,
+    /// from 1
+    pub month: u32,
+    /// from 1
+    pub day: u32,
+}
+
+impl Date {
+    /// what day it is where the clock on the wall is, by [`schedule::utc_offset`]; the day in
+    /// UTC if the platform won't say
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()) as i64;
+        let offset = schedule::utc_offset().unwrap_or(0) as i64 * 60;
+        Self::from_days((seconds + offset).div_euclid(86_400))
+    }
+
+    /// the day `days` after 1970-01-01
+    pub fn from_days(days: i64) -> Self {
+        let (year, month, day) = text::civil(days);
+        Self { year, month, day }
+    }
+
+    /// how many days after 1970-01-01 it is
+    pub fn days(&self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let year = self.year - (self.month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = (self.month as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// 0 for Monday to 6 for Sunday
+    pub fn weekday(&self) -> usize {
+        // 1970-01-01 was a Thursday
+        (self.days() + 3).rem_euclid(7) as usize
+    }
+
+    /// the first of its month
+    fn first(&self) -> Self {
+        Self { day: 1, ..*self }
+    }
+
+    /// how many days its month has
+    fn month_length(&self) -> u32 {
+        let next = match self.month {
+            12 => Self {
+                year: self.year + 1,
+                month: 1,
+                day: 1,
+            },
+            month => Self {
+                month: month + 1,
+                ..self.first()
+            },
+        };
+        (next.days() - self.first().days()) as u32
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{s:?} isn't a date like 2024-05-17");
+        let mut parts = s.trim().splitn(3, '-');
+        let mut part = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (part()?, part()?, part()?);
+        let date = Self {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        if !(1..=12).contains(&date.month) || !(1..=date.month_length()).contains(&date.day) {
+            return Err(format!("{s} isn't a day there is"));
+        }
+        Ok(date)
+    }
+}
+
+/// what calendar to keep a pane for, and how to write it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    pub span: Span,
+    /// the day it's for; today if unset
+    pub date: Option<Date>,
+    /// a TrueType font file; whichever [`find_font`](text::find_font) finds if unset
+    pub font: Option<PathBuf>,
+}
+
+impl Calendar {
+    pub fn new(span: Span) -> Self {
+        Self {
+            span,
+            date: None,
+            font: None,
+        }
+    }
+}
+
+/// a pane of whole pixels somewhere in `bounds` for a `span` calendar: a month two fifths of
+/// the short side across, or a year three fifths of the width; like
+/// [`qr::reserve`](crate::qr::reserve)'s, panes that would leave a sliver are pushed up to the edge
+pub fn reserve(bounds: &Rectangle, span: Span, rng: &mut dyn RngCore) -> Rectangle {
+    // how tall it is for how wide, and the most room it can take
+    let (aspect, widest) = match span {
+        Span::Month => (8.5 / 7.5, bounds.width.min(bounds.height) * 0.4),
+        Span::Year => (13.0 / 58.0, bounds.width * 0.6),
+    };
+    let width = widest.min(bounds.height * 0.9 / aspect).floor().max(1.0);
+    let height = (width * aspect).floor().max(1.0);
+    let sliver = width.min(height) / 4.0;
+    let place = |start: f32, length: f32, side: f32, rng: &mut dyn RngCore| {
+        let room = (length - side).floor().max(0.0);
+        let offset = rng.gen_range(0.0..=room).round();
+        let offset = if offset < sliver {
+            0.0
+        } else if room - offset < sliver {
+            room
+        } else {
+            offset
+        };
+        start.ceil() + offset
+    };
+    let x = place(bounds.x, bounds.width, width, rng);
+    let y = place(bounds.y, bounds.height, height, rng);
+    Rectangle::new(x, y, width, height)
+}
+
+/// the `span` calendar for `date` filling `pane`, in `ink` on `light`: today picked out in the
+/// first of `accents`, or for a year, each month's days gone by in the next of them
+pub fn draw(
+    span: Span,
+    date: Date,
+    pane: &Rectangle,
+    font: &Font,
+    ink: Rgb<u8>,
+    light: Rgb<u8>,
+    accents: &[Rgb<u8>],
+) -> Vec<Shape> {
+    let accents = if accents.is_empty() {
+        &[ink][..]
+    } else {
+        accents
+    };
+    let mut shapes = vec![];
+    let mut write = |text: &str, size: f32, anchor: Anchor, bounds: &Rectangle, color| {
+        let glyphs = text::layout_in(text, font, size, anchor, bounds);
+        shapes.extend(glyphs.into_iter().map(|polygon| Shape { polygon, color }));
+    };
+    let mut squares = vec![];
+    match span {
+        Span::Month => {
+            // the name, the days of the week, then up to six weeks, with a quarter of a cell
+            // around them
+            let (across, down) = (pane.width / 7.5, pane.height / 8.5);
+            let cell = |column: usize, row: usize| {
+                Rectangle::new(
+                    pane.x + across * (column as f32 + 0.25),
+                    pane.y + down * (row as f32 + 0.25),
+                    across,
+                    down,
+                )
+            };
+            let title = Rectangle {
+                width: across * 7.0,
+                ..cell(0, 0)
+            };
+            let name = format!("{} {}", MONTHS[date.month as usize - 1], date.year);
+            write(&name, down * 0.6, Anchor::Center, &title, ink);
+            for (column, weekday) in WEEKDAYS.iter().enumerate() {
+                write(weekday, down * 0.4, Anchor::Center, &cell(column, 1), ink);
+            }
+            let first = date.first().weekday();
+            for day in 1..=date.month_length() {
+                let slot = first + day as usize - 1;
+                let cell = cell(slot % 7, 2 + slot / 7);
+                let color = if day == date.day {
+                    let margin = across.min(down) * 0.08;
+                    squares.push((inset(&cell, margin), accents[0]));
+                    light
+                } else {
+                    ink
+                };
+                write(&day.to_string(), down * 0.45, Anchor::Center, &cell, color);
+            }
+        }
+        Span::Year => {
+            // a column a week and a row a weekday under the months' names, with two cells
+            // either side
+            let cell = pane.width / 58.0;
+            let left = pane.x + cell * 2.0;
+            let top = pane.y + (pane.height - cell * 9.0) / 2.0;
+            let january = Date {
+                month: 1,
+                day: 1,
+                ..date
+            };
+            let start = january.days();
+            let length = (Date {
+                year: date.year + 1,
+                ..january
+            })
+            .days()
+                - start;
+            let future = oklab::mix(light, ink, 0.12);
+            let first = january.weekday();
+            for offset in 0..length {
+                let day = Date::from_days(start + offset);
+                let slot = first + offset as usize;
+                let x = left + (slot / 7) as f32 * cell;
+                if day.day == 1 {
+                    let size = cell * 1.2;
+                    // back by the margin the text is put in by
+                    let label = Rectangle::new(x - size / 2.0, top, cell * 5.0, cell * 2.0);
+                    let name = &MONTHS[day.month as usize - 1][..3];
+                    write(name, size, Anchor::Left, &label, ink);
+                }
+                let square = Rectangle::new(x, top + (2 + slot % 7) as f32 * cell, cell, cell);
+                let color = match day.cmp(&date) {
+                    Ordering::Less => accents[(day.month as usize - 1) % accents.len()],
+                    Ordering::Equal => ink,
+                    Ordering::Greater => future,
+                };
+                squares.push((inset(&square, cell * 0.12), color));
+            }
+        }
+    }
+    // the squares go under the numbers
+    squares
+        .into_iter()
+        .map(|(square, color)| Shape {
+            polygon: Polygon::from_rectangle(&square),
+            color,
+        })
+        .chain(shapes)
+        .collect()
+}
+
+/// `rectangle` with `by` taken off every side
+fn inset(rectangle: &Rectangle, by: f32) -> Rectangle {
+    Rectangle::new(
+        rectangle.x + by,
+        rectangle.y + by,
+        rectangle.width - 2.0 * by,
+        rectangle.height - 2.0 * by,
+    )
+}
@@ -0,0 +1,785 @@
+use std::{borrow::Cow, error::Error, f32::consts::TAU, fmt, str::FromStr};
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+use rand::Rng;
+
+use crate::oklab::{self, Oklch};
+
+/// how many pixels [`Palette::from_image`] looks at, about; past this, more don't change the
+/// colors, only how long it takes
+const PALETTE_SAMPLES: u64 = 1 << 16;
+
+/// a set of colors and how likely each of them is to be picked for a pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Cow<'static, [Rgb<u8>]>,
+    weights: Cow<'static, [u32]>,
+}
+
+impl Palette {
+    /// white, red, yellow, blue; mostly white
+    pub const CLASSIC: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 255, 255]),
+            Rgb([255, 0, 0]),
+            Rgb([255, 255, 0]),
+            Rgb([0, 0, 255]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1]),
+    };
+
+    /// cream, red, mustard, navy and a little black, like a Bauhaus poster
+    pub const BAUHAUS: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([242, 232, 213]),
+            Rgb([214, 40, 40]),
+            Rgb([244, 180, 0]),
+            Rgb([29, 53, 87]),
+            Rgb([17, 17, 17]),
+        ]),
+        weights: Cow::Borrowed(&[8, 2, 2, 2, 1]),
+    };
+
+    /// white and three grays
+    pub const GRAYSCALE: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 255, 255]),
+            Rgb([204, 204, 204]),
+            Rgb([136, 136, 136]),
+            Rgb([34, 34, 34]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1]),
+    };
+
+    /// the classic colors, washed out
+    pub const PASTEL: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([253, 252, 247]),
+            Rgb([244, 167, 167]),
+            Rgb([251, 231, 161]),
+            Rgb([167, 199, 244]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1]),
+    };
+
+    /// cream, coral, amber and plum, like the light early and late in the day
+    pub const WARM: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 243, 224]),
+            Rgb([240, 110, 80]),
+            Rgb([250, 180, 60]),
+            Rgb([120, 60, 110]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1]),
+    };
+
+    /// dark slate, with deep blue, teal and wine, for after dark
+    pub const NIGHT: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([40, 44, 58]),
+            Rgb([30, 60, 140]),
+            Rgb([20, 110, 120]),
+            Rgb([130, 30, 60]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1]),
+    };
+
+    /// Okabe and Ito's colors, picked to stay apart for every kind of color blindness
+    pub const OKABE_ITO: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 255, 255]),
+            Rgb([230, 159, 0]),
+            Rgb([86, 180, 233]),
+            Rgb([0, 158, 115]),
+            Rgb([240, 228, 66]),
+            Rgb([0, 114, 178]),
+            Rgb([213, 94, 0]),
+            Rgb([204, 121, 167]),
+        ]),
+        weights: Cow::Borrowed(&[12, 2, 2, 1, 1, 1, 1, 1]),
+    };
+
+    /// IBM's color-blind-safe set: blue, purple, magenta, orange, gold
+    pub const IBM: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 255, 255]),
+            Rgb([100, 143, 255]),
+            Rgb([120, 94, 240]),
+            Rgb([220, 38, 127]),
+            Rgb([254, 97, 0]),
+            Rgb([255, 176, 0]),
+        ]),
+        weights: Cow::Borrowed(&[10, 2, 1, 1, 1, 2]),
+    };
+
+    /// Paul Tol's bright scheme, distinct for color-blind viewers too
+    pub const TOL_BRIGHT: Palette = Palette {
+        colors: Cow::Borrowed(&[
+            Rgb([255, 255, 255]),
+            Rgb([68, 119, 170]),
+            Rgb([238, 102, 119]),
+            Rgb([34, 136, 51]),
+            Rgb([204, 187, 68]),
+            Rgb([102, 204, 238]),
+            Rgb([170, 51, 119]),
+        ]),
+        weights: Cow::Borrowed(&[12, 2, 2, 1, 2, 1, 1]),
+    };
+
+    /// every color is equally likely
+    pub fn new(colors: impl Into<Vec<Rgb<u8>>>) -> Self {
+        let colors = colors.into();
+        let weights = vec![1; colors.len()];
+        Self {
+            colors: colors.into(),
+            weights: weights.into(),
+        }
+    }
+
+    /// replaces the weights; they're checked against the colors when the composition is built
+    pub fn with_weights(mut self, weights: impl Into<Vec<u32>>) -> Self {
+        self.weights = weights.into().into();
+        self
+    }
+
+    /// up to `count` colors taken from `image` by median cut (splitting at the average rather than
+    /// the median), each weighted by how many of its pixels it stands for, commonest first; fewer
+    /// if the image doesn't have that many
+    pub fn from_image(image: &RgbImage, count: usize) -> Self {
+        let pixels = image.width() as u64 * image.height() as u64;
+        let step = ((pixels / PALETTE_SAMPLES).max(1) as f64).sqrt().ceil() as usize;
+        let samples: Vec<[u8; 3]> = (0..image.height())
+            .step_by(step)
+            .flat_map(|y| (0..image.width()).step_by(step).map(move |x| (x, y)))
+            .map(|(x, y)| image.get_pixel(x, y).0)
+            .collect();
+        let total = samples.len();
+
+        // how far a box's pixels spread along each channel, all of them added up
+        let spread = |samples: &[[u8; 3]]| {
+            let n = samples.len() as f64;
+            [0, 1, 2].map(|channel| {
+                let (sum, squares) = samples.iter().fold((0.0, 0.0), |(sum, squares), sample| {
+                    let value = sample[channel] as f64;
+                    (sum + value, squares + value * value)
+                });
+                (sum / n, squares - sum * sum / n)
+            })
+        };
+        let mut boxes = vec![samples];
+        while boxes.len() < count {
+            // the box that's furthest from being one color, cut across its most varied channel
+            // at the average, which keeps one odd pixel from splitting a box down the middle
+            let Some((index, channel, mean)) = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, samples)| !samples.is_empty())
+                .map(|(index, samples)| {
+                    let spread = spread(samples);
+                    let channel = (0..3)
+                        .max_by(|&a, &b| spread[a].1.total_cmp(&spread[b].1))
+                        .expect("three channels");
+                    let (mean, error) = spread[channel];
+                    (index, channel, mean, error)
+                })
+                .filter(|&(_, _, _, error)| error > 0.0)
+                .max_by(|a, b| a.3.total_cmp(&b.3))
+                .map(|(index, channel, mean, _)| (index, channel, mean))
+            else {
+                break;
+            };
+            let (lower, upper) = std::mem::take(&mut boxes[index])
+                .into_iter()
+                .partition(|sample| (sample[channel] as f64) < mean);
+            boxes[index] = lower;
+            boxes.push(upper);
+        }
+
+        boxes.sort_by_key(|samples| std::cmp::Reverse(samples.len()));
+        boxes.retain(|samples| !samples.is_empty());
+        let colors: Vec<Rgb<u8>> = boxes
+            .iter()
+            .map(|samples| {
+                let mean = |channel: usize| {
+                    let sum: usize = samples.iter().map(|sample| sample[channel] as usize).sum();
+                    ((sum + samples.len() / 2) / samples.len()) as u8
+                };
+                Rgb([mean(0), mean(1), mean(2)])
+            })
+            .collect();
+        // in thousandths, but nothing that made the cut gets left out entirely
+        let weights: Vec<u32> = boxes
+            .iter()
+            .map(|samples| ((samples.len() * 1000 / total.max(1)) as u32).max(1))
+            .collect();
+        Self::new(colors).with_weights(weights)
+    }
+
+    /// the same palette with every color passed through `tone`, weights and all
+    pub fn toned(&self, tone: Tone) -> Self {
+        let colors: Vec<Rgb<u8>> = self.colors.iter().map(|color| tone.apply(*color)).collect();
+        Self::new(colors).with_weights(self.weights.to_vec())
+    }
+
+    /// the same palette for a dark background, every color passed through [`oklab::dark`],
+    /// weights and all
+    pub fn dark(&self) -> Self {
+        let colors: Vec<Rgb<u8>> = self
+            .colors
+            .iter()
+            .map(|color| oklab::dark(*color))
+            .collect();
+        Self::new(colors).with_weights(self.weights.to_vec())
+    }
+
+    /// the same palette with every color passed through [`oklab::drift`], weights and all
+    pub fn drifted(&self, hue: f32, lightness: f32) -> Self {
+        let colors: Vec<Rgb<u8>> = self
+            .colors
+            .iter()
+            .map(|color| oklab::drift(*color, hue, lightness))
+            .collect();
+        Self::new(colors).with_weights(self.weights.to_vec())
+    }
+
+    /// `t` of the way from this palette to `other`: color by color in OKLab, the shorter one's
+    /// going round again to match the longer, and each weight between its shares of the two
+    pub fn mix(&self, other: &Palette, t: f32) -> Self {
+        if self.colors.is_empty() || other.colors.is_empty() {
+            return self.clone();
+        }
+        let count = self.colors.len().max(other.colors.len());
+        let share = |palette: &Palette, index: usize| {
+            let index = index % palette.colors.len();
+            let total: u32 = palette.weights.iter().sum();
+            palette.weights.get(index).copied().unwrap_or(0) as f32 / total.max(1) as f32
+        };
+        let colors: Vec<Rgb<u8>> = (0..count)
+            .map(|index| {
+                let (from, to) = (
+                    self.colors[index % self.colors.len()],
+                    other.colors[index % other.colors.len()],
+                );
+                oklab::mix(from, to, t)
+            })
+            .collect();
+        // in thousandths, like [`Palette::from_image`]'s
+        let weights: Vec<u32> = (0..count)
+            .map(|index| {
+                let (from, to) = (share(self, index), share(other, index));
+                ((from + (to - from) * t) * 1000.0).round().max(1.0) as u32
+            })
+            .collect();
+        Self::new(colors).with_weights(weights)
+    }
+
+    pub fn colors(&self) -> &[Rgb<u8>] {
+        &self.colors
+    }
+
+    pub fn weights(&self) -> &[u32] {
+        &self.weights
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+/// `{"colors":[{"color":"#ffffff","weight":10},...]}`, the same as a JSON palette file; colors
+/// come back as anything [`parse`] takes, or as bare strings weighing 1
+#[cfg(feature = "serde")]
+impl serde::Serialize for Palette {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let entries: Vec<Entry> = self
+            .colors
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(color, weight)| Entry::Weighted {
+                color: to_hex(*color),
+                weight: *weight,
+            })
+            .collect();
+        let mut palette = serializer.serialize_struct("Palette", 1)?;
+        palette.serialize_field("colors", &entries)?;
+        palette.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Palette {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Colors {
+            colors: Vec<Entry>,
+        }
+
+        let Colors { colors: entries } = Colors::deserialize(deserializer)?;
+        let (mut colors, mut weights) = (vec![], vec![]);
+        for (index, entry) in entries.into_iter().enumerate() {
+            let (text, weight) = match entry {
+                Entry::Bare(text) => (text, 1),
+                Entry::Weighted { color, weight } => (color, weight),
+            };
+            let color = parse(&text)
+                .map_err(|err| serde::de::Error::custom(format!("color {index}: {err}")))?;
+            colors.push(color);
+            weights.push(weight);
+        }
+        Ok(Self::new(colors).with_weights(weights))
+    }
+}
+
+/// one of a serialized [`Palette`]'s colors
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Bare(String),
+    Weighted {
+        color: String,
+        #[serde(default = "one")]
+        weight: u32,
+    },
+}
+
+#[cfg(feature = "serde")]
+fn one() -> u32 {
+    1
+}
+
+/// how [`Harmony::palette`] spaces its colors around the color wheel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Harmony {
+    /// a hue, a darker shade of it, and the hue opposite
+    #[default]
+    Complementary,
+    /// three hues a third of the way round from each other
+    Triadic,
+    /// three neighboring hues, 30° apart
+    Analogous,
+    /// one hue, light, medium and dark
+    Monochrome,
+}
+
+impl Harmony {
+    /// a random base hue and three colors worked out from it, after a pale tint of it to stand in
+    /// for white; weighted like [`Palette::CLASSIC`]. the hues and lightnesses are OKLCH's, so
+    /// the colors look as far apart and as light as they're meant to
+    pub fn palette<R: Rng + ?Sized>(self, rng: &mut R) -> Palette {
+        let hue = rng.gen_range(0.0..360.0);
+        let chroma = rng.gen_range(0.12..0.2);
+        let color =
+            |offset: f32, lightness: f32| Oklch::new(lightness, chroma, hue + offset).to_rgb();
+        let colors = match self {
+            Self::Complementary => [color(0.0, 0.65), color(0.0, 0.45), color(180.0, 0.65)],
+            Self::Triadic => [color(0.0, 0.65), color(120.0, 0.65), color(240.0, 0.65)],
+            Self::Analogous => [color(-30.0, 0.65), color(0.0, 0.65), color(30.0, 0.65)],
+            Self::Monochrome => [color(0.0, 0.85), color(0.0, 0.65), color(0.0, 0.45)],
+        };
+        let neutral = Oklch::new(0.97, 0.015, hue).to_rgb();
+        Palette::new([neutral, colors[0], colors[1], colors[2]]).with_weights([10, 2, 1, 1])
+    }
+}
+
+impl fmt::Display for Harmony {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Harmony {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// a different look for the same fills: each color keeps its lightness, and loses its hue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// a gray as light as the color
+    Grayscale,
+    /// as far along a ramp from the first color (black's place) to the second (white's) as the
+    /// color is light, both taken in OKLab so the middle of the ramp isn't muddy
+    Duotone(Rgb<u8>, Rgb<u8>),
+}
+
+impl Tone {
+    pub fn apply(self, color: Rgb<u8>) -> Rgb<u8> {
+        match self {
+            Self::Grayscale => {
+                let gray = luma(color).round() as u8;
+                Rgb([gray, gray, gray])
+            }
+            Self::Duotone(dark, light) => oklab::mix(dark, light, oklab::lightness(color)),
+        }
+    }
+}
+
+/// how light `color` looks, from 0 to 255, by the Rec. 601 weights
+pub fn luma(color: Rgb<u8>) -> f32 {
+    let Rgb([r, g, b]) = color;
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+}
+
+/// sRGB's transfer function, undone, from 0 to 1
+pub fn to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// [`to_linear`] the other way
+pub fn from_linear(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// `hue` in degrees (any, it wraps), `saturation` and `lightness` from 0 to 1
+fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    Rgb([channel(r), channel(g), channel(b)])
+}
+
+/// formats Rgb(255, 0, 0) as `#ff0000`
+pub fn to_hex(color: Rgb<u8>) -> String {
+    let Rgb([r, g, b]) = color;
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// why [`parse`] couldn't make a color out of some text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorError {
+    /// hex, but not 3 or 6 digits of it
+    Hex(String),
+    /// one of the values inside `rgb(...)` or `hsl(...)`
+    Value {
+        input: String,
+        value: String,
+        expected: &'static str,
+    },
+    /// `rgb(...)` or `hsl(...)` with too few or too many values
+    Count { input: String, count: usize },
+    /// not a color name, hex, or a function we know
+    Unknown(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(input) => write!(
+                f,
+                "{input:?} isn't a hex color; those have 3 or 6 digits, like #f00 or #ff0000"
+            ),
+            Self::Value {
+                input,
+                value,
+                expected,
+            } => write!(f, "{value:?} in {input:?} should be {expected}"),
+            Self::Count { input, count } => write!(
+                f,
+                "{input:?} has {count} values; it takes 3, and optionally an alpha after a `/`"
+            ),
+            Self::Unknown(input) => write!(
+                f,
+                "{input:?} isn't a color; try a name like crimson, #rrggbb, rgb(r, g, b) or hsl(h, s%, l%)"
+            ),
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+/// a color the way CSS writes them: `#f00` or `#ff0000` (the `#` is optional), one of the named
+/// colors like `crimson` or `teal`, `rgb(255, 0, 0)` or `rgb(100% 0% 0%)`, or
+/// `hsl(0deg 100% 50%)`; an alpha is allowed, but ignored, since panes are opaque
+pub fn parse(input: &str) -> Result<Rgb<u8>, ColorError> {
+    let text = input.trim().to_ascii_lowercase();
+    if let Some((function, arguments)) = text.split_once('(') {
+        let Some(arguments) = arguments.strip_suffix(')') else {
+            return Err(ColorError::Unknown(input.to_owned()));
+        };
+        return match function.trim() {
+            "rgb" | "rgba" => function_color(input, arguments, |[r, g, b]| {
+                let channel = |value: &str| {
+                    let byte = match value.strip_suffix('%') {
+                        Some(percent) => percent.parse::<f32>().ok()? * 2.55,
+                        None => value.parse::<f32>().ok()?,
+                    };
+                    Some(byte.round().clamp(0.0, 255.0) as u8)
+                };
+                let expected = "a number from 0 to 255, or a percentage";
+                Ok(Rgb([
+                    channel(r).ok_or((r, expected))?,
+                    channel(g).ok_or((g, expected))?,
+                    channel(b).ok_or((b, expected))?,
+                ]))
+            }),
+            "hsl" | "hsla" => function_color(input, arguments, |[h, s, l]| {
+                let hue = |value: &str| {
+                    let (number, per_turn) = if let Some(degrees) = value.strip_suffix("deg") {
+                        (degrees, 360.0)
+                    } else if let Some(turns) = value.strip_suffix("turn") {
+                        (turns, 1.0)
+                    } else if let Some(radians) = value.strip_suffix("rad") {
+                        (radians, TAU)
+                    } else {
+                        (value, 360.0)
+                    };
+                    Some(number.parse::<f32>().ok()? / per_turn * 360.0)
+                };
+                let percent = |value: &str| {
+                    let number = value.strip_suffix('%').unwrap_or(value);
+                    Some(number.parse::<f32>().ok()?.clamp(0.0, 100.0) / 100.0)
+                };
+                let expected = "a percentage";
+                Ok(from_hsl(
+                    hue(h).ok_or((h, "an angle, like 120 or 120deg or 0.5turn"))?,
+                    percent(s).ok_or((s, expected))?,
+                    percent(l).ok_or((l, expected))?,
+                ))
+            }),
+            _ => Err(ColorError::Unknown(input.to_owned())),
+        };
+    }
+
+    if let Ok(index) = NAMES.binary_search_by_key(&text.as_str(), |(name, _)| name) {
+        return Ok(NAMES[index].1);
+    }
+    let hex = text.strip_prefix('#').unwrap_or(&text);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(match text.starts_with('#') {
+            true => ColorError::Hex(input.to_owned()),
+            false => ColorError::Unknown(input.to_owned()),
+        });
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).expect("checked it's hex");
+    match hex.len() {
+        3 => Ok(Rgb([digit(0) * 17, digit(1) * 17, digit(2) * 17])),
+        6 => Ok(Rgb([
+            digit(0) * 16 + digit(1),
+            digit(2) * 16 + digit(3),
+            digit(4) * 16 + digit(5),
+        ])),
+        _ => Err(ColorError::Hex(input.to_owned())),
+    }
+}
+
+/// the three values of `rgb(...)` or `hsl(...)`, split on commas or spaces, made into a color by
+/// `make`, which says which value was wrong if one was
+fn function_color<'a>(
+    input: &str,
+    arguments: &'a str,
+    make: impl FnOnce([&'a str; 3]) -> Result<Rgb<u8>, (&'a str, &'static str)>,
+) -> Result<Rgb<u8>, ColorError> {
+    // an alpha comes after a `/`, or as a fourth value in the older comma style
+    let (values, alpha) = match arguments.split_once('/') {
+        Some((values, alpha)) => (values, Some(alpha.trim())),
+        None => (arguments, None),
+    };
+    let mut values: Vec<&str> = values
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .collect();
+    let alpha = match (alpha, values.len()) {
+        (None, 4) => values.pop(),
+        _ => alpha,
+    };
+    let [a, b, c] = values[..] else {
+        return Err(ColorError::Count {
+            input: input.to_owned(),
+            count: values.len(),
+        });
+    };
+    if let Some(alpha) = alpha {
+        let number = alpha.strip_suffix('%').unwrap_or(alpha);
+        if number.parse::<f32>().is_err() {
+            return Err(ColorError::Value {
+                input: input.to_owned(),
+                value: alpha.to_owned(),
+                expected: "an alpha, a number or a percentage",
+            });
+        }
+    }
+    make([a, b, c]).map_err(|(value, expected)| ColorError::Value {
+        input: input.to_owned(),
+        value: value.to_owned(),
+        expected,
+    })
+}
+
+/// the CSS named colors, sorted, so [`parse`] can binary search them
+const NAMES: [(&str, Rgb<u8>); 148] = [
+    ("aliceblue", Rgb([240, 248, 255])),
+    ("antiquewhite", Rgb([250, 235, 215])),
+    ("aqua", Rgb([0, 255, 255])),
+    ("aquamarine", Rgb([127, 255, 212])),
+    ("azure", Rgb([240, 255, 255])),
+    ("beige", Rgb([245, 245, 220])),
+    ("bisque", Rgb([255, 228, 196])),
+    ("black", Rgb([0, 0, 0])),
+    ("blanchedalmond", Rgb([255, 235, 205])),
+    ("blue", Rgb([0, 0, 255])),
+    ("blueviolet", Rgb([138, 43, 226])),
+    ("brown", Rgb([165, 42, 42])),
+    ("burlywood", Rgb([222, 184, 135])),
+    ("cadetblue", Rgb([95, 158, 160])),
+    ("chartreuse", Rgb([127, 255, 0])),
+    ("chocolate", Rgb([210, 105, 30])),
+    ("coral", Rgb([255, 127, 80])),
+    ("cornflowerblue", Rgb([100, 149, 237])),
+    ("cornsilk", Rgb([255, 248, 220])),
+    ("crimson", Rgb([220, 20, 60])),
+    ("cyan", Rgb([0, 255, 255])),
+    ("darkblue", Rgb([0, 0, 139])),
+    ("darkcyan", Rgb([0, 139, 139])),
+    ("darkgoldenrod", Rgb([184, 134, 11])),
+    ("darkgray", Rgb([169, 169, 169])),
+    ("darkgreen", Rgb([0, 100, 0])),
+    ("darkgrey", Rgb([169, 169, 169])),
+    ("darkkhaki", Rgb([189, 183, 107])),
+    ("darkmagenta", Rgb([139, 0, 139])),
+    ("darkolivegreen", Rgb([85, 107, 47])),
+    ("darkorange", Rgb([255, 140, 0])),
+    ("darkorchid", Rgb([153, 50, 204])),
+    ("darkred", Rgb([139, 0, 0])),
+    ("darksalmon", Rgb([233, 150, 122])),
+    ("darkseagreen", Rgb([143, 188, 143])),
+    ("darkslateblue", Rgb([72, 61, 139])),
+    ("darkslategray", Rgb([47, 79, 79])),
+    ("darkslategrey", Rgb([47, 79, 79])),
+    ("darkturquoise", Rgb([0, 206, 209])),
+    ("darkviolet", Rgb([148, 0, 211])),
+    ("deeppink", Rgb([255, 20, 147])),
+    ("deepskyblue", Rgb([0, 191, 255])),
+    ("dimgray", Rgb([105, 105, 105])),
+    ("dimgrey", Rgb([105, 105, 105])),
+    ("dodgerblue", Rgb([30, 144, 255])),
+    ("firebrick", Rgb([178, 34, 34])),
+    ("floralwhite", Rgb([255, 250, 240])),
+    ("forestgreen", Rgb([34, 139, 34])),
+    ("fuchsia", Rgb([255, 0, 255])),
+    ("gainsboro", Rgb([220, 220, 220])),
+    ("ghostwhite", Rgb([248, 248, 255])),
+    ("gold", Rgb([255, 215, 0])),
+    ("goldenrod", Rgb([218, 165, 32])),
+    ("gray", Rgb([128, 128, 128])),
+    ("green", Rgb([0, 128, 0])),
+    ("greenyellow", Rgb([173, 255, 47])),
+    ("grey", Rgb([128, 128, 128])),
+    ("honeydew", Rgb([240, 255, 240])),
+    ("hotpink", Rgb([255, 105, 180])),
+    ("indianred", Rgb([205, 92, 92])),
+    ("indigo", Rgb([75, 0, 130])),
+    ("ivory", Rgb([255, 255, 240])),
+    ("khaki", Rgb([240, 230, 140])),
+    ("lavender", Rgb([230, 230, 250])),
+    ("lavenderblush", Rgb([255, 240, 245])),
+    ("lawngreen", Rgb([124, 252, 0])),
+    ("lemonchiffon", Rgb([255, 250, 205])),
+    ("lightblue", Rgb([173, 216, 230])),
+    ("lightcoral", Rgb([240, 128, 128])),
+    ("lightcyan", Rgb([224, 255, 255])),
+    ("lightgoldenrodyellow", Rgb([250, 250, 210])),
+    ("lightgray", Rgb([211, 211, 211])),
+    ("lightgreen", Rgb([144, 238, 144])),
+    ("lightgrey", Rgb([211, 211, 211])),
+    ("lightpink", Rgb([255, 182, 193])),
+    ("lightsalmon", Rgb([255, 160, 122])),
+    ("lightseagreen", Rgb([32, 178, 170])),
+    ("lightskyblue", Rgb([135, 206, 250])),
+    ("lightslategray", Rgb([119, 136, 153])),
+    ("lightslategrey", Rgb([119, 136, 153])),
+    ("lightsteelblue", Rgb([176, 196, 222])),
+    ("lightyellow", Rgb([255, 255, 224])),
+    ("lime", Rgb([0, 255, 0])),
+    ("limegreen", Rgb([50, 205, 50])),
+    ("linen", Rgb([250, 240, 230])),
+    ("magenta", Rgb([255, 0, 255])),
+    ("maroon", Rgb([128, 0, 0])),
+    ("mediumaquamarine", Rgb([102, 205, 170])),
+    ("mediumblue", Rgb([0, 0, 205])),
+    ("mediumorchid", Rgb([186, 85, 211])),
+    ("mediumpurple", Rgb([147, 112, 219])),
+    ("mediumseagreen", Rgb([60, 179, 113])),
+    ("mediumslateblue", Rgb([123, 104, 238])),
+    ("mediumspringgreen", Rgb([0, 250, 154])),
+    ("mediumturquoise", Rgb([72, 209, 204])),
+    ("mediumvioletred", Rgb([199, 21, 133])),
+    ("midnightblue", Rgb([25, 25, 112])),
+    ("mintcream", Rgb([245, 255, 250])),
+    ("mistyrose", Rgb([255, 228, 225])),
+    ("moccasin", Rgb([255, 228, 181])),
+    ("navajowhite", Rgb([255, 222, 173])),
+    ("navy", Rgb([0, 0, 128])),
+    ("oldlace", Rgb([253, 245, 230])),
+    ("olive", Rgb([128, 128, 0])),
+    ("olivedrab", Rgb([107, 142, 35])),
+    ("orange", Rgb([255, 165, 0])),
+    ("orangered", Rgb([255, 69, 0])),
+    ("orchid", Rgb([218, 112, 214])),
+    ("palegoldenrod", Rgb([238, 232, 170])),
+    ("palegreen", Rgb([152, 251, 152])),
+    ("paleturquoise", Rgb([175, 238, 238])),
+    ("palevioletred", Rgb([219, 112, 147])),
+    ("papayawhip", Rgb([255, 239, 213])),
+    ("peachpuff", Rgb([255, 218, 185])),
+    ("peru", Rgb([205, 133, 63])),
+    ("pink", Rgb([255, 192, 203])),
+    ("plum", Rgb([221, 160, 221])),
+    ("powderblue", Rgb([176, 224, 230])),
+    ("purple", Rgb([128, 0, 128])),
+    ("rebeccapurple", Rgb([102, 51, 153])),
+    ("red", Rgb([255, 0, 0])),
+    ("rosybrown", Rgb([188, 143, 143])),
+    ("royalblue", Rgb([65, 105, 225])),
+    ("saddlebrown", Rgb([139, 69, 19])),
+    ("salmon", Rgb([250, 128, 114])),
+    ("sandybrown", Rgb([244, 164, 96])),
+    ("seagreen", Rgb([46, 139, 87])),
+    ("seashell", Rgb([255, 245, 238])),
+    ("sienna", Rgb([160, 82, 45])),
+    ("silver", Rgb([192, 192, 192])),
+    ("skyblue", Rgb([135, 206, 235])),
+    ("slateblue", Rgb([106, 90, 205])),
+    ("slategray", Rgb([112, 128, 144])),
+    ("slategrey", Rgb([112, 128, 144])),
+    ("snow", Rgb([255, 250, 250])),
+    ("springgreen", Rgb([0, 255, 127])),
+    ("steelblue", Rgb([70, 130, 180])),
+    ("tan", Rgb([210, 180, 140])),
+    ("teal", Rgb([0, 128, 128])),
+    ("thistle", Rgb([216, 191, 216])),
+    ("tomato", Rgb([255, 99, 71])),
+    ("turquoise", Rgb([64, 224, 208])),
+    ("violet", Rgb([238, 130, 238])),
+    ("wheat", Rgb([245, 222, 179])),
+    ("white", Rgb([255, 255, 255])),
+    ("whitesmoke", Rgb([245, 245, 245])),
+    ("yellow", Rgb([255, 255, 0])),
+    ("yellowgreen", Rgb([154, 205, 50])),
+];
@@ -0,0 +1,2440 @@
+use std::{
+    cmp::max,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+use image::{ImageFormat, Rgb, RgbImage};
+use rand::{random, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[cfg(feature = "script")]
+use crate::script::{self, Script};
+use crate::{
+    audio::{self, Features},
+    calendar::{self, Calendar, Date},
+    color::{luma, to_hex, Palette},
+    dashboard::{self, Dashboard},
+    detail::{self, SafeArea},
+    edit::{Edit, EditError, History},
+    encode::{self, EncodeError},
+    evolve::Genome,
+    fill::{self, ColorStrategy, FillOptions, FillStrategy, Pattern},
+    geometry::{self, Edge, Orientation, Rectangle, SplitOptions, SplittableGraphic, TilingError},
+    hex,
+    metadata::Parameters,
+    morph,
+    mosaic::{self, Mosaic},
+    noise::NoiseOptions,
+    oklab, penrose,
+    plugin::{self, PluginError},
+    polygon::{self, Point, Polygon, Segment, Shape},
+    qr::{self, Placed, QrCode, QrError},
+    relief, render,
+    score::Score,
+    seed,
+    size::SizePreset,
+    sketch,
+    stats::Stats,
+    strategy::{Builtin, Crossed, SplitStrategy},
+    style::Style,
+    symmetry::{self, Symmetry},
+    text::{self, Card, Font, Text, TextError},
+    timeline,
+    timing::Timings,
+    trace::{self, Level},
+    tree::{Pick, Tree},
+    treemap, truchet,
+    viewport::Viewport,
+    voronoi,
+};
+
+/// how many rows [`Composition::write_png`] renders at once; a 16K-wide strip is about 12 MB
+pub const STRIP_ROWS: u32 = 256;
+
+/// why a [`CompositionBuilder`] refused to build
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// width or height is zero
+    EmptyCanvas,
+    /// the palette has no colors in it
+    EmptyPalette,
+    /// the palette has a different number of weights than colors
+    WeightCount { colors: usize, weights: usize },
+    /// every weight is zero, so nothing can be picked
+    ZeroWeights,
+    /// the matte leaves no room for the composition
+    MatteTooLarge,
+    /// a max aspect ratio under 2 would stop squares from ever splitting
+    MaxAspect(f32),
+    /// fill probability has to be between 0 and 1
+    FillProbability(f32),
+    /// the chance of a diagonal cut has to be between 0 and 1
+    Diagonal(f32),
+    /// the chance of crossing a parent's cut has to be between 0 and 1
+    Cross(f32),
+    /// the chance of a patterned pane has to be between 0 and 1
+    Pattern(f32),
+    /// the chance of a line stopping short has to be between 0 and 1
+    Unfinished(f32),
+    /// the first lines can't be thinner than the deepest ones
+    BorderTaper(f32),
+    /// the noise field's frequency has to be positive, and there has to be 1 to 16 octaves of it
+    Noise { frequency: f32, octaves: u32 },
+    /// 2^levels panes won't fit in the canvas even at a pixel each
+    TooManyLevels { levels: usize, pixels: u64 },
+    /// there isn't enough memory for a tree this deep
+    OutOfMemory { levels: usize },
+    /// a style's cell count has to be at least 1, and no more than there are pixels
+    Cells { cells: usize, pixels: u64 },
+    /// there has to be at least 1 pane, and no more than there are pixels
+    TargetPanes { panes: usize, pixels: u64 },
+    /// a treemap needs at least one weight
+    EmptyTreemap,
+    /// treemap weights have to be positive and finite
+    TreemapWeight { index: usize, weight: f64 },
+    /// a timeline needs at least one value
+    EmptyTimeline,
+    /// timeline values have to be finite
+    TimelineValue { index: usize, value: f64 },
+    /// a mosaic's tolerance has to be a number, and not negative
+    Tolerance(f32),
+    /// the photo for a mosaic couldn't be opened or decoded
+    Image { path: PathBuf, why: String },
+    /// the track to compose to couldn't be opened or decoded
+    Audio { path: PathBuf, why: String },
+    /// one of the edits to make after building didn't apply
+    Edit { edit: Edit, error: EditError },
+    /// the split and color script couldn't be read, or went wrong running
+    Script { path: PathBuf, why: String },
+    /// a split strategy or fill plugin isn't registered
+    Plugin(PluginError),
+    /// the text couldn't be written
+    Text(TextError),
+    /// the QR code couldn't be made, or doesn't fit
+    Qr(QrError),
+    /// the viewport goes past the edge of the canvas
+    Viewport(Viewport),
+    /// the safe area has to be between 0 and 20 percent in from each side
+    SafeArea(f32),
+    /// tileable was asked for with a style that can't draw the canvas edges as half lines: 3d,
+    /// the ones that aren't split into rectangles, or lines-only with unfinished lines
+    Tileable(Style),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyCanvas => write!(f, "width and height must be non-zero"),
+            Self::EmptyPalette => write!(f, "palette must have at least one color"),
+            Self::WeightCount { colors, weights } => write!(
+                f,
+                "palette has {colors} colors but {weights} weights; they must match"
+            ),
+            Self::ZeroWeights => write!(f, "at least one palette weight must be non-zero"),
+            Self::MatteTooLarge => write!(f, "matte is wider than half the canvas"),
+            Self::MaxAspect(max_aspect) => {
+                write!(f, "max aspect ratio must be at least 2, not {max_aspect}")
+            }
+            Self::FillProbability(probability) => {
+                write!(
+                    f,
+                    "fill probability must be between 0 and 1, not {probability}"
+                )
+            }
+            Self::Diagonal(probability) => write!(
+                f,
+                "diagonal probability must be between 0 and 1, not {probability}"
+            ),
+            Self::Cross(probability) => write!(
+                f,
+                "cross probability must be between 0 and 1, not {probability}"
+            ),
+            Self::Pattern(probability) => write!(
+                f,
+                "pattern probability must be between 0 and 1, not {probability}"
+            ),
+            Self::Unfinished(probability) => write!(
+                f,
+                "unfinished probability must be between 0 and 1, not {probability}"
+            ),
+            Self::BorderTaper(taper) => write!(f, "border taper must be at least 1, not {taper}"),
+            Self::Noise { frequency, octaves } => write!(
+                f,
+                "noise needs a frequency above 0 and 1 to 16 octaves, not {frequency} and {octaves}"
+            ),
+            Self::TooManyLevels { levels, pixels } => write!(
+                f,
+                "{levels} levels make 2^{levels} panes, more than the {pixels} pixels there are to put them in"
+            ),
+            Self::OutOfMemory { levels } => write!(f, "not enough memory for {levels} levels"),
+            Self::Cells { cells, pixels } => write!(
+                f,
+                "can't make {cells} cells; it takes at least 1, and at most the {pixels} pixels there are"
+            ),
+            Self::TargetPanes { panes, pixels } => write!(
+                f,
+                "can't make {panes} panes; it takes at least 1, and at most the {pixels} pixels there are"
+            ),
+            Self::EmptyTreemap => write!(f, "a treemap needs at least one weight"),
+            Self::TreemapWeight { index, weight } => write!(
+                f,
+                "treemap weights must be positive, but weight {index} is {weight}"
+            ),
+            Self::EmptyTimeline => write!(f, "a timeline needs at least one value"),
+            Self::TimelineValue { index, value } => write!(
+                f,
+                "timeline values must be finite, but value {index} is {value}"
+            ),
+            Self::Tolerance(tolerance) => {
+                write!(f, "tolerance must be 0 or more, not {tolerance}")
+            }
+            Self::Image { path, why } => write!(f, "couldn't read {}: {why}", path.display()),
+            Self::Audio { path, why } => write!(f, "couldn't read {}: {why}", path.display()),
+            Self::Edit { edit, error } => write!(f, "couldn't make edit {edit}: {error}"),
+            Self::Script { path, why } => write!(f, "script {}: {why}", path.display()),
+            Self::Plugin(err) => write!(f, "{err}"),
+            Self::Text(err) => write!(f, "{err}"),
+            Self::Qr(err) => write!(f, "{err}"),
+            Self::SafeArea(percent) => write!(
+                f,
+                "safe area must be between 0 and 20 percent, not {percent}"
+            ),
+            Self::Viewport(viewport) => {
+                write!(f, "viewport {viewport} goes past the edge of the canvas")
+            }
+            Self::Tileable(Style::LinesOnly) => write!(
+                f,
+                "unfinished lines can't be tileable; they stop short of the edges copies meet at"
+            ),
+            Self::Tileable(style) => write!(
+                f,
+                "{style} compositions can't be tileable; their edges aren't lines to share"
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// the builder under the name embedders tend to look for first; it's the same type
+pub type MondrianBuilder = CompositionBuilder;
+
+/// fluent configuration for a [`Composition`]; nothing is checked until [`build`](Self::build)
+#[derive(Debug, Clone)]
+pub struct CompositionBuilder {
+    width: u32,
+    height: u32,
+    seed: Option<u64>,
+    levels: usize,
+    palette: Palette,
+    weights: Option<Vec<u32>>,
+    border_width: Option<u32>,
+    border_taper: f32,
+    frame_width: Option<u32>,
+    frame_color: Rgb<u8>,
+    line_color: Rgb<u8>,
+    matte: u32,
+    matte_color: Rgb<u8>,
+    antialias: bool,
+    split_options: SplitOptions,
+    fill_options: FillOptions,
+    treemap: Option<Vec<f64>>,
+    timeline: Option<Vec<f64>>,
+    mosaic: Option<Mosaic>,
+    audio: Option<PathBuf>,
+    genome: Option<Genome>,
+    style: Style,
+    cells: Option<usize>,
+    target_panes: Option<usize>,
+    pick: Option<Pick>,
+    dpi: Option<u32>,
+    edits: Vec<Edit>,
+    undone: Vec<Edit>,
+    script: Option<PathBuf>,
+    symmetry: Option<Symmetry>,
+    detail_mask: Option<PathBuf>,
+    keep_clear: Vec<Rectangle>,
+    text: Option<Text>,
+    card: Option<Card>,
+    qr: Option<String>,
+    calendar: Option<Calendar>,
+    dashboard: Option<Dashboard>,
+    tileable: bool,
+    unfinished: f32,
+    shadow: u32,
+    viewport: Option<Viewport>,
+    safe_area: f32,
+}
+
+impl Default for CompositionBuilder {
+    fn default() -> Self {
+        Self {
+            width: 4096,
+            height: 2160,
+            seed: None,
+            levels: 5,
+            palette: Palette::CLASSIC,
+            weights: None,
+            border_width: None,
+            border_taper: 1.0,
+            frame_width: None,
+            frame_color: Rgb([0, 0, 0]),
+            line_color: Rgb([0, 0, 0]),
+            matte: 0,
+            matte_color: Rgb([255, 255, 255]),
+            antialias: true,
+            split_options: SplitOptions::default(),
+            fill_options: FillOptions::default(),
+            treemap: None,
+            timeline: None,
+            mosaic: None,
+            audio: None,
+            genome: None,
+            style: Style::default(),
+            cells: None,
+            target_panes: None,
+            pick: None,
+            dpi: None,
+            edits: vec![],
+            undone: vec![],
+            script: None,
+            symmetry: None,
+            detail_mask: None,
+            keep_clear: vec![],
+            text: None,
+            card: None,
+            qr: None,
+            calendar: None,
+            dashboard: None,
+            tileable: false,
+            unfinished: 0.0,
+            shadow: 0,
+            viewport: None,
+            safe_area: 0.0,
+        }
+    }
+}
+
+impl CompositionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// canvas size in pixels
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// canvas width in pixels, leaving the height as it is
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// canvas height in pixels, leaving the width as it is
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// canvas size (and DPI, for print presets) from a named preset
+    pub fn preset(mut self, preset: SizePreset) -> Self {
+        (self.width, self.height) = preset.dimensions();
+        self.dpi = preset.dpi();
+        self
+    }
+
+    /// physical resolution written into the output file; doesn't change the pixels
+    pub fn dpi(mut self, dpi: u32) -> Self {
+        self.dpi = Some(dpi);
+        self
+    }
+
+    /// same seed and settings always give the same composition; random if unset
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// like [`seed`](Self::seed), but hashes some text into the seed; handy for identicons
+    pub fn seed_from(self, text: &str) -> Self {
+        self.seed(seed::from_text(text))
+    }
+
+    /// split iterations (max 2^n this many squares)
+    pub fn levels(mut self, levels: usize) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// how likely each of the palette's colors is, in place of its own weights, whichever order
+    /// the two are set in; there has to be one per color
+    pub fn weights(mut self, weights: impl Into<Vec<u32>>) -> Self {
+        self.weights = Some(weights.into());
+        self
+    }
+
+    /// line width in pixels; defaults to 1/1000th of the longest side
+    pub fn border_width(mut self, border_width: u32) -> Self {
+        self.border_width = Some(border_width);
+        self
+    }
+
+    /// shorthand for [`border_width`](Self::border_width)
+    pub fn border(self, border_width: u32) -> Self {
+        self.border_width(border_width)
+    }
+
+    /// lines from the first split `taper` times as wide as the deepest ones, which are the border
+    /// width, and the ones between in proportion, so the grid has a hierarchy to it; 1 (the
+    /// default) makes them all the same
+    pub fn border_taper(mut self, taper: f32) -> Self {
+        self.border_taper = taper;
+        self
+    }
+
+    /// width of the line around the whole composition; defaults to the border width, 0 turns it off
+    pub fn frame_width(mut self, frame_width: u32) -> Self {
+        self.frame_width = Some(frame_width);
+        self
+    }
+
+    pub fn frame_color(mut self, frame_color: Rgb<u8>) -> Self {
+        self.frame_color = frame_color;
+        self
+    }
+
+    /// color of the lines between panes, and of the floor 3d blocks stand on; black unless set
+    pub fn line_color(mut self, line_color: Rgb<u8>) -> Self {
+        self.line_color = line_color;
+        self
+    }
+
+    /// margin of `color` between the frame and the edge of the canvas, for print-style framing;
+    /// the canvas stays the same size and the composition shrinks to fit
+    pub fn matte(mut self, matte: u32, color: Rgb<u8>) -> Self {
+        self.matte = matte;
+        self.matte_color = color;
+        self
+    }
+
+    /// smooth edges at sub-pixel positions, on by default; off cuts panes on whole pixels and
+    /// draws every edge hard, which is how images were made before this existed
+    pub fn antialias(mut self, antialias: bool) -> Self {
+        self.antialias = antialias;
+        self.split_options.whole_pixels = !antialias;
+        self
+    }
+
+    /// chance that a pane is also cut corner to corner into two triangles, each with its own
+    /// color, like van Doesburg's counter-compositions; 0 (the default) never does
+    pub fn diagonal(mut self, probability: f32) -> Self {
+        self.split_options.diagonal = probability;
+        self
+    }
+
+    /// chance that a squarish pane is cut across its parent's cut rather than alongside it, so
+    /// there are fewer venetian blinds of parallel lines; 1 never cuts one the same way twice,
+    /// and 0 (the default) leaves the strategy alone. see [`Crossed`]
+    pub fn cross(mut self, probability: f32) -> Self {
+        self.split_options.cross = probability;
+        self
+    }
+
+    /// keep every pane within this aspect ratio (e.g. `3.0` for at most 3:1); must be at least 2
+    pub fn max_aspect(mut self, max_aspect: f32) -> Self {
+        self.split_options.max_aspect = Some(max_aspect);
+        self
+    }
+
+    /// where and which way panes get cut; see [`Builtin`]
+    pub fn split_strategy(mut self, strategy: Builtin) -> Self {
+        self.split_options.strategy = strategy;
+        self
+    }
+
+    /// shorthand for [`split_strategy`](Self::split_strategy)
+    pub fn strategy(self, strategy: Builtin) -> Self {
+        self.split_strategy(strategy)
+    }
+
+    /// what the field [`Builtin::Noise`] cuts by looks like: about `frequency` blobs across the
+    /// long side, with `octaves` layers of finer detail
+    pub fn noise(mut self, frequency: f32, octaves: u32) -> Self {
+        self.split_options.noise = NoiseOptions { frequency, octaves };
+        self
+    }
+
+    /// a split strategy [registered](plugin::register_split) by this name, in place of the
+    /// builtin one
+    pub fn split_plugin(mut self, name: impl Into<String>) -> Self {
+        self.split_options.plugin = Some(name.into());
+        self
+    }
+
+    /// one pane per weight, each with its share of the area, laid out as a squarified
+    /// [treemap](crate::treemap::layout) instead of split at random; levels and the split strategy
+    /// are ignored
+    pub fn treemap(mut self, weights: impl Into<Vec<f64>>) -> Self {
+        self.treemap = Some(weights.into());
+        self
+    }
+
+    /// a strip standing up for each of `values`, left to right, cut finer and colored further along
+    /// the palette the higher it is, the highest `levels` deep; see [`timeline`]. a treemap takes
+    /// precedence
+    pub fn timeline(mut self, values: impl Into<Vec<f64>>) -> Self {
+        self.timeline = Some(values.into());
+        self
+    }
+
+    /// panes cut again wherever the photo under them is busy, up to `levels` deep, and colored to
+    /// match it; see [`mosaic`]. treemaps and timelines take precedence
+    pub fn mosaic(mut self, mosaic: Mosaic) -> Self {
+        self.mosaic = Some(mosaic);
+        self
+    }
+
+    /// panes cut to the song at `path`, time running left to right, the loudest parts `levels`
+    /// deep; see [`audio`](crate::audio). treemaps, timelines and mosaics take precedence
+    pub fn audio(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audio = Some(path.into());
+        self
+    }
+
+    /// panes laid out by `genome`'s cuts instead of split at random; see
+    /// [`evolve`](crate::evolve). levels and the split strategy are ignored, and treemaps,
+    /// timelines and mosaics take precedence
+    pub fn genome(mut self, genome: Genome) -> Self {
+        self.genome = Some(genome);
+        self
+    }
+
+    /// what kind of picture to make; see [`Style`]. treemaps, timelines and mosaics are always
+    /// [`Style::Mondrian`]
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// how many cells the styles made of shapes divide the canvas into, i.e. voronoi cells,
+    /// truchet tiles, hexagons or rhombs (about, so they keep their shape); 2^levels unless set
+    pub fn cells(mut self, cells: usize) -> Self {
+        self.cells = Some(cells);
+        self
+    }
+
+    /// about `panes` panes instead of 2^levels, split one at a time, the largest next unless
+    /// [`pick`](Self::pick) says otherwise, rather than all of them to the same depth. only plain
+    /// Mondrians use it: scripts, symmetry, detail masks, keep-clear zones and QR codes still go
+    /// by levels
+    pub fn target_panes(mut self, panes: usize) -> Self {
+        self.target_panes = Some(panes);
+        self
+    }
+
+    /// split one pane at a time, whichever `pick` says next, until there are as many as
+    /// [`target_panes`](Self::target_panes) asks for, or 2^levels; with [`Pick::Weighted`], the
+    /// leaves end up at all sorts of depths. the same layouts as `target_panes` use it
+    pub fn pick(mut self, pick: Pick) -> Self {
+        self.pick = Some(pick);
+        self
+    }
+
+    /// split and color rules of your own, from the script at `path`; see the `script` module,
+    /// which needs the `script` feature. only [`Style::Mondrian`] uses it, and treemaps and
+    /// mosaics take precedence
+    pub fn script(mut self, path: impl Into<PathBuf>) -> Self {
+        self.script = Some(path.into());
+        self
+    }
+
+    /// lay out one half or quarter and mirror it into the rest; see [`Symmetry`]. only plain
+    /// [`Style::Mondrian`] compositions have it, not treemaps, mosaics or scripts that split
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = Some(symmetry);
+        self
+    }
+
+    /// split as deep as a grayscale image at `path` says, stretched over the canvas: all the
+    /// levels where it's white, fewer where it's darker, none where it's black. like
+    /// [`symmetry`](Self::symmetry), only plain [`Style::Mondrian`] compositions have it, and
+    /// symmetry takes precedence
+    pub fn detail_mask(mut self, path: impl Into<PathBuf>) -> Self {
+        self.detail_mask = Some(path.into());
+        self
+    }
+
+    /// areas of the canvas, in pixels, for no cuts to go through, so they stay calm behind a
+    /// clock or a dock; panes mostly inside them are left the background. what can't be cut
+    /// around them isn't cut, and max aspect can't always be kept to. like
+    /// [`detail_mask`](Self::detail_mask), symmetric and other compositions don't have them
+    pub fn keep_clear(mut self, zones: impl Into<Vec<Rectangle>>) -> Self {
+        self.keep_clear = zones.into();
+        self
+    }
+
+    /// a title and a subtitle for a social-share card, written before the panes are laid out so
+    /// the area behind them is kept clear, like [`keep_clear`](Self::keep_clear)'s zones, and
+    /// left the background; see [`Card`]. it's under [`text`](Self::text)
+    pub fn card(mut self, card: Card) -> Self {
+        self.card = Some(card);
+        self
+    }
+
+    /// words over everything else, e.g. the date on a daily wallpaper; see [`Text`]
+    pub fn text(mut self, text: Text) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// a QR code of `data`, say a link, in a square pane of its own somewhere, in the palette's
+    /// darkest color on its lightest. like [`detail_mask`](Self::detail_mask), only plain
+    /// [`Style::Mondrian`] compositions have it, and symmetry and scripts take precedence
+    pub fn qr(mut self, data: impl Into<String>) -> Self {
+        self.qr = Some(data.into());
+        self
+    }
+
+    /// a month or a year's calendar in a pane of its own somewhere, in the line color on the
+    /// palette's lightest, with today in its next color; see [`calendar`]. it's kept where a
+    /// [`qr`](Self::qr) code would be, and a QR code takes precedence
+    pub fn calendar(mut self, calendar: Calendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// a few small panes over everything, a bar each for how busy the CPU is and how full memory
+    /// and the disk are; see [`dashboard`]
+    pub fn dashboard(mut self, dashboard: Dashboard) -> Self {
+        self.dashboard = Some(dashboard);
+        self
+    }
+
+    /// an image that repeats seamlessly, for a wallpaper or web page background: the edges of the
+    /// canvas are drawn as a split line, half a line on each side, so copies put next to each
+    /// other join up into whole lines. there's no matte, and the frame is always black and as
+    /// wide as the lines. [`Style::Mondrian`], [`Style::LinesOnly`] and [`Style::Sketchy`] have
+    /// it, as do treemaps, timelines and mosaics; building any other style with it, or with
+    /// [`unfinished`](Self::unfinished) lines, is a [`BuildError::Tileable`]
+    pub fn tileable(mut self, tileable: bool) -> Self {
+        self.tileable = tileable;
+        self
+    }
+
+    /// chance that a line running into the edge of the canvas stops short of it instead, like in
+    /// an unfinished study; 0 (the default) never does. only [`Style::LinesOnly`] has it, and
+    /// there's no frame unless one's asked for
+    pub fn unfinished(mut self, probability: f32) -> Self {
+        self.unfinished = probability;
+        self
+    }
+
+    /// soft shadows `radius` pixels across, cast down and to the right by the panes that aren't
+    /// the background onto the ones that are; 0 (the default) for none. 3d compositions have
+    /// their own
+    pub fn shadow(mut self, radius: u32) -> Self {
+        self.shadow = radius;
+        self
+    }
+
+    /// for TVs and signage that crop the edges of the picture: nothing small within `percent` of
+    /// the canvas's width and height of its edges. panes reaching in there are three times that
+    /// or more both ways, and the text, QR code, calendar and stats go inside it; 0 (the default)
+    /// for none. only compositions split the usual way have the panes kept big
+    pub fn safe_area(mut self, percent: f32) -> Self {
+        self.safe_area = percent;
+        self
+    }
+
+    /// draws only `viewport` of the canvas, cut straight out if it's in pixels or blown up to the
+    /// canvas's size if it's in fractions; everything's laid out over the whole canvas either way.
+    /// see [`Viewport`]
+    pub fn viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    /// changes to make to the panes once they're laid out, in order, as if by
+    /// [`Composition::apply`]
+    pub fn edits(mut self, edits: impl Into<Vec<Edit>>) -> Self {
+        self.edits = edits.into();
+        self
+    }
+
+    /// like [`edits`](Self::edits), but with the undone ones too, ready to
+    /// [`redo`](Composition::redo)
+    pub fn history(mut self, history: History) -> Self {
+        self.edits = history.done;
+        self.undone = history.undone;
+        self
+    }
+
+    /// chance that a pane gets a palette color; the others are left as the background
+    pub fn fill_probability(mut self, probability: f32) -> Self {
+        self.fill_options.probability = probability;
+        self
+    }
+
+    /// how strongly big panes are left unfilled; see [`FillOptions::area_bias`]
+    pub fn fill_area_bias(mut self, area_bias: f32) -> Self {
+        self.fill_options.area_bias = area_bias;
+        self
+    }
+
+    /// chance that a pane in one of the palette's colors is striped or checkered with another,
+    /// like the pier-and-ocean studies; 0 (the default) never is. the fill strategy decides, by
+    /// [`FillStrategy::patterns`]
+    pub fn pattern(mut self, probability: f32) -> Self {
+        self.fill_options.pattern = probability;
+        self
+    }
+
+    /// what unfilled panes are left as; white unless set
+    pub fn background(mut self, background: Rgb<u8>) -> Self {
+        self.fill_options.background = background;
+        self
+    }
+
+    pub fn color_strategy(mut self, strategy: ColorStrategy) -> Self {
+        self.fill_options.strategy = strategy;
+        self
+    }
+
+    /// a fill [registered](plugin::register_fill) by this name, in place of the builtin one
+    pub fn fill_plugin(mut self, name: impl Into<String>) -> Self {
+        self.fill_options.plugin = Some(name.into());
+        self
+    }
+
+    /// replaces every split knob at once
+    pub fn split_options(mut self, split_options: SplitOptions) -> Self {
+        self.split_options = split_options;
+        self
+    }
+
+    /// replaces every fill knob at once
+    pub fn fill_options(mut self, fill_options: FillOptions) -> Self {
+        self.fill_options = fill_options;
+        self
+    }
+
+    /// validates the settings, then splits the canvas and picks a color for every pane
+    pub fn build(self) -> Result<Composition, BuildError> {
+        self.build_timed(&mut Timings::new())
+    }
+
+    /// [`build`](Self::build), adding how long splitting (`split`) and coloring (`color`) took
+    /// to `timings`
+    pub fn build_timed(mut self, timings: &mut Timings) -> Result<Composition, BuildError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BuildError::EmptyCanvas);
+        }
+        if !(0.0..=20.0).contains(&self.safe_area) {
+            return Err(BuildError::SafeArea(self.safe_area));
+        }
+        let safe =
+            (self.safe_area > 0.0).then(|| SafeArea::new(self.safe_area, self.width, self.height));
+        if let Some(viewport) = self.viewport {
+            let on = viewport.on(self.width, self.height);
+            // a little over for fractions that don't come out exactly
+            let slack = 1e-3;
+            if on.x + on.width > self.width as f32 + slack
+                || on.y + on.height > self.height as f32 + slack
+            {
+                return Err(BuildError::Viewport(viewport));
+            }
+        }
+        // copies meet at the edges of the canvas, so there's no room for a matte, and the frame
+        // is half of the line between them
+        let tileable = self.tileable;
+        let rectangles = self.treemap.is_some() || self.timeline.is_some() || self.mosaic.is_some();
+        let shared = match self.style {
+            Style::Mondrian | Style::Sketchy => true,
+            Style::LinesOnly => self.unfinished == 0.0,
+            _ => false,
+        };
+        if tileable && !rectangles && !shared {
+            return Err(BuildError::Tileable(self.style));
+        }
+        if tileable {
+            self.matte = 0;
+            self.frame_width = None;
+            self.frame_color = self.line_color;
+        }
+        if let Some(weights) = self.weights.take() {
+            self.palette = self.palette.with_weights(weights);
+        }
+        let colors = self.palette.colors();
+        let weights = self.palette.weights();
+        if colors.is_empty() {
+            return Err(BuildError::EmptyPalette);
+        }
+        if colors.len() != weights.len() {
+            return Err(BuildError::WeightCount {
+                colors: colors.len(),
+                weights: weights.len(),
+            });
+        }
+        if weights.iter().all(|weight| *weight == 0) {
+            return Err(BuildError::ZeroWeights);
+        }
+        let fill_probability = self.fill_options.probability;
+        if !(0.0..=1.0).contains(&fill_probability) {
+            return Err(BuildError::FillProbability(fill_probability));
+        }
+        let pattern = self.fill_options.pattern;
+        if !(0.0..=1.0).contains(&pattern) {
+            return Err(BuildError::Pattern(pattern));
+        }
+        if self.matte.saturating_mul(2) >= self.width.min(self.height) {
+            return Err(BuildError::MatteTooLarge);
+        }
+        let diagonal = self.split_options.diagonal;
+        if !(0.0..=1.0).contains(&diagonal) {
+            return Err(BuildError::Diagonal(diagonal));
+        }
+        let cross = self.split_options.cross;
+        if !(0.0..=1.0).contains(&cross) {
+            return Err(BuildError::Cross(cross));
+        }
+        if !(0.0..=1.0).contains(&self.unfinished) {
+            return Err(BuildError::Unfinished(self.unfinished));
+        }
+        if !(self.border_taper.is_finite() && self.border_taper >= 1.0) {
+            return Err(BuildError::BorderTaper(self.border_taper));
+        }
+        let noise = self.split_options.noise;
+        if !(noise.frequency.is_finite()
+            && noise.frequency > 0.0
+            && (1..=16).contains(&noise.octaves))
+        {
+            return Err(BuildError::Noise {
+                frequency: noise.frequency,
+                octaves: noise.octaves,
+            });
+        }
+        if let Some(max_aspect) = self.split_options.max_aspect {
+            if max_aspect.is_nan() || max_aspect < 2.0 {
+                return Err(BuildError::MaxAspect(max_aspect));
+            }
+        }
+
+        if let Some(weights) = &self.treemap {
+            if weights.is_empty() {
+                return Err(BuildError::EmptyTreemap);
+            }
+            let bad = weights
+                .iter()
+                .position(|weight| !(weight.is_finite() && *weight > 0.0));
+            if let Some(index) = bad {
+                return Err(BuildError::TreemapWeight {
+                    index,
+                    weight: weights[index],
+                });
+            }
+        }
+
+        let timeline = self.timeline.as_ref().filter(|_| self.treemap.is_none());
+        if let Some(values) = timeline {
+            if values.is_empty() {
+                return Err(BuildError::EmptyTimeline);
+            }
+            if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+                return Err(BuildError::TimelineValue {
+                    index,
+                    value: values[index],
+                });
+            }
+        }
+
+        let mosaic = match (&self.treemap, timeline) {
+            (None, None) => self.mosaic,
+            _ => None,
+        };
+        if let Some(mosaic) = &mosaic {
+            if mosaic.tolerance.is_nan() || mosaic.tolerance < 0.0 {
+                return Err(BuildError::Tolerance(mosaic.tolerance));
+            }
+        }
+
+        let inner = |side: u32| (side - 2 * self.matte) as u64;
+        let pixels = inner(self.width) * inner(self.height);
+        let style = match (&self.treemap, timeline, &mosaic) {
+            (None, None, None) => self.style,
+            _ => Style::Mondrian,
+        };
+        let cells = match (style, self.cells) {
+            (style, _) if style.splits() => None,
+            (_, Some(cells)) if cells == 0 || cells as u64 > pixels => {
+                return Err(BuildError::Cells { cells, pixels });
+            }
+            (_, Some(cells)) => Some(cells),
+            (_, None) => None,
+        };
+        let sets_layout =
+            style.splits() && self.treemap.is_none() && timeline.is_none() && mosaic.is_none();
+        let track = self.audio.as_ref().filter(|_| sets_layout);
+        let genome = self
+            .genome
+            .as_ref()
+            .filter(|_| sets_layout && track.is_none());
+        let script_error = |why: String| BuildError::Script {
+            path: self.script.clone().unwrap_or_default(),
+            why,
+        };
+        #[cfg(feature = "script")]
+        let mut script = match &self.script {
+            Some(path) if sets_layout => {
+                Some(Script::load(path).map_err(|err| script_error(err.to_string()))?)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "script")]
+        let defines = |name| script.as_ref().is_some_and(|script| script.defines(name));
+        #[cfg(not(feature = "script"))]
+        if self.script.is_some() && sets_layout {
+            return Err(script_error(
+                "mondrian-rs was built without `--features script`".to_owned(),
+            ));
+        }
+        #[cfg(not(feature = "script"))]
+        let defines = |_: &str| false;
+        let (scripted_splits, scripted_colors) = (
+            defines("should_split") || defines("split_ratio"),
+            defines("pick_color"),
+        );
+
+        // 2^levels panes, or by default, 2^levels cells; mosaics stop wherever the photo is plain,
+        // and scripts wherever they like
+        let by_levels = self.treemap.is_none()
+            && timeline.is_none()
+            && mosaic.is_none()
+            && track.is_none()
+            && genome.is_none()
+            && cells.is_none()
+            && !defines("should_split");
+        let grows = (self.target_panes.is_some() || self.pick.is_some())
+            && by_levels
+            && style.splits()
+            && self.symmetry.is_none()
+            && self.detail_mask.is_none()
+            && self.keep_clear.is_empty()
+            && self.card.is_none()
+            && safe.is_none()
+            && self.qr.is_none()
+            && self.calendar.is_none();
+        if let (true, Some(panes)) = (grows, self.target_panes) {
+            if panes == 0 || panes as u64 > pixels {
+                return Err(BuildError::TargetPanes { panes, pixels });
+            }
+        } else if by_levels && (self.levels >= 64 || pixels >> self.levels == 0) {
+            return Err(BuildError::TooManyLevels {
+                levels: self.levels,
+                pixels,
+            });
+        }
+        let target = grows.then(|| {
+            (
+                self.target_panes.unwrap_or(1 << self.levels),
+                self.pick.unwrap_or_default(),
+            )
+        });
+
+        let seed = self.seed.unwrap_or_else(random);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let _build = trace::span(
+            Level::Info,
+            "build",
+            &[
+                ("seed", &seed),
+                ("levels", &self.levels),
+                ("width", &self.width),
+                ("height", &self.height),
+            ],
+        );
+
+        let root_rectangle = Rectangle::new(
+            self.matte as f32,
+            self.matte as f32,
+            (self.width - 2 * self.matte) as f32,
+            (self.height - 2 * self.matte) as f32,
+        );
+        let mut split_strategy = plugin::split_strategy(&self.split_options, &root_rectangle)
+            .map_err(BuildError::Plugin)?;
+        if self.split_options.cross > 0.0 {
+            split_strategy = Box::new(Crossed::new(split_strategy));
+        }
+        let fill_strategy = plugin::fill_strategy(&self.fill_options, &root_rectangle)
+            .map_err(BuildError::Plugin)?;
+        #[cfg(feature = "script")]
+        if let Some(script) = &mut script {
+            script.set_canvas(&root_rectangle, &self.palette, self.fill_options.background);
+        }
+        let photo = match &mosaic {
+            Some(mosaic) => Some(mosaic::load(&mosaic.path, &root_rectangle).map_err(|err| {
+                BuildError::Image {
+                    path: mosaic.path.clone(),
+                    why: err.to_string(),
+                }
+            })?),
+            None => None,
+        };
+        // the card's placed before the split, for the panes behind it to be kept calm
+        let card = match &self.card {
+            Some(card) => {
+                let path = card
+                    .font
+                    .clone()
+                    .or_else(text::find_font)
+                    .ok_or(BuildError::Text(TextError::NoFont))?;
+                let font = Font::load(&path).map_err(BuildError::Text)?;
+                let canvas = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+                let bounds = safe.map_or(canvas, |safe| safe.inside);
+                let (area, polygons) = text::card(card, &font, &bounds);
+                let card = Card {
+                    font: Some(path),
+                    ..card.clone()
+                };
+                Some((card, area, polygons))
+            }
+            None => None,
+        };
+        let clear: Vec<Rectangle> = self
+            .keep_clear
+            .iter()
+            .copied()
+            .chain(card.as_ref().map(|&(_, area, _)| area))
+            .collect();
+        // for symmetric compositions, the pane each one copies, and how it's flipped
+        let mut sources = None;
+        // the zones kept clear, if the layout kept them
+        let mut zones: &[Rectangle] = &[];
+        // whether there can be a pane kept for a QR code or a calendar
+        let reserves = style.splits()
+            && style != Style::ThreeD
+            && self.treemap.is_none()
+            && timeline.is_none()
+            && mosaic.is_none()
+            && track.is_none()
+            && genome.is_none()
+            && !scripted_splits
+            && self.symmetry.is_none();
+        // the QR code's square, and how big its modules are
+        // kept panes go inside the ring of big ones, if there is one
+        let room = match &safe {
+            Some(safe) => safe.clear_of_ring(&root_rectangle),
+            None => root_rectangle,
+        };
+        let code = match &self.qr {
+            Some(data) if reserves => {
+                let code = QrCode::encode(data.as_bytes()).map_err(BuildError::Qr)?;
+                let (pane, module) = qr::reserve(&room, &code, &mut rng).map_err(BuildError::Qr)?;
+                Some((data, code, pane, module))
+            }
+            _ => None,
+        };
+        let calendar_pane = match &self.calendar {
+            Some(calendar) if reserves && code.is_none() => {
+                Some(calendar::reserve(&room, calendar.span, &mut rng))
+            }
+            _ => None,
+        };
+        let reserved = code.as_ref().map(|&(_, _, pane, _)| pane).or(calendar_pane);
+        let mask = match &self.detail_mask {
+            Some(path)
+                if style.splits()
+                    && self.symmetry.is_none()
+                    && timeline.is_none()
+                    && track.is_none()
+                    && genome.is_none() =>
+            {
+                Some(
+                    detail::load(path, &root_rectangle).map_err(|err| BuildError::Image {
+                        path: path.clone(),
+                        why: err.to_string(),
+                    })?,
+                )
+            }
+            _ => None,
+        };
+        let features = match track {
+            Some(path) => {
+                let track = audio::read(path).map_err(|err| BuildError::Audio {
+                    path: path.clone(),
+                    why: err.to_string(),
+                })?;
+                Some(Features::of(&track))
+            }
+            None => None,
+        };
+        let start = Instant::now();
+        let split = trace::span(Level::Info, "split", &[]);
+        let (tree, levels) = match (&self.treemap, &mosaic, &photo) {
+            (Some(weights), _, _) => {
+                let tree = treemap::layout(root_rectangle, weights, &self.split_options);
+                let levels = tree.max_depth();
+                (tree, levels)
+            }
+            _ if timeline.is_some() => {
+                let values = timeline.expect("only timelines are laid out by one");
+                let tree = timeline::layout(
+                    root_rectangle,
+                    values,
+                    self.levels,
+                    &self.split_options,
+                    &mut rng,
+                );
+                // how deep the highest value goes, which lower ones are a part of
+                (tree, self.levels)
+            }
+            (None, Some(mosaic), Some(photo)) => {
+                let tree = mosaic::layout(
+                    root_rectangle,
+                    photo,
+                    self.levels,
+                    mosaic.tolerance,
+                    &self.split_options,
+                );
+                let levels = tree.max_depth();
+                (tree, levels)
+            }
+            // the other styles only use the tree for the area inside the matte
+            _ if !style.splits() => (Tree::new(root_rectangle), self.levels),
+            _ if features.is_some() => {
+                let features = features.as_ref().expect("only tracks are laid out by one");
+                let tree =
+                    audio::layout(root_rectangle, features, self.levels, &self.split_options);
+                // how deep the loudest parts go, which quieter ones are a part of
+                (tree, self.levels)
+            }
+            _ if genome.is_some() => {
+                let genome = genome.expect("only genomes are laid out by one");
+                let tree = genome.tree(root_rectangle, &self.split_options);
+                let levels = tree.max_depth();
+                (tree, levels)
+            }
+            #[cfg(feature = "script")]
+            _ if scripted_splits => {
+                let script = script.as_ref().expect("only scripts split by script");
+                let tree = script::layout(
+                    script,
+                    root_rectangle,
+                    self.levels,
+                    &*split_strategy,
+                    &self.split_options,
+                    &mut rng,
+                )
+                .map_err(|err| script_error(err.to_string()))?;
+                // --levels is only how deep it could go, but asking for less would change what
+                // random() gives
+                (tree, self.levels)
+            }
+            _ => match (self.symmetry, &mask) {
+                (Some(symmetry), _) => {
+                    let (tree, copies) = symmetry::layout(
+                        root_rectangle,
+                        symmetry,
+                        self.levels,
+                        &*split_strategy,
+                        &self.split_options,
+                        &mut rng,
+                    );
+                    sources = Some(copies);
+                    (tree, self.levels)
+                }
+                (None, mask)
+                    if mask.is_some()
+                        || !clear.is_empty()
+                        || reserved.is_some()
+                        || safe.is_some() =>
+                {
+                    zones = &clear;
+                    let tree = detail::layout(
+                        root_rectangle,
+                        mask.as_ref(),
+                        zones,
+                        reserved,
+                        safe.as_ref(),
+                        self.levels,
+                        &*split_strategy,
+                        &self.split_options,
+                        &mut rng,
+                    );
+                    (tree, self.levels)
+                }
+                (None, _) if target.is_some() => {
+                    let (panes, pick) = target.expect("only targets grow");
+                    let mut tree = Tree::new(root_rectangle);
+                    tree.grow(panes, pick, &*split_strategy, &self.split_options, &mut rng);
+                    // without a target, the pane count comes from --levels, so it has to be kept
+                    // to make them again
+                    let levels = match self.target_panes {
+                        Some(_) => tree.max_depth(),
+                        None => self.levels,
+                    };
+                    (tree, levels)
+                }
+                (None, _) => {
+                    let mut tree = Tree::new(root_rectangle);
+                    tree.split_with(self.levels, &*split_strategy, &self.split_options, &mut rng)
+                        .map_err(|_| BuildError::OutOfMemory {
+                            levels: self.levels,
+                        })?;
+                    (tree, self.levels)
+                }
+            },
+        };
+        drop(split);
+        timings.record("split", start.elapsed());
+
+        let start = Instant::now();
+        let color = trace::span(Level::Info, "color", &[]);
+        let (panes, edges, shapes, segments) = match style {
+            Style::Mondrian | Style::LinesOnly | Style::Sketchy | Style::ThreeD => {
+                let leaves: Vec<Rectangle> = tree.leaves().copied().collect();
+                let mut panes = match &photo {
+                    Some(photo) => {
+                        let colors = mosaic::colors(
+                            &leaves,
+                            &root_rectangle,
+                            photo,
+                            &self.palette,
+                            self.fill_options.background,
+                        );
+                        uncut(leaves, colors)
+                    }
+                    None if timeline.is_some() => {
+                        let values = timeline.expect("only timelines color by one");
+                        let colors = timeline::colors(
+                            &leaves,
+                            &root_rectangle,
+                            values,
+                            &self.palette,
+                            &self.fill_options,
+                            &mut rng,
+                        );
+                        uncut(leaves, colors)
+                    }
+                    None if features.is_some() && !scripted_colors => {
+                        let features = features.as_ref().expect("only tracks color by one");
+                        let colors = audio::colors(
+                            &leaves,
+                            &root_rectangle,
+                            features,
+                            &self.palette,
+                            &self.fill_options,
+                            &mut rng,
+                        );
+                        uncut(leaves, colors)
+                    }
+                    #[cfg(feature = "script")]
+                    None if scripted_colors => {
+                        let script = script.as_ref().expect("only scripts color by script");
+                        script::fill(script, leaves, self.fill_options.background, &mut rng)
+                            .map_err(|err| script_error(err.to_string()))?
+                    }
+                    None => {
+                        let colors = fill_strategy.colors(
+                            &leaves,
+                            &self.palette,
+                            &self.fill_options,
+                            &mut rng,
+                        );
+                        uncut(leaves, colors)
+                    }
+                };
+                // the rng is only touched when there are diagonals, so older seeds still match
+                if self.split_options.diagonal > 0.0 {
+                    for pane in &mut panes {
+                        if !rng.gen_bool(self.split_options.diagonal as f64) {
+                            continue;
+                        }
+                        let rising = rng.gen();
+                        let half = [pane.rectangle.area() / 2.0];
+                        let [color] =
+                            fill::colors(&half, &self.palette, &self.fill_options, &mut rng)[..]
+                        else {
+                            unreachable!("one area, one color");
+                        };
+                        pane.diagonal = Some(Diagonal { rising, color });
+                    }
+                }
+                let rectangles: Vec<Rectangle> = panes.iter().map(|pane| pane.rectangle).collect();
+                let colors: Vec<Rgb<u8>> = panes.iter().map(|pane| pane.color).collect();
+                let patterns = fill_strategy.patterns(
+                    &rectangles,
+                    &colors,
+                    &self.palette,
+                    &self.fill_options,
+                    &mut rng,
+                );
+                for (pane, pattern) in panes.iter_mut().zip(patterns) {
+                    // a diagonal is already a second color
+                    pane.pattern = pattern.filter(|_| pane.diagonal.is_none());
+                }
+                // colored all the same, so a seed's lines are the ones it has in color
+                if style == Style::LinesOnly {
+                    for pane in &mut panes {
+                        pane.color = self.fill_options.background;
+                        pane.pattern = None;
+                        if let Some(diagonal) = &mut pane.diagonal {
+                            diagonal.color = self.fill_options.background;
+                        }
+                    }
+                }
+                // after the diagonals, so the rng goes the same as if there were no zones
+                for pane in &mut panes {
+                    if detail::covered(&pane.rectangle, zones) >= 0.5 {
+                        pane.color = self.fill_options.background;
+                        pane.diagonal = None;
+                        pane.pattern = None;
+                    }
+                }
+                // the QR code's pane is the lightest color for the darkest to go on
+                let light = self
+                    .palette
+                    .colors()
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| luma(*a).total_cmp(&luma(*b)));
+                for pane in &mut panes {
+                    if reserved == Some(pane.rectangle) {
+                        pane.color = light.unwrap_or(self.fill_options.background);
+                        pane.diagonal = None;
+                        pane.pattern = None;
+                    }
+                }
+                // the copies are colored, and cut, like what they copy; the rng was still used
+                // for them, so it's the same whichever pane is kept
+                for (index, &(source, flip)) in sources.iter().flatten().enumerate() {
+                    let mut pane = panes[source].clone();
+                    pane.rectangle = panes[index].rectangle;
+                    if let Some(diagonal) = &mut pane.diagonal {
+                        diagonal.rising ^= flip.x != flip.y;
+                        if flip.y {
+                            (pane.color, diagonal.color) = (diagonal.color, pane.color);
+                        }
+                    }
+                    panes[index] = pane;
+                }
+                let segments = panes.iter().filter_map(Pane::diagonal_segment).collect();
+                let edges = match style {
+                    Style::LinesOnly => {
+                        unfinished(tree.edges(), &root_rectangle, self.unfinished, seed)
+                    }
+                    _ => tree.edges(),
+                };
+                (panes, edges, vec![], segments)
+            }
+            Style::Voronoi | Style::Truchet | Style::Hex | Style::Penrose => {
+                let count = cells.unwrap_or(1 << self.levels);
+                let (polygons, segments) = match style {
+                    Style::Voronoi => voronoi::cells(&root_rectangle, count, &mut rng),
+                    Style::Truchet => {
+                        truchet::tiles(&root_rectangle, count, &self.split_options, &mut rng)
+                    }
+                    Style::Hex => hex::cells(&root_rectangle, count, &mut rng),
+                    _ => penrose::tiles(&root_rectangle, count, &mut rng),
+                };
+                let areas: Vec<f64> = polygons.iter().map(Polygon::area).collect();
+                let colors = fill::colors(&areas, &self.palette, &self.fill_options, &mut rng);
+                let shapes = polygons
+                    .into_iter()
+                    .zip(colors)
+                    .map(|(polygon, color)| Shape { polygon, color })
+                    .collect();
+                (vec![], vec![], shapes, segments)
+            }
+        };
+        drop(color);
+        timings.record("color", start.elapsed());
+
+        let (text, glyphs) = match self.text {
+            Some(text) => {
+                let fields = text::Fields {
+                    seed,
+                    width: self.width,
+                    height: self.height,
+                    levels,
+                    now: SystemTime::now(),
+                };
+                let written = text::expand(&text.text, &fields).map_err(BuildError::Text)?;
+                let size = text.size.unwrap_or(self.height as f32 / 20.0);
+                if !(size.is_finite() && size > 0.0) {
+                    return Err(BuildError::Text(TextError::Size(size)));
+                }
+                let path = text
+                    .font
+                    .clone()
+                    .or_else(text::find_font)
+                    .ok_or(BuildError::Text(TextError::NoFont))?;
+                let font = Font::load(&path).map_err(BuildError::Text)?;
+                let canvas = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+                let bounds = safe.map_or(canvas, |safe| safe.inside);
+                let glyphs = text::layout_in(&written, &font, size, text.anchor, &bounds)
+                    .into_iter()
+                    .map(|polygon| Shape {
+                        polygon,
+                        color: text.color,
+                    })
+                    .collect();
+                // kept as it came out, so making it again (later, say) writes the same thing
+                let text = Text {
+                    text: written.replace('{', "{{").replace('}', "}}"),
+                    font: Some(path),
+                    size: Some(size),
+                    ..text
+                };
+                (Some(text), glyphs)
+            }
+            None => (None, vec![]),
+        };
+        // the card under the text
+        let (card, glyphs) = match card {
+            Some((card, _, polygons)) => {
+                let mut marks: Vec<Shape> = polygons
+                    .into_iter()
+                    .map(|polygon| Shape {
+                        polygon,
+                        color: card.color,
+                    })
+                    .collect();
+                marks.extend(glyphs);
+                (Some(card), marks)
+            }
+            None => (None, glyphs),
+        };
+        // under the text, which is over everything
+        let (calendar, glyphs) = match (self.calendar, calendar_pane) {
+            (Some(calendar), Some(pane)) => {
+                let date = calendar.date.unwrap_or_else(Date::today);
+                let path = calendar
+                    .font
+                    .clone()
+                    .or_else(text::find_font)
+                    .ok_or(BuildError::Text(TextError::NoFont))?;
+                let font = Font::load(&path).map_err(BuildError::Text)?;
+                let (light, accents) = overlay_colors(&self.palette, self.fill_options.background);
+                let mut marks = calendar::draw(
+                    calendar.span,
+                    date,
+                    &pane,
+                    &font,
+                    self.line_color,
+                    light,
+                    &accents,
+                );
+                marks.extend(glyphs);
+                // kept as it came out, so making it again tomorrow shows the same day
+                let calendar = Calendar {
+                    date: Some(date),
+                    font: Some(path),
+                    ..calendar
+                };
+                (Some(calendar), marks)
+            }
+            _ => (None, glyphs),
+        };
+        // under the calendar and the text
+        let (dashboard, glyphs) = match self.dashboard {
+            Some(dashboard) => {
+                let font = match dashboard.legend {
+                    true => {
+                        let path = dashboard
+                            .font
+                            .clone()
+                            .or_else(text::find_font)
+                            .ok_or(BuildError::Text(TextError::NoFont))?;
+                        Some((Font::load(&path).map_err(BuildError::Text)?, path))
+                    }
+                    false => None,
+                };
+                let (light, accents) = overlay_colors(&self.palette, self.fill_options.background);
+                let canvas = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+                let mut marks = dashboard::draw(
+                    &dashboard,
+                    &safe.map_or(canvas, |safe| safe.inside),
+                    font.as_ref().map(|(font, _)| font),
+                    self.line_color,
+                    light,
+                    &accents,
+                );
+                marks.extend(glyphs);
+                let dashboard = Dashboard {
+                    font: font.map(|(_, path)| path),
+                    ..dashboard
+                };
+                (Some(dashboard), marks)
+            }
+            None => (None, glyphs),
+        };
+
+        let qr = code.map(|(data, code, pane, module)| {
+            let quiet = qr::QUIET_ZONE as f32 * module;
+            let dark = self
+                .palette
+                .colors()
+                .iter()
+                .copied()
+                .min_by(|a, b| luma(*a).total_cmp(&luma(*b)));
+            Placed {
+                data: data.clone(),
+                pane,
+                modules: code.runs((pane.x + quiet, pane.y + quiet), module),
+                color: dark.unwrap_or(Rgb([0, 0, 0])),
+            }
+        });
+
+        let border_width = self
+            .border_width
+            .unwrap_or_else(|| max(self.width, self.height).div_euclid(1000));
+        // the ends of unfinished lines would only run into a frame
+        let frame_width = match style {
+            Style::LinesOnly if self.unfinished > 0.0 => self.frame_width.unwrap_or(0),
+            Style::ThreeD => self.frame_width.unwrap_or(0),
+            _ => self.frame_width.unwrap_or(border_width),
+        };
+
+        let mut composition = Composition {
+            width: self.width,
+            height: self.height,
+            seed,
+            levels,
+            border_width,
+            border_taper: self.border_taper,
+            frame_width,
+            frame_color: self.frame_color,
+            line_color: self.line_color,
+            matte: self.matte,
+            matte_color: self.matte_color,
+            antialias: self.antialias,
+            split_options: self.split_options,
+            fill_options: self.fill_options,
+            treemap: self.treemap,
+            timeline: self.timeline,
+            mosaic,
+            audio: self.audio,
+            genome: self.genome,
+            style,
+            cells: self.cells,
+            target_panes: self.target_panes,
+            pick: self.pick,
+            dpi: self.dpi,
+            palette: self.palette,
+            tree,
+            panes,
+            edges,
+            shapes,
+            segments,
+            edits: vec![],
+            undone: vec![],
+            script: self.script.clone(),
+            symmetry: self.symmetry,
+            detail_mask: self.detail_mask.clone(),
+            keep_clear: self.keep_clear.clone(),
+            text,
+            card,
+            glyphs,
+            qr,
+            calendar,
+            dashboard,
+            tileable,
+            unfinished: self.unfinished,
+            shadow: self.shadow,
+            viewport: self.viewport,
+            safe_area: self.safe_area,
+            split_strategy,
+            fill_strategy,
+        };
+        for edit in self.edits {
+            composition
+                .apply(edit)
+                .map_err(|error| BuildError::Edit { edit, error })?;
+        }
+        composition.undone = self.undone;
+        if trace::enabled(Level::Debug) {
+            let depths = composition.tree.leaf_depths();
+            for (pane, depth) in composition.panes.iter().zip(depths) {
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = pane.rectangle;
+                trace::event(
+                    Level::Debug,
+                    "pane",
+                    &[
+                        ("x", &x),
+                        ("y", &y),
+                        ("width", &width),
+                        ("height", &height),
+                        ("depth", &depth),
+                        ("color", &to_hex(pane.color)),
+                    ],
+                );
+            }
+        }
+        Ok(composition)
+    }
+}
+
+/// the palette's lightest color, for the calendar and dashboard to go on, and the rest it uses,
+/// for picking things out in
+fn overlay_colors(palette: &Palette, background: Rgb<u8>) -> (Rgb<u8>, Vec<Rgb<u8>>) {
+    let colors = palette.colors();
+    let light = colors
+        .iter()
+        .copied()
+        .max_by(|a, b| luma(*a).total_cmp(&luma(*b)))
+        .unwrap_or(background);
+    let accents = colors
+        .iter()
+        .zip(palette.weights())
+        .filter(|&(&color, &weight)| weight > 0 && color != light)
+        .map(|(&color, _)| color)
+        .collect();
+    (light, accents)
+}
+
+/// `leaves` as panes of `colors`, none of them cut diagonally
+fn uncut(leaves: Vec<Rectangle>, colors: Vec<Rgb<u8>>) -> Vec<Pane> {
+    leaves
+        .into_iter()
+        .zip(colors)
+        .map(|(rectangle, color)| Pane {
+            rectangle,
+            color,
+            diagonal: None,
+            pattern: None,
+        })
+        .collect()
+}
+
+/// `edges`, with those running into the sides of `bounds` stopping short of them at that end
+/// by `probability`. each edge has an rng of its own, so after edits the same lines still stop
+/// in the same places
+fn unfinished(edges: Vec<Edge>, bounds: &Rectangle, probability: f32, seed: u64) -> Vec<Edge> {
+    if probability == 0.0 {
+        return edges;
+    }
+    edges
+        .into_iter()
+        .map(|mut edge| {
+            let text = format!("{seed}:line:{}:{}:{:?}", edge.x, edge.y, edge.orientation);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed::from_text(&text));
+            let (start, low, high) = match edge.orientation {
+                Orientation::Horizontal => (edge.x, bounds.x, bounds.x + bounds.width),
+                Orientation::Vertical => (edge.y, bounds.y, bounds.y + bounds.height),
+            };
+            let (mut from, mut to) = (start, start + edge.length);
+            // a tenth to a third of the way back, on a whole pixel
+            let short =
+                |rng: &mut ChaCha8Rng| (rng.gen_range(0.1..1.0 / 3.0) * edge.length).round();
+            if from <= low && rng.gen_bool(probability as f64) {
+                from += short(&mut rng);
+            }
+            if to >= high && rng.gen_bool(probability as f64) {
+                to -= short(&mut rng);
+            }
+            match edge.orientation {
+                Orientation::Horizontal => edge.x = from,
+                Orientation::Vertical => edge.y = from,
+            }
+            edge.length = to - from;
+            edge
+        })
+        .collect()
+}
+
+/// a leaf of the split tree and the color it got
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pane {
+    pub rectangle: Rectangle,
+    pub color: Rgb<u8>,
+    /// if it was cut corner to corner too; `color` is then the color of the top half
+    pub diagonal: Option<Diagonal>,
+    /// stripes or squares over `color`, if it has any; never with a diagonal
+    pub pattern: Option<Pattern>,
+}
+
+/// how a [`Pane`] was cut into two triangles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagonal {
+    /// from the bottom left corner to the top right; otherwise from the top left to the bottom
+    /// right
+    pub rising: bool,
+    /// the bottom half's color
+    pub color: Rgb<u8>,
+}
+
+impl Pane {
+    /// the top and bottom triangles, if the pane was cut diagonally
+    pub fn halves(&self) -> Option<[Polygon; 2]> {
+        let diagonal = self.diagonal?;
+        let [top_left, top_right, bottom_right, bottom_left] =
+            Polygon::from_rectangle(&self.rectangle).points[..]
+        else {
+            unreachable!("rectangles have four corners");
+        };
+        Some(if diagonal.rising {
+            [
+                Polygon::new([top_left, top_right, bottom_left]),
+                Polygon::new([top_right, bottom_right, bottom_left]),
+            ]
+        } else {
+            [
+                Polygon::new([top_left, top_right, bottom_right]),
+                Polygon::new([top_left, bottom_right, bottom_left]),
+            ]
+        })
+    }
+
+    /// the line a diagonal cut left, if there was one
+    pub fn diagonal_segment(&self) -> Option<Segment> {
+        let diagonal = self.diagonal?;
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = self.rectangle;
+        let (from, to) = if diagonal.rising {
+            (Point::new(x, y + height), Point::new(x + width, y))
+        } else {
+            (Point::new(x, y), Point::new(x + width, y + height))
+        };
+        Some(Segment { from, to })
+    }
+}
+
+/// a finished layout; render it as many times and in as many formats as you like
+#[derive(Debug)]
+pub struct Composition {
+    width: u32,
+    height: u32,
+    seed: u64,
+    levels: usize,
+    border_width: u32,
+    border_taper: f32,
+    frame_width: u32,
+    frame_color: Rgb<u8>,
+    line_color: Rgb<u8>,
+    matte: u32,
+    matte_color: Rgb<u8>,
+    antialias: bool,
+    split_options: SplitOptions,
+    fill_options: FillOptions,
+    treemap: Option<Vec<f64>>,
+    timeline: Option<Vec<f64>>,
+    mosaic: Option<Mosaic>,
+    audio: Option<PathBuf>,
+    genome: Option<Genome>,
+    style: Style,
+    cells: Option<usize>,
+    target_panes: Option<usize>,
+    pick: Option<Pick>,
+    dpi: Option<u32>,
+    palette: Palette,
+    tree: Tree<Rectangle>,
+    panes: Vec<Pane>,
+    edges: Vec<Edge>,
+    shapes: Vec<Shape>,
+    segments: Vec<Segment>,
+    edits: Vec<Edit>,
+    undone: Vec<Edit>,
+    script: Option<PathBuf>,
+    symmetry: Option<Symmetry>,
+    detail_mask: Option<PathBuf>,
+    keep_clear: Vec<Rectangle>,
+    text: Option<Text>,
+    card: Option<Card>,
+    glyphs: Vec<Shape>,
+    qr: Option<Placed>,
+    calendar: Option<Calendar>,
+    dashboard: Option<Dashboard>,
+    tileable: bool,
+    unfinished: f32,
+    shadow: u32,
+    viewport: Option<Viewport>,
+    safe_area: f32,
+    /// what split edits cut with, and color the new half with
+    split_strategy: Box<dyn SplitStrategy<Rectangle>>,
+    fill_strategy: Box<dyn FillStrategy>,
+}
+
+impl Composition {
+    pub fn builder() -> CompositionBuilder {
+        CompositionBuilder::new()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// the part of the canvas that's drawn, if not all of it; see
+    /// [`CompositionBuilder::viewport`]
+    pub fn viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// [`viewport`](Self::viewport) in pixels, or the whole canvas
+    pub fn viewed(&self) -> Rectangle {
+        match self.viewport {
+            Some(viewport) => viewport.on(self.width, self.height),
+            None => Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32),
+        }
+    }
+
+    /// how wide it's drawn: [`width`](Self::width), unless there's a viewport
+    pub fn output_width(&self) -> u32 {
+        self.output_size().0
+    }
+
+    /// how tall it's drawn: [`height`](Self::height), unless there's a viewport
+    pub fn output_height(&self) -> u32 {
+        self.output_size().1
+    }
+
+    fn output_size(&self) -> (u32, u32) {
+        match self.viewport {
+            Some(viewport) => viewport.output_size(self.width, self.height),
+            None => (self.width, self.height),
+        }
+    }
+
+    /// the seed actually used, even if none was given to the builder
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+
+    /// how wide the deepest lines are, and with no taper all of them
+    pub fn border_width(&self) -> u32 {
+        self.border_width
+    }
+
+    /// how much wider the first split's lines are; see [`CompositionBuilder::border_taper`]
+    pub fn border_taper(&self) -> f32 {
+        self.border_taper
+    }
+
+    /// how far across the panes' shadows are; see [`CompositionBuilder::shadow`]
+    pub fn shadow(&self) -> u32 {
+        self.shadow
+    }
+
+    /// the rings of shadow to paint over the panes, lightest first, before the colored ones are
+    /// painted again over them; see [`relief::shadows`]
+    pub fn shadows(&self) -> Vec<(Rectangle, Rgb<u8>)> {
+        if self.shadow == 0 || self.style == Style::ThreeD {
+            return vec![];
+        }
+        relief::shadows(&self.panes, self.shadow, self.fill_options.background)
+    }
+
+    /// what [`Style::ThreeD`] compositions draw instead of the panes and lines, back to front;
+    /// nothing for the others
+    pub fn blocks(&self) -> Vec<Shape> {
+        if self.style != Style::ThreeD {
+            return vec![];
+        }
+        relief::blocks(
+            &self.tree,
+            &self.panes,
+            self.border_width,
+            self.line_color,
+            self.fill_options.background,
+            self.seed,
+        )
+    }
+
+    /// the outline `edge` is drawn as in [`Style::Sketchy`] compositions, wobbles and all; the
+    /// others draw its [`to_rectangle`](Edge::to_rectangle)
+    pub fn sketched(&self, edge: &Edge) -> Option<Polygon> {
+        (self.style == Style::Sketchy)
+            .then(|| sketch::stroke(edge, self.edge_width(edge), self.seed))
+    }
+
+    /// the paint that goes over the lines in [`Style::Sketchy`] compositions, drawn after them:
+    /// a rectangle for every pane that isn't the background or cut diagonally
+    pub fn spills(&self) -> Vec<(Rectangle, Rgb<u8>)> {
+        if self.style != Style::Sketchy {
+            return vec![];
+        }
+        self.panes
+            .iter()
+            .filter(|pane| pane.color != self.fill_options.background && pane.diagonal.is_none())
+            .map(|pane| {
+                let spill = sketch::spill(&pane.rectangle, self.border_width, self.seed);
+                (spill, pane.color)
+            })
+            .collect()
+    }
+
+    /// how wide `edge` gets drawn: the border width for lines from as deep as the levels go
+    /// (and edits' deeper ones), up to [`border_taper`](Self::border_taper) times that for the
+    /// first split's
+    pub fn edge_width(&self, edge: &Edge) -> u32 {
+        let deepest = self.levels.saturating_sub(1);
+        let shallowness = deepest.saturating_sub(edge.depth) as f32 / deepest.max(1) as f32;
+        let scale = 1.0 + (self.border_taper - 1.0) * shallowness;
+        (self.border_width as f32 * scale).round() as u32
+    }
+
+    pub fn frame_width(&self) -> u32 {
+        self.frame_width
+    }
+
+    pub fn frame_color(&self) -> Rgb<u8> {
+        self.frame_color
+    }
+
+    pub fn line_color(&self) -> Rgb<u8> {
+        self.line_color
+    }
+
+    pub fn matte(&self) -> u32 {
+        self.matte
+    }
+
+    pub fn matte_color(&self) -> Rgb<u8> {
+        self.matte_color
+    }
+
+    pub fn antialias(&self) -> bool {
+        self.antialias
+    }
+
+    pub fn split_options(&self) -> &SplitOptions {
+        &self.split_options
+    }
+
+    pub fn fill_options(&self) -> &FillOptions {
+        &self.fill_options
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// the cell count the builder was given, if any
+    pub fn cells(&self) -> Option<usize> {
+        self.cells
+    }
+
+    /// the pane count the builder was given, if any
+    pub fn target_panes(&self) -> Option<usize> {
+        self.target_panes
+    }
+
+    /// which pane the builder was told to split next, if it was
+    pub fn pick(&self) -> Option<Pick> {
+        self.pick
+    }
+
+    /// the weights the panes were laid out from, if this is a treemap
+    pub fn treemap(&self) -> Option<&[f64]> {
+        self.treemap.as_deref()
+    }
+
+    /// the values the strips were laid out from, if this is a timeline
+    pub fn timeline(&self) -> Option<&[f64]> {
+        self.timeline.as_deref()
+    }
+
+    /// the song the panes were cut to, if any
+    pub fn audio(&self) -> Option<&Path> {
+        self.audio.as_deref()
+    }
+
+    /// the cuts the panes were laid out by, if it was bred
+    pub fn genome(&self) -> Option<&Genome> {
+        self.genome.as_ref()
+    }
+
+    /// the script the builder was given, if any
+    pub fn script(&self) -> Option<&Path> {
+        self.script.as_deref()
+    }
+
+    /// how the panes mirror each other, if the builder asked them to
+    pub fn symmetry(&self) -> Option<Symmetry> {
+        self.symmetry
+    }
+
+    /// the image that said how deep to split where, if the builder was given one
+    pub fn detail_mask(&self) -> Option<&Path> {
+        self.detail_mask.as_deref()
+    }
+
+    /// the areas the builder was asked to keep clear of cuts
+    pub fn keep_clear(&self) -> &[Rectangle] {
+        &self.keep_clear
+    }
+
+    /// the text written over it, if any, as written: templates filled in, font and size decided
+    pub fn text(&self) -> Option<&Text> {
+        self.text.as_ref()
+    }
+
+    /// the card written on it, if any, its font decided
+    pub fn card(&self) -> Option<&Card> {
+        self.card.as_ref()
+    }
+
+    /// the dashboard's and the calendar's squares, then the outlines of their, the card's and the
+    /// text's
+    /// letters, a shape each, painted last
+    pub fn glyphs(&self) -> &[Shape] {
+        &self.glyphs
+    }
+
+    /// the calendar in the pane kept for it, if the builder asked for one and it got one, as it
+    /// was drawn: its day and font decided
+    pub fn calendar(&self) -> Option<&Calendar> {
+        self.calendar.as_ref()
+    }
+
+    /// the system's usage drawn over it, if any, and the legend's font if it has one
+    pub fn dashboard(&self) -> Option<&Dashboard> {
+        self.dashboard.as_ref()
+    }
+
+    /// the QR code and the pane kept for it, if the builder asked for one and it got one
+    pub fn qr(&self) -> Option<&Placed> {
+        self.qr.as_ref()
+    }
+
+    /// whether copies side by side join up; see [`CompositionBuilder::tileable`]
+    pub fn tileable(&self) -> bool {
+        self.tileable
+    }
+
+    /// how far in from each side small things are kept, in percent; see
+    /// [`CompositionBuilder::safe_area`]
+    pub fn safe_area(&self) -> f32 {
+        self.safe_area
+    }
+
+    /// chance a line stops short of the canvas edge; see [`CompositionBuilder::unfinished`]
+    pub fn unfinished(&self) -> f32 {
+        self.unfinished
+    }
+
+    /// the photo the panes were cut and colored to match, if this is a mosaic
+    pub fn mosaic(&self) -> Option<&Mosaic> {
+        self.mosaic.as_ref()
+    }
+
+    pub fn dpi(&self) -> Option<u32> {
+        self.dpi
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn tree(&self) -> &Tree<Rectangle> {
+        &self.tree
+    }
+
+    /// panes in the same order as [`Tree::leaves`]
+    pub fn panes(&self) -> &[Pane] {
+        &self.panes
+    }
+
+    /// where each pane is, without its color; shorthand for the [tree](Self::tree)'s leaves
+    pub fn leaves(&self) -> impl Iterator<Item = &Rectangle> + '_ {
+        self.tree.leaves()
+    }
+
+    /// the lines between panes, each shared line once; the canvas edges aren't included
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// what styles other than [`Style::Mondrian`] cover the canvas with instead of panes
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// the lines between [`shapes`](Self::shapes), each shared line once, like
+    /// [`edges`](Self::edges)
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// what's been changed since the panes were laid out, in order
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// the edits made and undone, to [`CompositionBuilder::history`] later
+    pub fn history(&self) -> History {
+        History {
+            done: self.edits.clone(),
+            undone: self.undone.clone(),
+        }
+    }
+
+    /// takes back the last edit, returning it, or `None` if there's nothing to undo; it's laid
+    /// out again from the parameters without it, so this fails the way building would
+    pub fn undo(&mut self) -> Result<Option<Edit>, BuildError> {
+        let mut parameters = self.parameters();
+        let Some(edit) = parameters.edits.pop() else {
+            return Ok(None);
+        };
+        let mut undone = std::mem::take(&mut self.undone);
+        *self = parameters.builder().build()?;
+        undone.push(edit);
+        self.undone = undone;
+        Ok(Some(edit))
+    }
+
+    /// makes the last undone edit again, returning it, or `None` if there's nothing to redo
+    pub fn redo(&mut self) -> Result<Option<Edit>, EditError> {
+        let Some(edit) = self.undone.pop() else {
+            return Ok(None);
+        };
+        let undone = std::mem::take(&mut self.undone);
+        let result = self.apply(edit);
+        self.undone = undone;
+        if result.is_err() {
+            self.undone.push(edit);
+        }
+        result.map(|()| Some(edit))
+    }
+
+    /// cuts a pane in two the way the builder would have one level further down; the first
+    /// half keeps its color and the second gets a new one
+    pub fn split_node(&mut self, pane: usize) -> Result<(), EditError> {
+        self.apply(Edit::Split(pane))
+    }
+
+    /// joins a pane with whatever it was split from, back into one pane of its color
+    pub fn merge(&mut self, pane: usize) -> Result<(), EditError> {
+        self.apply(Edit::Merge(pane))
+    }
+
+    /// gives a pane a color of its own; it keeps it until it's merged away
+    pub fn set_color(&mut self, pane: usize, color: Rgb<u8>) -> Result<(), EditError> {
+        self.apply(Edit::Color(pane, color))
+    }
+
+    /// makes the edit and records it in [`edits`](Self::edits), so the
+    /// [`parameters`](Self::parameters) make the edited layout again; pane indices are the ones
+    /// from before it, and the panes after a split or merge move along to keep tree order
+    ///
+    /// anything undone can't be redone after
+    pub fn apply(&mut self, edit: Edit) -> Result<(), EditError> {
+        if self.panes.is_empty() {
+            return Err(EditError::NoPanes);
+        }
+        let pane = edit.pane();
+        let panes = self.panes.len();
+        let leaf = self
+            .tree
+            .leaf(pane)
+            .ok_or(EditError::NoSuchPane { pane, panes })?;
+        match edit {
+            Edit::Split(_) => {
+                let node = self.tree.node(leaf);
+                let (rectangle, depth) = (*node.item(), node.depth());
+                // an rng of its own, so the same edits always cut the same way
+                let seed = seed::from_text(&format!("{}:edit:{}", self.seed, self.edits.len()));
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let (left, right) =
+                    self.split_strategy
+                        .split(&rectangle, depth, &self.split_options, &mut rng);
+                if [left, right]
+                    .iter()
+                    .any(|half| half.width < 1.0 || half.height < 1.0)
+                {
+                    return Err(EditError::TooSmall(pane));
+                }
+                let [color] = self.fill_strategy.colors(
+                    &[right],
+                    &self.palette,
+                    &self.fill_options,
+                    &mut rng,
+                )[..] else {
+                    unreachable!("one area, one color");
+                };
+                let first = Pane {
+                    rectangle: left,
+                    color: self.panes[pane].color,
+                    diagonal: None,
+                    pattern: None,
+                };
+                let second = Pane {
+                    rectangle: right,
+                    color: match self.style {
+                        Style::LinesOnly => self.fill_options.background,
+                        _ => color,
+                    },
+                    diagonal: None,
+                    pattern: None,
+                };
+                self.tree.split_node(leaf, (left, right));
+                self.panes.splice(pane..=pane, [first, second]);
+            }
+            Edit::Merge(_) => {
+                let parent = self.tree.parent(leaf).ok_or(EditError::Root)?;
+                let merged = Pane {
+                    rectangle: *self.tree.node(parent).item(),
+                    color: self.panes[pane].color,
+                    diagonal: None,
+                    pattern: None,
+                };
+                let range = self.tree.leaf_range(parent);
+                self.tree.merge(parent);
+                self.panes.splice(range, [merged]);
+            }
+            Edit::Color(_, color) => self.panes[pane].color = color,
+        }
+        self.edges = match self.style {
+            Style::LinesOnly => unfinished(
+                self.tree.edges(),
+                self.tree.item(),
+                self.unfinished,
+                self.seed,
+            ),
+            _ => self.tree.edges(),
+        };
+        self.segments = self
+            .panes
+            .iter()
+            .filter_map(Pane::diagonal_segment)
+            .collect();
+        self.edits.push(edit);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// this composition as it is `t` of the way from `from`, for a frame of a morph: the lines
+    /// `from` has slide over to this one's, the ones only one of them has grow out of or shrink
+    /// into the side of their pane, and the colors are mixed in OKLab. everything else, from the
+    /// line widths to the text, is this one's
+    ///
+    /// at `t` 1, or if either has no splits to go between, it's just this one
+    pub fn morphed_from(mut self, from: &Composition, t: f32) -> Self {
+        if t >= 1.0 || !self.style.splits() || !from.style.splits() {
+            return self;
+        }
+        let bounds = *self.tree.item();
+        let (tree, sources) = morph::layout(&from.tree, &self.tree, bounds, t, &self.split_options);
+        let panes = tree
+            .leaves()
+            .zip(sources)
+            .map(|(rectangle, (before, after))| {
+                let (before, after) = (
+                    before.map(|pane| &from.panes[pane]),
+                    after.map(|pane| &self.panes[pane]),
+                );
+                let color = match (before, after) {
+                    (Some(before), Some(after)) => oklab::mix(before.color, after.color, t),
+                    _ => {
+                        before
+                            .or(after)
+                            .expect("a pane from one or the other")
+                            .color
+                    }
+                };
+                // halves and stripes can't be in between, so they're whichever's nearer
+                let nearer = if t < 0.5 {
+                    before.or(after)
+                } else {
+                    after.or(before)
+                };
+                let nearer = nearer.expect("a pane from one or the other");
+                Pane {
+                    rectangle: *rectangle,
+                    color,
+                    diagonal: nearer.diagonal,
+                    pattern: nearer.pattern,
+                }
+            })
+            .collect();
+        self.relaid(tree, panes);
+        self
+    }
+
+    /// `tree` and `panes` in place of this composition's own, with the lines between them worked
+    /// out again; for the layouts of [`morphed_from`](Self::morphed_from) and
+    /// [`Zoom`](crate::zoom::Zoom)
+    pub(crate) fn relaid(&mut self, tree: Tree<Rectangle>, panes: Vec<Pane>) {
+        self.tree = tree;
+        self.panes = panes;
+        self.edges = match self.style {
+            Style::LinesOnly => unfinished(
+                self.tree.edges(),
+                self.tree.item(),
+                self.unfinished,
+                self.seed,
+            ),
+            _ => self.tree.edges(),
+        };
+        self.segments = self
+            .panes
+            .iter()
+            .filter_map(Pane::diagonal_segment)
+            .collect();
+    }
+
+    /// what split edits cut with
+    pub(crate) fn split_strategy(&self) -> &dyn SplitStrategy<Rectangle> {
+        &*self.split_strategy
+    }
+
+    /// what split edits color the new half with
+    pub(crate) fn fill_strategy(&self) -> &dyn FillStrategy {
+        &*self.fill_strategy
+    }
+
+    /// the four bands of frame just inside the matte: top, bottom, left, right. tileable ones are
+    /// half as wide, the halves of the lines between copies
+    pub fn frame(&self) -> [Rectangle; 4] {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = *self.tree.item();
+        let frame = if self.tileable {
+            self.frame_width as f32 / 2.0
+        } else {
+            self.frame_width as f32
+        };
+        let frame = frame.min(width).min(height);
+        [
+            Rectangle::new(x, y, width, frame),
+            Rectangle::new(x, y + height - frame, width, frame),
+            Rectangle::new(x, y, frame, height),
+            Rectangle::new(x + width - frame, y, frame, height),
+        ]
+    }
+
+    /// checks the panes cover the area inside the matte exactly once; the split arithmetic
+    /// should make that always true, so this is for anything that depends on it
+    pub fn validate(&self) -> Result<(), TilingError> {
+        if !self.shapes.is_empty() {
+            let polygons: Vec<Polygon> = self.shapes.iter().map(|s| s.polygon.clone()).collect();
+            return polygon::check_cover(self.tree.item(), &polygons);
+        }
+        let tiles: Vec<Rectangle> = self.panes.iter().map(|pane| pane.rectangle).collect();
+        geometry::check_tiling(self.tree.item(), &tiles)
+    }
+
+    /// pane counts, sizes and color coverage; see [`Stats`]
+    pub fn stats(&self) -> Stats {
+        Stats::of(self)
+    }
+
+    /// how it does on a few rules of thumb for balance and spread; see [`Score`]
+    pub fn score(&self) -> Score {
+        Score::of(self)
+    }
+
+    pub fn render_rgb(&self) -> RgbImage {
+        render::draw(self, render::raster::Canvas::new(self, 1))
+    }
+
+    /// an SVG drawing this; see [`render::svg::Document`]
+    pub fn render_svg(&self) -> String {
+        render::draw(self, render::svg::Document::new(self))
+    }
+
+    /// a standalone page drawing this with `div`s; see [`render::html::Page`]
+    pub fn render_html(&self) -> String {
+        render::draw(self, render::html::Page::new(self))
+    }
+
+    /// a one-page PDF; see [`render::pdf`]
+    pub fn render_pdf(&self) -> String {
+        render::draw(self, render::pdf::Document::new(self))
+    }
+
+    /// a `tikzpicture` `width` centimeters wide; see [`render::tikz::Picture`]
+    pub fn render_tikz(&self, width: f32) -> String {
+        render::draw(self, render::tikz::Picture::new(self, width))
+    }
+
+    /// what to embed in output files so this can be made again
+    pub fn parameters(&self) -> Parameters {
+        Parameters::of(self)
+    }
+
+    /// renders and writes to `path`, picking the format from the extension; PNGs and JPEGs get
+    /// the [`parameters`](Self::parameters) embedded
+    ///
+    /// PNGs are rendered and written a strip at a time, so they can be any size
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EncodeError> {
+        self.save_with(path, |_, _| {})
+    }
+
+    /// [`save`](Self::save), calling `progress` with how far it's got and how far there is to
+    /// go: rows for PNGs, marks painted (see [`raster::render_with`](render::raster::render_with))
+    /// for the rest
+    pub fn save_with(
+        &self,
+        path: impl AsRef<Path>,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<(), EncodeError> {
+        let path = path.as_ref();
+        if ImageFormat::from_path(path).ok() != Some(ImageFormat::Png) {
+            let image = render::raster::render_with(self, 1, progress);
+            return encode::save(&image, &self.parameters(), path);
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_png_with(&mut file, progress)?;
+        Ok(file.flush()?)
+    }
+
+    /// streams a PNG to `writer`, never holding more than [`STRIP_ROWS`] rows in memory
+    pub fn write_png(&self, writer: impl Write) -> Result<(), EncodeError> {
+        self.write_png_with(writer, |_, _| {})
+    }
+
+    /// [`write_png`](Self::write_png), calling `progress` with how many rows are done, and of
+    /// how many, after each strip
+    pub fn write_png_with(
+        &self,
+        writer: impl Write,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), EncodeError> {
+        let height = self.output_height() as usize;
+        let mut done = 0;
+        let strips = render::raster::strips(self, STRIP_ROWS).inspect(|strip| {
+            done += strip.height() as usize;
+            progress(done, height);
+        });
+        let (width, height) = self.output_size();
+        encode::write_png_strips(width, height, &self.parameters(), strips, writer)
+    }
+
+    /// the split tree as JSON, with each leaf carrying its color
+    pub fn tree_json(&self) -> String {
+        render::json::render(self)
+    }
+}
@@ -0,0 +1,83 @@
+//! how an image looks to someone with a color vision deficiency, to check a palette still reads
+
+use std::{fmt, str::FromStr};
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+
+use crate::color::{from_linear, to_linear};
+
+/// the kinds of dichromacy, each missing one of the three cone types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Deficiency {
+    /// no red cones; reds look dark and muddy, and close to greens
+    Protanopia,
+    /// no green cones, the most common; reds and greens run together
+    Deuteranopia,
+    /// no blue cones, rare; blues look green and yellows pink
+    Tritanopia,
+}
+
+impl Deficiency {
+    /// Machado, Oliveira and Fernandes (2009) at full severity, on linear RGB
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// `color` as it's seen with this deficiency
+    pub fn simulate(self, color: Rgb<u8>) -> Rgb<u8> {
+        let linear = color.0.map(|channel| to_linear(channel as f32 / 255.0));
+        let seen = self.matrix().map(|row| {
+            let value: f32 = row.iter().zip(linear).map(|(m, c)| m * c).sum();
+            (from_linear(value.clamp(0.0, 1.0)) * 255.0).round() as u8
+        });
+        Rgb(seen)
+    }
+
+    /// [`simulate`](Self::simulate)s every pixel of `image`
+    pub fn apply(self, image: &mut RgbImage) {
+        // compositions are mostly long runs of one color, so the last one is nearly always it
+        let mut last = None;
+        for pixel in image.pixels_mut() {
+            let seen = match last {
+                Some((color, seen)) if color == *pixel => seen,
+                _ => {
+                    let seen = self.simulate(*pixel);
+                    last = Some((*pixel, seen));
+                    seen
+                }
+            };
+            *pixel = seen;
+        }
+    }
+}
+
+impl fmt::Display for Deficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Deficiency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
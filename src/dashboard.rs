@@ -0,0 +1,249 @@
+//! a wallpaper as an ambient dashboard: a few small panes in a corner, a bar each for how busy
+//! the CPU is and how full memory and the disk are
+//!
+//! reading the numbers off the system needs the `stats` feature, on Linux; drawing them doesn't
+
+use std::{error::Error, fmt, io, path::PathBuf, str::FromStr};
+
+use image::Rgb;
+
+use crate::{
+    geometry::{Rectangle, SplittableGraphic},
+    polygon::{Polygon, Shape},
+    text::{self, Anchor, Font},
+};
+
+/// how much of each is in use, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    pub cpu: f32,
+    pub memory: f32,
+    /// of the filesystem `/` is on
+    pub disk: f32,
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    Io(io::Error),
+    /// a file under /proc didn't say what it should have
+    Format(&'static str),
+    /// built without the `stats` feature, or not on Linux
+    Disabled,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the system's usage: {err}"),
+            Self::Format(file) => write!(f, "couldn't make sense of {file}"),
+            Self::Disabled => write!(
+                f,
+                "reading the system's usage needs the `stats` feature, on Linux"
+            ),
+        }
+    }
+}
+
+impl Error for StatsError {}
+
+impl From<io::Error> for StatsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Usage {
+    /// how the system is doing now; the CPU is watched for a quarter of a second
+    #[cfg(all(feature = "stats", target_os = "linux"))]
+    pub fn sample() -> Result<Self, StatsError> {
+        use std::{fs, thread, time::Duration};
+
+        // time spent busy and in total since boot, in ticks
+        let ticks = || -> Result<(u64, u64), StatsError> {
+            let stat = fs::read_to_string("/proc/stat")?;
+            let line = stat
+                .lines()
+                .next()
+                .ok_or(StatsError::Format("/proc/stat"))?;
+            let fields: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| StatsError::Format("/proc/stat"))?;
+            if fields.len() < 4 {
+                return Err(StatsError::Format("/proc/stat"));
+            }
+            let total: u64 = fields.iter().sum();
+            // idle, and waiting on the disk
+            let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+            Ok((total - idle, total))
+        };
+        let (busy, total) = ticks()?;
+        thread::sleep(Duration::from_millis(250));
+        let (busy_after, total_after) = ticks()?;
+        let cpu = (busy_after - busy) as f32 / (total_after - total).max(1) as f32;
+
+        let meminfo = fs::read_to_string("/proc/meminfo")?;
+        let kilobytes = |key: &str| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+                .ok_or(StatsError::Format("/proc/meminfo"))
+        };
+        let (available, total) = (kilobytes("MemAvailable")?, kilobytes("MemTotal")?);
+        let memory = 1.0 - available as f32 / total.max(1) as f32;
+
+        // SAFETY: statvfs only writes the struct it's given, which is plain data
+        let mut fs: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c"/".as_ptr(), &mut fs) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // like df, out of what's used and what's left to anyone, not counting root's reserve
+        let used = (fs.f_blocks - fs.f_bfree) as f64;
+        let disk = (used / (used + fs.f_bavail as f64).max(1.0)) as f32;
+
+        Ok(Self {
+            cpu: cpu.clamp(0.0, 1.0),
+            memory: memory.clamp(0.0, 1.0),
+            disk: disk.clamp(0.0, 1.0),
+        })
+    }
+
+    #[cfg(not(all(feature = "stats", target_os = "linux")))]
+    pub fn sample() -> Result<Self, StatsError> {
+        Err(StatsError::Disabled)
+    }
+}
+
+/// `cpu,memory,disk`
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.cpu, self.memory, self.disk)
+    }
+}
+
+impl FromStr for Usage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{s:?} isn't cpu,memory,disk usage from 0 to 1");
+        let values: Vec<f32> = s
+            .split(',')
+            .map(|value| value.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        match values[..] {
+            [cpu, memory, disk] if values.iter().all(|value| (0.0..=1.0).contains(value)) => {
+                Ok(Self { cpu, memory, disk })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// what to show, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dashboard {
+    pub usage: Usage,
+    /// which corner, side or middle it goes in
+    pub anchor: Anchor,
+    /// whether each bar is labeled with what it is and how full
+    pub legend: bool,
+    /// a TrueType font file for the legend; whichever [`find_font`](text::find_font) finds if
+    /// unset
+    pub font: Option<PathBuf>,
+}
+
+impl Dashboard {
+    pub fn new(usage: Usage) -> Self {
+        Self {
+            usage,
+            anchor: Anchor::TopRight,
+            legend: false,
+            font: None,
+        }
+    }
+}
+
+/// `dashboard` in `bounds`, the canvas or the part of it that's sure to be seen: a bar a row,
+/// each as much of it in one of `accents` as it's used and the rest `light`, with `ink` lines
+/// between, like panes; `font` is for the legend, if there's one
+pub fn draw(
+    dashboard: &Dashboard,
+    bounds: &Rectangle,
+    font: Option<&Font>,
+    ink: Rgb<u8>,
+    light: Rgb<u8>,
+    accents: &[Rgb<u8>],
+) -> Vec<Shape> {
+    let accents = if accents.is_empty() {
+        &[ink][..]
+    } else {
+        accents
+    };
+    let short = bounds.width.min(bounds.height);
+    let line = (short / 360.0).round().max(1.0);
+    let row = (short * 0.03).round().max(1.0);
+    let label = match (dashboard.legend, font) {
+        (true, Some(_)) => (short * 0.12).round(),
+        _ => 0.0,
+    };
+    let bar = (short * 0.25).round();
+    let panel_width = line + if label > 0.0 { label + line } else { 0.0 } + bar + line;
+    let panel_height = line + 3.0 * (row + line);
+
+    let margin = (short / 30.0).round();
+    let (across, down) = dashboard.anchor.position();
+    let left = (bounds.x + margin + (bounds.width - 2.0 * margin - panel_width) * across).round();
+    let top = (bounds.y + margin + (bounds.height - 2.0 * margin - panel_height) * down).round();
+
+    let mut shapes = vec![];
+    let mut fill = |rectangle: Rectangle, color| {
+        shapes.push(Shape {
+            polygon: Polygon::from_rectangle(&rectangle),
+            color,
+        })
+    };
+    // the lines are what shows between the panes on top of it
+    fill(Rectangle::new(left, top, panel_width, panel_height), ink);
+    let usage = dashboard.usage;
+    let rows = [
+        ("cpu", usage.cpu),
+        ("ram", usage.memory),
+        ("disk", usage.disk),
+    ];
+    let mut labels = vec![];
+    for (index, &(name, used)) in rows.iter().enumerate() {
+        let y = top + line + index as f32 * (row + line);
+        let mut x = left + line;
+        if label > 0.0 {
+            let cell = Rectangle::new(x, y, label, row);
+            fill(cell, light);
+            labels.push((format!("{name} {:.0}%", used * 100.0), cell));
+            x += label + line;
+        }
+        let filled = (bar * used).round();
+        if filled > 0.0 {
+            fill(
+                Rectangle::new(x, y, filled, row),
+                accents[index % accents.len()],
+            );
+        }
+        // a line between the used part and the rest, unless one of them is all of it
+        let rest = if filled > 0.0 { filled + line } else { 0.0 };
+        if rest < bar {
+            fill(Rectangle::new(x + rest, y, bar - rest, row), light);
+        }
+    }
+    if let Some(font) = font.filter(|_| label > 0.0) {
+        for (written, cell) in labels {
+            let glyphs = text::layout_in(&written, font, row * 0.6, Anchor::Left, &cell);
+            shapes.extend(glyphs.into_iter().map(|polygon| Shape {
+                polygon,
+                color: ink,
+            }));
+        }
+    }
+    shapes
+}
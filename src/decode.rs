@@ -0,0 +1,174 @@
+//! reading [`Parameters`] back out of files we wrote with [`encode`](crate::encode)
+
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use image::ImageFormat;
+
+use crate::metadata::{Parameters, ParametersError};
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    /// the file has no parameters we recognize
+    NoMetadata,
+    /// only PNG and JPEG carry parameters
+    UnsupportedFormat(String),
+    Parameters(ParametersError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Png(err) => write!(f, "{err}"),
+            Self::NoMetadata => write!(f, "no generation parameters found; was this made by us?"),
+            Self::UnsupportedFormat(path) => {
+                write!(f, "can only read parameters from PNG or JPEG, not {path}")
+            }
+            Self::Parameters(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Png(err) => Some(err),
+            Self::Parameters(err) => Some(err),
+            Self::NoMetadata | Self::UnsupportedFormat(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<png::DecodingError> for DecodeError {
+    fn from(err: png::DecodingError) -> Self {
+        Self::Png(err)
+    }
+}
+
+impl From<ParametersError> for DecodeError {
+    fn from(err: ParametersError) -> Self {
+        Self::Parameters(err)
+    }
+}
+
+/// parameters from the `tEXt` and `iTXt` chunks; only reads up to the image data
+pub fn read_png(reader: impl Read) -> Result<Parameters, DecodeError> {
+    let reader = png::Decoder::new(reader).read_info()?;
+    let info = reader.info();
+    let latin1 = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()));
+    let utf8 = info
+        .utf8_text
+        .iter()
+        .map(|chunk| Ok((chunk.keyword.clone(), chunk.get_text()?)))
+        .collect::<Result<Vec<_>, png::DecodingError>>()?;
+    let chunks: Vec<(String, String)> = latin1.chain(utf8).collect();
+    if chunks.is_empty() {
+        return Err(DecodeError::NoMetadata);
+    }
+    Ok(Parameters::from_pairs(
+        chunks
+            .iter()
+            .map(|(key, text)| (key.as_str(), text.as_str())),
+    )?)
+}
+
+/// parameters from the EXIF `ImageDescription`
+pub fn read_jpeg(jpeg: &[u8]) -> Result<Parameters, DecodeError> {
+    let description = exif_description(jpeg).ok_or(DecodeError::NoMetadata)?;
+    Ok(Parameters::from_pairs(
+        description.lines().filter_map(|line| line.split_once('=')),
+    )?)
+}
+
+/// walks the segments until the scan starts, looking for an `Exif` APP1
+fn exif_description(jpeg: &[u8]) -> Option<String> {
+    let mut at = 2;
+    while let Some(&[0xff, marker, hi, lo]) = jpeg.get(at..at + 4) {
+        let length = u16::from_be_bytes([hi, lo]) as usize;
+        let segment = jpeg.get(at + 4..at + 2 + length)?;
+        if marker == 0xe1 {
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return tiff_image_description(tiff);
+            }
+        }
+        // start of scan; no more metadata after this
+        if marker == 0xda {
+            return None;
+        }
+        at += 2 + length;
+    }
+    None
+}
+
+fn tiff_image_description(tiff: &[u8]) -> Option<String> {
+    const IMAGE_DESCRIPTION: u16 = 0x010e;
+
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let u32_at = |at: usize| {
+        let bytes = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    for entry in (0..entries).map(|i| ifd + 2 + 12 * i) {
+        if u16_at(entry)? != IMAGE_DESCRIPTION {
+            continue;
+        }
+        let count = u32_at(entry + 4)? as usize;
+        // values that fit in 4 bytes live in the offset field itself
+        let offset = if count <= 4 {
+            entry + 8
+        } else {
+            u32_at(entry + 8)? as usize
+        };
+        let text = tiff.get(offset..offset + count)?;
+        let text = text.strip_suffix(&[0]).unwrap_or(text);
+        return Some(String::from_utf8_lossy(text).into_owned());
+    }
+    None
+}
+
+/// picks the reader from the extension
+pub fn read(path: impl AsRef<Path>) -> Result<Parameters, DecodeError> {
+    let path = path.as_ref();
+    match ImageFormat::from_path(path) {
+        Ok(ImageFormat::Png) => read_png(BufReader::new(File::open(path)?)),
+        Ok(ImageFormat::Jpeg) => read_jpeg(&fs::read(path)?),
+        _ => Err(DecodeError::UnsupportedFormat(path.display().to_string())),
+    }
+}
@@ -0,0 +1,368 @@
+//! how deep to split, place by place: from a grayscale mask, where white gets every level, black
+//! none, and the grays in between some of them, and around zones kept clear of cuts altogether
+//! or a pane kept whole for something else, like a QR code. near the edges of a TV, which may
+//! not show them, panes are kept big
+
+use std::path::Path;
+
+use image::{imageops::FilterType, GrayImage, ImageError};
+use rand::RngCore;
+
+use crate::{
+    geometry::{Rectangle, SplitOptions, SplittableGraphic},
+    strategy::SplitStrategy,
+    tree::Tree,
+};
+
+/// the image at `path` as brightness, stretched to exactly `bounds`, one pixel per canvas pixel
+pub fn load(path: &Path, bounds: &Rectangle) -> Result<GrayImage, ImageError> {
+    let (width, height) = (bounds.width.max(1.0) as u32, bounds.height.max(1.0) as u32);
+    Ok(image::open(path)?
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma8())
+}
+
+/// how many margins across a pane reaching into one has to be, each way
+const RING: f32 = 3.0;
+
+/// the part of a canvas a TV that overscans is sure to show, `percent` in from each side, and
+/// the ring around its edges where panes are kept big
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeArea {
+    /// what's shown
+    pub inside: Rectangle,
+    /// how wide and how tall a pane reaching past `inside` has to be, at least
+    pub least: (f32, f32),
+}
+
+impl SafeArea {
+    pub fn new(percent: f32, width: u32, height: u32) -> Self {
+        let (across, down) = (
+            width as f32 * percent / 100.0,
+            height as f32 * percent / 100.0,
+        );
+        Self {
+            inside: Rectangle::new(
+                across,
+                down,
+                width as f32 - 2.0 * across,
+                height as f32 - 2.0 * down,
+            ),
+            least: ((RING * across).ceil(), (RING * down).ceil()),
+        }
+    }
+
+    /// the part of `bounds` inside the ring, where a pane kept whole for something else can go
+    /// without making small ones around it
+    pub fn clear_of_ring(&self, bounds: &Rectangle) -> Rectangle {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = self.inside;
+        // the ring goes as far in from the canvas's edges as a pane reaching past it is across
+        let (across, down) = (self.least.0 - x, self.least.1 - y);
+        let (left, top) = ((x + across).max(bounds.x), (y + down).max(bounds.y));
+        let right = (x + width - across).min(bounds.x + bounds.width);
+        let bottom = (y + height - down).min(bounds.y + bounds.height);
+        Rectangle::new(left, top, (right - left).max(0.0), (bottom - top).max(0.0))
+    }
+}
+
+/// a zone to keep clear, `x,y,w,h` in whole canvas pixels
+pub fn parse_zone(input: &str) -> Result<Rectangle, String> {
+    let numbers = input
+        .split(',')
+        .map(|number| number.trim().parse::<u32>())
+        .collect::<Result<Vec<_>, _>>();
+    match numbers.as_deref() {
+        Ok(&[x, y, width, height]) if width > 0 && height > 0 => Ok(Rectangle::new(
+            x as f32,
+            y as f32,
+            width as f32,
+            height as f32,
+        )),
+        _ => Err(format!(
+            "expected x,y,w,h in whole pixels, with some width and height, not {input:?}"
+        )),
+    }
+}
+
+/// a zone the other way round from [`parse_zone`]
+pub fn format_zone(zone: &Rectangle) -> String {
+    format!("{},{},{},{}", zone.x, zone.y, zone.width, zone.height)
+}
+
+/// splits every node with `strategy` while it's fewer levels deep than the brightest part of
+/// `mask` (if any) under it asks for, out of `levels`, moving cuts off any of `zones` they'd go
+/// through, and any that'd leave a small pane past the `safe` area; nodes that can't be cut
+/// without either are left whole. the nodes around `reserved` are cut along its edges first,
+/// however deep that takes, until it's a leaf itself
+#[allow(clippy::too_many_arguments)]
+pub fn layout(
+    bounds: Rectangle,
+    mask: Option<&GrayImage>,
+    zones: &[Rectangle],
+    reserved: Option<Rectangle>,
+    safe: Option<&SafeArea>,
+    levels: usize,
+    strategy: &dyn SplitStrategy<Rectangle>,
+    options: &SplitOptions,
+    rng: &mut dyn RngCore,
+) -> Tree<Rectangle> {
+    let mut tree = Tree::new(bounds);
+    tree.split_by(|rectangle, depth| {
+        if let Some(reserved) = reserved {
+            if *rectangle == reserved {
+                return None;
+            }
+            if let Some(halves) = around(rectangle, &reserved) {
+                return Some(halves);
+            }
+        }
+        // nothing left to split once a pane is down to a pixel
+        if depth >= levels || rectangle.width.max(rectangle.height) < 2.0 {
+            return None;
+        }
+        if let Some(mask) = mask {
+            // as deep as levels × brightness / 255, rounding up
+            let threshold = (255 * depth / levels) as u8;
+            if !brighter(mask, &bounds, rectangle, threshold) {
+                return None;
+            }
+        }
+        let halves = strategy.split(rectangle, depth, options, rng);
+        let halves = clear(rectangle, halves, zones)?;
+        match safe {
+            Some(safe) => kept_big(rectangle, halves, safe),
+            None => Some(halves),
+        }
+    });
+    tree
+}
+
+/// `halves` of `node`, with the cut between them moved if it leaves either one smaller than
+/// `safe` says a pane reaching past it can be; `None` if there's nowhere it doesn't
+fn kept_big(
+    node: &Rectangle,
+    halves: (Rectangle, Rectangle),
+    safe: &SafeArea,
+) -> Option<(Rectangle, Rectangle)> {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = *node;
+    let inside = &safe.inside;
+    let horz_split = halves.0.width < width;
+    // along the cut and across it: where the node is, where the safe area is, and the least a
+    // half reaching past it can be
+    let (start, length, safe_start, safe_length, least) = if horz_split {
+        (x, width, inside.x, inside.width, safe.least.0)
+    } else {
+        (y, height, inside.y, inside.height, safe.least.1)
+    };
+    let (across, breadth, safe_across, safe_breadth) = if horz_split {
+        (y, height, inside.y, inside.height)
+    } else {
+        (x, width, inside.x, inside.width)
+    };
+    // both halves reach past it if the node does across the cut; otherwise each one does by
+    // its own end, or else by being so far out the other's end reaches too
+    let both = across < safe_across || across + breadth > safe_across + safe_breadth;
+    let end = start + length;
+    let low = if both || start < safe_start {
+        start + least
+    } else {
+        start
+    };
+    let high = if both || end > safe_start + safe_length {
+        end - least
+    } else {
+        end
+    };
+    let first = if horz_split {
+        halves.0.width
+    } else {
+        halves.0.height
+    };
+    let cut = (start + first).clamp(low, high.max(low));
+    if low > high || cut <= start || cut >= end {
+        return None;
+    }
+    if cut == start + first {
+        return Some(halves);
+    }
+    Some(if horz_split {
+        (
+            Rectangle::new(x, y, cut - x, height),
+            Rectangle::new(cut, y, end - cut, height),
+        )
+    } else {
+        (
+            Rectangle::new(x, y, width, cut - y),
+            Rectangle::new(x, cut, width, end - cut),
+        )
+    })
+}
+
+/// `node` cut along whichever edge of `reserved` inside it leaves the most of it on the far side,
+/// if `reserved` is inside it
+fn around(node: &Rectangle, reserved: &Rectangle) -> Option<(Rectangle, Rectangle)> {
+    let (right, bottom) = (node.x + node.width, node.y + node.height);
+    let inside = reserved.x >= node.x
+        && reserved.y >= node.y
+        && reserved.x + reserved.width <= right
+        && reserved.y + reserved.height <= bottom;
+    if !inside {
+        return None;
+    }
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = *node;
+    // each edge, with how much is cut off beyond it
+    [
+        (true, reserved.x, reserved.x - x),
+        (
+            true,
+            reserved.x + reserved.width,
+            right - reserved.x - reserved.width,
+        ),
+        (false, reserved.y, reserved.y - y),
+        (
+            false,
+            reserved.y + reserved.height,
+            bottom - reserved.y - reserved.height,
+        ),
+    ]
+    .into_iter()
+    .filter(|&(_, _, beyond)| beyond > 0.0)
+    .max_by(|a, b| {
+        let area = |&(horz_split, _, beyond): &(bool, f32, f32)| {
+            beyond * if horz_split { height } else { width }
+        };
+        area(a).total_cmp(&area(b))
+    })
+    .map(|(horz_split, edge, _)| {
+        if horz_split {
+            (
+                Rectangle::new(x, y, edge - x, height),
+                Rectangle::new(edge, y, right - edge, height),
+            )
+        } else {
+            (
+                Rectangle::new(x, y, width, edge - y),
+                Rectangle::new(x, edge, width, bottom - edge),
+            )
+        }
+    })
+}
+
+/// how much of `pane` is inside `zones`, from 0 to 1
+pub fn covered(pane: &Rectangle, zones: &[Rectangle]) -> f64 {
+    let overlap = |start: f32, length: f32, zone_start: f32, zone_length: f32| {
+        ((start + length).min(zone_start + zone_length) - start.max(zone_start)).max(0.0) as f64
+    };
+    let area: f64 = zones
+        .iter()
+        .map(|zone| {
+            overlap(pane.x, pane.width, zone.x, zone.width)
+                * overlap(pane.y, pane.height, zone.y, zone.height)
+        })
+        .sum();
+    (area / pane.area()).min(1.0)
+}
+
+/// `halves` of `node`, or if the cut between them goes through one of `zones`, the node cut along
+/// the nearest edge of a zone that doesn't, the same way if there's one or else the other;
+/// `None` if there isn't one
+fn clear(
+    node: &Rectangle,
+    halves: (Rectangle, Rectangle),
+    zones: &[Rectangle],
+) -> Option<(Rectangle, Rectangle)> {
+    // a node, or a zone, along one side or the other: (start, length) both ways
+    let spans = |r: &Rectangle, horz_split: bool| {
+        if horz_split {
+            ((r.x, r.width), (r.y, r.height))
+        } else {
+            ((r.y, r.height), (r.x, r.width))
+        }
+    };
+    // the zones a cut the one way could go through
+    let crossing = |horz_split: bool| {
+        let ((_, _), (across, breadth)) = spans(node, horz_split);
+        zones
+            .iter()
+            .map(move |zone| spans(zone, horz_split))
+            .filter(move |&(_, (zone_across, zone_breadth))| {
+                zone_across < across + breadth && zone_across + zone_breadth > across
+            })
+    };
+    let clear_at = |horz_split: bool, cut: f32| {
+        crossing(horz_split).all(|((start, length), _)| cut <= start || cut >= start + length)
+    };
+
+    let horz_split = halves.0.width < node.width;
+    let ((start, _), _) = spans(node, horz_split);
+    let cut = start + spans(&halves.0, horz_split).0 .1;
+    if clear_at(horz_split, cut) {
+        return Some(halves);
+    }
+    // failing a clear place near the cut, a clear place across the other way near the middle
+    [(horz_split, cut), (!horz_split, f32::NAN)]
+        .into_iter()
+        .find_map(|(horz_split, near)| {
+            let ((start, length), _) = spans(node, horz_split);
+            let near = if near.is_nan() {
+                start + length / 2.0
+            } else {
+                near
+            };
+            crossing(horz_split)
+                .flat_map(|((zone_start, zone_length), _)| [zone_start, zone_start + zone_length])
+                .filter(|&edge| edge > start && edge < start + length && clear_at(horz_split, edge))
+                .min_by(|a, b| (a - near).abs().total_cmp(&(b - near).abs()))
+                .map(|edge| (horz_split, edge))
+        })
+        .map(|(horz_split, edge)| {
+            let Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } = *node;
+            if horz_split {
+                (
+                    Rectangle::new(x, y, edge - x, height),
+                    Rectangle::new(edge, y, x + width - edge, height),
+                )
+            } else {
+                (
+                    Rectangle::new(x, y, width, edge - y),
+                    Rectangle::new(x, edge, width, y + height - edge),
+                )
+            }
+        })
+}
+
+/// whether any pixel of `mask` under `rectangle` is brighter than `threshold`
+fn brighter(mask: &GrayImage, bounds: &Rectangle, rectangle: &Rectangle, threshold: u8) -> bool {
+    let span = |start: f32, length: f32, origin: f32, limit: u32| {
+        let from = ((start - origin).floor().max(0.0) as u32).min(limit - 1);
+        let to = ((start + length - origin).ceil() as u32).clamp(from + 1, limit);
+        from..to
+    };
+    let columns = span(rectangle.x, rectangle.width, bounds.x, mask.width());
+    let mut rows = span(rectangle.y, rectangle.height, bounds.y, mask.height());
+    rows.any(|y| {
+        columns
+            .clone()
+            .any(|x| mask.get_pixel(x, y).0[0] > threshold)
+    })
+}
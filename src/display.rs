@@ -0,0 +1,84 @@
+//! asking the OS which monitors are attached; needs the `display` feature
+
+use std::{error::Error, fmt};
+
+/// one attached monitor, in physical pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Display {
+    /// what the OS calls the output, e.g. `DP-1`
+    pub name: String,
+    /// position in the virtual desktop
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayError {
+    /// built without the `display` feature
+    Unsupported,
+    /// the OS didn't tell us anything useful
+    Query(String),
+    NoDisplays,
+}
+
+impl fmt::Display for DisplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(
+                f,
+                "display detection needs mondrian-rs built with `--features display`"
+            ),
+            Self::Query(err) => write!(f, "couldn't query displays: {err}"),
+            Self::NoDisplays => write!(f, "no displays found"),
+        }
+    }
+}
+
+impl Error for DisplayError {}
+
+/// every attached monitor
+#[cfg(feature = "display")]
+pub fn displays() -> Result<Vec<Display>, DisplayError> {
+    let displays: Vec<Display> = display_info::DisplayInfo::all()
+        .map_err(|err| DisplayError::Query(err.to_string()))?
+        .into_iter()
+        .map(|info| {
+            // macOS reports points rather than pixels
+            let scale = if cfg!(target_os = "macos") {
+                info.scale_factor.max(1.0)
+            } else {
+                1.0
+            };
+            Display {
+                name: info.name,
+                x: info.x,
+                y: info.y,
+                width: (info.width as f32 * scale).round() as u32,
+                height: (info.height as f32 * scale).round() as u32,
+                is_primary: info.is_primary,
+            }
+        })
+        .collect();
+    if displays.is_empty() {
+        return Err(DisplayError::NoDisplays);
+    }
+    Ok(displays)
+}
+
+#[cfg(not(feature = "display"))]
+pub fn displays() -> Result<Vec<Display>, DisplayError> {
+    Err(DisplayError::Unsupported)
+}
+
+/// the primary monitor, or the first one if none is marked primary
+pub fn primary() -> Result<Display, DisplayError> {
+    let mut displays = displays()?;
+    let index = displays
+        .iter()
+        .position(|display| display.is_primary)
+        .unwrap_or(0);
+    Ok(displays.swap_remove(index))
+}
@@ -0,0 +1,230 @@
+//! bringing an image down to a handful of colors, e.g. an e-paper panel's few or an indexed
+//! PNG's, with or without dithering to make up the shades in between out of patterns of the ones
+//! there are
+
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Bayer's 8×8 threshold matrix, each cell 0 to 63
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// how many cells there are across [`blue_noise`]
+const BLUE_NOISE: usize = 64;
+
+/// how [`Dither::apply`] picks between the colors there are
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Dither {
+    /// every pixel the nearest color, flat areas staying flat and gradients banding
+    #[default]
+    None,
+    /// a fixed Bayer pattern nudges each pixel before it's matched; regular crosshatching that
+    /// stays put from one image to the next
+    Ordered,
+    /// Floyd–Steinberg: how far each pixel was from its color carries over to the ones after it,
+    /// for the finest grain
+    FloydSteinberg,
+    /// like `ordered`, but the pattern is blue noise: evenly spread dots with no grid to them,
+    /// which reads as grain rather than crosshatching
+    BlueNoise,
+}
+
+impl Dither {
+    /// `image` in nothing but `colors`
+    pub fn apply(self, image: &mut RgbImage, colors: &[Rgb<u8>]) {
+        if colors.is_empty() {
+            return;
+        }
+        match self {
+            Self::None => {
+                for pixel in image.pixels_mut() {
+                    *pixel = nearest(pixel.0.map(f32::from), colors);
+                }
+            }
+            Self::Ordered => {
+                let spread = spread(colors);
+                for (x, y, pixel) in image.enumerate_pixels_mut() {
+                    let threshold = BAYER[y as usize % 8][x as usize % 8] as f32 / 64.0;
+                    let nudge = (threshold + 0.5 / 64.0 - 0.5) * spread;
+                    *pixel = nearest(pixel.0.map(|channel| channel as f32 + nudge), colors);
+                }
+            }
+            Self::BlueNoise => {
+                let spread = spread(colors);
+                let ranks = blue_noise();
+                let cells = (BLUE_NOISE * BLUE_NOISE) as f32;
+                for (x, y, pixel) in image.enumerate_pixels_mut() {
+                    let rank =
+                        ranks[y as usize % BLUE_NOISE * BLUE_NOISE + x as usize % BLUE_NOISE];
+                    let nudge = ((rank as f32 + 0.5) / cells - 0.5) * spread;
+                    *pixel = nearest(pixel.0.map(|channel| channel as f32 + nudge), colors);
+                }
+            }
+            Self::FloydSteinberg => {
+                let (width, height) = image.dimensions();
+                let (width, height) = (width as usize, height as usize);
+                // what's carried into this row and the next
+                let mut row = vec![[0.0f32; 3]; width];
+                let mut next = vec![[0.0f32; 3]; width];
+                for y in 0..height {
+                    for x in 0..width {
+                        let pixel = image.get_pixel_mut(x as u32, y as u32);
+                        let wanted: [f32; 3] = [0, 1, 2].map(|c| pixel.0[c] as f32 + row[x][c]);
+                        let got = nearest(wanted, colors);
+                        *pixel = got;
+                        let error: [f32; 3] = [0, 1, 2].map(|c| wanted[c] - got.0[c] as f32);
+                        let spill = |to: &mut [f32; 3], share: f32| {
+                            for c in 0..3 {
+                                to[c] += error[c] * share;
+                            }
+                        };
+                        if x + 1 < width {
+                            spill(&mut row[x + 1], 7.0 / 16.0);
+                            spill(&mut next[x + 1], 1.0 / 16.0);
+                        }
+                        if x > 0 {
+                            spill(&mut next[x - 1], 3.0 / 16.0);
+                        }
+                        spill(&mut next[x], 5.0 / 16.0);
+                    }
+                    row = std::mem::replace(&mut next, vec![[0.0; 3]; width]);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// which of `colors` is closest to `wanted`, the channels weighted by how much they count
+/// towards lightness
+fn nearest(wanted: [f32; 3], colors: &[Rgb<u8>]) -> Rgb<u8> {
+    let distance = |color: &Rgb<u8>| {
+        let [r, g, b] = [0, 1, 2].map(|c| wanted[c] - color.0[c] as f32);
+        0.299 * r * r + 0.587 * g * g + 0.114 * b * b
+    };
+    *colors
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .expect("checked there are colors")
+}
+
+/// how far apart the two closest of `colors` are, by the channel they differ most in: about
+/// how big a step there is to dither across
+fn spread(colors: &[Rgb<u8>]) -> f32 {
+    let mut spread = 255u8;
+    for (index, a) in colors.iter().enumerate() {
+        for b in &colors[index + 1..] {
+            let step = (0..3).map(|c| a.0[c].abs_diff(b.0[c])).max().unwrap_or(0);
+            if step > 0 {
+                spread = spread.min(step);
+            }
+        }
+    }
+    spread as f32
+}
+
+/// a [`BLUE_NOISE`]-square tile that wraps, every cell ranked from 0 by when it switches on, made
+/// once by Ulichney's void-and-cluster: each new cell goes where the ones already on are thinnest
+/// on the ground
+fn blue_noise() -> &'static [u16] {
+    static RANKS: OnceLock<Vec<u16>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        let size = BLUE_NOISE;
+        let cells = size * size;
+        // how much a cell on at one offset crowds another, wrapping round the edges
+        let kernel: Vec<f32> = (0..cells)
+            .map(|offset| {
+                let wrap = |d: usize| d.min(size - d) as f32;
+                let (dx, dy) = (wrap(offset % size), wrap(offset / size));
+                (-(dx * dx + dy * dy) / (2.0 * 1.5 * 1.5)).exp()
+            })
+            .collect();
+        let mut on = vec![false; cells];
+        let mut crowding = vec![0.0f32; cells];
+        let toggle = |on: &mut [bool], crowding: &mut [f32], cell: usize| {
+            on[cell] = !on[cell];
+            let sign = if on[cell] { 1.0 } else { -1.0 };
+            let (cx, cy) = (cell % size, cell / size);
+            for (other, crowd) in crowding.iter_mut().enumerate() {
+                let dx = (other % size + size - cx) % size;
+                let dy = (other / size + size - cy) % size;
+                *crowd += sign * kernel[dy * size + dx];
+            }
+        };
+        // of the cells on (or off), the most (or least) crowded
+        let pick = |on: &[bool], crowding: &[f32], want: bool, most: bool| {
+            (0..cells)
+                .filter(|&cell| on[cell] == want)
+                .max_by(|&a, &b| {
+                    let order = crowding[a].total_cmp(&crowding[b]);
+                    if most {
+                        order
+                    } else {
+                        order.reverse()
+                    }
+                })
+                .expect("there's always one of each")
+        };
+        // a tenth of the cells on at random, then moved one at a time from the tightest cluster
+        // into the biggest void until that's where it'd go back to
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let start = cells / 10;
+        while on.iter().filter(|&&on| on).count() < start {
+            let cell = rng.gen_range(0..cells);
+            if !on[cell] {
+                toggle(&mut on, &mut crowding, cell);
+            }
+        }
+        loop {
+            let cluster = pick(&on, &crowding, true, true);
+            toggle(&mut on, &mut crowding, cluster);
+            let void = pick(&on, &crowding, false, false);
+            toggle(&mut on, &mut crowding, void);
+            if void == cluster {
+                break;
+            }
+        }
+        // the starting cells are ranked by taking them away tightest first, the rest by filling
+        // in the biggest voids
+        let mut ranks = vec![0u16; cells];
+        let (mut taken, mut taken_crowding) = (on.clone(), crowding.clone());
+        for rank in (0..start).rev() {
+            let cluster = pick(&taken, &taken_crowding, true, true);
+            toggle(&mut taken, &mut taken_crowding, cluster);
+            ranks[cluster] = rank as u16;
+        }
+        for rank in start..cells - 1 {
+            let void = pick(&on, &crowding, false, false);
+            toggle(&mut on, &mut crowding, void);
+            ranks[void] = rank as u16;
+        }
+        let last = on.iter().position(|&on| !on).expect("one cell left off");
+        ranks[last] = (cells - 1) as u16;
+        ranks
+    })
+}
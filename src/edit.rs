@@ -0,0 +1,147 @@
+//! changes made to a [`Composition`](crate::composition::Composition) after it was built, by
+//! pane index; they're kept with the parameters, so an edited layout can be made again at any size
+
+use std::{error::Error, fmt, str::FromStr};
+
+use image::Rgb;
+
+use crate::{color::to_hex, metadata::parse_hex};
+
+/// one change to the panes, by their index in [`Composition::panes`](crate::composition::Composition::panes)
+/// at the time it was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// cut the pane in two, one level deeper
+    Split(usize),
+    /// join the pane with whatever it was split from, back into one pane of its color
+    Merge(usize),
+    /// give the pane this color
+    Color(usize, Rgb<u8>),
+}
+
+impl Edit {
+    /// the pane it changes
+    pub fn pane(&self) -> usize {
+        match *self {
+            Self::Split(pane) | Self::Merge(pane) | Self::Color(pane, _) => pane,
+        }
+    }
+}
+
+/// `split:3`, `merge:5`, `color:2:#ff0000`
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Split(pane) => write!(f, "split:{pane}"),
+            Self::Merge(pane) => write!(f, "merge:{pane}"),
+            Self::Color(pane, color) => write!(f, "color:{pane}:{}", to_hex(*color)),
+        }
+    }
+}
+
+impl FromStr for Edit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{s:?} isn't an edit; try split:N, merge:N or color:N:#rrggbb");
+        let mut parts = s.trim().split(':');
+        let (kind, pane) = (parts.next(), parts.next().map(str::parse));
+        let edit = match (kind, pane, parts.next()) {
+            (Some("split"), Some(Ok(pane)), None) => Self::Split(pane),
+            (Some("merge"), Some(Ok(pane)), None) => Self::Merge(pane),
+            (Some("color"), Some(Ok(pane)), Some(color)) => {
+                Self::Color(pane, parse_hex(color).ok_or_else(invalid)?)
+            }
+            _ => return Err(invalid()),
+        };
+        match parts.next() {
+            None => Ok(edit),
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+/// the edits made to a composition and the ones undone since, to carry on from later; written a
+/// line each, oldest first, with the undone ones after as `undone <edit>`, last undone last
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    pub done: Vec<Edit>,
+    /// what redo puts back, from the end
+    pub undone: Vec<Edit>,
+}
+
+impl History {
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty() && self.undone.is_empty()
+    }
+}
+
+impl fmt::Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for edit in &self.done {
+            writeln!(f, "{edit}")?;
+        }
+        for edit in &self.undone {
+            writeln!(f, "undone {edit}")?;
+        }
+        Ok(())
+    }
+}
+
+/// blank lines and `#` comments are skipped
+impl FromStr for History {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut history = Self::default();
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (list, edit) = match line.strip_prefix("undone ") {
+                Some(edit) => (&mut history.undone, edit),
+                None if history.undone.is_empty() => (&mut history.done, line),
+                None => {
+                    return Err(format!(
+                        "line {}: edits can't come after undone ones",
+                        n + 1
+                    ))
+                }
+            };
+            list.push(
+                edit.parse()
+                    .map_err(|err| format!("line {}: {err}", n + 1))?,
+            );
+        }
+        Ok(history)
+    }
+}
+
+/// why an [`Edit`] couldn't be made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// the styles made of shapes have no panes to edit
+    NoPanes,
+    /// there aren't that many panes
+    NoSuchPane { pane: usize, panes: usize },
+    /// the only pane there is wasn't split from anything
+    Root,
+    /// the pane is too small to cut
+    TooSmall(usize),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPanes => write!(f, "only the mondrian style has panes to edit"),
+            Self::NoSuchPane { pane, panes } => {
+                write!(f, "there's no pane {pane}; there are only {panes}")
+            }
+            Self::Root => write!(f, "there's nothing to merge a single pane with"),
+            Self::TooSmall(pane) => write!(f, "pane {pane} is too small to split"),
+        }
+    }
+}
+
+impl Error for EditError {}
@@ -0,0 +1,104 @@
+//! e-paper panels: the few colors each one shows, and the packed framebuffer their drivers take
+
+use std::{fmt, str::FromStr};
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+
+use crate::dither::Dither;
+
+/// the kinds of panel there are palettes for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Panel {
+    /// black and white, a bit a pixel, white set
+    Bw,
+    /// four grays, two bits a pixel, from black at 0 to white at 3
+    Gray4,
+    /// seven-color ACeP (e.g. Waveshare 5.65" and Inky Impression): black, white, green, blue,
+    /// red, yellow and orange, in that order, four bits a pixel
+    Acep7,
+}
+
+impl Panel {
+    /// the colors it shows, by the index the panel knows each one as
+    pub fn colors(self) -> &'static [Rgb<u8>] {
+        match self {
+            Self::Bw => &[Rgb([0, 0, 0]), Rgb([255, 255, 255])],
+            Self::Gray4 => &[
+                Rgb([0, 0, 0]),
+                Rgb([85, 85, 85]),
+                Rgb([170, 170, 170]),
+                Rgb([255, 255, 255]),
+            ],
+            Self::Acep7 => &[
+                Rgb([0, 0, 0]),
+                Rgb([255, 255, 255]),
+                Rgb([0, 255, 0]),
+                Rgb([0, 0, 255]),
+                Rgb([255, 0, 0]),
+                Rgb([255, 255, 0]),
+                Rgb([255, 128, 0]),
+            ],
+        }
+    }
+
+    /// how many bits each pixel takes in [`Panel::pack`]
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Bw => 1,
+            Self::Gray4 => 2,
+            Self::Acep7 => 4,
+        }
+    }
+
+    /// `image` in nothing but the panel's colors
+    pub fn quantize(self, image: &mut RgbImage, dither: Dither) {
+        dither.apply(image, self.colors());
+    }
+
+    /// `image`, already [quantized](Self::quantize), as the panel's framebuffer: each pixel's
+    /// color index in [`bits`](Self::bits) bits, the leftmost pixel in the highest ones, row by
+    /// row from the top, each row padded out to a whole byte. colors the panel doesn't have
+    /// become the nearest one it does
+    pub fn pack(self, image: &RgbImage) -> Vec<u8> {
+        let colors = self.colors();
+        let bits = self.bits() as usize;
+        let index = |pixel: &Rgb<u8>| {
+            let distance = |color: &Rgb<u8>| {
+                (0..3)
+                    .map(|c| (pixel.0[c] as i32 - color.0[c] as i32).pow(2))
+                    .sum::<i32>()
+            };
+            (0..colors.len())
+                .min_by_key(|&index| distance(&colors[index]))
+                .unwrap_or(0) as u8
+        };
+        let per_byte = 8 / bits;
+        let row_bytes = (image.width() as usize).div_ceil(per_byte);
+        let mut buffer = Vec::with_capacity(row_bytes * image.height() as usize);
+        for row in image.rows() {
+            let pixels: Vec<Rgb<u8>> = row.copied().collect();
+            for chunk in pixels.chunks(per_byte) {
+                let byte = chunk.iter().enumerate().fold(0u8, |byte, (n, pixel)| {
+                    byte | index(pixel) << (8 - bits * (n + 1))
+                });
+                buffer.push(byte);
+            }
+        }
+        buffer
+    }
+}
+
+impl fmt::Display for Panel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Panel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
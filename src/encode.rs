@@ -0,0 +1,543 @@
+//! writing rendered images out, with the generation [`Parameters`] embedded
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use image::{
+    codecs::jpeg::{JpegEncoder, PixelDensity},
+    ImageFormat, Rgb, RgbImage,
+};
+
+use crate::{
+    color::to_linear,
+    icc::{self, Profile},
+    metadata::Parameters,
+    render::raster::Image,
+};
+
+#[derive(Debug)]
+pub enum EncodeError {
+    Io(io::Error),
+    Png(png::EncodingError),
+    Image(image::ImageError),
+    /// couldn't tell the format from the file name
+    UnknownFormat(String),
+    /// a format that can't hold samples this many bits deep
+    Depth {
+        path: String,
+        bits: u32,
+    },
+    /// more colors than an indexed PNG has room for
+    Colors(usize),
+    /// parameters, in bytes, too long for a JPEG's EXIF segment
+    Exif(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Png(err) => write!(f, "{err}"),
+            Self::Image(err) => write!(f, "{err}"),
+            Self::UnknownFormat(path) => write!(f, "don't know what format to write {path} as"),
+            Self::Depth { path, bits: 16 } => {
+                write!(f, "can't write 16-bit samples to {path}; try .png or .tiff")
+            }
+            Self::Depth { path, bits } => {
+                write!(
+                    f,
+                    "can't write {bits}-bit samples to {path}; try .exr or .hdr"
+                )
+            }
+            Self::Colors(count) => {
+                write!(f, "an indexed PNG holds up to 256 colors, not {count}")
+            }
+            Self::Exif(bytes) => write!(
+                f,
+                "the parameters come to {bytes} bytes, more than a JPEG's EXIF holds; try .png"
+            ),
+        }
+    }
+}
+
+impl Error for EncodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Png(err) => Some(err),
+            Self::Image(err) => Some(err),
+            Self::UnknownFormat(_) | Self::Depth { .. } | Self::Colors(_) | Self::Exif(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for EncodeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for EncodeError {
+    fn from(err: png::EncodingError) -> Self {
+        Self::Png(err)
+    }
+}
+
+impl From<image::ImageError> for EncodeError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+/// what we write into the PNG `Software` chunk and EXIF `Software` tag
+pub(crate) const SOFTWARE: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// PNG with every parameter in its own `tEXt` chunk
+pub fn write_png(
+    image: &RgbImage,
+    parameters: &Parameters,
+    writer: impl Write,
+) -> Result<(), EncodeError> {
+    let encoder = png_encoder(image.width(), image.height(), 8, parameters, writer)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    Ok(writer.finish()?)
+}
+
+/// [`write_png`] for 16-bit samples
+pub fn write_png16(
+    image: &Image<u16>,
+    parameters: &Parameters,
+    writer: impl Write,
+) -> Result<(), EncodeError> {
+    let encoder = png_encoder(image.width(), image.height(), 16, parameters, writer)?;
+    let mut writer = encoder.write_header()?;
+    // PNG is big-endian throughout
+    let bytes: Vec<u8> = image
+        .iter()
+        .flat_map(|sample| sample.to_be_bytes())
+        .collect();
+    writer.write_image_data(&bytes)?;
+    Ok(writer.finish()?)
+}
+
+/// [`write_png`] as an indexed PNG with `colors` for its palette, as few bits a pixel as they
+/// fit in. pixels in none of them become the nearest, so dither first for anything better
+pub fn write_png8(
+    image: &RgbImage,
+    colors: &[Rgb<u8>],
+    parameters: &Parameters,
+    writer: impl Write,
+) -> Result<(), EncodeError> {
+    if colors.is_empty() || colors.len() > 256 {
+        return Err(EncodeError::Colors(colors.len()));
+    }
+    let bits = match colors.len() {
+        0..=2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    };
+    let mut encoder = png_encoder(image.width(), image.height(), 8, parameters, writer)?;
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(match bits {
+        1 => png::BitDepth::One,
+        2 => png::BitDepth::Two,
+        4 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    });
+    encoder.set_palette(colors.iter().flat_map(|color| color.0).collect::<Vec<u8>>());
+    // indices aren't a gradient to predict, and runs of them squeeze down best unfiltered
+    encoder.set_filter(png::FilterType::NoFilter);
+    encoder.set_compression(png::Compression::Best);
+    // flat compositions are mostly a few exact colors, so each is only looked up once
+    let mut indices = HashMap::new();
+    let mut index = |pixel: Rgb<u8>| {
+        *indices.entry(pixel).or_insert_with(|| {
+            let distance = |color: &Rgb<u8>| {
+                (0..3)
+                    .map(|c| (pixel.0[c] as i32 - color.0[c] as i32).pow(2))
+                    .sum::<i32>()
+            };
+            (0..colors.len())
+                .min_by_key(|&index| distance(&colors[index]))
+                .unwrap_or(0) as u8
+        })
+    };
+    let per_byte = 8 / bits;
+    let mut data =
+        Vec::with_capacity((image.width() as usize).div_ceil(per_byte) * image.height() as usize);
+    for row in image.rows() {
+        let pixels: Vec<Rgb<u8>> = row.copied().collect();
+        for chunk in pixels.chunks(per_byte) {
+            data.push(chunk.iter().enumerate().fold(0u8, |byte, (n, pixel)| {
+                byte | index(*pixel) << (8 - bits * (n + 1))
+            }));
+        }
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    Ok(writer.finish()?)
+}
+
+/// like [`write_png`], but takes the image a strip of rows at a time so only one strip has to be
+/// in memory; strips have to be `width` wide and add up to `height` rows
+pub fn write_png_strips(
+    width: u32,
+    height: u32,
+    parameters: &Parameters,
+    strips: impl IntoIterator<Item = RgbImage>,
+    writer: impl Write,
+) -> Result<(), EncodeError> {
+    let encoder = png_encoder(width, height, 8, parameters, writer)?;
+    let mut writer = encoder.write_header()?;
+    // bigger than the 4K default so each strip isn't dozens of tiny IDAT chunks
+    let mut stream = writer.stream_writer_with_size(1 << 20)?;
+    for strip in strips {
+        stream.write_all(strip.as_raw())?;
+    }
+    stream.finish()?;
+    Ok(writer.finish()?)
+}
+
+/// a Windows icon with each of `images` in it as a PNG, the way browsers take favicons; they're
+/// square, and there's no metadata in them so the small ones stay small
+pub fn write_ico(images: &[RgbImage], mut writer: impl Write) -> Result<(), EncodeError> {
+    let mut entries = vec![];
+    for image in images {
+        let mut data = vec![];
+        let mut encoder = png::Encoder::new(&mut data, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Best);
+        let mut png = encoder.write_header()?;
+        png.write_image_data(image.as_raw())?;
+        png.finish()?;
+        entries.push((image.width(), image.height(), data));
+    }
+    // reserved, 1 for an icon, and how many there are
+    writer.write_all(&[0, 0, 1, 0])?;
+    writer.write_all(&(entries.len() as u16).to_le_bytes())?;
+    let mut offset = 6 + 16 * entries.len() as u32;
+    for (width, height, data) in &entries {
+        // a byte each, with 0 for 256 or more
+        let side = |pixels: u32| if pixels >= 256 { 0 } else { pixels as u8 };
+        // no palette, reserved, one plane, 24 bits a pixel
+        writer.write_all(&[side(*width), side(*height), 0, 0, 1, 0, 24, 0])?;
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        offset += data.len() as u32;
+    }
+    for (_, _, data) in &entries {
+        writer.write_all(data)?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// `bits` is 8 or 16
+fn png_encoder<W: Write>(
+    width: u32,
+    height: u32,
+    bits: u32,
+    parameters: &Parameters,
+    writer: W,
+) -> Result<png::Encoder<'static, W>, EncodeError> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(if bits == 16 {
+        png::BitDepth::Sixteen
+    } else {
+        png::BitDepth::Eight
+    });
+    encoder.add_text_chunk("Software".to_owned(), SOFTWARE.to_owned())?;
+    if let Some(dpi) = parameters.dpi {
+        // pHYs only knows about meters
+        let per_meter = (dpi as f64 / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: per_meter,
+            yppu: per_meter,
+            unit: png::Unit::Meter,
+        }));
+    }
+    for (key, value) in parameters.to_pairs() {
+        // tEXt is Latin-1 only; anything else, like text overlays in other scripts, goes in iTXt
+        if value.chars().all(|c| (c as u32) < 0x100) {
+            encoder.add_text_chunk(key, value)?;
+        } else {
+            encoder.add_itxt_chunk(key, value)?;
+        }
+    }
+    Ok(encoder)
+}
+
+/// JPEG with the parameters as `key=value` lines in the EXIF `ImageDescription`
+pub fn write_jpeg(
+    image: &RgbImage,
+    parameters: &Parameters,
+    mut writer: impl Write,
+) -> Result<(), EncodeError> {
+    // before the encoding, which is the slow part
+    let exif = exif_segment(parameters)?;
+    let mut jpeg = vec![];
+    let mut encoder = JpegEncoder::new_with_quality(&mut jpeg, 95);
+    if let Some(dpi) = parameters.dpi {
+        encoder.set_pixel_density(PixelDensity::dpi(dpi.min(u16::MAX as u32) as u16));
+    }
+    encoder.encode_image(image)?;
+
+    // SOI, then the JFIF APP0 if the encoder wrote one; EXIF goes right after
+    let mut insert_at = 2;
+    if jpeg.get(2..4) == Some(&[0xff, 0xe0]) {
+        insert_at += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+
+    writer.write_all(&jpeg[..insert_at])?;
+    writer.write_all(&exif)?;
+    writer.write_all(&jpeg[insert_at..])?;
+    Ok(writer.flush()?)
+}
+
+/// binary PPM (`P6`) with the parameters as header comments; ffmpeg's `image2pipe` reads a stream
+/// of these
+pub fn write_ppm(
+    image: &RgbImage,
+    parameters: &Parameters,
+    mut writer: impl Write,
+) -> Result<(), EncodeError> {
+    writeln!(writer, "P6")?;
+    writeln!(writer, "# {SOFTWARE}")?;
+    for line in description(parameters).lines() {
+        writeln!(writer, "# {line}")?;
+    }
+    writeln!(writer, "{} {}", image.width(), image.height())?;
+    writeln!(writer, "255")?;
+    writer.write_all(image.as_raw())?;
+    Ok(writer.flush()?)
+}
+
+/// uncompressed CMYK TIFF, converted through `profile` and with it embedded (or with
+/// [`icc::naive_cmyk`] and none), the resolution, and the parameters in `ImageDescription`
+pub fn write_tiff_cmyk(
+    image: &RgbImage,
+    profile: Option<&Profile>,
+    parameters: &Parameters,
+    mut writer: impl Write,
+) -> Result<(), EncodeError> {
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+    const ASCII: u16 = 2;
+    const RATIONAL: u16 = 5;
+    const UNDEFINED: u16 = 7;
+
+    let pixels = icc::convert(image, profile);
+    let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "too big for a TIFF");
+    let length = u32::try_from(pixels.len()).map_err(|_| too_big())?;
+
+    // tag, type, count, and either the value itself or the bytes it points to
+    let mut entries: Vec<(u16, u16, u32, Vec<u8>)> = vec![];
+    let mut short = |tag, value: u16| entries.push((tag, SHORT, 1, value.to_le_bytes().to_vec()));
+    short(259, 1); // no compression
+    short(262, 5); // separated, i.e. inks
+    short(277, 4);
+    short(284, 1); // chunky
+    short(332, 1); // the inks are CMYK
+    if parameters.dpi.is_some() {
+        short(296, 2); // inches
+    }
+    entries.push((256, LONG, 1, image.width().to_le_bytes().to_vec()));
+    entries.push((257, LONG, 1, image.height().to_le_bytes().to_vec()));
+    entries.push((258, SHORT, 4, [8u16; 4].map(u16::to_le_bytes).concat()));
+    entries.push((278, LONG, 1, image.height().to_le_bytes().to_vec()));
+    entries.push((279, LONG, 1, length.to_le_bytes().to_vec()));
+    for (tag, text) in [(270, description(parameters)), (305, SOFTWARE.to_owned())] {
+        let mut bytes = text.into_bytes();
+        bytes.push(0);
+        entries.push((tag, ASCII, bytes.len() as u32, bytes));
+    }
+    if let Some(dpi) = parameters.dpi {
+        let rational = [dpi, 1].map(u32::to_le_bytes).concat();
+        entries.push((282, RATIONAL, 1, rational.clone()));
+        entries.push((283, RATIONAL, 1, rational));
+    }
+    if let Some(profile) = profile {
+        let bytes = profile.bytes().to_vec();
+        entries.push((34675, UNDEFINED, bytes.len() as u32, bytes));
+    }
+    // the strip offset is the only value that depends on where everything else went
+    entries.push((273, LONG, 1, vec![0; 4]));
+    entries.sort_by_key(|(tag, ..)| *tag);
+
+    // header (8) + entry count (2) + entries (12 each) + next IFD offset (4)
+    let mut data_offset = 8 + 2 + 12 * entries.len() + 4;
+    let data_start = data_offset;
+    let mut data = vec![];
+    let mut ifd = vec![];
+    ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    let strip_offset = data_offset
+        + entries
+            .iter()
+            .filter(|(.., value)| value.len() > 4)
+            .map(|(.., value)| value.len().div_ceil(2) * 2)
+            .sum::<usize>();
+    let strip_offset = u32::try_from(strip_offset).map_err(|_| too_big())?;
+    for (tag, kind, count, mut value) in entries {
+        if tag == 273 {
+            value = strip_offset.to_le_bytes().to_vec();
+        }
+        ifd.extend_from_slice(&tag.to_le_bytes());
+        ifd.extend_from_slice(&kind.to_le_bytes());
+        ifd.extend_from_slice(&count.to_le_bytes());
+        if value.len() <= 4 {
+            value.resize(4, 0);
+            ifd.extend_from_slice(&value);
+        } else {
+            ifd.extend_from_slice(&(data_offset as u32).to_le_bytes());
+            // values have to start on a word boundary
+            if value.len() % 2 == 1 {
+                value.push(0);
+            }
+            data_offset += value.len();
+            data.extend(value);
+        }
+    }
+    ifd.extend_from_slice(&0u32.to_le_bytes());
+    debug_assert_eq!(data_start + data.len(), strip_offset as usize);
+
+    writer.write_all(b"II")?;
+    writer.write_all(&42u16.to_le_bytes())?;
+    writer.write_all(&8u32.to_le_bytes())?;
+    writer.write_all(&ifd)?;
+    writer.write_all(&data)?;
+    writer.write_all(&pixels)?;
+    Ok(writer.flush()?)
+}
+
+/// `ImageDescription` lines look like `mondrian:seed=7`
+pub fn description(parameters: &Parameters) -> String {
+    parameters
+        .to_pairs()
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// an APP1 segment holding a little-endian TIFF with just `ImageDescription` and `Software`, if
+/// it fits in the 64K a segment can be
+fn exif_segment(parameters: &Parameters) -> Result<Vec<u8>, EncodeError> {
+    const ASCII: u16 = 2;
+    const IMAGE_DESCRIPTION: u16 = 0x010e;
+    const SOFTWARE_TAG: u16 = 0x0131;
+
+    let strings = [
+        (IMAGE_DESCRIPTION, description(parameters)),
+        (SOFTWARE_TAG, SOFTWARE.to_owned()),
+    ];
+
+    // header (8) + entry count (2) + entries (12 each) + next IFD offset (4)
+    let mut data_offset = 8 + 2 + 12 * strings.len() as u32 + 4;
+    let mut tiff = vec![];
+    tiff.extend_from_slice(b"II");
+    tiff.extend_from_slice(&42u16.to_le_bytes());
+    tiff.extend_from_slice(&8u32.to_le_bytes());
+    tiff.extend_from_slice(&(strings.len() as u16).to_le_bytes());
+
+    let mut data = vec![];
+    for (tag, value) in &strings {
+        let mut bytes = value.clone().into_bytes();
+        bytes.push(0);
+        tiff.extend_from_slice(&tag.to_le_bytes());
+        tiff.extend_from_slice(&ASCII.to_le_bytes());
+        tiff.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        tiff.extend_from_slice(&data_offset.to_le_bytes());
+        data_offset += bytes.len() as u32;
+        data.extend(bytes);
+    }
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+    tiff.extend(data);
+
+    // the length counts itself and the `Exif` header, but not the marker
+    let length = 2 + 6 + tiff.len();
+    if length > u16::MAX as usize {
+        return Err(EncodeError::Exif(tiff.len()));
+    }
+    let mut segment = vec![0xff, 0xe1];
+    segment.extend_from_slice(&(length as u16).to_be_bytes());
+    segment.extend_from_slice(b"Exif\0\0");
+    segment.extend(tiff);
+    Ok(segment)
+}
+
+/// picks PNG or JPEG (with metadata) from the extension; anything else `image` knows how to
+/// write is written without metadata
+pub fn save(
+    image: &RgbImage,
+    parameters: &Parameters,
+    path: impl AsRef<Path>,
+) -> Result<(), EncodeError> {
+    let path = path.as_ref();
+    let format = format(path)?;
+
+    if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg) {
+        return Ok(image.save_with_format(path, format)?);
+    }
+
+    if format == ImageFormat::Jpeg {
+        // made first, so parameters too long for it don't leave an empty file
+        let mut jpeg = vec![];
+        write_jpeg(image, parameters, &mut jpeg)?;
+        return Ok(fs::write(path, jpeg)?);
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    write_png(image, parameters, &mut file)?;
+    Ok(file.flush()?)
+}
+
+/// [`save`] for 16-bit samples: PNG (with metadata) or TIFF (without)
+pub fn save16(
+    image: &Image<u16>,
+    parameters: &Parameters,
+    path: impl AsRef<Path>,
+) -> Result<(), EncodeError> {
+    let path = path.as_ref();
+    match format(path)? {
+        ImageFormat::Png => {
+            let mut file = BufWriter::new(File::create(path)?);
+            write_png16(image, parameters, &mut file)?;
+            Ok(file.flush()?)
+        }
+        ImageFormat::Tiff => Ok(image.save_with_format(path, ImageFormat::Tiff)?),
+        _ => Err(EncodeError::Depth {
+            path: path.display().to_string(),
+            bits: 16,
+        }),
+    }
+}
+
+/// [`save`] for floating point samples, which OpenEXR and Radiance HDR expect in linear light
+/// rather than sRGB; neither gets metadata
+pub fn save_float(image: &Image<f32>, path: impl AsRef<Path>) -> Result<(), EncodeError> {
+    let path = path.as_ref();
+    let format = format(path)?;
+    if !matches!(format, ImageFormat::OpenExr | ImageFormat::Hdr) {
+        return Err(EncodeError::Depth {
+            path: path.display().to_string(),
+            bits: 32,
+        });
+    }
+    let mut linear = image.clone();
+    for sample in linear.iter_mut() {
+        *sample = to_linear(*sample);
+    }
+    Ok(linear.save_with_format(path, format)?)
+}
+
+fn format(path: &Path) -> Result<ImageFormat, EncodeError> {
+    ImageFormat::from_path(path).map_err(|_| EncodeError::UnknownFormat(path.display().to_string()))
+}
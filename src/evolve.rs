@@ -0,0 +1,338 @@
+//! layouts bred instead of split at random: a [`Genome`] is a split tree with its cuts as
+//! fractions, so any part of one fits anywhere in another, and a [`Population`] is a generation
+//! of them, kept in a file between sessions
+
+use std::{fmt, str::FromStr};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    geometry::{Rectangle, SplitOptions},
+    tree::{Subtree, Tree},
+};
+
+/// how far a cut can move in one mutation, as a fraction of the side it's across
+const NUDGE: f32 = 0.15;
+/// cuts this close to a side are moved off it, so both halves stay something to look at
+const MARGIN: f32 = 0.05;
+
+/// a pane, or a cut into two more; read and written in preorder: `|0.4` cuts side by side 0.4
+/// of the way across, `-0.4` one above the other, and `.` is a pane
+#[derive(Debug, Clone, PartialEq)]
+pub enum Genome {
+    Pane,
+    Cut {
+        side_by_side: bool,
+        /// from 0 to 1
+        at: f32,
+        halves: Box<[Genome; 2]>,
+    },
+}
+
+impl Genome {
+    /// the cuts `tree` was made with, to a thousandth
+    pub fn of(tree: &Tree<Rectangle>) -> Self {
+        Self::of_subtree(tree.root())
+    }
+
+    fn of_subtree(subtree: Subtree<'_, Rectangle>) -> Self {
+        let (Some(left), Some(right)) = (subtree.left(), subtree.right()) else {
+            return Self::Pane;
+        };
+        let (whole, part) = (subtree.item(), left.item());
+        let side_by_side = part.height == whole.height && part.width != whole.width;
+        let (part, whole) = if side_by_side {
+            (part.width, whole.width)
+        } else {
+            (part.height, whole.height)
+        };
+        let at = if whole > 0.0 { part / whole } else { 0.5 };
+        Self::Cut {
+            side_by_side,
+            at: rounded(at),
+            halves: Box::new([Self::of_subtree(left), Self::of_subtree(right)]),
+        }
+    }
+
+    /// lays it out over `bounds`, each cut rounded to the grid `options` cuts on
+    pub fn tree(&self, bounds: Rectangle, options: &SplitOptions) -> Tree<Rectangle> {
+        let mut tree = Tree::new(bounds);
+        // the tree visits nodes in the same order they're written in
+        let mut pending = vec![self];
+        tree.split_by(
+            |rectangle, _| match pending.pop().expect("one genome per node") {
+                Self::Pane => None,
+                Self::Cut {
+                    side_by_side,
+                    at,
+                    halves,
+                } => {
+                    pending.extend([&halves[1], &halves[0]]);
+                    Some(rectangle.divide(*side_by_side, *at, options))
+                }
+            },
+        );
+        tree
+    }
+
+    pub fn panes(&self) -> usize {
+        match self {
+            Self::Pane => 1,
+            Self::Cut { halves, .. } => halves[0].panes() + halves[1].panes(),
+        }
+    }
+
+    /// panes and cuts together
+    fn nodes(&self) -> usize {
+        2 * self.panes() - 1
+    }
+
+    /// the `n`th node in preorder
+    fn node(&self, n: usize) -> &Self {
+        match self {
+            _ if n == 0 => self,
+            Self::Pane => unreachable!("a pane has no nodes under it"),
+            Self::Cut { halves, .. } => match n - 1 {
+                n if n < halves[0].nodes() => halves[0].node(n),
+                n => halves[1].node(n - halves[0].nodes()),
+            },
+        }
+    }
+
+    fn node_mut(&mut self, n: usize) -> &mut Self {
+        if n == 0 {
+            return self;
+        }
+        match self {
+            Self::Pane => unreachable!("a pane has no nodes under it"),
+            Self::Cut { halves, .. } => {
+                let [left, right] = &mut **halves;
+                match n - 1 {
+                    n if n < left.nodes() => left.node_mut(n),
+                    n => right.node_mut(n - left.nodes()),
+                }
+            }
+        }
+    }
+
+    /// this with a part of it, anywhere but the whole, swapped for a part of `other`
+    pub fn crossed<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        let mut child = self.clone();
+        let at = match child.nodes() {
+            1 => 0,
+            nodes => rng.gen_range(1..nodes),
+        };
+        let from = other.node(rng.gen_range(0..other.nodes())).clone();
+        *child.node_mut(at) = from;
+        child
+    }
+
+    /// this with one thing changed: a pane cut in two, or a cut moved, turned or joined up
+    pub fn mutated<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        let mut child = self.clone();
+        let node = child.node_mut(rng.gen_range(0..self.nodes()));
+        match node {
+            Self::Pane => {
+                *node = Self::Cut {
+                    side_by_side: rng.gen(),
+                    at: rounded(rng.gen_range(0.2..0.8)),
+                    halves: Box::new([Self::Pane, Self::Pane]),
+                }
+            }
+            Self::Cut {
+                side_by_side, at, ..
+            } => match rng.gen_range(0..4) {
+                0 => *side_by_side = !*side_by_side,
+                1 => *node = Self::Pane,
+                _ => {
+                    let nudged = *at + rng.gen_range(-NUDGE..NUDGE);
+                    *at = rounded(nudged.clamp(MARGIN, 1.0 - MARGIN));
+                }
+            },
+        }
+        child
+    }
+
+    fn write(&self, words: &mut Vec<String>) {
+        match self {
+            Self::Pane => words.push(".".to_owned()),
+            Self::Cut {
+                side_by_side,
+                at,
+                halves,
+            } => {
+                let direction = if *side_by_side { '|' } else { '-' };
+                words.push(format!("{direction}{at}"));
+                halves[0].write(words);
+                halves[1].write(words);
+            }
+        }
+    }
+
+    fn read<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let word = words
+            .next()
+            .ok_or("the genome ends before all its cuts do")?;
+        let invalid = || format!("{word:?} isn't a cut; try |0.5, -0.5 or .");
+        let (side_by_side, at) = match word.split_at_checked(1) {
+            Some((".", "")) => return Ok(Self::Pane),
+            Some(("|", at)) => (true, at),
+            Some(("-", at)) => (false, at),
+            _ => return Err(invalid()),
+        };
+        let at = at
+            .parse::<f32>()
+            .ok()
+            .filter(|at| (0.0..=1.0).contains(at))
+            .ok_or_else(invalid)?;
+        let left = Self::read(words)?;
+        let right = Self::read(words)?;
+        Ok(Self::Cut {
+            side_by_side,
+            at,
+            halves: Box::new([left, right]),
+        })
+    }
+}
+
+/// `|0.5 . -0.25 . .`: a cut down the middle, and the right half cut a quarter of the way down
+impl fmt::Display for Genome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = vec![];
+        self.write(&mut words);
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+impl FromStr for Genome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let genome = Self::read(&mut words)?;
+        match words.next() {
+            None => Ok(genome),
+            Some(word) => Err(format!("{word:?} is past the end of the genome")),
+        }
+    }
+}
+
+/// to a thousandth, which is plenty, and keeps them short to write down
+fn rounded(at: f32) -> f32 {
+    (at * 1000.0).round() / 1000.0
+}
+
+/// one of a [`Population`]: how it's laid out, the seed it's colored with, and whether it's been
+/// picked to breed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub genome: Genome,
+    pub seed: u64,
+    pub favorite: bool,
+}
+
+/// a generation of layouts, written as `generation N` and then a line each of the seed and the
+/// genome, favorites starred: `* 42 |0.5 . .`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Population {
+    /// how many times it's been bred
+    pub generation: usize,
+    pub members: Vec<Member>,
+}
+
+impl Population {
+    /// the next generation, `size` strong: `parents`, by index, carried over unchanged and then
+    /// children of two of them (or of one, if there's only one), each changed a little more
+    pub fn bred<R: Rng + ?Sized>(&self, parents: &[usize], size: usize, rng: &mut R) -> Self {
+        let parents: Vec<&Member> = parents.iter().map(|&n| &self.members[n]).collect();
+        let mut members: Vec<Member> = parents
+            .iter()
+            .take(size)
+            .map(|parent| Member {
+                favorite: false,
+                ..(*parent).clone()
+            })
+            .collect();
+        while members.len() < size {
+            let Some(&mother) = parents.choose(rng) else {
+                break;
+            };
+            let father = parents.choose(rng).expect("there's a mother");
+            let genome = mother.genome.crossed(&father.genome, rng).mutated(rng);
+            // mostly the mother's colors, sometimes new ones
+            let seed = if rng.gen_bool(0.25) {
+                rng.gen()
+            } else {
+                mother.seed
+            };
+            members.push(Member {
+                genome,
+                seed,
+                favorite: false,
+            });
+        }
+        Self {
+            generation: self.generation + 1,
+            members,
+        }
+    }
+
+    /// the indices of the starred members
+    pub fn favorites(&self) -> Vec<usize> {
+        (0..self.members.len())
+            .filter(|&n| self.members[n].favorite)
+            .collect()
+    }
+}
+
+impl fmt::Display for Population {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "generation {}", self.generation)?;
+        for member in &self.members {
+            let star = if member.favorite { "* " } else { "" };
+            writeln!(f, "{star}{} {}", member.seed, member.genome)?;
+        }
+        Ok(())
+    }
+}
+
+/// blank lines and `#` comments are skipped
+impl FromStr for Population {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line.trim()))
+            .filter(|(_, line)| !(line.is_empty() || line.starts_with('#')));
+        let generation = match lines.next() {
+            Some((n, line)) => line
+                .strip_prefix("generation ")
+                .and_then(|generation| generation.trim().parse().ok())
+                .ok_or(format!("line {n}: a population starts with `generation N`"))?,
+            None => return Err("the population is empty".to_owned()),
+        };
+        let mut members = vec![];
+        for (n, line) in lines {
+            let (favorite, line) = match line.strip_prefix('*') {
+                Some(line) => (true, line.trim_start()),
+                None => (false, line),
+            };
+            let (seed, genome) = line
+                .split_once(char::is_whitespace)
+                .ok_or(format!("line {n}: members are a seed and a genome"))?;
+            members.push(Member {
+                genome: genome.parse().map_err(|err| format!("line {n}: {err}"))?,
+                seed: seed
+                    .parse()
+                    .map_err(|_| format!("line {n}: {seed:?} isn't a seed"))?,
+                favorite,
+            });
+        }
+        Ok(Self {
+            generation,
+            members,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+//! a C interface, to embed the generator in compositors, screensavers and anything else that
+//! isn't Rust: [`mondrian_generate`] renders straight into a buffer the caller owns
+//!
+//! `include/mondrian.h` declares it all; `cbindgen --config cbindgen.toml --output
+//! include/mondrian.h` writes it again after a change. to link against it, build the library as
+//! a C one:
+//!
+//! ```text
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! cc screensaver.c -Iinclude -Ltarget/release -lmondrian
+//! ```
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CString},
+    panic::{self, AssertUnwindSafe},
+    slice,
+};
+
+use crate::composition::CompositionBuilder;
+
+/// it worked
+pub const MONDRIAN_OK: c_int = 0;
+/// the buffer was null, or its stride too small for a row of pixels
+pub const MONDRIAN_BAD_BUFFER: c_int = -1;
+/// there's no such pixel format
+pub const MONDRIAN_BAD_FORMAT: c_int = -2;
+/// the composition couldn't be built, e.g. for a zero width or too many levels
+pub const MONDRIAN_BUILD_FAILED: c_int = -3;
+/// something went wrong inside that shouldn't have; it didn't get past the call
+pub const MONDRIAN_PANICKED: c_int = -4;
+
+/// three bytes a pixel: red, green, blue
+pub const MONDRIAN_RGB8: u32 = 0;
+/// four bytes a pixel: red, green, blue and an opaque alpha
+pub const MONDRIAN_RGBA8: u32 = 1;
+/// four bytes a pixel: blue, green, red and an opaque alpha, i.e. cairo's and pixman's ARGB32
+/// (and XRGB8888) on little-endian machines
+pub const MONDRIAN_BGRA8: u32 = 2;
+
+thread_local! {
+    /// why the last call on this thread failed, for [`mondrian_last_error`]
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// renders a Mondrian `width` by `height` pixels from `seed`, split `levels` times (0 for the
+/// default) in the classic palette, into `out_buf`: `height` rows of `format` pixels, each row
+/// starting `stride` bytes after the one before. returns [`MONDRIAN_OK`] or one of the other
+/// codes, and [`mondrian_last_error`] says why
+///
+/// the same arguments always give the same pixels, from any thread
+///
+/// # Safety
+///
+/// `out_buf` has to be null or point to `stride * height` writable bytes, which nothing else
+/// touches until this returns
+#[no_mangle]
+pub unsafe extern "C" fn mondrian_generate(
+    width: u32,
+    height: u32,
+    seed: u64,
+    levels: u32,
+    format: u32,
+    out_buf: *mut u8,
+    stride: usize,
+) -> c_int {
+    let channels = match format {
+        MONDRIAN_RGB8 => 3,
+        MONDRIAN_RGBA8 | MONDRIAN_BGRA8 => 4,
+        _ => return fail(MONDRIAN_BAD_FORMAT, format!("no pixel format {format}")),
+    };
+    if out_buf.is_null() {
+        return fail(MONDRIAN_BAD_BUFFER, "the buffer is null".to_owned());
+    }
+    let row = width as usize * channels;
+    let Some(size) = stride
+        .checked_mul(height as usize)
+        .filter(|_| stride >= row)
+    else {
+        return fail(
+            MONDRIAN_BAD_BUFFER,
+            format!("a stride of {stride} bytes doesn't fit {width} pixels a row"),
+        );
+    };
+    // SAFETY: the caller promises the buffer is this big and ours for now
+    let buffer = unsafe { slice::from_raw_parts_mut(out_buf, size) };
+    // unwinding into C is undefined, so a panic stops here
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut builder = CompositionBuilder::new().size(width, height).seed(seed);
+        if levels > 0 {
+            builder = builder.levels(levels as usize);
+        }
+        let composition = builder.build()?;
+        let image = composition.render_rgb();
+        for (row, pixels) in buffer.chunks_mut(stride).zip(image.rows()) {
+            for (out, pixel) in row.chunks_exact_mut(channels).zip(pixels) {
+                let [r, g, b] = pixel.0;
+                match format {
+                    MONDRIAN_RGB8 => out.copy_from_slice(&[r, g, b]),
+                    MONDRIAN_RGBA8 => out.copy_from_slice(&[r, g, b, 255]),
+                    _ => out.copy_from_slice(&[b, g, r, 255]),
+                }
+            }
+        }
+        Ok::<_, crate::composition::BuildError>(())
+    }));
+    match result {
+        Ok(Ok(())) => MONDRIAN_OK,
+        Ok(Err(err)) => fail(MONDRIAN_BUILD_FAILED, err.to_string()),
+        Err(_) => fail(MONDRIAN_PANICKED, "the generator panicked".to_owned()),
+    }
+}
+
+/// why the last call that failed on this thread did, as UTF-8; empty if none has. it's good
+/// until the next call that fails on the same thread, and isn't the caller's to free
+#[no_mangle]
+pub extern "C" fn mondrian_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+/// the crate version this was built from, e.g. `0.1.0`; static, and isn't the caller's to free
+#[no_mangle]
+pub extern "C" fn mondrian_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// keeps `why` for [`mondrian_last_error`], and hands back `code`
+fn fail(code: c_int, why: String) -> c_int {
+    // error messages are ours, so there's no NUL in them to trip over
+    let why = CString::new(why).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = why);
+    code
+}
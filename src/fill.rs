@@ -0,0 +1,293 @@
+//! deciding what color every pane gets
+
+use std::{fmt, str::FromStr};
+
+use clap::ValueEnum;
+use image::Rgb;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng, RngCore};
+
+use crate::{
+    color::Palette,
+    composition::Pane,
+    geometry::{Rectangle, SplittableGraphic},
+};
+
+/// how a filled pane picks from the palette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorStrategy {
+    /// straight from the palette weights
+    #[default]
+    Random,
+    /// big panes lean toward the most neutral color, small ones toward the saturated ones
+    AreaWeighted,
+}
+
+impl fmt::Display for ColorStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for ColorStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// knobs for picking pane colors, on top of the palette weights
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillOptions {
+    /// chance that a pane gets a palette color at all; the rest are left as `background`
+    pub probability: f32,
+    /// how strongly big panes avoid getting filled; 0 treats every pane the same, 1 makes the fill
+    /// chance inversely proportional to area
+    pub area_bias: f32,
+    /// what unfilled panes are left as
+    pub background: Rgb<u8>,
+    pub strategy: ColorStrategy,
+    /// a fill registered as a [plugin](crate::plugin) by this name, in place of `strategy`
+    pub plugin: Option<String>,
+    /// chance that a pane in a palette color gets stripes or a checkerboard in another one
+    pub pattern: f32,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self {
+            probability: 1.0,
+            area_bias: 0.0,
+            background: Rgb([255, 255, 255]),
+            strategy: ColorStrategy::default(),
+            plugin: None,
+            pattern: 0.0,
+        }
+    }
+}
+
+/// picks a color for every pane; [`ColorStrategy`] is the builtin one, and
+/// [plugins](crate::plugin) can bring more. the second color of a diagonal pane, and the colors of
+/// the styles made of shapes, always come from the builtin one
+pub trait FillStrategy: fmt::Debug {
+    /// a color for each of `panes`, in order; `palette` is already validated
+    fn colors(
+        &self,
+        panes: &[Rectangle],
+        palette: &Palette,
+        options: &FillOptions,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Rgb<u8>>;
+
+    /// a pattern to lay over each of `panes`, or none, once [`colors`](Self::colors) gave them
+    /// `colors`; by default [`patterns`]
+    fn patterns(
+        &self,
+        _panes: &[Rectangle],
+        colors: &[Rgb<u8>],
+        palette: &Palette,
+        options: &FillOptions,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Option<Pattern>> {
+        patterns(colors, palette, options, rng)
+    }
+}
+
+impl FillStrategy for ColorStrategy {
+    fn colors(
+        &self,
+        panes: &[Rectangle],
+        palette: &Palette,
+        options: &FillOptions,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Rgb<u8>> {
+        let areas: Vec<f64> = panes.iter().map(Rectangle::area).collect();
+        let options = FillOptions {
+            strategy: *self,
+            ..options.clone()
+        };
+        colors(&areas, palette, &options, rng)
+    }
+}
+
+/// colors `leaves` in order; `palette` must already be validated
+pub fn fill<R: Rng + ?Sized>(
+    leaves: Vec<Rectangle>,
+    palette: &Palette,
+    options: &FillOptions,
+    rng: &mut R,
+) -> Vec<Pane> {
+    let areas: Vec<f64> = leaves.iter().map(Rectangle::area).collect();
+    leaves
+        .into_iter()
+        .zip(colors(&areas, palette, options, rng))
+        .map(|(rectangle, color)| Pane {
+            rectangle,
+            color,
+            diagonal: None,
+            pattern: None,
+        })
+        .collect()
+}
+
+/// [`fill`] for shapes of any kind: a color for each of `areas`, in order
+pub fn colors<R: Rng + ?Sized>(
+    areas: &[f64],
+    palette: &Palette,
+    options: &FillOptions,
+    rng: &mut R,
+) -> Vec<Rgb<u8>> {
+    let colors = palette.colors();
+    let dist = WeightedIndex::new(palette.weights()).expect("palette was validated");
+
+    let neutral = most_neutral(colors);
+    let mean_area = areas.iter().sum::<f64>() / areas.len() as f64;
+
+    areas
+        .iter()
+        .map(|&area| {
+            // don't touch the rng unless we have to, so filling everything matches older output
+            let filled = options.probability >= 1.0 || {
+                let relative = mean_area / area.max(1.0);
+                let chance = options.probability as f64 * relative.powf(options.area_bias as f64);
+                rng.gen_bool(chance.clamp(0.0, 1.0))
+            };
+            if !filled {
+                options.background
+            } else if options.strategy == ColorStrategy::AreaWeighted {
+                let relative = area / mean_area;
+                let weights = palette.weights().iter().enumerate().map(|(i, weight)| {
+                    let lean = if i == neutral {
+                        relative
+                    } else {
+                        relative.recip()
+                    };
+                    *weight as f64 * lean
+                });
+                match WeightedIndex::new(weights) {
+                    Ok(dist) => colors[dist.sample(rng)],
+                    // only happens if every non-zero weight is on a color that leaned to 0
+                    Err(_) => colors[neutral],
+                }
+            } else {
+                colors[dist.sample(rng)]
+            }
+        })
+        .collect()
+}
+
+/// a second color over a [`Pane`]'s own, in bands or squares, like the fields of Mondrian's
+/// pier-and-ocean studies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pattern {
+    pub motif: Motif,
+    /// how many bands or rows of squares across the pane's short side; odd, so it looks the same
+    /// mirrored
+    pub bands: u32,
+    pub color: Rgb<u8>,
+}
+
+/// what a [`Pattern`] is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motif {
+    /// bands along the pane's long side, every other one in the pattern's color
+    Stripes,
+    /// squares, or as near as fit a whole odd number of them along the long side, every other
+    /// one in the pattern's color
+    Checkerboard,
+}
+
+impl Motif {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stripes => "stripes",
+            Self::Checkerboard => "checkerboard",
+        }
+    }
+}
+
+impl Pattern {
+    /// the parts of `pane` in the pattern's color, the ones in between being the pane's
+    pub fn rectangles(&self, pane: &Rectangle) -> Vec<Rectangle> {
+        let wide = pane.width >= pane.height;
+        let (long, short) = if wide {
+            (pane.width, pane.height)
+        } else {
+            (pane.height, pane.width)
+        };
+        let size = short / self.bands as f32;
+        let cells = match self.motif {
+            Motif::Stripes => 1,
+            Motif::Checkerboard => ((long / size).round() as u32).max(1) | 1,
+        };
+        let length = long / cells as f32;
+        let mut rectangles = vec![];
+        for band in 0..self.bands {
+            for cell in (0..cells).filter(|cell| (band + cell) % 2 == 1) {
+                let (along, across) = (cell as f32 * length, band as f32 * size);
+                rectangles.push(if wide {
+                    Rectangle::new(pane.x + along, pane.y + across, length, size)
+                } else {
+                    Rectangle::new(pane.x + across, pane.y + along, size, length)
+                });
+            }
+        }
+        rectangles
+    }
+}
+
+/// [`FillOptions::pattern`] of the panes in palette colors get stripes or a checkerboard, 3 to 9
+/// bands across, in one of the palette's other colors by weight
+pub fn patterns<R: Rng + ?Sized>(
+    colors: &[Rgb<u8>],
+    palette: &Palette,
+    options: &FillOptions,
+    rng: &mut R,
+) -> Vec<Option<Pattern>> {
+    // the rng is only touched when there are patterns, so older seeds still match
+    if options.pattern <= 0.0 {
+        return vec![None; colors.len()];
+    }
+    colors
+        .iter()
+        .map(|&color| {
+            if !palette.colors().contains(&color) || !rng.gen_bool(options.pattern as f64) {
+                return None;
+            }
+            let others = palette
+                .colors()
+                .iter()
+                .zip(palette.weights())
+                .map(|(&other, &weight)| if other == color { 0 } else { weight });
+            let dist = WeightedIndex::new(others).ok()?;
+            let motif = if rng.gen() {
+                Motif::Stripes
+            } else {
+                Motif::Checkerboard
+            };
+            Some(Pattern {
+                motif,
+                bands: 2 * rng.gen_range(1..=4) + 1,
+                color: palette.colors()[dist.sample(rng)],
+            })
+        })
+        .collect()
+}
+
+/// index of the least saturated color, which area weighting treats as the background-ish one
+fn most_neutral(colors: &[Rgb<u8>]) -> usize {
+    let saturation = |Rgb(channels): &Rgb<u8>| {
+        let max = *channels.iter().max().unwrap() as f32;
+        let min = *channels.iter().min().unwrap() as f32;
+        if max == 0.0 {
+            0.0
+        } else {
+            (max - min) / max
+        }
+    };
+    colors
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| saturation(a).total_cmp(&saturation(b)))
+        .map_or(0, |(i, _)| i)
+}
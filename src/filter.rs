@@ -0,0 +1,194 @@
+//! things done to a finished image, in order, like a darkroom would: a [`Pipeline`] of
+//! [`Filter`]s, e.g. `vignette,grain:0.2,blur:1.5,chromatic:1`
+//!
+//! a filter of your own only needs to implement [`Filter`] and go on the end with
+//! [`Pipeline::then`]
+
+use std::{error::Error, fmt, str::FromStr, sync::Arc};
+
+use image::{imageops, Rgb, RgbImage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// one step of a [`Pipeline`]
+pub trait Filter: fmt::Debug + Send + Sync {
+    fn apply(&self, image: &mut RgbImage);
+}
+
+/// why a pipeline couldn't be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterError {
+    Unknown(String),
+    /// an amount that isn't a number, or is out of range for the filter
+    Amount {
+        filter: String,
+        amount: String,
+    },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(
+                f,
+                "unknown filter {name:?}; use vignette, grain, blur or chromatic"
+            ),
+            Self::Amount { filter, amount } => {
+                write!(f, "{amount:?} isn't an amount {filter} can use")
+            }
+        }
+    }
+}
+
+impl Error for FilterError {}
+
+/// filters run one after the other, each on what the last one left
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    filters: Vec<Arc<dyn Filter>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `filter` after the ones already in it
+    pub fn then(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn apply(&self, image: &mut RgbImage) {
+        for filter in &self.filters {
+            filter.apply(image);
+        }
+    }
+}
+
+/// comma separated `name` or `name:amount`, e.g. `vignette,grain:0.2`; see each filter for what
+/// its amount means and what it is when left out
+impl FromStr for Pipeline {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pipeline = Self::new();
+        for step in s.split(',').map(str::trim).filter(|step| !step.is_empty()) {
+            let (name, amount) = match step.split_once(':') {
+                Some((name, amount)) => (name.trim(), Some(amount.trim())),
+                None => (step, None),
+            };
+            let amount = |default: f32, valid: fn(f32) -> bool| match amount {
+                None => Ok(default),
+                Some(written) => written
+                    .parse()
+                    .ok()
+                    .filter(|amount: &f32| amount.is_finite() && valid(*amount))
+                    .ok_or_else(|| FilterError::Amount {
+                        filter: name.to_owned(),
+                        amount: written.to_owned(),
+                    }),
+            };
+            pipeline = match name {
+                "vignette" => pipeline.then(Vignette(amount(0.5, |a| (0.0..=1.0).contains(&a))?)),
+                "grain" => pipeline.then(Grain(amount(0.1, |a| (0.0..=1.0).contains(&a))?)),
+                "blur" => pipeline.then(Blur(amount(1.0, |a| a > 0.0)?)),
+                "chromatic" => pipeline.then(Chromatic(amount(1.0, |a| a >= 0.0)?)),
+                _ => return Err(FilterError::Unknown(name.to_owned())),
+            };
+        }
+        Ok(pipeline)
+    }
+}
+
+/// darker towards the corners, by this much of the brightness right in them, 0.5 unless given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette(pub f32);
+
+impl Filter for Vignette {
+    fn apply(&self, image: &mut RgbImage) {
+        let (width, height) = image.dimensions();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let corner = (cx * cx + cy * cy).max(f32::EPSILON);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            // nothing in the middle, easing in towards the edges
+            let t = (dx * dx + dy * dy) / corner;
+            let light = 1.0 - self.0 * t * t;
+            *pixel = Rgb(pixel
+                .0
+                .map(|channel| (channel as f32 * light).round() as u8));
+        }
+    }
+}
+
+/// film grain: every pixel made lighter or darker at random, by up to this much of full
+/// brightness, 0.1 unless given. the same size image always gets the same grain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grain(pub f32);
+
+impl Filter for Grain {
+    fn apply(&self, image: &mut RgbImage) {
+        let (width, height) = image.dimensions();
+        let mut rng = ChaCha8Rng::seed_from_u64((width as u64) << 32 | height as u64);
+        let amount = self.0 * 255.0;
+        for pixel in image.pixels_mut() {
+            let noise = rng.gen_range(-amount..=amount);
+            *pixel = Rgb(pixel
+                .0
+                .map(|channel| (channel as f32 + noise).round().clamp(0.0, 255.0) as u8));
+        }
+    }
+}
+
+/// a gaussian blur this many pixels wide (the standard deviation), 1 unless given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blur(pub f32);
+
+impl Filter for Blur {
+    fn apply(&self, image: &mut RgbImage) {
+        *image = imageops::blur(image, self.0);
+    }
+}
+
+/// a cheap lens's color fringes: red pushed out from the middle and blue pulled in, this many
+/// pixels apart by the corners, 1 unless given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chromatic(pub f32);
+
+impl Filter for Chromatic {
+    fn apply(&self, image: &mut RgbImage) {
+        let (width, height) = image.dimensions();
+        if self.0 == 0.0 || width == 0 || height == 0 {
+            return;
+        }
+        let source = image.clone();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        // half the shift each way, as a share of the distance from the middle to a corner
+        let scale = self.0 / 2.0 / (cx * cx + cy * cy).sqrt().max(f32::EPSILON);
+        // `channel` from where `(x, y)` lands once it's moved `scale` of the way further out
+        let sample = |x: f32, y: f32, scale: f32, channel: usize| {
+            let (sx, sy) = (cx + (x - cx) * (1.0 + scale), cy + (y - cy) * (1.0 + scale));
+            let (sx, sy) = (
+                (sx - 0.5).clamp(0.0, (width - 1) as f32),
+                (sy - 0.5).clamp(0.0, (height - 1) as f32),
+            );
+            let (x0, y0) = (sx.floor() as u32, sy.floor() as u32);
+            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+            let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+            let at = |x, y| source.get_pixel(x, y).0[channel] as f32;
+            let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+            let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+            (top * (1.0 - fy) + bottom * fy).round() as u8
+        };
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            pixel.0[0] = sample(x, y, -scale, 0);
+            pixel.0[2] = sample(x, y, scale, 2);
+        }
+    }
+}
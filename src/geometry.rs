@@ -0,0 +1,306 @@
+use std::{error::Error, fmt};
+
+use rand::Rng;
+
+use crate::{noise::NoiseOptions, strategy::Builtin};
+
+/// cuts that aren't on whole pixels land on a grid this fine, so a parent's two halves always add
+/// back up to it exactly; f32 holds that exactly for canvases up to 65536 pixels a side
+pub const SUBPIXELS: f32 = 256.0;
+
+/// knobs that constrain how a graphic is allowed to split
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SplitOptions {
+    /// no child may be more than this many times longer than it is wide (or vice versa)
+    pub max_aspect: Option<f32>,
+    /// only cut on pixel boundaries, so every rectangle is a whole number of pixels
+    pub whole_pixels: bool,
+    /// where and which way to cut
+    pub strategy: Builtin,
+    /// chance that a pane is cut once more, corner to corner, into two triangles
+    pub diagonal: f32,
+    /// chance that a squarish pane is cut across its parent's cut when it would have been cut
+    /// the same way; see [`Crossed`](crate::strategy::Crossed)
+    pub cross: f32,
+    /// a split strategy registered as a [plugin](crate::plugin) by this name, in place of
+    /// `strategy`
+    pub plugin: Option<String>,
+    /// the field [`Builtin::Noise`] cuts by
+    pub noise: NoiseOptions,
+}
+
+pub trait SplittableGraphic
+where
+    Self: std::marker::Sized,
+{
+    fn new(x: f32, y: f32, width: f32, height: f32) -> Self;
+    fn split<R: Rng + ?Sized>(&self, options: &SplitOptions, rng: &mut R) -> (Self, Self);
+}
+
+/// in pixels, but not necessarily whole ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rectangle {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rectangle {
+    pub fn area(&self) -> f64 {
+        self.width as f64 * self.height as f64
+    }
+
+    /// the same rectangle on a canvas `factor` times the size
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self::new(
+            self.x * factor,
+            self.y * factor,
+            self.width * factor,
+            self.height * factor,
+        )
+    }
+
+    /// every side moved to the nearest pixel boundary, halves rounding up
+    pub fn snapped(&self) -> Self {
+        let (left, top) = (self.x.round(), self.y.round());
+        let right = (self.x + self.width).round();
+        let bottom = (self.y + self.height).round();
+        Self::new(left, top, right - left, bottom - top)
+    }
+}
+
+impl SplittableGraphic for Rectangle {
+    fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn split<R: Rng + ?Sized>(&self, options: &SplitOptions, rng: &mut R) -> (Self, Self) {
+        let mut horz_split = self.direction(rng);
+
+        let horz_range = split_range(self.width, self.height, options.max_aspect, (0.4, 0.6));
+        let vert_range = split_range(self.height, self.width, options.max_aspect, (0.4, 0.6));
+        let range = match (horz_range, vert_range) {
+            (Some(horz), Some(vert)) => {
+                if horz_split {
+                    horz
+                } else {
+                    vert
+                }
+            }
+            // only one direction keeps the children within the aspect limit, so force it
+            (Some(horz), None) => {
+                horz_split = true;
+                horz
+            }
+            (None, Some(vert)) => {
+                horz_split = false;
+                vert
+            }
+            // we're already past the limit; halving the long side gets closest to it
+            (None, None) => {
+                horz_split = self.width >= self.height;
+                (0.5, 0.5)
+            }
+        };
+
+        self.divide(horz_split, rng.gen_range(range.0..=range.1), options)
+    }
+}
+
+impl Rectangle {
+    /// true to cut the width (children side by side), false to cut the height; across the long
+    /// side if it's at least 3 times the short one, otherwise a coin flip
+    pub fn direction<R: Rng + ?Sized>(&self, rng: &mut R) -> bool {
+        // if ratio is fucked, don't randomly select split direction; same as `width / height > 2`,
+        // but a zero-pixel side doesn't divide by zero
+        if self.width >= 3.0 * self.height {
+            true
+        } else if self.height >= 3.0 * self.width {
+            false
+        } else {
+            rng.gen()
+        }
+    }
+
+    /// cuts `t` of the way across the width if `horz_split`, otherwise down the height, rounding
+    /// the cut down to a whole pixel or the [`SUBPIXELS`] grid
+    pub fn divide(&self, horz_split: bool, t: f32, options: &SplitOptions) -> (Self, Self) {
+        let cut = |side: f32| {
+            let cut = side * t;
+            if options.whole_pixels {
+                cut.trunc()
+            } else {
+                (cut * SUBPIXELS).trunc() / SUBPIXELS
+            }
+        };
+        // both halves are on the same grid as the parent, so these add and subtract exactly and
+        // the halves meet with no gap or overlap
+        if horz_split {
+            let width = cut(self.width);
+            (
+                Self::new(self.x, self.y, width, self.height),
+                Self::new(self.x + width, self.y, self.width - width, self.height),
+            )
+        } else {
+            let height = cut(self.height);
+            (
+                Self::new(self.x, self.y, self.width, height),
+                Self::new(self.x, self.y + height, self.width, self.height - height),
+            )
+        }
+    }
+
+    /// [`divide`](Self::divide), for strategies that pick their own cut: `t` is moved as little
+    /// as it takes to keep both halves within `max_aspect`, and the direction is swapped if
+    /// that's the only way to
+    pub fn divide_within(&self, horz_split: bool, t: f32, options: &SplitOptions) -> (Self, Self) {
+        let range = |horz_split: bool| {
+            let (along, across) = if horz_split {
+                (self.width, self.height)
+            } else {
+                (self.height, self.width)
+            };
+            split_range(along, across, options.max_aspect, (0.0, 1.0))
+        };
+        match (range(horz_split), range(!horz_split)) {
+            (Some((low, high)), _) => self.divide(horz_split, t.clamp(low, high), options),
+            (None, Some((low, high))) => self.divide(!horz_split, t.clamp(low, high), options),
+            (None, None) => self.divide(self.width >= self.height, 0.5, options),
+        }
+    }
+}
+
+/// which fractions of `along` inside `window` we can cut at so both children stay within
+/// `max_aspect`, when cutting across a side of length `along` with the other side `across` long
+fn split_range(
+    along: f32,
+    across: f32,
+    max_aspect: Option<f32>,
+    (mut low, mut high): (f32, f32),
+) -> Option<(f32, f32)> {
+    if let Some(max_aspect) = max_aspect {
+        // a child t * along wide must be at least across / max_aspect and at most across * max_aspect
+        // wide, and the same goes for its sibling at (1 - t) * along
+        let narrowest = across / (max_aspect * along);
+        let widest = max_aspect * across / along;
+        low = low.max(narrowest).max(1.0 - widest);
+        high = high.min(widest).min(1.0 - narrowest);
+    }
+    (low <= high).then_some((low, high))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// runs left to right
+    Horizontal,
+    /// runs top to bottom
+    Vertical,
+}
+
+/// the line a split left behind between two siblings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    /// where the line starts; lines are centered on this, not drawn to one side of it
+    pub x: f32,
+    pub y: f32,
+    pub length: f32,
+    pub orientation: Orientation,
+    /// depth of the node that was split
+    pub depth: usize,
+}
+
+impl Edge {
+    /// the band of pixels this edge covers when drawn `width` thick
+    pub fn to_rectangle(&self, width: u32) -> Rectangle {
+        let (width, half) = (width as f32, width as f32 / 2.0);
+        match self.orientation {
+            Orientation::Horizontal => {
+                Rectangle::new(self.x, (self.y - half).max(0.0), self.length, width)
+            }
+            Orientation::Vertical => {
+                Rectangle::new((self.x - half).max(0.0), self.y, width, self.length)
+            }
+        }
+    }
+}
+
+/// how a set of rectangles failed to tile the area they were meant to cover
+#[derive(Debug, Clone, PartialEq)]
+pub enum TilingError {
+    /// a tile pokes out of the area
+    OutOfBounds { index: usize, tile: Rectangle },
+    /// two tiles cover some of the same area
+    Overlap { first: usize, second: usize },
+    /// the tiles don't overlap, but leave this much area uncovered
+    Gap { area: f64 },
+}
+
+impl fmt::Display for TilingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { index, tile } => write!(
+                f,
+                "pane {index} ({}x{} at {},{}) is outside the canvas",
+                tile.width, tile.height, tile.x, tile.y
+            ),
+            Self::Overlap { first, second } => write!(f, "panes {first} and {second} overlap"),
+            Self::Gap { area } => write!(f, "panes leave {area} square pixels uncovered"),
+        }
+    }
+}
+
+impl Error for TilingError {}
+
+/// checks that `tiles` cover `bounds` exactly once: every tile inside it, no two overlapping, and
+/// their areas adding up to all of it; tiles with no area are allowed anywhere inside
+pub fn check_tiling(bounds: &Rectangle, tiles: &[Rectangle]) -> Result<(), TilingError> {
+    let right = |r: &Rectangle| r.x + r.width;
+    let bottom = |r: &Rectangle| r.y + r.height;
+    for (index, tile) in tiles.iter().enumerate() {
+        let inside = tile.width >= 0.0
+            && tile.height >= 0.0
+            && tile.x >= bounds.x
+            && tile.y >= bounds.y
+            && right(tile) <= right(bounds)
+            && bottom(tile) <= bottom(bounds);
+        if !inside {
+            return Err(TilingError::OutOfBounds { index, tile: *tile });
+        }
+    }
+
+    // sweep left to right; only tiles that start before this one ends can overlap it
+    let mut order: Vec<usize> = (0..tiles.len())
+        .filter(|&i| tiles[i].area() > 0.0)
+        .collect();
+    order.sort_by(|&a, &b| tiles[a].x.total_cmp(&tiles[b].x));
+    for (n, &first) in order.iter().enumerate() {
+        let tile = &tiles[first];
+        for &second in &order[n + 1..] {
+            let other = &tiles[second];
+            if other.x >= right(tile) {
+                break;
+            }
+            if other.y < bottom(tile) && tile.y < bottom(other) {
+                return Err(TilingError::Overlap {
+                    first: first.min(second),
+                    second: first.max(second),
+                });
+            }
+        }
+    }
+
+    // on the subpixel grid every area and partial sum is exact in f64
+    let covered: f64 = tiles.iter().map(Rectangle::area).sum();
+    let gap = bounds.area() - covered;
+    if gap != 0.0 {
+        return Err(TilingError::Gap { area: gap });
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! the honeycomb style: hexagons, pointy side up, in offset rows, the grid set down somewhere
+//! random so the canvas edges cut it in different places
+
+use rand::Rng;
+
+use crate::{
+    geometry::Rectangle,
+    polygon::{Point, Polygon, Segment},
+};
+
+/// a corner's offset from its hexagon's middle, in half widths across and quarter heights down,
+/// going round clockwise from the top
+const CORNERS: [(i32, i32); 6] = [(0, -2), (1, -1), (1, 1), (0, 2), (-1, 1), (-1, -1)];
+
+/// about `count` hexagons covering `bounds`, cut off where they run past it, and every line
+/// between two of them once; the canvas edges aren't lines
+pub fn cells<R: Rng + ?Sized>(
+    bounds: &Rectangle,
+    count: usize,
+    rng: &mut R,
+) -> (Vec<Polygon>, Vec<Segment>) {
+    // a hexagon's area is 3√3/2 times the square of its side
+    let side = (bounds.width * bounds.height / count.max(1) as f32 / (1.5 * 3f32.sqrt())).sqrt();
+    let (half_width, quarter_height) = (side * 3f32.sqrt() / 2.0, side / 2.0);
+    let origin = Point::new(
+        bounds.x - rng.gen::<f32>() * 2.0 * half_width,
+        bounds.y - rng.gen::<f32>() * 3.0 * quarter_height,
+    );
+    // every corner is a whole number of steps from the origin, so neighbors share them exactly
+    let at = |across: i32, down: i32| {
+        Point::new(
+            origin.x + across as f32 * half_width,
+            origin.y + down as f32 * quarter_height,
+        )
+    };
+    let columns = (bounds.width / (2.0 * half_width)).ceil() as i32 + 1;
+    let rows = (bounds.height / (3.0 * quarter_height)).ceil() as i32 + 1;
+
+    let mut hexagons = vec![];
+    let mut segments = vec![];
+    // a row and a column past each side, for the ones the half-hexagon overhang reaches
+    for row in -1..=rows {
+        for column in -1..=columns {
+            let (across, down) = (2 * column + (row & 1), 3 * row);
+            let corners = CORNERS.map(|(x, y)| at(across + x, down + y));
+            let hexagon = Polygon::new(corners).clipped_to(bounds);
+            if hexagon.area() > 0.0 {
+                hexagons.push(hexagon);
+            }
+            // the right side and the two below, which are nobody else's; the rest are the
+            // neighbors' to the left and above
+            segments.extend((1..4).filter_map(|i| {
+                let segment = Segment {
+                    from: corners[i],
+                    to: corners[i + 1],
+                };
+                segment
+                    .clipped_to(bounds)
+                    .filter(|segment| !along_edge(segment, bounds))
+            }));
+        }
+    }
+    (hexagons, segments)
+}
+
+/// whether `segment` runs right along one of `bounds`' sides, where the frame goes instead
+fn along_edge(segment: &Segment, bounds: &Rectangle) -> bool {
+    let (right, bottom) = (bounds.x + bounds.width, bounds.y + bounds.height);
+    let (from, to) = (segment.from, segment.to);
+    (from.x == to.x && (from.x == bounds.x || from.x == right))
+        || (from.y == to.y && (from.y == bounds.y || from.y == bottom))
+}
@@ -0,0 +1,760 @@
+//! just enough of ICC profiles to turn sRGB into a printer's CMYK: the PCS-to-device lookup
+//! tables of an output profile, evaluated without a color management library
+
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use image::{Rgb, RgbImage};
+
+use crate::color::to_linear;
+
+/// sRGB to XYZ, already adapted to the D50 white the profile connection space uses
+const SRGB_TO_XYZ_D50: [[f32; 3]; 3] = [
+    [0.436_074_7, 0.385_064_9, 0.143_080_4],
+    [0.222_504_5, 0.716_878_6, 0.060_616_9],
+    [0.013_932_2, 0.097_104_5, 0.714_173_3],
+];
+
+/// XYZ of the D50 white
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+
+/// why a profile couldn't be used
+#[derive(Debug)]
+pub enum IccError {
+    Io(io::Error),
+    /// not an ICC profile, or a broken one
+    Invalid(String),
+    /// a real profile, just not one that turns colors into CMYK in a way we understand
+    Unsupported(String),
+}
+
+impl fmt::Display for IccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the profile: {err}"),
+            Self::Invalid(why) => write!(f, "not a usable ICC profile: {why}"),
+            Self::Unsupported(why) => write!(f, "unsupported ICC profile: {why}"),
+        }
+    }
+}
+
+impl Error for IccError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IccError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// how the profile connection space is encoded into a table's inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pcs {
+    /// CIELAB, with L* from 0 to 100 and a*, b* from -128 to 127 spread over 0 to `scale`
+    Lab { scale: f32 },
+    /// CIEXYZ, from 0 to a little under 2
+    Xyz,
+}
+
+/// a tone curve for one channel, from 0 to 1 to 0 to 1
+#[derive(Debug, Clone, PartialEq)]
+enum Curve {
+    /// evenly spaced samples, linearly interpolated
+    Table(Vec<f32>),
+    Gamma(f32),
+    /// one of the `para` functions, by type, with its parameters
+    Parametric(u16, [f32; 7]),
+}
+
+impl Curve {
+    fn apply(&self, x: f32) -> f32 {
+        let y = match self {
+            Self::Table(table) => interpolate(table, x),
+            Self::Gamma(gamma) => x.powf(*gamma),
+            Self::Parametric(kind, [g, a, b, c, d, e, f]) => match kind {
+                0 => x.powf(*g),
+                1 if x >= -b / a => (a * x + b).powf(*g),
+                1 => 0.0,
+                2 if x >= -b / a => (a * x + b).powf(*g) + c,
+                2 => *c,
+                3 if x >= *d => (a * x + b).powf(*g),
+                3 => c * x,
+                4 if x >= *d => (a * x + b).powf(*g) + e,
+                _ => c * x + f,
+            },
+        };
+        y.clamp(0.0, 1.0)
+    }
+}
+
+/// a grid of output values over the unit cube, interpolated trilinearly
+#[derive(Debug, Clone, PartialEq)]
+struct Clut {
+    /// grid points along each of the three inputs
+    grid: [usize; 3],
+    outputs: usize,
+    /// the first input varies slowest
+    values: Vec<f32>,
+}
+
+impl Clut {
+    fn apply(&self, input: &[f32]) -> Vec<f32> {
+        let mut low = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let position = input[axis].clamp(0.0, 1.0) * (self.grid[axis] - 1) as f32;
+            low[axis] = (position.floor() as usize).min(self.grid[axis].saturating_sub(2));
+            fraction[axis] = position - low[axis] as f32;
+        }
+        let mut output = vec![0.0; self.outputs];
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = 0;
+            for axis in 0..3 {
+                let up = corner >> (2 - axis) & 1;
+                weight *= if up == 1 {
+                    fraction[axis]
+                } else {
+                    1.0 - fraction[axis]
+                };
+                let point = (low[axis] + up).min(self.grid[axis] - 1);
+                index = index * self.grid[axis] + point;
+            }
+            if weight == 0.0 {
+                continue;
+            }
+            let values = &self.values[index * self.outputs..][..self.outputs];
+            for (out, value) in output.iter_mut().zip(values) {
+                *out += weight * value;
+            }
+        }
+        output
+    }
+}
+
+/// one stage of a lookup table
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Curves(Vec<Curve>),
+    /// a 3×3 matrix then an offset, on values from 0 to 1
+    Matrix([[f32; 3]; 3], [f32; 3]),
+    Clut(Clut),
+}
+
+/// an output profile for a CMYK printing process
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    bytes: Vec<u8>,
+    pcs: Pcs,
+    steps: Vec<Step>,
+}
+
+impl Profile {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, IccError> {
+        Self::parse(fs::read(path)?)
+    }
+
+    /// a profile from its bytes; it has to be for CMYK and have a `B2A1` (relative colorimetric,
+    /// which keeps flat colors true) or `B2A0` (perceptual) table
+    pub fn parse(bytes: Vec<u8>) -> Result<Self, IccError> {
+        let invalid = |why: &str| IccError::Invalid(why.to_owned());
+        if bytes.len() < 132 || &bytes[36..40] != b"acsp" {
+            return Err(invalid("there's no `acsp` signature in the header"));
+        }
+        if &bytes[16..20] != b"CMYK" {
+            return Err(IccError::Unsupported(format!(
+                "its color space is {}, not CMYK",
+                String::from_utf8_lossy(&bytes[16..20]).trim_end()
+            )));
+        }
+        let xyz = match &bytes[20..24] {
+            b"Lab " => false,
+            b"XYZ " => true,
+            _ => return Err(invalid("the connection space isn't Lab or XYZ")),
+        };
+
+        let count = read_u32(&bytes, 128)? as usize;
+        let mut tags = vec![];
+        for index in 0..count {
+            let at = 132 + index * 12;
+            let signature = bytes
+                .get(at..at + 4)
+                .ok_or_else(|| invalid("the tag table is cut off"))?;
+            let offset = read_u32(&bytes, at + 4)? as usize;
+            let size = read_u32(&bytes, at + 8)? as usize;
+            tags.push((signature, offset, size));
+        }
+        let find = |wanted: &[u8]| tags.iter().find(|(signature, ..)| *signature == wanted);
+        let Some(&(_, offset, size)) = find(b"B2A1").or_else(|| find(b"B2A0")) else {
+            return Err(IccError::Unsupported(
+                "it has no table from Lab or XYZ to CMYK (B2A0 or B2A1)".to_owned(),
+            ));
+        };
+        let tag = bytes
+            .get(offset..offset.saturating_add(size))
+            .ok_or_else(|| invalid("a tag runs past the end of the file"))?;
+        let (pcs, steps) = parse_lut(tag, xyz)?;
+        Ok(Self { bytes, pcs, steps })
+    }
+
+    /// the whole profile, to embed in a file
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// `color`, taken as sRGB, in this profile's ink amounts from 0 to 255
+    pub fn to_cmyk(&self, color: Rgb<u8>) -> [u8; 4] {
+        let linear = color.0.map(|channel| to_linear(channel as f32 / 255.0));
+        let xyz = SRGB_TO_XYZ_D50.map(|row| row.iter().zip(linear).map(|(m, c)| m * c).sum());
+        let mut values: Vec<f32> = match self.pcs {
+            Pcs::Xyz => xyz
+                .iter()
+                .map(|value| value / (1.0 + 32767.0 / 32768.0))
+                .collect(),
+            Pcs::Lab { scale } => {
+                let [l, a, b] = lab(xyz);
+                vec![
+                    l / 100.0 * scale,
+                    (a + 128.0) / 255.0 * scale,
+                    (b + 128.0) / 255.0 * scale,
+                ]
+            }
+        };
+        for step in &self.steps {
+            values = match step {
+                Step::Curves(curves) => curves
+                    .iter()
+                    .zip(&values)
+                    .map(|(curve, value)| curve.apply(*value))
+                    .collect(),
+                Step::Matrix(matrix, offset) => (0..3)
+                    .map(|row| {
+                        let value: f32 = (0..3)
+                            .map(|column| matrix[row][column] * values[column])
+                            .sum();
+                        (value + offset[row]).clamp(0.0, 1.0)
+                    })
+                    .collect(),
+                Step::Clut(clut) => clut.apply(&values),
+            };
+        }
+        let mut cmyk = [0; 4];
+        for (ink, value) in cmyk.iter_mut().zip(values) {
+            *ink = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        cmyk
+    }
+}
+
+/// `color` in CMYK with no profile: as much black as the lightest channel allows, and the rest
+/// made up with cyan, magenta and yellow; fine for proofs, but no substitute for the print
+/// shop's profile
+pub fn naive_cmyk(color: Rgb<u8>) -> [u8; 4] {
+    let [r, g, b] = color.0.map(|channel| channel as f32 / 255.0);
+    let black = 1.0 - r.max(g).max(b);
+    if black >= 1.0 {
+        return [0, 0, 0, 255];
+    }
+    let ink = |channel: f32| ((1.0 - channel - black) / (1.0 - black) * 255.0).round() as u8;
+    [ink(r), ink(g), ink(b), (black * 255.0).round() as u8]
+}
+
+/// every pixel of `image` in CMYK through `profile`, or [`naive_cmyk`] without one, four bytes
+/// each
+pub fn convert(image: &RgbImage, profile: Option<&Profile>) -> Vec<u8> {
+    // compositions are mostly long runs of one color, so the last one is nearly always it
+    let mut last = None;
+    let mut samples = Vec::with_capacity(image.width() as usize * image.height() as usize * 4);
+    for pixel in image.pixels() {
+        let cmyk = match last {
+            Some((color, cmyk)) if color == *pixel => cmyk,
+            _ => {
+                let cmyk = match profile {
+                    Some(profile) => profile.to_cmyk(*pixel),
+                    None => naive_cmyk(*pixel),
+                };
+                last = Some((*pixel, cmyk));
+                cmyk
+            }
+        };
+        samples.extend_from_slice(&cmyk);
+    }
+    samples
+}
+
+/// D50 XYZ to CIELAB
+fn lab(xyz: [f32; 3]) -> [f32; 3] {
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let [x, y, z] = [0, 1, 2].map(|axis| f(xyz[axis] / D50[axis]));
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+/// `table` read at `x` from 0 to 1
+fn interpolate(table: &[f32], x: f32) -> f32 {
+    match table {
+        [] => x,
+        [only] => *only,
+        _ => {
+            let position = x.clamp(0.0, 1.0) * (table.len() - 1) as f32;
+            let low = (position.floor() as usize).min(table.len() - 2);
+            let fraction = position - low as f32;
+            table[low] * (1.0 - fraction) + table[low + 1] * fraction
+        }
+    }
+}
+
+/// a `mft1`, `mft2` or `mBA ` tag, as the connection space it expects and what it does to it
+fn parse_lut(tag: &[u8], xyz: bool) -> Result<(Pcs, Vec<Step>), IccError> {
+    let invalid = |why: &str| IccError::Invalid(why.to_owned());
+    if tag.len() < 12 {
+        return Err(invalid("the B2A table is cut off"));
+    }
+    let (inputs, outputs) = (tag[8] as usize, tag[9] as usize);
+    if inputs != 3 || outputs != 4 {
+        return Err(invalid("the B2A table doesn't go from 3 channels to 4"));
+    }
+    match &tag[0..4] {
+        kind @ (b"mft1" | b"mft2") => {
+            let sixteen = kind == b"mft2";
+            let grid = tag[10] as usize;
+            if grid < 2 {
+                return Err(invalid("the color table has fewer than 2 points a side"));
+            }
+            let mut matrix = [[0.0; 3]; 3];
+            for (index, entry) in matrix.iter_mut().flatten().enumerate() {
+                *entry = read_s15f16(tag, 12 + index * 4)?;
+            }
+            let (input_entries, output_entries, mut at) = if sixteen {
+                (read_u16(tag, 48)? as usize, read_u16(tag, 50)? as usize, 52)
+            } else {
+                (256, 256, 48)
+            };
+            let (size, max) = if sixteen { (2, 65535.0) } else { (1, 255.0) };
+            let mut samples = |count: usize| -> Result<Vec<f32>, IccError> {
+                let bytes = tag
+                    .get(at..at + count * size)
+                    .ok_or_else(|| invalid("the B2A table is cut off"))?;
+                at += count * size;
+                Ok(bytes
+                    .chunks_exact(size)
+                    .map(|sample| match sample {
+                        [high, low] => u16::from_be_bytes([*high, *low]) as f32 / max,
+                        [byte] => *byte as f32 / max,
+                        _ => unreachable!("chunks are one or two bytes"),
+                    })
+                    .collect())
+            };
+            let input = (0..inputs)
+                .map(|_| samples(input_entries).map(Curve::Table))
+                .collect::<Result<Vec<_>, _>>()?;
+            let clut = Clut {
+                grid: [grid; 3],
+                outputs,
+                values: samples(grid.pow(3) * outputs)?,
+            };
+            let output = (0..outputs)
+                .map(|_| samples(output_entries).map(Curve::Table))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // the legacy encoding of Lab tops out at 0xff00 in 16 bits
+            let pcs = match (xyz, sixteen) {
+                (true, _) => Pcs::Xyz,
+                (false, true) => Pcs::Lab {
+                    scale: 65280.0 / 65535.0,
+                },
+                (false, false) => Pcs::Lab { scale: 1.0 },
+            };
+            let mut steps = vec![];
+            // only ever used for XYZ, though some profiles fill it in either way
+            if xyz {
+                steps.push(Step::Matrix(matrix, [0.0; 3]));
+            }
+            steps.extend([Step::Curves(input), Step::Clut(clut), Step::Curves(output)]);
+            Ok((pcs, steps))
+        }
+        b"mBA " => {
+            let offset = |at: usize| read_u32(tag, at).map(|offset| offset as usize);
+            let (b, matrix, m, clut, a) = (
+                offset(12)?,
+                offset(16)?,
+                offset(20)?,
+                offset(24)?,
+                offset(28)?,
+            );
+            if b == 0 {
+                return Err(invalid("the B2A table has no B curves"));
+            }
+            let mut steps = vec![Step::Curves(parse_curves(tag, b, inputs)?)];
+            if matrix != 0 {
+                let mut values = [0.0; 12];
+                for (index, value) in values.iter_mut().enumerate() {
+                    *value = read_s15f16(tag, matrix + index * 4)?;
+                }
+                let rows = [0, 1, 2].map(|row| [0, 1, 2].map(|column| values[row * 3 + column]));
+                steps.push(Step::Matrix(rows, [values[9], values[10], values[11]]));
+            }
+            if m != 0 {
+                steps.push(Step::Curves(parse_curves(tag, m, inputs)?));
+            }
+            if clut != 0 {
+                steps.push(Step::Clut(parse_clut(tag, clut, outputs)?));
+            } else {
+                return Err(IccError::Unsupported(
+                    "its B2A table has no color table to get from 3 channels to 4".to_owned(),
+                ));
+            }
+            if a != 0 {
+                steps.push(Step::Curves(parse_curves(tag, a, outputs)?));
+            }
+            let pcs = if xyz {
+                Pcs::Xyz
+            } else {
+                Pcs::Lab { scale: 1.0 }
+            };
+            Ok((pcs, steps))
+        }
+        other => Err(IccError::Unsupported(format!(
+            "its B2A table is a {:?}, not an mft1, mft2 or mBA",
+            String::from_utf8_lossy(other)
+        ))),
+    }
+}
+
+/// `count` `curv` or `para` curves one after another from `at`, each padded to four bytes
+fn parse_curves(tag: &[u8], mut at: usize, count: usize) -> Result<Vec<Curve>, IccError> {
+    let mut curves = vec![];
+    for _ in 0..count {
+        let kind = tag
+            .get(at..at + 4)
+            .ok_or_else(|| IccError::Invalid("a curve is cut off".to_owned()))?;
+        let (curve, length) = match kind {
+            b"curv" => {
+                let entries = read_u32(tag, at + 8)? as usize;
+                let curve = match entries {
+                    0 => Curve::Gamma(1.0),
+                    1 => Curve::Gamma(read_u16(tag, at + 12)? as f32 / 256.0),
+                    _ => Curve::Table(
+                        (0..entries)
+                            .map(|index| {
+                                read_u16(tag, at + 12 + index * 2).map(|v| v as f32 / 65535.0)
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                };
+                (curve, 12 + entries * 2)
+            }
+            b"para" => {
+                let kind = read_u16(tag, at + 8)?;
+                let count = match kind {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    4 => 7,
+                    _ => {
+                        return Err(IccError::Unsupported(format!(
+                            "parametric curve type {kind}"
+                        )))
+                    }
+                };
+                let mut parameters = [0.0; 7];
+                for (index, parameter) in parameters.iter_mut().take(count).enumerate() {
+                    *parameter = read_s15f16(tag, at + 12 + index * 4)?;
+                }
+                (Curve::Parametric(kind, parameters), 12 + count * 4)
+            }
+            _ => {
+                return Err(IccError::Invalid(
+                    "a curve that isn't a curv or para".to_owned(),
+                ))
+            }
+        };
+        curves.push(curve);
+        at += length.div_ceil(4) * 4;
+    }
+    Ok(curves)
+}
+
+/// the color table of an `mBA `, with its own number of points along each input
+fn parse_clut(tag: &[u8], at: usize, outputs: usize) -> Result<Clut, IccError> {
+    let cut_off = || IccError::Invalid("the color table is cut off".to_owned());
+    let header = tag.get(at..at + 20).ok_or_else(cut_off)?;
+    let grid = [header[0], header[1], header[2]].map(|points| points as usize);
+    if grid.iter().any(|points| *points < 2) {
+        return Err(IccError::Invalid(
+            "the color table has fewer than 2 points a side".to_owned(),
+        ));
+    }
+    let precision = header[16] as usize;
+    let count = grid.iter().product::<usize>() * outputs;
+    let bytes = tag
+        .get(at + 20..at + 20 + count * precision)
+        .ok_or_else(cut_off)?;
+    let values = match precision {
+        1 => bytes.iter().map(|byte| *byte as f32 / 255.0).collect(),
+        2 => bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as f32 / 65535.0)
+            .collect(),
+        _ => {
+            return Err(IccError::Invalid(
+                "the color table isn't 8 or 16 bits".to_owned(),
+            ))
+        }
+    };
+    Ok(Clut {
+        grid,
+        outputs,
+        values,
+    })
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, IccError> {
+    match bytes.get(at..at + 2) {
+        Some(&[high, low]) => Ok(u16::from_be_bytes([high, low])),
+        _ => Err(IccError::Invalid("it ends too soon".to_owned())),
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, IccError> {
+    match bytes.get(at..at + 4) {
+        Some(&[a, b, c, d]) => Ok(u32::from_be_bytes([a, b, c, d])),
+        _ => Err(IccError::Invalid("it ends too soon".to_owned())),
+    }
+}
+
+/// a signed 15.16 fixed point number
+fn read_s15f16(bytes: &[u8], at: usize) -> Result<f32, IccError> {
+    Ok(read_u32(bytes, at)? as i32 as f32 / 65536.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: [u8; 4] = 65536i32.to_be_bytes();
+
+    /// a profile for `space` through `pcs` with `tags`, laid out one after another after the
+    /// tag table
+    fn profile(space: &[u8; 4], pcs: &[u8; 4], tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = vec![0; 128];
+        bytes[16..20].copy_from_slice(space);
+        bytes[20..24].copy_from_slice(pcs);
+        bytes[36..40].copy_from_slice(b"acsp");
+        bytes.extend((tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + 12 * tags.len();
+        for (signature, tag) in tags {
+            bytes.extend_from_slice(*signature);
+            bytes.extend((offset as u32).to_be_bytes());
+            bytes.extend((tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+        }
+        for (_, tag) in tags {
+            bytes.extend(tag);
+        }
+        bytes
+    }
+
+    /// the corners of a two-point color table that puts the first input in cyan and the rest
+    /// of it in black, `max` being full
+    fn corners(max: u16) -> Vec<u16> {
+        (0..8)
+            .flat_map(|corner| {
+                let first = corner >> 2;
+                [first * max, 0, 0, (1 - first) * max]
+            })
+            .collect()
+    }
+
+    fn mft2() -> Vec<u8> {
+        let mut tag = b"mft2\0\0\0\0".to_vec();
+        tag.extend([3, 4, 2, 0]);
+        for index in 0..9 {
+            tag.extend(if index % 4 == 0 { ONE } else { [0; 4] });
+        }
+        tag.extend([0, 2, 0, 2]);
+        let identity = [0u16, 65535];
+        let values = [identity.repeat(3), corners(65535), identity.repeat(4)].concat();
+        tag.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+        tag
+    }
+
+    fn mft1() -> Vec<u8> {
+        let mut tag = b"mft1\0\0\0\0".to_vec();
+        tag.extend([3, 4, 2, 0]);
+        for index in 0..9 {
+            tag.extend(if index % 4 == 0 { ONE } else { [0; 4] });
+        }
+        let identity: Vec<u8> = (0..=255).collect();
+        tag.extend(identity.repeat(3));
+        tag.extend(corners(255).iter().map(|value| *value as u8));
+        tag.extend(identity.repeat(4));
+        tag
+    }
+
+    fn mba() -> Vec<u8> {
+        let mut tag = b"mBA \0\0\0\0".to_vec();
+        tag.extend([3, 4, 0, 0]);
+        for offset in [32u32, 0, 0, 68, 120] {
+            tag.extend(offset.to_be_bytes());
+        }
+        // the B curves, each a `curv` with no entries: the identity
+        for _ in 0..3 {
+            tag.extend(b"curv\0\0\0\0\0\0\0\0");
+        }
+        tag.extend([2, 2, 2]);
+        tag.extend([0; 13]);
+        tag.extend([1, 0, 0, 0]);
+        tag.extend(corners(255).iter().map(|value| *value as u8));
+        // the A curves, each a `para` of gamma 1
+        for _ in 0..4 {
+            tag.extend(b"para\0\0\0\0\0\0\0\0");
+            tag.extend(ONE);
+        }
+        tag
+    }
+
+    fn identity() -> Vec<Curve> {
+        vec![Curve::Table(vec![0.0, 1.0]); 3]
+    }
+
+    #[test]
+    fn reads_lut_tables() {
+        let bytes = profile(
+            b"CMYK",
+            b"Lab ",
+            &[(b"desc", vec![0; 8]), (b"B2A0", mft2())],
+        );
+        let lab = Profile::parse(bytes.clone()).expect("it parses");
+        assert_eq!(lab.bytes(), bytes);
+        assert_eq!(
+            lab.pcs,
+            Pcs::Lab {
+                scale: 65280.0 / 65535.0
+            }
+        );
+        assert!(
+            matches!(&lab.steps[..], [Step::Curves(input), Step::Clut(clut), Step::Curves(output)]
+            if *input == identity() && clut.grid == [2; 3] && output.len() == 4)
+        );
+        // L* in cyan, short of full by the legacy encoding, and the rest in black
+        assert_eq!(lab.to_cmyk(Rgb([255, 255, 255])), [254, 0, 0, 1]);
+        assert_eq!(lab.to_cmyk(Rgb([0, 0, 0])), [0, 0, 0, 255]);
+
+        let xyz =
+            Profile::parse(profile(b"CMYK", b"XYZ ", &[(b"B2A1", mft1())])).expect("it parses");
+        assert_eq!(xyz.pcs, Pcs::Xyz);
+        let unit = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert_eq!(xyz.steps[0], Step::Matrix(unit, [0.0; 3]));
+        assert_eq!(xyz.steps.len(), 4);
+        assert_eq!(xyz.to_cmyk(Rgb([0, 0, 0])), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn prefers_relative_colorimetric() {
+        let mut perceptual = mft2();
+        // L* always half way, and so half cyan
+        perceptual[52..58].copy_from_slice(&[0x80, 0, 0x80, 0, 0x80, 0]);
+        let bytes = profile(
+            b"CMYK",
+            b"Lab ",
+            &[(b"B2A0", perceptual), (b"B2A1", mft2())],
+        );
+        let profile = Profile::parse(bytes).expect("it parses");
+        assert_eq!(profile.to_cmyk(Rgb([0, 0, 0])), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn reads_mba_tables() {
+        let profile =
+            Profile::parse(profile(b"CMYK", b"Lab ", &[(b"B2A1", mba())])).expect("it parses");
+        let mut gamma = [0.0; 7];
+        gamma[0] = 1.0;
+        assert_eq!(
+            profile.steps,
+            [
+                Step::Curves(vec![Curve::Gamma(1.0); 3]),
+                Step::Clut(Clut {
+                    grid: [2; 3],
+                    outputs: 4,
+                    values: corners(1).iter().map(|value| *value as f32).collect(),
+                }),
+                Step::Curves(vec![Curve::Parametric(0, gamma); 4]),
+            ]
+        );
+        assert_eq!(profile.to_cmyk(Rgb([255, 255, 255])), [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn malformed_profiles_are_refused() {
+        let message = |bytes: Vec<u8>| Profile::parse(bytes).err().map(|err| err.to_string());
+        assert_eq!(
+            message(vec![0; 200]),
+            Some("not a usable ICC profile: there's no `acsp` signature in the header".to_owned())
+        );
+        assert_eq!(
+            message(profile(b"RGB ", b"Lab ", &[])),
+            Some("unsupported ICC profile: its color space is RGB, not CMYK".to_owned())
+        );
+        assert_eq!(
+            message(profile(b"CMYK", b"Lab ", &[(b"A2B0", mft2())])),
+            Some(
+                "unsupported ICC profile: it has no table from Lab or XYZ to CMYK (B2A0 or B2A1)"
+                    .to_owned()
+            )
+        );
+
+        let mut cut = profile(b"CMYK", b"Lab ", &[(b"B2A1", mft2())]);
+        cut.truncate(cut.len() - 1);
+        assert_eq!(
+            message(cut),
+            Some("not a usable ICC profile: a tag runs past the end of the file".to_owned())
+        );
+        let mut table = profile(b"CMYK", b"Lab ", &[]);
+        table[131] = 3;
+        assert_eq!(
+            message(table),
+            Some("not a usable ICC profile: the tag table is cut off".to_owned())
+        );
+
+        let mut three = mft2();
+        three[9] = 3;
+        assert_eq!(
+            message(profile(b"CMYK", b"Lab ", &[(b"B2A1", three)])),
+            Some(
+                "not a usable ICC profile: the B2A table doesn't go from 3 channels to 4"
+                    .to_owned()
+            )
+        );
+        let mut text = mft2();
+        text[..4].copy_from_slice(b"text");
+        assert_eq!(
+            message(profile(b"CMYK", b"Lab ", &[(b"B2A1", text)])),
+            Some(
+                "unsupported ICC profile: its B2A table is a \"text\", not an mft1, mft2 or mBA"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn naive_conversion() {
+        assert_eq!(naive_cmyk(Rgb([255, 255, 255])), [0, 0, 0, 0]);
+        assert_eq!(naive_cmyk(Rgb([0, 0, 0])), [0, 0, 0, 255]);
+        assert_eq!(naive_cmyk(Rgb([255, 0, 0])), [0, 255, 255, 0]);
+        assert_eq!(naive_cmyk(Rgb([0, 0, 128])), [255, 255, 0, 127]);
+        let image = RgbImage::from_raw(2, 1, vec![255, 0, 0, 255, 0, 0]).expect("2 pixels");
+        assert_eq!(convert(&image, None), [0, 255, 255, 0, 0, 255, 255, 0]);
+    }
+}
@@ -0,0 +1,77 @@
+//! generate rectangles, Mondrian style
+//!
+//! most things go through [`CompositionBuilder`](composition::CompositionBuilder):
+//! `CompositionBuilder::new().size(3840, 2160).seed(7).palette(Palette::CLASSIC).levels(6).build()?`
+//! gives a [`Composition`](composition::Composition) that can be rendered to pixels, SVG, or JSON.
+
+pub mod audio;
+pub mod calendar;
+pub mod color;
+pub mod composition;
+pub mod cvd;
+pub mod dashboard;
+pub mod decode;
+pub mod detail;
+pub mod display;
+pub mod dither;
+pub mod edit;
+pub mod eink;
+pub mod encode;
+pub mod evolve;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fill;
+pub mod filter;
+pub mod geometry;
+pub mod hex;
+pub mod icc;
+pub mod metadata;
+pub mod morph;
+pub mod mosaic;
+pub mod noise;
+pub mod oklab;
+pub mod palettes;
+pub mod penrose;
+pub mod playlist;
+pub mod plugin;
+pub mod polygon;
+pub mod qr;
+pub mod relief;
+pub mod render;
+pub mod schedule;
+pub mod score;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod seed;
+pub mod size;
+pub mod sketch;
+pub mod stats;
+pub mod strategy;
+pub mod style;
+pub mod symmetry;
+pub mod text;
+pub mod timeline;
+pub mod timing;
+pub mod trace;
+pub mod tree;
+pub mod treemap;
+pub mod truchet;
+pub mod video;
+pub mod viewport;
+pub mod voronoi;
+pub mod wallpaper;
+pub mod zoom;
+
+/// everything you need for the common case
+pub mod prelude {
+    pub use crate::color::Palette;
+    pub use crate::composition::{
+        BuildError, Composition, CompositionBuilder, MondrianBuilder, Pane,
+    };
+    pub use crate::fill::{ColorStrategy, FillOptions};
+    pub use crate::geometry::{Rectangle, SplitOptions, SplittableGraphic};
+    pub use crate::metadata::Parameters;
+    pub use crate::size::SizePreset;
+    pub use crate::strategy::Builtin;
+    pub use crate::tree::Tree;
+}
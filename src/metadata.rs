@@ -0,0 +1,892 @@
+//! the generation parameters we stash inside output files so they can be reproduced later
+
+use std::{error::Error, fmt, path::PathBuf, str::FromStr};
+
+use image::Rgb;
+
+use crate::{
+    calendar::{Calendar, Span},
+    color::{to_hex, Palette},
+    composition::{Composition, CompositionBuilder},
+    dashboard::{Dashboard, Usage},
+    detail,
+    edit::Edit,
+    evolve::Genome,
+    fill::FillOptions,
+    geometry::{Rectangle, SplitOptions},
+    mosaic::{self, Mosaic},
+    noise::NoiseOptions,
+    strategy::Builtin,
+    style::Style,
+    symmetry::Symmetry,
+    text::{Anchor, Card, Text},
+    tree::Pick,
+    viewport::Viewport,
+};
+
+/// every key we write starts with this, e.g. `mondrian:seed`
+pub const KEY_PREFIX: &str = "mondrian:";
+
+/// everything needed to build the same composition again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameters {
+    pub width: u32,
+    pub height: u32,
+    pub seed: u64,
+    pub levels: usize,
+    pub border_width: u32,
+    /// how many times wider the first lines are than the deepest
+    pub border_taper: f32,
+    pub frame_width: u32,
+    pub frame_color: Rgb<u8>,
+    pub line_color: Rgb<u8>,
+    pub matte: u32,
+    pub matte_color: Rgb<u8>,
+    pub antialias: bool,
+    pub split: SplitOptions,
+    pub fill: FillOptions,
+    /// the weights of a treemap, which are what lays it out
+    pub treemap: Option<Vec<f64>>,
+    /// the values of a timeline, which are what lays it out
+    pub timeline: Option<Vec<f64>>,
+    /// the song the panes were cut to
+    pub audio: Option<PathBuf>,
+    /// the cuts of a bred layout, which are what lays it out
+    pub genome: Option<Genome>,
+    /// the photo a mosaic was made from; it has to still be there to make it again
+    pub mosaic: Option<Mosaic>,
+    pub style: Style,
+    pub cells: Option<usize>,
+    /// the pane count a layout kept splitting up to
+    pub target_panes: Option<usize>,
+    /// how it picked which pane to split next
+    pub pick: Option<Pick>,
+    pub dpi: Option<u32>,
+    pub palette: Palette,
+    /// what was changed by hand after the panes were laid out
+    pub edits: Vec<Edit>,
+    /// the split and color rules; like a mosaic's photo, it has to still be there
+    pub script: Option<PathBuf>,
+    pub symmetry: Option<Symmetry>,
+    /// what said how deep to split where; it has to still be there too
+    pub detail_mask: Option<PathBuf>,
+    /// areas no cuts go through, in canvas pixels
+    pub keep_clear: Vec<Rectangle>,
+    /// what's written on top, as it was written; its font has to still be there
+    pub text: Option<Text>,
+    /// the social-share card's words; its font has to still be there too
+    pub card: Option<Card>,
+    /// what the QR code says
+    pub qr: Option<String>,
+    /// the calendar, and the day it was drawn for
+    pub calendar: Option<Calendar>,
+    /// the system's usage as it was drawn
+    pub dashboard: Option<Dashboard>,
+    /// whether copies side by side join up
+    pub tileable: bool,
+    /// chance a line stops short of the canvas edge
+    pub unfinished: f32,
+    /// how far across the panes' shadows are, in pixels
+    pub shadow: u32,
+    /// the part of the canvas that was drawn, if not all of it
+    pub viewport: Option<Viewport>,
+    /// how far in from each side small things were kept, in percent
+    pub safe_area: f32,
+    /// crate version that made the file
+    pub version: String,
+}
+
+/// a key was missing or didn't parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParametersError {
+    Missing(&'static str),
+    Invalid { key: &'static str, value: String },
+}
+
+impl fmt::Display for ParametersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(key) => write!(f, "no `{KEY_PREFIX}{key}` in metadata"),
+            Self::Invalid { key, value } => {
+                write!(f, "couldn't understand `{KEY_PREFIX}{key}` = {value:?}")
+            }
+        }
+    }
+}
+
+impl Error for ParametersError {}
+
+impl Parameters {
+    pub fn of(composition: &Composition) -> Self {
+        Self {
+            width: composition.width(),
+            height: composition.height(),
+            seed: composition.seed(),
+            levels: composition.levels(),
+            border_width: composition.border_width(),
+            border_taper: composition.border_taper(),
+            frame_width: composition.frame_width(),
+            frame_color: composition.frame_color(),
+            line_color: composition.line_color(),
+            matte: composition.matte(),
+            matte_color: composition.matte_color(),
+            antialias: composition.antialias(),
+            split: composition.split_options().clone(),
+            fill: composition.fill_options().clone(),
+            treemap: composition.treemap().map(<[f64]>::to_vec),
+            timeline: composition.timeline().map(<[f64]>::to_vec),
+            mosaic: composition.mosaic().cloned(),
+            audio: composition.audio().map(PathBuf::from),
+            genome: composition.genome().cloned(),
+            style: composition.style(),
+            cells: composition.cells(),
+            target_panes: composition.target_panes(),
+            pick: composition.pick(),
+            dpi: composition.dpi(),
+            palette: composition.palette().clone(),
+            edits: composition.edits().to_vec(),
+            script: composition.script().map(PathBuf::from),
+            symmetry: composition.symmetry(),
+            detail_mask: composition.detail_mask().map(PathBuf::from),
+            keep_clear: composition.keep_clear().to_vec(),
+            text: composition.text().cloned(),
+            card: composition.card().cloned(),
+            qr: composition.qr().map(|qr| qr.data.clone()),
+            calendar: composition.calendar().cloned(),
+            dashboard: composition.dashboard().cloned(),
+            tileable: composition.tileable(),
+            unfinished: composition.unfinished(),
+            shadow: composition.shadow(),
+            viewport: composition.viewport(),
+            safe_area: composition.safe_area(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    }
+
+    /// the colors the composition is drawn in: the palette, the background, the lines, and the
+    /// frame, matte, text, card and edited panes' if there are any. antialiased edges, shadows
+    /// and the like blend between them
+    pub fn colors(&self) -> Vec<Rgb<u8>> {
+        let mut colors = self.palette.colors().to_vec();
+        colors.extend([self.fill.background, self.line_color]);
+        if self.frame_width > 0 {
+            colors.push(self.frame_color);
+        }
+        if self.matte > 0 {
+            colors.push(self.matte_color);
+        }
+        colors.extend(self.text.as_ref().map(|text| text.color));
+        colors.extend(self.card.as_ref().map(|card| card.color));
+        colors.extend(self.edits.iter().filter_map(|edit| match edit {
+            Edit::Color(_, color) => Some(*color),
+            _ => None,
+        }));
+        let mut seen = vec![];
+        colors.retain(|color| {
+            let new = !seen.contains(color);
+            seen.push(*color);
+            new
+        });
+        colors
+    }
+
+    /// a builder that makes the same composition
+    pub fn builder(&self) -> CompositionBuilder {
+        let mut builder = CompositionBuilder::new();
+        if let Some(dpi) = self.dpi {
+            builder = builder.dpi(dpi);
+        }
+        if let Some(weights) = &self.treemap {
+            builder = builder.treemap(weights.clone());
+        }
+        if let Some(values) = &self.timeline {
+            builder = builder.timeline(values.clone());
+        }
+        if let Some(mosaic) = &self.mosaic {
+            builder = builder.mosaic(mosaic.clone());
+        }
+        if let Some(path) = &self.audio {
+            builder = builder.audio(path);
+        }
+        if let Some(genome) = &self.genome {
+            builder = builder.genome(genome.clone());
+        }
+        if let Some(cells) = self.cells {
+            builder = builder.cells(cells);
+        }
+        if let Some(panes) = self.target_panes {
+            builder = builder.target_panes(panes);
+        }
+        if let Some(pick) = self.pick {
+            builder = builder.pick(pick);
+        }
+        if let Some(path) = &self.script {
+            builder = builder.script(path.clone());
+        }
+        if let Some(symmetry) = self.symmetry {
+            builder = builder.symmetry(symmetry);
+        }
+        if let Some(path) = &self.detail_mask {
+            builder = builder.detail_mask(path.clone());
+        }
+        if !self.keep_clear.is_empty() {
+            builder = builder.keep_clear(self.keep_clear.clone());
+        }
+        if let Some(text) = &self.text {
+            builder = builder.text(text.clone());
+        }
+        if let Some(card) = &self.card {
+            builder = builder.card(card.clone());
+        }
+        if let Some(data) = &self.qr {
+            builder = builder.qr(data.clone());
+        }
+        if let Some(calendar) = &self.calendar {
+            builder = builder.calendar(calendar.clone());
+        }
+        if let Some(dashboard) = &self.dashboard {
+            builder = builder.dashboard(dashboard.clone());
+        }
+        if self.tileable {
+            builder = builder.tileable(true);
+        }
+        if self.unfinished > 0.0 {
+            builder = builder.unfinished(self.unfinished);
+        }
+        if self.shadow > 0 {
+            builder = builder.shadow(self.shadow);
+        }
+        if let Some(viewport) = self.viewport {
+            builder = builder.viewport(viewport);
+        }
+        if self.safe_area > 0.0 {
+            builder = builder.safe_area(self.safe_area);
+        }
+        // antialias first, since it also sets whether splits snap to whole pixels
+        builder
+            .antialias(self.antialias)
+            .split_options(self.split.clone())
+            .fill_options(self.fill.clone())
+            .size(self.width, self.height)
+            .seed(self.seed)
+            .levels(self.levels)
+            .border_width(self.border_width)
+            .border_taper(self.border_taper)
+            .frame_width(self.frame_width)
+            .frame_color(self.frame_color)
+            .line_color(self.line_color)
+            .matte(self.matte, self.matte_color)
+            .palette(self.palette.clone())
+            .style(self.style)
+            .edits(self.edits.clone())
+    }
+
+    /// the same composition at another resolution; lines, the matte and shadows scale with the
+    /// longest side
+    ///
+    /// layouts only line up exactly when the aspect ratio stays the same
+    pub fn resized(&self, width: u32, height: u32) -> Self {
+        let old = self.width.max(self.height) as u64;
+        let new = width.max(height) as u64;
+        let scale = |pixels: u32| (pixels as u64 * new / old.max(1)) as u32;
+        Self {
+            width,
+            height,
+            border_width: scale(self.border_width),
+            frame_width: scale(self.frame_width),
+            matte: scale(self.matte),
+            shadow: scale(self.shadow),
+            // fractions of the canvas stay where they are
+            viewport: self.viewport.map(|viewport| {
+                if viewport.normalized() {
+                    return viewport;
+                }
+                let factor = new as f32 / old.max(1) as f32;
+                let Viewport {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = viewport;
+                Viewport::new(x * factor, y * factor, width * factor, height * factor)
+            }),
+            keep_clear: self
+                .keep_clear
+                .iter()
+                .map(|zone| zone.scaled(new as f32 / old.max(1) as f32).snapped())
+                .collect(),
+            text: self.text.clone().map(|text| Text {
+                size: text.size.map(|size| size * new as f32 / old.max(1) as f32),
+                ..text
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// `(key, value)` pairs with [`KEY_PREFIX`] already on the keys
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let join = |values: Vec<String>| values.join(",");
+        let mut pairs = vec![
+            ("version", self.version.clone()),
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("seed", self.seed.to_string()),
+            ("levels", self.levels.to_string()),
+            ("border-width", self.border_width.to_string()),
+            ("frame-width", self.frame_width.to_string()),
+            ("frame-color", to_hex(self.frame_color)),
+            ("matte", self.matte.to_string()),
+            ("matte-color", to_hex(self.matte_color)),
+            (
+                "palette",
+                join(self.palette.colors().iter().map(|c| to_hex(*c)).collect()),
+            ),
+            (
+                "weights",
+                join(self.palette.weights().iter().map(u32::to_string).collect()),
+            ),
+            ("fill-probability", self.fill.probability.to_string()),
+            ("fill-area-bias", self.fill.area_bias.to_string()),
+            ("background", to_hex(self.fill.background)),
+            ("color-strategy", self.fill.strategy.to_string()),
+            ("antialias", self.antialias.to_string()),
+            ("split-strategy", self.split.strategy.to_string()),
+            ("style", self.style.to_string()),
+        ];
+        if self.line_color != Rgb([0, 0, 0]) {
+            pairs.push(("line-color", to_hex(self.line_color)));
+        }
+        if self.border_taper != 1.0 {
+            pairs.push(("border-taper", self.border_taper.to_string()));
+        }
+        if let Some(max_aspect) = self.split.max_aspect {
+            pairs.push(("max-aspect", max_aspect.to_string()));
+        }
+        if self.split.diagonal > 0.0 {
+            pairs.push(("diagonal", self.split.diagonal.to_string()));
+        }
+        if self.fill.pattern > 0.0 {
+            pairs.push(("pattern", self.fill.pattern.to_string()));
+        }
+        if self.split.cross > 0.0 {
+            pairs.push(("cross", self.split.cross.to_string()));
+        }
+        if self.split.whole_pixels == self.antialias {
+            // only when it's been set apart from antialiasing
+            pairs.push(("whole-pixels", self.split.whole_pixels.to_string()));
+        }
+        if self.split.strategy == Builtin::Noise {
+            pairs.push(("noise-frequency", self.split.noise.frequency.to_string()));
+            pairs.push(("noise-octaves", self.split.noise.octaves.to_string()));
+        }
+        if let Some(name) = &self.split.plugin {
+            pairs.push(("split-plugin", name.clone()));
+        }
+        if let Some(name) = &self.fill.plugin {
+            pairs.push(("fill-plugin", name.clone()));
+        }
+        if let Some(dpi) = self.dpi {
+            pairs.push(("dpi", dpi.to_string()));
+        }
+        if let Some(cells) = self.cells {
+            pairs.push(("cells", cells.to_string()));
+        }
+        if let Some(panes) = self.target_panes {
+            pairs.push(("target-panes", panes.to_string()));
+        }
+        if let Some(pick) = self.pick {
+            pairs.push(("pick", pick.to_string()));
+        }
+        if let Some(weights) = &self.treemap {
+            pairs.push((
+                "treemap",
+                join(weights.iter().map(f64::to_string).collect()),
+            ));
+        }
+        if let Some(values) = &self.timeline {
+            pairs.push((
+                "timeline",
+                join(values.iter().map(f64::to_string).collect()),
+            ));
+        }
+        if let Some(mosaic) = &self.mosaic {
+            pairs.push(("from-image", mosaic.path.display().to_string()));
+            pairs.push(("tolerance", mosaic.tolerance.to_string()));
+        }
+        if let Some(path) = &self.audio {
+            pairs.push(("from-audio", path.display().to_string()));
+        }
+        if let Some(genome) = &self.genome {
+            pairs.push(("genome", genome.to_string()));
+        }
+        if let Some(path) = &self.script {
+            pairs.push(("script", path.display().to_string()));
+        }
+        if let Some(symmetry) = self.symmetry {
+            pairs.push(("symmetry", symmetry.to_string()));
+        }
+        if let Some(path) = &self.detail_mask {
+            pairs.push(("detail-mask", path.display().to_string()));
+        }
+        if !self.keep_clear.is_empty() {
+            let zones: Vec<String> = self.keep_clear.iter().map(detail::format_zone).collect();
+            pairs.push(("keep-clear", zones.join(" ")));
+        }
+        if let Some(text) = &self.text {
+            pairs.push(("text", escape(&text.text)));
+            if let Some(font) = &text.font {
+                pairs.push(("font", font.display().to_string()));
+            }
+            if let Some(size) = text.size {
+                pairs.push(("text-size", size.to_string()));
+            }
+            pairs.push(("text-color", to_hex(text.color)));
+            pairs.push(("text-anchor", text.anchor.to_string()));
+        }
+        if let Some(card) = &self.card {
+            pairs.push(("title", escape(&card.title)));
+            if let Some(subtitle) = &card.subtitle {
+                pairs.push(("subtitle", escape(subtitle)));
+            }
+            if let Some(font) = &card.font {
+                pairs.push(("title-font", font.display().to_string()));
+            }
+            pairs.push(("title-color", to_hex(card.color)));
+            pairs.push(("title-anchor", card.anchor.to_string()));
+        }
+        if let Some(data) = &self.qr {
+            pairs.push(("qr", escape(data)));
+        }
+        if let Some(calendar) = &self.calendar {
+            pairs.push(("calendar", calendar.span.to_string()));
+            if let Some(date) = calendar.date {
+                pairs.push(("calendar-date", date.to_string()));
+            }
+            if let Some(font) = &calendar.font {
+                pairs.push(("calendar-font", font.display().to_string()));
+            }
+        }
+        if let Some(dashboard) = &self.dashboard {
+            pairs.push(("stats", dashboard.usage.to_string()));
+            pairs.push(("stats-anchor", dashboard.anchor.to_string()));
+            if dashboard.legend {
+                pairs.push(("stats-legend", true.to_string()));
+            }
+            if let Some(font) = &dashboard.font {
+                pairs.push(("stats-font", font.display().to_string()));
+            }
+        }
+        if self.tileable {
+            pairs.push(("tileable", true.to_string()));
+        }
+        if self.unfinished > 0.0 {
+            pairs.push(("unfinished", self.unfinished.to_string()));
+        }
+        if self.shadow > 0 {
+            pairs.push(("shadow", self.shadow.to_string()));
+        }
+        if let Some(viewport) = self.viewport {
+            pairs.push(("viewport", viewport.to_string()));
+        }
+        if self.safe_area > 0.0 {
+            pairs.push(("safe-area", self.safe_area.to_string()));
+        }
+        if !self.edits.is_empty() {
+            pairs.push((
+                "edits",
+                join(self.edits.iter().map(Edit::to_string).collect()),
+            ));
+        }
+        pairs
+            .into_iter()
+            .map(|(key, value)| (format!("{KEY_PREFIX}{key}"), value))
+            .collect()
+    }
+
+    /// the opposite of [`to_pairs`](Self::to_pairs); keys without our prefix are ignored
+    pub fn from_pairs<'a>(
+        pairs: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, ParametersError> {
+        let lookup = Lookup(
+            pairs
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.strip_prefix(KEY_PREFIX)?, value)))
+                .collect(),
+        );
+
+        let palette = lookup.required::<String>("palette")?;
+        let colors = palette
+            .split(',')
+            .map(|hex| parse_hex(hex).ok_or_else(|| invalid("palette", &palette)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let weights = lookup.required::<String>("weights")?;
+        let weights = weights
+            .split(',')
+            .map(|w| w.trim().parse().map_err(|_| invalid("weights", &weights)))
+            .collect::<Result<Vec<u32>, _>>()?;
+
+        // keys added after the first release fall back to what older versions did
+        let defaults = FillOptions::default();
+        let fill = FillOptions {
+            probability: lookup.or("fill-probability", defaults.probability)?,
+            area_bias: lookup.or("fill-area-bias", defaults.area_bias)?,
+            background: lookup.color_or("background", defaults.background)?,
+            strategy: lookup.or("color-strategy", defaults.strategy)?,
+            plugin: lookup.optional("fill-plugin")?,
+            pattern: lookup.or("pattern", defaults.pattern)?,
+        };
+        // everything before antialiasing cut on whole pixels and drew hard edges
+        let antialias = lookup.or("antialias", false)?;
+        let noise = NoiseOptions::default();
+        let split = SplitOptions {
+            max_aspect: lookup.optional("max-aspect")?,
+            whole_pixels: lookup.or("whole-pixels", !antialias)?,
+            strategy: lookup.or("split-strategy", Default::default())?,
+            diagonal: lookup.or("diagonal", 0.0)?,
+            cross: lookup.or("cross", 0.0)?,
+            plugin: lookup.optional("split-plugin")?,
+            noise: NoiseOptions {
+                frequency: lookup.or("noise-frequency", noise.frequency)?,
+                octaves: lookup.or("noise-octaves", noise.octaves)?,
+            },
+        };
+
+        let treemap = match lookup.optional::<String>("treemap")? {
+            None => None,
+            Some(weights) => Some(
+                weights
+                    .split(',')
+                    .map(|w| w.trim().parse().map_err(|_| invalid("treemap", &weights)))
+                    .collect::<Result<Vec<f64>, _>>()?,
+            ),
+        };
+
+        let timeline = match lookup.optional::<String>("timeline")? {
+            None => None,
+            Some(values) => Some(
+                values
+                    .split(',')
+                    .map(|v| v.trim().parse().map_err(|_| invalid("timeline", &values)))
+                    .collect::<Result<Vec<f64>, _>>()?,
+            ),
+        };
+
+        let mosaic = match lookup.optional::<PathBuf>("from-image")? {
+            None => None,
+            Some(path) => Some(Mosaic {
+                path,
+                tolerance: lookup.or("tolerance", mosaic::DEFAULT_TOLERANCE)?,
+            }),
+        };
+
+        let edits = match lookup.optional::<String>("edits")? {
+            None => vec![],
+            Some(edits) => edits
+                .split(',')
+                .map(|edit| edit.parse().map_err(|_| invalid("edits", &edits)))
+                .collect::<Result<Vec<Edit>, _>>()?,
+        };
+
+        let keep_clear = match lookup.optional::<String>("keep-clear")? {
+            None => vec![],
+            Some(zones) => zones
+                .split_whitespace()
+                .map(|zone| detail::parse_zone(zone).map_err(|_| invalid("keep-clear", &zones)))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        let text = match lookup.optional::<String>("text")? {
+            None => None,
+            Some(written) => Some(Text {
+                text: unescape(&written),
+                font: lookup.optional("font")?,
+                size: lookup.optional("text-size")?,
+                color: lookup.color_or("text-color", Rgb([0, 0, 0]))?,
+                anchor: lookup.or("text-anchor", Anchor::default())?,
+            }),
+        };
+
+        let card = match lookup.optional::<String>("title")? {
+            None => None,
+            Some(title) => Some(Card {
+                title: unescape(&title),
+                subtitle: lookup
+                    .optional::<String>("subtitle")?
+                    .as_deref()
+                    .map(unescape),
+                font: lookup.optional("title-font")?,
+                color: lookup.color_or("title-color", Rgb([0, 0, 0]))?,
+                anchor: lookup.or("title-anchor", Anchor::Left)?,
+            }),
+        };
+
+        let calendar = match lookup.optional::<Span>("calendar")? {
+            None => None,
+            Some(span) => Some(Calendar {
+                span,
+                date: lookup.optional("calendar-date")?,
+                font: lookup.optional("calendar-font")?,
+            }),
+        };
+
+        let dashboard = match lookup.optional::<Usage>("stats")? {
+            None => None,
+            Some(usage) => Some(Dashboard {
+                usage,
+                anchor: lookup.or("stats-anchor", Anchor::TopRight)?,
+                legend: lookup.or("stats-legend", false)?,
+                font: lookup.optional("stats-font")?,
+            }),
+        };
+
+        let border_width = lookup.required("border-width")?;
+        Ok(Self {
+            width: lookup.required("width")?,
+            height: lookup.required("height")?,
+            seed: lookup.required("seed")?,
+            levels: lookup.required("levels")?,
+            border_width,
+            border_taper: lookup.or("border-taper", 1.0)?,
+            frame_width: lookup.or("frame-width", border_width)?,
+            frame_color: lookup.color_or("frame-color", Rgb([0, 0, 0]))?,
+            line_color: lookup.color_or("line-color", Rgb([0, 0, 0]))?,
+            matte: lookup.or("matte", 0)?,
+            matte_color: lookup.color_or("matte-color", Rgb([255, 255, 255]))?,
+            antialias,
+            split,
+            fill,
+            treemap,
+            timeline,
+            mosaic,
+            audio: lookup.optional("from-audio")?,
+            genome: lookup.optional("genome")?,
+            style: lookup.or("style", Style::default())?,
+            cells: lookup.optional("cells")?,
+            target_panes: lookup.optional("target-panes")?,
+            pick: lookup.optional("pick")?,
+            dpi: lookup.optional("dpi")?,
+            palette: Palette::new(colors).with_weights(weights),
+            edits,
+            script: lookup.optional("script")?,
+            symmetry: lookup.optional("symmetry")?,
+            detail_mask: lookup.optional("detail-mask")?,
+            keep_clear,
+            text,
+            card,
+            qr: lookup.optional::<String>("qr")?.as_deref().map(unescape),
+            calendar,
+            dashboard,
+            tileable: lookup.or("tileable", false)?,
+            unfinished: lookup.or("unfinished", 0.0)?,
+            shadow: lookup.or("shadow", 0)?,
+            viewport: lookup.optional("viewport")?,
+            safe_area: lookup.or("safe-area", 0.0)?,
+            version: lookup.required("version")?,
+        })
+    }
+}
+
+/// the keys written as numbers when [`Parameters`] are serialized; the rest are strings, but for
+/// [`BOOLEANS`]
+#[cfg(feature = "serde")]
+const NUMBERS: &[&str] = &[
+    "width",
+    "height",
+    "seed",
+    "levels",
+    "border-width",
+    "border-taper",
+    "frame-width",
+    "matte",
+    "fill-probability",
+    "fill-area-bias",
+    "max-aspect",
+    "diagonal",
+    "cross",
+    "pattern",
+    "noise-frequency",
+    "noise-octaves",
+    "dpi",
+    "cells",
+    "target-panes",
+    "tolerance",
+    "text-size",
+    "unfinished",
+    "shadow",
+    "safe-area",
+];
+
+/// the keys written as `true` or `false` when [`Parameters`] are serialized
+#[cfg(feature = "serde")]
+const BOOLEANS: &[&str] = &["antialias", "whole-pixels", "tileable", "stats-legend"];
+
+/// a flat object of the same keys and values as [`to_pairs`](Parameters::to_pairs), without
+/// [`KEY_PREFIX`], so files, images and anything else that takes parameters share one schema;
+/// `version` says which release wrote them, and keys added since are filled in the way
+/// [`from_pairs`](Parameters::from_pairs) does for images
+///
+/// ```text
+/// {"version":"0.1.0","width":3840,"height":2160,"seed":7,"levels":5,...,"antialias":true}
+/// ```
+///
+/// numbers and booleans can be given as strings too
+#[cfg(feature = "serde")]
+impl serde::Serialize for Parameters {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let pairs = self.to_pairs();
+        let mut map = serializer.serialize_map(Some(pairs.len()))?;
+        for (key, value) in &pairs {
+            let key = key.strip_prefix(KEY_PREFIX).unwrap_or(key);
+            match (
+                value.parse::<u64>(),
+                value.parse::<f64>(),
+                value.parse::<bool>(),
+            ) {
+                (Ok(number), _, _) if NUMBERS.contains(&key) => {
+                    map.serialize_entry(key, &number)?
+                }
+                (_, Ok(number), _) if NUMBERS.contains(&key) => {
+                    map.serialize_entry(key, &number)?
+                }
+                (_, _, Ok(boolean)) if BOOLEANS.contains(&key) => {
+                    map.serialize_entry(key, &boolean)?
+                }
+                _ => map.serialize_entry(key, value)?,
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Parameters {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs = std::collections::BTreeMap::<String, Scalar>::deserialize(deserializer)?;
+        let pairs: Vec<(String, String)> = pairs
+            .into_iter()
+            .map(|(key, Scalar(value))| (format!("{KEY_PREFIX}{key}"), value))
+            .collect();
+        Self::from_pairs(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+/// a string, number or boolean, as the text [`Parameters::from_pairs`] takes
+#[cfg(feature = "serde")]
+struct Scalar(String);
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Scalar {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Scalar;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string, number or boolean")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Scalar, E> {
+                Ok(Scalar(value.to_owned()))
+            }
+
+            fn visit_bool<E>(self, value: bool) -> Result<Scalar, E> {
+                Ok(Scalar(value.to_string()))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Scalar, E> {
+                Ok(Scalar(value.to_string()))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Scalar, E> {
+                Ok(Scalar(value.to_string()))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Scalar, E> {
+                Ok(Scalar(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// newlines written `\n`, and backslashes `\\`, so a value stays on one line
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// `\n`s back into newlines and `\\`s into backslashes, undoing [`escape`]
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn invalid(key: &'static str, value: &str) -> ParametersError {
+    ParametersError::Invalid {
+        key,
+        value: value.to_owned(),
+    }
+}
+
+/// `(key, value)` pairs with the prefix already stripped
+struct Lookup<'a>(Vec<(&'a str, &'a str)>);
+
+impl Lookup<'_> {
+    fn optional<T: FromStr>(&self, key: &'static str) -> Result<Option<T>, ParametersError> {
+        let Some((_, value)) = self.0.iter().find(|(k, _)| *k == key) else {
+            return Ok(None);
+        };
+        value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid(key, value))
+    }
+
+    fn required<T: FromStr>(&self, key: &'static str) -> Result<T, ParametersError> {
+        self.optional(key)?.ok_or(ParametersError::Missing(key))
+    }
+
+    fn or<T: FromStr>(&self, key: &'static str, default: T) -> Result<T, ParametersError> {
+        Ok(self.optional(key)?.unwrap_or(default))
+    }
+
+    fn color_or(&self, key: &'static str, default: Rgb<u8>) -> Result<Rgb<u8>, ParametersError> {
+        match self.optional::<String>(key)? {
+            None => Ok(default),
+            Some(value) => parse_hex(&value).ok_or_else(|| invalid(key, &value)),
+        }
+    }
+}
+
+/// just enough to read back what [`to_hex`] wrote
+pub(crate) fn parse_hex(hex: &str) -> Option<Rgb<u8>> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
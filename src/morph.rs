@@ -0,0 +1,142 @@
+//! one composition turning into another: the two split trees are walked together from the root,
+//! and where both cut a node the same way the line slides from one's place to the other's. a cut
+//! only one of them has slides in from, or out to, the side of the node its smaller half is on,
+//! so panes grow out of the lines and shrink back into them rather than popping
+//!
+//! see [`Composition::morphed_from`](crate::composition::Composition::morphed_from)
+
+use crate::{
+    geometry::{Rectangle, SplitOptions},
+    tree::{Subtree, Tree},
+};
+
+/// which of `from`'s panes and `to`'s a pane in between comes from; only one of them has one
+/// where the other's is shrunk to nothing or not grown yet
+pub type Source = (Option<usize>, Option<usize>);
+
+/// a node of one of the two trees, and which of its panes its first leaf is
+type Side<'a> = Option<(Subtree<'a, Rectangle>, usize)>;
+
+/// how a node's cut goes: side by side or one above the other, and how far across
+fn cut(node: Subtree<'_, Rectangle>) -> Option<(bool, f32)> {
+    let (parent, left) = (node.item(), node.left()?.item());
+    let side_by_side = left.height >= parent.height && left.width < parent.width;
+    let t = if side_by_side {
+        left.width / parent.width
+    } else {
+        left.height / parent.height
+    };
+    Some((side_by_side, t))
+}
+
+/// the halves of `side`'s node, each with its first pane
+fn halves(side: Side<'_>) -> (Side<'_>, Side<'_>) {
+    match side {
+        Some((node, first)) => match (node.left(), node.right()) {
+            (Some(left), Some(right)) => {
+                let after = first + left.leaves().count();
+                (Some((left, first)), Some((right, after)))
+            }
+            _ => (None, None),
+        },
+        None => (None, None),
+    }
+}
+
+/// what to do with a node of the tree in between
+enum Step<'a> {
+    /// cut it `t` of the way across, side by side or not, and go on with what goes in each half
+    Cut {
+        side_by_side: bool,
+        t: f32,
+        halves: [(Side<'a>, Side<'a>); 2],
+    },
+    /// a pane, of `from`'s and `to`'s, if they have one there
+    Leaf(Option<usize>, Option<usize>),
+}
+
+/// how `from`'s node and `to`'s, both where the tree in between is, cut it `t` of the way from
+/// one to the other
+fn step<'a>(from: Side<'a>, to: Side<'a>, t: f32) -> Step<'a> {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    // a cut there's none of on the other side goes off towards whichever side is nearer, and
+    // the bigger half stands in for the whole node
+    let edge = |at: f32| if at >= 0.5 { 1.0 } else { 0.0 };
+    let cuts = (
+        from.and_then(|(node, _)| cut(node)),
+        to.and_then(|(node, _)| cut(node)),
+    );
+    let (from_halves, to_halves) = (halves(from), halves(to));
+    match cuts {
+        (Some((a, from_t)), Some((b, to_t))) if a == b => Step::Cut {
+            side_by_side: a,
+            t: lerp(from_t, to_t),
+            halves: [(from_halves.0, to_halves.0), (from_halves.1, to_halves.1)],
+        },
+        (Some((side_by_side, from_t)), _) => Step::Cut {
+            side_by_side,
+            t: lerp(from_t, edge(from_t)),
+            halves: if from_t >= 0.5 {
+                [(from_halves.0, to), (from_halves.1, None)]
+            } else {
+                [(from_halves.0, None), (from_halves.1, to)]
+            },
+        },
+        (None, Some((side_by_side, to_t))) => Step::Cut {
+            side_by_side,
+            t: lerp(edge(to_t), to_t),
+            halves: if to_t >= 0.5 {
+                [(from, to_halves.0), (None, to_halves.1)]
+            } else {
+                [(None, to_halves.0), (from, to_halves.1)]
+            },
+        },
+        (None, None) => Step::Leaf(from.map(|(_, pane)| pane), to.map(|(_, pane)| pane)),
+    }
+}
+
+/// the split tree `t` of the way from `from`'s to `to`'s, over `bounds`, with cuts on the grid
+/// `options` says, and the [`Source`] of each of its leaves
+pub fn layout(
+    from: &Tree<Rectangle>,
+    to: &Tree<Rectangle>,
+    bounds: Rectangle,
+    t: f32,
+    options: &SplitOptions,
+) -> (Tree<Rectangle>, Vec<Source>) {
+    let t = t.clamp(0.0, 1.0);
+    let mut tree = Tree::new(bounds);
+    let mut sources = vec![];
+    // what goes in the nodes the tree hasn't reached yet, in the order it reaches them
+    let mut pending = vec![(Some((from.root(), 0)), Some((to.root(), 0)))];
+    tree.split_by(|rectangle, _| {
+        let (mut from, mut to) = pending.pop().expect("one pair per node");
+        loop {
+            match step(from, to, t) {
+                Step::Leaf(from, to) => {
+                    sources.push((from, to));
+                    return None;
+                }
+                Step::Cut {
+                    side_by_side,
+                    t,
+                    halves: [first, second],
+                } => {
+                    let (left, right) = rectangle.divide(side_by_side, t, options);
+                    // a half with nothing in it isn't cut off at all; the other is the node
+                    let empty = |half: &Rectangle| half.width <= 0.0 || half.height <= 0.0;
+                    if empty(&left) {
+                        (from, to) = second;
+                    } else if empty(&right) {
+                        (from, to) = first;
+                    } else {
+                        pending.push(second);
+                        pending.push(first);
+                        return Some((left, right));
+                    }
+                }
+            }
+        }
+    });
+    (tree, sources)
+}
@@ -0,0 +1,127 @@
+//! abstracting a photo: panes get cut again wherever the picture under them is busy, and each
+//! ends up the palette color closest to what it covers
+
+use std::path::{Path, PathBuf};
+
+use image::{imageops::FilterType, ImageError, Rgb, RgbImage};
+
+use crate::{
+    color::Palette,
+    geometry::{Rectangle, SplitOptions},
+    tree::Tree,
+};
+
+/// how far apart a region's colors can be, as a standard deviation in 0-255 steps, before the
+/// mosaic cuts it again, unless set otherwise
+pub const DEFAULT_TOLERANCE: f32 = 24.0;
+
+/// a photo to approximate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mosaic {
+    pub path: PathBuf,
+    /// see [`DEFAULT_TOLERANCE`]
+    pub tolerance: f32,
+}
+
+impl Mosaic {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+/// the image at `path`, scaled to cover `bounds` and cropped to the middle of it, one pixel per
+/// canvas pixel
+pub fn load(path: &Path, bounds: &Rectangle) -> Result<RgbImage, ImageError> {
+    let (width, height) = (bounds.width.max(1.0) as u32, bounds.height.max(1.0) as u32);
+    Ok(image::open(path)?
+        .resize_to_fill(width, height, FilterType::Triangle)
+        .to_rgb8())
+}
+
+/// halves every node across its long side, quadtree fashion, for as long as the part of `image`
+/// under it spreads wider than `tolerance` and it's fewer than `levels` cuts deep
+pub fn layout(
+    bounds: Rectangle,
+    image: &RgbImage,
+    levels: usize,
+    tolerance: f32,
+    options: &SplitOptions,
+) -> Tree<Rectangle> {
+    let mut tree = Tree::new(bounds);
+    tree.split_by(|rectangle, depth| {
+        // nothing left to split once a pane is down to a pixel
+        if depth >= levels || rectangle.width.max(rectangle.height) < 2.0 {
+            return None;
+        }
+        let (_, spread) = region(image, &bounds, rectangle);
+        (spread > tolerance as f64)
+            .then(|| rectangle.divide(rectangle.width >= rectangle.height, 0.5, options))
+    });
+    tree
+}
+
+/// for each of `leaves`, whichever color of `palette` (leaving out any weighted zero) or
+/// `background` is nearest the average of `image` under it
+pub fn colors(
+    leaves: &[Rectangle],
+    bounds: &Rectangle,
+    image: &RgbImage,
+    palette: &Palette,
+    background: Rgb<u8>,
+) -> Vec<Rgb<u8>> {
+    let candidates: Vec<Rgb<u8>> = palette
+        .colors()
+        .iter()
+        .zip(palette.weights())
+        .filter(|(_, weight)| **weight > 0)
+        .map(|(color, _)| *color)
+        .chain([background])
+        .collect();
+    leaves
+        .iter()
+        .map(|leaf| {
+            let (mean, _) = region(image, bounds, leaf);
+            let distance = |color: &Rgb<u8>| {
+                (0..3)
+                    .map(|channel| (color.0[channel] as f64 - mean[channel]).powi(2))
+                    .sum::<f64>()
+            };
+            *candidates
+                .iter()
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                .expect("there's always the background")
+        })
+        .collect()
+}
+
+/// the average color of the pixels of `image` under `rectangle`, and how far they spread around
+/// it, as one standard deviation pooled over the channels
+fn region(image: &RgbImage, bounds: &Rectangle, rectangle: &Rectangle) -> ([f64; 3], f64) {
+    let span = |start: f32, length: f32, origin: f32, limit: u32| {
+        let from = ((start - origin).floor().max(0.0) as u32).min(limit - 1);
+        let to = ((start + length - origin).ceil() as u32).clamp(from + 1, limit);
+        from..to
+    };
+    let columns = span(rectangle.x, rectangle.width, bounds.x, image.width());
+    let rows = span(rectangle.y, rectangle.height, bounds.y, image.height());
+
+    let (mut sum, mut squares) = ([0.0; 3], [0.0; 3]);
+    for y in rows.clone() {
+        for x in columns.clone() {
+            for (channel, &value) in image.get_pixel(x, y).0.iter().enumerate() {
+                let value = value as f64;
+                sum[channel] += value;
+                squares[channel] += value * value;
+            }
+        }
+    }
+    let count = (columns.len() * rows.len()) as f64;
+    let mean = sum.map(|total| total / count);
+    let variance: f64 = (0..3)
+        .map(|channel| (squares[channel] / count - mean[channel].powi(2)).max(0.0))
+        .sum();
+    (mean, (variance / 3.0).sqrt())
+}
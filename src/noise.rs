@@ -0,0 +1,82 @@
+//! Perlin noise, for split strategies that want to be denser in some places than others
+
+/// how the noise field [`Builtin::Noise`](crate::strategy::Builtin::Noise) cuts by looks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseOptions {
+    /// how many blobs fit across the canvas's long side, about
+    pub frequency: f32,
+    /// layers of finer and finer detail added on top, each at twice the frequency and half the
+    /// strength of the last; at least 1
+    pub octaves: u32,
+}
+
+impl Default for NoiseOptions {
+    fn default() -> Self {
+        Self {
+            frequency: 3.0,
+            octaves: 4,
+        }
+    }
+}
+
+/// gradient noise over the plane, the same for the same seed, roughly -1 to 1 and 0 on every
+/// whole-number point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Perlin {
+    pub seed: u64,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// the unit gradient at a lattice point, from a hash of it
+    fn gradient(&self, x: i64, y: i64) -> (f32, f32) {
+        // splitmix64, over the point and the seed
+        let mut h = self.seed ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        h ^= (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+        let angle = (h >> 40) as f32 / (1u64 << 24) as f32 * std::f32::consts::TAU;
+        (angle.cos(), angle.sin())
+    }
+
+    pub fn get(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i64, y0 as i64);
+        let corner = |dx: i64, dy: i64| {
+            let (gx, gy) = self.gradient(ix + dx, iy + dy);
+            gx * (fx - dx as f32) + gy * (fy - dy as f32)
+        };
+        // smootherstep, so the field's slope is continuous across cells too
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let (u, v) = (fade(fx), fade(fy));
+        let top = lerp(corner(0, 0), corner(1, 0), u);
+        let bottom = lerp(corner(0, 1), corner(1, 1), u);
+        // the most a 2D gradient noise reaches is √½, so this stretches it out to about ±1
+        lerp(top, bottom, v) * std::f32::consts::SQRT_2
+    }
+
+    /// `octaves` layers of [`get`](Self::get) added up, each twice as fine and half as strong,
+    /// scaled back to roughly -1 to 1
+    pub fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut sum, mut total, mut amplitude, mut scale) = (0.0, 0.0, 1.0, 1.0);
+        for octave in 0..octaves {
+            // each layer elsewhere in the field, so their zeroes don't line up
+            let offset = octave as f32 * 17.31;
+            sum += amplitude * self.get(x * scale + offset, y * scale - offset);
+            total += amplitude;
+            amplitude /= 2.0;
+            scale *= 2.0;
+        }
+        if total > 0.0 {
+            sum / total
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! color math done in Björn Ottosson's OKLab, where equal steps look like equal steps: a ramp
+//! from red to blue goes through a purple rather than a muddy one, and a darker blue is still
+//! blue
+//!
+//! `oklab::mix(red, white, 0.5)` is a pink, `oklab::darken(blue, 0.4)` a navy, and
+//! [`Oklch`] makes colors from a lightness, chroma and hue
+
+use image::Rgb;
+
+use crate::color::{from_linear, to_linear};
+
+/// a color's lightness `l`, from 0 (black) to 1 (white), and where it sits on the green–red (`a`)
+/// and blue–yellow (`b`) axes; gray has both at 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// [`Oklab`] around the gray axis: lightness, how far out from gray (the chroma) and which way,
+/// in degrees, with red about 30, yellow about 110 and blue about 265
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl Oklab {
+    pub fn from_rgb(color: Rgb<u8>) -> Self {
+        let [r, g, b] = color.0.map(|channel| to_linear(channel as f32 / 255.0));
+        let l = (0.4122215 * r + 0.5363325 * g + 0.051446 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.107397 * b).cbrt();
+        let s = (0.0883025 * r + 0.2817188 * g + 0.6299787 * b).cbrt();
+        Self {
+            l: 0.2104543 * l + 0.7936178 * m - 0.004072 * s,
+            a: 1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            b: 0.025904 * l + 0.7827718 * m - 0.8086758 * s,
+        }
+    }
+
+    /// the nearest sRGB color, each channel clipped if this is outside what sRGB can show
+    pub fn to_rgb(self) -> Rgb<u8> {
+        Rgb(self
+            .to_linear()
+            .map(|channel| (from_linear(channel.clamp(0.0, 1.0)) * 255.0).round() as u8))
+    }
+
+    /// `t` of the way from `self` to `other`, in a straight line
+    pub fn mix(self, other: Self, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            l: lerp(self.l, other.l),
+            a: lerp(self.a, other.a),
+            b: lerp(self.b, other.b),
+        }
+    }
+
+    pub fn to_lch(self) -> Oklch {
+        Oklch {
+            l: self.l,
+            c: self.a.hypot(self.b),
+            h: self.b.atan2(self.a).to_degrees().rem_euclid(360.0),
+        }
+    }
+
+    /// linear sRGB, unclipped
+    fn to_linear(self) -> [f32; 3] {
+        let l = (self.l + 0.3963378 * self.a + 0.2158038 * self.b).powi(3);
+        let m = (self.l - 0.1055613 * self.a - 0.0638542 * self.b).powi(3);
+        let s = (self.l - 0.0894842 * self.a - 1.2914855 * self.b).powi(3);
+        [
+            4.0767417 * l - 3.3077116 * m + 0.2309699 * s,
+            -1.268438 * l + 2.6097574 * m - 0.3413194 * s,
+            -0.0041961 * l - 0.7034186 * m + 1.7076147 * s,
+        ]
+    }
+
+    fn in_gamut(self) -> bool {
+        self.to_linear()
+            .iter()
+            .all(|channel| (-1e-4..=1.0 + 1e-4).contains(channel))
+    }
+}
+
+impl Oklch {
+    /// `h` in degrees, any, it wraps
+    pub fn new(l: f32, c: f32, h: f32) -> Self {
+        Self { l, c, h }
+    }
+
+    pub fn from_rgb(color: Rgb<u8>) -> Self {
+        Oklab::from_rgb(color).to_lch()
+    }
+
+    pub fn to_lab(self) -> Oklab {
+        let h = self.h.to_radians();
+        Oklab {
+            l: self.l,
+            a: self.c * h.cos(),
+            b: self.c * h.sin(),
+        }
+    }
+
+    /// the sRGB color with this lightness and hue, and as much of the chroma as sRGB can show
+    /// there; less saturated rather than a different hue, which clipping would give
+    pub fn to_rgb(self) -> Rgb<u8> {
+        let l = self.l.clamp(0.0, 1.0);
+        let fits = |c: f32| Self { l, c, h: self.h }.to_lab().in_gamut();
+        if fits(self.c) {
+            return Self { l, ..self }.to_lab().to_rgb();
+        }
+        let (mut low, mut high) = (0.0, self.c);
+        for _ in 0..16 {
+            let c = (low + high) / 2.0;
+            if fits(c) {
+                low = c;
+            } else {
+                high = c;
+            }
+        }
+        Self {
+            l,
+            c: low,
+            h: self.h,
+        }
+        .to_lab()
+        .to_rgb()
+    }
+}
+
+/// `t` of the way from `from` to `to`, 0 giving `from` and 1 `to`
+pub fn mix(from: Rgb<u8>, to: Rgb<u8>, t: f32) -> Rgb<u8> {
+    Oklab::from_rgb(from).mix(Oklab::from_rgb(to), t).to_rgb()
+}
+
+/// `amount` of the way to white
+pub fn lighten(color: Rgb<u8>, amount: f32) -> Rgb<u8> {
+    mix(color, Rgb([255, 255, 255]), amount)
+}
+
+/// `amount` of the way to black, which keeps the hue, and takes the chroma down with the
+/// lightness so it doesn't glow
+pub fn darken(color: Rgb<u8>, amount: f32) -> Rgb<u8> {
+    mix(color, Rgb([0, 0, 0]), amount)
+}
+
+/// how light `color` looks, from 0 to 1
+pub fn lightness(color: Rgb<u8>) -> f32 {
+    Oklab::from_rgb(color).l
+}
+
+/// `color` with its hue turned `hue` degrees and `lightness` added to its lightness, keeping as
+/// much of its chroma as still fits
+pub fn drift(color: Rgb<u8>, hue: f32, lightness: f32) -> Rgb<u8> {
+    if hue == 0.0 && lightness == 0.0 {
+        return color;
+    }
+    let Oklch { l, c, h } = Oklch::from_rgb(color);
+    Oklch::new(l + lightness, c, h + hue).to_rgb()
+}
+
+/// `color`'s counterpart on a dark background: near-whites and grays go as dark as they were
+/// light, black going to a middling gray, and colors keep their hue but lose a fifth of their
+/// chroma and a little lightness, so they don't glare
+pub fn dark(color: Rgb<u8>) -> Rgb<u8> {
+    let Oklch { l, c, h } = Oklch::from_rgb(color);
+    if c < 0.05 {
+        Oklch::new(0.18 + (1.0 - l) * 0.35, c, h).to_rgb()
+    } else {
+        Oklch::new(l * 0.85, c * 0.8, h).to_rgb()
+    }
+}
@@ -0,0 +1,268 @@
+//! palettes from files, and finding them by name among the built-in ones and any installed in
+//! [`user_dir`]
+
+use std::{
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::Rgb;
+
+use crate::color::{self, Palette};
+
+/// the palettes that come with the crate, by name; the last three are safe for color-blind
+/// viewers
+pub const BUILTIN: [(&str, Palette); 9] = [
+    ("classic", Palette::CLASSIC),
+    ("bauhaus", Palette::BAUHAUS),
+    ("grayscale", Palette::GRAYSCALE),
+    ("pastel", Palette::PASTEL),
+    ("warm", Palette::WARM),
+    ("night", Palette::NIGHT),
+    ("okabe-ito", Palette::OKABE_ITO),
+    ("ibm", Palette::IBM),
+    ("tol-bright", Palette::TOL_BRIGHT),
+];
+
+/// the extensions [`read`] knows, and so the files in [`user_dir`] that count as palettes
+pub const EXTENSIONS: [&str; 3] = ["gpl", "txt", "json"];
+
+/// why a palette couldn't be read or found
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(io::Error),
+    /// a line of a palette file that isn't a color
+    Line {
+        line: usize,
+        why: String,
+    },
+    /// not JSON, or not JSON shaped like a palette
+    Json(String),
+    /// JSON input without the `json` feature
+    Unsupported,
+    /// a file with no colors in it
+    Empty,
+    /// no built-in or installed palette by that name
+    NotFound(String),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the palette: {err}"),
+            Self::Line { line, why } => write!(f, "line {line}: {why}"),
+            Self::Json(why) => write!(f, "couldn't read the JSON: {why}"),
+            Self::Unsupported => write!(
+                f,
+                "reading JSON needs mondrian-rs to be built with the `json` feature"
+            ),
+            Self::Empty => write!(f, "the palette has no colors in it"),
+            Self::NotFound(name) => write!(
+                f,
+                "no palette file or palette called {name:?}; `palette list` shows the ones there are"
+            ),
+        }
+    }
+}
+
+impl Error for PaletteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PaletteError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// [`parse_gpl`] for GIMP palettes (by extension, or the header they start with), [`parse_json`]
+/// for `.json` files, and [`parse_text`] for anything else
+pub fn read(path: impl AsRef<Path>) -> Result<Palette, PaletteError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let palette = if extension == Some("gpl") || text.starts_with("GIMP Palette") {
+        parse_gpl(&text)?
+    } else if extension == Some("json") {
+        parse_json(&text)?
+    } else {
+        parse_text(&text)?
+    };
+    if palette.colors().is_empty() {
+        return Err(PaletteError::Empty);
+    }
+    Ok(palette)
+}
+
+/// GIMP's format: a `GIMP Palette` header, maybe `Name:` and `Columns:` lines, then `r g b name`
+/// per color; every color is equally likely, since there's nowhere to put a weight
+pub fn parse_gpl(text: &str) -> Result<Palette, PaletteError> {
+    let mut colors = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let header = ["GIMP Palette", "Name:", "Columns:"]
+            .iter()
+            .any(|header| line.starts_with(header));
+        if line.is_empty() || line.starts_with('#') || header {
+            continue;
+        }
+        let channels: Option<Vec<u8>> = line
+            .split_whitespace()
+            .take(3)
+            .map(|channel| channel.parse().ok())
+            .collect();
+        match channels.as_deref() {
+            Some(&[r, g, b]) => colors.push(Rgb([r, g, b])),
+            _ => {
+                return Err(PaletteError::Line {
+                    line: index + 1,
+                    why: format!("expected `red green blue`, each 0 to 255, got {line:?}"),
+                })
+            }
+        }
+    }
+    Ok(Palette::new(colors))
+}
+
+/// one color per line, in any form [`color::parse`] takes, optionally followed by a weight, e.g.
+/// `#ff0000 2`; lines starting with `# ` (with the space, unlike hex) or `//` are comments
+pub fn parse_text(text: &str) -> Result<Palette, PaletteError> {
+    let (mut colors, mut weights) = (vec![], vec![]);
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line == "#" || line.starts_with("# ") || line.starts_with("//") {
+            continue;
+        }
+        // `rgb(1 2 3)` has spaces in it too, so the last word is only a weight if it's a number
+        let (color, weight) = match line.rsplit_once(char::is_whitespace) {
+            Some((color, weight)) if weight.parse::<u32>().is_ok() => {
+                (color, weight.parse().expect("just parsed it"))
+            }
+            _ => (line, 1),
+        };
+        let color = color::parse(color).map_err(|err| PaletteError::Line {
+            line: index + 1,
+            why: err.to_string(),
+        })?;
+        colors.push(color);
+        weights.push(weight);
+    }
+    Ok(Palette::new(colors).with_weights(weights))
+}
+
+/// an array of colors, each either a string like `"#ff0000"` or an object like
+/// `{"color": "crimson", "weight": 2}`, or an object with an array like that under `colors`
+#[cfg(feature = "json")]
+pub fn parse_json(text: &str) -> Result<Palette, PaletteError> {
+    use serde_json::Value;
+
+    let json = PaletteError::Json;
+    let value: Value = serde_json::from_str(text).map_err(|err| json(err.to_string()))?;
+    let entries = match &value {
+        Value::Array(entries) => entries,
+        Value::Object(map) => match map.get("colors") {
+            Some(Value::Array(entries)) => entries,
+            _ => return Err(json("expected a `colors` array".to_owned())),
+        },
+        _ => return Err(json("expected an array of colors".to_owned())),
+    };
+    let color = |index: usize, text: &str| {
+        color::parse(text).map_err(|err| json(format!("color {index}: {err}")))
+    };
+    let (mut colors, mut weights) = (vec![], vec![]);
+    for (index, entry) in entries.iter().enumerate() {
+        let (text, weight) = match entry {
+            Value::String(text) => (text.as_str(), 1),
+            Value::Object(_) => {
+                let Some(text) = entry.get("color").and_then(Value::as_str) else {
+                    return Err(json(format!("color {index} has no `color` string")));
+                };
+                let weight = match entry.get("weight") {
+                    None => 1,
+                    Some(weight) => weight
+                        .as_u64()
+                        .and_then(|weight| u32::try_from(weight).ok())
+                        .ok_or_else(|| {
+                            json(format!("the weight of color {index} isn't a whole number"))
+                        })?,
+                };
+                (text, weight)
+            }
+            _ => return Err(json(format!("color {index} isn't a string or an object"))),
+        };
+        colors.push(color(index, text)?);
+        weights.push(weight);
+    }
+    Ok(Palette::new(colors).with_weights(weights))
+}
+
+#[cfg(not(feature = "json"))]
+pub fn parse_json(_text: &str) -> Result<Palette, PaletteError> {
+    Err(PaletteError::Unsupported)
+}
+
+/// `mondrian-rs` under `$XDG_CONFIG_HOME` (or `~/.config`), or under `%APPDATA%` on Windows
+pub fn config_dir() -> Option<PathBuf> {
+    let config = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    Some(config?.join("mondrian-rs"))
+}
+
+/// where installed palettes live: `palettes` in [`config_dir`]
+pub fn user_dir() -> Option<PathBuf> {
+    Some(config_dir()?.join("palettes"))
+}
+
+/// the palette files in [`user_dir`], sorted; none if it doesn't exist
+pub fn installed() -> Result<Vec<PathBuf>, io::Error> {
+    let Some(dir) = user_dir() else {
+        return Ok(vec![]);
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let mut paths = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let known = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| EXTENSIONS.contains(&extension));
+        if known && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// the palette called `name`: an installed file with that name before the extension, which can
+/// stand in for a built-in one, or else the built-in one
+pub fn find(name: &str) -> Result<Palette, PaletteError> {
+    let file = installed()?
+        .into_iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem == name));
+    if let Some(path) = file {
+        return read(path);
+    }
+    BUILTIN
+        .into_iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, palette)| palette)
+        .ok_or_else(|| PaletteError::NotFound(name.to_owned()))
+}
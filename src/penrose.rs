@@ -0,0 +1,188 @@
+//! the aperiodic style: Penrose's tiling of thick and thin rhombs (his P3), grown by cutting a
+//! wheel of Robinson triangles into smaller ones over and over, turned and centered at random
+
+use std::{
+    collections::{HashMap, HashSet},
+    f64::consts::PI,
+};
+
+use rand::Rng;
+
+use crate::{
+    geometry::Rectangle,
+    polygon::{Point, Polygon, Segment},
+};
+
+/// the golden ratio, which every cut shrinks the triangles by
+const PHI: f64 = 1.618_033_988_749_895;
+/// a rhomb's area over the square of its side, averaged over thick and thin ones in the
+/// proportion the tiling has them, φ to 1
+const RHOMB_AREA: f64 = 0.812;
+/// corners closer than this, in pixels, are taken to be the same one
+const SNAP: f64 = 1.0 / 1024.0;
+
+/// half a rhomb, cut across the diagonal `b`–`c`; `a` is the corner opposite, and the sides
+/// either side of it are the rhomb's
+#[derive(Debug, Clone, Copy)]
+struct Triangle {
+    /// half a thin rhomb, 36° at `a`; otherwise half a thick one, 108° at `a`
+    thin: bool,
+    a: (f64, f64),
+    b: (f64, f64),
+    c: (f64, f64),
+}
+
+impl Triangle {
+    /// the Robinson triangles it's cut into, whose sides are shorter by φ
+    fn cut(&self) -> Vec<Triangle> {
+        let towards = |from: (f64, f64), to: (f64, f64)| {
+            (
+                from.0 + (to.0 - from.0) / PHI,
+                from.1 + (to.1 - from.1) / PHI,
+            )
+        };
+        let triangle = |thin, a, b, c| Triangle { thin, a, b, c };
+        let Triangle { a, b, c, .. } = *self;
+        if self.thin {
+            let p = towards(a, b);
+            vec![triangle(true, c, p, b), triangle(false, p, c, a)]
+        } else {
+            let (q, r) = (towards(b, a), towards(b, c));
+            vec![
+                triangle(false, r, c, a),
+                triangle(false, q, r, b),
+                triangle(true, r, q, a),
+            ]
+        }
+    }
+
+    /// whether it could have any of `bounds` in it, going by the box around it
+    fn touches(&self, bounds: &Rectangle) -> bool {
+        let xs = [self.a.0, self.b.0, self.c.0];
+        let ys = [self.a.1, self.b.1, self.c.1];
+        let (left, right) = (
+            xs.into_iter().fold(f64::MAX, f64::min),
+            xs.into_iter().fold(f64::MIN, f64::max),
+        );
+        let (top, bottom) = (
+            ys.into_iter().fold(f64::MAX, f64::min),
+            ys.into_iter().fold(f64::MIN, f64::max),
+        );
+        right >= bounds.x as f64
+            && left <= (bounds.x + bounds.width) as f64
+            && bottom >= bounds.y as f64
+            && top <= (bounds.y + bounds.height) as f64
+    }
+}
+
+/// a corner, rounded so the same one reached two ways is equal
+fn key((x, y): (f64, f64)) -> (i64, i64) {
+    ((x / SNAP).round() as i64, (y / SNAP).round() as i64)
+}
+
+/// the two ends of a side, whichever way round it was given
+fn side_key(from: (f64, f64), to: (f64, f64)) -> [(i64, i64); 2] {
+    let (from, to) = (key(from), key(to));
+    if from <= to {
+        [from, to]
+    } else {
+        [to, from]
+    }
+}
+
+/// rhombs with sides about as long as `count` of them covering `bounds` takes, cut off where
+/// they run past it, and every line between two of them once; the canvas edges aren't lines
+pub fn tiles<R: Rng + ?Sized>(
+    bounds: &Rectangle,
+    count: usize,
+    rng: &mut R,
+) -> (Vec<Polygon>, Vec<Segment>) {
+    let (width, height) = (bounds.width as f64, bounds.height as f64);
+    let center = (
+        bounds.x as f64 + rng.gen::<f64>() * width,
+        bounds.y as f64 + rng.gen::<f64>() * height,
+    );
+    let turn = rng.gen::<f64>() * 2.0 * PI;
+    // far enough out that the wheel, a decagon, covers the corner farthest from its middle
+    let reach = [bounds.x as f64, bounds.x as f64 + width]
+        .into_iter()
+        .flat_map(|x| [bounds.y as f64, bounds.y as f64 + height].map(|y| (x, y)))
+        .map(|(x, y)| (x - center.0).hypot(y - center.1))
+        .fold(0.0, f64::max);
+    let radius = reach / (PI / 10.0).cos();
+    let side = (width * height / count.max(1) as f64 / RHOMB_AREA).sqrt();
+    let cuts = ((radius / side).ln() / PHI.ln()).round().max(0.0) as u32;
+
+    // ten thin halves around the middle, every other one mirrored so neighbors share sides
+    let at = |step: i32| {
+        let angle = turn + step as f64 * PI / 10.0;
+        (
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        )
+    };
+    let mut triangles: Vec<Triangle> = (0..10)
+        .map(|i| {
+            let (b, c) = (at(2 * i - 1), at(2 * i + 1));
+            let (b, c) = if i % 2 == 0 { (c, b) } else { (b, c) };
+            Triangle {
+                thin: true,
+                a: center,
+                b,
+                c,
+            }
+        })
+        .collect();
+    for _ in 0..cuts {
+        // what's off the canvas only ever gets cut into more of what's off the canvas
+        triangles = triangles
+            .iter()
+            .flat_map(Triangle::cut)
+            .filter(|triangle| triangle.touches(bounds))
+            .collect();
+    }
+
+    // the two halves of each rhomb share a diagonal; halves whose other half is off
+    // the canvas stay triangles, and only their part on it is left anyway
+    let mut halves: HashMap<[(i64, i64); 2], usize> = HashMap::new();
+    let mut mates = vec![None; triangles.len()];
+    for (i, triangle) in triangles.iter().enumerate() {
+        let diagonal = side_key(triangle.b, triangle.c);
+        if let Some(j) = halves.remove(&diagonal) {
+            mates[i] = Some(j);
+            mates[j] = Some(i);
+        } else {
+            halves.insert(diagonal, i);
+        }
+    }
+    let point = |(x, y): (f64, f64)| Point::new(x as f32, y as f32);
+    let mut rhombs = vec![];
+    for (i, triangle) in triangles.iter().enumerate() {
+        let corners = match mates[i] {
+            Some(j) if j < i => continue,
+            Some(j) => vec![triangle.a, triangle.b, triangles[j].a, triangle.c],
+            None => vec![triangle.a, triangle.b, triangle.c],
+        };
+        let rhomb = Polygon::new(corners.into_iter().map(point).collect::<Vec<_>>());
+        let rhomb = rhomb.clipped_to(bounds);
+        if rhomb.area() > 0.0 {
+            rhombs.push(rhomb);
+        }
+    }
+
+    // the sides from `a`, which are the rhombs'; each is shared with a neighbor
+    let mut seen = HashSet::new();
+    let segments = triangles
+        .iter()
+        .flat_map(|triangle| [(triangle.a, triangle.b), (triangle.a, triangle.c)])
+        .filter(|&(from, to)| seen.insert(side_key(from, to)))
+        .filter_map(|(from, to)| {
+            let segment = Segment {
+                from: point(from),
+                to: point(to),
+            };
+            segment.clipped_to(bounds)
+        })
+        .collect();
+    (rhombs, segments)
+}
@@ -0,0 +1,699 @@
+//! a playlist for signage: sets of settings, each put up on a cron-like schedule and kept until
+//! the next one's time comes, so one long-running daemon can go through different sizes,
+//! palettes and styles over the day
+//!
+//! it's written in a small part of TOML: settings at the top are shared by every entry, and
+//! each `[[entry]]` has a `schedule` and its own settings, which win over the shared ones.
+//! settings are named after the flags, and take strings, numbers, `true` for a flag without a
+//! value, or arrays for one given more than once:
+//!
+//! ```text
+//! output = "/srv/signage/now.png"
+//! size = "1080p"
+//!
+//! # weekday mornings
+//! [[entry]]
+//! schedule = "0 7 * * 1-5"
+//! palette = "warm"
+//!
+//! [[entry]]
+//! schedule = "30 12 * * *"
+//! palette = "classic"
+//! levels = 6
+//! text = ["lunch", "{time}"]
+//! ```
+//!
+//! a schedule is minute, hour, day of the month, month and day of the week (0 or 7 for Sunday),
+//! each `*`, a number, a range `1-5`, a step `*/15` or `8-18/2`, or a list of those; or one of
+//! `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly`. as in cron, when both days are given
+//! either will do. the times are local, like the [palette schedule](crate::schedule)'s
+
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+use crate::calendar::Date;
+
+/// minutes in a day
+const DAY: i64 = 24 * 60;
+/// how many days back and ahead a schedule is looked for: long enough for one that's only on
+/// the 29th of February
+const LOOK: i64 = 8 * 366;
+
+/// why a playlist couldn't be read
+#[derive(Debug)]
+pub enum PlaylistError {
+    Io(io::Error),
+    /// a line that isn't what the [module docs](self) say a line can be
+    Line {
+        line: usize,
+        why: String,
+    },
+    /// an entry with no `schedule`, or one that doesn't make sense
+    Schedule {
+        entry: usize,
+        why: String,
+    },
+    /// no `[[entry]]` in it at all
+    Empty,
+}
+
+impl fmt::Display for PlaylistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the playlist: {err}"),
+            Self::Line { line, why } => write!(f, "line {line} of the playlist: {why}"),
+            Self::Schedule { entry, why } => write!(f, "entry {entry} of the playlist: {why}"),
+            Self::Empty => write!(f, "the playlist has no [[entry]] in it"),
+        }
+    }
+}
+
+impl Error for PlaylistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PlaylistError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// what a setting is set to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// a string, or a number as it was written
+    Text(String),
+    /// a flag that's given or not
+    Switch(bool),
+    /// a flag given once for each
+    List(Vec<String>),
+}
+
+/// when to put an entry up: the minutes, hours, days, months and weekdays it fires on, a bit
+/// each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    /// from bit 1
+    days: u32,
+    /// from bit 1
+    months: u16,
+    /// from Sunday, bit 0
+    weekdays: u8,
+    /// whether the days of the month and of the week were each left `*`
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// whether it fires on `day` days after 1970-01-01
+    fn fires_on(&self, day: i64) -> bool {
+        let date = Date::from_days(day);
+        let weekday = (date.weekday() + 1) % 7;
+        let (by_date, by_weekday) = (
+            self.days & 1 << date.day != 0,
+            self.weekdays & 1 << weekday != 0,
+        );
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => by_date,
+            (true, false) => by_weekday,
+            (false, false) => by_date || by_weekday,
+        };
+        day_matches && self.months & 1 << date.month != 0
+    }
+
+    /// the minutes after 1970-01-01 it fires at on `day`, earliest first
+    fn times_on(&self, day: i64) -> impl DoubleEndedIterator<Item = i64> + '_ {
+        let minutes = if self.fires_on(day) { 0..DAY } else { 0..0 };
+        minutes
+            .filter(|minute| {
+                self.hours & 1 << (minute / 60) != 0 && self.minutes & 1 << (minute % 60) != 0
+            })
+            .map(move |minute| day * DAY + minute)
+    }
+
+    /// the last time, in minutes after 1970-01-01, it fired at or before `minute`
+    pub fn last(&self, minute: i64) -> Option<i64> {
+        let today = minute.div_euclid(DAY);
+        (today - LOOK..=today)
+            .rev()
+            .find_map(|day| self.times_on(day).rev().find(|&at| at <= minute))
+    }
+
+    /// the first time, in minutes after 1970-01-01, it fires after `minute`
+    pub fn next(&self, minute: i64) -> Option<i64> {
+        let today = minute.div_euclid(DAY);
+        (today..=today + LOOK).find_map(|day| self.times_on(day).find(|&at| at > minute))
+    }
+}
+
+/// the format in the [module docs](self): `0 7 * * 1-5`
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expanded = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "{s:?} isn't a schedule of minute, hour, day, month and weekday, like `0 7 * * 1-5`"
+            ));
+        };
+        let sunday_to_sunday = field(weekdays, 0, 7, "weekday")?;
+        Ok(Self {
+            minutes: field(minutes, 0, 59, "minute")?,
+            hours: field(hours, 0, 23, "hour")? as u32,
+            days: field(days, 1, 31, "day")? as u32,
+            months: field(months, 1, 12, "month")? as u16,
+            // 7 is Sunday too
+            weekdays: (sunday_to_sunday | sunday_to_sunday >> 7) as u8 & 0x7f,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+/// one field of a schedule, a bit for each of `low..=high` it takes in
+fn field(text: &str, low: u32, high: u32, name: &str) -> Result<u64, String> {
+    let invalid = || format!("{text:?} isn't a {name} from {low} to {high}");
+    let mut bits = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let number = |text: &str| {
+            text.parse::<u32>()
+                .ok()
+                .filter(|n| (low..=high).contains(n))
+                .ok_or_else(invalid)
+        };
+        let (from, to) = match range.split_once('-') {
+            _ if range == "*" => (low, high),
+            Some((from, to)) => (number(from)?, number(to)?),
+            // a step from a single number goes on to the end
+            None if part.contains('/') => (number(range)?, high),
+            None => (number(range)?, number(range)?),
+        };
+        if step == 0 || from > to {
+            return Err(invalid());
+        }
+        for n in (from..=to).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+/// settings put up on a schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub schedule: Cron,
+    /// its own settings after the shared ones, so a name given in both is the entry's
+    pub settings: Vec<(String, Value)>,
+}
+
+impl Entry {
+    /// each setting once, the last one given under its name
+    pub fn merged(&self) -> Vec<(&str, &Value)> {
+        let mut merged: Vec<(&str, &Value)> = vec![];
+        for (name, value) in &self.settings {
+            merged.retain(|(other, _)| other != name);
+            merged.push((name, value));
+        }
+        merged
+    }
+}
+
+/// the entries of a playlist, in the order they're written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    pub entries: Vec<Entry>,
+}
+
+impl Playlist {
+    pub fn open(path: &Path) -> Result<Self, PlaylistError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// which entry is up at `minute` minutes after 1970-01-01, local time: the one whose
+    /// schedule fired last, the first written if two fired at once. the first if none has
+    pub fn at(&self, minute: i64) -> usize {
+        let fired = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((entry.schedule.last(minute)?, index)));
+        fired
+            .max_by_key(|&(at, index)| (at, std::cmp::Reverse(index)))
+            .map_or(0, |(_, index)| index)
+    }
+
+    /// when, in minutes after 1970-01-01, the next entry's time comes after `minute`
+    pub fn next(&self, minute: i64) -> Option<i64> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.schedule.next(minute))
+            .min()
+    }
+}
+
+/// the format in the [module docs](self)
+impl FromStr for Playlist {
+    type Err = PlaylistError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut shared = vec![];
+        // each entry's settings, with the line its `[[entry]]` is on
+        let mut entries: Vec<(usize, Vec<(String, Value)>)> = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let at = |why: String| PlaylistError::Line {
+                line: index + 1,
+                why,
+            };
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                if line != "[[entry]]" {
+                    return Err(at(format!("only [[entry]] tables go in it, not {line}")));
+                }
+                entries.push((index + 1, vec![]));
+                continue;
+            }
+            let (name, value) = line.split_once('=').ok_or_else(|| {
+                at(format!(
+                    "expected a setting like `palette = \"warm\"`, got {line:?}"
+                ))
+            })?;
+            let name = key(name.trim()).map_err(at)?;
+            let value = parse_value(value.trim()).map_err(at)?;
+            let settings = match entries.last_mut() {
+                Some((_, settings)) => settings,
+                None => &mut shared,
+            };
+            if settings.iter().any(|(other, _)| *other == name) {
+                return Err(at(format!("{name} is set twice")));
+            }
+            settings.push((name, value));
+        }
+        if entries.is_empty() {
+            return Err(PlaylistError::Empty);
+        }
+
+        let entries = entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, mut settings))| {
+                let invalid = |why: String| PlaylistError::Schedule {
+                    entry: index + 1,
+                    why,
+                };
+                let at = settings
+                    .iter()
+                    .position(|(name, _)| name == "schedule")
+                    .ok_or_else(|| invalid("it has no schedule".into()))?;
+                let schedule = match settings.remove(at).1 {
+                    Value::Text(schedule) => schedule.parse().map_err(invalid)?,
+                    _ => return Err(invalid("its schedule isn't a string".into())),
+                };
+                let mut all = shared.clone();
+                all.append(&mut settings);
+                Ok(Entry {
+                    schedule,
+                    settings: all,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { entries })
+    }
+}
+
+/// `line` up to a `#` that isn't in a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (at, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..at],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// a bare key, or a quoted one
+fn key(text: &str) -> Result<String, String> {
+    if text.starts_with(['"', '\'']) {
+        return string(text);
+    }
+    let bare = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if text.is_empty() || !text.chars().all(bare) {
+        return Err(format!("{text:?} isn't a setting's name"));
+    }
+    Ok(text.to_owned())
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    match text {
+        "true" => return Ok(Value::Switch(true)),
+        "false" => return Ok(Value::Switch(false)),
+        _ => {}
+    }
+    if let Some(inner) = text.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| format!("{text:?} isn't an array on one line"))?;
+        return split_array(inner)
+            .into_iter()
+            .map(|item| match parse_value(item)? {
+                Value::Text(text) => Ok(text),
+                _ => Err(format!(
+                    "{text:?} has something in it that isn't a string or number"
+                )),
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::List);
+    }
+    if text.starts_with(['"', '\'']) {
+        return string(text).map(Value::Text);
+    }
+    let number = text.replace('_', "");
+    if number.parse::<f64>().is_ok() {
+        return Ok(Value::Text(number));
+    }
+    Err(format!("{text:?} isn't a string, number, boolean or array"))
+}
+
+/// the items of an array, between the commas that aren't in strings
+fn split_array(inner: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let (mut quote, mut escaped, mut start) = (None, false, 0);
+    for (at, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ',') => {
+                items.push(inner[start..at].trim());
+                start = at + 1;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    items.push(inner[start..].trim());
+    // a comma after the last is allowed
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// a `"basic"` string, with its escapes, or a `'literal'` one
+fn string(text: &str) -> Result<String, String> {
+    let unterminated = || format!("{text:?} isn't a string on one line");
+    if let Some(literal) = text.strip_prefix('\'') {
+        return literal
+            .strip_suffix('\'')
+            .filter(|inner| !inner.contains('\''))
+            .map(str::to_owned)
+            .ok_or_else(unterminated);
+    }
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(unterminated)?;
+    let mut string = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return Err(unterminated());
+        }
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(u @ ('u' | 'U')) => {
+                let digits: String = chars.by_ref().take(if u == 'u' { 4 } else { 8 }).collect();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("\\{u}{digits} isn't a character"))?
+            }
+            other => return Err(format!("\\{} isn't an escape", other.unwrap_or(' '))),
+        };
+        string.push(escaped);
+    }
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// minutes after 1970-01-01 at `hour`:`minute` on the date
+    fn at(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        Date { year, month, day }.days() * DAY + hour * 60 + minute
+    }
+
+    fn cron(schedule: &str) -> Cron {
+        schedule.parse().expect("the schedule parses")
+    }
+
+    #[test]
+    fn next_crosses_month_and_year_ends() {
+        let monthly = cron("@monthly");
+        assert_eq!(
+            monthly.next(at(2024, 1, 31, 12, 0)),
+            Some(at(2024, 2, 1, 0, 0))
+        );
+        assert_eq!(
+            monthly.next(at(2024, 12, 31, 23, 59)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+        // there's no 31st in April, June, September or November
+        let thirty_first = cron("0 9 31 * *");
+        assert_eq!(
+            thirty_first.next(at(2024, 3, 31, 9, 0)),
+            Some(at(2024, 5, 31, 9, 0))
+        );
+        assert_eq!(
+            thirty_first.last(at(2024, 7, 30, 0, 0)),
+            Some(at(2024, 5, 31, 9, 0))
+        );
+        // every quarter hour, from the last one of a month to the first of the next
+        assert_eq!(
+            cron("*/15 * * * *").next(at(2023, 4, 30, 23, 45)),
+            Some(at(2023, 5, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_finds_february_29th() {
+        let leap = cron("0 0 29 2 *");
+        assert_eq!(
+            leap.next(at(2024, 2, 28, 0, 0)),
+            Some(at(2024, 2, 29, 0, 0))
+        );
+        assert_eq!(
+            leap.next(at(2024, 2, 29, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+        // 2100 isn't a leap year
+        assert_eq!(leap.next(at(2097, 1, 1, 0, 0)), Some(at(2104, 2, 29, 0, 0)));
+        assert_eq!(leap.last(at(2027, 6, 1, 0, 0)), Some(at(2024, 2, 29, 0, 0)));
+        // and a day that never comes
+        assert_eq!(cron("0 0 30 2 *").next(0), None);
+    }
+
+    #[test]
+    fn either_day_will_do() {
+        // 2024-03-01 is a Friday; the 13th or any Friday
+        let friday_or_13th = cron("0 0 13 * 5");
+        assert_eq!(
+            friday_or_13th.next(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 8, 0, 0))
+        );
+        assert_eq!(
+            friday_or_13th.next(at(2024, 3, 8, 0, 0)),
+            Some(at(2024, 3, 13, 0, 0))
+        );
+        // 7 is Sunday too
+        assert_eq!(cron("0 0 * * 7"), cron("0 0 * * 0"));
+        assert_eq!(
+            cron("@weekly").next(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 3, 0, 0))
+        );
+        assert_eq!(
+            cron("30 8-18/5 * * 1-5").next(at(2024, 3, 1, 18, 30)),
+            Some(at(2024, 3, 4, 8, 30))
+        );
+    }
+
+    #[test]
+    fn malformed_schedules_are_refused() {
+        let error = |schedule: &str| schedule.parse::<Cron>().err();
+        assert_eq!(
+            error("0 7 * *"),
+            Some(
+                "\"0 7 * *\" isn't a schedule of minute, hour, day, month and weekday, like \
+                 `0 7 * * 1-5`"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            error("60 * * * *"),
+            Some("\"60\" isn't a minute from 0 to 59".to_owned())
+        );
+        assert_eq!(
+            error("0 0 0 * *"),
+            Some("\"0\" isn't a day from 1 to 31".to_owned())
+        );
+        assert_eq!(
+            error("*/0 * * * *"),
+            Some("\"*/0\" isn't a minute from 0 to 59".to_owned())
+        );
+        assert_eq!(
+            error("0 9-5 * * *"),
+            Some("\"9-5\" isn't a hour from 0 to 23".to_owned())
+        );
+        assert!(error("0 0 * * mon").is_some());
+    }
+
+    const PLAYLIST: &str = r#"
+output = "/srv/signage/now.png"  # where it goes
+size = '1080p'
+
+# weekday mornings
+[[entry]]
+schedule = "0 7 * * 1-5"
+palette = "warm"
+
+[[entry]]
+schedule = "30 12 * * *"
+palette = "classic # not a comment"
+levels = 1_000
+text = ["lunch", "{time}", "\u00e9\t",]
+grid = true
+"#;
+
+    #[test]
+    fn reads_playlists() {
+        let playlist: Playlist = PLAYLIST.parse().expect("it parses");
+        let text = |text: &str| Value::Text(text.to_owned());
+        assert_eq!(playlist.entries.len(), 2);
+        assert_eq!(playlist.entries[0].schedule, cron("0 7 * * 1-5"));
+        assert_eq!(
+            playlist.entries[1].merged(),
+            [
+                ("output", &text("/srv/signage/now.png")),
+                ("size", &text("1080p")),
+                ("palette", &text("classic # not a comment")),
+                ("levels", &text("1000")),
+                (
+                    "text",
+                    &Value::List(vec!["lunch".into(), "{time}".into(), "é\t".into()])
+                ),
+                ("grid", &Value::Switch(true)),
+            ]
+        );
+        // a Friday: the morning one from 7 until lunch, then lunch's until Monday morning
+        assert_eq!(playlist.at(at(2024, 3, 1, 6, 59)), 1);
+        assert_eq!(playlist.at(at(2024, 3, 1, 7, 0)), 0);
+        assert_eq!(playlist.at(at(2024, 3, 1, 12, 30)), 1);
+        assert_eq!(playlist.at(at(2024, 3, 2, 9, 0)), 1);
+        assert_eq!(
+            playlist.next(at(2024, 3, 1, 12, 30)),
+            Some(at(2024, 3, 2, 12, 30))
+        );
+    }
+
+    #[test]
+    fn malformed_playlists_are_refused() {
+        let error = |text: &str| text.parse::<Playlist>().err().map(|err| err.to_string());
+        assert_eq!(
+            error("size = \"4k\""),
+            Some("the playlist has no [[entry]] in it".to_owned())
+        );
+        assert_eq!(
+            error("[entry]"),
+            Some("line 1 of the playlist: only [[entry]] tables go in it, not [entry]".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule"),
+            Some(
+                "line 2 of the playlist: expected a setting like `palette = \"warm\"`, got \
+                 \"schedule\""
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"@daily\"\nlevels = 3\nlevels = 4"),
+            Some("line 4 of the playlist: levels is set twice".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"@daily\"\npalette = \"warm"),
+            Some("line 3 of the playlist: \"\\\"warm\" isn't a string on one line".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"@daily\"\npalette = warm"),
+            Some(
+                "line 3 of the playlist: \"warm\" isn't a string, number, boolean or array"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            error("[[entry]]\ntext = [\"a\", true]\nschedule = \"@daily\""),
+            Some(
+                "line 2 of the playlist: \"[\\\"a\\\", true]\" has something in it that isn't a \
+                 string or number"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"@daily\"\ntext = \"\\q\""),
+            Some("line 3 of the playlist: \\q isn't an escape".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"@daily\"\n[[entry]]\npalette = \"warm\""),
+            Some("entry 2 of the playlist: it has no schedule".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = true"),
+            Some("entry 1 of the playlist: its schedule isn't a string".to_owned())
+        );
+        assert_eq!(
+            error("[[entry]]\nschedule = \"0 25 * * *\""),
+            Some("entry 1 of the playlist: \"25\" isn't a hour from 0 to 23".to_owned())
+        );
+    }
+}
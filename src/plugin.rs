@@ -0,0 +1,394 @@
+//! split strategies and fills from outside the crate, picked by name with
+//! [`SplitOptions::plugin`] and [`FillOptions::plugin`]
+//!
+//! a crate that links this one registers its own with [`register_split`] and [`register_fill`]
+//! before building anything. with the `plugins` feature, [`load`] also opens shared libraries
+//! that do the same from an [`export_plugin!`](crate::export_plugin) function. what `load` finds
+//! there is a [`Plugin`], plain C, so it can tell a library built some other way before calling
+//! into it; it has to be, since the strategies themselves go across as Rust trait objects, so
+//! only this same version of the crate with the same features and compiler will do
+//!
+//! see `examples/plugin.rs` for one of each
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    mem::{align_of, size_of},
+    path::Path,
+    sync::RwLock,
+};
+
+use image::Rgb;
+
+use crate::{
+    color::Palette,
+    fill::{FillOptions, FillStrategy},
+    geometry::{Rectangle, SplitOptions},
+    strategy::SplitStrategy,
+};
+
+/// what shared libraries have to have been built against, the crate's version and the
+/// compiler's, checked by [`load`]
+pub const API: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("MONDRIAN_RUSTC"), ")");
+
+/// which layout of [`Plugin`] a library exports; goes up whenever it changes
+pub const ABI: u32 = 1;
+
+/// the sizes and alignments of what goes between a library and the program, mixed together
+/// like [`seed::from_text`](crate::seed::from_text) does; features and settings that change
+/// them change this
+pub const LAYOUT: u64 = {
+    let layouts = [
+        (size_of::<Plugin>(), align_of::<Plugin>()),
+        (size_of::<Registry>(), align_of::<Registry>()),
+        (size_of::<Rectangle>(), align_of::<Rectangle>()),
+        (size_of::<SplitOptions>(), align_of::<SplitOptions>()),
+        (size_of::<FillOptions>(), align_of::<FillOptions>()),
+        (size_of::<Palette>(), align_of::<Palette>()),
+        (size_of::<Rgb<u8>>(), align_of::<Rgb<u8>>()),
+        (
+            size_of::<Box<dyn SplitStrategy<Rectangle>>>(),
+            align_of::<Box<dyn SplitStrategy<Rectangle>>>(),
+        ),
+        (
+            size_of::<Box<dyn FillStrategy>>(),
+            align_of::<Box<dyn FillStrategy>>(),
+        ),
+    ];
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut i = 0;
+    while i < layouts.len() {
+        hash = (hash ^ layouts[i].0 as u64).wrapping_mul(0x100000001b3);
+        hash = (hash ^ layouts[i].1 as u64).wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+};
+
+/// what a library's `mondrian_plugin` hands [`load`], made by
+/// [`export_plugin!`](crate::export_plugin); everything but `register` can be read whatever
+/// built it
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Plugin {
+    /// [`ABI`]; nothing else is looked at unless it's the same
+    pub abi: u32,
+    /// [`API`], `api_len` bytes of UTF-8
+    pub api: *const u8,
+    pub api_len: usize,
+    /// [`LAYOUT`]
+    pub layout: u64,
+    /// registers everything the library has
+    pub register: unsafe extern "C" fn(registry: *mut Registry),
+}
+
+impl Plugin {
+    /// for this build of the crate, with `register` doing the registering
+    pub const fn new(register: unsafe extern "C" fn(registry: *mut Registry)) -> Self {
+        Self {
+            abi: ABI,
+            api: API.as_ptr(),
+            api_len: API.len(),
+            layout: LAYOUT,
+            register,
+        }
+    }
+
+    /// whether it was made by a build of the crate that [`register`](Self::register) can be
+    /// called from this one, and why not if it wasn't
+    ///
+    /// # Safety
+    ///
+    /// if `abi` is [`ABI`], `api` has to point to `api_len` bytes
+    pub unsafe fn check(&self) -> Result<(), PluginError> {
+        if self.abi != ABI {
+            return Err(PluginError::Abi { found: self.abi });
+        }
+        let api = std::slice::from_raw_parts(self.api, self.api_len);
+        let api = String::from_utf8_lossy(api);
+        if api != API {
+            return Err(PluginError::Version {
+                found: api.into_owned(),
+            });
+        }
+        if self.layout != LAYOUT {
+            return Err(PluginError::Layout);
+        }
+        Ok(())
+    }
+}
+
+/// makes a split strategy for a composition covering `canvas`, the way
+/// [`Builtin::strategy`](crate::strategy::Builtin::strategy) does
+pub type SplitFactory = fn(canvas: &Rectangle) -> Box<dyn SplitStrategy<Rectangle>>;
+
+/// makes a fill for a composition covering `canvas`
+pub type FillFactory = fn(canvas: &Rectangle) -> Box<dyn FillStrategy>;
+
+/// plugins by name; registering a name again replaces the old one
+#[derive(Debug, Default)]
+pub struct Registry {
+    splits: BTreeMap<String, SplitFactory>,
+    fills: BTreeMap<String, FillFactory>,
+}
+
+impl Registry {
+    pub const fn new() -> Self {
+        Self {
+            splits: BTreeMap::new(),
+            fills: BTreeMap::new(),
+        }
+    }
+
+    pub fn register_split(&mut self, name: impl Into<String>, factory: SplitFactory) {
+        self.splits.insert(name.into(), factory);
+    }
+
+    pub fn register_fill(&mut self, name: impl Into<String>, factory: FillFactory) {
+        self.fills.insert(name.into(), factory);
+    }
+}
+
+/// the one every composition looks plugins up in
+static REGISTRY: RwLock<Registry> = RwLock::new(Registry::new());
+
+fn registry() -> std::sync::RwLockReadGuard<'static, Registry> {
+    // registering can't leave it half done, so a panic elsewhere doesn't matter
+    REGISTRY
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn registry_mut() -> std::sync::RwLockWriteGuard<'static, Registry> {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// makes a split strategy available as [`SplitOptions::plugin`] `name`
+pub fn register_split(name: impl Into<String>, factory: SplitFactory) {
+    registry_mut().register_split(name, factory);
+}
+
+/// makes a fill available as [`FillOptions::plugin`] `name`
+pub fn register_fill(name: impl Into<String>, factory: FillFactory) {
+    registry_mut().register_fill(name, factory);
+}
+
+/// registers everything `register` does, the way [`load`] does for a shared library's
+/// [`export_plugin!`](crate::export_plugin) function
+pub fn install(register: fn(&mut Registry)) {
+    register(&mut registry_mut());
+}
+
+/// the names of the split strategies registered so far, sorted
+pub fn splits() -> Vec<String> {
+    registry().splits.keys().cloned().collect()
+}
+
+/// the names of the fills registered so far, sorted
+pub fn fills() -> Vec<String> {
+    registry().fills.keys().cloned().collect()
+}
+
+/// why a plugin couldn't be found or loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// nothing is registered by that name
+    Unknown { kind: &'static str, name: String },
+    /// the shared library couldn't be opened, or doesn't export a plugin
+    Load(String),
+    /// the shared library exports another layout of [`Plugin`]
+    Abi { found: u32 },
+    /// the shared library was built against another version of the crate, or with another
+    /// compiler
+    Version { found: String },
+    /// the shared library was built against this version, but with other features
+    Layout,
+    /// loading shared libraries needs the `plugins` feature
+    Unsupported,
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown { kind, name } => {
+                write!(f, "there's no {kind} plugin called {name:?}; is it loaded?")
+            }
+            Self::Load(why) => write!(f, "couldn't load the plugin: {why}"),
+            Self::Abi { found } => write!(
+                f,
+                "the plugin exports plugin ABI {found}, but this reads {ABI}"
+            ),
+            Self::Version { found } => write!(
+                f,
+                "the plugin was built for mondrian-rs {found}, but this is {API}"
+            ),
+            Self::Layout => write!(
+                f,
+                "the plugin was built for mondrian-rs {API}, but with other features"
+            ),
+            Self::Unsupported => write!(
+                f,
+                "loading plugins needs mondrian-rs to be built with the `plugins` feature"
+            ),
+        }
+    }
+}
+
+impl Error for PluginError {}
+
+/// the split strategy `options` asks for, set up for `canvas`: its plugin if it names one,
+/// otherwise its builtin
+pub fn split_strategy(
+    options: &SplitOptions,
+    canvas: &Rectangle,
+) -> Result<Box<dyn SplitStrategy<Rectangle>>, PluginError> {
+    let Some(name) = &options.plugin else {
+        return Ok(options.strategy.strategy(canvas));
+    };
+    match registry().splits.get(name) {
+        Some(factory) => Ok(factory(canvas)),
+        None => Err(PluginError::Unknown {
+            kind: "split",
+            name: name.clone(),
+        }),
+    }
+}
+
+/// the fill `options` asks for, like [`split_strategy`]
+pub fn fill_strategy(
+    options: &FillOptions,
+    canvas: &Rectangle,
+) -> Result<Box<dyn FillStrategy>, PluginError> {
+    let Some(name) = &options.plugin else {
+        return Ok(Box::new(options.strategy));
+    };
+    match registry().fills.get(name) {
+        Some(factory) => Ok(factory(canvas)),
+        None => Err(PluginError::Unknown {
+            kind: "fill",
+            name: name.clone(),
+        }),
+    }
+}
+
+/// exports `register`, a `fn(&mut Registry)`, from a `cdylib` crate for [`load`] to find, as an
+/// `extern "C" fn mondrian_plugin() -> Plugin`
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:path) => {
+        #[no_mangle]
+        pub extern "C" fn mondrian_plugin() -> $crate::plugin::Plugin {
+            unsafe extern "C" fn mondrian_plugin_register(registry: *mut $crate::plugin::Registry) {
+                // SAFETY: load only ever passes the registry it holds locked
+                $register(unsafe { &mut *registry })
+            }
+            $crate::plugin::Plugin::new(mondrian_plugin_register)
+        }
+    };
+}
+
+/// opens the shared library at `path` and registers what it exports; it stays loaded for as long
+/// as the program runs, and loading the same one twice does nothing
+#[cfg(feature = "plugins")]
+pub fn load(path: &Path) -> Result<(), PluginError> {
+    use std::{path::PathBuf, sync::Mutex};
+
+    use libloading::Library;
+
+    /// never unloaded, since the factories and everything they made point into them
+    static LIBRARIES: Mutex<Vec<(PathBuf, Library)>> = Mutex::new(vec![]);
+
+    let mut libraries = LIBRARIES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    if libraries.iter().any(|(loaded, _)| *loaded == path) {
+        return Ok(());
+    }
+    let load = |err: libloading::Error| PluginError::Load(err.to_string());
+    // SAFETY: whatever the library runs when it's opened, and the `mondrian_plugin` it exports,
+    // are trusted to be what they say; past that, register isn't called unless the library was
+    // built the same way as this, so the trait objects it registers mean the same on both sides
+    unsafe {
+        let library = Library::new(&path).map_err(load)?;
+        let plugin: extern "C" fn() -> Plugin = *library.get(b"mondrian_plugin").map_err(load)?;
+        let plugin = plugin();
+        plugin.check()?;
+        (plugin.register)(&mut *registry_mut());
+        libraries.push((path, library));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "plugins"))]
+pub fn load(_path: &Path) -> Result<(), PluginError> {
+    Err(PluginError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::Thirds;
+
+    fn register(registry: &mut Registry) {
+        registry.register_split("thirds", |_canvas| Box::new(Thirds));
+    }
+
+    crate::export_plugin!(register);
+
+    #[test]
+    fn exports_a_plugin_for_this_build() {
+        let plugin = mondrian_plugin();
+        assert_eq!((plugin.abi, plugin.layout), (ABI, LAYOUT));
+        assert!(API.starts_with(env!("CARGO_PKG_VERSION")) && API.contains("rustc"));
+        unsafe {
+            assert_eq!(plugin.check(), Ok(()));
+            let mut registry = Registry::new();
+            (plugin.register)(&mut registry);
+            assert_eq!(registry.splits.keys().collect::<Vec<_>>(), ["thirds"]);
+        }
+    }
+
+    #[test]
+    fn refuses_other_builds() {
+        let plugin = mondrian_plugin();
+        let other = "0.0.1 (rustc 1.0.0)";
+        let cases = [
+            (Plugin { abi: 2, ..plugin }, PluginError::Abi { found: 2 }),
+            (
+                Plugin {
+                    api: other.as_ptr(),
+                    api_len: other.len(),
+                    ..plugin
+                },
+                PluginError::Version {
+                    found: other.to_owned(),
+                },
+            ),
+            (
+                Plugin {
+                    layout: LAYOUT ^ 1,
+                    ..plugin
+                },
+                PluginError::Layout,
+            ),
+        ];
+        for (plugin, err) in cases {
+            assert_eq!(unsafe { plugin.check() }, Err(err));
+        }
+        // the rest of one with another ABI isn't read at all
+        let unread = Plugin {
+            abi: 0,
+            api: std::ptr::null(),
+            api_len: usize::MAX,
+            ..plugin
+        };
+        assert_eq!(
+            unsafe { unread.check() },
+            Err(PluginError::Abi { found: 0 })
+        );
+    }
+}
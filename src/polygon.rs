@@ -0,0 +1,226 @@
+//! shapes that aren't axis-aligned rectangles, for the styles that aren't cut from a split tree
+
+use image::Rgb;
+
+use crate::geometry::{Rectangle, SplittableGraphic, TilingError};
+
+/// in pixels, like [`Rectangle`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// a polygon with no holes whose sides don't cross, its corners in order going either way round;
+/// the renderers handle any of those, but [`clipped`](Self::clipped) expects a convex one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(points: impl Into<Vec<Point>>) -> Self {
+        Self {
+            points: points.into(),
+        }
+    }
+
+    /// corners clockwise from the top left
+    pub fn from_rectangle(rectangle: &Rectangle) -> Self {
+        let (right, bottom) = (
+            rectangle.x + rectangle.width,
+            rectangle.y + rectangle.height,
+        );
+        Self::new([
+            Point::new(rectangle.x, rectangle.y),
+            Point::new(right, rectangle.y),
+            Point::new(right, bottom),
+            Point::new(rectangle.x, bottom),
+        ])
+    }
+
+    pub fn area(&self) -> f64 {
+        let n = self.points.len();
+        let twice: f64 = (0..n)
+            .map(|i| {
+                let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+                a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64
+            })
+            .sum();
+        twice.abs() / 2.0
+    }
+
+    /// the smallest rectangle around every corner
+    pub fn bounds(&self) -> Rectangle {
+        let Some(first) = self.points.first() else {
+            return Rectangle::new(0.0, 0.0, 0.0, 0.0);
+        };
+        let (mut left, mut top, mut right, mut bottom) = (first.x, first.y, first.x, first.y);
+        for point in &self.points {
+            left = left.min(point.x);
+            top = top.min(point.y);
+            right = right.max(point.x);
+            bottom = bottom.max(point.y);
+        }
+        Rectangle::new(left, top, right - left, bottom - top)
+    }
+
+    /// the same polygon on a canvas `factor` times the size
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self::new(
+            self.points
+                .iter()
+                .map(|point| Point::new(point.x * factor, point.y * factor))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// the same polygon moved `dx` right and `dy` down
+    pub fn translated(&self, dx: f32, dy: f32) -> Self {
+        Self::new(
+            self.points
+                .iter()
+                .map(|point| Point::new(point.x + dx, point.y + dy))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// what's left on the side of the line through `from` and `to` where `inside` is; a
+    /// polygon entirely on the other side comes back with no points
+    pub fn clipped(&self, from: Point, to: Point, inside: Point) -> Self {
+        let side = |point: Point| {
+            (to.x - from.x) * (point.y - from.y) - (to.y - from.y) * (point.x - from.x)
+        };
+        let sign = side(inside).signum();
+        let keeps = |point: Point| side(point) * sign >= 0.0;
+        let mut points = vec![];
+        let n = self.points.len();
+        for i in 0..n {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            if keeps(a) {
+                points.push(a);
+            }
+            if keeps(a) != keeps(b) {
+                let (side_a, side_b) = (side(a), side(b));
+                let t = side_a / (side_a - side_b);
+                points.push(Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t));
+            }
+        }
+        Self::new(points)
+    }
+
+    /// [`clipped`](Self::clipped) to each side of `rectangle` in turn
+    pub fn clipped_to(&self, rectangle: &Rectangle) -> Self {
+        let corners = Self::from_rectangle(rectangle).points;
+        let center = Point::new(
+            rectangle.x + rectangle.width / 2.0,
+            rectangle.y + rectangle.height / 2.0,
+        );
+        (0..4).fold(self.clone(), |polygon, i| {
+            polygon.clipped(corners[i], corners[(i + 1) % 4], center)
+        })
+    }
+}
+
+/// a polygon and the color it's filled with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub polygon: Polygon,
+    pub color: Rgb<u8>,
+}
+
+/// a border line between two shapes, drawn with the border width like [`Edge`](crate::geometry::Edge)s
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Segment {
+    /// the band this segment covers when drawn `width` thick, running on past each end by half
+    /// that so lines meeting at an angle leave no notch
+    pub fn to_polygon(&self, width: u32) -> Polygon {
+        let (dx, dy) = (self.to.x - self.from.x, self.to.y - self.from.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 || width == 0 {
+            return Polygon::default();
+        }
+        let half = width as f32 / 2.0;
+        let (ux, uy) = (dx / length * half, dy / length * half);
+        let (from, to) = (
+            Point::new(self.from.x - ux, self.from.y - uy),
+            Point::new(self.to.x + ux, self.to.y + uy),
+        );
+        Polygon::new([
+            Point::new(from.x - uy, from.y + ux),
+            Point::new(to.x - uy, to.y + ux),
+            Point::new(to.x + uy, to.y - ux),
+            Point::new(from.x + uy, from.y - ux),
+        ])
+    }
+
+    /// the part of the segment inside `rectangle`, if any of it is more than a point
+    pub fn clipped_to(&self, rectangle: &Rectangle) -> Option<Self> {
+        let (dx, dy) = (self.to.x - self.from.x, self.to.y - self.from.y);
+        let (mut start, mut end) = (0.0f32, 1.0f32);
+        // how far along the segment it crosses each side, entering or leaving
+        let sides = [
+            (-dx, self.from.x - rectangle.x),
+            (dx, rectangle.x + rectangle.width - self.from.x),
+            (-dy, self.from.y - rectangle.y),
+            (dy, rectangle.y + rectangle.height - self.from.y),
+        ];
+        for (step, room) in sides {
+            if step == 0.0 {
+                if room < 0.0 {
+                    return None;
+                }
+            } else if step < 0.0 {
+                start = start.max(room / step);
+            } else {
+                end = end.min(room / step);
+            }
+        }
+        (start < end).then(|| Self {
+            from: Point::new(self.from.x + dx * start, self.from.y + dy * start),
+            to: Point::new(self.from.x + dx * end, self.from.y + dy * end),
+        })
+    }
+}
+
+/// how far off the areas of [`check_cover`]ed polygons may add up to, as a share of the whole;
+/// their corners are wherever lines happened to cross, so they aren't exact
+const AREA_TOLERANCE: f64 = 1e-5;
+
+/// the polygon version of [`check_tiling`](crate::geometry::check_tiling): every polygon inside
+/// `bounds`, and their areas adding up to all of it, give or take rounding
+pub fn check_cover(bounds: &Rectangle, polygons: &[Polygon]) -> Result<(), TilingError> {
+    // corners land on the boundary with a little rounding either way
+    let slack = 1e-3 * bounds.width.max(bounds.height);
+    for (index, polygon) in polygons.iter().enumerate() {
+        let outside = polygon.points.iter().any(|point| {
+            point.x < bounds.x - slack
+                || point.y < bounds.y - slack
+                || point.x > bounds.x + bounds.width + slack
+                || point.y > bounds.y + bounds.height + slack
+        });
+        if outside {
+            return Err(TilingError::OutOfBounds {
+                index,
+                tile: polygon.bounds(),
+            });
+        }
+    }
+    let covered: f64 = polygons.iter().map(Polygon::area).sum();
+    let gap = bounds.area() - covered;
+    if gap.abs() > AREA_TOLERANCE * bounds.area() {
+        return Err(TilingError::Gap { area: gap });
+    }
+    Ok(())
+}
@@ -0,0 +1,679 @@
+//! QR codes, for posters that link somewhere: one square pane of the composition is kept for the
+//! code, which is drawn into it module by module
+//!
+//! codes are encoded here in byte mode at error correction level M (up to 2331 bytes), with
+//! whichever of the eight masks scores best
+
+use std::{error::Error, fmt};
+
+use image::Rgb;
+use rand::{Rng, RngCore};
+
+use crate::geometry::{Rectangle, SplittableGraphic};
+
+/// modules of light margin each side, as the standard asks for
+pub const QUIET_ZONE: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    /// more bytes than the biggest code holds
+    TooLong(usize),
+    /// the canvas hasn't room for a pixel per module
+    TooSmall { modules: usize },
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong(bytes) => write!(
+                f,
+                "{bytes} bytes is too much for a QR code; they hold at most {MAX_BYTES}"
+            ),
+            Self::TooSmall { modules } => write!(
+                f,
+                "the canvas is too small for a QR code {modules} modules across, margin included"
+            ),
+        }
+    }
+}
+
+impl Error for QrError {}
+
+/// what a version 40 code holds at level M
+const MAX_BYTES: usize = 2331;
+
+/// error correction codewords per block at level M, by version
+const ECC_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// blocks the codewords are split into at level M, by version
+const BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// a square of dark and light modules, with no margin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// `data` in the smallest code it fits
+    pub fn encode(data: &[u8]) -> Result<Self, QrError> {
+        let version = (1..=40)
+            .find(|&version| header_bits(version) + 8 * data.len() <= data_codewords(version) * 8)
+            .ok_or(QrError::TooLong(data.len()))?;
+        let capacity = data_codewords(version);
+
+        // byte mode, the length, the bytes, then a terminator and padding to the capacity
+        let mut bits = Bits::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, if version < 10 { 8 } else { 16 });
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        let terminator = (capacity * 8 - bits.len).min(4);
+        bits.push(0, terminator);
+        bits.push(0, (8 - bits.len % 8) % 8);
+        let mut codewords = bits.bytes;
+        for pad in [0xec, 0x11].into_iter().cycle() {
+            if codewords.len() == capacity {
+                break;
+            }
+            codewords.push(pad);
+        }
+
+        let mut code = Canvas::new(version);
+        code.draw_function_patterns();
+        code.draw_codewords(&with_error_correction(version, &codewords));
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                code.apply_mask(mask);
+                code.draw_format_bits(mask);
+                let penalty = code.penalty();
+                code.apply_mask(mask);
+                penalty
+            })
+            .expect("there are masks");
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Ok(Self {
+            size: code.size,
+            modules: code.modules,
+        })
+    }
+
+    /// modules across, and down
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// whether the module `x` across and `y` down is dark
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// rectangles covering the dark modules, one per run of them along a row, for a code whose
+    /// top left module is at `origin`, `module` pixels square
+    pub fn runs(&self, origin: (f32, f32), module: f32) -> Vec<Rectangle> {
+        let mut runs = vec![];
+        for y in 0..self.size {
+            let mut x = 0;
+            while x < self.size {
+                if !self.get(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.size && self.get(x, y) {
+                    x += 1;
+                }
+                runs.push(Rectangle::new(
+                    origin.0 + start as f32 * module,
+                    origin.1 + y as f32 * module,
+                    (x - start) as f32 * module,
+                    module,
+                ));
+            }
+        }
+        runs
+    }
+}
+
+/// a code laid into a composition: the pane kept for it, margin and all, and its dark modules
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placed {
+    /// what the code says
+    pub data: String,
+    pub pane: Rectangle,
+    pub modules: Vec<Rectangle>,
+    pub color: Rgb<u8>,
+}
+
+/// a square of whole pixels somewhere in `bounds` for `code` and its margin, a quarter of the
+/// short side or the nearest whole number of pixels per module under it; squares that would
+/// leave a sliver between them and the edge are pushed up against it instead
+pub fn reserve(
+    bounds: &Rectangle,
+    code: &QrCode,
+    rng: &mut dyn RngCore,
+) -> Result<(Rectangle, f32), QrError> {
+    let modules = code.size() + 2 * QUIET_ZONE;
+    let short = bounds.width.min(bounds.height).floor();
+    let module = ((short / 4.0) / modules as f32).floor().max(1.0);
+    let side = module * modules as f32;
+    if side > short {
+        return Err(QrError::TooSmall { modules });
+    }
+    let place = |start: f32, length: f32, rng: &mut dyn RngCore| {
+        let room = (length - side).floor();
+        let offset = rng.gen_range(0.0..=room).round();
+        let offset = if offset < side / 4.0 {
+            0.0
+        } else if room - offset < side / 4.0 {
+            room
+        } else {
+            offset
+        };
+        start.ceil() + offset
+    };
+    let x = place(bounds.x, bounds.width, rng);
+    let y = place(bounds.y, bounds.height, rng);
+    Ok((Rectangle::new(x, y, side, side), module))
+}
+
+/// the bits for the mode and the length, before the data
+fn header_bits(version: usize) -> usize {
+    4 + if version < 10 { 8 } else { 16 }
+}
+
+/// modules that aren't finders, timing, alignment, format or version, so hold codewords
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+/// where the alignment patterns' centers go, across and down
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return vec![];
+    }
+    let count = version / 7 + 2;
+    let size = version * 4 + 17;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// `data` split into blocks, each with its Reed-Solomon codewords on the end, and interleaved
+fn with_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc = ECC_PER_BLOCK[version];
+    let raw = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw % blocks;
+    let short_length = raw / blocks;
+    let divisor = rs_divisor(ecc);
+
+    let mut split = vec![];
+    let mut at = 0;
+    for i in 0..blocks {
+        let length = short_length - ecc + usize::from(i >= short_blocks);
+        let block = &data[at..at + length];
+        at += length;
+        split.push((block, rs_remainder(block, &divisor)));
+    }
+    let mut interleaved = Vec::with_capacity(raw);
+    for i in 0..short_length - ecc + 1 {
+        for (block, _) in &split {
+            if let Some(&byte) = block.get(i) {
+                interleaved.push(byte);
+            }
+        }
+    }
+    for i in 0..ecc {
+        for (_, remainder) in &split {
+            interleaved.push(remainder[i]);
+        }
+    }
+    interleaved
+}
+
+/// the generator polynomial for `degree` error correction codewords, highest power first and
+/// the leading 1 left off
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (r, &d) in remainder.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    remainder
+}
+
+/// multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11d);
+        z ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    z as u8
+}
+
+#[derive(Debug, Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bits {
+    /// the low `count` bits of `value`, highest first
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let bit = (value >> i) & 1;
+            *self.bytes.last_mut().expect("just pushed") |= (bit as u8) << (7 - self.len % 8);
+            self.len += 1;
+        }
+    }
+}
+
+/// a code being drawn, with which modules are fixed patterns rather than data
+struct Canvas {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Canvas {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        }
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder(x, y);
+        }
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // where the finders are
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                self.draw_alignment(x, y);
+            }
+        }
+        // placeholders, so the data goes around them; the mask picks the real ones
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    /// a finder centered on `x`, `y`, with the light separator around it
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let dark = dx.abs().max(dy.abs()) != 1;
+                self.set_function((x as i32 + dx) as usize, (y as i32 + dy) as usize, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        // level M is 00
+        let data = mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        let size = self.size;
+        // around the top left finder
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        // and split between the other two
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let data = self.version as u32;
+        let mut remainder = data;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1f25);
+        }
+        let bits = (data << 12) | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// up and down two columns at a time from the bottom right, skipping the vertical timing line
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y * size + x] && i < codewords.len() * 8 {
+                        self.modules[y * size + x] = (codewords[i / 8] >> (7 - i % 8)) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// flips the data modules `mask` picks; doing it twice undoes it
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let at = y * self.size + x;
+                if flip && !self.function[at] {
+                    self.modules[at] ^= true;
+                }
+            }
+        }
+    }
+
+    /// how hard the code looks to scan: long runs, 2×2 blocks, things that look like finders,
+    /// and more of one color than the other all count against it
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let get = |x: usize, y: usize| self.modules[y * size + x];
+        let mut penalty = 0;
+        for transpose in [false, true] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| if transpose { get(a, b) } else { get(b, a) })
+                    .collect();
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty += run - 2;
+                    }
+                    run = 1;
+                }
+                // dark, light, 3 dark, light, dark, with 4 light on one side
+                const FINDER: [bool; 7] = [true, false, true, true, true, false, true];
+                for b in 0..size.saturating_sub(6) {
+                    if line[b..b + 7] != FINDER {
+                        continue;
+                    }
+                    let light = |from: usize, to: usize| line[from..to].iter().all(|&dark| !dark);
+                    let before = b >= 4 && light(b - 4, b);
+                    let after = b + 11 <= size && light(b + 7, b + 11);
+                    if before || after {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = get(x, y);
+                if get(x + 1, y) == color && get(x, y + 1) == color && get(x + 1, y + 1) == color {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let total = size * size;
+        // every 5% further from half and half
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        penalty + k * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `code`'s modules a row at a time, `#` for dark
+    fn rows(code: &QrCode) -> Vec<String> {
+        (0..code.size())
+            .map(|y| {
+                (0..code.size())
+                    .map(|x| if code.get(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn encodes_a_known_version_1_code() {
+        // read back by hand: mask 3, then 0100, a length of 8, `mondrian`, the terminator and
+        // padding, and Reed-Solomon codewords that check out
+        let expected = [
+            "#######.#.##..#######",
+            "#.....#.#.#.#.#.....#",
+            "#.###.#...#.#.#.###.#",
+            "#.###.#.####..#.###.#",
+            "#.###.#..##...#.###.#",
+            "#.....#..#....#.....#",
+            "#######.#.#.#.#######",
+            "........###..........",
+            "#.##.###.##...#..#.##",
+            "##..#..#....######..#",
+            ".#.##.###.#.#..#..###",
+            "###......##.##.###...",
+            "#.#####..#.#...#...##",
+            "........#.#..#..#.#..",
+            "#######.#.#..####.#..",
+            "#.....#.##.####..##..",
+            "#.###.#....#..#.###..",
+            "#.###.#.###..#...#.#.",
+            "#.###.#.#.##.#....#..",
+            "#.....#....##.#.#...#",
+            "#######.#.###...###..",
+        ];
+        let code = QrCode::encode(b"mondrian").expect("it fits");
+        assert_eq!(rows(&code), expected);
+    }
+
+    #[test]
+    fn reed_solomon_known_answer() {
+        // HELLO WORLD at 1-M, the usual worked example
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn format_bits_for_level_m() {
+        const FORMATS: [&str; 8] = [
+            "101010000010010",
+            "101000100100101",
+            "101111001111100",
+            "101101101001011",
+            "100010111111001",
+            "100000011001110",
+            "100111110010111",
+            "100101010100000",
+        ];
+        for (mask, expected) in FORMATS.iter().enumerate() {
+            let mut canvas = Canvas::new(1);
+            canvas.draw_format_bits(mask as u32);
+            let get = |x: usize, y: usize| canvas.modules[y * canvas.size + x];
+            // along the top left finder's bottom, then up its right side, skipping the timing
+            let around: String = [(0, 8), (1, 8), (2, 8), (3, 8), (4, 8), (5, 8), (7, 8)]
+                .into_iter()
+                .chain([
+                    (8, 8),
+                    (8, 7),
+                    (8, 5),
+                    (8, 4),
+                    (8, 3),
+                    (8, 2),
+                    (8, 1),
+                    (8, 0),
+                ])
+                .map(|(x, y)| if get(x, y) { '1' } else { '0' })
+                .collect();
+            assert_eq!(around, *expected, "mask {mask}");
+        }
+    }
+
+    #[test]
+    fn versions_and_capacities() {
+        assert_eq!([1, 2, 7, 40].map(data_codewords), [16, 28, 124, 2334]);
+        assert_eq!(alignment_positions(1), [] as [usize; 0]);
+        assert_eq!(alignment_positions(2), [6, 18]);
+        assert_eq!(alignment_positions(7), [6, 22, 38]);
+        assert_eq!(alignment_positions(32), [6, 34, 60, 86, 112, 138]);
+        assert_eq!(alignment_positions(40), [6, 30, 58, 86, 114, 142, 170]);
+
+        let size = |bytes: usize| QrCode::encode(&vec![b'a'; bytes]).map(|code| code.size());
+        assert_eq!(size(14), Ok(21));
+        assert_eq!(size(15), Ok(25));
+        assert_eq!(size(MAX_BYTES), Ok(177));
+        assert_eq!(size(MAX_BYTES + 1), Err(QrError::TooLong(MAX_BYTES + 1)));
+    }
+
+    #[test]
+    fn runs_cover_the_dark_modules() {
+        let code = QrCode::encode(b"mondrian").expect("it fits");
+        let runs = code.runs((10.0, 20.0), 2.0);
+        // the top row: the two finders' edges and what's between
+        let top: Vec<Rectangle> = runs.iter().copied().filter(|run| run.y == 20.0).collect();
+        assert_eq!(
+            top,
+            [
+                Rectangle::new(10.0, 20.0, 14.0, 2.0),
+                Rectangle::new(26.0, 20.0, 2.0, 2.0),
+                Rectangle::new(30.0, 20.0, 4.0, 2.0),
+                Rectangle::new(38.0, 20.0, 14.0, 2.0),
+            ]
+        );
+        let covered: f32 = runs.iter().map(|run| run.width / 2.0).sum();
+        let dark = rows(&code).concat().matches('#').count();
+        assert_eq!(covered as usize, dark);
+    }
+}
@@ -0,0 +1,201 @@
+//! panes that stand up off the canvas: the blocks [`Style::ThreeD`](crate::style::Style::ThreeD)
+//! draws them as, and the drop shadows flat ones can cast
+//!
+//! like [`sketch`](crate::sketch), none of it touches the composition's rng, so edits and every
+//! renderer get the same blocks and shadows
+
+use image::Rgb;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    composition::Pane,
+    geometry::Rectangle,
+    oklab,
+    polygon::{Point, Polygon, Shape},
+    seed,
+    tree::{Subtree, Tree},
+};
+
+/// how much light each face of a block gets, from above and behind on the left: its top, the
+/// side facing down the canvas, and the side facing right
+const LIGHT: [f32; 3] = [1.0, 0.8, 0.6];
+
+/// how dark a shadow is right under its pane, as a share of what it falls on
+const SHADOW: f32 = 0.6;
+
+/// `panes`, laid out by `tree`, as blocks seen from above the bottom right corner in isometric
+/// projection and fit into the area the tree covers, the nearest drawn last. the colored panes stand a few times
+/// taller than the background ones, and `gap` pixels of `floor` show between them where the
+/// lines were
+pub fn blocks(
+    tree: &Tree<Rectangle>,
+    panes: &[Pane],
+    gap: u32,
+    floor: Rgb<u8>,
+    background: Rgb<u8>,
+    seed: u64,
+) -> Vec<Shape> {
+    let bounds = *tree.item();
+    let unit = 0.04 * bounds.width.min(bounds.height);
+    let heights: Vec<f32> = panes
+        .iter()
+        .map(|pane| {
+            let text = format!("{seed}:block:{}:{}", pane.rectangle.x, pane.rectangle.y);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed::from_text(&text));
+            if pane.color == background {
+                unit * rng.gen_range(0.3..1.0)
+            } else {
+                unit * rng.gen_range(1.5..4.0)
+            }
+        })
+        .collect();
+
+    // isometric, before it's scaled and moved to fit
+    let project = |x: f32, y: f32, z: f32| {
+        let (x, y) = (x - bounds.x, y - bounds.y);
+        Point::new((x - y) * 0.866, (x + y) * 0.5 - z)
+    };
+    let tallest = heights.iter().copied().fold(0.0, f32::max);
+    let corners = [
+        project(bounds.x, bounds.y + bounds.height, 0.0),
+        project(bounds.x + bounds.width, bounds.y, 0.0),
+        project(bounds.x, bounds.y, tallest),
+        project(bounds.x + bounds.width, bounds.y + bounds.height, 0.0),
+    ];
+    let (left, right) = (corners[0].x, corners[1].x);
+    let (top, bottom) = (corners[2].y, corners[3].y);
+    let scale = 0.9 * (bounds.width / (right - left)).min(bounds.height / (bottom - top));
+    let (dx, dy) = (
+        bounds.x + (bounds.width - scale * (right - left)) / 2.0 - scale * left,
+        bounds.y + (bounds.height - scale * (bottom - top)) / 2.0 - scale * top,
+    );
+    let place = |x: f32, y: f32, z: f32| {
+        let point = project(x, y, z);
+        Point::new(dx + scale * point.x, dy + scale * point.y)
+    };
+
+    let mut shapes = vec![];
+    if gap > 0 {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = bounds;
+        shapes.push(Shape {
+            polygon: Polygon::new([
+                place(x, y, 0.0),
+                place(x + width, y, 0.0),
+                place(x + width, y + height, 0.0),
+                place(x, y + height, 0.0),
+            ]),
+            color: floor,
+        });
+    }
+    let mut order = vec![];
+    back_to_front(tree.root(), 0, &mut order);
+    // so the lines on screen are still `gap` wide after shrinking to fit
+    let inset = gap as f32 / 2.0 / scale;
+    for index in order {
+        let (Some(pane), Some(&z)) = (panes.get(index), heights.get(index)) else {
+            continue;
+        };
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = pane.rectangle;
+        let (x0, y0, x1, y1) = (x + inset, y + inset, x + width - inset, y + height - inset);
+        if x1 <= x0 || y1 <= y0 {
+            continue;
+        }
+        let faces = [
+            [
+                place(x0, y0, z),
+                place(x1, y0, z),
+                place(x1, y1, z),
+                place(x0, y1, z),
+            ],
+            [
+                place(x0, y1, 0.0),
+                place(x1, y1, 0.0),
+                place(x1, y1, z),
+                place(x0, y1, z),
+            ],
+            [
+                place(x1, y0, 0.0),
+                place(x1, y1, 0.0),
+                place(x1, y1, z),
+                place(x1, y0, z),
+            ],
+        ];
+        // the sides first, so the top's edges go over theirs
+        for face in [2, 1, 0] {
+            shapes.push(Shape {
+                polygon: Polygon::new(faces[face]),
+                color: shade(pane.color, LIGHT[face]),
+            });
+        }
+    }
+    shapes
+}
+
+/// the indices of the leaves under `node`, the first of which is leaf `first`, in an order
+/// that paints nearer blocks over further ones: whichever half of a split is further from the
+/// bottom right goes first, since nothing in it can be in front of anything in the other
+fn back_to_front(node: Subtree<'_, Rectangle>, first: usize, order: &mut Vec<usize>) {
+    let (Some(left), Some(right)) = (node.left(), node.right()) else {
+        order.push(first);
+        return;
+    };
+    let after = first + left.leaves().count();
+    let nearer = |rectangle: &Rectangle| rectangle.x + rectangle.y;
+    if nearer(right.item()) >= nearer(left.item()) {
+        back_to_front(left, first, order);
+        back_to_front(right, after, order);
+    } else {
+        back_to_front(right, after, order);
+        back_to_front(left, first, order);
+    }
+}
+
+/// soft shadows under the panes in `panes` that aren't the background, falling down and to the
+/// right onto the ones that are, `radius` pixels across the soft part: rings from the lightest
+/// out at the edge to the darkest under the pane, every pane's lightest before anyone's darker
+/// ones so where two overlap the darker shows. drawn over all the panes, then the colored ones
+/// go over them again
+pub fn shadows(panes: &[Pane], radius: u32, background: Rgb<u8>) -> Vec<(Rectangle, Rgb<u8>)> {
+    let radius = radius as f32;
+    let rings = (radius.ceil() as usize).clamp(1, 32);
+    let casting: Vec<&Rectangle> = panes
+        .iter()
+        .filter(|pane| pane.color != background)
+        .map(|pane| &pane.rectangle)
+        .collect();
+    (0..rings)
+        .flat_map(|ring| {
+            // how far this ring reaches past the shadow's core, and how dark it is
+            let reach = radius * (1.0 - ring as f32 / rings as f32);
+            let darkness = (ring + 1) as f32 / rings as f32;
+            let color = shade(background, 1.0 - (1.0 - SHADOW) * darkness);
+            casting.iter().map(move |rectangle| {
+                (
+                    Rectangle {
+                        x: rectangle.x + radius / 2.0 - reach / 2.0,
+                        y: rectangle.y + radius / 2.0 - reach / 2.0,
+                        width: rectangle.width + reach,
+                        height: rectangle.height + reach,
+                    },
+                    color,
+                )
+            })
+        })
+        .collect()
+}
+
+/// `color` with this much of the light on it
+fn shade(color: Rgb<u8>, light: f32) -> Rgb<u8> {
+    oklab::darken(color, 1.0 - light)
+}
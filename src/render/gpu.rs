@@ -0,0 +1,537 @@
+//! drawing on the GPU with wgpu; needs the `gpu` feature
+//!
+//! every rectangle the raster renderer would paint becomes one instanced quad, and every polygon a
+//! fan of triangles, drawn in the same order, so without antialiasing the pixels come out the
+//! same (bar the odd pixel whose center is exactly on a polygon's side). with it, edges are still
+//! drawn hard, just at their sub-pixel positions. images bigger than the GPU's texture limit are
+//! drawn a tile at a time
+
+use std::{error::Error, fmt};
+
+use image::RgbImage;
+
+use crate::composition::Composition;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuError {
+    /// built without the `gpu` feature
+    Unsupported,
+    /// no adapter, or it wouldn't give us a device
+    Device(String),
+    /// the framebuffer couldn't be read back
+    Readback(String),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(
+                f,
+                "GPU rendering needs mondrian-rs built with `--features gpu`"
+            ),
+            Self::Device(err) => write!(f, "couldn't get a GPU: {err}"),
+            Self::Readback(err) => write!(f, "couldn't read the image back from the GPU: {err}"),
+        }
+    }
+}
+
+impl Error for GpuError {}
+
+/// a device and pipeline, kept around so animations don't set the GPU up again every frame
+pub struct Renderer {
+    #[cfg(feature = "gpu")]
+    state: backend::State,
+}
+
+impl Renderer {
+    #[cfg(feature = "gpu")]
+    pub fn new() -> Result<Self, GpuError> {
+        Ok(Self {
+            state: backend::State::new()?,
+        })
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn new() -> Result<Self, GpuError> {
+        Err(GpuError::Unsupported)
+    }
+
+    #[cfg(feature = "gpu")]
+    pub fn render(&self, composition: &Composition) -> Result<RgbImage, GpuError> {
+        self.state.render(composition, 1)
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn render(&self, _composition: &Composition) -> Result<RgbImage, GpuError> {
+        Err(GpuError::Unsupported)
+    }
+
+    /// like [`raster::render_supersampled`](super::raster::render_supersampled): drawn `factor`
+    /// times bigger, then scaled back down on the CPU
+    #[cfg(feature = "gpu")]
+    pub fn render_supersampled(
+        &self,
+        composition: &Composition,
+        factor: u32,
+    ) -> Result<RgbImage, GpuError> {
+        if factor <= 1 {
+            return self.render(composition);
+        }
+        let large = self.state.render(composition, factor)?;
+        Ok(super::raster::downscale(&large, composition))
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn render_supersampled(
+        &self,
+        _composition: &Composition,
+        _factor: u32,
+    ) -> Result<RgbImage, GpuError> {
+        Err(GpuError::Unsupported)
+    }
+}
+
+/// sets up a GPU just for this one image; use a [`Renderer`] for more than one
+pub fn render(composition: &Composition) -> Result<RgbImage, GpuError> {
+    Renderer::new()?.render(composition)
+}
+
+#[cfg(feature = "gpu")]
+mod backend {
+    use std::sync::mpsc;
+
+    use image::RgbImage;
+    use wgpu::util::DeviceExt;
+
+    use super::GpuError;
+    use crate::{
+        composition::Composition,
+        polygon::Point,
+        render::{marks, Mark, Quad},
+    };
+
+    const SHADER: &str = r#"
+struct Quad {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) corner: u32,
+    @location(0) bounds: vec4<f32>,
+    @location(1) color: vec4<f32>,
+) -> Quad {
+    // a triangle strip: top left, top right, bottom left, bottom right
+    let t = vec2<f32>(f32(corner & 1u), f32(corner >> 1u));
+    var quad: Quad;
+    quad.position = vec4<f32>(mix(bounds.xy, bounds.zw, t), 0.0, 1.0);
+    quad.color = color;
+    return quad;
+}
+
+@vertex
+fn triangle(@location(0) position: vec2<f32>, @location(1) color: vec4<f32>) -> Quad {
+    var vertex: Quad;
+    vertex.position = vec4<f32>(position, 0.0, 1.0);
+    vertex.color = color;
+    return vertex;
+}
+
+@fragment
+fn fragment(quad: Quad) -> @location(0) vec4<f32> {
+    return quad.color;
+}
+"#;
+
+    /// clip-space corners (4 f32s), then RGBA (4 u8s)
+    const INSTANCE_SIZE: u64 = 20;
+    /// a clip-space point (2 f32s), then RGBA (4 u8s)
+    const VERTEX_SIZE: u64 = 12;
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    pub struct State {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        pipeline: wgpu::RenderPipeline,
+        /// for polygons, cut into triangles
+        triangles: wgpu::RenderPipeline,
+    }
+
+    /// marks that can go in one draw call
+    enum Run {
+        Quads(Vec<Quad>),
+        /// three corners per triangle
+        Triangles(Vec<(Point, [u8; 4])>),
+    }
+
+    impl State {
+        pub fn new() -> Result<Self, GpuError> {
+            let instance = wgpu::Instance::new(
+                wgpu::InstanceDescriptor::new_without_display_handle_from_env(),
+            );
+            let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+                .map_err(|err| GpuError::Device(err.to_string()))?;
+            // ask for everything the adapter has, mostly for the biggest textures it can do
+            let (device, queue) =
+                pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                    label: Some("mondrian"),
+                    required_limits: adapter.limits(),
+                    ..Default::default()
+                }))
+                .map_err(|err| GpuError::Device(err.to_string()))?;
+
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("quads"),
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+            let pipeline = |entry_point, stride, step_mode, attributes, topology| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(entry_point),
+                    layout: None,
+                    vertex: wgpu::VertexState {
+                        module: &module,
+                        entry_point: Some(entry_point),
+                        compilation_options: Default::default(),
+                        buffers: &[Some(wgpu::VertexBufferLayout {
+                            array_stride: stride,
+                            step_mode,
+                            attributes,
+                        })],
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology,
+                        ..Default::default()
+                    },
+                    depth_stencil: None,
+                    multisample: Default::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &module,
+                        entry_point: Some("fragment"),
+                        compilation_options: Default::default(),
+                        targets: &[Some(FORMAT.into())],
+                    }),
+                    multiview_mask: None,
+                    cache: None,
+                })
+            };
+            let vertex_attributes = wgpu::vertex_attr_array![0 => Float32x2, 1 => Unorm8x4];
+            let instance_attributes = wgpu::vertex_attr_array![0 => Float32x4, 1 => Unorm8x4];
+            let triangles = pipeline(
+                "triangle",
+                VERTEX_SIZE,
+                wgpu::VertexStepMode::Vertex,
+                &vertex_attributes,
+                wgpu::PrimitiveTopology::TriangleList,
+            );
+            let pipeline = pipeline(
+                "vertex",
+                INSTANCE_SIZE,
+                wgpu::VertexStepMode::Instance,
+                &instance_attributes,
+                wgpu::PrimitiveTopology::TriangleStrip,
+            );
+
+            Ok(Self {
+                device,
+                queue,
+                pipeline,
+                triangles,
+            })
+        }
+
+        /// at `scale` times the composition's size
+        pub fn render(&self, composition: &Composition, scale: u32) -> Result<RgbImage, GpuError> {
+            let (width, height) = (
+                composition.output_width() * scale,
+                composition.output_height() * scale,
+            );
+            let limits = self.device.limits();
+            let tile = limits.max_texture_dimension_2d;
+            let (tile_width, tile_height) = (width.min(tile), height.min(tile));
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("tile"),
+                size: wgpu::Extent3d {
+                    width: tile_width,
+                    height: tile_height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&Default::default());
+
+            // rows of a texture copy have to start on 256-byte boundaries
+            let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+            let padded_row = (tile_width * 4).div_ceil(align) * align;
+            let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("readback"),
+                size: padded_row as u64 * tile_height as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+
+            let runs = runs(composition, scale);
+            // no single buffer may be bigger than the device allows; draws in one pass still go
+            // down in order
+            let per_buffer = (limits.max_buffer_size / INSTANCE_SIZE).max(1) as usize;
+            let per_vertex_buffer = (limits.max_buffer_size / VERTEX_SIZE / 3).max(1) as usize * 3;
+
+            let mut image = RgbImage::new(width, height);
+            for top in (0..height).step_by(tile_height as usize) {
+                for left in (0..width).step_by(tile_width as usize) {
+                    let origin = (left, top);
+                    let tile = (tile_width, tile_height);
+                    let buffer = |contents: &[u8]| {
+                        self.device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some("marks"),
+                                contents,
+                                usage: wgpu::BufferUsages::VERTEX,
+                            })
+                    };
+                    // each buffer with whether it's triangles, and how many quads or vertices
+                    let buffers: Vec<_> = runs
+                        .iter()
+                        .flat_map(|run| -> Vec<_> {
+                            match run {
+                                Run::Quads(quads) => quads
+                                    .chunks(per_buffer)
+                                    .map(|chunk| {
+                                        let contents = instances(chunk, origin, tile);
+                                        (buffer(&contents), false, chunk.len() as u32)
+                                    })
+                                    .collect(),
+                                Run::Triangles(corners) => corners
+                                    .chunks(per_vertex_buffer)
+                                    .map(|chunk| {
+                                        let contents = vertices(chunk, origin, tile);
+                                        (buffer(&contents), true, chunk.len() as u32)
+                                    })
+                                    .collect(),
+                            }
+                        })
+                        .collect();
+
+                    let mut encoder = self.device.create_command_encoder(&Default::default());
+                    {
+                        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                            label: Some("quads"),
+                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                                view: &view,
+                                depth_slice: None,
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    // the raster renderer starts from zeroed (black) pixels too
+                                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                    store: wgpu::StoreOp::Store,
+                                },
+                            })],
+                            ..Default::default()
+                        });
+                        for (buffer, triangles, count) in &buffers {
+                            pass.set_vertex_buffer(0, buffer.slice(..));
+                            if *triangles {
+                                pass.set_pipeline(&self.triangles);
+                                pass.draw(0..*count, 0..1);
+                            } else {
+                                pass.set_pipeline(&self.pipeline);
+                                pass.draw(0..4, 0..*count);
+                            }
+                        }
+                    }
+
+                    let (copy_width, copy_height) = (
+                        (width - left).min(tile_width),
+                        (height - top).min(tile_height),
+                    );
+                    encoder.copy_texture_to_buffer(
+                        texture.as_image_copy(),
+                        wgpu::TexelCopyBufferInfo {
+                            buffer: &readback,
+                            layout: wgpu::TexelCopyBufferLayout {
+                                offset: 0,
+                                bytes_per_row: Some(padded_row),
+                                rows_per_image: None,
+                            },
+                        },
+                        wgpu::Extent3d {
+                            width: copy_width,
+                            height: copy_height,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                    self.queue.submit([encoder.finish()]);
+
+                    let rgba = self.read(&readback)?;
+                    for y in 0..copy_height {
+                        let row = &rgba[(y * padded_row) as usize..][..(copy_width * 4) as usize];
+                        for (x, pixel) in row.chunks_exact(4).enumerate() {
+                            let at = image.get_pixel_mut(left + x as u32, top + y);
+                            at.0 = [pixel[0], pixel[1], pixel[2]];
+                        }
+                    }
+                    readback.unmap();
+                }
+            }
+            Ok(image)
+        }
+
+        /// blocks until `buffer` is mapped, then copies it out
+        fn read(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>, GpuError> {
+            let (sender, receiver) = mpsc::channel();
+            buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+                let _ = sender.send(result);
+            });
+            self.device
+                .poll(wgpu::PollType::wait_indefinitely())
+                .map_err(|err| GpuError::Readback(err.to_string()))?;
+            receiver
+                .recv()
+                .map_err(|err| GpuError::Readback(err.to_string()))?
+                .map_err(|err| GpuError::Readback(err.to_string()))?;
+            let rgba = buffer
+                .get_mapped_range(..)
+                .map_err(|err| GpuError::Readback(err.to_string()))?
+                .to_vec();
+            Ok(rgba)
+        }
+    }
+
+    /// [`marks`] `scale` times bigger, consecutive ones of the same kind together
+    fn runs(composition: &Composition, scale: u32) -> Vec<Run> {
+        let mut runs: Vec<Run> = vec![];
+        for mark in marks(composition, None) {
+            match mark {
+                Mark::Quad(quad) => {
+                    let quad = Quad {
+                        rectangle: quad.rectangle.scaled(scale as f32),
+                        ..quad
+                    };
+                    match runs.last_mut() {
+                        Some(Run::Quads(quads)) => quads.push(quad),
+                        _ => runs.push(Run::Quads(vec![quad])),
+                    }
+                }
+                Mark::Polygon { polygon, color, .. } => {
+                    let points = polygon.scaled(scale as f32).points;
+                    if points.len() < 3 {
+                        continue;
+                    }
+                    if !matches!(runs.last(), Some(Run::Triangles(_))) {
+                        runs.push(Run::Triangles(vec![]));
+                    }
+                    let Some(Run::Triangles(corners)) = runs.last_mut() else {
+                        unreachable!();
+                    };
+                    let color = [color[0], color[1], color[2], 255];
+                    for triangle in triangulate(&points) {
+                        corners.extend(triangle.map(|point| (point, color)));
+                    }
+                }
+            }
+        }
+        runs
+    }
+
+    /// cuts a polygon into triangles by clipping off one ear (a corner whose triangle has no other
+    /// corner in it) after another
+    fn triangulate(points: &[Point]) -> Vec<[Point; 3]> {
+        let cross =
+            |a: Point, b: Point, c: Point| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        let n = points.len();
+        let winding: f32 = (0..n)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % n]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f32>()
+            .signum();
+        let inside = |p: Point, [a, b, c]: [Point; 3]| {
+            cross(a, b, p) * winding > 0.0
+                && cross(b, c, p) * winding > 0.0
+                && cross(c, a, p) * winding > 0.0
+        };
+
+        let mut left: Vec<usize> = (0..n).collect();
+        let mut triangles = Vec::with_capacity(n.saturating_sub(2));
+        while left.len() > 3 {
+            let m = left.len();
+            let ear = (0..m).find(|&i| {
+                let [a, b, c] = [left[(i + m - 1) % m], left[i], left[(i + 1) % m]];
+                let triangle = [points[a], points[b], points[c]];
+                cross(triangle[0], triangle[1], triangle[2]) * winding > 0.0
+                    && !left
+                        .iter()
+                        .filter(|&&other| other != a && other != b && other != c)
+                        .any(|&other| inside(points[other], triangle))
+            });
+            // only a degenerate polygon has no ears; fan out whatever's left of it
+            let Some(i) = ear else {
+                break;
+            };
+            let [a, b, c] = [left[(i + m - 1) % m], left[i], left[(i + 1) % m]];
+            triangles.push([points[a], points[b], points[c]]);
+            left.remove(i);
+        }
+        for pair in left[1..].windows(2) {
+            triangles.push([points[left[0]], points[pair[0]], points[pair[1]]]);
+        }
+        triangles
+    }
+
+    /// from pixels to clip space for the tile at `origin` of size `tile`
+    fn to_clip(origin: (u32, u32), tile: (u32, u32)) -> impl Fn(f32, f32) -> (f32, f32) {
+        let (left, top) = (origin.0 as f64, origin.1 as f64);
+        let (width, height) = (tile.0 as f64, tile.1 as f64);
+        move |x, y| {
+            (
+                ((x as f64 - left) / width * 2.0 - 1.0) as f32,
+                (1.0 - (y as f64 - top) / height * 2.0) as f32,
+            )
+        }
+    }
+
+    /// vertex data for `corners`, like [`instances`]
+    fn vertices(corners: &[(Point, [u8; 4])], origin: (u32, u32), tile: (u32, u32)) -> Vec<u8> {
+        let to_clip = to_clip(origin, tile);
+        let mut bytes = Vec::with_capacity(corners.len() * VERTEX_SIZE as usize);
+        for (point, color) in corners {
+            let (x, y) = to_clip(point.x, point.y);
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&y.to_le_bytes());
+            bytes.extend_from_slice(color);
+        }
+        bytes
+    }
+
+    /// instance data for `quads`, in clip space for the tile at `origin` of size `tile`
+    fn instances(quads: &[Quad], origin: (u32, u32), tile: (u32, u32)) -> Vec<u8> {
+        let to_clip = to_clip(origin, tile);
+        let clip_x = |x: f32| to_clip(x, 0.0).0;
+        let clip_y = |y: f32| to_clip(0.0, y).1;
+
+        let mut bytes = Vec::with_capacity(quads.len() * INSTANCE_SIZE as usize);
+        for Quad {
+            rectangle, color, ..
+        } in quads
+        {
+            let right = rectangle.x + rectangle.width;
+            let bottom = rectangle.y + rectangle.height;
+            for corner in [
+                clip_x(rectangle.x),
+                clip_y(rectangle.y),
+                clip_x(right),
+                clip_y(bottom),
+            ] {
+                bytes.extend_from_slice(&corner.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[color[0], color[1], color[2], 255]);
+        }
+        bytes
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use image::Rgb;
+
+use super::{Part, Renderer};
+use crate::{
+    color::to_hex,
+    composition::Composition,
+    encode::{description, SOFTWARE},
+    geometry::{Rectangle, SplittableGraphic},
+    polygon::Polygon,
+};
+
+/// the HTML backend for [`draw`](super::draw): a standalone page made of absolutely positioned
+/// `div`s placed in percentages, so it fills the width of whatever holds it and stays sharp at
+/// any size; the palette, background and line colors are CSS custom properties (`--color-0`,
+/// `--background`, `--line`, ...) on the `.mondrian` element for pages to restyle
+#[derive(Debug, Clone)]
+pub struct Page {
+    html: String,
+    colors: Colors,
+    canvas: Canvas,
+    part: Option<Part>,
+}
+
+impl Page {
+    /// an empty page for `composition`
+    pub fn new(composition: &Composition) -> Self {
+        let (width, height) = (composition.output_width(), composition.output_height());
+        let colors = Colors::of(composition);
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        writeln!(html, "<meta name=\"generator\" content=\"{SOFTWARE}\">").unwrap();
+        // the parameters, to make it again
+        html.push_str("<!--\n");
+        for line in description(&composition.parameters()).lines() {
+            // `--` can't appear in a comment
+            writeln!(html, "{}", line.replace("--", "- -")).unwrap();
+        }
+        html.push_str("-->\n<title>mondrian</title>\n<style>\n.mondrian {\n");
+        for (name, color) in &colors.variables {
+            writeln!(html, "  --{name}: {};", to_hex(*color)).unwrap();
+        }
+        writeln!(
+            html,
+            "  position: relative;\n  width: 100%;\n  aspect-ratio: {width} / {height};\n  \
+             overflow: hidden;\n  background: var(--background);\n}}"
+        )
+        .unwrap();
+        html.push_str(".mondrian > div { position: absolute; }\n");
+        html.push_str(".mondrian > .shape { inset: 0; }\n");
+        html.push_str("body { margin: 0; }\n</style>\n</head>\n<body>\n<div class=\"mondrian\">\n");
+        Self {
+            html,
+            colors,
+            canvas: Canvas(Rectangle::new(0.0, 0.0, width as f32, height as f32)),
+            part: None,
+        }
+    }
+
+    /// the fill for the shape about to be written
+    fn fill(&self, color: Rgb<u8>) -> String {
+        match self.part {
+            Some(Part::Background) => "var(--background)".to_owned(),
+            Some(Part::Line) => "var(--line)".to_owned(),
+            Some(Part::Frame) => "var(--frame)".to_owned(),
+            Some(Part::Matte) => "var(--matte)".to_owned(),
+            _ => self.colors.fill(color),
+        }
+    }
+}
+
+impl Renderer for Page {
+    type Output = String;
+
+    fn part(&mut self, part: Part) {
+        self.part = Some(part);
+    }
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, _tiles: bool) {
+        let fill = self.fill(color);
+        self.canvas.rect(&mut self.html, rectangle, &fill);
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, _tiles: bool) {
+        let fill = self.fill(color);
+        self.canvas.polygon(&mut self.html, polygon, &fill);
+    }
+
+    fn finish(mut self) -> String {
+        self.html.push_str("</div>\n</body>\n</html>\n");
+        self.html
+    }
+}
+
+/// the colors that get a custom property, by name
+#[derive(Debug, Clone)]
+struct Colors {
+    variables: Vec<(String, Rgb<u8>)>,
+    palette: usize,
+}
+
+impl Colors {
+    fn of(composition: &Composition) -> Self {
+        let mut variables: Vec<(String, Rgb<u8>)> = composition
+            .palette()
+            .colors()
+            .iter()
+            .enumerate()
+            .map(|(n, color)| (format!("color-{n}"), *color))
+            .collect();
+        let palette = variables.len();
+        variables.extend([
+            (
+                "background".to_owned(),
+                composition.fill_options().background,
+            ),
+            ("line".to_owned(), composition.line_color()),
+            ("frame".to_owned(), composition.frame_color()),
+            ("matte".to_owned(), composition.matte_color()),
+        ]);
+        Self { variables, palette }
+    }
+
+    /// the property for a fill color, if it's one of the palette's or the background, or else
+    /// the color itself
+    fn fill(&self, color: Rgb<u8>) -> String {
+        self.variables[..=self.palette]
+            .iter()
+            .find(|(_, variable)| *variable == color)
+            .map(|(name, _)| format!("var(--{name})"))
+            .unwrap_or_else(|| to_hex(color))
+    }
+}
+
+/// turns pixel positions into percentages of the part of the canvas that's drawn
+#[derive(Debug, Clone)]
+struct Canvas(Rectangle);
+
+impl Canvas {
+    fn rect(&self, html: &mut String, rectangle: &Rectangle, fill: &str) {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = *rectangle;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        writeln!(
+            html,
+            r#"  <div style="left:{}%;top:{}%;width:{}%;height:{}%;background:{fill}"></div>"#,
+            percent(x - self.0.x, self.0.width),
+            percent(y - self.0.y, self.0.height),
+            percent(width, self.0.width),
+            percent(height, self.0.height),
+        )
+        .unwrap();
+    }
+
+    /// a canvas-sized `div` clipped to `polygon`
+    fn polygon(&self, html: &mut String, polygon: &Polygon, fill: &str) {
+        if polygon.points.len() < 3 {
+            return;
+        }
+        html.push_str(r#"  <div class="shape" style="clip-path:polygon("#);
+        for (i, point) in polygon.points.iter().enumerate() {
+            if i > 0 {
+                html.push(',');
+            }
+            write!(
+                html,
+                "{}% {}%",
+                percent(point.x - self.0.x, self.0.width),
+                percent(point.y - self.0.y, self.0.height)
+            )
+            .unwrap();
+        }
+        writeln!(html, r#");background:{fill}"></div>"#).unwrap();
+    }
+}
+
+/// `value` as a percentage of `of`, to a ten-thousandth of a percent, which is finer than a pixel
+/// on any screen
+fn percent(value: f32, of: f32) -> f32 {
+    (value as f64 / of as f64 * 1e6).round() as f32 / 1e4
+}
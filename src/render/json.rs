@@ -0,0 +1,93 @@
+use std::{fmt::Write, slice::Iter};
+
+use crate::{
+    color::to_hex,
+    composition::{Composition, Pane},
+    geometry::Rectangle,
+    tree::Subtree,
+};
+
+/// nested nodes like `{"x":0,"y":0,"width":10,"height":10,"depth":0,"children":[...]}`; leaves get a
+/// `"color"` instead of `"children"` (and diagonally cut ones `"diagonal":"rising"` or `"falling"`
+/// with the bottom half's `"diagonal-color"`, patterned ones `"pattern":"stripes"` or
+/// `"checkerboard"` with its `"pattern-color"` and `"bands"`), and styles made of shapes list them
+/// in the root as
+/// `"shapes":[{"points":[[x,y],...],"color":"#ff0000"},...]`
+pub fn render(composition: &Composition) -> String {
+    let mut json = String::new();
+    let mut panes = composition.panes().iter();
+    write_node(&mut json, composition.tree().root(), &mut panes);
+    if !composition.shapes().is_empty() {
+        // the other styles' shapes go in the root, which is all their tree is
+        json.pop();
+        json.push_str(r#","shapes":["#);
+        for (i, shape) in composition.shapes().iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(r#"{"points":["#);
+            for (n, point) in shape.polygon.points.iter().enumerate() {
+                if n > 0 {
+                    json.push(',');
+                }
+                write!(json, "[{},{}]", point.x, point.y).unwrap();
+            }
+            write!(json, r#"],"color":"{}"}}"#, to_hex(shape.color)).unwrap();
+        }
+        json.push_str("]}");
+    }
+    json.push('\n');
+    json
+}
+
+fn write_node(json: &mut String, node: Subtree<'_, Rectangle>, panes: &mut Iter<'_, Pane>) {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = node.item();
+    write!(
+        json,
+        r#"{{"x":{x},"y":{y},"width":{width},"height":{height},"depth":{}"#,
+        node.depth()
+    )
+    .unwrap();
+
+    if node.is_leaf() {
+        // leaves come out in the same order as the panes were made
+        if let Some(pane) = panes.next() {
+            write!(json, r#","color":"{}""#, to_hex(pane.color)).unwrap();
+            if let Some(diagonal) = pane.diagonal {
+                let direction = if diagonal.rising { "rising" } else { "falling" };
+                write!(
+                    json,
+                    r#","diagonal":"{direction}","diagonal-color":"{}""#,
+                    to_hex(diagonal.color)
+                )
+                .unwrap();
+            }
+            if let Some(pattern) = pane.pattern {
+                write!(
+                    json,
+                    r#","pattern":"{}","pattern-color":"{}","bands":{}"#,
+                    pattern.motif.name(),
+                    to_hex(pattern.color),
+                    pattern.bands
+                )
+                .unwrap();
+            }
+        }
+    } else {
+        json.push_str(r#","children":["#);
+        let children = node.left().into_iter().chain(node.right());
+        for (i, child) in children.enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_node(json, child, panes);
+        }
+        json.push(']');
+    }
+    json.push('}');
+}
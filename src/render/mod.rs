@@ -0,0 +1,417 @@
+//! turning a [`Composition`](crate::composition::Composition) into something you can look at
+
+use std::iter;
+
+use image::Rgb;
+
+use crate::{
+    composition::{self, Composition},
+    geometry::{Rectangle, SplittableGraphic},
+    polygon::Polygon,
+    style::Style,
+};
+
+pub mod gpu;
+pub mod html;
+pub mod json;
+pub mod pdf;
+pub mod raster;
+pub mod svg;
+pub mod terminal;
+pub mod tikz;
+
+/// a backend [`draw`] paints compositions onto, one solid shape at a time, in the order the
+/// raster renderer paints them (matte and panes, then lines, then the frame and text over them)
+/// and at the same positions; a new output format is just these, with nothing to walk itself
+pub trait Renderer {
+    /// what's left once everything's painted, e.g. the image or the document
+    type Output;
+
+    /// what the shapes painted from here on are, until it's called again; it's called before each
+    /// one, for backends that label or restyle things by what they are, like SVG's classes
+    fn part(&mut self, _part: Part) {}
+
+    /// a solid rectangle: a pane, a band of the matte or frame, a QR module. `tiles` if it's one
+    /// of the panes, which fit together exactly, so where two share an antialiased pixel their
+    /// coverage adds up rather than one going over the other; backends that don't blend can
+    /// ignore it
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, tiles: bool);
+
+    /// a solid polygon, its inside even-odd: the bottom halves of diagonal panes, shapes, 3d
+    /// blocks, sketched and curved lines, text. `tiles` like [`fill_rect`](Self::fill_rect)'s
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, tiles: bool);
+
+    /// a straight line between panes, `rectangle` being all of it, its width and the ends that
+    /// run into the lines it meets included; filled like any other rectangle unless the backend
+    /// has strokes of its own
+    fn stroke_edge(&mut self, rectangle: &Rectangle, color: Rgb<u8>) {
+        self.fill_rect(rectangle, color, false);
+    }
+
+    /// everything's painted
+    fn finish(self) -> Self::Output;
+}
+
+/// what a shape [`draw`] paints is part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Part {
+    /// a band of the matte
+    Matte,
+    /// pane `n` of [`Composition::panes`]
+    Pane(usize),
+    /// the canvas before there are panes on it, or under 3d blocks
+    Background,
+    /// one of the rings of a pane's shadow
+    Shadow,
+    /// pane `n` again, over the shadows
+    Lifted(usize),
+    /// the bottom half of a diagonally cut pane
+    Diagonal,
+    /// one of a pane's stripes or squares
+    Pattern,
+    /// one of the shapes a style that isn't rectangles is made of
+    Shape,
+    /// a face of a 3d block
+    Block,
+    /// a line between panes, straight, sketched or curved
+    Line,
+    /// the paint a sketchy pane spills over its lines
+    Spill,
+    /// one of the QR code's modules
+    Qr,
+    /// a band of the frame
+    Frame,
+    /// a glyph of the text
+    Text,
+}
+
+impl Part {
+    /// whether it's one of the shapes that fit together exactly; see [`Renderer::fill_rect`]
+    pub fn tiles(self) -> bool {
+        matches!(self, Self::Pane(_) | Self::Background | Self::Shape)
+    }
+}
+
+/// `composition` painted onto `renderer`, which is then [finished](Renderer::finish)
+pub fn draw<R: Renderer>(composition: &Composition, mut renderer: R) -> R::Output {
+    paint(&mut renderer, marks(composition, None));
+    renderer.finish()
+}
+
+/// `marks` painted onto `renderer` in order, the straight lines as [edges](Renderer::stroke_edge)
+pub(crate) fn paint<R: Renderer + ?Sized>(renderer: &mut R, marks: impl Iterator<Item = Mark>) {
+    for mark in marks {
+        match mark {
+            Mark::Quad(quad) => {
+                renderer.part(quad.part);
+                if quad.part == Part::Line {
+                    renderer.stroke_edge(&quad.rectangle, quad.color);
+                } else {
+                    renderer.fill_rect(&quad.rectangle, quad.color, quad.part.tiles());
+                }
+            }
+            Mark::Polygon {
+                polygon,
+                color,
+                part,
+            } => {
+                renderer.part(part);
+                renderer.fill_polygon(&polygon, color, part.tiles());
+            }
+        }
+    }
+}
+
+/// one solid rectangle for a backend to paint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Quad {
+    pub rectangle: Rectangle,
+    pub color: Rgb<u8>,
+    pub part: Part,
+}
+
+/// one solid shape for a backend to paint
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Mark {
+    Quad(Quad),
+    Polygon {
+        polygon: Polygon,
+        color: Rgb<u8>,
+        part: Part,
+    },
+}
+
+/// everything the raster backends paint, in the order they paint them: matte, panes (with any
+/// shadows, under the colored ones painted again, the bottom halves of diagonally cut ones and
+/// patterns),
+/// shapes or 3d blocks, then every shared edge and segment once, so no line is drawn twice and
+/// comes out heavier, the paint sketchy panes spill over them, the QR code's modules, then the
+/// frame, and last the text
+///
+/// with a `depth`, only the lines from splits shallower than it and no pane colors or shapes yet;
+/// without antialiasing every rectangle is snapped to whole pixels. they're where they go in the
+/// image, which is only the canvas too if there's no [viewport](Composition::viewport)
+pub(crate) fn marks(
+    composition: &Composition,
+    depth: Option<usize>,
+) -> impl Iterator<Item = Mark> + '_ {
+    fill_marks(composition, depth).chain(line_marks(composition, depth))
+}
+
+/// the first of [`marks`]: everything under the lines, matte through 3d blocks
+pub(crate) fn fill_marks(
+    composition: &Composition,
+    depth: Option<usize>,
+) -> impl Iterator<Item = Mark> + '_ {
+    let (width, height) = (composition.width() as f32, composition.height() as f32);
+    let matte = composition.matte() as f32;
+    let matte_color = composition.matte_color();
+    // around the composition rather than under it, so nothing is left for the panes to add onto
+    let matte = [
+        Rectangle::new(0.0, 0.0, width, matte),
+        Rectangle::new(0.0, height - matte, width, matte),
+        Rectangle::new(0.0, matte, matte, height - 2.0 * matte),
+        Rectangle::new(width - matte, matte, matte, height - 2.0 * matte),
+    ]
+    .into_iter()
+    .filter(move |_| matte > 0.0)
+    .map(move |band| (band, matte_color, Part::Matte));
+
+    // 3d compositions draw blocks instead of panes and lines
+    let flat = composition.style() != Style::ThreeD;
+    let panes = if depth.is_none() && flat {
+        composition.panes()
+    } else {
+        &[]
+    };
+    let antialias = composition.antialias();
+    // bottom halves go over the whole pane; the rest of it is already the top half's color
+    let halves = panes.iter().filter_map(move |pane| {
+        let pane = composition::Pane {
+            rectangle: if antialias {
+                pane.rectangle
+            } else {
+                pane.rectangle.snapped()
+            },
+            ..pane.clone()
+        };
+        let [_, bottom] = pane.halves()?;
+        Some(Mark::Polygon {
+            polygon: bottom,
+            color: pane.diagonal?.color,
+            part: Part::Diagonal,
+        })
+    });
+    // stripes and squares go over the pane too
+    let patterns = panes.iter().flat_map(|pane| {
+        pane.pattern.into_iter().flat_map(|pattern| {
+            let rectangles = pattern.rectangles(&pane.rectangle).into_iter();
+            rectangles.map(move |rectangle| (rectangle, pattern.color, Part::Pattern))
+        })
+    });
+    let background = composition.fill_options().background;
+    let shadows = composition.shadows();
+    // the colored panes again, over their shadows
+    let lifted = panes
+        .iter()
+        .enumerate()
+        .filter(move |(_, pane)| !shadows.is_empty() && pane.color != background)
+        .map(|(n, pane)| (pane.rectangle, pane.color, Part::Lifted(n)));
+    let shadows = composition
+        .shadows()
+        .into_iter()
+        .filter(move |_| depth.is_none())
+        .map(|(rectangle, color)| (rectangle, color, Part::Shadow));
+    let panes = panes
+        .iter()
+        .enumerate()
+        .map(|(n, pane)| (pane.rectangle, pane.color, Part::Pane(n)));
+    let unfilled = (depth.is_some() || !flat)
+        .then(|| (*composition.tree().item(), background, Part::Background));
+    let blocks = composition
+        .blocks()
+        .into_iter()
+        .filter(move |_| depth.is_none())
+        .map(|block| Mark::Polygon {
+            polygon: block.polygon,
+            color: block.color,
+            part: Part::Block,
+        });
+
+    let shapes = if depth.is_none() {
+        composition.shapes()
+    } else {
+        &[]
+    };
+    let shapes = shapes.iter().map(|shape| Mark::Polygon {
+        polygon: shape.polygon.clone(),
+        color: shape.color,
+        part: Part::Shape,
+    });
+
+    let quad = quad(antialias);
+    iter::empty()
+        .chain(matte.chain(panes).chain(unfilled).map(quad))
+        .chain(shadows.chain(lifted).map(quad))
+        .chain(halves)
+        .chain(patterns.map(quad))
+        .chain(shapes)
+        .chain(blocks)
+        .map(place(composition))
+}
+
+/// the rest of [`marks`]: the lines, and what goes over them
+pub(crate) fn line_marks(
+    composition: &Composition,
+    depth: Option<usize>,
+) -> impl Iterator<Item = Mark> + '_ {
+    edge_marks(composition, depth)
+        .chain(over_marks(composition, depth))
+        .map(place(composition))
+}
+
+/// the first of [`line_marks`]: the lines between panes, straight, sketched or curved
+fn edge_marks(composition: &Composition, depth: Option<usize>) -> impl Iterator<Item = Mark> + '_ {
+    let flat = composition.style() != Style::ThreeD;
+    let border_width = composition.border_width();
+    let line_color = composition.line_color();
+    // clipped, so the ends that stick out past where segments meet don't poke into the frame
+    let inside = *composition.tree().item();
+    let segments = composition
+        .segments()
+        .iter()
+        .filter(move |_| border_width > 0 && depth.is_none() && flat)
+        .map(move |segment| Mark::Polygon {
+            polygon: segment.to_polygon(border_width).clipped_to(&inside),
+            color: line_color,
+            part: Part::Line,
+        });
+
+    let edges = composition.edges().iter().filter(move |edge| {
+        border_width > 0 && flat && depth.is_none_or(|depth| edge.depth < depth)
+    });
+    let sketched = edges.clone().filter_map(move |edge| {
+        Some(Mark::Polygon {
+            polygon: composition.sketched(edge)?.clipped_to(&inside),
+            color: line_color,
+            part: Part::Line,
+        })
+    });
+    let edges = edges
+        .filter(move |_| composition.style() != Style::Sketchy)
+        .map(move |edge| {
+            let width = composition.edge_width(edge);
+            (edge.to_rectangle(width), line_color, Part::Line)
+        });
+
+    iter::empty()
+        .chain(edges.map(quad(composition.antialias())))
+        .chain(sketched)
+        .chain(segments)
+}
+
+/// the rest of [`line_marks`]: spills, the QR code, the frame and the text
+fn over_marks(composition: &Composition, depth: Option<usize>) -> impl Iterator<Item = Mark> + '_ {
+    let flat = composition.style() != Style::ThreeD;
+    let spills = composition
+        .spills()
+        .into_iter()
+        .filter(move |_| depth.is_none())
+        .map(|(rectangle, color)| (rectangle, color, Part::Spill));
+
+    let qr = composition
+        .qr()
+        .filter(|_| depth.is_none() && flat)
+        .into_iter()
+        .flat_map(|qr| {
+            qr.modules
+                .iter()
+                .map(|module| (*module, qr.color, Part::Qr))
+        });
+
+    let frame_color = composition.frame_color();
+    let frame = composition
+        .frame()
+        .into_iter()
+        .filter(|_| composition.frame_width() > 0)
+        .map(move |band| (band, frame_color, Part::Frame));
+
+    // over everything, frame included
+    let glyphs = composition
+        .glyphs()
+        .iter()
+        .filter(move |_| depth.is_none())
+        .map(|glyph| Mark::Polygon {
+            polygon: glyph.polygon.clone(),
+            color: glyph.color,
+            part: Part::Text,
+        });
+
+    let quad = quad(composition.antialias());
+    iter::empty()
+        .chain(spills.map(quad))
+        .chain(qr.map(quad))
+        .chain(frame.map(quad))
+        .chain(glyphs)
+}
+
+/// a rectangle, its color and what it's [part](Part) of, as a mark; on whole pixels unless it's
+/// `antialias`ed
+fn quad(antialias: bool) -> impl Fn((Rectangle, Rgb<u8>, Part)) -> Mark + Copy {
+    move |(rectangle, color, part)| {
+        Mark::Quad(Quad {
+            rectangle: if antialias {
+                rectangle
+            } else {
+                rectangle.snapped()
+            },
+            color,
+            part,
+        })
+    }
+}
+
+/// moves a mark from where it is on the canvas to where it is in the image of the composition's
+/// [viewport](Composition::viewport); without one it stays put
+fn place(composition: &Composition) -> impl Fn(Mark) -> Mark + Copy {
+    let antialias = composition.antialias();
+    let view = composition.viewport().map(|viewport| {
+        let scale = viewport.scale(composition.width(), composition.height());
+        (composition.viewed(), scale)
+    });
+    move |mark| {
+        let Some((view, scale)) = view else {
+            return mark;
+        };
+        match mark {
+            Mark::Quad(Quad {
+                rectangle,
+                color,
+                part,
+            }) => {
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = rectangle;
+                let rectangle = Rectangle::new(
+                    (x - view.x) * scale,
+                    (y - view.y) * scale,
+                    width * scale,
+                    height * scale,
+                );
+                quad(antialias)((rectangle, color, part))
+            }
+            Mark::Polygon {
+                polygon,
+                color,
+                part,
+            } => Mark::Polygon {
+                polygon: polygon.translated(-view.x, -view.y).scaled(scale),
+                color,
+                part,
+            },
+        }
+    }
+}
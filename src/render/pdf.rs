@@ -0,0 +1,157 @@
+//! a one-page PDF the composition's size, a point to a pixel, for printing or dropping into a
+//! layout as vectors
+
+use std::fmt::Write;
+
+use image::Rgb;
+
+use super::Renderer;
+use crate::{composition::Composition, geometry::Rectangle, polygon::Polygon};
+
+/// a PDF for [`draw`](super::draw); it's all ASCII, so it comes out as a `String`, the page's
+/// drawing uncompressed
+#[derive(Debug, Clone)]
+pub struct Document {
+    width: u32,
+    height: u32,
+    /// the page's content stream so far
+    content: String,
+    /// the fill and stroke colors it's left set to, so they're only set again when they change
+    fill: Option<Rgb<u8>>,
+    stroke: Option<Rgb<u8>>,
+}
+
+impl Document {
+    /// an empty page as big as `composition`
+    pub fn new(composition: &Composition) -> Self {
+        let (width, height) = (composition.output_width(), composition.output_height());
+        // PDF's y goes up from the bottom, so it's flipped once to go down from the top like ours
+        let content = format!("1 0 0 -1 0 {height} cm\n");
+        Self {
+            width,
+            height,
+            content,
+            fill: None,
+            stroke: None,
+        }
+    }
+
+    /// sets the fill or, with `stroke`, the stroke color to `color`, unless it already is
+    fn color(&mut self, color: Rgb<u8>, stroke: bool) {
+        let current = if stroke {
+            &mut self.stroke
+        } else {
+            &mut self.fill
+        };
+        if current.replace(color) == Some(color) {
+            return;
+        }
+        let [r, g, b] = color.0.map(|channel| number(channel as f32 / 255.0));
+        let operator = if stroke { "RG" } else { "rg" };
+        writeln!(self.content, "{r} {g} {b} {operator}").unwrap();
+    }
+}
+
+impl Renderer for Document {
+    type Output = String;
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, _tiles: bool) {
+        if rectangle.width <= 0.0 || rectangle.height <= 0.0 {
+            return;
+        }
+        self.color(color, false);
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = *rectangle;
+        let [x, y, width, height] = [x, y, width, height].map(number);
+        writeln!(self.content, "{x} {y} {width} {height} re f").unwrap();
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, _tiles: bool) {
+        if polygon.points.len() < 3 {
+            return;
+        }
+        self.color(color, false);
+        for (i, point) in polygon.points.iter().enumerate() {
+            let operator = if i == 0 { "m" } else { "l" };
+            writeln!(
+                self.content,
+                "{} {} {operator}",
+                number(point.x),
+                number(point.y)
+            )
+            .unwrap();
+        }
+        self.content.push_str("h f*\n");
+    }
+
+    /// a stroke down the middle of the rectangle, as wide as its short side
+    fn stroke_edge(&mut self, rectangle: &Rectangle, color: Rgb<u8>) {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = *rectangle;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        let ((x1, y1), (x2, y2), stroke) = if width >= height {
+            let middle = y + height / 2.0;
+            ((x, middle), (x + width, middle), height)
+        } else {
+            let middle = x + width / 2.0;
+            ((middle, y), (middle, y + height), width)
+        };
+        self.color(color, true);
+        let [x1, y1, x2, y2, stroke] = [x1, y1, x2, y2, stroke].map(number);
+        writeln!(self.content, "{stroke} w {x1} {y1} m {x2} {y2} l S").unwrap();
+    }
+
+    fn finish(self) -> String {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << >> /Contents 4 0 R >>",
+                self.width, self.height
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                self.content.len(),
+                self.content
+            ),
+        ];
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = vec![];
+        for (n, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj\n{object}\nendobj", n + 1).unwrap();
+        }
+        let xref = pdf.len();
+        writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1).unwrap();
+        for offset in offsets {
+            writeln!(pdf, "{offset:010} 00000 n ").unwrap();
+        }
+        writeln!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF",
+            objects.len() + 1
+        )
+        .unwrap();
+        pdf
+    }
+}
+
+/// `value` to a thousandth, without the zeros after the point; PDF has no exponents
+fn number(value: f32) -> String {
+    let text = format!("{value:.3}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_owned(),
+        text => text.to_owned(),
+    }
+}
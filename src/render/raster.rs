@@ -0,0 +1,483 @@
+use std::{mem, ops::Range};
+
+use image::{imageops, ImageBuffer, Pixel, Primitive, Rgb, RgbImage};
+
+use super::{fill_marks, line_marks, marks, paint, Part, Renderer};
+use crate::{
+    composition::Composition,
+    geometry::Rectangle,
+    polygon::{Polygon, Shape},
+};
+
+/// how many rows of samples an antialiased polygon gets in each pixel; across each row its
+/// coverage is exact
+const POLYGON_SAMPLES: u32 = 16;
+
+/// what each channel of a rendered image is stored as; colors are 8 bits, but antialiased edges
+/// get finer steps between them out of more, which helps when there's post-processing to come
+pub trait Channel: Primitive + 'static {
+    /// full intensity; [`Channel`]s go from 0 to this
+    const MAX: f32;
+
+    /// `value`, from 0 to [`MAX`](Self::MAX), rounded if need be
+    fn from_f32(value: f32) -> Self;
+
+    fn to_f32(self) -> f32;
+
+    /// the same intensity as the 8-bit `value`
+    fn scale(value: u8) -> f32 {
+        value as f32 * (Self::MAX / 255.0)
+    }
+}
+
+impl Channel for u8 {
+    const MAX: f32 = 255.0;
+
+    fn from_f32(value: f32) -> Self {
+        value.round() as u8
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Channel for u16 {
+    const MAX: f32 = 65535.0;
+
+    fn from_f32(value: f32) -> Self {
+        value.round() as u16
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Channel for f32 {
+    const MAX: f32 = 1.0;
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+/// an RGB image with [`Channel`]s of any kind; `Image<u8>` is an [`RgbImage`]
+pub type Image<C> = ImageBuffer<Rgb<C>, Vec<C>>;
+
+/// paints [`marks`](super::marks) in order; see it for what goes on top of what
+pub fn render(composition: &Composition) -> RgbImage {
+    super::draw(composition, Canvas::new(composition, 1))
+}
+
+/// [`render`] at `factor` times the size, then scaled back down with a Lanczos filter, which
+/// smooths edges (thin lines especially) further than antialiasing alone; `factor`² times the
+/// memory while it's at it
+pub fn render_supersampled(composition: &Composition, factor: u32) -> RgbImage {
+    if factor <= 1 {
+        return render(composition);
+    }
+    let large = super::draw(composition, Canvas::new(composition, factor));
+    downscale(&large, composition)
+}
+
+/// [`render_supersampled`], calling `progress` with how many [marks](super::marks) (each pane,
+/// line, and so on) are painted, and of how many, as it goes
+pub fn render_with(
+    composition: &Composition,
+    factor: u32,
+    progress: impl FnMut(usize, usize),
+) -> RgbImage {
+    let factor = factor.max(1);
+    let total = marks(composition, None).count();
+    let counted = Counted {
+        canvas: Canvas::new(composition, factor),
+        done: 0,
+        total,
+        progress,
+    };
+    let large = super::draw(composition, counted);
+    if factor == 1 {
+        return large;
+    }
+    downscale(&large, composition)
+}
+
+/// [`render_supersampled`] into `C`s instead of bytes, e.g. `u16` for a 16-bit PNG
+pub fn render_as<C: Channel>(composition: &Composition, factor: u32) -> Image<C>
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    let factor = factor.max(1);
+    let large = super::draw(composition, Canvas::new(composition, factor));
+    if factor == 1 {
+        return large;
+    }
+    let mut image = downscale(&large, composition);
+    // Lanczos rings a little past either end, which only floats keep
+    for channel in image.iter_mut() {
+        *channel = C::from_f32(channel.to_f32().clamp(0.0, C::MAX));
+    }
+    image
+}
+
+/// shrinks an image rendered at some multiple of `composition`'s size back down to it
+pub(crate) fn downscale<C: Channel>(large: &Image<C>, composition: &Composition) -> Image<C>
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    imageops::resize(
+        large,
+        composition.output_width(),
+        composition.output_height(),
+        imageops::FilterType::Lanczos3,
+    )
+}
+
+/// just `rows` of [`render`]; the same pixels, without holding the whole image at once
+pub fn render_strip(composition: &Composition, rows: Range<u32>) -> RgbImage {
+    let rows =
+        rows.start.min(composition.output_height())..rows.end.min(composition.output_height());
+    let mut canvas = Canvas::new(composition, 1);
+    canvas.image = RgbImage::new(composition.output_width(), rows.len() as u32);
+    canvas.top = rows.start;
+    super::draw(composition, canvas)
+}
+
+/// the whole image, top to bottom, `rows` tall at a time (the last strip may be shorter)
+pub fn strips(composition: &Composition, rows: u32) -> impl Iterator<Item = RgbImage> + '_ {
+    let rows = rows.max(1);
+    (0..composition.output_height())
+        .step_by(rows as usize)
+        .map(move |top| render_strip(composition, top..top.saturating_add(rows)))
+}
+
+/// the composition partway through being made: only the lines from splits shallower than `depth`,
+/// and every pane still the background color until all the splits are in
+pub fn render_splits(composition: &Composition, depth: usize) -> RgbImage {
+    if depth > composition.levels() {
+        return render(composition);
+    }
+    let mut canvas = Canvas::new(composition, 1);
+    paint(&mut canvas, marks(composition, Some(depth)));
+    canvas.finish()
+}
+
+/// [`render`] up to the lines: the matte, panes, shadows, shapes and blocks. with
+/// [`render_lines`] on top it's the same pixels, in two steps that can be timed apart
+pub fn render_fill(composition: &Composition) -> RgbImage {
+    let mut canvas = Canvas::new(composition, 1);
+    paint(&mut canvas, fill_marks(composition, None));
+    canvas.finish()
+}
+
+/// the rest of [`render`] over what [`render_fill`] drew: the lines, and the QR code, frame and
+/// text that go over them
+pub fn render_lines(composition: &Composition, image: &mut RgbImage) {
+    let mut canvas = Canvas::new(composition, 1);
+    canvas.image = mem::take(image);
+    paint(&mut canvas, line_marks(composition, None));
+    *image = canvas.finish();
+}
+
+/// `shapes` painted over an image that's already drawn, antialiased, e.g. labels laid out by
+/// [`text::layout`](crate::text::layout)
+pub fn paint_shapes(image: &mut RgbImage, shapes: &[Shape]) {
+    for shape in shapes {
+        fill_polygon(image, 0, &shape.polygon, shape.color, true, false);
+    }
+}
+
+/// the raster backend: an image [`draw`](super::draw) paints into, `scale` times the
+/// composition's size (so a strip of it `top` rows down, in scaled pixels too); the rectangles
+/// it's given are in whole-image coordinates and get clipped to it
+pub struct Canvas<C: Channel>
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    image: Image<C>,
+    top: u32,
+    scale: u32,
+    antialias: bool,
+}
+
+impl<C: Channel> Canvas<C>
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    /// a blank image for all of `composition`, `scale` times bigger
+    pub fn new(composition: &Composition, scale: u32) -> Self {
+        let scale = scale.max(1);
+        Self {
+            image: Image::new(
+                composition.output_width() * scale,
+                composition.output_height() * scale,
+            ),
+            top: 0,
+            scale,
+            antialias: composition.antialias(),
+        }
+    }
+}
+
+impl<C: Channel> Renderer for Canvas<C>
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    type Output = Image<C>;
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, tiles: bool) {
+        let mut rectangle = *rectangle;
+        if self.scale > 1 {
+            rectangle = rectangle.scaled(self.scale as f32);
+        }
+        if self.antialias {
+            blend(&mut self.image, self.top, &rectangle, color, tiles);
+        } else {
+            fill(&mut self.image, self.top, &rectangle, color);
+        }
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, tiles: bool) {
+        let (top, antialias) = (self.top, self.antialias);
+        if self.scale > 1 {
+            let polygon = polygon.scaled(self.scale as f32);
+            fill_polygon(&mut self.image, top, &polygon, color, antialias, tiles);
+        } else {
+            fill_polygon(&mut self.image, top, polygon, color, antialias, tiles);
+        }
+    }
+
+    fn finish(self) -> Image<C> {
+        self.image
+    }
+}
+
+/// a [`Canvas`] that calls `progress` with how many marks it's been given, of `total`, before
+/// each one, and once more at the end
+struct Counted<F> {
+    canvas: Canvas<u8>,
+    done: usize,
+    total: usize,
+    progress: F,
+}
+
+impl<F: FnMut(usize, usize)> Renderer for Counted<F> {
+    type Output = RgbImage;
+
+    fn part(&mut self, part: Part) {
+        (self.progress)(self.done, self.total);
+        self.done += 1;
+        self.canvas.part(part);
+    }
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, tiles: bool) {
+        self.canvas.fill_rect(rectangle, color, tiles);
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, tiles: bool) {
+        self.canvas.fill_polygon(polygon, color, tiles);
+    }
+
+    fn finish(mut self) -> RgbImage {
+        (self.progress)(self.total, self.total);
+        self.canvas.finish()
+    }
+}
+
+/// paints `rectangle`, clipped to the image, which starts `top` rows down; copies whole rows at a
+/// time rather than going pixel by pixel
+///
+/// `rectangle` should already be on whole pixels; anything left over is rounded
+fn fill<C: Channel>(imagebuf: &mut Image<C>, top: u32, rectangle: &Rectangle, color: Rgb<u8>)
+where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    let (image_width, image_height) = imagebuf.dimensions();
+    let Some((columns, rows)) = clip(rectangle, top, image_width, image_height) else {
+        return;
+    };
+    let pixel = |at: f32| at.round() as u32;
+    let (x_start, x_end) = (pixel(columns.start), pixel(columns.end));
+    let (y_start, y_end) = (pixel(rows.start), pixel(rows.end));
+    if x_start >= x_end || y_start >= y_end {
+        return;
+    }
+
+    let row = color
+        .0
+        .map(|channel| C::from_f32(C::scale(channel)))
+        .repeat((x_end - x_start) as usize);
+    let stride = image_width as usize * 3;
+    let buffer: &mut [C] = imagebuf;
+    for y in y_start - top..y_end - top {
+        let start = y as usize * stride + x_start as usize * 3;
+        buffer[start..start + row.len()].copy_from_slice(&row);
+    }
+}
+
+/// like [`fill`], but pixels the rectangle only partly covers get that fraction of its color;
+/// `tiles` adds coverage up rather than laying it over
+///
+/// only the pixels along the sides are blended; the fully covered middle is still copied a row
+/// at a time
+fn blend<C: Channel>(
+    imagebuf: &mut Image<C>,
+    top: u32,
+    rectangle: &Rectangle,
+    color: Rgb<u8>,
+    tiles: bool,
+) where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    let (image_width, image_height) = imagebuf.dimensions();
+    let Some((columns, rows)) = clip(rectangle, top, image_width, image_height) else {
+        return;
+    };
+    // the pixels touched at all, the ones covered completely, and the ones along the sides
+    let touched = columns.start.floor() as u32..columns.end.ceil() as u32;
+    let full = columns.start.ceil() as u32..(columns.end.floor() as u32).max(touched.start);
+    let all: Vec<u32> = touched.clone().collect();
+    let sides: Vec<u32> = if full.is_empty() {
+        all.clone()
+    } else {
+        (touched.start..full.start)
+            .chain(full.end..touched.end)
+            .collect()
+    };
+    let source = color.0.map(C::scale);
+    let solid = source.map(C::from_f32).repeat(full.len());
+
+    let stride = image_width as usize * 3;
+    let buffer: &mut [C] = imagebuf;
+    for y in rows.start.floor() as u32..rows.end.ceil() as u32 {
+        let row_coverage = coverage(&rows, y);
+        let row = &mut buffer[(y - top) as usize * stride..][..stride];
+        let blended = if row_coverage == 1.0 {
+            let start = full.start as usize * 3;
+            row[start..start + solid.len()].copy_from_slice(&solid);
+            &sides
+        } else {
+            &all
+        };
+        for &x in blended {
+            let alpha = row_coverage * coverage(&columns, x);
+            let at = x as usize * 3;
+            for (channel, source) in row[at..at + 3].iter_mut().zip(source) {
+                let under = if tiles {
+                    channel.to_f32()
+                } else {
+                    channel.to_f32() * (1.0 - alpha)
+                };
+                *channel = C::from_f32((under + source * alpha).min(C::MAX));
+            }
+        }
+    }
+}
+
+/// paints `polygon` over the image, which starts `top` rows down; without `antialias`, exactly
+/// the pixels whose centers are inside it. like [`blend`], `tiles` adds coverage up rather than
+/// laying it over
+fn fill_polygon<C: Channel>(
+    imagebuf: &mut Image<C>,
+    top: u32,
+    polygon: &Polygon,
+    color: Rgb<u8>,
+    antialias: bool,
+    tiles: bool,
+) where
+    Rgb<C>: Pixel<Subpixel = C>,
+{
+    let (image_width, image_height) = imagebuf.dimensions();
+    let Some((columns, rows)) = clip(&polygon.bounds(), top, image_width, image_height) else {
+        return;
+    };
+    // the stretches of a horizontal line at `y` that are inside the polygon, left to right
+    let mut crossings = vec![];
+    let mut spans = |y: f32| {
+        crossings.clear();
+        let n = polygon.points.len();
+        for i in 0..n {
+            let (a, b) = (polygon.points[i], polygon.points[(i + 1) % n]);
+            if (a.y <= y) != (b.y <= y) {
+                crossings.push(a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y));
+            }
+        }
+        crossings.sort_by(f32::total_cmp);
+        crossings
+            .chunks_exact(2)
+            .map(|pair| pair[0].max(columns.start)..pair[1].min(columns.end))
+            .filter(|span| span.start < span.end)
+            .collect::<Vec<_>>()
+    };
+
+    let (first, last) = (columns.start.floor() as u32, columns.end.ceil() as u32);
+    let mut covered = vec![0.0f32; (last - first) as usize];
+    let source = color.0.map(C::scale);
+    let solid = source.map(C::from_f32);
+    let stride = image_width as usize * 3;
+    let buffer: &mut [C] = imagebuf;
+    for y in rows.start.floor() as u32..rows.end.ceil() as u32 {
+        let row = &mut buffer[(y - top) as usize * stride..][..stride];
+        if !antialias {
+            for span in spans(y as f32 + 0.5) {
+                // the pixels whose centers are in [start, end)
+                let start = (span.start - 0.5).ceil().max(0.0) as usize;
+                let end = ((span.end - 0.5).ceil().max(0.0) as usize).min(image_width as usize);
+                for x in start..end.max(start) {
+                    row[x * 3..x * 3 + 3].copy_from_slice(&solid);
+                }
+            }
+            continue;
+        }
+
+        covered.fill(0.0);
+        let weight = 1.0 / POLYGON_SAMPLES as f32;
+        for sample in 0..POLYGON_SAMPLES {
+            for span in spans(y as f32 + (sample as f32 + 0.5) * weight) {
+                for x in span.start.floor() as u32..span.end.ceil() as u32 {
+                    covered[(x - first) as usize] += weight * coverage(&span, x);
+                }
+            }
+        }
+        for (x, alpha) in (first..last).zip(&covered) {
+            let alpha = alpha.min(1.0);
+            if alpha <= 0.0 {
+                continue;
+            }
+            let at = x as usize * 3;
+            for (channel, source) in row[at..at + 3].iter_mut().zip(source) {
+                let under = if tiles {
+                    channel.to_f32()
+                } else {
+                    channel.to_f32() * (1.0 - alpha)
+                };
+                *channel = C::from_f32((under + source * alpha).min(C::MAX));
+            }
+        }
+    }
+}
+
+/// the columns and rows `rectangle` spans inside an image `width` by `height` that starts `top`
+/// rows down, or nothing if it's entirely outside
+fn clip(
+    rectangle: &Rectangle,
+    top: u32,
+    width: u32,
+    height: u32,
+) -> Option<(Range<f32>, Range<f32>)> {
+    let columns = rectangle.x.max(0.0)..(rectangle.x + rectangle.width).min(width as f32);
+    let rows =
+        rectangle.y.max(top as f32)..(rectangle.y + rectangle.height).min((top + height) as f32);
+    (columns.start < columns.end && rows.start < rows.end).then_some((columns, rows))
+}
+
+/// how much of `pixel` (a column or row) lies inside `span`
+fn coverage(span: &Range<f32>, pixel: u32) -> f32 {
+    let (start, end) = (pixel as f32, pixel as f32 + 1.0);
+    (span.end.min(end) - span.start.max(start)).clamp(0.0, 1.0)
+}
@@ -0,0 +1,331 @@
+use std::fmt::Write;
+
+use image::Rgb;
+
+use super::{Part, Renderer};
+use crate::{
+    color::{luma, to_hex},
+    composition::Composition,
+    geometry::Rectangle,
+    polygon::{Point, Polygon},
+    tree::Subtree,
+};
+
+/// labels smaller than this many pixels are left out rather than drawn unreadably
+const MIN_LABEL_SIZE: f32 = 6.0;
+
+/// the SVG backend for [`draw`](super::draw): every shape a `<rect>` or `<polygon>` with its
+/// own fill, except in the `shadow`, `line`, `qr` and `text` groups, which share theirs
+///
+/// every pane carries `data-depth` (how many splits down it is) and `data-seed-path` (the way
+/// down to it from the whole canvas, `0` for each first half and `1` for each second) and a class
+/// for its color: `color-0` and so on for the palette's, `background` for the background's;
+/// diagonal halves, patterns' stripes and squares (`pattern`), shapes and the paint sketchy
+/// panes spill (`spill`) get the color classes too, and the frame and matte are `frame` and
+/// `matte`
+#[derive(Debug, Clone)]
+pub struct Document {
+    width: u32,
+    height: u32,
+    seed: u64,
+    antialias: bool,
+    palette: Vec<Rgb<u8>>,
+    background: Rgb<u8>,
+    /// the colors [`styled`](Self::styled) gives the lines, frame and matte
+    colors: [Rgb<u8>; 3],
+    /// the depth and `data-seed-path` of each pane
+    leaves: Vec<(usize, String)>,
+    labels: Vec<String>,
+    styled: bool,
+    body: String,
+    part: Option<Part>,
+    /// the part whose group is open, and that group's fill, if it has one
+    group: Option<(Part, Option<Rgb<u8>>)>,
+    /// where the text starts in `body`, which the labels go under
+    text: Option<usize>,
+    /// each pane's rectangle and color, for its label
+    panes: Vec<(Rectangle, Rgb<u8>)>,
+}
+
+impl Document {
+    /// an empty document as big as `composition`
+    pub fn new(composition: &Composition) -> Self {
+        let mut leaves = vec![];
+        walk(composition.tree().root(), &mut String::new(), &mut leaves);
+        Self {
+            width: composition.output_width(),
+            height: composition.output_height(),
+            seed: composition.seed(),
+            antialias: composition.antialias(),
+            palette: composition.palette().colors().to_vec(),
+            background: composition.fill_options().background,
+            colors: [
+                composition.line_color(),
+                composition.frame_color(),
+                composition.matte_color(),
+            ],
+            leaves,
+            labels: vec![],
+            styled: false,
+            body: String::new(),
+            part: None,
+            group: None,
+            text: None,
+            panes: vec![],
+        }
+    }
+
+    /// `labels[n]` written in the middle of pane `n`, in black or white, whichever shows up
+    /// better on it; labels that won't fit legibly are left out
+    pub fn labeled(mut self, labels: &[impl AsRef<str>]) -> Self {
+        self.labels = labels
+            .iter()
+            .map(|label| label.as_ref().to_owned())
+            .collect();
+        self
+    }
+
+    /// with a `<style>` block coloring everything by class, for pages to override, if `styled`
+    pub fn styled(mut self, styled: bool) -> Self {
+        self.styled = styled;
+        self
+    }
+
+    /// the class for something of `kind` painted `color`
+    fn class(&self, kind: &str, color: Rgb<u8>) -> String {
+        match self.palette.iter().position(|c| *c == color) {
+            Some(index) => format!("{kind} color-{index}"),
+            None if color == self.background => format!("{kind} background"),
+            None => kind.to_owned(),
+        }
+    }
+
+    /// the fill and class for the shape about to be written, and any more attributes; opens and
+    /// closes groups as the parts change
+    fn attributes(&mut self, color: Rgb<u8>) -> (String, String, String) {
+        let Some(part) = self.part else {
+            return (to_hex(color), String::new(), String::new());
+        };
+        let grouped = matches!(part, Part::Shadow | Part::Line | Part::Qr | Part::Text);
+        if self.group.is_some_and(|(open, _)| open != part) {
+            self.body.push_str("  </g>\n");
+            self.group = None;
+        }
+        if grouped && self.group.is_none() {
+            let fill = matches!(part, Part::Line | Part::Qr).then_some(color);
+            match (part, fill) {
+                (Part::Shadow, _) => self.body.push_str("  <g class=\"shadow\">\n"),
+                (Part::Text, _) => {
+                    self.text.get_or_insert(self.body.len());
+                    self.body.push_str("  <g class=\"text\">\n");
+                }
+                (_, Some(fill)) => writeln!(
+                    self.body,
+                    r#"  <g class="{}" fill="{}">"#,
+                    if part == Part::Line { "line" } else { "qr" },
+                    to_hex(fill)
+                )
+                .unwrap(),
+                _ => {}
+            }
+            self.group = Some((part, fill));
+        }
+        let fill = match self.group {
+            Some((_, Some(fill))) if fill == color => String::new(),
+            _ => to_hex(color),
+        };
+        let (class, data) = match part {
+            Part::Matte => ("matte".to_owned(), String::new()),
+            Part::Pane(n) => {
+                // every pane is a leaf, in the same order
+                let data = match self.leaves.get(n) {
+                    Some((depth, path)) => {
+                        format!(r#" data-depth="{depth}" data-seed-path="{path}""#)
+                    }
+                    None => String::new(),
+                };
+                (self.class("pane", color), data)
+            }
+            Part::Lifted(_) => (self.class("pane", color), String::new()),
+            Part::Background => ("background".to_owned(), String::new()),
+            Part::Diagonal => (self.class("diagonal", color), String::new()),
+            Part::Pattern => (self.class("pattern", color), String::new()),
+            Part::Shape => (self.class("shape", color), String::new()),
+            Part::Block => ("block".to_owned(), String::new()),
+            Part::Spill => (self.class("spill", color), String::new()),
+            Part::Frame => ("frame".to_owned(), String::new()),
+            Part::Shadow | Part::Line | Part::Qr | Part::Text => (String::new(), String::new()),
+        };
+        (fill, class, data)
+    }
+}
+
+impl Renderer for Document {
+    type Output = String;
+
+    fn part(&mut self, part: Part) {
+        self.part = Some(part);
+    }
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, _tiles: bool) {
+        if let Some(Part::Pane(_)) = self.part {
+            self.panes.push((*rectangle, color));
+        }
+        let (fill, class, data) = self.attributes(color);
+        write_rect(&mut self.body, rectangle, &fill, &class, &data);
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, _tiles: bool) {
+        let (fill, class, _) = self.attributes(color);
+        write_polygon(&mut self.body, polygon, &fill, &class);
+    }
+
+    fn finish(mut self) -> String {
+        if self.group.take().is_some() {
+            self.body.push_str("  </g>\n");
+        }
+        let (width, height) = (self.width, self.height);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" fill-rule="evenodd" data-seed="{}"{}>"#,
+            self.seed,
+            if self.antialias {
+                ""
+            } else {
+                r#" shape-rendering="crispEdges""#
+            }
+        )
+        .unwrap();
+        if self.styled {
+            svg.push_str("  <style>\n");
+            for (index, color) in self.palette.iter().enumerate() {
+                writeln!(svg, "    .color-{index} {{ fill: {}; }}", to_hex(*color)).unwrap();
+            }
+            writeln!(
+                svg,
+                "    .background {{ fill: {}; }}",
+                to_hex(self.background)
+            )
+            .unwrap();
+            for (class, color) in ["line", "frame", "matte"].iter().zip(self.colors) {
+                writeln!(svg, "    .{class} {{ fill: {}; }}", to_hex(color)).unwrap();
+            }
+            svg.push_str("  </style>\n");
+        }
+        // the labels go over everything but the text
+        let text = self.text.unwrap_or(self.body.len());
+        svg.push_str(&self.body[..text]);
+        if !self.labels.is_empty() && !self.panes.is_empty() {
+            svg.push_str(
+                r#"  <g font-family="sans-serif" text-anchor="middle" dominant-baseline="central">"#,
+            );
+            svg.push('\n');
+            for ((rectangle, color), label) in self.panes.iter().zip(&self.labels) {
+                write_label(&mut svg, rectangle, *color, label);
+            }
+            svg.push_str("  </g>\n");
+        }
+        svg.push_str(&self.body[text..]);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// the depth and [`Document`]'s `data-seed-path` of every leaf under `node`, in order
+fn walk(node: Subtree<'_, Rectangle>, path: &mut String, leaves: &mut Vec<(usize, String)>) {
+    if node.is_leaf() {
+        leaves.push((node.depth(), path.clone()));
+        return;
+    }
+    for (step, child) in [('0', node.left()), ('1', node.right())] {
+        if let Some(child) = child {
+            path.push(step);
+            walk(child, path, leaves);
+            path.pop();
+        }
+    }
+}
+
+/// like [`write_rect`]
+fn write_polygon(svg: &mut String, polygon: &Polygon, fill: &str, class: &str) {
+    if polygon.points.len() < 3 {
+        return;
+    }
+    svg.push_str(r#"  <polygon points=""#);
+    for (i, Point { x, y }) in polygon.points.iter().enumerate() {
+        if i > 0 {
+            svg.push(' ');
+        }
+        write!(svg, "{x},{y}").unwrap();
+    }
+    svg.push('"');
+    if !fill.is_empty() {
+        write!(svg, r#" fill="{fill}""#).unwrap();
+    }
+    if !class.is_empty() {
+        write!(svg, r#" class="{class}""#).unwrap();
+    }
+    svg.push_str("/>\n");
+}
+
+/// `label` centered in `rectangle`, as big as fits on one line up to half its height
+fn write_label(svg: &mut String, rectangle: &Rectangle, background: Rgb<u8>, label: &str) {
+    let characters = label.chars().count();
+    if characters == 0 {
+        return;
+    }
+    // a sans-serif character is about 0.6em wide on average
+    let size = (rectangle.height * 0.5).min(rectangle.width * 0.9 / (characters as f32 * 0.6));
+    if size < MIN_LABEL_SIZE {
+        return;
+    }
+    let fill = if luma(background) > 140.0 {
+        "#000000"
+    } else {
+        "#ffffff"
+    };
+    let (x, y) = (
+        rectangle.x + rectangle.width / 2.0,
+        rectangle.y + rectangle.height / 2.0,
+    );
+    writeln!(
+        svg,
+        r#"    <text x="{x}" y="{y}" font-size="{size:.1}" fill="{fill}">{}</text>"#,
+        escape(label)
+    )
+    .unwrap();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// an empty `fill` inherits from the enclosing group, and an empty `class` leaves it off; `data`
+/// is any more attributes, with a space before each
+fn write_rect(svg: &mut String, rectangle: &Rectangle, fill: &str, class: &str, data: &str) {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = rectangle;
+    if *width <= 0.0 || *height <= 0.0 {
+        return;
+    }
+    write!(
+        svg,
+        r#"  <rect x="{x}" y="{y}" width="{width}" height="{height}""#
+    )
+    .unwrap();
+    if !fill.is_empty() {
+        write!(svg, r#" fill="{fill}""#).unwrap();
+    }
+    if !class.is_empty() {
+        write!(svg, r#" class="{class}""#).unwrap();
+    }
+    svg.push_str(data);
+    svg.push_str("/>\n");
+}
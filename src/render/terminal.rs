@@ -0,0 +1,113 @@
+//! a quick look at a composition in a truecolor terminal, two pixels to a character cell: a `▀`
+//! with the top one as its color and the bottom one as its background
+
+use std::fmt::Write;
+
+use image::Rgb;
+
+use super::Renderer;
+use crate::{composition::Composition, geometry::Rectangle, polygon::Polygon};
+
+/// a preview for [`draw`](super::draw), each of its pixels the color of whatever was painted
+/// last over its middle. lines and the frame are at least a pixel thick, so they don't fall
+/// between them; polygons get no such help
+#[derive(Debug, Clone)]
+pub struct Preview {
+    width: u32,
+    height: u32,
+    /// how many of the composition's pixels to one of the preview's, each way
+    scale: f32,
+    pixels: Vec<Rgb<u8>>,
+}
+
+impl Preview {
+    /// `columns` characters across, with as many rows as keep the composition's shape
+    pub fn new(composition: &Composition, columns: u32) -> Self {
+        let width = columns.max(1);
+        let scale = composition.output_width() as f32 / width as f32;
+        // an even number, for whole rows of cells
+        let height = ((composition.output_height() as f32 / scale).round() as u32).max(2);
+        let height = height + height % 2;
+        Self {
+            width,
+            height,
+            scale,
+            pixels: vec![Rgb([255, 255, 255]); (width * height) as usize],
+        }
+    }
+
+    /// `rectangle`'s pixels: the ones with their middles in it, or with `thin`, every one it
+    /// touches, so there's at least one
+    fn paint(&mut self, rectangle: &Rectangle, color: Rgb<u8>, thin: bool) {
+        let span = |start: f32, length: f32, end: u32| {
+            let (start, length) = (start / self.scale, length / self.scale);
+            let (first, last) = if thin {
+                (start.floor(), (start + length).ceil())
+            } else {
+                (start.round(), (start + length).round())
+            };
+            first.max(0.0) as u32..(last.max(0.0) as u32).min(end)
+        };
+        let columns = span(rectangle.x, rectangle.width, self.width);
+        let rows = span(rectangle.y, rectangle.height, self.height);
+        for y in rows {
+            for x in columns.clone() {
+                self.pixels[(y * self.width + x) as usize] = color;
+            }
+        }
+    }
+}
+
+impl Renderer for Preview {
+    type Output = String;
+
+    /// panes by their middles; everything else is thin enough to need help showing up
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, tiles: bool) {
+        self.paint(rectangle, color, !tiles);
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, _tiles: bool) {
+        let bounds = polygon.bounds();
+        let range = |start: f32, length: f32, end: u32| {
+            let first = (start / self.scale).floor().max(0.0) as u32;
+            first..(((start + length) / self.scale).ceil().max(0.0) as u32).min(end)
+        };
+        let n = polygon.points.len();
+        for y in range(bounds.y, bounds.height, self.height) {
+            for x in range(bounds.x, bounds.width, self.width) {
+                let (px, py) = ((x as f32 + 0.5) * self.scale, (y as f32 + 0.5) * self.scale);
+                // inside if a ray to the right crosses the outline an odd number of times
+                let crossings = (0..n)
+                    .filter(|&i| {
+                        let (a, b) = (polygon.points[i], polygon.points[(i + 1) % n]);
+                        (a.y <= py) != (b.y <= py)
+                            && px < a.x + (py - a.y) * (b.x - a.x) / (b.y - a.y)
+                    })
+                    .count();
+                if crossings % 2 == 1 {
+                    self.pixels[(y * self.width + x) as usize] = color;
+                }
+            }
+        }
+    }
+
+    /// a row per two of the preview's, each ending in a reset and a newline
+    fn finish(self) -> String {
+        let mut screen = String::new();
+        for y in (0..self.height).step_by(2) {
+            let mut last = None;
+            for x in 0..self.width {
+                let top = self.pixels[(y * self.width + x) as usize];
+                let bottom = self.pixels[((y + 1) * self.width + x) as usize];
+                if last != Some((top, bottom)) {
+                    let (Rgb([r, g, b]), Rgb([br, bg, bb])) = (top, bottom);
+                    write!(screen, "\x1b[38;2;{r};{g};{b};48;2;{br};{bg};{bb}m").unwrap();
+                    last = Some((top, bottom));
+                }
+                screen.push('▀');
+            }
+            screen.push_str("\x1b[0m\n");
+        }
+        screen
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Write;
+
+use image::Rgb;
+
+use super::{Part, Renderer};
+use crate::{
+    color::to_hex,
+    composition::Composition,
+    encode::{description, SOFTWARE},
+    geometry::Rectangle,
+    polygon::Polygon,
+};
+
+/// how wide [`Picture`]s are unless asked otherwise, in centimeters; the width of the text on an
+/// A4 page with the default margins, about
+pub const DEFAULT_WIDTH: f32 = 12.0;
+
+/// the TikZ backend for [`draw`](super::draw): a `tikzpicture` some number of centimeters wide,
+/// for `\input`ting into a LaTeX document or beamer slide; it needs `\usepackage{tikz}`, and its
+/// colors are defined as `mondrian-0`, `mondrian-background`, ...
+#[derive(Debug, Clone)]
+pub struct Picture {
+    tikz: String,
+    colors: Vec<(String, Rgb<u8>)>,
+    /// how many centimeters a pixel is
+    unit: f32,
+    part: Option<Part>,
+}
+
+impl Picture {
+    /// an empty picture of `composition`, `width` centimeters wide
+    pub fn new(composition: &Composition, width: f32) -> Self {
+        let (pixels, rows) = (composition.output_width(), composition.output_height());
+        let unit = width / pixels as f32;
+        let mut tikz = String::new();
+        writeln!(tikz, "% {SOFTWARE}").unwrap();
+        for line in description(&composition.parameters()).lines() {
+            writeln!(tikz, "% {line}").unwrap();
+        }
+        let colors = colors(composition);
+        for (name, color) in &colors {
+            let hex = to_hex(*color);
+            writeln!(
+                tikz,
+                r"\definecolor{{mondrian-{name}}}{{HTML}}{{{}}}",
+                &hex[1..]
+            )
+            .unwrap();
+        }
+        // y goes down the page like it does in the image
+        tikz.push_str("\\begin{tikzpicture}[x=1cm, y=-1cm]\n");
+        // the picture's only as big as what's clipped to
+        if composition.viewport().is_some() {
+            writeln!(
+                tikz,
+                r"  \clip (0,0) rectangle ({},{});",
+                cm(pixels as f32, unit),
+                cm(rows as f32, unit)
+            )
+            .unwrap();
+        }
+        Self {
+            tikz,
+            colors,
+            unit,
+            part: None,
+        }
+    }
+
+    /// the fill for the shape about to be written: palette colors and the background get their
+    /// names, and so do the lines, frame and matte; anything else is spelled out
+    fn fill(&self, color: Rgb<u8>) -> String {
+        let named = match self.part {
+            Some(Part::Line) => Some("line"),
+            Some(Part::Frame) => Some("frame"),
+            Some(Part::Matte) => Some("matte"),
+            _ => self
+                .colors
+                .iter()
+                .take(self.colors.len() - 3)
+                .find(|(_, c)| *c == color)
+                .map(|(name, _)| name.as_str()),
+        };
+        match named {
+            Some(name) => format!("mondrian-{name}"),
+            None => {
+                let Rgb([r, g, b]) = color;
+                format!("fill={{rgb,255:red,{r};green,{g};blue,{b}}}")
+            }
+        }
+    }
+}
+
+impl Renderer for Picture {
+    type Output = String;
+
+    fn part(&mut self, part: Part) {
+        self.part = Some(part);
+    }
+
+    fn fill_rect(&mut self, rectangle: &Rectangle, color: Rgb<u8>, _tiles: bool) {
+        let fill = self.fill(color);
+        write_rect(&mut self.tikz, rectangle, &fill, self.unit);
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon, color: Rgb<u8>, _tiles: bool) {
+        let fill = self.fill(color);
+        write_polygon(&mut self.tikz, polygon, &fill, self.unit);
+    }
+
+    fn finish(mut self) -> String {
+        self.tikz.push_str("\\end{tikzpicture}\n");
+        self.tikz
+    }
+}
+
+/// the palette's colors by index, then the background, then the lines, frame and matte
+fn colors(composition: &Composition) -> Vec<(String, Rgb<u8>)> {
+    let palette = composition.palette().colors().iter().enumerate();
+    palette
+        .map(|(n, color)| (n.to_string(), *color))
+        .chain([
+            (
+                "background".to_owned(),
+                composition.fill_options().background,
+            ),
+            ("line".to_owned(), composition.line_color()),
+            ("frame".to_owned(), composition.frame_color()),
+            ("matte".to_owned(), composition.matte_color()),
+        ])
+        .collect()
+}
+
+/// `rectangle`'s pixels `unit` centimeters each
+fn write_rect(tikz: &mut String, rectangle: &Rectangle, fill: &str, unit: f32) {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = *rectangle;
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    writeln!(
+        tikz,
+        r"  \fill[{fill}] ({},{}) rectangle ({},{});",
+        cm(x, unit),
+        cm(y, unit),
+        cm(x + width, unit),
+        cm(y + height, unit)
+    )
+    .unwrap();
+}
+
+/// like [`write_rect`]
+fn write_polygon(tikz: &mut String, polygon: &Polygon, fill: &str, unit: f32) {
+    if polygon.points.len() < 3 {
+        return;
+    }
+    write!(tikz, r"  \fill[{fill}] ").unwrap();
+    for point in &polygon.points {
+        write!(tikz, "({},{}) -- ", cm(point.x, unit), cm(point.y, unit)).unwrap();
+    }
+    tikz.push_str("cycle;\n");
+}
+
+/// `pixels` in centimeters, to the micrometer, short enough for TeX's arithmetic to keep up
+fn cm(pixels: f32, unit: f32) -> f32 {
+    (pixels as f64 * unit as f64 * 1e4).round() as f32 / 1e4
+}
@@ -0,0 +1,232 @@
+//! palettes that follow the time of day: a [`Schedule`] says which palette to use when, and
+//! [`Schedule::palette_at`] blends from one to the next in between
+//!
+//! the schedule in [`user_file`], if there is one, is lines of a time and a palette's name,
+//! built-in or installed, blank lines and `#` comments aside:
+//!
+//! ```text
+//! 05:00 night
+//! 07:00 warm
+//! 12:00 classic
+//! ```
+//!
+//! and after the last it blends back round into the first
+
+use std::{error::Error, fmt, fs, io, path::PathBuf, process::Command, str::FromStr};
+
+use crate::{
+    color::Palette,
+    palettes::{self, PaletteError},
+};
+
+/// minutes in a day
+const DAY: u32 = 24 * 60;
+
+/// why a schedule couldn't be read, or the time of day found out
+#[derive(Debug)]
+pub enum ScheduleError {
+    Io(io::Error),
+    /// a line that isn't a time and a palette
+    Line {
+        line: usize,
+        why: String,
+    },
+    /// a palette in it that isn't there
+    Palette {
+        line: usize,
+        err: PaletteError,
+    },
+    /// no times in it at all
+    Empty,
+    /// the clock couldn't be read
+    Clock(String),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the schedule: {err}"),
+            Self::Line { line, why } => write!(f, "line {line} of the schedule: {why}"),
+            Self::Palette { line, err } => write!(f, "line {line} of the schedule: {err}"),
+            Self::Empty => write!(f, "the schedule has no times in it"),
+            Self::Clock(why) => write!(f, "couldn't tell what time it is: {why}"),
+        }
+    }
+}
+
+impl Error for ScheduleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Palette { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScheduleError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// palettes at times of day, in minutes after midnight, earliest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    entries: Vec<(u32, Palette)>,
+}
+
+impl Schedule {
+    /// `entries` in any order; the same palette at two times in a row holds it between them.
+    /// times past the end of the day wrap
+    pub fn new(entries: impl Into<Vec<(u32, Palette)>>) -> Self {
+        let mut entries: Vec<(u32, Palette)> = entries
+            .into()
+            .into_iter()
+            .map(|(minute, palette)| (minute % DAY, palette))
+            .collect();
+        entries.sort_by_key(|(minute, _)| *minute);
+        Self { entries }
+    }
+
+    /// the one in [`user_file`], or [`Schedule::default`] if there isn't one
+    pub fn user() -> Result<Self, ScheduleError> {
+        let Some(path) = user_file() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// the palette for `minute` minutes after midnight: the last one scheduled, as far into
+    /// the next as the time is between theirs
+    pub fn palette_at(&self, minute: u32) -> Palette {
+        let minute = minute % DAY;
+        let Some(last) = self.entries.last() else {
+            return Palette::default();
+        };
+        // the entry at or before `minute`, going back round to yesterday's last one if need be
+        let index = self.entries.iter().rposition(|(start, _)| *start <= minute);
+        let (from, to) = match index {
+            Some(index) => (
+                &self.entries[index],
+                &self.entries[(index + 1) % self.entries.len()],
+            ),
+            None => (last, &self.entries[0]),
+        };
+        let since = (minute + DAY - from.0) % DAY;
+        let length = match (to.0 + DAY - from.0) % DAY {
+            0 => DAY,
+            length => length,
+        };
+        from.1.mix(&to.1, since as f32 / length as f32)
+    }
+}
+
+/// the night palette until five, dawn coming up warm by seven, the classic colors from noon
+/// into the afternoon, then warm again for sunset and dark by nine
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new([
+            (5 * 60, Palette::NIGHT),
+            (7 * 60, Palette::WARM),
+            (12 * 60, Palette::CLASSIC),
+            (17 * 60 + 30, Palette::CLASSIC),
+            (19 * 60, Palette::WARM),
+            (21 * 60, Palette::NIGHT),
+        ])
+    }
+}
+
+/// the format in the [module docs](self): `HH:MM name` per line
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut entries = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = || ScheduleError::Line {
+                line: index + 1,
+                why: format!("expected a time and a palette, like `07:00 warm`, got {line:?}"),
+            };
+            let (time, name) = line.split_once(char::is_whitespace).ok_or_else(malformed)?;
+            let minute = parse_time(time).ok_or_else(malformed)?;
+            let palette = palettes::find(name.trim()).map_err(|err| ScheduleError::Palette {
+                line: index + 1,
+                err,
+            })?;
+            entries.push((minute, palette));
+        }
+        if entries.is_empty() {
+            return Err(ScheduleError::Empty);
+        }
+        Ok(Self::new(entries))
+    }
+}
+
+/// `HH:MM`, 24-hour, as minutes after midnight
+pub fn parse_time(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// where the schedule lives: `schedule` in [`palettes::config_dir`]
+pub fn user_file() -> Option<PathBuf> {
+    Some(palettes::config_dir()?.join("schedule"))
+}
+
+/// what the clock on the wall says, in minutes after midnight. there's nothing in std for local
+/// time, so like [`wallpaper`](crate::wallpaper) this asks the platform: `date`, or PowerShell
+/// on Windows
+pub fn local_time() -> Result<u32, ScheduleError> {
+    let text = ask_clock("+%H:%M", "HH:mm")?;
+    parse_time(&text).ok_or_else(|| ScheduleError::Clock(format!("got {:?}", text.trim())))
+}
+
+/// how far ahead of UTC the clock on the wall is, in minutes, asked for like [`local_time`]
+pub fn utc_offset() -> Result<i32, ScheduleError> {
+    let text = ask_clock("+%z", "zzz")?;
+    parse_offset(&text).ok_or_else(|| ScheduleError::Clock(format!("got {:?}", text.trim())))
+}
+
+/// `+0200` from date, or `+02:00` from PowerShell, as minutes
+fn parse_offset(text: &str) -> Option<i32> {
+    let digits = text.trim().replace(':', "");
+    let (sign, digits) = match digits.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    let (hours, minutes) = digits.split_at_checked(2)?;
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+/// what `date` prints in `unix`'s format, or PowerShell's Get-Date in `windows`'s
+fn ask_clock(unix: &str, windows: &str) -> Result<String, ScheduleError> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!("Get-Date -Format {windows}"),
+        ]);
+        command
+    } else {
+        let mut command = Command::new("date");
+        command.arg(unix);
+        command
+    };
+    let output = command
+        .output()
+        .map_err(|err| ScheduleError::Clock(err.to_string()))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,157 @@
+//! how good a [`Composition`] looks going by a few rules of thumb, none of them taste, for keeping
+//! the best of several or comparing strategies over many seeds
+
+use std::fmt;
+
+use crate::{
+    composition::Composition,
+    geometry::{Orientation, Rectangle},
+    polygon::Point,
+};
+
+/// the canvas is cut into this many cells each way to see where the lines went
+const GRID: usize = 4;
+/// how many octaves apart, as a standard deviation, pane areas have to be for full variety
+const OCTAVES: f64 = 1.5;
+
+/// each part from 0 to 1, higher being better, and `total` the mean of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+    /// how close the area each palette color covers is to its share of the palette weights
+    pub balance: f64,
+    /// how much the panes differ in size, big ones among small ones, on a log scale
+    pub variety: f64,
+    /// how evenly the lines are spread over the canvas, rather than bunched in one part of it
+    pub spread: f64,
+    pub total: f64,
+}
+
+impl Score {
+    /// shapes count as panes, and their lines as lines
+    pub fn of(composition: &Composition) -> Self {
+        let balance = balance(composition);
+        let variety = variety(composition);
+        let spread = spread(composition);
+        Self {
+            balance,
+            variety,
+            spread,
+            total: (balance + variety + spread) / 3.0,
+        }
+    }
+}
+
+/// `total 0.812  balance 0.900  variety 0.700  spread 0.836`
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "total {:.3}  balance {:.3}  variety {:.3}  spread {:.3}",
+            self.total, self.balance, self.variety, self.spread
+        )
+    }
+}
+
+/// 1 less how far apart two spreads of shares are, half the sum of the differences
+fn closeness(shares: &[f64], wanted: &[f64]) -> f64 {
+    let apart: f64 = shares.iter().zip(wanted).map(|(a, b)| (a - b).abs()).sum();
+    (1.0 - apart / 2.0).clamp(0.0, 1.0)
+}
+
+fn balance(composition: &Composition) -> f64 {
+    let palette = composition.palette();
+    let stats = composition.stats();
+    let coverage: Vec<f64> = palette
+        .colors()
+        .iter()
+        .map(|color| {
+            stats
+                .colors
+                .iter()
+                .find(|stats| stats.color == *color)
+                .map_or(0.0, |stats| stats.coverage)
+        })
+        .collect();
+    // the background, if it isn't in the palette, is neither here nor there
+    let covered: f64 = coverage.iter().sum();
+    let weights: f64 = palette.weights().iter().map(|&weight| weight as f64).sum();
+    if covered <= 0.0 || weights <= 0.0 {
+        return 0.0;
+    }
+    let shares: Vec<f64> = coverage.iter().map(|coverage| coverage / covered).collect();
+    let wanted: Vec<f64> = palette
+        .weights()
+        .iter()
+        .map(|&weight| weight as f64 / weights)
+        .collect();
+    closeness(&shares, &wanted)
+}
+
+fn variety(composition: &Composition) -> f64 {
+    let areas: Vec<f64> = composition
+        .panes()
+        .iter()
+        .map(|pane| pane.rectangle.area())
+        .chain(
+            composition
+                .shapes()
+                .iter()
+                .map(|shape| shape.polygon.area()),
+        )
+        .filter(|&area| area > 0.0)
+        .collect();
+    // each weighed by its area, so the slivers cut off along the canvas edges hardly count
+    let total: f64 = areas.iter().sum();
+    if areas.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let mean = areas.iter().map(|area| area * area.log2()).sum::<f64>() / total;
+    let variance = areas
+        .iter()
+        .map(|area| area * (area.log2() - mean).powi(2))
+        .sum::<f64>()
+        / total;
+    (variance.sqrt() / OCTAVES).min(1.0)
+}
+
+fn spread(composition: &Composition) -> f64 {
+    let inside = *composition.tree().item();
+    let mut cells = [0.0; GRID * GRID];
+    // a point every so often along each line, counted in the cell it falls in
+    let step = inside.width.max(inside.height) / (GRID * 16) as f32;
+    let mut add = |from: Point, to: Point| {
+        let length = (to.x - from.x).hypot(to.y - from.y);
+        let points = (length / step).ceil().max(1.0) as usize;
+        for i in 0..points {
+            let t = (i as f32 + 0.5) / points as f32;
+            let point = Point::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            if let Some(cell) = cell_of(&inside, point) {
+                cells[cell] += (length / points as f32) as f64;
+            }
+        }
+    };
+    for edge in composition.edges() {
+        let to = match edge.orientation {
+            Orientation::Horizontal => Point::new(edge.x + edge.length, edge.y),
+            Orientation::Vertical => Point::new(edge.x, edge.y + edge.length),
+        };
+        add(Point::new(edge.x, edge.y), to);
+    }
+    for segment in composition.segments() {
+        add(segment.from, segment.to);
+    }
+    let total: f64 = cells.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let shares: Vec<f64> = cells.iter().map(|cell| cell / total).collect();
+    closeness(&shares, &[1.0 / cells.len() as f64; GRID * GRID])
+}
+
+/// which of the [`GRID`] cells over `inside` `point` is in
+fn cell_of(inside: &Rectangle, point: Point) -> Option<usize> {
+    let column = ((point.x - inside.x) / inside.width * GRID as f32).floor();
+    let row = ((point.y - inside.y) / inside.height * GRID as f32).floor();
+    let within = |n: f32| (0.0..GRID as f32).contains(&n);
+    (within(column) && within(row)).then(|| row as usize * GRID + column as usize)
+}
@@ -0,0 +1,1434 @@
+//! split and color rules written by hand, in a little language that looks like rhai: a script is
+//! a list of `fn`s, and the composition calls the ones it knows about
+//!
+//! - `should_split(rect, depth)` returns whether to cut a pane, in place of `--levels`, which
+//!   becomes how deep it can go
+//! - `split_ratio(rect)` returns how far across to cut it, from 0 to 1
+//! - `pick_color(rect, neighbors)` returns an index into `palette`, a `"#rrggbb"` string, or
+//!   `()` for the background; `neighbors` are the colors of the panes beside it that have one
+//!
+//! ```text
+//! fn should_split(rect, depth) {
+//!     depth < 3 || rect.area > 40000 && random() < 0.7
+//! }
+//!
+//! fn pick_color(rect, neighbors) {
+//!     for color in palette {
+//!         if !neighbors.contains(color) { return color; }
+//!     }
+//! }
+//! ```
+//!
+//! `rect`s have `x`, `y`, `width`, `height`, `area` and `aspect` (long side over short), and
+//! every function can see `palette`, `background` and `canvas`. there are numbers, strings,
+//! `true`/`false`, arrays and `()`; `let`, assignment, `if`/`else` (which has a value), `for x in
+//! array`, `return`, `//` comments and the usual operators; `random()` comes from the
+//! composition's seed, and `len`, `contains`, `min`, `max`, `abs`, `floor`, `ceil`, `round`,
+//! `sqrt` and `range(from, to)` can be called either as `f(x, y)` or `x.f(y)`
+//!
+//! needs the `script` feature. scripts can't nest deeper than [`MAX_DEPTH`] or run for more
+//! than [`MAX_STEPS`] in one composition, so one from somewhere else can't hang or crash a build
+
+use std::{cell::Cell, collections::HashMap, error::Error, fmt, fs, path::Path};
+
+use image::Rgb;
+use rand::{Rng, RngCore};
+
+use crate::{
+    color::{to_hex, Palette},
+    composition::Pane,
+    geometry::{Rectangle, SplitOptions},
+    metadata::parse_hex,
+    strategy::SplitStrategy,
+    tree::Tree,
+};
+
+/// how many calls deep functions can go before the script is assumed to be stuck
+const MAX_CALLS: usize = 64;
+
+/// the most a `range` can hold
+const MAX_RANGE: f64 = 65536.0;
+
+/// how deeply expressions and blocks can nest inside one another, and how long a chain of
+/// operators or `.`s can be
+pub const MAX_DEPTH: usize = 64;
+
+/// how many expressions and loop rounds a script gets to run, all its calls in one composition
+/// together, before it's assumed to be stuck
+pub const MAX_STEPS: usize = 4_000_000;
+
+/// where a script went wrong, reading it or running it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+fn failure(line: usize, message: impl Into<String>) -> ScriptError {
+    ScriptError {
+        line,
+        message: message.into(),
+    }
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ScriptError> {
+    Err(failure(line, message))
+}
+
+/// a parsed script, and what its functions see besides their arguments
+#[derive(Debug, Clone)]
+pub struct Script {
+    functions: HashMap<String, Function>,
+    globals: Vec<(String, Value)>,
+    palette: Vec<Rgb<u8>>,
+    /// what's left of [`MAX_STEPS`]
+    steps: Cell<usize>,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Self, ScriptError> {
+        let mut parser = Parser {
+            tokens: lex(source)?,
+            position: 0,
+            depth: 0,
+        };
+        let mut functions = HashMap::new();
+        while parser.peek().is_some() {
+            let line = parser.line();
+            if !parser.eat_name("fn") {
+                return error(line, "only `fn`s can go at the top of a script");
+            }
+            let name = parser.name()?;
+            parser.expect("(")?;
+            let parameters = parser.list(")", Parser::name)?;
+            let body = parser.block()?;
+            let function = Function {
+                parameters,
+                body,
+                line,
+            };
+            if functions.insert(name.clone(), function).is_some() {
+                return error(line, format!("`{name}` is defined twice"));
+            }
+        }
+        Ok(Self {
+            functions,
+            globals: vec![],
+            palette: vec![],
+            steps: Cell::new(MAX_STEPS),
+        })
+    }
+
+    /// reads and parses the script at `path`
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::parse(&fs::read_to_string(path)?)?)
+    }
+
+    /// whether the script has a function called `name`
+    pub fn defines(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// sets what `canvas`, `palette` and `background` are
+    pub fn set_canvas(&mut self, canvas: &Rectangle, palette: &Palette, background: Rgb<u8>) {
+        let hex = |color: &Rgb<u8>| Value::Text(to_hex(*color));
+        self.globals = vec![
+            ("canvas".to_owned(), rect(canvas)),
+            (
+                "palette".to_owned(),
+                Value::Array(palette.colors().iter().map(hex).collect()),
+            ),
+            ("background".to_owned(), hex(&background)),
+        ];
+        self.palette = palette.colors().to_vec();
+    }
+
+    /// calls `name` if the script has it
+    fn call(
+        &self,
+        name: &str,
+        arguments: Vec<Value>,
+        rng: &mut dyn RngCore,
+    ) -> Result<Option<Value>, ScriptError> {
+        if !self.defines(name) {
+            return Ok(None);
+        }
+        let mut run = Run {
+            script: self,
+            rng,
+            calls: 0,
+        };
+        match run.call(name, arguments, self.line(name)) {
+            Ok(value) => Ok(Some(value)),
+            Err(Stop::Error(err)) => Err(err),
+            Err(Stop::Return(_)) => unreachable!("calls catch their returns"),
+        }
+    }
+
+    /// the line `name` was defined on, for errors about what it returned
+    fn line(&self, name: &str) -> usize {
+        self.functions.get(name).map_or(0, |function| function.line)
+    }
+}
+
+/// the tree `should_split` and `split_ratio` cut `bounds` into, no more than `levels` deep; panes
+/// under 2 pixels across are never cut, and whatever one of them leaves out, `strategy` decides
+pub fn layout(
+    script: &Script,
+    bounds: Rectangle,
+    levels: usize,
+    strategy: &dyn SplitStrategy<Rectangle>,
+    options: &SplitOptions,
+    rng: &mut dyn RngCore,
+) -> Result<Tree<Rectangle>, ScriptError> {
+    let mut failed = None;
+    let mut tree = Tree::new(bounds);
+    tree.split_by(|rectangle, depth| {
+        if failed.is_some() || depth >= levels || rectangle.width.max(rectangle.height) < 2.0 {
+            return None;
+        }
+        let cut = |rng: &mut dyn RngCore| {
+            let arguments = vec![rect(rectangle), Value::Number(depth as f64)];
+            match script.call("should_split", arguments, rng)? {
+                None | Some(Value::Bool(true)) => {}
+                Some(Value::Bool(false)) => return Ok(None),
+                Some(other) => {
+                    let message = format!(
+                        "should_split has to return true or false, not {}",
+                        other.kind()
+                    );
+                    return error(script.line("should_split"), message);
+                }
+            }
+            let halves = match script.call("split_ratio", vec![rect(rectangle)], rng)? {
+                None => strategy.split(rectangle, depth, options, rng),
+                Some(Value::Number(t)) if (0.0..=1.0).contains(&t) => {
+                    let horz_split = rectangle.direction(rng);
+                    rectangle.divide_within(horz_split, t as f32, options)
+                }
+                Some(other) => {
+                    let message = format!(
+                        "split_ratio has to return a number from 0 to 1, not {}",
+                        other.describe()
+                    );
+                    return error(script.line("split_ratio"), message);
+                }
+            };
+            Ok(Some(halves))
+        };
+        cut(&mut *rng).unwrap_or_else(|err| {
+            failed = Some(err);
+            None
+        })
+    });
+    match failed {
+        Some(err) => Err(err),
+        None => Ok(tree),
+    }
+}
+
+/// colors `leaves` in order with `pick_color`
+pub fn fill(
+    script: &Script,
+    leaves: Vec<Rectangle>,
+    background: Rgb<u8>,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<Pane>, ScriptError> {
+    let neighbors = neighbors(&leaves);
+    let mut panes: Vec<Pane> = Vec::with_capacity(leaves.len());
+    for (index, rectangle) in leaves.into_iter().enumerate() {
+        // only the ones before it have a color yet
+        let colors = neighbors[index]
+            .iter()
+            .filter(|&&other| other < index)
+            .map(|&other| Value::Text(to_hex(panes[other].color)))
+            .collect();
+        let arguments = vec![rect(&rectangle), Value::Array(colors)];
+        let line = script.line("pick_color");
+        let color = match script.call("pick_color", arguments, rng)? {
+            None | Some(Value::Unit) => background,
+            Some(Value::Number(n)) => match script.palette.get(n as usize) {
+                Some(color) if n.fract() == 0.0 && n >= 0.0 => *color,
+                _ => {
+                    let message = format!(
+                        "pick_color returned {n}, but the palette only goes from 0 to {}",
+                        script.palette.len() - 1
+                    );
+                    return error(line, message);
+                }
+            },
+            Some(Value::Text(text)) => match parse_hex(&text) {
+                Some(color) => color,
+                None => return error(line, format!("pick_color returned {text:?}, not #rrggbb")),
+            },
+            Some(other) => {
+                let message = format!(
+                    "pick_color has to return a palette index, a color or (), not {}",
+                    other.kind()
+                );
+                return error(line, message);
+            }
+        };
+        panes.push(Pane {
+            rectangle,
+            color,
+            diagonal: None,
+            pattern: None,
+        });
+    }
+    Ok(panes)
+}
+
+/// for every rectangle, the others it shares some of a side with
+fn neighbors(rectangles: &[Rectangle]) -> Vec<Vec<usize>> {
+    // the tiles are on the subpixel grid, so sides that meet are exactly equal
+    let mut lefts: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut tops: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (index, r) in rectangles.iter().enumerate() {
+        lefts.entry(r.x.to_bits()).or_default().push(index);
+        tops.entry(r.y.to_bits()).or_default().push(index);
+    }
+    let overlap = |start: f32, length: f32, other: f32, other_length: f32| {
+        start < other + other_length && other < start + length
+    };
+    let mut neighbors = vec![vec![]; rectangles.len()];
+    for (index, r) in rectangles.iter().enumerate() {
+        let right = lefts.get(&(r.x + r.width).to_bits()).into_iter().flatten();
+        let below = tops.get(&(r.y + r.height).to_bits()).into_iter().flatten();
+        for &other in right {
+            let o = &rectangles[other];
+            if overlap(r.y, r.height, o.y, o.height) {
+                neighbors[index].push(other);
+                neighbors[other].push(index);
+            }
+        }
+        for &other in below {
+            let o = &rectangles[other];
+            if overlap(r.x, r.width, o.x, o.width) {
+                neighbors[index].push(other);
+                neighbors[other].push(index);
+            }
+        }
+    }
+    neighbors
+}
+
+/// `rectangle` as scripts see it
+fn rect(rectangle: &Rectangle) -> Value {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = *rectangle;
+    let (long, short) = (width.max(height), width.min(height));
+    let fields = [
+        ("x", x as f64),
+        ("y", y as f64),
+        ("width", width as f64),
+        ("height", height as f64),
+        ("area", rectangle.area()),
+        ("aspect", (long / short.max(f32::MIN_POSITIVE)) as f64),
+    ];
+    Value::Map(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), Value::Number(value)))
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Unit,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Unit => "()",
+            Self::Bool(_) => "a bool",
+            Self::Number(_) => "a number",
+            Self::Text(_) => "a string",
+            Self::Array(_) => "an array",
+            Self::Map(_) => "a map",
+        }
+    }
+
+    /// the value itself if it's short, otherwise what kind it is
+    fn describe(&self) -> String {
+        match self {
+            Self::Number(_) | Self::Bool(_) | Self::Unit => self.to_string(),
+            _ => self.kind().to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Text(value) => write!(f, "{value}"),
+            Self::Array(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Self::Map(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}"))
+                    .collect();
+                write!(f, "#{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    parameters: Vec<String>,
+    body: Block,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    statements: Vec<Statement>,
+    /// the last expression, if it had no `;` after it
+    value: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Let(String, Expr),
+    Assign(String, Expr, usize),
+    Return(Option<Expr>),
+    For(String, Expr, Block),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+struct Expr {
+    kind: ExprKind,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+enum ExprKind {
+    Literal(Value),
+    Variable(String),
+    Array(Vec<Expr>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    /// `x.f(y)` is `f(x, y)`
+    Call(String, Vec<Expr>),
+    If(Box<Expr>, Block, Option<Box<Expr>>),
+    Block(Block),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Text(value) => write!(f, "{value:?}"),
+            Self::Name(name) => write!(f, "`{name}`"),
+            Self::Symbol(symbol) => write!(f, "`{symbol}`"),
+        }
+    }
+}
+
+/// longest first, so `<=` isn't read as `<` and `=`
+const SYMBOLS: [&str; 24] = [
+    "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", "[", "]", ",", ";", ".", "=", "<", ">",
+    "+", "-", "*", "/", "%", "!",
+];
+
+fn lex(source: &str) -> Result<Vec<(Token, usize)>, ScriptError> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line += 1;
+        }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            // `1.max(2)` is a method call on 1
+            let end = match rest[..end].find('.') {
+                Some(dot) if !rest[dot + 1..].starts_with(|c: char| c.is_ascii_digit()) => dot,
+                _ => end,
+            };
+            let number = rest[..end].replace('_', "");
+            match number.parse() {
+                Ok(value) => tokens.push((Token::Number(value), line)),
+                Err(_) => return error(line, format!("{:?} isn't a number", &rest[..end])),
+            }
+            rest = &rest[end..];
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push((Token::Name(rest[..end].to_owned()), line));
+            rest = &rest[end..];
+            continue;
+        }
+        if c == '"' {
+            let mut text = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    None | Some((_, '\n')) => return error(line, "this string never ends"),
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => text.push('\n'),
+                        Some((_, 't')) => text.push('\t'),
+                        Some((_, c @ ('"' | '\\'))) => text.push(c),
+                        _ => return error(line, "strings only have \\n, \\t, \\\" and \\\\"),
+                    },
+                    Some((_, c)) => text.push(c),
+                }
+            };
+            tokens.push((Token::Text(text), line));
+            rest = &rest[end..];
+            continue;
+        }
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            Some(symbol) => {
+                tokens.push((Token::Symbol(symbol), line));
+                rest = &rest[symbol.len()..];
+            }
+            None => return error(line, format!("didn't expect {c:?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// how far into [`MAX_DEPTH`] the expression or block being read is
+    depth: usize,
+}
+
+impl Parser {
+    /// one level further in, or an error if that's deeper than [`MAX_DEPTH`]; an error ends the
+    /// parse, so only the levels that went well are left again, with [`Parser::leave`]
+    fn enter(&mut self) -> Result<(), ScriptError> {
+        if self.depth >= MAX_DEPTH {
+            return error(self.line(), "nested too deeply");
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self, levels: usize) {
+        self.depth -= levels;
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        let last = self.tokens.last().map_or(1, |(_, line)| *line);
+        self.tokens
+            .get(self.position)
+            .map_or(last, |(_, line)| *line)
+    }
+
+    fn next(&mut self) -> Result<Token, ScriptError> {
+        match self.tokens.get(self.position) {
+            Some((token, _)) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => error(self.line(), "the script ends too soon"),
+        }
+    }
+
+    fn is(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let is = self.is(symbol);
+        if is {
+            self.position += 1;
+        }
+        is
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let is = matches!(self.peek(), Some(Token::Name(n)) if n == name);
+        if is {
+            self.position += 1;
+        }
+        is
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ScriptError> {
+        // a missing `;` belongs to the line before whatever comes next
+        let line = match (symbol, self.position.checked_sub(1)) {
+            (";", Some(last)) => self.tokens[last].1,
+            _ => self.line(),
+        };
+        match self.next()? {
+            Token::Symbol(s) if s == symbol => Ok(()),
+            other => error(line, format!("expected `{symbol}`, not {other}")),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ScriptError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Name(name) if !is_keyword(&name) => Ok(name),
+            other => error(line, format!("expected a name, not {other}")),
+        }
+    }
+
+    /// `item`s separated by commas, up to and including `end`
+    fn list<T>(
+        &mut self,
+        end: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T, ScriptError>,
+    ) -> Result<Vec<T>, ScriptError> {
+        let mut items = vec![];
+        while !self.eat(end) {
+            items.push(item(self)?);
+            if !self.eat(",") {
+                self.expect(end)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn block(&mut self) -> Result<Block, ScriptError> {
+        self.enter()?;
+        let block = self.statements()?;
+        self.leave(1);
+        Ok(block)
+    }
+
+    fn statements(&mut self) -> Result<Block, ScriptError> {
+        self.expect("{")?;
+        let mut statements = vec![];
+        loop {
+            if self.eat("}") {
+                return Ok(Block {
+                    statements,
+                    value: None,
+                });
+            }
+            let line = self.line();
+            if self.eat_name("let") {
+                let name = self.name()?;
+                self.expect("=")?;
+                statements.push(Statement::Let(name, self.expr()?));
+                self.expect(";")?;
+                continue;
+            }
+            if self.eat_name("return") {
+                let value = match self.is(";") {
+                    true => None,
+                    false => Some(self.expr()?),
+                };
+                statements.push(Statement::Return(value));
+                self.expect(";")?;
+                continue;
+            }
+            if self.eat_name("for") {
+                let name = self.name()?;
+                if !self.eat_name("in") {
+                    return error(line, "expected `in` after the name in a `for`");
+                }
+                let array = self.expr()?;
+                statements.push(Statement::For(name, array, self.block()?));
+                continue;
+            }
+            let assignment = matches!(
+                (self.peek(), self.tokens.get(self.position + 1)),
+                (Some(Token::Name(_)), Some((Token::Symbol("="), _)))
+            );
+            if assignment {
+                let name = self.name()?;
+                self.expect("=")?;
+                statements.push(Statement::Assign(name, self.expr()?, line));
+                self.expect(";")?;
+                continue;
+            }
+            let expr = self.expr()?;
+            if self.eat(";") {
+                statements.push(Statement::Expr(expr));
+            } else if self.eat("}") {
+                return Ok(Block {
+                    statements,
+                    value: Some(Box::new(expr)),
+                });
+            } else if matches!(expr.kind, ExprKind::If(..) | ExprKind::Block(_)) {
+                statements.push(Statement::Expr(expr));
+            } else {
+                // on the line the expression ended on, not wherever the next thing is
+                let line = self.tokens[self.position - 1].1;
+                let found = self.next()?;
+                return error(line, format!("expected `;`, not {found}"));
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ScriptError> {
+        self.binary(0)
+    }
+
+    /// operators at `level` of [`PRECEDENCE`] and tighter
+    fn binary(&mut self, level: usize) -> Result<Expr, ScriptError> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        // each operator puts everything before it one level further down
+        let mut levels = 0;
+        while let Some(&operator) = operators.iter().find(|operator| self.is(operator)) {
+            let line = self.line();
+            self.position += 1;
+            self.enter()?;
+            levels += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr {
+                kind: ExprKind::Binary(operator, Box::new(left), Box::new(right)),
+                line,
+            };
+        }
+        self.leave(levels);
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        self.enter()?;
+        let expr = self.operand()?;
+        self.leave(1);
+        Ok(expr)
+    }
+
+    fn operand(&mut self) -> Result<Expr, ScriptError> {
+        let line = self.line();
+        let kind = if self.eat("!") {
+            ExprKind::Not(Box::new(self.unary()?))
+        } else if self.eat("-") {
+            ExprKind::Negate(Box::new(self.unary()?))
+        } else {
+            return self.postfix();
+        };
+        Ok(Expr { kind, line })
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ScriptError> {
+        let mut expr = self.primary()?;
+        // like operators, each `.` or `[` puts what's before it a level down
+        let mut levels = 0;
+        loop {
+            let line = self.line();
+            if self.is(".") || self.is("[") {
+                self.enter()?;
+                levels += 1;
+            }
+            let kind = if self.eat(".") {
+                let name = self.name()?;
+                if self.eat("(") {
+                    let mut arguments = vec![expr];
+                    arguments.extend(self.list(")", Self::expr)?);
+                    ExprKind::Call(name, arguments)
+                } else {
+                    ExprKind::Field(Box::new(expr), name)
+                }
+            } else if self.eat("[") {
+                let index = self.expr()?;
+                self.expect("]")?;
+                ExprKind::Index(Box::new(expr), Box::new(index))
+            } else {
+                self.leave(levels);
+                return Ok(expr);
+            };
+            expr = Expr { kind, line };
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ScriptError> {
+        let line = self.line();
+        if self.is("{") {
+            let block = self.block()?;
+            return Ok(Expr {
+                kind: ExprKind::Block(block),
+                line,
+            });
+        }
+        let kind = match self.next()? {
+            Token::Number(value) => ExprKind::Literal(Value::Number(value)),
+            Token::Text(text) => ExprKind::Literal(Value::Text(text)),
+            Token::Symbol("(") if self.eat(")") => ExprKind::Literal(Value::Unit),
+            Token::Symbol("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                return Ok(expr);
+            }
+            Token::Symbol("[") => ExprKind::Array(self.list("]", Self::expr)?),
+            Token::Name(name) => match name.as_str() {
+                "true" => ExprKind::Literal(Value::Bool(true)),
+                "false" => ExprKind::Literal(Value::Bool(false)),
+                "if" => {
+                    let condition = self.expr()?;
+                    let then = self.block()?;
+                    let otherwise = if !self.eat_name("else") {
+                        None
+                    } else if matches!(self.peek(), Some(Token::Name(n)) if n == "if") {
+                        Some(Box::new(self.primary()?))
+                    } else {
+                        let line = self.line();
+                        let block = self.block()?;
+                        Some(Box::new(Expr {
+                            kind: ExprKind::Block(block),
+                            line,
+                        }))
+                    };
+                    ExprKind::If(Box::new(condition), then, otherwise)
+                }
+                _ if is_keyword(&name) => {
+                    return error(line, format!("`{name}` can't go here"));
+                }
+                _ if self.eat("(") => ExprKind::Call(name, self.list(")", Self::expr)?),
+                _ => ExprKind::Variable(name),
+            },
+            other => return error(line, format!("didn't expect {other}")),
+        };
+        Ok(Expr { kind, line })
+    }
+}
+
+/// loosest first
+const PRECEDENCE: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "fn" | "let" | "if" | "else" | "for" | "in" | "return" | "true" | "false"
+    )
+}
+
+/// why evaluating stopped early
+enum Stop {
+    Return(Value),
+    Error(ScriptError),
+}
+
+impl From<ScriptError> for Stop {
+    fn from(err: ScriptError) -> Self {
+        Self::Error(err)
+    }
+}
+
+/// one call into the script from outside, and everything it calls in turn
+struct Run<'a> {
+    script: &'a Script,
+    rng: &'a mut dyn RngCore,
+    calls: usize,
+}
+
+/// the variables in scope, innermost last
+type Scope = Vec<(String, Value)>;
+
+impl Run<'_> {
+    /// takes one of the script's [`MAX_STEPS`]
+    fn step(&self, line: usize) -> Result<(), Stop> {
+        let steps = self.script.steps.get();
+        if steps == 0 {
+            let message = "ran too long; is a loop going on forever?";
+            return Err(failure(line, message).into());
+        }
+        self.script.steps.set(steps - 1);
+        Ok(())
+    }
+
+    fn call(&mut self, name: &str, arguments: Vec<Value>, line: usize) -> Result<Value, Stop> {
+        let Some(function) = self.script.functions.get(name) else {
+            return Ok(builtin(name, arguments, line, self.rng)?);
+        };
+        if arguments.len() != function.parameters.len() {
+            let count = function.parameters.len();
+            let message = format!(
+                "`{name}` takes {count} argument{}, not {}",
+                if count == 1 { "" } else { "s" },
+                arguments.len()
+            );
+            return Err(failure(line, message).into());
+        }
+        if self.calls >= MAX_CALLS {
+            let message = "too many calls deep; is something recursing forever?";
+            return Err(failure(line, message).into());
+        }
+        self.calls += 1;
+        let mut scope: Scope = function.parameters.iter().cloned().zip(arguments).collect();
+        let result = self.block(&function.body, &mut scope);
+        self.calls -= 1;
+        match result {
+            Err(Stop::Return(value)) => Ok(value),
+            other => other,
+        }
+    }
+
+    fn block(&mut self, block: &Block, scope: &mut Scope) -> Result<Value, Stop> {
+        let depth = scope.len();
+        let result = self.statements(block, scope);
+        scope.truncate(depth);
+        result
+    }
+
+    fn statements(&mut self, block: &Block, scope: &mut Scope) -> Result<Value, Stop> {
+        for statement in &block.statements {
+            match statement {
+                Statement::Let(name, expr) => {
+                    let value = self.eval(expr, scope)?;
+                    scope.push((name.clone(), value));
+                }
+                Statement::Assign(name, expr, line) => {
+                    let value = self.eval(expr, scope)?;
+                    match scope.iter_mut().rev().find(|(n, _)| n == name) {
+                        Some((_, slot)) => *slot = value,
+                        None => {
+                            let message = format!("no variable `{name}`; make it with `let` first");
+                            return Err(failure(*line, message).into());
+                        }
+                    }
+                }
+                Statement::Return(expr) => {
+                    let value = match expr {
+                        Some(expr) => self.eval(expr, scope)?,
+                        None => Value::Unit,
+                    };
+                    return Err(Stop::Return(value));
+                }
+                Statement::For(name, array, body) => {
+                    let values = match self.eval(array, scope)? {
+                        Value::Array(values) => values,
+                        other => {
+                            let message = format!("`for` goes over arrays, not {}", other.kind());
+                            return Err(failure(array.line, message).into());
+                        }
+                    };
+                    for value in values {
+                        self.step(array.line)?;
+                        scope.push((name.clone(), value));
+                        let result = self.block(body, scope);
+                        scope.pop();
+                        result?;
+                    }
+                }
+                Statement::Expr(expr) => {
+                    self.eval(expr, scope)?;
+                }
+            }
+        }
+        match &block.value {
+            Some(expr) => self.eval(expr, scope),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, scope: &mut Scope) -> Result<Value, Stop> {
+        let line = expr.line;
+        self.step(line)?;
+        let fail = |message: String| Err(Stop::Error(failure(line, message)));
+        match &expr.kind {
+            ExprKind::Literal(value) => Ok(value.clone()),
+            ExprKind::Variable(name) => {
+                let local = scope.iter().rev().find(|(n, _)| n == name);
+                match local.or_else(|| self.script.globals.iter().find(|(n, _)| n == name)) {
+                    Some((_, value)) => Ok(value.clone()),
+                    None => fail(format!("no variable `{name}`")),
+                }
+            }
+            ExprKind::Array(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.eval(item, scope)?);
+                }
+                Ok(Value::Array(values))
+            }
+            ExprKind::Not(operand) => match self.eval(operand, scope)? {
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                other => fail(format!("can't `!` {}", other.kind())),
+            },
+            ExprKind::Negate(operand) => match self.eval(operand, scope)? {
+                Value::Number(value) => Ok(Value::Number(-value)),
+                other => fail(format!("can't negate {}", other.kind())),
+            },
+            ExprKind::Binary(operator @ ("&&" | "||"), left, right) => {
+                let mut truth = |expr: &Expr, scope: &mut Scope| match self.eval(expr, scope)? {
+                    Value::Bool(value) => Ok(value),
+                    other => {
+                        let message = format!("`{operator}` needs bools, not {}", other.kind());
+                        Err(Stop::Error(failure(line, message)))
+                    }
+                };
+                // the right side only runs if it matters, so it can rely on the left
+                let left = truth(left, scope)?;
+                Ok(Value::Bool(match (*operator, left) {
+                    ("&&", false) => false,
+                    ("||", true) => true,
+                    _ => truth(right, scope)?,
+                }))
+            }
+            ExprKind::Binary(operator, left, right) => {
+                let left = self.eval(left, scope)?;
+                let right = self.eval(right, scope)?;
+                Ok(binary(operator, left, right, line)?)
+            }
+            ExprKind::Field(target, name) => match self.eval(target, scope)? {
+                Value::Map(fields) => match fields.into_iter().find(|(n, _)| n == name) {
+                    Some((_, value)) => Ok(value),
+                    None => fail(format!("no field `{name}`")),
+                },
+                other => fail(format!("{} has no fields", other.kind())),
+            },
+            ExprKind::Index(target, index) => {
+                let target = self.eval(target, scope)?;
+                let index = self.eval(index, scope)?;
+                match (target, index) {
+                    (Value::Array(values), Value::Number(n)) => {
+                        // negative indices count from the end
+                        let i = if n < 0.0 { values.len() as f64 + n } else { n };
+                        match values.get(i as usize) {
+                            Some(value) if i >= 0.0 && i.fract() == 0.0 => Ok(value.clone()),
+                            _ => fail(format!("there's no [{n}] in an array of {}", values.len())),
+                        }
+                    }
+                    (target, index) => fail(format!(
+                        "can't index {} with {}",
+                        target.kind(),
+                        index.kind()
+                    )),
+                }
+            }
+            ExprKind::Call(name, arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.eval(argument, scope)?);
+                }
+                self.call(name, values, line)
+            }
+            ExprKind::If(condition, then, otherwise) => match self.eval(condition, scope)? {
+                Value::Bool(true) => self.block(then, scope),
+                Value::Bool(false) => match otherwise {
+                    Some(otherwise) => self.eval(otherwise, scope),
+                    None => Ok(Value::Unit),
+                },
+                other => fail(format!("`if` needs a bool, not {}", other.kind())),
+            },
+            ExprKind::Block(block) => self.block(block, scope),
+        }
+    }
+}
+
+fn binary(operator: &str, left: Value, right: Value, line: usize) -> Result<Value, ScriptError> {
+    use Value::{Array, Bool, Number, Text};
+    Ok(match (operator, left, right) {
+        ("==", left, right) => Bool(left == right),
+        ("!=", left, right) => Bool(left != right),
+        ("+", Number(a), Number(b)) => Number(a + b),
+        ("+", Text(a), b) => Text(a + &b.to_string()),
+        ("+", a @ Number(_), Text(b)) => Text(a.to_string() + &b),
+        ("+", Array(mut a), Array(b)) => {
+            a.extend(b);
+            Array(a)
+        }
+        ("-", Number(a), Number(b)) => Number(a - b),
+        ("*", Number(a), Number(b)) => Number(a * b),
+        ("/", Number(a), Number(b)) => Number(a / b),
+        ("%", Number(a), Number(b)) => Number(a.rem_euclid(b)),
+        ("<", Number(a), Number(b)) => Bool(a < b),
+        ("<=", Number(a), Number(b)) => Bool(a <= b),
+        (">", Number(a), Number(b)) => Bool(a > b),
+        (">=", Number(a), Number(b)) => Bool(a >= b),
+        ("<", Text(a), Text(b)) => Bool(a < b),
+        ("<=", Text(a), Text(b)) => Bool(a <= b),
+        (">", Text(a), Text(b)) => Bool(a > b),
+        (">=", Text(a), Text(b)) => Bool(a >= b),
+        (operator, left, right) => {
+            let message = format!("can't {} `{operator}` {}", left.kind(), right.kind());
+            return error(line, message);
+        }
+    })
+}
+
+/// the functions every script has
+fn builtin(
+    name: &str,
+    arguments: Vec<Value>,
+    line: usize,
+    rng: &mut dyn RngCore,
+) -> Result<Value, ScriptError> {
+    use Value::{Array, Bool, Number, Text};
+    let math = |f: fn(f64) -> f64| match arguments[..] {
+        [Number(n)] => Ok(Number(f(n))),
+        _ => error(line, format!("`{name}` takes a number")),
+    };
+    match name {
+        "random" if arguments.is_empty() => Ok(Number(rng.gen())),
+        "random" => error(line, "`random` takes nothing"),
+        "abs" => math(f64::abs),
+        "floor" => math(f64::floor),
+        "ceil" => math(f64::ceil),
+        "round" => math(f64::round),
+        "sqrt" => math(f64::sqrt),
+        "min" | "max" => match arguments[..] {
+            [Number(a), Number(b)] => Ok(Number(if name == "min" { a.min(b) } else { a.max(b) })),
+            _ => error(line, format!("`{name}` takes two numbers")),
+        },
+        "len" => match &arguments[..] {
+            [Array(values)] => Ok(Number(values.len() as f64)),
+            [Text(text)] => Ok(Number(text.chars().count() as f64)),
+            _ => error(line, "`len` takes an array or a string"),
+        },
+        "contains" => match &arguments[..] {
+            [Array(values), value] => Ok(Bool(values.contains(value))),
+            [Text(text), Text(part)] => Ok(Bool(text.contains(part.as_str()))),
+            _ => error(
+                line,
+                "`contains` takes an array and a value, or two strings",
+            ),
+        },
+        "range" => match arguments[..] {
+            [Number(from), Number(to)] if to - from <= MAX_RANGE => {
+                let (from, to) = (from.ceil() as i64, to.ceil() as i64);
+                Ok(Array((from..to).map(|n| Number(n as f64)).collect()))
+            }
+            [Number(_), Number(_)] => error(line, format!("ranges go up to {MAX_RANGE} long")),
+            _ => error(line, "`range` takes two numbers"),
+        },
+        _ => error(line, format!("there's no function `{name}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{geometry::SplittableGraphic, strategy::Thirds};
+
+    fn run(source: &str) -> Result<Value, ScriptError> {
+        let script = Script::parse(source)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        Ok(script
+            .call("main", vec![], &mut rng)?
+            .expect("there's a main"))
+    }
+
+    fn fails(source: &str) -> ScriptError {
+        run(source).expect_err(source)
+    }
+
+    #[test]
+    fn evaluates() {
+        use Value::{Array, Bool, Number, Text, Unit};
+        let cases = [
+            ("1 + 2 * 3 - 4 / 2", Number(5.0)),
+            ("(1 + 2) * 3", Number(9.0)),
+            ("-7 % 3", Number(2.0)),
+            ("!(1 < 2) || 2 >= 2 && \"a\" < \"b\"", Bool(true)),
+            ("\"n = \" + 1.5", Text("n = 1.5".to_owned())),
+            (
+                "[1, 2] + [3]",
+                Array(vec![Number(1.0), Number(2.0), Number(3.0)]),
+            ),
+            ("[10, 20, 30][-1]", Number(30.0)),
+            ("if 1 > 2 { 1 } else if 2 > 1 { 2 } else { 3 }", Number(2.0)),
+            ("if false { 1 }", Unit),
+            ("{ let x = 2; x * x }", Number(4.0)),
+            ("(3.7).floor().max(2) + (-2).abs()", Number(5.0)),
+            ("range(2, 5).len() + len(\"héllo\")", Number(8.0)),
+            (
+                "[1, \"a\"].contains(\"a\") && !\"abc\".contains(\"d\")",
+                Bool(true),
+            ),
+            ("()", Unit),
+        ];
+        for (expr, value) in cases {
+            assert_eq!(run(&format!("fn main() {{ {expr} }}")), Ok(value), "{expr}");
+        }
+    }
+
+    #[test]
+    fn runs_statements_and_functions() {
+        let source = "
+            // sums the squares below n, stopping early at 10
+            fn squares(n) {
+                let total = 0;
+                for i in range(0, n) {
+                    if i == 10 { return total; }
+                    total = total + square(i);
+                }
+                total
+            }
+            fn square(x) { x * x }
+            fn main() { [squares(4), squares(100), squares(0)] }
+        ";
+        let got = run(source);
+        let want = Value::Array(
+            vec![14.0, 285.0, 0.0]
+                .into_iter()
+                .map(Value::Number)
+                .collect(),
+        );
+        assert_eq!(got, Ok(want));
+
+        let script = Script::parse(source).unwrap();
+        assert!(script.defines("square") && !script.defines("pick_color"));
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(script.call("pick_color", vec![], &mut rng), Ok(None));
+    }
+
+    #[test]
+    fn sees_the_canvas() {
+        let mut script = Script::parse(
+            "fn main() { [canvas.width, canvas.aspect, palette[1], background, palette.len()] }",
+        )
+        .unwrap();
+        let canvas = Rectangle::new(0.0, 0.0, 300.0, 200.0);
+        script.set_canvas(&canvas, &Palette::CLASSIC, Rgb([0, 0, 0]));
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let got = script.call("main", vec![], &mut rng).unwrap().unwrap();
+        assert_eq!(got.to_string(), "[300, 1.5, #ff0000, #000000, 4]");
+    }
+
+    #[test]
+    fn malformed_scripts_are_refused() {
+        let deep = format!("fn main() {{ {}1{} }}", "(".repeat(100), ")".repeat(100));
+        let long = format!("fn main() {{ 1{} }}", " + 1".repeat(100));
+        let cases = [
+            ("let x = 1;", 1, "only `fn`s can go at the top of a script"),
+            ("fn f() {}\nfn f() {}", 2, "`f` is defined twice"),
+            ("fn main() {\n\"never", 2, "this string never ends"),
+            ("fn main() { 3.7.floor() }", 1, "\"3.7.\" isn't a number"),
+            ("fn main() { 1 $ 2 }", 1, "didn't expect '$'"),
+            ("fn main() { let x = 1 }", 1, "expected `;`, not `}`"),
+            ("fn main() {\n1 2 }", 2, "expected `;`, not 2"),
+            ("fn main() { 1 +", 1, "the script ends too soon"),
+            ("fn main() { let if = 1; }", 1, "expected a name, not `if`"),
+            ("fn main() { else }", 1, "`else` can't go here"),
+            (
+                "fn main() { for x [1] {} }",
+                1,
+                "expected `in` after the name in a `for`",
+            ),
+            (&deep, 1, "nested too deeply"),
+            (&long, 1, "nested too deeply"),
+        ];
+        for (source, line, message) in cases {
+            let err = Script::parse(source).expect_err(source);
+            assert_eq!(
+                (err.line, err.message.as_str()),
+                (line, message),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn runtime_errors_say_where() {
+        let cases = [
+            ("fn main() {\nnope()\n}", 2, "there's no function `nope`"),
+            ("fn main() { x }", 1, "no variable `x`"),
+            (
+                "fn main() { x = 1; }",
+                1,
+                "no variable `x`; make it with `let` first",
+            ),
+            (
+                "fn f(a) { a }\nfn main() { f(1, 2) }",
+                2,
+                "`f` takes 1 argument, not 2",
+            ),
+            (
+                "fn main() { main() }",
+                1,
+                "too many calls deep; is something recursing forever?",
+            ),
+            ("fn main() { 1 + true }", 1, "can't a number `+` a bool"),
+            ("fn main() { [1][1] }", 1, "there's no [1] in an array of 1"),
+            (
+                "fn main() { if 1 { 2 } }",
+                1,
+                "`if` needs a bool, not a number",
+            ),
+            (
+                "fn main() { for x in 3 {} }",
+                1,
+                "`for` goes over arrays, not a number",
+            ),
+            (
+                "fn main() { range(0, 100000) }",
+                1,
+                "ranges go up to 65536 long",
+            ),
+            ("fn main() { sqrt(\"4\") }", 1, "`sqrt` takes a number"),
+            ("fn main() { (1).width }", 1, "a number has no fields"),
+        ];
+        for (source, line, message) in cases {
+            let err = fails(source);
+            assert_eq!(
+                (err.line, err.message.as_str()),
+                (line, message),
+                "{source}"
+            );
+        }
+
+        // every round of every loop counts, however the loops are nested
+        let forever = "fn main() {\nfor i in range(0, 60000) { for j in range(0, 60000) {} }\n}";
+        assert_eq!(
+            fails(forever),
+            failure(2, "ran too long; is a loop going on forever?")
+        );
+    }
+
+    #[test]
+    fn random_follows_the_seed() {
+        let source = "fn main() { [random(), random()] }";
+        assert_eq!(run(source), run(source));
+        let Ok(Value::Array(values)) = run(source) else {
+            panic!("random() returns numbers");
+        };
+        assert_ne!(values[0], values[1]);
+        assert!(values
+            .iter()
+            .all(|value| matches!(value, Value::Number(n) if (0.0..1.0).contains(n))));
+    }
+
+    #[test]
+    fn lays_out_and_fills() {
+        let source = "
+            fn should_split(rect, depth) { rect.width > 100 }
+            fn split_ratio(rect) { 0.5 }
+            fn pick_color(rect, neighbors) {
+                if neighbors.contains(palette[1]) { 2 } else { palette[1] }
+            }
+        ";
+        let mut script = Script::parse(source).unwrap();
+        let bounds = Rectangle::new(0.0, 0.0, 400.0, 100.0);
+        script.set_canvas(&bounds, &Palette::CLASSIC, Rgb([9, 9, 9]));
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let options = SplitOptions::default();
+        let tree = layout(&script, bounds, 10, &Thirds, &options, &mut rng).unwrap();
+        let leaves: Vec<Rectangle> = tree.leaves().copied().collect();
+        assert!(leaves.iter().all(|leaf| leaf.width <= 100.0));
+        let area: f64 = leaves.iter().map(Rectangle::area).sum();
+        assert_eq!(area, 40000.0);
+
+        let panes = fill(&script, leaves, Rgb([9, 9, 9]), &mut rng).unwrap();
+        let red = Rgb([255, 0, 0]);
+        let yellow = Rgb([255, 255, 0]);
+        assert_eq!(panes[0].color, red);
+        assert!(panes
+            .iter()
+            .all(|pane| pane.color == red || pane.color == yellow));
+    }
+
+    #[test]
+    fn bad_answers_are_refused() {
+        let bounds = Rectangle::new(0.0, 0.0, 100.0, 100.0);
+        let options = SplitOptions::default();
+        let outcome = |source: &str| {
+            let mut script = Script::parse(source).unwrap();
+            script.set_canvas(&bounds, &Palette::CLASSIC, Rgb([0, 0, 0]));
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            let tree = layout(&script, bounds, 2, &Thirds, &options, &mut rng)?;
+            fill(
+                &script,
+                tree.leaves().copied().collect(),
+                Rgb([0, 0, 0]),
+                &mut rng,
+            )
+        };
+        let cases = [
+            (
+                "fn should_split(rect, depth) { 1 }",
+                "should_split has to return true or false, not a number",
+            ),
+            (
+                "fn split_ratio(rect) { 2 }",
+                "split_ratio has to return a number from 0 to 1, not 2",
+            ),
+            (
+                "fn split_ratio(rect) { \"half\" }",
+                "split_ratio has to return a number from 0 to 1, not a string",
+            ),
+            (
+                "fn pick_color(rect, neighbors) { 4 }",
+                "pick_color returned 4, but the palette only goes from 0 to 3",
+            ),
+            (
+                "fn pick_color(rect, neighbors) { 0.5 }",
+                "pick_color returned 0.5, but the palette only goes from 0 to 3",
+            ),
+            (
+                "fn pick_color(rect, neighbors) { \"red\" }",
+                "pick_color returned \"red\", not #rrggbb",
+            ),
+            (
+                "fn pick_color(rect, neighbors) { true }",
+                "pick_color has to return a palette index, a color or (), not a bool",
+            ),
+        ];
+        for (source, message) in cases {
+            let err = outcome(source).expect_err(source);
+            assert_eq!((err.line, err.message.as_str()), (1, message), "{source}");
+        }
+        let panes = outcome("fn pick_color(rect, neighbors) { () }").unwrap();
+        assert!(panes.iter().all(|pane| pane.color == Rgb([0, 0, 0])));
+    }
+}
@@ -0,0 +1,14 @@
+//! turning arbitrary text into rng seeds
+
+/// hashes text (a username, commit hash, hostname...) into a seed with 64-bit FNV-1a
+///
+/// unlike `std`'s hashers this is stable across platforms and rust versions, so the same text
+/// keeps giving the same composition
+pub fn from_text(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
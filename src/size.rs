@@ -0,0 +1,61 @@
+//! named canvas sizes, so nobody has to remember pixel dimensions
+
+use std::fmt;
+
+use clap::ValueEnum;
+
+/// common screen and print sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SizePreset {
+    /// 1920x1080
+    #[value(name = "1080p")]
+    FullHd,
+    /// 2560x1440
+    #[value(name = "1440p")]
+    Qhd,
+    /// 3840x2160
+    #[value(name = "4k")]
+    Uhd,
+    /// 5120x2880
+    #[value(name = "5k")]
+    FiveK,
+    /// 3440x1440, 21:9
+    Ultrawide,
+    /// 1170x2532, portrait
+    Phone,
+    /// 1200x630, for link previews on social sites
+    #[value(name = "og")]
+    OpenGraph,
+    /// 2480x3508 at 300 DPI
+    #[value(name = "a4-300dpi")]
+    A4,
+}
+
+impl fmt::Display for SizePreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl SizePreset {
+    pub fn dimensions(self) -> (u32, u32) {
+        match self {
+            Self::FullHd => (1920, 1080),
+            Self::Qhd => (2560, 1440),
+            Self::Uhd => (3840, 2160),
+            Self::FiveK => (5120, 2880),
+            Self::Ultrawide => (3440, 1440),
+            Self::Phone => (1170, 2532),
+            Self::OpenGraph => (1200, 630),
+            Self::A4 => (2480, 3508),
+        }
+    }
+
+    /// only print sizes care about physical resolution
+    pub fn dpi(self) -> Option<u32> {
+        match self {
+            Self::A4 => Some(300),
+            _ => None,
+        }
+    }
+}
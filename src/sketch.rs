@@ -0,0 +1,69 @@
+//! the hand-drawn look of [`Style::Sketchy`](crate::style::Style::Sketchy): lines that wander
+//! a little and get thicker and thinner along the way, and paint that goes over their edges
+//!
+//! nothing here touches the composition's rng; each line and pane gets one of its own from the
+//! seed and where it is, so edits and other renderers draw the same wobbles
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    geometry::{Edge, Orientation, Rectangle},
+    polygon::{Point, Polygon},
+    seed,
+};
+
+/// the outline of `edge` drawn `width` thick by a not quite steady hand: the middle of it drifts
+/// up to about a quarter of the width either way, the width goes between 0.8 and 1.2 times
+/// itself, and each end overshoots by up to half the width
+pub fn stroke(edge: &Edge, width: u32, seed: u64) -> Polygon {
+    let text = format!("{seed}:sketch:{}:{}:{:?}", edge.x, edge.y, edge.orientation);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed::from_text(&text));
+    let width = width as f32;
+    let drift = 0.25 * width + 0.25;
+    let (before, after) = (
+        rng.gen_range(0.0..=width / 2.0),
+        rng.gen_range(0.0..=width / 2.0),
+    );
+    let length = edge.length + before + after;
+    // a wobble every few line widths, so it bends rather than shakes
+    let step = (8.0 * width).clamp(16.0, 64.0);
+    let stations = (length / step).ceil().max(1.0) as usize + 1;
+    let points: Vec<(f32, f32, f32)> = (0..stations)
+        .map(|i| {
+            let along = i as f32 * length / (stations - 1) as f32 - before;
+            let middle = rng.gen_range(-drift..=drift);
+            let half = width / 2.0 * rng.gen_range(0.8..=1.2);
+            (along, middle, half)
+        })
+        .collect();
+
+    let place = |along: f32, across: f32| match edge.orientation {
+        Orientation::Horizontal => Point::new(edge.x + along, edge.y + across),
+        Orientation::Vertical => Point::new(edge.x + across, edge.y + along),
+    };
+    let one_side = points
+        .iter()
+        .map(|&(along, middle, half)| place(along, middle - half));
+    let other_side = points
+        .iter()
+        .rev()
+        .map(|&(along, middle, half)| place(along, middle + half));
+    Polygon::new(one_side.chain(other_side).collect::<Vec<_>>())
+}
+
+/// how far the paint in `pane` goes past the middle of the lines around it, which are `width`
+/// thick: over the inner edge of each by half a pixel to two, different on every side
+pub fn spill(pane: &Rectangle, width: u32, seed: u64) -> Rectangle {
+    let text = format!("{seed}:spill:{}:{}", pane.x, pane.y);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed::from_text(&text));
+    let inset = width as f32 / 2.0;
+    let mut side = || inset - rng.gen_range(0.5..=2.0);
+    let (left, top, right, bottom) = (side(), side(), side(), side());
+    Rectangle {
+        x: pane.x + left,
+        y: pane.y + top,
+        width: (pane.width - left - right).max(0.0),
+        height: (pane.height - top - bottom).max(0.0),
+    }
+}
@@ -0,0 +1,157 @@
+//! numbers about a [`Composition`] that don't need it rendered, for tuning levels, ratios and
+//! weights
+
+use std::fmt;
+
+use image::Rgb;
+
+use crate::{color::to_hex, composition::Composition, geometry::Rectangle};
+
+/// how many `#`s the biggest histogram bar gets
+const BAR_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// width and height of the whole canvas
+    pub canvas: (u32, u32),
+    pub panes: usize,
+    pub smallest: Rectangle,
+    pub largest: Rectangle,
+    /// `areas[n]` is how many panes cover between 1/2^(n+1) and 1/2^n of the area inside the
+    /// matte
+    pub areas: Vec<usize>,
+    /// every color some pane got, in palette order, then any others (like the background)
+    pub colors: Vec<ColorStats>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorStats {
+    pub color: Rgb<u8>,
+    pub panes: usize,
+    /// fraction of the area inside the matte, 0 to 1
+    pub coverage: f64,
+}
+
+impl Stats {
+    /// shapes and the halves of diagonally cut panes count as panes, and their sizes are those of
+    /// the rectangles around them
+    pub fn of(composition: &Composition) -> Self {
+        // area, size and color of every pane or shape
+        let panes: Vec<(f64, Rectangle, Rgb<u8>)> = composition
+            .panes()
+            .iter()
+            .flat_map(|pane| match (pane.halves(), pane.diagonal) {
+                (Some([top, bottom]), Some(diagonal)) => vec![
+                    (top.area(), top.bounds(), pane.color),
+                    (bottom.area(), bottom.bounds(), diagonal.color),
+                ],
+                _ => vec![(pane.rectangle.area(), pane.rectangle, pane.color)],
+            })
+            .chain(
+                composition
+                    .shapes()
+                    .iter()
+                    .map(|shape| (shape.polygon.area(), shape.polygon.bounds(), shape.color)),
+            )
+            .collect();
+        let total = composition.tree().item().area().max(f64::MIN_POSITIVE);
+        let smallest = panes
+            .iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|pane| pane.1);
+        let largest = panes
+            .iter()
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|pane| pane.1);
+
+        let mut areas = vec![];
+        for &(area, _, _) in &panes {
+            let fraction = area / total;
+            // zero-area panes go in the last bucket there is room for
+            let bucket = if fraction > 0.0 {
+                (-fraction.log2()).floor().max(0.0) as usize
+            } else {
+                composition.levels() + 1
+            };
+            if areas.len() <= bucket {
+                areas.resize(bucket + 1, 0);
+            }
+            areas[bucket] += 1;
+        }
+
+        let mut colors: Vec<ColorStats> = composition
+            .palette()
+            .colors()
+            .iter()
+            .map(|&color| ColorStats {
+                color,
+                panes: 0,
+                coverage: 0.0,
+            })
+            .collect();
+        for &(area, _, color) in &panes {
+            let index = match colors.iter().position(|stats| stats.color == color) {
+                Some(index) => index,
+                None => {
+                    colors.push(ColorStats {
+                        color,
+                        panes: 0,
+                        coverage: 0.0,
+                    });
+                    colors.len() - 1
+                }
+            };
+            colors[index].panes += 1;
+            colors[index].coverage += area / total;
+        }
+        colors.retain(|stats| stats.panes > 0);
+
+        let empty = Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+        };
+        Self {
+            canvas: (composition.width(), composition.height()),
+            panes: panes.len(),
+            smallest: smallest.unwrap_or(empty),
+            largest: largest.unwrap_or(empty),
+            areas,
+            colors,
+        }
+    }
+}
+
+/// a plain-text report, one section after another
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |r: &Rectangle| format!("{:.1}x{:.1}", r.width, r.height);
+        writeln!(f, "canvas    {}x{}", self.canvas.0, self.canvas.1)?;
+        writeln!(f, "panes     {}", self.panes)?;
+        writeln!(f, "smallest  {}", size(&self.smallest))?;
+        writeln!(f, "largest   {}", size(&self.largest))?;
+
+        // each row is panes at least that share of the canvas, but less than twice it
+        writeln!(f, "\npane area, as a share of the canvas")?;
+        let most = self.areas.iter().copied().max().unwrap_or(0).max(1);
+        let first = self.areas.iter().position(|count| *count > 0).unwrap_or(0);
+        for (bucket, count) in self.areas.iter().enumerate().skip(first) {
+            let bar = "#".repeat(count * BAR_WIDTH / most);
+            let share = format!("1/{}", 1u128 << (bucket + 1).min(127));
+            writeln!(f, "  {share:>8}  {count:>6}  {bar}")?;
+        }
+
+        writeln!(f, "\ncolors")?;
+        for stats in &self.colors {
+            writeln!(
+                f,
+                "  {}  {:>6} panes  {:>5.1}%",
+                to_hex(stats.color),
+                stats.panes,
+                stats.coverage * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! where and which way panes get cut; [`Tree::split_with`](crate::tree::Tree::split_with) takes
+//! any [`SplitStrategy`], and [`Builtin`] names the ones that come with the crate
+
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+};
+
+use clap::ValueEnum;
+use rand::{Rng, RngCore};
+
+use crate::{
+    geometry::{Rectangle, SplitOptions, SplittableGraphic},
+    noise::Perlin,
+};
+
+/// decides how one node of the tree becomes two; [plugins](crate::plugin) implement it for
+/// [`Rectangle`]
+pub trait SplitStrategy<P>: fmt::Debug {
+    /// splits `item`, which is `depth` splits down from the root
+    fn split(
+        &self,
+        item: &P,
+        depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (P, P);
+}
+
+/// the original: somewhere between 40% and 60% of the way across, whatever `P` does itself
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+impl<P: SplittableGraphic> SplitStrategy<P> for Random {
+    fn split(
+        &self,
+        item: &P,
+        _depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (P, P) {
+        item.split(options, rng)
+    }
+}
+
+/// a third of the way across, from one side or the other
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Thirds;
+
+impl SplitStrategy<Rectangle> for Thirds {
+    fn split(
+        &self,
+        item: &Rectangle,
+        _depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        let horz_split = item.direction(rng);
+        let t = if rng.gen() { 1.0 / 3.0 } else { 2.0 / 3.0 };
+        item.divide_within(horz_split, t, options)
+    }
+}
+
+/// random cuts, moved onto the nearest line of a grid of `cell`-sized squares laid over the whole
+/// canvas, so lines in different panes line up
+#[derive(Debug, Clone, Copy)]
+pub struct Grid {
+    pub cell: f32,
+}
+
+impl SplitStrategy<Rectangle> for Grid {
+    fn split(
+        &self,
+        item: &Rectangle,
+        _depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        let horz_split = item.direction(rng);
+        let (start, along) = if horz_split {
+            (item.x, item.width)
+        } else {
+            (item.y, item.height)
+        };
+        let mut t = rng.gen_range(0.25..=0.75);
+        if self.cell > 0.0 {
+            let line = ((start + along * t) / self.cell).round() * self.cell;
+            // panes narrower than a cell or two have no grid line inside them to use
+            if line > start && line < start + along {
+                t = (line - start) / along;
+            }
+        }
+        item.divide_within(horz_split, t, options)
+    }
+}
+
+/// cuts at ratios of consecutive Fibonacci numbers, 1/2, 2/3, 3/5, 5/8..., one step further each
+/// level down, so deep panes approach the golden ratio
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fibonacci;
+
+impl SplitStrategy<Rectangle> for Fibonacci {
+    fn split(
+        &self,
+        item: &Rectangle,
+        depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        let horz_split = item.direction(rng);
+        let (mut smaller, mut larger) = (1u64, 2u64);
+        // past this the ratio stops changing in an f32 anyway
+        for _ in 0..depth.min(40) {
+            (smaller, larger) = (larger, smaller + larger);
+        }
+        let t = smaller as f32 / larger as f32;
+        let t = if rng.gen() { t } else { 1.0 - t };
+        item.divide_within(horz_split, t, options)
+    }
+}
+
+/// always 1:φ across the long side, with the bigger half going left, top, right, bottom in turn
+/// level by level, so the panes nest like a golden-rectangle spiral; doesn't use the rng at all
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Golden;
+
+impl SplitStrategy<Rectangle> for Golden {
+    fn split(
+        &self,
+        item: &Rectangle,
+        depth: usize,
+        options: &SplitOptions,
+        _rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        // 1/φ, so the bigger half is to the smaller as the whole is to the bigger
+        let larger = 2.0 / (1.0 + 5f32.sqrt());
+        let t = if depth % 4 < 2 { larger } else { 1.0 - larger };
+        item.divide_within(item.width >= item.height, t, options)
+    }
+}
+
+/// cuts that leave as much of a Perlin noise field on one side as the other, so panes get small
+/// where it's high and stay big where it's low; it cuts across the side the field changes more
+/// along, more often than not. the field comes from the rng on the first cut, so each seed gets
+/// its own, and [`SplitOptions::noise`] says what it looks like
+#[derive(Debug, Clone)]
+pub struct Noise {
+    /// what the field is laid over
+    pub canvas: Rectangle,
+    field: OnceCell<Perlin>,
+}
+
+impl Noise {
+    pub fn new(canvas: Rectangle) -> Self {
+        Self {
+            canvas,
+            field: OnceCell::new(),
+        }
+    }
+}
+
+impl SplitStrategy<Rectangle> for Noise {
+    fn split(
+        &self,
+        item: &Rectangle,
+        _depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        /// slices of the pane the field is added up in, along the side being cut
+        const SLICES: usize = 16;
+        /// samples across each slice
+        const ACROSS: usize = 4;
+        /// how much denser the high parts are than the low ones: e^(2 × this) times
+        const CONTRAST: f32 = 3.5;
+
+        let field = *self.field.get_or_init(|| Perlin::new(rng.next_u64()));
+        let noise = options.noise;
+        let scale = noise.frequency / self.canvas.width.max(self.canvas.height);
+        let density = |x: f32, y: f32| {
+            let (x, y) = ((x - self.canvas.x) * scale, (y - self.canvas.y) * scale);
+            (field.fractal(x, y, noise.octaves) * CONTRAST).exp()
+        };
+        // how much of the field is in each slice, cutting the width or the height
+        let slices = |horz_split: bool| {
+            let mut slices = [0.0; SLICES];
+            for (i, slice) in slices.iter_mut().enumerate() {
+                let along = (i as f32 + 0.5) / SLICES as f32;
+                for j in 0..ACROSS {
+                    let across = (j as f32 + 0.5) / ACROSS as f32;
+                    let (u, v) = if horz_split {
+                        (along, across)
+                    } else {
+                        (across, along)
+                    };
+                    *slice += density(item.x + item.width * u, item.y + item.height * v);
+                }
+            }
+            slices
+        };
+        // how lopsided the two halves are, from 0 (not at all) to 1
+        let imbalance = |slices: &[f32; SLICES]| {
+            let (first, second) = slices.split_at(SLICES / 2);
+            let (first, second): (f32, f32) = (first.iter().sum(), second.iter().sum());
+            (first - second).abs() / (first + second)
+        };
+
+        let (horz, vert) = (slices(true), slices(false));
+        let horz_split = if item.width >= 3.0 * item.height {
+            true
+        } else if item.height >= 3.0 * item.width {
+            false
+        } else {
+            // weighed by length too, so panes don't turn into strips
+            let horz = imbalance(&horz) * item.width;
+            let vert = imbalance(&vert) * item.height;
+            match horz + vert {
+                total if total > 0.0 => rng.gen_bool((horz / total) as f64),
+                _ => rng.gen(),
+            }
+        };
+
+        // where the running total gets to half
+        let slices = if horz_split { horz } else { vert };
+        let half = slices.iter().sum::<f32>() / 2.0;
+        let mut sum = 0.0;
+        let mut t = 0.5;
+        for (i, slice) in slices.iter().enumerate() {
+            if sum + slice >= half {
+                t = (i as f32 + (half - sum) / slice) / SLICES as f32;
+                break;
+            }
+            sum += slice;
+        }
+        // never so lopsided that the dense half is a sliver
+        item.divide_within(horz_split, t.clamp(0.15, 0.85), options)
+    }
+}
+
+/// how near square a pane has to be, long side over short, for [`Crossed`] to turn its cut
+const SQUARISH: f32 = 1.5;
+
+/// another strategy's cuts, except that a squarish pane about to be cut the same way as its
+/// parent was is cut across instead, [`SplitOptions::cross`] of the time, for fewer runs of
+/// parallel lines and more of a cross-hatched look
+#[derive(Debug)]
+pub struct Crossed {
+    inner: Box<dyn SplitStrategy<Rectangle>>,
+    /// which way every half it made was cut from its parent, by the half's exact bits; true for
+    /// side by side
+    cuts: RefCell<HashMap<[u32; 4], bool>>,
+}
+
+impl Crossed {
+    pub fn new(inner: Box<dyn SplitStrategy<Rectangle>>) -> Self {
+        Self {
+            inner,
+            cuts: RefCell::default(),
+        }
+    }
+}
+
+/// a rectangle exactly, as a map key
+fn bits(rectangle: &Rectangle) -> [u32; 4] {
+    [rectangle.x, rectangle.y, rectangle.width, rectangle.height].map(f32::to_bits)
+}
+
+impl SplitStrategy<Rectangle> for Crossed {
+    fn split(
+        &self,
+        item: &Rectangle,
+        depth: usize,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> (Rectangle, Rectangle) {
+        let (mut left, mut right) = self.inner.split(item, depth, options, rng);
+        let side_by_side = left.y == right.y;
+        let parent = self.cuts.borrow().get(&bits(item)).copied();
+        let squarish = item.width.max(item.height) < SQUARISH * item.width.min(item.height);
+        // the rng is only asked when the cut could turn, so other panes are cut as they'd be
+        // without this
+        if parent == Some(side_by_side) && squarish && rng.gen_bool(options.cross as f64) {
+            let t = if side_by_side {
+                left.width / item.width
+            } else {
+                left.height / item.height
+            };
+            (left, right) = item.divide_within(!side_by_side, t, options);
+        }
+        let side_by_side = left.y == right.y;
+        let mut cuts = self.cuts.borrow_mut();
+        for half in [&left, &right] {
+            cuts.insert(bits(half), side_by_side);
+        }
+        (left, right)
+    }
+}
+
+/// the strategies you can pick by name, e.g. with `--split-strategy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Builtin {
+    /// anywhere from 40% to 60% of the way across
+    #[default]
+    Random,
+    /// a third of the way across, from either side
+    Thirds,
+    /// random, but snapped to a grid 1/32 of the canvas's long side, so lines line up
+    Grid,
+    /// ratios of Fibonacci numbers, getting closer to the golden ratio each level down
+    Fibonacci,
+    /// 1:φ every time, the bigger half turning a quarter each level, like a golden spiral
+    Golden,
+    /// wherever halves a Perlin noise field, so panes are dense in patches and sparse in others
+    Noise,
+}
+
+impl Builtin {
+    /// the strategy itself, set up for a composition covering `canvas`
+    pub fn strategy(self, canvas: &Rectangle) -> Box<dyn SplitStrategy<Rectangle>> {
+        match self {
+            Self::Random => Box::new(Random),
+            Self::Thirds => Box::new(Thirds),
+            Self::Grid => Box::new(Grid {
+                cell: canvas.width.max(canvas.height) / 32.0,
+            }),
+            Self::Fibonacci => Box::new(Fibonacci),
+            Self::Golden => Box::new(Golden),
+            Self::Noise => Box::new(Noise::new(*canvas)),
+        }
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Builtin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
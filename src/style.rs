@@ -0,0 +1,60 @@
+//! what kind of picture gets made; [`Style::Voronoi`], [`Style::Truchet`], [`Style::Hex`] and
+//! [`Style::Penrose`] cover the canvas with [`Shape`](crate::polygon::Shape)s rather than splitting
+//! it into rectangles. [`Style::LinesOnly`] goes everywhere [`Style::Mondrian`] does,
+//! [`Style::Sketchy`] is drawn by [`sketch`](crate::sketch) and [`Style::ThreeD`] by
+//! [`relief`](crate::relief)
+//!
+//! the variants' docs are `--style`'s help, so they're kept to plain words
+
+use std::{fmt, str::FromStr};
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Style {
+    /// rectangles, split again and again
+    #[default]
+    Mondrian,
+    /// the same layout with nothing filled in but the lines, like the charcoal studies he worked
+    /// them out in; everything that goes for mondrian goes for it too
+    LinesOnly,
+    /// the same again, but as if painted by hand: the lines wobble and the paint goes over them
+    /// a little
+    Sketchy,
+    /// the same layout with every pane a block standing up off the canvas, lit from one side and
+    /// seen from above a corner
+    #[value(name = "3d")]
+    ThreeD,
+    /// random seed points and the cells of everything nearest each one, like crystals
+    Voronoi,
+    /// a grid of tiles, each with quarter circles or a diagonal turned either way at random
+    Truchet,
+    /// a honeycomb of hexagons
+    Hex,
+    /// Penrose's rhombs, thick and thin, which never settle into a repeating pattern
+    Penrose,
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+impl Style {
+    /// whether the canvas is split into rectangles, so there are panes and lines between them
+    pub fn splits(self) -> bool {
+        matches!(
+            self,
+            Self::Mondrian | Self::LinesOnly | Self::Sketchy | Self::ThreeD
+        )
+    }
+}
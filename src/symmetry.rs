@@ -0,0 +1,219 @@
+//! compositions that mirror themselves: one part of the canvas is split as usual, and the rest are
+//! copies of it, flipped or turned, panes, lines, colors and all
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use clap::ValueEnum;
+use rand::RngCore;
+
+use crate::{
+    geometry::{Rectangle, SplitOptions},
+    strategy::SplitStrategy,
+    tree::Tree,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Symmetry {
+    /// the right half mirrors the left
+    Horizontal,
+    /// the bottom half mirrors the top
+    Vertical,
+    /// all four quarters mirror the top left one
+    Quad,
+    /// the right half is the left one turned upside down
+    Rotational,
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// which ways a copy is flipped from the original; both is half a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flip {
+    pub x: bool,
+    pub y: bool,
+}
+
+impl Symmetry {
+    /// the cuts down the middle that make the parts, outermost first: true cuts the width
+    fn cuts(self) -> &'static [bool] {
+        match self {
+            Self::Horizontal | Self::Rotational => &[true],
+            Self::Vertical => &[false],
+            Self::Quad => &[true, false],
+        }
+    }
+
+    /// how each copy is made from the original; the mirror lines are only known once the
+    /// middle cuts are made
+    fn copies(self) -> &'static [Flip] {
+        const X: Flip = Flip { x: true, y: false };
+        const Y: Flip = Flip { x: false, y: true };
+        const XY: Flip = Flip { x: true, y: true };
+        match self {
+            Self::Horizontal => &[X],
+            Self::Vertical => &[Y],
+            Self::Quad => &[X, Y, XY],
+            Self::Rotational => &[XY],
+        }
+    }
+}
+
+/// reflects coordinates across a canvas from `start` to `end`, with the middle cut at `line`
+/// (`None` for a straight flip): the original side's coordinates land on the other side, and
+/// `line` stays put. if the cut wasn't quite in the middle, the panes along it take up the
+/// difference, so the copy still fits its side exactly
+#[derive(Debug, Clone, Copy)]
+struct Mirror {
+    sum: f32,
+    line: Option<f32>,
+}
+
+impl Mirror {
+    fn new(start: f32, end: f32, line: Option<f32>) -> Self {
+        Self {
+            sum: start + end,
+            line,
+        }
+    }
+
+    fn point(&self, c: f32) -> f32 {
+        match self.line {
+            Some(line) if c == line => line,
+            _ => self.sum - c,
+        }
+    }
+
+    /// the span from `start`, `length` long, reflected
+    fn span(&self, start: f32, length: f32) -> (f32, f32) {
+        let (a, b) = (self.point(start), self.point(start + length));
+        (b, a - b)
+    }
+}
+
+/// a copy's pair of mirrors, one per axis, or `None` if that axis isn't flipped
+#[derive(Debug, Clone, Copy)]
+struct Transform {
+    x: Option<Mirror>,
+    y: Option<Mirror>,
+}
+
+impl Transform {
+    fn apply(&self, r: &Rectangle) -> Rectangle {
+        let (x, width) = self.x.map_or((r.x, r.width), |m| m.span(r.x, r.width));
+        let (y, height) = self.y.map_or((r.y, r.height), |m| m.span(r.y, r.height));
+        Rectangle {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+fn key(r: &Rectangle, depth: usize) -> ([u32; 4], usize) {
+    ([r.x, r.y, r.width, r.height].map(f32::to_bits), depth)
+}
+
+/// `bounds` cut down the middle as `symmetry` says, with the first part split `levels` deep in all
+/// by `strategy` and the others made copies of it; also, for every leaf, the one it's a copy of
+/// (itself, for the original's) and how it's flipped
+pub fn layout(
+    bounds: Rectangle,
+    symmetry: Symmetry,
+    levels: usize,
+    strategy: &dyn SplitStrategy<Rectangle>,
+    options: &SplitOptions,
+    rng: &mut dyn RngCore,
+) -> (Tree<Rectangle>, Vec<(usize, Flip)>) {
+    let cuts = symmetry.cuts();
+    // set once the middle cuts are in
+    let (mut lines_x, mut lines_y) = (None, None);
+    let mut transforms: Vec<(Transform, Flip)> = vec![];
+    // what to split each copied node into, by its rectangle and depth
+    let mut copies = HashMap::new();
+
+    let mut tree = Tree::new(bounds);
+    tree.split_by(|rectangle, depth| {
+        if depth >= levels {
+            return None;
+        }
+        if let Some(&horz_split) = cuts.get(depth) {
+            let (first, second) = rectangle.divide(horz_split, 0.5, options);
+            match horz_split {
+                true => lines_x = Some(second.x),
+                false => lines_y = Some(second.y),
+            }
+            return Some((first, second));
+        }
+        if transforms.is_empty() {
+            // the first node past the middle cuts; they're all known by now
+            let mirror_x = Mirror::new(bounds.x, bounds.x + bounds.width, lines_x);
+            let mirror_y = Mirror::new(bounds.y, bounds.y + bounds.height, lines_y);
+            transforms = symmetry
+                .copies()
+                .iter()
+                .map(|&flip| {
+                    let transform = Transform {
+                        x: flip.x.then_some(mirror_x),
+                        y: flip.y.then_some(mirror_y),
+                    };
+                    (transform, flip)
+                })
+                .collect();
+        }
+        if let Some(halves) = copies.remove(&key(rectangle, depth)) {
+            return Some(halves);
+        }
+        let (first, second) = strategy.split(rectangle, depth, options, rng);
+        for (transform, _) in &transforms {
+            let (a, b) = (transform.apply(&first), transform.apply(&second));
+            // the tree keeps the left or top half first
+            let halves = if (a.x, a.y) <= (b.x, b.y) {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            copies.insert(key(&transform.apply(rectangle), depth), halves);
+        }
+        Some((first, second))
+    });
+
+    // leaves of the copies, and which original leaf each came from
+    let leaves: Vec<Rectangle> = tree.leaves().copied().collect();
+    let mut sources = HashMap::new();
+    let original = |r: &Rectangle| {
+        let inside = |line: Option<f32>, start: f32, length: f32| {
+            line.is_none_or(|line| start + length <= line && (length > 0.0 || start < line))
+        };
+        inside(lines_x, r.x, r.width) && inside(lines_y, r.y, r.height)
+    };
+    for (index, leaf) in leaves.iter().enumerate() {
+        if !original(leaf) {
+            continue;
+        }
+        for &(transform, flip) in &transforms {
+            sources.insert(key(&transform.apply(leaf), 0), (index, flip));
+        }
+    }
+    let sources = leaves
+        .iter()
+        .enumerate()
+        .map(|(index, leaf)| match sources.get(&key(leaf, 0)) {
+            Some(&source) if !original(leaf) => source,
+            _ => (index, Flip::default()),
+        })
+        .collect();
+    (tree, sources)
+}
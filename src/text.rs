@@ -0,0 +1,957 @@
+//! words on top of a composition: dates on daily wallpapers, titles on posters
+//!
+//! fonts are TrueType (`.ttf`, or the first font of a `.ttc`), read here rather than by a font
+//! library; glyphs come out as [`Polygon`]s, so every renderer paints them like any other shape.
+//! there's no kerning or shaping, so scripts that need it won't look right
+
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use image::Rgb;
+
+use crate::{
+    geometry::{Rectangle, SplittableGraphic},
+    polygon::{Point, Polygon},
+};
+
+/// what to write, and how
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    /// can have `{seed}`, `{date}` and the others [`expand`] fills in, and `\n`s between lines
+    pub text: String,
+    /// a TrueType font file; whichever [`find_font`] finds if unset
+    pub font: Option<PathBuf>,
+    /// the em size in pixels; a twentieth of the canvas height if unset
+    pub size: Option<f32>,
+    pub color: Rgb<u8>,
+    pub anchor: Anchor,
+}
+
+impl Text {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            font: None,
+            size: None,
+            color: Rgb([0, 0, 0]),
+            anchor: Anchor::default(),
+        }
+    }
+}
+
+/// a title, and a line under it half the size, for a social-share card: the title is as big as
+/// fits, up to a ninth of the canvas height, and the panes behind the two are kept calm. no
+/// templates are filled in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub title: String,
+    /// can have `\n`s between lines, like the title
+    pub subtitle: Option<String>,
+    /// a TrueType font file; whichever [`find_font`] finds if unset
+    pub font: Option<PathBuf>,
+    pub color: Rgb<u8>,
+    /// where in the canvas the two go, an em in from the edges; they're aligned the same way
+    pub anchor: Anchor,
+}
+
+impl Card {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            font: None,
+            color: Rgb([0, 0, 0]),
+            anchor: Anchor::Left,
+        }
+    }
+}
+
+/// which corner, side or middle of the canvas the text goes in, half an em in from the edges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+impl Anchor {
+    /// how far across and down, from 0 to 1
+    pub(crate) fn position(self) -> (f32, f32) {
+        match self {
+            Self::TopLeft => (0.0, 0.0),
+            Self::Top => (0.5, 0.0),
+            Self::TopRight => (1.0, 0.0),
+            Self::Left => (0.0, 0.5),
+            Self::Center => (0.5, 0.5),
+            Self::Right => (1.0, 0.5),
+            Self::BottomLeft => (0.0, 1.0),
+            Self::Bottom => (0.5, 1.0),
+            Self::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl fmt::Display for Anchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// why text couldn't be written
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextError {
+    /// no font was given, and none of the usual ones are installed
+    NoFont,
+    /// the font couldn't be read, or isn't TrueType
+    Font { path: PathBuf, why: String },
+    /// a `{...}` that [`expand`] doesn't know
+    Template(String),
+    /// the size has to be a number of pixels above 0
+    Size(f32),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFont => write!(f, "couldn't find a font for the text; give one with --font"),
+            Self::Font { path, why } => {
+                write!(f, "couldn't use the font {}: {why}", path.display())
+            }
+            Self::Template(name) => write!(
+                f,
+                "there's no {{{name}}} to put in the text; use {{seed}}, {{date}}, {{time}}, \
+                 {{width}}, {{height}} or {{levels}}, and {{{{ for a {{"
+            ),
+            Self::Size(size) => write!(f, "text size must be more than 0 pixels, not {size}"),
+        }
+    }
+}
+
+impl Error for TextError {}
+
+/// what [`expand`] can put in the text
+#[derive(Debug, Clone, Copy)]
+pub struct Fields {
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    pub levels: usize,
+    pub now: SystemTime,
+}
+
+/// `template` with `{seed}`, `{date}` (`2024-05-17`), `{time}` (`09:41`), `{width}`, `{height}`
+/// and `{levels}` filled in, dates and times in UTC, and `{{` and `}}` for braces
+pub fn expand(template: &str, fields: &Fields) -> Result<String, TextError> {
+    let seconds = fields
+        .now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (year, month, day) = civil((seconds / 86_400) as i64);
+    let (hour, minute) = (seconds % 86_400 / 3600, seconds % 3600 / 60);
+
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        text.push_str(&rest[..at]);
+        let brace = &rest[at..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            text.push_str(&brace[..1]);
+            rest = &brace[2..];
+            continue;
+        }
+        let Some(end) = brace.find('}').filter(|_| brace.starts_with('{')) else {
+            text.push_str(&brace[..1]);
+            rest = &brace[1..];
+            continue;
+        };
+        let name = &brace[1..end];
+        let value = match name {
+            "seed" => fields.seed.to_string(),
+            "date" => format!("{year:04}-{month:02}-{day:02}"),
+            "time" => format!("{hour:02}:{minute:02}"),
+            "width" => fields.width.to_string(),
+            "height" => fields.height.to_string(),
+            "levels" => fields.levels.to_string(),
+            _ => return Err(TextError::Template(name.to_owned())),
+        };
+        text.push_str(&value);
+        rest = &brace[end + 1..];
+    }
+    text.push_str(rest);
+    Ok(text)
+}
+
+/// the year, month and day `days` after 1970-01-01
+pub(crate) fn civil(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days_from_civil, backwards
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// a sans-serif font in one of the places they usually are, if there is one
+pub fn find_font() -> Option<PathBuf> {
+    const FONTS: &[&str] = &[
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+        "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+        "/usr/share/fonts/liberation-sans/LiberationSans-Regular.ttf",
+        "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+        "/System/Library/Fonts/Supplemental/Arial.ttf",
+        "/Library/Fonts/Arial.ttf",
+        "C:\\Windows\\Fonts\\arial.ttf",
+    ];
+    FONTS.iter().map(PathBuf::from).find(|path| path.is_file())
+}
+
+/// a TrueType font's outlines and metrics
+#[derive(Debug, Clone)]
+pub struct Font {
+    data: Vec<u8>,
+    units_per_em: f32,
+    long_offsets: bool,
+    glyphs: u16,
+    ascender: f32,
+    descender: f32,
+    line_gap: f32,
+    metrics: u16,
+    /// where each table starts, and how long it is
+    cmap: (usize, usize),
+    loca: usize,
+    glyf: (usize, usize),
+    hmtx: usize,
+}
+
+/// big-endian reads that run off the end as `None`
+fn read<const N: usize>(data: &[u8], at: usize) -> Option<[u8; N]> {
+    data.get(at..at.checked_add(N)?)?.try_into().ok()
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    read(data, at).map(u16::from_be_bytes)
+}
+
+fn i16_at(data: &[u8], at: usize) -> Option<i16> {
+    read(data, at).map(i16::from_be_bytes)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    read(data, at).map(u32::from_be_bytes)
+}
+
+impl Font {
+    pub fn load(path: &Path) -> Result<Self, TextError> {
+        let error = |why: String| TextError::Font {
+            path: path.to_owned(),
+            why,
+        };
+        let data = fs::read(path).map_err(|err| error(err.to_string()))?;
+        Self::parse(data).map_err(|why| error(why.to_owned()))
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, &'static str> {
+        let short = "it's cut short, or isn't a font";
+        let mut start = 0;
+        match &read::<4>(&data, 0).ok_or(short)? {
+            [0, 1, 0, 0] | b"true" => {}
+            b"ttcf" => start = u32_at(&data, 12).ok_or(short)? as usize,
+            b"OTTO" => return Err("it has PostScript outlines, and only TrueType ones work"),
+            _ => return Err(short),
+        }
+        let tables = u16_at(&data, start + 4).ok_or(short)?;
+        let table = |tag: &[u8; 4]| {
+            (0..tables as usize)
+                .map(|i| start + 12 + 16 * i)
+                .find(|&record| data.get(record..record + 4) == Some(tag))
+                .and_then(|record| {
+                    let offset = u32_at(&data, record + 8)? as usize;
+                    let length = u32_at(&data, record + 12)? as usize;
+                    (offset.checked_add(length)? <= data.len()).then_some((offset, length))
+                })
+        };
+        let missing = "it's missing a table TrueType fonts need";
+        let (head, _) = table(b"head").ok_or(missing)?;
+        let (maxp, _) = table(b"maxp").ok_or(missing)?;
+        let (hhea, _) = table(b"hhea").ok_or(missing)?;
+        let (hmtx, _) = table(b"hmtx").ok_or(missing)?;
+        let (loca, _) = table(b"loca").ok_or(missing)?;
+        let cmap = table(b"cmap").ok_or(missing)?;
+        let glyf = table(b"glyf").ok_or(missing)?;
+
+        let font = Self {
+            units_per_em: u16_at(&data, head + 18).ok_or(short)?.max(1) as f32,
+            long_offsets: i16_at(&data, head + 50).ok_or(short)? != 0,
+            glyphs: u16_at(&data, maxp + 4).ok_or(short)?,
+            ascender: i16_at(&data, hhea + 4).ok_or(short)? as f32,
+            descender: i16_at(&data, hhea + 6).ok_or(short)? as f32,
+            line_gap: i16_at(&data, hhea + 8).ok_or(short)? as f32,
+            metrics: u16_at(&data, hhea + 34).ok_or(short)?.max(1),
+            cmap,
+            loca,
+            glyf,
+            hmtx,
+            data,
+        };
+        font.subtable().ok_or("it has no Unicode character map")?;
+        Ok(font)
+    }
+
+    /// the Unicode character map to use: its format and where it starts
+    fn subtable(&self) -> Option<(u16, usize)> {
+        let (cmap, _) = self.cmap;
+        let count = u16_at(&self.data, cmap + 2)?;
+        let mut best = None;
+        for i in 0..count as usize {
+            let record = cmap + 4 + 8 * i;
+            let platform = u16_at(&self.data, record)?;
+            let encoding = u16_at(&self.data, record + 2)?;
+            let at = cmap + u32_at(&self.data, record + 4)? as usize;
+            let format = u16_at(&self.data, at)?;
+            let unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+            if !unicode || !matches!(format, 4 | 12) {
+                continue;
+            }
+            // the full-range format over the one for the first 65536 characters
+            if best.is_none_or(|(best, _)| best == 4 && format == 12) {
+                best = Some((format, at));
+            }
+        }
+        best
+    }
+
+    /// the glyph for `c`, 0 (the missing-glyph box) if there isn't one
+    pub fn glyph(&self, c: char) -> u16 {
+        self.lookup(c as u32)
+            .filter(|&glyph| glyph < self.glyphs)
+            .unwrap_or(0)
+    }
+
+    fn lookup(&self, c: u32) -> Option<u16> {
+        let data = &self.data;
+        let (format, at) = self.subtable()?;
+        if format == 12 {
+            let groups = u32_at(data, at + 12)? as usize;
+            for group in (0..groups).map(|i| at + 16 + 12 * i) {
+                let (start, end) = (u32_at(data, group)?, u32_at(data, group + 4)?);
+                if (start..=end).contains(&c) {
+                    return u16::try_from(u32_at(data, group + 8)? + (c - start)).ok();
+                }
+            }
+            return None;
+        }
+        let c = u16::try_from(c).ok()?;
+        let segments = u16_at(data, at + 6)? as usize / 2;
+        let ends = at + 14;
+        let starts = ends + 2 * segments + 2;
+        let deltas = starts + 2 * segments;
+        let ranges = deltas + 2 * segments;
+        for i in 0..segments {
+            if u16_at(data, ends + 2 * i)? < c {
+                continue;
+            }
+            let start = u16_at(data, starts + 2 * i)?;
+            if start > c {
+                return None;
+            }
+            let delta = u16_at(data, deltas + 2 * i)?;
+            let range = u16_at(data, ranges + 2 * i)? as usize;
+            if range == 0 {
+                return Some(c.wrapping_add(delta));
+            }
+            let glyph = u16_at(data, ranges + 2 * i + range + 2 * (c - start) as usize)?;
+            return (glyph != 0).then(|| glyph.wrapping_add(delta));
+        }
+        None
+    }
+
+    /// how far along the next glyph goes, in font units
+    fn advance(&self, glyph: u16) -> f32 {
+        let index = glyph.min(self.metrics - 1) as usize;
+        u16_at(&self.data, self.hmtx + 4 * index).unwrap_or(0) as f32
+    }
+
+    /// the outline of `glyph` in font units, y up, as closed contours of on-curve points with
+    /// the curves between them flattened into `steps` lines each
+    fn contours(&self, glyph: u16, steps: usize, depth: usize) -> Option<Vec<Vec<Point>>> {
+        let data = &self.data;
+        let offset = |glyph: u16| {
+            let glyph = glyph as usize;
+            if self.long_offsets {
+                Some(u32_at(data, self.loca + 4 * glyph)? as usize)
+            } else {
+                Some(u16_at(data, self.loca + 2 * glyph)? as usize * 2)
+            }
+        };
+        let (start, end) = (offset(glyph)?, offset(glyph + 1)?);
+        let (glyf, length) = self.glyf;
+        if start >= end || end > length {
+            // spaces and the like have no outline at all
+            return Some(vec![]);
+        }
+        let at = glyf + start;
+        let contours = i16_at(data, at)?;
+        if contours < 0 {
+            return self.composite(at + 10, steps, depth);
+        }
+
+        let contours = contours as usize;
+        let mut ends = Vec::with_capacity(contours);
+        for i in 0..contours {
+            ends.push(u16_at(data, at + 10 + 2 * i)? as usize);
+        }
+        let points = ends.last().map_or(0, |last| last + 1);
+        let instructions = u16_at(data, at + 10 + 2 * contours)? as usize;
+        let mut cursor = at + 12 + 2 * contours + instructions;
+
+        let mut flags = Vec::with_capacity(points);
+        while flags.len() < points {
+            let flag = *data.get(cursor)?;
+            cursor += 1;
+            let repeats = if flag & 8 != 0 {
+                cursor += 1;
+                *data.get(cursor - 1)? as usize
+            } else {
+                0
+            };
+            flags.extend(std::iter::repeat_n(flag, repeats + 1));
+        }
+        flags.truncate(points);
+        // x then y, each as a run of differences: a byte with its sign in the flags, the same as
+        // the last, or a whole i16
+        let mut coordinates = |short: u8, same: u8| {
+            let mut value = 0i32;
+            let mut values = Vec::with_capacity(points);
+            for &flag in &flags {
+                if flag & short != 0 {
+                    let byte = *data.get(cursor)? as i32;
+                    cursor += 1;
+                    value += if flag & same != 0 { byte } else { -byte };
+                } else if flag & same == 0 {
+                    value += i16_at(data, cursor)? as i32;
+                    cursor += 2;
+                }
+                values.push(value as f32);
+            }
+            Some(values)
+        };
+        let xs = coordinates(2, 16)?;
+        let ys = coordinates(4, 32)?;
+
+        let mut outline = vec![];
+        let mut first = 0;
+        for &last in &ends {
+            if last < first || last >= points {
+                return None;
+            }
+            let contour: Vec<(Point, bool)> = (first..=last)
+                .map(|i| (Point { x: xs[i], y: ys[i] }, flags[i] & 1 != 0))
+                .collect();
+            outline.push(flatten(&contour, steps));
+            first = last + 1;
+        }
+        Some(outline)
+    }
+
+    /// a glyph made of other glyphs, moved and scaled
+    fn composite(&self, mut at: usize, steps: usize, depth: usize) -> Option<Vec<Vec<Point>>> {
+        const WORDS: u16 = 1;
+        const XY: u16 = 2;
+        const SCALE: u16 = 8;
+        const MORE: u16 = 32;
+        const XY_SCALE: u16 = 64;
+        const TWO_BY_TWO: u16 = 128;
+        // fonts that nest deeper than this are broken, or trying to be
+        if depth > 8 {
+            return None;
+        }
+        let data = &self.data;
+        let f2dot14 = |at: usize| Some(i16_at(data, at)? as f32 / 16384.0);
+        let mut outline = vec![];
+        loop {
+            let flags = u16_at(data, at)?;
+            let glyph = u16_at(data, at + 2)?;
+            at += 4;
+            let (dx, dy) = if flags & WORDS != 0 {
+                at += 4;
+                (i16_at(data, at - 4)? as f32, i16_at(data, at - 2)? as f32)
+            } else {
+                at += 2;
+                (
+                    *data.get(at - 2)? as i8 as f32,
+                    *data.get(at - 1)? as i8 as f32,
+                )
+            };
+            // the other kind lines points up with each other, which only hinting cares about
+            let (dx, dy) = if flags & XY != 0 {
+                (dx, dy)
+            } else {
+                (0.0, 0.0)
+            };
+            let (mut a, mut b, mut c, mut d) = (1.0, 0.0, 0.0, 1.0);
+            if flags & SCALE != 0 {
+                (a, d) = (f2dot14(at)?, f2dot14(at)?);
+                at += 2;
+            } else if flags & XY_SCALE != 0 {
+                (a, d) = (f2dot14(at)?, f2dot14(at + 2)?);
+                at += 4;
+            } else if flags & TWO_BY_TWO != 0 {
+                (a, b, c, d) = (
+                    f2dot14(at)?,
+                    f2dot14(at + 2)?,
+                    f2dot14(at + 4)?,
+                    f2dot14(at + 6)?,
+                );
+                at += 8;
+            }
+            for contour in self.contours(glyph, steps, depth + 1)? {
+                outline.push(
+                    contour
+                        .into_iter()
+                        .map(|Point { x, y }| Point {
+                            x: a * x + c * y + dx,
+                            y: b * x + d * y + dy,
+                        })
+                        .collect(),
+                );
+            }
+            if flags & MORE == 0 {
+                return Some(outline);
+            }
+        }
+    }
+}
+
+/// a contour of on- and off-curve points as straight lines, each quadratic curve in `steps`
+fn flatten(contour: &[(Point, bool)], steps: usize) -> Vec<Point> {
+    let n = contour.len();
+    if n == 0 {
+        return vec![];
+    }
+    let mid = |a: Point, b: Point| Point {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    };
+    // start on the curve: at an on-curve point, or between the first two off-curve ones
+    let (start, from) = match contour.iter().position(|(_, on)| *on) {
+        Some(i) => (contour[i].0, i),
+        None => (mid(contour[0].0, contour[1 % n].0), 0),
+    };
+    let mut points = vec![start];
+    let mut control: Option<Point> = None;
+    for step in 1..=n {
+        let (point, on) = contour[(from + step) % n];
+        let last = *points.last().expect("starts with one");
+        match (control, on) {
+            (None, true) => points.push(point),
+            (None, false) => control = Some(point),
+            (Some(c), true) => {
+                curve(&mut points, last, c, point, steps);
+                control = None;
+            }
+            (Some(c), false) => {
+                let between = mid(c, point);
+                curve(&mut points, last, c, between, steps);
+                control = Some(point);
+            }
+        }
+    }
+    if let Some(c) = control {
+        let last = *points.last().expect("starts with one");
+        curve(&mut points, last, c, start, steps);
+    }
+    // the loop came back round to the start
+    if points.len() > 1 && points.last() == points.first() {
+        points.pop();
+    }
+    points
+}
+
+fn curve(points: &mut Vec<Point>, from: Point, control: Point, to: Point, steps: usize) {
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        let u = 1.0 - t;
+        points.push(Point {
+            x: u * u * from.x + 2.0 * u * t * control.x + t * t * to.x,
+            y: u * u * from.y + 2.0 * u * t * control.y + t * t * to.y,
+        });
+    }
+}
+
+/// `text` in `font`, `size` pixels to the em, placed at `anchor` on a `width` by `height` canvas:
+/// a polygon per glyph, its contours joined into one by there-and-back seams, so the renderers'
+/// even-odd filling leaves the holes in letters like `o` open
+pub fn layout(
+    text: &str,
+    font: &Font,
+    size: f32,
+    anchor: Anchor,
+    width: u32,
+    height: u32,
+) -> Vec<Polygon> {
+    let canvas = Rectangle::new(0.0, 0.0, width as f32, height as f32);
+    layout_in(text, font, size, anchor, &canvas)
+}
+
+/// like [`layout`], but placed at `anchor` in `bounds` rather than the whole canvas
+pub fn layout_in(
+    text: &str,
+    font: &Font,
+    size: f32,
+    anchor: Anchor,
+    bounds: &Rectangle,
+) -> Vec<Polygon> {
+    let scale = size / font.units_per_em;
+    let line_height = (font.ascender - font.descender + font.line_gap) * scale;
+    let lines: Vec<&str> = text.lines().collect();
+    let line_width = |line: &str| -> f32 {
+        line.chars()
+            .map(|c| font.advance(font.glyph(c)) * scale)
+            .sum()
+    };
+    let (block_width, block_height) = measure(text, font, size);
+
+    let margin = size / 2.0;
+    let (across, down) = anchor.position();
+    let left = bounds.x + margin + (bounds.width - 2.0 * margin - block_width) * across;
+    let top = bounds.y + margin + (bounds.height - 2.0 * margin - block_height) * down;
+    // finer curves for bigger letters, a step every few pixels of em
+    let steps = ((size / 8.0).ceil() as usize).clamp(2, 16);
+
+    let mut polygons = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        let baseline = top + font.ascender * scale + row as f32 * line_height;
+        let mut x = left + (block_width - line_width(line)) * across;
+        for c in line.chars() {
+            let glyph = font.glyph(c);
+            let contours = font.contours(glyph, steps, 0).unwrap_or_default();
+            let contours: Vec<Vec<Point>> = contours
+                .into_iter()
+                .filter(|contour| contour.len() >= 3)
+                .map(|contour| {
+                    contour
+                        .into_iter()
+                        .map(|Point { x: u, y: v }| Point {
+                            x: x + u * scale,
+                            y: baseline - v * scale,
+                        })
+                        .collect()
+                })
+                .collect();
+            if let Some(polygon) = joined(&contours) {
+                polygons.push(polygon);
+            }
+            x += font.advance(glyph) * scale;
+        }
+    }
+    polygons
+}
+
+/// how wide and tall `text` comes out in `font` at `size` pixels to the em, from the first line's
+/// ascender to the last's descender
+pub fn measure(text: &str, font: &Font, size: f32) -> (f32, f32) {
+    let scale = size / font.units_per_em;
+    let line_height = (font.ascender - font.descender + font.line_gap) * scale;
+    let lines: Vec<&str> = text.lines().collect();
+    let width = lines
+        .iter()
+        .map(|line| -> f32 {
+            line.chars()
+                .map(|c| font.advance(font.glyph(c)) * scale)
+                .sum()
+        })
+        .fold(0.0, f32::max);
+    let height = lines.len().saturating_sub(1) as f32 * line_height
+        + (font.ascender - font.descender) * scale;
+    (width, height)
+}
+
+/// `card` in `font`, placed in `bounds`: the area to keep calm behind it, half a title em round
+/// the words, and the words' polygons
+pub fn card(card: &Card, font: &Font, bounds: &Rectangle) -> (Rectangle, Vec<Polygon>) {
+    // a ninth of the height, or less if that'd run into the sides
+    let mut size = bounds.height / 9.0;
+    let (width, _) = measure(&card.title, font, size);
+    if width > 0.0 {
+        size = size
+            .min(size * (bounds.width - 2.0 * size) / width)
+            .max(1.0);
+    }
+    let small = size / 2.0;
+    let (title_width, title_height) = measure(&card.title, font, size);
+    let (subtitle_width, subtitle_height) = card
+        .subtitle
+        .as_deref()
+        .map_or((0.0, 0.0), |subtitle| measure(subtitle, font, small));
+    let gap = if card.subtitle.is_some() {
+        small / 2.0
+    } else {
+        0.0
+    };
+    let block_width = title_width.max(subtitle_width);
+    let block_height = title_height + gap + subtitle_height;
+
+    let (across, down) = card.anchor.position();
+    let left = bounds.x + size + (bounds.width - 2.0 * size - block_width) * across;
+    let top = bounds.y + size + (bounds.height - 2.0 * size - block_height) * down;
+    // each part in a strip as wide as the block, lined up across like the anchor says
+    let aligned = match across {
+        across if across < 0.5 => Anchor::TopLeft,
+        across if across > 0.5 => Anchor::TopRight,
+        _ => Anchor::Top,
+    };
+    // layout_in keeps half an em in from what it's given
+    let strip = |y: f32, height: f32, size: f32| {
+        Rectangle::new(
+            left - size / 2.0,
+            y - size / 2.0,
+            block_width + size,
+            height + size,
+        )
+    };
+    let mut polygons = layout_in(
+        &card.title,
+        font,
+        size,
+        aligned,
+        &strip(top, title_height, size),
+    );
+    if let Some(subtitle) = &card.subtitle {
+        let y = top + title_height + gap;
+        polygons.extend(layout_in(
+            subtitle,
+            font,
+            small,
+            aligned,
+            &strip(y, subtitle_height, small),
+        ));
+    }
+    let area = Rectangle::new(
+        left - size / 2.0,
+        top - size / 2.0,
+        block_width + size,
+        block_height + size,
+    );
+    (area, polygons)
+}
+
+/// `contours` as one polygon: each one after the first is gone to from the first's start, round,
+/// and back, along the same line both ways so it adds no crossings of its own
+fn joined(contours: &[Vec<Point>]) -> Option<Polygon> {
+    let (first, rest) = contours.split_first()?;
+    let home = first[0];
+    let mut points = first.clone();
+    points.push(home);
+    for contour in rest {
+        points.extend_from_slice(contour);
+        points.push(contour[0]);
+        points.push(home);
+    }
+    Some(Polygon::new(points))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    /// a font with the tables it needs and one glyph, a 400 by 700 box for `A`, 600 units
+    /// across in a 1000-unit em
+    fn font_with(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut data = u16s(&[1, 0, tables.len() as u16, 0, 0, 0]);
+        let mut offset = 12 + 16 * tables.len();
+        let mut bodies = vec![];
+        for (tag, body) in tables {
+            data.extend_from_slice(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((body.len() as u32).to_be_bytes());
+            offset += body.len();
+            bodies.extend_from_slice(body);
+        }
+        data.extend(bodies);
+        data
+    }
+
+    fn tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let maxp = u16s(&[0, 0x5000, 2]);
+        let mut hhea = vec![0; 36];
+        hhea[4..10].copy_from_slice(&u16s(&[800, -200i16 as u16, 0]));
+        hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+        let hmtx = u16s(&[500, 0, 600, 0]);
+        // one contour of four on-curve points, every coordinate a whole i16 difference
+        let mut glyf = u16s(&[1, 100, 0, 500, 700, 3, 0]);
+        glyf.extend([1; 4]);
+        glyf.extend(u16s(&[100, 0, 400, 0, 0, 700, 0, -700i16 as u16]));
+        glyf.extend([0; 2]);
+        let loca = u16s(&[0, 0, glyf.len() as u16 / 2]);
+        // Windows Unicode, format 4: `A` to glyph 1, and the segment that ends every map
+        let mut cmap = u16s(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(u16s(&[4, 32, 0, 4, 4, 1, 0]));
+        cmap.extend(u16s(&[
+            0x41,
+            0xffff,
+            0,
+            0x41,
+            0xffff,
+            1u16.wrapping_sub(0x41),
+            1,
+            0,
+            0,
+        ]));
+        vec![
+            (b"head", head),
+            (b"maxp", maxp),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+        ]
+    }
+
+    fn font() -> Font {
+        Font::parse(font_with(&tables())).expect("the test font parses")
+    }
+
+    fn points(points: &[(f32, f32)]) -> Vec<Point> {
+        points.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn reads_glyphs_and_metrics() {
+        let font = font();
+        assert_eq!(font.glyph('A'), 1);
+        assert_eq!(font.glyph('B'), 0);
+        assert_eq!(font.advance(1), 600.0);
+        assert_eq!(font.advance(0), 500.0);
+        assert_eq!(
+            font.contours(1, 4, 0),
+            Some(vec![points(&[
+                (100.0, 0.0),
+                (100.0, 700.0),
+                (500.0, 700.0),
+                (500.0, 0.0)
+            ])])
+        );
+        assert_eq!(font.contours(0, 4, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn lays_out_in_pixels() {
+        let font = font();
+        // a tenth of a pixel a unit, half an em in from the corner, y down from the baseline
+        let polygons = layout("A", &font, 100.0, Anchor::TopLeft, 1000, 1000);
+        assert_eq!(
+            polygons,
+            vec![Polygon::new(points(&[
+                (60.0, 130.0),
+                (60.0, 60.0),
+                (100.0, 60.0),
+                (100.0, 130.0),
+                (60.0, 130.0)
+            ]))]
+        );
+        assert_eq!(measure("AA\nA", &font, 100.0), (120.0, 200.0));
+    }
+
+    #[test]
+    fn malformed_fonts_are_refused() {
+        let short = Some("it's cut short, or isn't a font");
+        assert_eq!(Font::parse(vec![]).err(), short);
+        assert_eq!(Font::parse(b"wxyz".repeat(4)).err(), short);
+        assert_eq!(
+            Font::parse(b"OTTO".to_vec()).err(),
+            Some("it has PostScript outlines, and only TrueType ones work")
+        );
+
+        let mut data = font_with(&tables());
+        data.truncate(100);
+        assert!(Font::parse(data).is_err());
+
+        let mut missing = tables();
+        missing.retain(|(tag, _)| *tag != b"glyf");
+        assert_eq!(
+            Font::parse(font_with(&missing)).err(),
+            Some("it's missing a table TrueType fonts need")
+        );
+
+        // a Macintosh map only
+        let mut mac = tables();
+        mac[5].1[4..6].copy_from_slice(&1u16.to_be_bytes());
+        assert_eq!(
+            Font::parse(font_with(&mac)).err(),
+            Some("it has no Unicode character map")
+        );
+    }
+
+    #[test]
+    fn expands_templates() {
+        let fields = Fields {
+            seed: 7,
+            width: 640,
+            height: 480,
+            levels: 5,
+            // 2024-02-29 09:41:30
+            now: UNIX_EPOCH + Duration::from_secs(19_782 * 86_400 + 9 * 3600 + 41 * 60 + 30),
+        };
+        assert_eq!(
+            expand("{seed} {date} {time} {width}x{height} {levels}", &fields),
+            Ok("7 2024-02-29 09:41 640x480 5".to_owned())
+        );
+        assert_eq!(expand("{{seed}} }{", &fields), Ok("{seed} }{".to_owned()));
+        assert_eq!(
+            expand("{moon}", &fields),
+            Err(TextError::Template("moon".to_owned()))
+        );
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil(0), (1970, 1, 1));
+        assert_eq!(civil(-1), (1969, 12, 31));
+        assert_eq!(civil(11_016), (2000, 2, 29));
+        assert_eq!(civil(19_782), (2024, 2, 29));
+        assert_eq!(civil(19_783), (2024, 3, 1));
+    }
+}
@@ -0,0 +1,198 @@
+//! data art from a series, e.g. commits or the temperature a day: one strip standing up per
+//! value, left to right, cut finer and colored further along the palette the higher it is
+
+use std::{error::Error, fmt, fs, io, ops::Range, path::Path};
+
+use image::Rgb;
+use rand::Rng;
+
+use crate::{
+    color::Palette,
+    fill::FillOptions,
+    geometry::{Rectangle, SplitOptions},
+    tree::Tree,
+};
+
+/// why a CSV file couldn't be read as a series
+#[derive(Debug)]
+pub enum TimelineError {
+    Io(io::Error),
+    /// there's no header line to find the column in
+    Empty,
+    /// the header has no column by that name
+    NoColumn {
+        column: String,
+        header: Vec<String>,
+    },
+    /// a line whose value in the column isn't a number
+    Line {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the data: {err}"),
+            Self::Empty => write!(f, "there's no header line naming the columns"),
+            Self::NoColumn { column, header } => write!(
+                f,
+                "there's no column {column:?}; there's {}",
+                header.join(", ")
+            ),
+            Self::Line { line, text } => {
+                write!(f, "line {line}: the column isn't a number in {text:?}")
+            }
+        }
+    }
+}
+
+impl Error for TimelineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TimelineError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// [`parse_csv`] of the file at `path`
+pub fn read(path: impl AsRef<Path>, column: Option<&str>) -> Result<Vec<f64>, TimelineError> {
+    parse_csv(&fs::read_to_string(path)?, column)
+}
+
+/// the numbers in `column`, or the last column, of CSV with a header line naming them; fields
+/// are split at every comma, so quoted ones can't have commas in them
+///
+/// blank lines and `#` comments are skipped
+pub fn parse_csv(text: &str, column: Option<&str>) -> Result<Vec<f64>, TimelineError> {
+    let unquoted = |field: &str| {
+        let field = field.trim();
+        field
+            .strip_prefix('"')
+            .and_then(|field| field.strip_suffix('"'))
+            .unwrap_or(field)
+            .to_owned()
+    };
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !(line.is_empty() || line.starts_with('#')));
+    let (_, header) = lines.next().ok_or(TimelineError::Empty)?;
+    let header: Vec<String> = header.split(',').map(unquoted).collect();
+    let index = match column {
+        Some(column) => header
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| TimelineError::NoColumn {
+                column: column.to_owned(),
+                header: header.clone(),
+            })?,
+        None => header.len() - 1,
+    };
+    lines
+        .map(|(line, text)| {
+            text.split(',')
+                .nth(index)
+                .and_then(|field| unquoted(field).parse().ok())
+                .ok_or_else(|| TimelineError::Line {
+                    line,
+                    text: text.to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// `values` from 0 for the lowest to 1 for the highest; a series that never changes is all
+/// halfway
+fn shares(values: &[f64]) -> Vec<f64> {
+    let low = values.iter().copied().fold(f64::MAX, f64::min);
+    let high = values.iter().copied().fold(f64::MIN, f64::max);
+    values
+        .iter()
+        .map(|value| match high - low {
+            range if range > 0.0 => (value - low) / range,
+            _ => 0.5,
+        })
+        .collect()
+}
+
+/// a strip as wide as every other for each of `values`, then each strip cut across its long side
+/// as many of `levels` deep as its value is high, somewhere around the middle
+pub fn layout<R: Rng + ?Sized>(
+    bounds: Rectangle,
+    values: &[f64],
+    levels: usize,
+    options: &SplitOptions,
+    rng: &mut R,
+) -> Tree<Rectangle> {
+    let shares = shares(values);
+    let mut tree = Tree::new(bounds);
+    // the values the nodes the tree hasn't reached yet are strips for, and how many times
+    // they've been cut since they were down to a single one
+    let mut parts: Vec<(Range<usize>, usize)> = vec![(0..values.len(), 0)];
+    tree.split_by(|rectangle, _| {
+        let (items, depth) = parts.pop().expect("one part per node");
+        if items.len() > 1 {
+            let middle = items.start + items.len() / 2;
+            let t = (middle - items.start) as f32 / items.len() as f32;
+            parts.push((middle..items.end, 0));
+            parts.push((items.start..middle, 0));
+            return Some(rectangle.divide(true, t, options));
+        }
+        let deepest = (levels as f64 * shares[items.start]).round() as usize;
+        // nothing left to split once a pane is down to a pixel
+        if depth >= deepest || rectangle.width.max(rectangle.height) < 2.0 {
+            return None;
+        }
+        parts.push((items.clone(), depth + 1));
+        parts.push((items, depth + 1));
+        let t = rng.gen_range(0.3..0.7);
+        Some(rectangle.divide(rectangle.width >= rectangle.height, t, options))
+    });
+    tree
+}
+
+/// a color for each of `leaves`, which cover `bounds` in [`layout`]'s strips: the palette read
+/// as a scale, first color lowest and each as much of it as its weight, and each pane somewhere
+/// between halfway up to its strip's value and all the way
+pub fn colors<R: Rng + ?Sized>(
+    leaves: &[Rectangle],
+    bounds: &Rectangle,
+    values: &[f64],
+    palette: &Palette,
+    options: &FillOptions,
+    rng: &mut R,
+) -> Vec<Rgb<u8>> {
+    let shares = shares(values);
+    let total: f64 = palette.weights().iter().map(|&weight| weight as f64).sum();
+    leaves
+        .iter()
+        .map(|leaf| {
+            if !rng.gen_bool(options.probability as f64) {
+                return options.background;
+            }
+            let middle =
+                (leaf.x + leaf.width / 2.0 - bounds.x) / bounds.width.max(f32::MIN_POSITIVE);
+            let strip = ((middle * values.len() as f32) as usize).min(values.len() - 1);
+            let mut along = shares[strip] * rng.gen_range(0.5..=1.0) * total;
+            for (color, &weight) in palette.colors().iter().zip(palette.weights()) {
+                if along < weight as f64 {
+                    return *color;
+                }
+                along -= weight as f64;
+            }
+            // the very top of the scale
+            let last = palette.weights().iter().rposition(|&weight| weight > 0);
+            last.map_or(options.background, |last| palette.colors()[last])
+        })
+        .collect()
+}
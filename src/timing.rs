@@ -0,0 +1,63 @@
+//! how long each step of making an image took, e.g. to see which one got slower
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::trace::{self, Level};
+
+/// steps and how long they took, in the order they happened; the same step twice adds up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// runs `f` in a [span](crate::trace::span) named `phase`, counting the time towards it
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let span = trace::span(Level::Info, phase, &[]);
+        let value = f();
+        drop(span);
+        self.record(phase, start.elapsed());
+        value
+    }
+
+    pub fn record(&mut self, phase: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase, duration)),
+        }
+    }
+
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, duration)| *duration).sum()
+    }
+}
+
+/// a line per step in milliseconds, then the total
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .phases
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        for (name, duration) in &self.phases {
+            writeln!(f, "{name:<width$}  {:>9.2} ms", ms(*duration))?;
+        }
+        writeln!(f, "{:<width$}  {:>9.2} ms", "total", ms(self.total()))
+    }
+}
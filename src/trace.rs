@@ -0,0 +1,110 @@
+//! structured logging, tracing-style: [spans](span) around the big steps (building, splitting,
+//! coloring, rendering, encoding) and [events](event) inside them, each with `key=value` fields.
+//! events go out through the [`log`] crate, with their fields as its key-values, to whatever
+//! logger the application sets up; what spans are open on the thread is there in [`spans`] for
+//! it to write too. nothing is logged, and no fields are worked out, unless the logger wants it
+//!
+//! ```text
+//!  INFO build{seed=7 levels=8}:split: close elapsed_ms=0.08
+//! DEBUG build{seed=7 levels=8}: pane x=0 y=0 width=480 height=270 depth=3 color=#ffffff
+//! ```
+
+use std::{cell::RefCell, fmt, time::Instant};
+
+pub use log::Level;
+use log::{
+    kv::{self, Key, Source, Value, VisitSource},
+    Record,
+};
+
+/// what events and spans are logged as
+pub const TARGET: &str = "mondrian";
+
+/// `key=value`s for [`event`] and [`span`], written with their `Display`
+pub type Fields<'a> = &'a [(&'a str, &'a dyn fmt::Display)];
+
+/// fields once they've been written out
+pub type Written = Vec<(String, String)>;
+
+thread_local! {
+    /// the spans open on this thread, outermost first, fields already written out
+    static SPANS: RefCell<Vec<(&'static str, Written)>> = const { RefCell::new(vec![]) };
+}
+
+/// whether anything at `level` is logged, e.g. to skip working out fields nobody will see
+pub fn enabled(level: Level) -> bool {
+    log::log_enabled!(target: TARGET, level)
+}
+
+/// logs `message` and `fields`, in whatever spans are open
+pub fn event(level: Level, message: &str, fields: Fields) {
+    if !enabled(level) {
+        return;
+    }
+    log::logger().log(
+        &Record::builder()
+            .level(level)
+            .target(TARGET)
+            .args(format_args!("{message}"))
+            .key_values(&Pairs(fields))
+            .build(),
+    );
+}
+
+/// calls `f` with the spans open on this thread, outermost first, and their fields
+pub fn spans<T>(f: impl FnOnce(&[(&'static str, Written)]) -> T) -> T {
+    SPANS.with(|spans| f(&spans.borrow()))
+}
+
+/// an event's fields as the key-values `log` hands the logger
+struct Pairs<'a>(Fields<'a>);
+
+impl Source for Pairs<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), kv::Error> {
+        for (key, value) in self.0 {
+            visitor.visit_pair(Key::from_str(key), Value::from_dyn_display(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// a step that events are logged inside of until it's dropped, which logs a `close` event with
+/// how long it took
+#[must_use = "the span closes as soon as it's dropped"]
+pub struct Span {
+    level: Level,
+    start: Instant,
+    open: bool,
+}
+
+/// opens a span called `name`, if `level` is logged
+pub fn span(level: Level, name: &'static str, fields: Fields) -> Span {
+    let open = enabled(level);
+    if open {
+        let fields = fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        SPANS.with(|spans| spans.borrow_mut().push((name, fields)));
+    }
+    Span {
+        level,
+        start: Instant::now(),
+        open,
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !self.open {
+            return;
+        }
+        let elapsed = self.start.elapsed().as_secs_f64() * 1000.0;
+        event(
+            self.level,
+            "close",
+            &[("elapsed_ms", &format_args!("{elapsed:.2}"))],
+        );
+        SPANS.with(|spans| spans.borrow_mut().pop());
+    }
+}
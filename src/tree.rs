@@ -0,0 +1,566 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, TryReserveError},
+    fmt,
+    ops::Range,
+    str::FromStr,
+};
+
+use clap::ValueEnum;
+use rand::{Rng, RngCore};
+
+use crate::{
+    geometry::{Edge, Orientation, Rectangle, SplitOptions, SplittableGraphic},
+    strategy::{Random, SplitStrategy},
+};
+
+/// every node lives in one flat Vec in depth-first order, so a node's subtree is the run of nodes
+/// right after it and the leaves come out left to right just by walking the Vec
+#[derive(Debug, Clone)]
+pub struct Tree<P>
+where
+    P: SplittableGraphic + Clone,
+{
+    nodes: Vec<Node<P>>,
+}
+
+#[derive(Debug, Clone)]
+struct Node<P> {
+    item: P,
+    depth: usize,
+    /// indices of the two halves `item` was split into
+    children: Option<(usize, usize)>,
+}
+
+/// one node of a [`Tree`] and everything under it
+#[derive(Debug)]
+pub struct Subtree<'a, P> {
+    nodes: &'a [Node<P>],
+    index: usize,
+}
+
+// derive would want P: Clone/Copy, but we only hold a reference
+impl<P> Clone for Subtree<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for Subtree<'_, P> {}
+
+impl<P> Tree<P>
+where
+    P: SplittableGraphic + Clone,
+{
+    pub fn new(item: P) -> Self {
+        Self {
+            nodes: vec![Node {
+                item,
+                depth: 0,
+                children: None,
+            }],
+        }
+    }
+
+    /// if max_depth is not fulfilled, call P's split until it is; any earlier splits are thrown away
+    ///
+    /// fails without splitting anything if the whole tree can't be allocated up front
+    pub fn split<R: RngCore>(
+        &mut self,
+        max_depth: usize,
+        options: &SplitOptions,
+        rng: &mut R,
+    ) -> Result<(), TryReserveError> {
+        self.split_with(max_depth, &Random, options, rng)
+    }
+
+    /// like [`split`](Self::split), but `strategy` decides every cut instead of P
+    pub fn split_with(
+        &mut self,
+        max_depth: usize,
+        strategy: &dyn SplitStrategy<P>,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), TryReserveError> {
+        self.nodes.truncate(1);
+        self.nodes[0].children = None;
+        // a full binary tree has 2^(max_depth + 1) - 1 nodes, and the root is already in
+        let count = u32::try_from(max_depth.saturating_add(1))
+            .ok()
+            .and_then(|bits| 1usize.checked_shl(bits))
+            .map_or(usize::MAX, |count| count - 2);
+        self.nodes.try_reserve_exact(count)?;
+
+        self.split_by(|item, depth| {
+            (depth < max_depth).then(|| strategy.split(item, depth, options, rng))
+        });
+        Ok(())
+    }
+
+    /// builds the tree from the root down, throwing away any earlier splits: `split` sees every
+    /// node with its depth, parents before children and a left subtree before its right sibling,
+    /// and returns the two halves to split it into, or `None` to leave it a leaf
+    pub fn split_by(&mut self, mut split: impl FnMut(&P, usize) -> Option<(P, P)>) {
+        self.nodes.truncate(1);
+        self.nodes[0].children = None;
+
+        // right halves waiting for their left sibling's subtree to finish, with their parent's
+        // index; they come off in the order the recursive version visited them, so the rng is
+        // consumed in the same order too
+        let mut pending: Vec<(P, usize, usize)> = vec![];
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if let Some((left, right)) = split(&node.item, node.depth) {
+                let depth = node.depth + 1;
+                pending.push((right, depth, current));
+
+                // the left subtree is finished before the right child goes in, to stay
+                // depth-first
+                let left_index = self.push(left, depth);
+                self.nodes[current].children = Some((left_index, 0));
+                current = left_index;
+                continue;
+            }
+
+            let Some((right, depth, parent)) = pending.pop() else {
+                break;
+            };
+            let right_index = self.push(right, depth);
+            if let Some((_, slot)) = &mut self.nodes[parent].children {
+                *slot = right_index;
+            }
+            current = right_index;
+        }
+    }
+
+    fn push(&mut self, item: P, depth: usize) -> usize {
+        self.nodes.push(Node {
+            item,
+            depth,
+            children: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    pub fn root(&self) -> Subtree<'_, P> {
+        Subtree {
+            nodes: &self.nodes,
+            index: 0,
+        }
+    }
+
+    /// left to right (or top to bottom), without allocating
+    pub fn leaves(&self) -> impl Iterator<Item = &P> + '_ {
+        self.root().leaves()
+    }
+
+    /// the graphic the root covers; see [`Subtree::item`]
+    pub fn item(&self) -> &P {
+        &self.nodes[0].item
+    }
+
+    pub fn left(&self) -> Option<Subtree<'_, P>> {
+        self.root().left()
+    }
+
+    pub fn right(&self) -> Option<Subtree<'_, P>> {
+        self.root().right()
+    }
+
+    pub fn depth(&self) -> usize {
+        0
+    }
+
+    /// how many splits down each leaf is, in the order of [`leaves`](Self::leaves)
+    pub fn leaf_depths(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .filter(|node| node.children.is_none())
+            .map(|node| node.depth)
+    }
+
+    /// how many splits down the deepest leaf is
+    pub fn max_depth(&self) -> usize {
+        self.nodes.iter().map(|node| node.depth).max().unwrap_or(0)
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.root().is_leaf()
+    }
+
+    /// the node at `index`, as found by [`leaf`](Self::leaf) or [`parent`](Self::parent)
+    pub fn node(&self, index: usize) -> Subtree<'_, P> {
+        self.root().at(index)
+    }
+
+    /// the index of the `n`th leaf from the left, if there are that many
+    pub fn leaf(&self, n: usize) -> Option<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.children.is_none())
+            .nth(n)
+            .map(|(index, _)| index)
+    }
+
+    /// the index of the node that node `index` was split from, or `None` for the root
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        // parents always come before their children
+        self.nodes[..index].iter().rposition(
+            |node| matches!(node.children, Some((left, right)) if left == index || right == index),
+        )
+    }
+
+    /// which leaves, counting from the left, are under node `index`
+    pub fn leaf_range(&self, index: usize) -> Range<usize> {
+        let is_leaf = |node: &&Node<P>| node.children.is_none();
+        let before = self.nodes[..index].iter().filter(is_leaf).count();
+        before..before + self.node(index).nodes().iter().filter(is_leaf).count()
+    }
+
+    /// splits the leaf at `index` into `left` and `right`, one level down; the rest of the tree
+    /// stays as it was
+    ///
+    /// panics if the node isn't a leaf
+    pub fn split_node(&mut self, index: usize, (left, right): (P, P)) {
+        let node = &self.nodes[index];
+        assert!(node.children.is_none(), "only leaves can be split");
+        let depth = node.depth + 1;
+        // the halves go right after it, so everything later moves along two
+        self.renumber(index, |child| child + 2);
+        let halves = [left, right].map(|item| Node {
+            item,
+            depth,
+            children: None,
+        });
+        self.nodes.splice(index + 1..index + 1, halves);
+        self.nodes[index].children = Some((index + 1, index + 2));
+    }
+
+    /// turns the node at `index` back into a leaf, throwing away everything under it
+    pub fn merge(&mut self, index: usize) {
+        let under = self.node(index).nodes().len() - 1;
+        self.nodes.drain(index + 1..=index + under);
+        self.nodes[index].children = None;
+        self.renumber(index, |child| child - under);
+    }
+
+    /// applies `change` to every child index past `index`
+    fn renumber(&mut self, index: usize, change: impl Fn(usize) -> usize) {
+        for node in &mut self.nodes {
+            if let Some((left, right)) = &mut node.children {
+                for child in [left, right] {
+                    if *child > index {
+                        *child = change(*child);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, P> Subtree<'a, P> {
+    fn node(&self) -> &'a Node<P> {
+        &self.nodes[self.index]
+    }
+
+    fn at(&self, index: usize) -> Self {
+        Self {
+            nodes: self.nodes,
+            index,
+        }
+    }
+
+    /// the graphic this node covers; for nodes with children this is the area before splitting
+    pub fn item(&self) -> &'a P {
+        &self.node().item
+    }
+
+    pub fn left(&self) -> Option<Self> {
+        let (left, _) = self.node().children?;
+        Some(self.at(left))
+    }
+
+    pub fn right(&self) -> Option<Self> {
+        let (_, right) = self.node().children?;
+        Some(self.at(right))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.node().depth
+    }
+
+    /// where it is in its tree, for [`Tree::node`] and the like
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.node().children.is_none()
+    }
+
+    /// this node and everything under it, parents before children
+    fn nodes(&self) -> &'a [Node<P>] {
+        // the last node under us is at the end of the rightmost path down
+        let mut last = self.index;
+        while let Some((_, right)) = self.nodes[last].children {
+            last = right;
+        }
+        &self.nodes[self.index..=last]
+    }
+
+    pub fn leaves(&self) -> impl Iterator<Item = &'a P> + 'a {
+        self.nodes()
+            .iter()
+            .filter(|node| node.children.is_none())
+            .map(|node| &node.item)
+    }
+}
+
+/// which leaf [`Tree::grow`] splits next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Pick {
+    /// the biggest, so the panes come out about the same size
+    #[default]
+    Largest,
+    /// any of them, the bigger the likelier, so a few big panes are left among the small ones
+    Weighted,
+}
+
+impl fmt::Display for Pick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl FromStr for Pick {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+    }
+}
+
+/// a leaf by its area, biggest first, and the earliest of those that are as big
+#[derive(Debug, PartialEq)]
+struct Biggest(f64, usize);
+
+impl Eq for Biggest {}
+
+impl PartialOrd for Biggest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Biggest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .total_cmp(&other.0)
+            .then_with(|| other.1.cmp(&self.1))
+    }
+}
+
+impl Tree<Rectangle> {
+    /// splits one leaf at a time, whichever `pick` says, until there are `panes` of them or none
+    /// is left that's at least 2 pixels some way; any earlier splits are thrown away
+    pub fn grow(
+        &mut self,
+        panes: usize,
+        pick: Pick,
+        strategy: &dyn SplitStrategy<Rectangle>,
+        options: &SplitOptions,
+        rng: &mut dyn RngCore,
+    ) {
+        let splittable = |item: &Rectangle| item.width.max(item.height) >= 2.0;
+        // grown on the side, since splitting a leaf in the depth-first Vec moves every node after
+        // it along, and the queue's indices with them
+        let mut nodes = vec![Node {
+            item: self.nodes[0].item,
+            depth: 0,
+            children: None,
+        }];
+        let mut queue = BinaryHeap::from([Biggest(nodes[0].item.area(), 0)]);
+        let mut leaves = vec![0];
+        while leaves.len() < panes {
+            let index = match pick {
+                Pick::Largest => loop {
+                    let Some(Biggest(_, index)) = queue.pop() else {
+                        break None;
+                    };
+                    if splittable(&nodes[index].item) {
+                        break Some(index);
+                    }
+                },
+                Pick::Weighted => {
+                    let candidates: Vec<usize> = leaves
+                        .iter()
+                        .copied()
+                        .filter(|&index| splittable(&nodes[index].item))
+                        .collect();
+                    let total: f64 = candidates.iter().map(|&i| nodes[i].item.area()).sum();
+                    let mut point = rng.gen::<f64>() * total;
+                    candidates
+                        .iter()
+                        .copied()
+                        .find(|&index| {
+                            point -= nodes[index].item.area();
+                            point < 0.0
+                        })
+                        .or(candidates.last().copied())
+                }
+            };
+            let Some(index) = index else {
+                break;
+            };
+            let node = &nodes[index];
+            let depth = node.depth + 1;
+            let (left, right) = strategy.split(&node.item, node.depth, options, rng);
+            let first = nodes.len();
+            for item in [left, right] {
+                queue.push(Biggest(item.area(), nodes.len()));
+                nodes.push(Node {
+                    item,
+                    depth,
+                    children: None,
+                });
+            }
+            nodes[index].children = Some((first, first + 1));
+            leaves.retain(|&leaf| leaf != index);
+            leaves.extend([first, first + 1]);
+        }
+
+        // split_by visits them parents first, then the left subtree, then the right
+        let mut order = vec![];
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            order.push(index);
+            if let Some((left, right)) = nodes[index].children {
+                stack.extend([right, left]);
+            }
+        }
+        let mut order = order.into_iter();
+        self.split_by(|_, _| {
+            let (left, right) = nodes[order.next()?].children?;
+            Some((nodes[left].item, nodes[right].item))
+        });
+    }
+
+    /// every line where a split happened, each exactly once, parents before children
+    pub fn edges(&self) -> Vec<Edge> {
+        self.nodes
+            .iter()
+            .filter_map(|node| {
+                let (left, right) = node.children?;
+                let (left_item, right_item) = (&self.nodes[left].item, &self.nodes[right].item);
+                let edge = if left_item.y == right_item.y {
+                    // side by side, so the line between them runs top to bottom
+                    Edge {
+                        x: right_item.x,
+                        y: node.item.y,
+                        length: node.item.height,
+                        orientation: Orientation::Vertical,
+                        depth: node.depth,
+                    }
+                } else {
+                    Edge {
+                        x: node.item.x,
+                        y: right_item.y,
+                        length: node.item.width,
+                        orientation: Orientation::Horizontal,
+                        depth: node.depth,
+                    }
+                };
+                Some(edge)
+            })
+            .collect()
+    }
+}
+
+/// nested nodes, `{"item":...,"children":[left,right]}`, with no `children` for leaves; depths
+/// and the order of the leaves come from the nesting
+#[cfg(feature = "serde")]
+impl<P> serde::Serialize for Tree<P>
+where
+    P: SplittableGraphic + Clone + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Nested(self.root()).serialize(serializer)
+    }
+}
+
+/// a [`Subtree`] as it's serialized
+#[cfg(feature = "serde")]
+struct Nested<'a, P>(Subtree<'a, P>);
+
+#[cfg(feature = "serde")]
+impl<P: serde::Serialize> serde::Serialize for Nested<'_, P> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let node = self.0;
+        let children: Vec<Nested<'_, P>> = node
+            .left()
+            .into_iter()
+            .chain(node.right())
+            .map(Nested)
+            .collect();
+        let mut nested = serializer.serialize_struct("Node", 1 + !children.is_empty() as usize)?;
+        nested.serialize_field("item", node.item())?;
+        if !children.is_empty() {
+            nested.serialize_field("children", &children)?;
+        }
+        nested.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, P> serde::Deserialize<'de> for Tree<P>
+where
+    P: SplittableGraphic + Clone + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let root = Grown::<P>::deserialize(deserializer)?;
+        let mut tree = Tree::new(root.item);
+        tree.graft(0, root.children)
+            .map_err(serde::de::Error::custom)?;
+        Ok(tree)
+    }
+}
+
+/// a node as it's deserialized, before it's put in a [`Tree`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound = "P: serde::Deserialize<'de>")]
+struct Grown<P> {
+    item: P,
+    #[serde(default)]
+    children: Vec<Grown<P>>,
+}
+
+#[cfg(feature = "serde")]
+impl<P> Tree<P>
+where
+    P: SplittableGraphic + Clone,
+{
+    /// splits the leaf at `index` into `children`, and them into theirs, all the way down
+    fn graft(&mut self, index: usize, children: Vec<Grown<P>>) -> Result<(), String> {
+        let [left, right]: [Grown<P>; 2] = match children.try_into() {
+            Ok(halves) => halves,
+            Err(children) if children.is_empty() => return Ok(()),
+            Err(children) => {
+                return Err(format!(
+                    "nodes have two children or none, not {}",
+                    children.len()
+                ))
+            }
+        };
+        self.split_node(index, (left.item, right.item));
+        // the right half moves along as the left one's split
+        let (left_index, _) = self.nodes[index].children.expect("just split");
+        self.graft(left_index, left.children)?;
+        let (_, right_index) = self.nodes[index].children.expect("just split");
+        self.graft(right_index, right.children)
+    }
+}
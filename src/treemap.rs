@@ -0,0 +1,238 @@
+//! laying panes out from data instead of at random: every pane's share of the canvas is its
+//! share of the total weight, e.g. bytes on disk or sales per category
+
+use std::{error::Error, fmt, fs, io, ops::Range, path::Path};
+
+use crate::{
+    geometry::{Rectangle, SplitOptions},
+    tree::Tree,
+};
+
+/// one labeled value to give a pane to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub label: String,
+    pub weight: f64,
+}
+
+/// why some input couldn't be read as [`Item`]s
+#[derive(Debug)]
+pub enum TreemapError {
+    Io(io::Error),
+    /// a CSV line with no weight we could find
+    Line {
+        line: usize,
+        text: String,
+    },
+    /// not JSON, or not JSON shaped like a list of items
+    Json(String),
+    /// JSON input without the `json` feature
+    Unsupported,
+}
+
+impl fmt::Display for TreemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read the data: {err}"),
+            Self::Line { line, text } => {
+                write!(f, "line {line}: expected `label,weight`, got {text:?}")
+            }
+            Self::Json(why) => write!(f, "couldn't read the JSON: {why}"),
+            Self::Unsupported => write!(
+                f,
+                "reading JSON needs mondrian-rs to be built with the `json` feature"
+            ),
+        }
+    }
+}
+
+impl Error for TreemapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TreemapError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// [`parse_json`] for `.json` files, [`parse_csv`] for anything else
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<Item>, TreemapError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        parse_json(&text)
+    } else {
+        parse_csv(&text)
+    }
+}
+
+/// one `label,weight` per line, split at the last comma so labels can have commas in them;
+/// lines with no comma are read as `weight label`, which is what `du` prints
+///
+/// blank lines and `#` comments are skipped, and so is a first line whose weight isn't a number,
+/// since that's a header
+pub fn parse_csv(text: &str) -> Result<Vec<Item>, TreemapError> {
+    let mut items = vec![];
+    let mut header = true;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (label, weight) = match line.rsplit_once(',') {
+            Some((label, weight)) => (label, weight),
+            None => match line.split_once(char::is_whitespace) {
+                Some((weight, label)) => (label, weight),
+                None => ("", line),
+            },
+        };
+        let Ok(weight) = weight.trim().parse() else {
+            if std::mem::take(&mut header) {
+                continue;
+            }
+            return Err(TreemapError::Line {
+                line: index + 1,
+                text: line.to_owned(),
+            });
+        };
+        header = false;
+        let label = label.trim();
+        let label = label
+            .strip_prefix('"')
+            .and_then(|label| label.strip_suffix('"'))
+            .unwrap_or(label);
+        items.push(Item {
+            label: label.to_owned(),
+            weight,
+        });
+    }
+    Ok(items)
+}
+
+/// either an object of `{"label": weight, ...}`, or an array of objects each with a `label` (or
+/// `name`) and a `weight` (or `value`, or `size`)
+#[cfg(feature = "json")]
+pub fn parse_json(text: &str) -> Result<Vec<Item>, TreemapError> {
+    use serde_json::Value;
+
+    let json = |why: &str| TreemapError::Json(why.to_owned());
+    let value: Value =
+        serde_json::from_str(text).map_err(|err| TreemapError::Json(err.to_string()))?;
+    match value {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(label, weight)| {
+                let weight = weight
+                    .as_f64()
+                    .ok_or_else(|| json(&format!("the value of {label:?} isn't a number")))?;
+                Ok(Item { label, weight })
+            })
+            .collect(),
+        Value::Array(values) => values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let field = |names: &[&str]| names.iter().find_map(|name| value.get(name));
+                let label = match field(&["label", "name"]) {
+                    Some(Value::String(label)) => label.clone(),
+                    Some(label) => label.to_string(),
+                    None => String::new(),
+                };
+                let weight = field(&["weight", "value", "size"])
+                    .and_then(Value::as_f64)
+                    .ok_or_else(|| json(&format!("item {index} has no numeric weight")))?;
+                Ok(Item { label, weight })
+            })
+            .collect(),
+        _ => Err(json("expected an object or an array at the top level")),
+    }
+}
+
+#[cfg(not(feature = "json"))]
+pub fn parse_json(_text: &str) -> Result<Vec<Item>, TreemapError> {
+    Err(TreemapError::Unsupported)
+}
+
+/// what a node of the layout still has to place
+#[derive(Debug, Clone)]
+enum Part {
+    /// these items, anywhere in the node
+    Free(Range<usize>),
+    /// these items in a single row, cut one after another the same way
+    Row(Range<usize>, bool),
+}
+
+/// lays `weights` out over `bounds` as a squarified treemap (Bruls, Huizing and van Wijk): rows
+/// along the short side, each growing for as long as that keeps its panes closer to square
+///
+/// the leaves come out in the same order as `weights`, each with its share of the area; sorting
+/// biggest first gives the squarest panes. `weights` should all be positive, and not empty
+pub fn layout(bounds: Rectangle, weights: &[f64], options: &SplitOptions) -> Tree<Rectangle> {
+    let sum = |items: &Range<usize>| weights[items.clone()].iter().sum::<f64>();
+    let mut tree = Tree::new(bounds);
+    // the parts of nodes the tree hasn't reached yet; it visits them in the order they come off
+    let mut parts = vec![Part::Free(0..weights.len())];
+    tree.split_by(|rectangle, _| {
+        let part = parts.pop().expect("one part per node");
+        let (items, horz_split) = match part {
+            Part::Free(items) | Part::Row(items, _) if items.len() <= 1 => return None,
+            Part::Row(items, horz_split) => (items, horz_split),
+            Part::Free(items) => {
+                let row = row_length(rectangle, &weights[items.clone()]);
+                // a row runs along the short side, so it's cut off across the long one and its
+                // own panes are cut the other way
+                let across_long = rectangle.width >= rectangle.height;
+                if row < items.len() {
+                    let (row, rest) =
+                        (items.start..items.start + row, items.start + row..items.end);
+                    let t = sum(&row) / sum(&items);
+                    parts.push(Part::Free(rest));
+                    parts.push(Part::Row(row, !across_long));
+                    return Some(rectangle.divide(across_long, t as f32, options));
+                }
+                (items, !across_long)
+            }
+        };
+        let (first, rest) = (items.start..items.start + 1, items.start + 1..items.end);
+        let t = sum(&first) / sum(&items);
+        parts.push(Part::Row(rest, horz_split));
+        parts.push(Part::Free(first));
+        Some(rectangle.divide(horz_split, t as f32, options))
+    });
+    tree
+}
+
+/// how many of `weights`, from the front, to lay in a row along `rectangle`'s short side: as many
+/// as keep lowering the worst aspect ratio in the row
+fn row_length(rectangle: &Rectangle, weights: &[f64]) -> usize {
+    let total: f64 = weights.iter().sum();
+    let (long, short) = (
+        rectangle.width.max(rectangle.height) as f64,
+        rectangle.width.min(rectangle.height) as f64,
+    );
+    // the row is `thickness` deep across the long side and each pane gets its share of `short`
+    let worst = |row: &[f64]| {
+        let row_total: f64 = row.iter().sum();
+        let thickness = long * row_total / total;
+        row.iter()
+            .map(|weight| {
+                let along = short * weight / row_total;
+                (along / thickness).max(thickness / along)
+            })
+            .fold(0.0, f64::max)
+    };
+    let mut length = 1;
+    while length < weights.len() && worst(&weights[..=length]) <= worst(&weights[..length]) {
+        length += 1;
+    }
+    length
+}
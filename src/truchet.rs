@@ -0,0 +1,126 @@
+//! the tile style: a grid of cells, each with the same kind of motif turned one of two ways at
+//! random, so the lines wander across the whole canvas
+
+use std::f32::consts::FRAC_PI_2;
+
+use rand::Rng;
+
+use crate::{
+    geometry::{Rectangle, SplitOptions, SplittableGraphic, SUBPIXELS},
+    polygon::{Point, Polygon, Segment},
+};
+
+/// how many straight pieces each quarter circle is drawn with
+const ARC_STEPS: usize = 16;
+
+/// about `count` cells covering `bounds`, as close to square as fits; each is cut along either two
+/// quarter circles around opposite corners or one diagonal, which way round at random. every
+/// piece of every cell comes back to be colored, along with the lines the motifs draw
+pub fn tiles<R: Rng + ?Sized>(
+    bounds: &Rectangle,
+    count: usize,
+    options: &SplitOptions,
+    rng: &mut R,
+) -> (Vec<Polygon>, Vec<Segment>) {
+    let side = (bounds.width * bounds.height / count.max(1) as f32).sqrt();
+    let columns = (bounds.width / side).round().max(1.0) as usize;
+    let rows = (bounds.height / side).round().max(1.0) as usize;
+    // cell boundaries go on the grid splits use, so neighbors meet exactly
+    let line = |start: f32, length: f32, i: usize, of: usize| {
+        let offset = length * i as f32 / of as f32;
+        start
+            + if options.whole_pixels {
+                offset.trunc()
+            } else {
+                (offset * SUBPIXELS).trunc() / SUBPIXELS
+            }
+    };
+
+    let mut pieces = vec![];
+    let mut segments = vec![];
+    for row in 0..rows {
+        let (top, bottom) = (
+            line(bounds.y, bounds.height, row, rows),
+            line(bounds.y, bounds.height, row + 1, rows),
+        );
+        for column in 0..columns {
+            let (left, right) = (
+                line(bounds.x, bounds.width, column, columns),
+                line(bounds.x, bounds.width, column + 1, columns),
+            );
+            let cell = Rectangle::new(left, top, right - left, bottom - top);
+            let arcs = rng.gen_bool(0.5);
+            let flip = rng.gen_bool(0.5);
+            let (cell_pieces, cell_segments) = if arcs {
+                quarter_circles(&cell, flip)
+            } else {
+                diagonal(&cell, flip)
+            };
+            pieces.extend(cell_pieces);
+            segments.extend(cell_segments);
+        }
+    }
+    (pieces, segments)
+}
+
+/// turns a point in a cell's own coordinates into the canvas's, mirrored left to right if `flip`
+fn place(cell: &Rectangle, flip: bool) -> impl Fn(f32, f32) -> Point + '_ {
+    move |x, y| {
+        let x = if flip { cell.width - x } else { x };
+        Point::new(cell.x + x, cell.y + y)
+    }
+}
+
+/// quarter circles (well, ellipses, if the cell isn't square) around the top left and bottom right
+/// corners, or the other two if `flip`, meeting the middle of every side: the two corners and the
+/// band between them
+fn quarter_circles(cell: &Rectangle, flip: bool) -> (Vec<Polygon>, Vec<Segment>) {
+    let at = place(cell, flip);
+    let (width, height) = (cell.width, cell.height);
+    let (rx, ry) = (width / 2.0, height / 2.0);
+    let angles = (0..=ARC_STEPS).map(|step| step as f32 / ARC_STEPS as f32 * FRAC_PI_2);
+    // from the middle of the top side round to the middle of the left
+    let near: Vec<Point> = angles
+        .clone()
+        .map(|angle| at(rx * angle.cos(), ry * angle.sin()))
+        .collect();
+    // from the middle of the bottom side round to the middle of the right
+    let far: Vec<Point> = angles
+        .map(|angle| at(width - rx * angle.cos(), height - ry * angle.sin()))
+        .collect();
+
+    let near_corner = [at(0.0, 0.0)].into_iter().chain(near.iter().copied());
+    let far_corner = [at(width, height)].into_iter().chain(far.iter().copied());
+    let band = [near[0], at(width, 0.0)]
+        .into_iter()
+        .chain(far.iter().rev().copied())
+        .chain([at(0.0, height)])
+        .chain(near[1..].iter().rev().copied());
+    let pieces = vec![
+        Polygon::new(near_corner.collect::<Vec<_>>()),
+        Polygon::new(band.collect::<Vec<_>>()),
+        Polygon::new(far_corner.collect::<Vec<_>>()),
+    ];
+
+    let segments = [&near, &far]
+        .into_iter()
+        .flat_map(|arc| arc.windows(2))
+        .map(|pair| Segment {
+            from: pair[0],
+            to: pair[1],
+        })
+        .collect();
+    (pieces, segments)
+}
+
+/// the diagonal from the top left to the bottom right, or the other one if `flip`
+fn diagonal(cell: &Rectangle, flip: bool) -> (Vec<Polygon>, Vec<Segment>) {
+    let at = place(cell, flip);
+    let (width, height) = (cell.width, cell.height);
+    let (from, to) = (at(0.0, 0.0), at(width, height));
+    let pieces = vec![
+        Polygon::new([from, at(width, 0.0), to]),
+        Polygon::new([from, to, at(0.0, height)]),
+    ];
+    (pieces, vec![Segment { from, to }])
+}
@@ -0,0 +1,157 @@
+//! encoding frames into MP4, WebM or GIF; needs the `ffmpeg` feature and `ffmpeg` on the PATH
+//!
+//! frames are piped to ffmpeg as raw RGB, so nothing gets written to disk but the video itself
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+    path::Path,
+    process::{Child, ChildStdin},
+};
+
+use image::RgbImage;
+
+#[derive(Debug)]
+pub enum VideoError {
+    /// built without the `ffmpeg` feature
+    Unsupported,
+    Io(io::Error),
+    /// ffmpeg ran but said no
+    Ffmpeg(String),
+    /// every frame has to be the size the video was created with
+    FrameSize {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+}
+
+impl fmt::Display for VideoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(
+                f,
+                "video output needs mondrian-rs built with `--features ffmpeg`"
+            ),
+            Self::Io(err) => write!(f, "couldn't run ffmpeg: {err}"),
+            Self::Ffmpeg(status) => write!(f, "ffmpeg exited with {status}"),
+            Self::FrameSize { expected, actual } => write!(
+                f,
+                "frame is {}x{} but the video is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+impl Error for VideoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VideoError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// whether `path` has an extension we'd hand to ffmpeg
+pub fn is_video(path: impl AsRef<Path>) -> bool {
+    let extension = path.as_ref().extension().unwrap_or_default();
+    ["mp4", "m4v", "mov", "mkv", "webm", "gif"]
+        .iter()
+        .any(|known| extension.eq_ignore_ascii_case(known))
+}
+
+/// an ffmpeg process waiting for frames
+pub struct Video {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    width: u32,
+    height: u32,
+}
+
+impl Video {
+    /// starts ffmpeg writing to `path`; the codec comes from the extension
+    #[cfg(feature = "ffmpeg")]
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        fps: u32,
+    ) -> Result<Self, VideoError> {
+        use std::process::{Command, Stdio};
+
+        let path = path.as_ref();
+        let extension = path.extension().unwrap_or_default().to_ascii_lowercase();
+        // yuv420p is what players expect, and it only works on even sizes; a GIF gets a palette
+        // of its own, made from the frames
+        let even = "pad=ceil(iw/2)*2:ceil(ih/2)*2";
+        let (codec, filter): (&[&str], &str) = match extension.to_str() {
+            Some("webm") => (&["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "30"], even),
+            Some("gif") => (&[], "split[a][b];[a]palettegen[p];[b][p]paletteuse"),
+            _ => (&["-c:v", "libx264", "-crf", "18"], even),
+        };
+        let pixels: &[&str] = if filter == even {
+            &["-pix_fmt", "yuv420p"]
+        } else {
+            &[]
+        };
+        let size = format!("{width}x{height}");
+        let fps = fps.to_string();
+        let mut child = Command::new("ffmpeg")
+            .args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-s", &size])
+            .args(["-framerate", &fps, "-i", "-"])
+            .args(codec)
+            .args(pixels)
+            .args(["-vf", filter])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        Ok(Self {
+            child,
+            stdin,
+            width,
+            height,
+        })
+    }
+
+    #[cfg(not(feature = "ffmpeg"))]
+    pub fn create(
+        _path: impl AsRef<Path>,
+        _width: u32,
+        _height: u32,
+        _fps: u32,
+    ) -> Result<Self, VideoError> {
+        Err(VideoError::Unsupported)
+    }
+
+    pub fn push(&mut self, frame: &RgbImage) -> Result<(), VideoError> {
+        if frame.dimensions() != (self.width, self.height) {
+            return Err(VideoError::FrameSize {
+                expected: (self.width, self.height),
+                actual: frame.dimensions(),
+            });
+        }
+        let stdin = self.stdin.as_mut().expect("stdin is only taken by finish");
+        Ok(stdin.write_all(frame.as_raw())?)
+    }
+
+    /// closes the pipe and waits for ffmpeg to write the file out
+    pub fn finish(mut self) -> Result<(), VideoError> {
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(VideoError::Ffmpeg(status.to_string()))
+        }
+    }
+}
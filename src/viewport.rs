@@ -0,0 +1,100 @@
+//! a window onto part of a bigger composition: the canvas is laid out whole, and only what's in
+//! the viewport is drawn. one of a desktop's monitors, a slice of a wider wallpaper, a close-up
+//!
+//! see [`CompositionBuilder::viewport`](crate::composition::CompositionBuilder::viewport)
+
+use std::{fmt, str::FromStr};
+
+use crate::geometry::{Rectangle, SplittableGraphic};
+
+/// `x,y,width,height` on the canvas, in pixels, or if none of them is more than 1, in fractions
+/// of it: `0.5,0,0.5,1` is the right half of any canvas. in pixels it's cut straight out, a pixel
+/// for a pixel; in fractions it's blown up as far as it'll go in the canvas's size, so a close-up
+/// comes out as big as the whole would have
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// whether it's in fractions of the canvas rather than pixels
+    pub fn normalized(&self) -> bool {
+        [self.x, self.y, self.width, self.height]
+            .iter()
+            .all(|value| *value <= 1.0)
+    }
+
+    /// where it is on a `width` by `height` canvas, in pixels
+    pub fn on(&self, width: u32, height: u32) -> Rectangle {
+        let (across, down) = if self.normalized() {
+            (width as f32, height as f32)
+        } else {
+            (1.0, 1.0)
+        };
+        Rectangle::new(
+            self.x * across,
+            self.y * down,
+            self.width * across,
+            self.height * down,
+        )
+    }
+
+    /// how many pixels of the image each of a `width` by `height` canvas's becomes
+    pub fn scale(&self, width: u32, height: u32) -> f32 {
+        if !self.normalized() {
+            return 1.0;
+        }
+        let on = self.on(width, height);
+        (width as f32 / on.width).min(height as f32 / on.height)
+    }
+
+    /// how big the image of it is, on a `width` by `height` canvas
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (on, scale) = (self.on(width, height), self.scale(width, height));
+        (
+            ((on.width * scale).round() as u32).max(1),
+            ((on.height * scale).round() as u32).max(1),
+        )
+    }
+}
+
+impl fmt::Display for Viewport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl FromStr for Viewport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{s:?} isn't a viewport like x,y,width,height");
+        let values: Vec<f32> = s
+            .split(',')
+            .map(|value| value.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        match values[..] {
+            [x, y, width, height] if values.iter().all(|value| value.is_finite()) => {
+                if x < 0.0 || y < 0.0 || width <= 0.0 || height <= 0.0 {
+                    return Err(format!(
+                        "a viewport can't start before the canvas or be empty, but {s} does"
+                    ));
+                }
+                Ok(Self::new(x, y, width, height))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! the crystalline style: random seed points, and the cells around them of everything closer to
+//! that seed than to any other
+
+use rand::Rng;
+
+use crate::{
+    geometry::Rectangle,
+    polygon::{Point, Polygon, Segment},
+};
+
+/// `count` cells covering `bounds`, with the seeds spread uniformly over it, and every line
+/// between two cells once; the canvas edges aren't lines
+pub fn cells<R: Rng + ?Sized>(
+    bounds: &Rectangle,
+    count: usize,
+    rng: &mut R,
+) -> (Vec<Polygon>, Vec<Segment>) {
+    let seeds: Vec<Point> = (0..count)
+        .map(|_| {
+            Point::new(
+                bounds.x + rng.gen::<f32>() * bounds.width,
+                bounds.y + rng.gen::<f32>() * bounds.height,
+            )
+        })
+        .collect();
+
+    // seeds bucketed into a grid of about one per bucket, so each cell only has to look at the
+    // seeds nearby
+    let columns = ((count as f32 * bounds.width / bounds.height.max(1.0))
+        .sqrt()
+        .ceil() as usize)
+        .clamp(1, count.max(1));
+    let rows = count.div_ceil(columns).max(1);
+    let (bucket_width, bucket_height) =
+        (bounds.width / columns as f32, bounds.height / rows as f32);
+    let bucket_of = |point: Point| {
+        let column = ((point.x - bounds.x) / bucket_width) as usize;
+        let row = ((point.y - bounds.y) / bucket_height) as usize;
+        (column.min(columns - 1), row.min(rows - 1))
+    };
+    let mut buckets = vec![vec![]; columns * rows];
+    for (i, &seed) in seeds.iter().enumerate() {
+        let (column, row) = bucket_of(seed);
+        buckets[row * columns + column].push(i);
+    }
+
+    let mut cells = Vec::with_capacity(count);
+    let mut segments = vec![];
+    for (i, &seed) in seeds.iter().enumerate() {
+        // the corners of the canvas, with no neighbor on the other side of any of its sides yet
+        let mut cell: Vec<(Point, Option<usize>)> = Polygon::from_rectangle(bounds)
+            .points
+            .into_iter()
+            .map(|point| (point, None))
+            .collect();
+
+        // rings of buckets further and further out, so the cell shrinks quickly
+        let (column, row) = bucket_of(seed);
+        for ring in 0..columns.max(rows) {
+            for j in ring_of(column, row, ring, columns, rows)
+                .flat_map(|(column, row)| &buckets[row * columns + column])
+                .copied()
+            {
+                if j != i {
+                    cell = clip(&cell, seed, seeds[j], j);
+                }
+            }
+            // every point of the cell is within `reach` of the seed, so a seed more than twice
+            // that away can't take any of it, and every seed past this ring is at least `ring`
+            // buckets away
+            let reach = cell
+                .iter()
+                .map(|(point, _)| distance(seed, *point))
+                .fold(0.0, f32::max);
+            let beyond = ring as f32 * bucket_width.min(bucket_height);
+            if beyond * beyond > 4.0 * reach {
+                break;
+            }
+        }
+
+        for (n, &(point, neighbor)) in cell.iter().enumerate() {
+            if neighbor.is_some_and(|j| j > i) {
+                segments.push(Segment {
+                    from: point,
+                    to: cell[(n + 1) % cell.len()].0,
+                });
+            }
+        }
+        cells.push(Polygon::new(
+            cell.into_iter().map(|(point, _)| point).collect::<Vec<_>>(),
+        ));
+    }
+    (cells, segments)
+}
+
+/// the buckets exactly `ring` steps (counting diagonals as one) from `(column, row)`, inside a
+/// `columns` by `rows` grid
+fn ring_of(
+    column: usize,
+    row: usize,
+    ring: usize,
+    columns: usize,
+    rows: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    let (column, row, ring) = (column as isize, row as isize, ring as isize);
+    (row - ring..=row + ring)
+        .flat_map(move |y| (column - ring..=column + ring).map(move |x| (x, y)))
+        .filter(move |&(x, y)| (x - column).abs().max((y - row).abs()) == ring)
+        .filter(move |&(x, y)| x >= 0 && y >= 0 && x < columns as isize && y < rows as isize)
+        .map(|(x, y)| (x as usize, y as usize))
+}
+
+/// squared, which is all the comparisons need
+fn distance(a: Point, b: Point) -> f32 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+/// cuts away the part of `cell` closer to `other` than to `seed`; each corner carries the
+/// neighbor on the other side of the side leading on from it, and sides along the cut get `j`
+fn clip(
+    cell: &[(Point, Option<usize>)],
+    seed: Point,
+    other: Point,
+    j: usize,
+) -> Vec<(Point, Option<usize>)> {
+    // inside is where the point is at least as close to `seed`, i.e. on its side of the
+    // perpendicular bisector
+    let (nx, ny) = (other.x - seed.x, other.y - seed.y);
+    let limit = (nx * (seed.x + other.x) + ny * (seed.y + other.y)) / 2.0;
+    let past = |point: Point| nx * point.x + ny * point.y - limit;
+
+    let mut clipped = Vec::with_capacity(cell.len() + 1);
+    for (n, &(a, side)) in cell.iter().enumerate() {
+        let (b, _) = cell[(n + 1) % cell.len()];
+        let (past_a, past_b) = (past(a), past(b));
+        let crossing = || {
+            let t = past_a / (past_a - past_b);
+            Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+        };
+        match (past_a <= 0.0, past_b <= 0.0) {
+            (true, true) => clipped.push((a, side)),
+            // leaving: the side runs on to the bisector, then the bisector takes over
+            (true, false) => {
+                clipped.push((a, side));
+                clipped.push((crossing(), Some(j)));
+            }
+            // coming back in partway along this side
+            (false, true) => clipped.push((crossing(), side)),
+            (false, false) => {}
+        }
+    }
+    clipped
+}
@@ -0,0 +1,289 @@
+//! making a file the desktop background; needs the `wallpaper` feature
+//!
+//! there's no portable API for this, so we shell out to whatever the platform or desktop
+//! environment provides
+
+use std::{error::Error, fmt, io, path::Path};
+
+#[derive(Debug)]
+pub enum WallpaperError {
+    /// built without the `wallpaper` feature, or no way we know of on this platform/desktop
+    Unsupported(String),
+    Io(io::Error),
+    /// the helper program ran but said no
+    Command {
+        program: String,
+        status: String,
+    },
+}
+
+impl fmt::Display for WallpaperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(why) => write!(f, "can't set the wallpaper: {why}"),
+            Self::Io(err) => write!(f, "can't set the wallpaper: {err}"),
+            Self::Command { program, status } => {
+                write!(f, "can't set the wallpaper: {program} exited with {status}")
+            }
+        }
+    }
+}
+
+impl Error for WallpaperError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WallpaperError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// sets `path` as the background of every monitor
+#[cfg(feature = "wallpaper")]
+pub fn set(path: impl AsRef<Path>) -> Result<(), WallpaperError> {
+    // every helper wants an absolute path; some resolve relative ones against their own cwd
+    let path = path.as_ref().canonicalize()?;
+    let path = path.to_string_lossy();
+    platform::set(&path)
+}
+
+#[cfg(not(feature = "wallpaper"))]
+pub fn set(_path: impl AsRef<Path>) -> Result<(), WallpaperError> {
+    Err(WallpaperError::Unsupported(
+        "mondrian-rs was built without `--features wallpaper`".to_owned(),
+    ))
+}
+
+#[cfg(feature = "wallpaper")]
+fn run(program: &str, args: &[&str]) -> Result<(), WallpaperError> {
+    let status = std::process::Command::new(program).args(args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(WallpaperError::Command {
+            program: program.to_owned(),
+            status: status.to_string(),
+        })
+    }
+}
+
+#[cfg(all(feature = "wallpaper", target_os = "windows"))]
+mod platform {
+    use super::{run, WallpaperError};
+
+    /// SPI_SETDESKWALLPAPER with SPIF_UPDATEINIFILE | SPIF_SENDCHANGE, via PowerShell so we
+    /// don't need a winapi dependency
+    pub fn set(path: &str) -> Result<(), WallpaperError> {
+        let script = format!(
+            r#"Add-Type -TypeDefinition 'using System.Runtime.InteropServices; public class W {{ [DllImport("user32.dll", CharSet = CharSet.Unicode)] public static extern int SystemParametersInfo(int a, int b, string c, int d); }}'; [W]::SystemParametersInfo(20, 0, '{}', 3) | Out-Null"#,
+            path.replace('\'', "''")
+        );
+        run("powershell", &["-NoProfile", "-Command", &script])
+    }
+}
+
+#[cfg(all(feature = "wallpaper", target_os = "macos"))]
+mod platform {
+    use super::{run, WallpaperError};
+
+    pub fn set(path: &str) -> Result<(), WallpaperError> {
+        let script = format!(
+            r#"tell application "System Events" to tell every desktop to set picture to "{}""#,
+            path.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        run("osascript", &["-e", &script])
+    }
+}
+
+#[cfg(all(
+    feature = "wallpaper",
+    target_family = "unix",
+    not(target_os = "macos")
+))]
+mod platform {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process::{Child, Command},
+        sync::Mutex,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use super::{run, WallpaperError};
+
+    /// the swaybg this process started last, to be reaped once it's replaced
+    static SWAYBG: Mutex<Option<Child>> = Mutex::new(None);
+
+    pub fn set(path: &str) -> Result<(), WallpaperError> {
+        let desktop = env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .to_lowercase();
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+
+        if ["gnome", "unity", "pantheon", "budgie"]
+            .iter()
+            .any(|name| desktop.contains(name))
+        {
+            // GNOME doesn't look again at a file it's already showing, so each one gets a new name
+            let copy = fresh(path)?;
+            let uri = format!("file://{}", copy.to_string_lossy());
+            run(
+                "gsettings",
+                &["set", "org.gnome.desktop.background", "picture-uri", &uri],
+            )?;
+            // newer GNOME keeps a separate one for dark mode; older ones don't have the key
+            let _ = run(
+                "gsettings",
+                &[
+                    "set",
+                    "org.gnome.desktop.background",
+                    "picture-uri-dark",
+                    &uri,
+                ],
+            );
+            forget(&copy);
+            return Ok(());
+        }
+        if desktop.contains("kde") {
+            return run("plasma-apply-wallpaperimage", &[path]);
+        }
+        if env::var_os("SWAYSOCK").is_some() {
+            return run("swaymsg", &["output", "*", "bg", path, "fill"]);
+        }
+        if wayland {
+            // other wlroots compositors: swaybg has to keep running to keep the background up, so
+            // the one from before, this process's or an earlier run's, makes way for the new one.
+            // only that one: any other swaybg is someone else's
+            let mut previous = SWAYBG.lock().unwrap_or_else(|err| err.into_inner());
+            if let Some(mut child) = previous.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            // without a cache there's no telling which one an earlier run left, so it stays
+            let pid_file = cache().ok().map(|cache| cache.join("swaybg.pid"));
+            if let Some(pid_file) = &pid_file {
+                stop(pid_file);
+            }
+            let child = Command::new("swaybg")
+                .args(["-m", "fill", "-i", path])
+                .spawn()?;
+            if let Some(pid_file) = &pid_file {
+                fs::write(pid_file, child.id().to_string())?;
+            }
+            *previous = Some(child);
+            return Ok(());
+        }
+        if env::var_os("DISPLAY").is_some() {
+            return run("feh", &["--bg-fill", path]);
+        }
+        Err(WallpaperError::Unsupported(format!(
+            "don't know how to set the wallpaper on desktop {desktop:?}"
+        )))
+    }
+
+    /// stops the swaybg whose pid is in `pid_file`, if it's still running; a pid that's been
+    /// taken by something else since is left alone
+    fn stop(pid_file: &Path) {
+        let Some(pid) = fs::read_to_string(pid_file)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+        else {
+            return;
+        };
+        let pid = pid.to_string();
+        let Ok(output) = Command::new("ps")
+            .args(["-p", &pid, "-o", "comm="])
+            .output()
+        else {
+            return;
+        };
+        let name = String::from_utf8_lossy(&output.stdout);
+        if name.trim().rsplit('/').next() == Some("swaybg") {
+            let _ = Command::new("kill").arg(&pid).status();
+        }
+    }
+
+    /// where copies of the wallpaper and swaybg's pid go: `$XDG_CACHE_HOME/mondrian-rs`, or
+    /// `~/.cache/mondrian-rs`
+    fn cache() -> Result<PathBuf, WallpaperError> {
+        let cache = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .ok_or_else(|| {
+                WallpaperError::Unsupported("neither XDG_CACHE_HOME nor HOME is set".to_owned())
+            })?;
+        let cache = cache.join("mondrian-rs");
+        fs::create_dir_all(&cache)?;
+        Ok(cache)
+    }
+
+    /// `path` copied to a name it hasn't had before, `wallpaper-<nanoseconds>.<extension>`
+    fn fresh(path: &str) -> Result<PathBuf, WallpaperError> {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "png".to_owned());
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let copy = cache()?.join(format!("wallpaper-{stamp}.{extension}"));
+        fs::copy(path, &copy)?;
+        Ok(copy)
+    }
+
+    /// removes the copies [`fresh`] made before `current`, which nothing's showing any more
+    fn forget(current: &Path) {
+        let Some(directory) = current.parent() else {
+            return;
+        };
+        let Ok(entries) = fs::read_dir(directory) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            if path != current && name.to_string_lossy().starts_with("wallpaper-") {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn only_stops_a_swaybg() {
+            let pid_file =
+                env::temp_dir().join(format!("mondrian-swaybg-{}.pid", std::process::id()));
+            // this test's own pid isn't a swaybg's, and nonsense isn't a pid
+            for contents in [std::process::id().to_string(), "swaybg".to_owned()] {
+                fs::write(&pid_file, contents).unwrap();
+                stop(&pid_file);
+            }
+            fs::remove_file(&pid_file).unwrap();
+            stop(&pid_file);
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "wallpaper",
+    not(any(target_os = "windows", target_family = "unix"))
+))]
+mod platform {
+    use super::WallpaperError;
+
+    pub fn set(_path: &str) -> Result<(), WallpaperError> {
+        Err(WallpaperError::Unsupported(
+            "no wallpaper support for this platform".to_owned(),
+        ))
+    }
+}
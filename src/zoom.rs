@@ -0,0 +1,454 @@
+//! an endless dive into a composition: the view closes in on one of its panes, and as panes get
+//! big on screen they're cut again, the new lines sliding in from the side like
+//! [`morph`](crate::morph)'s do, so there's always about as much going on as there was to begin
+//! with. once the view is all inside the pane it was heading for, that pane is made the root and
+//! another one further in is picked, so it never runs out of room
+//!
+//! what's below the composition's own panes is cut by its split strategy and colored by its fill,
+//! each pane seeded by where it is in the tree, so the same seed always zooms the same way
+
+use image::Rgb;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    composition::{Composition, Pane},
+    geometry::{Rectangle, SplittableGraphic},
+    seed,
+    style::Style,
+    tree::Tree,
+};
+
+/// how many times smaller than the view a pane has to be to head for it
+const TARGET: f64 = 8.0;
+/// the long side new cuts are worked out on, whatever size the pane is on screen, so a pane is
+/// always cut the same way however far in it is
+const UNIT: f32 = 1024.0;
+
+/// a pane of the endless tree, as big as it'll be once its parent's cut is all the way in
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    rectangle: Rectangle,
+    depth: usize,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// one of the composition's own, by where it is in its tree
+    Drawn(usize),
+    /// cut out of one of them, or out of one of these
+    Grown { id: u64, color: Rgb<u8> },
+}
+
+/// how a [`Node`] is cut
+struct Cut {
+    side_by_side: bool,
+    t: f32,
+    halves: [Node; 2],
+}
+
+/// a composition being zoomed into
+pub struct Zoom {
+    /// what each frame is drawn as, with its own layout swapped for the one on screen
+    composition: Composition,
+    tree: Tree<Rectangle>,
+    panes: Vec<Pane>,
+    /// where the panes go on screen, inside the frame and matte
+    bounds: Rectangle,
+    /// how big a pane can get on screen before it's cut again, in square pixels
+    detail: f64,
+    /// where everything is measured from: the view started out as `bounds` in this
+    root: Node,
+    /// the pane the view is heading for
+    heading: Node,
+    /// the biggest rectangle the shape of `bounds` in the middle of it, which the view ends up as
+    target: Rectangle,
+    /// the one point that stays still on screen as the view closes in on `target`
+    toward: (f32, f32),
+    /// how big the view is now, from 1 for `bounds` down to `target`'s size
+    scale: f32,
+    /// how far in the view has gone since the start
+    zoomed: f32,
+    /// how the composition's own rectangles are placed, measured like the root is: a point `x0`
+    /// across in the composition is `x + x0 * factor` across, as `(x, y, factor)`
+    drawn: (f32, f32, f32),
+    /// which pane it heads for next
+    rng: ChaCha8Rng,
+}
+
+impl Zoom {
+    /// starts off showing `composition` as it is
+    pub fn new(composition: Composition) -> Self {
+        let tree = composition.tree().clone();
+        let panes = composition.panes().to_vec();
+        let bounds = *tree.item();
+        let detail = bounds.area() / panes.len().max(1) as f64;
+        let seed = seed::from_text(&format!("{}:zoom", composition.seed()));
+        let root = Node {
+            rectangle: bounds,
+            depth: 0,
+            kind: Kind::Drawn(0),
+        };
+        let mut zoom = Self {
+            composition,
+            tree,
+            panes,
+            bounds,
+            detail,
+            root,
+            heading: root,
+            target: bounds,
+            toward: (0.0, 0.0),
+            scale: 1.0,
+            zoomed: 1.0,
+            drawn: (0.0, 0.0, 1.0),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+        zoom.aim();
+        zoom
+    }
+
+    /// goes `factor` times further in
+    pub fn advance(&mut self, factor: f32) {
+        self.scale /= factor;
+        self.zoomed *= factor;
+        loop {
+            let fitted = self.target.width / self.bounds.width;
+            if !(self.scale <= fitted && fitted < 1.0) {
+                break;
+            }
+            // the view is all inside the pane it was heading for, so that's the root now
+            let moved = self.transform(self.target);
+            self.root = Node {
+                rectangle: moved(self.heading.rectangle),
+                ..self.heading
+            };
+            let (x, y, factor) = self.drawn;
+            let corner = moved(Rectangle::new(x, y, 0.0, 0.0));
+            self.drawn = (corner.x, corner.y, factor / fitted);
+            self.scale /= fitted;
+            self.aim();
+        }
+    }
+
+    /// the composition as it looks from here
+    pub fn frame(&mut self) -> &Composition {
+        let view = self.view();
+        let to_screen = self.transform(view);
+        let mut tree = Tree::new(self.bounds);
+        let mut panes = vec![];
+        // the nodes the tree hasn't reached yet: each with where it is on screen, clipped and
+        // not, and how far its parent's cut has come in
+        let root = to_screen(self.root.rectangle);
+        let mut pending = vec![(self.root, root, 1.0)];
+        // diagonals and stripes only look right on panes that aren't moving
+        let still = self.zoomed == 1.0;
+        tree.split_by(|clipped, _| {
+            let (mut node, mut shown, mut parent) = pending.pop().expect("one node per pane");
+            loop {
+                let growth = self.growth(&node, &shown, parent);
+                let cut = self.cut(&node).filter(|_| growth > 0.0);
+                let Some(Cut {
+                    side_by_side,
+                    t,
+                    halves: [left, right],
+                }) = cut
+                else {
+                    let pane = match node.kind {
+                        Kind::Drawn(index) => Pane {
+                            rectangle: *clipped,
+                            ..self.panes[self.tree.leaf_range(index).start].clone()
+                        },
+                        Kind::Grown { color, .. } => Pane {
+                            rectangle: *clipped,
+                            color,
+                            diagonal: None,
+                            pattern: None,
+                        },
+                    };
+                    panes.push(Pane {
+                        diagonal: pane.diagonal.filter(|_| still),
+                        pattern: pane.pattern.filter(|_| still),
+                        ..pane
+                    });
+                    return None;
+                };
+                // grown in from whichever side the small half is on
+                let edge = if t >= 0.5 { 1.0 } else { 0.0 };
+                let t = edge + (t - edge) * growth;
+                let [first, second] = if growth >= 1.0 {
+                    [to_screen(left.rectangle), to_screen(right.rectangle)]
+                } else {
+                    let at = if side_by_side {
+                        shown.x + shown.width * t
+                    } else {
+                        shown.y + shown.height * t
+                    };
+                    halves(&shown, side_by_side, at)
+                };
+                let at = if side_by_side { second.x } else { second.y };
+                let [first_clipped, second_clipped] = halves(clipped, side_by_side, at);
+                // a half that's off screen, or not in yet, isn't cut off at all
+                let empty = |half: &Rectangle| half.width <= 0.0 || half.height <= 0.0;
+                if empty(&first_clipped) {
+                    (node, shown, parent) = (right, second, growth);
+                } else if empty(&second_clipped) {
+                    (node, shown, parent) = (left, first, growth);
+                } else {
+                    pending.push((right, second, growth));
+                    pending.push((left, first, growth));
+                    return Some((first_clipped, second_clipped));
+                }
+            }
+        });
+        self.composition.relaid(tree, panes);
+        &self.composition
+    }
+
+    /// how far `node`'s cut has come in, from 0 to 1, on screen as `shown`: all the way once it's
+    /// twice as big as a pane's allowed to get, but not at all until its parent's is halfway in,
+    /// so cuts in halves that are still most of their parent don't pile up
+    fn growth(&self, node: &Node, shown: &Rectangle, parent: f32) -> f32 {
+        let allowed = match node.kind {
+            // the composition's own cuts are always in
+            Kind::Drawn(index) if !self.tree.node(index).is_leaf() => return 1.0,
+            // and its big panes only start to be cut once they're bigger than they were
+            Kind::Drawn(index) => self.detail.max(self.tree.node(index).item().area()),
+            Kind::Grown { .. } => self.detail,
+        };
+        let allowed = allowed.min(self.bounds.area() / 2.0);
+        let growth = (shown.area() / allowed).log2().clamp(0.0, 1.0) as f32;
+        growth.min(2.0 * parent - 1.0).max(0.0)
+    }
+
+    /// how `node` is cut, once it's all the way in: like it is in the composition, if it's one of
+    /// its own, otherwise the way its seed says
+    fn cut(&self, node: &Node) -> Option<Cut> {
+        let depth = node.depth + 1;
+        let (id, color) = match node.kind {
+            Kind::Drawn(index) if !self.tree.node(index).is_leaf() => {
+                let subtree = self.tree.node(index);
+                let (left, right) = (subtree.left()?, subtree.right()?);
+                let side_by_side = left.item().height >= subtree.item().height;
+                let t = if side_by_side {
+                    left.item().width / subtree.item().width
+                } else {
+                    left.item().height / subtree.item().height
+                };
+                // where they are rather than how far across, so nothing's rounded differently
+                let halves = [left, right].map(|half| Node {
+                    rectangle: self.placed(half.item()),
+                    depth,
+                    kind: Kind::Drawn(half.index()),
+                });
+                return Some(Cut {
+                    side_by_side,
+                    t,
+                    halves,
+                });
+            }
+            Kind::Drawn(index) => {
+                let pane = self.tree.leaf_range(index).start;
+                let id = seed::from_text(&format!("{}:zoom:{pane}", self.composition.seed()));
+                (id, self.panes[pane].color)
+            }
+            Kind::Grown { id, color } => (id, color),
+        };
+        let (side_by_side, t, kinds) = self.grow(&node.rectangle, depth, id, color)?;
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = node.rectangle;
+        let at = if side_by_side {
+            x + width * t
+        } else {
+            y + height * t
+        };
+        let halves = halves(&node.rectangle, side_by_side, at);
+        let [left, right] = [0, 1].map(|half| Node {
+            rectangle: halves[half],
+            depth,
+            kind: kinds[half],
+        });
+        Some(Cut {
+            side_by_side,
+            t,
+            halves: [left, right],
+        })
+    }
+
+    /// a new cut of `rectangle`, `depth` down, seeded by `id`: the bigger half stays `color` and
+    /// the other gets one of its own
+    fn grow(
+        &self,
+        rectangle: &Rectangle,
+        depth: usize,
+        id: u64,
+        color: Rgb<u8>,
+    ) -> Option<(bool, f32, [Kind; 2])> {
+        let composition = &self.composition;
+        let mut rng = ChaCha8Rng::seed_from_u64(id);
+        let long = rectangle.width.max(rectangle.height);
+        let unit = Rectangle::new(
+            0.0,
+            0.0,
+            rectangle.width / long * UNIT,
+            rectangle.height / long * UNIT,
+        );
+        let (left, right) =
+            composition
+                .split_strategy()
+                .split(&unit, depth, composition.split_options(), &mut rng);
+        let side_by_side = left.height >= unit.height;
+        let t = if side_by_side {
+            left.width / unit.width
+        } else {
+            left.height / unit.height
+        };
+        if !(t > 0.0 && t < 1.0) {
+            return None;
+        }
+        let fresh = match composition.style() {
+            Style::LinesOnly => composition.fill_options().background,
+            _ => {
+                let smaller = if t >= 0.5 { right } else { left };
+                let colors = composition.fill_strategy().colors(
+                    &[smaller],
+                    composition.palette(),
+                    composition.fill_options(),
+                    &mut rng,
+                );
+                colors[0]
+            }
+        };
+        let (kept, cut_off) = (
+            Kind::Grown {
+                id: rng.gen(),
+                color,
+            },
+            Kind::Grown {
+                id: rng.gen(),
+                color: fresh,
+            },
+        );
+        let kinds = if t >= 0.5 {
+            [kept, cut_off]
+        } else {
+            [cut_off, kept]
+        };
+        Some((side_by_side, t, kinds))
+    }
+
+    /// picks the next pane to head for, somewhere under the root
+    fn aim(&mut self) {
+        let aspect = self.bounds.width / self.bounds.height;
+        let enough = self.bounds.area() / TARGET;
+        let mut node = self.root;
+        // at least one cut in, however big the root is
+        while let Some(cut) = self.cut(&node) {
+            // panes nearer the view's shape are headed for more often, so it doesn't have to go
+            // so far in to fill the screen with a sliver
+            let [left, right] = cut.halves.map(|half| {
+                let off = (half.rectangle.width / half.rectangle.height / aspect)
+                    .ln()
+                    .abs();
+                1.0 / (1.0 + off)
+            });
+            node = cut.halves[usize::from(!self.rng.gen_bool((left / (left + right)) as f64))];
+            if node.rectangle.area() <= enough {
+                break;
+            }
+        }
+        self.heading = node;
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = node.rectangle;
+        let (width, height) = if width / height > aspect {
+            (height * aspect, height)
+        } else {
+            (width, width / aspect)
+        };
+        self.target = Rectangle::new(
+            x + (node.rectangle.width - width) / 2.0,
+            y + (node.rectangle.height - height) / 2.0,
+            width,
+            height,
+        );
+        // the center of the zoom that takes `bounds` to `target`
+        let shrink = width / self.bounds.width;
+        let still = |from: f32, to: f32| (to - from * shrink) / (1.0 - shrink).max(f32::EPSILON);
+        self.toward = (
+            still(self.bounds.x, self.target.x),
+            still(self.bounds.y, self.target.y),
+        );
+    }
+
+    /// where one of the composition's own rectangles is, measured like the root is
+    fn placed(&self, rectangle: &Rectangle) -> Rectangle {
+        let (x, y, factor) = self.drawn;
+        Rectangle::new(
+            x + rectangle.x * factor,
+            y + rectangle.y * factor,
+            rectangle.width * factor,
+            rectangle.height * factor,
+        )
+    }
+
+    /// what's in view, measured like the root is
+    fn view(&self) -> Rectangle {
+        let (x, y) = self.toward;
+        // exactly `bounds` to begin with, so the first frame is the composition as it is
+        let (scale, rest) = (self.scale, 1.0 - self.scale);
+        Rectangle::new(
+            self.bounds.x * scale + x * rest,
+            self.bounds.y * scale + y * rest,
+            self.bounds.width * self.scale,
+            self.bounds.height * self.scale,
+        )
+    }
+
+    /// how to take a rectangle measured like the root to where it is on screen, with `view`
+    /// filling `bounds`
+    fn transform(&self, view: Rectangle) -> impl Fn(Rectangle) -> Rectangle {
+        let bounds = self.bounds;
+        let factor = bounds.width / view.width;
+        move |rectangle| {
+            Rectangle::new(
+                bounds.x + (rectangle.x - view.x) * factor,
+                bounds.y + (rectangle.y - view.y) * factor,
+                rectangle.width * factor,
+                rectangle.height * factor,
+            )
+        }
+    }
+}
+
+/// `rectangle` cut across at `at`, side by side or one above the other; a cut outside it leaves
+/// one half all of it and the other nothing
+fn halves(rectangle: &Rectangle, side_by_side: bool, at: f32) -> [Rectangle; 2] {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = *rectangle;
+    if side_by_side {
+        let at = at.clamp(x, x + width);
+        [
+            Rectangle::new(x, y, at - x, height),
+            Rectangle::new(at, y, x + width - at, height),
+        ]
+    } else {
+        let at = at.clamp(y, y + height);
+        [
+            Rectangle::new(x, y, width, at - y),
+            Rectangle::new(x, at, width, y + height - at),
+        ]
+    }
+}
@@ -0,0 +1,91 @@
+use image::{imageops, RgbImage};
+use mondrian::{
+    composition::{BuildError, CompositionBuilder},
+    style::Style,
+};
+
+const BORDER: u32 = 6;
+
+fn tile(style: Style) -> RgbImage {
+    CompositionBuilder::new()
+        .size(300, 200)
+        .seed(3)
+        .style(style)
+        .border_width(BORDER)
+        .tileable(true)
+        .build()
+        .expect("the style can tile")
+        .render_rgb()
+}
+
+/// two copies side by side and two stacked, the way a background repeats
+fn joined(tile: &RgbImage) -> (RgbImage, RgbImage) {
+    let (width, height) = tile.dimensions();
+    let mut across = RgbImage::new(width * 2, height);
+    imageops::replace(&mut across, tile, 0, 0);
+    imageops::replace(&mut across, tile, width as i64, 0);
+    let mut down = RgbImage::new(width, height * 2);
+    imageops::replace(&mut down, tile, 0, 0);
+    imageops::replace(&mut down, tile, 0, height as i64);
+    (across, down)
+}
+
+#[test]
+fn copies_join_into_whole_lines() {
+    for style in [Style::Mondrian, Style::LinesOnly, Style::Sketchy] {
+        let tile = tile(style);
+        let (width, height) = tile.dimensions();
+        let line = *tile.get_pixel(0, 0);
+        let (across, down) = joined(&tile);
+
+        // where the copies meet, the halves on either side make one line as wide as the rest
+        for y in 0..height {
+            for x in width - BORDER / 2..width + BORDER / 2 {
+                assert_eq!(*across.get_pixel(x, y), line, "{style} at ({x}, {y})");
+            }
+            assert_eq!(
+                tile.get_pixel(0, y),
+                tile.get_pixel(width - 1, y),
+                "{style}"
+            );
+        }
+        for x in 0..width {
+            for y in height - BORDER / 2..height + BORDER / 2 {
+                assert_eq!(*down.get_pixel(x, y), line, "{style} at ({x}, {y})");
+            }
+            assert_eq!(
+                tile.get_pixel(x, 0),
+                tile.get_pixel(x, height - 1),
+                "{style}"
+            );
+        }
+    }
+}
+
+#[test]
+fn styles_without_edge_lines_refuse() {
+    for style in [
+        Style::ThreeD,
+        Style::Voronoi,
+        Style::Truchet,
+        Style::Hex,
+        Style::Penrose,
+    ] {
+        let built = CompositionBuilder::new()
+            .size(300, 200)
+            .style(style)
+            .tileable(true)
+            .build();
+        assert_eq!(built.err(), Some(BuildError::Tileable(style)));
+    }
+    let unfinished = CompositionBuilder::new()
+        .size(300, 200)
+        .style(Style::LinesOnly)
+        .unfinished(0.5)
+        .tileable(true)
+        .build();
+    assert_eq!(
+        unfinished.err(),
+        Some(BuildError::Tileable(Style::LinesOnly))
+    );
+}